@@ -81,6 +81,7 @@ class _AdvancedGroupsDemoTabState extends State<AdvancedGroupsDemoTab> {
         groupIdBytes: inspGid,
         signerBytes: alice.signer,
         keyPackagesBytes: [bobKpI.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       final wi = await bob.client.inspectWelcome(
         config: cfg,
@@ -102,6 +103,7 @@ class _AdvancedGroupsDemoTabState extends State<AdvancedGroupsDemoTab> {
         groupIdBytes: builder.groupId,
         signerBytes: alice.signer,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       final joinR = await bob.client.joinGroupFromWelcomeWithOptions(
         config: cfg,
@@ -187,6 +189,7 @@ class _AdvancedGroupsDemoTabState extends State<AdvancedGroupsDemoTab> {
         newSignerBytes: aliceNew.signer,
         newCredentialIdentity: utf8.encode('alice-rotated'),
         newSignerPublicKey: aliceNew.publicKey,
+        ensureGroupInfo: false,
       );
       await bob.client.processMessage(
         groupIdBytes: builder.groupId,
@@ -217,6 +220,7 @@ class _AdvancedGroupsDemoTabState extends State<AdvancedGroupsDemoTab> {
         groupIdBytes: gid4,
         signerBytes: aliceNew.signer,
         keyPackagesBytes: [eveKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await alice.client.mergePendingCommit(groupIdBytes: gid4);
       r.writeln('7. addMembersWithoutUpdate');
@@ -237,6 +241,7 @@ class _AdvancedGroupsDemoTabState extends State<AdvancedGroupsDemoTab> {
         groupIdBytes: gid4,
         signerBytes: aliceNew.signer,
         keyPackagesBytes: [frankKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       final fCred = MlsCredential.basic(identity: utf8.encode('frank'));
       final fIdx = await alice.client.groupMemberLeafIndex(
@@ -247,6 +252,7 @@ class _AdvancedGroupsDemoTabState extends State<AdvancedGroupsDemoTab> {
         groupIdBytes: gid4,
         signerBytes: aliceNew.signer,
         memberIndices: [fIdx!],
+        ensureGroupInfo: false,
       );
       r.writeln('8. removeMembers');
       r.writeln(
@@ -272,6 +278,7 @@ class _AdvancedGroupsDemoTabState extends State<AdvancedGroupsDemoTab> {
         signerBytes: aliceNew.signer,
         removeIndices: [eIdx!],
         addKeyPackagesBytes: [graceKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await alice.client.mergePendingCommit(groupIdBytes: gid4);
       final swapM = await alice.client.groupMembers(groupIdBytes: gid4);
@@ -310,6 +317,7 @@ class _AdvancedGroupsDemoTabState extends State<AdvancedGroupsDemoTab> {
         groupIdBytes: ptG.groupId,
         signerBytes: ptA.signer,
         keyPackagesBytes: [ptBKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await ptB.client.joinGroupFromWelcome(
         config: ptCfg,
@@ -74,6 +74,7 @@ class _AdvancedProposalsDemoTabState extends State<AdvancedProposalsDemoTab> {
         groupIdBytes: gid,
         signerBytes: aliceSigner,
         keyPackagesBytes: [bobKeyPkg.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await bobClient.joinGroupFromWelcome(
         config: cfg,
@@ -182,6 +183,7 @@ class _AdvancedProposalsDemoTabState extends State<AdvancedProposalsDemoTab> {
         groupIdBytes: gid,
         signerBytes: aliceSigner,
         extensions: [],
+        ensureGroupInfo: false,
       );
       await bobClient.processMessage(
         groupIdBytes: gid,
@@ -82,6 +82,7 @@ class _GroupsDemoTabState extends State<GroupsDemoTab> {
         groupIdBytes: gid,
         signerBytes: aliceSigner,
         keyPackagesBytes: [bobKeyPkg.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       r.writeln('3. Alice added Bob');
       r.writeln(
@@ -142,6 +143,7 @@ class _GroupsDemoTabState extends State<GroupsDemoTab> {
       final upd = await aliceClient.selfUpdate(
         groupIdBytes: gid,
         signerBytes: aliceSigner,
+        ensureGroupInfo: false,
       );
       final insp = await bobClient.processMessageWithInspect(
         groupIdBytes: gid,
@@ -113,6 +113,7 @@ class _PostQuantumDemoTabState extends State<PostQuantumDemoTab> {
       groupIdBytes: gid,
       signerBytes: aliceSigner,
       keyPackagesBytes: [bobKeyPkg.keyPackageBytes],
+      ensureGroupInfo: false,
     );
     r.writeln(
       '   Alice added Bob — commit: ${add.commit.length}, '
@@ -85,6 +85,7 @@ class _ProposalsDemoTabState extends State<ProposalsDemoTab> {
         groupIdBytes: gid,
         signerBytes: aliceSigner,
         keyPackagesBytes: [bobKeyPkg.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await bobClient.joinGroupFromWelcome(
         config: cfg,
@@ -102,6 +103,7 @@ class _ProposalsDemoTabState extends State<ProposalsDemoTab> {
         groupIdBytes: gid,
         signerBytes: aliceSigner,
         keyPackagesBytes: [charlieKeyPkg.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await bobClient.processMessage(
         groupIdBytes: gid,
@@ -119,6 +121,7 @@ class _ProposalsDemoTabState extends State<ProposalsDemoTab> {
       final upd = await aliceClient.selfUpdate(
         groupIdBytes: gid,
         signerBytes: aliceSigner,
+        ensureGroupInfo: false,
       );
       final eAfter = await aliceClient.groupEpoch(groupIdBytes: gid);
       r.writeln('1. Self-update: epoch $eBefore -> $eAfter');
@@ -168,6 +171,7 @@ class _ProposalsDemoTabState extends State<ProposalsDemoTab> {
       final commit = await aliceClient.commitToPendingProposals(
         groupIdBytes: gid,
         signerBytes: aliceSigner,
+        ensureGroupInfo: false,
       );
       r.writeln('4. Alice committed: ${commit.commit.length} bytes');
       await bobClient.processMessage(
@@ -73,6 +73,7 @@ class _StateDemoTabState extends State<StateDemoTab> {
         groupIdBytes: gid,
         signerBytes: aliceSigner,
         keyPackagesBytes: [bobKeyPkg.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await bobClient.joinGroupFromWelcome(
         config: cfg,
@@ -39,7 +39,11 @@ export 'src/openmls.dart';
 export 'src/rust/api/config.dart';
 export 'src/rust/api/credential.dart';
 export 'src/rust/api/engine.dart';
+export 'src/rust/api/init.dart'
+    show MlsLogRedaction, logRedaction, setLogRedaction;
 export 'src/rust/api/keys.dart';
 export 'src/rust/api/types.dart';
+export 'src/rust/api/v2.dart'
+    show MlsCompatMode, compatMode, setCompatMode;
 export 'src/security/secure_bytes.dart';
 export 'src/security/secure_uint8list.dart';
@@ -0,0 +1,19 @@
+/// Version 2 of the engine API: functions that take the engine and fail
+/// with a structured [MlsApiError] instead of a message string.
+///
+/// Import with a prefix next to the main library and migrate call sites
+/// one at a time:
+///
+/// ```dart
+/// import 'package:openmls/openmls.dart';
+/// import 'package:openmls/v2.dart' as v2;
+///
+/// final result = await v2.processMessage(
+///   engine: engine,
+///   groupIdBytes: groupId,
+///   messageBytes: message,
+/// );
+/// ```
+library;
+
+export 'src/rust/api/v2.dart';
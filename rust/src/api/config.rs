@@ -2,7 +2,12 @@
 
 use openmls::prelude::*;
 
-use super::types::{ciphersuite_to_native, wire_format_to_native, MlsCiphersuite, MlsWireFormatPolicy};
+use crate::snapshot_storage::SnapshotStorageProvider;
+
+use super::types::{
+    ciphersuite_to_native, native_to_ciphersuite, native_to_wire_format, protocol_version_to_native,
+    protocol_version_value, wire_format_to_native, MlsCiphersuite, MlsWireFormatPolicy,
+};
 
 /// Group configuration parameters.
 ///
@@ -13,10 +18,30 @@ pub struct MlsGroupConfig {
     pub use_ratchet_tree_extension: bool,
     pub max_past_epochs: u32,
     pub padding_size: u32,
+    /// How many past generations of a sender's application message ratchet
+    /// stay decryptable. Also applies to the handshake ratchet unless
+    /// `handshake_sender_ratchet_max_out_of_order` is set.
     pub sender_ratchet_max_out_of_order: u32,
     pub sender_ratchet_max_forward_distance: u32,
+    /// How many past generations of a sender's handshake ratchet (encrypted
+    /// commits and proposals) stay decryptable. None = same as
+    /// `sender_ratchet_max_out_of_order`.
+    ///
+    /// The join config has no room for it, so it is kept in the group's
+    /// metadata and swapped in while an encrypted handshake message is
+    /// processed.
+    pub handshake_sender_ratchet_max_out_of_order: Option<u32>,
     /// How many past resumption PSKs to keep (0 = none).
+    ///
+    /// OpenMLS sizes the resumption PSK store when the group is created or
+    /// joined; changing this later via `set_configuration` does not resize it.
+    /// Use `list_resumption_psks` to see which epochs are retained.
     pub number_of_resumption_psks: u32,
+    /// MLS protocol version of the group (1 = MLS 1.0). None = MLS 1.0.
+    ///
+    /// OpenMLS currently implements only MLS 1.0; other values are rejected
+    /// when creating or joining a group.
+    pub protocol_version: Option<u16>,
 }
 
 impl MlsGroupConfig {
@@ -31,8 +56,101 @@ impl MlsGroupConfig {
             padding_size: 0,
             sender_ratchet_max_out_of_order: 5,
             sender_ratchet_max_forward_distance: 1000,
+            handshake_sender_ratchet_max_out_of_order: None,
             number_of_resumption_psks: 0,
+            protocol_version: None,
+        }
+    }
+
+    /// The configured protocol version, checked to be supported.
+    pub(crate) fn native_protocol_version(&self) -> Result<ProtocolVersion, String> {
+        protocol_version_to_native(self.protocol_version.unwrap_or(1))
+    }
+
+    /// Ensure a created or joined group runs the configured protocol version.
+    pub(crate) fn check_protocol_version(&self, group: &MlsGroup) -> Result<(), String> {
+        let expected = self.native_protocol_version()?;
+        if group.version() != expected {
+            return Err(format!(
+                "Group uses MLS protocol version {:?}, config expects {:?}",
+                group.version(),
+                expected
+            ));
         }
+        Ok(())
+    }
+
+    /// The configuration `group` was created or joined with, as persisted
+    /// in its join config and metadata.
+    pub(crate) fn from_group(group: &MlsGroup, storage: &SnapshotStorageProvider) -> Result<MlsGroupConfig, String> {
+        let join_config = group.configuration();
+        // Not every setting has an accessor on `MlsGroupJoinConfig`.
+        let value = serde_json::to_value(join_config)
+            .map_err(|e| format!("Failed to serialize group configuration: {}", e))?;
+        let setting = |name: &str| value.get(name).and_then(serde_json::Value::as_u64).unwrap_or(0) as u32;
+        let ratchet = join_config.sender_ratchet_configuration();
+        Ok(MlsGroupConfig {
+            ciphersuite: native_to_ciphersuite(group.ciphersuite())?,
+            wire_format_policy: native_to_wire_format(join_config.wire_format_policy()),
+            use_ratchet_tree_extension: value
+                .get("use_ratchet_tree_extension")
+                .and_then(serde_json::Value::as_bool)
+                .unwrap_or(false),
+            max_past_epochs: setting("max_past_epochs"),
+            padding_size: join_config.padding_size() as u32,
+            sender_ratchet_max_out_of_order: ratchet.out_of_order_tolerance(),
+            sender_ratchet_max_forward_distance: ratchet.maximum_forward_distance(),
+            handshake_sender_ratchet_max_out_of_order: handshake_out_of_order(storage, group.group_id().as_slice())?,
+            number_of_resumption_psks: setting("number_of_resumption_psks"),
+            protocol_version: Some(protocol_version_value(group.version())),
+        })
+    }
+
+    /// Names of the settings in which this configuration differs from the
+    /// one persisted for `group`. Empty if they agree.
+    pub(crate) fn conflicts_with(&self, group: &MlsGroup, storage: &SnapshotStorageProvider) -> Result<Vec<String>, String> {
+        let stored = MlsGroupConfig::from_group(group, storage)?;
+        let checks = [
+            ("ciphersuite", ciphersuite_to_native(&self.ciphersuite) != group.ciphersuite()),
+            (
+                "wire_format_policy",
+                wire_format_to_native(&self.wire_format_policy) != wire_format_to_native(&stored.wire_format_policy),
+            ),
+            ("use_ratchet_tree_extension", self.use_ratchet_tree_extension != stored.use_ratchet_tree_extension),
+            ("max_past_epochs", self.max_past_epochs != stored.max_past_epochs),
+            ("padding_size", self.padding_size != stored.padding_size),
+            (
+                "sender_ratchet_max_out_of_order",
+                self.sender_ratchet_max_out_of_order != stored.sender_ratchet_max_out_of_order,
+            ),
+            (
+                "sender_ratchet_max_forward_distance",
+                self.sender_ratchet_max_forward_distance != stored.sender_ratchet_max_forward_distance,
+            ),
+            (
+                "handshake_sender_ratchet_max_out_of_order",
+                self.handshake_sender_ratchet_max_out_of_order != stored.handshake_sender_ratchet_max_out_of_order,
+            ),
+            ("number_of_resumption_psks", self.number_of_resumption_psks != stored.number_of_resumption_psks),
+            ("protocol_version", self.native_protocol_version()? != group.version()),
+        ];
+        Ok(checks
+            .into_iter()
+            .filter(|(_, differs)| *differs)
+            .map(|(name, _)| name.to_string())
+            .collect())
+    }
+
+    /// Store the settings of a created or joined group that its join config
+    /// cannot hold.
+    pub(crate) fn store_group_metadata(
+        &self,
+        storage: &mut SnapshotStorageProvider,
+        group_id: &[u8],
+    ) -> Result<(), String> {
+        storage
+            .write_app_group(group_id, HANDSHAKE_OUT_OF_ORDER, &self.handshake_sender_ratchet_max_out_of_order)
+            .map_err(|e| format!("Failed to write handshake ratchet tolerance: {}", e))
     }
 
     pub(crate) fn to_create_config(&self) -> MlsGroupCreateConfig {
@@ -67,3 +185,15 @@ impl MlsGroupConfig {
             .build()
     }
 }
+
+/// Group metadata entry holding `handshake_sender_ratchet_max_out_of_order`.
+const HANDSHAKE_OUT_OF_ORDER: &str = "handshake_out_of_order";
+
+/// The handshake ratchet's out-of-order tolerance stored for a group, if it
+/// differs from the application ratchet's.
+pub(crate) fn handshake_out_of_order(storage: &SnapshotStorageProvider, group_id: &[u8]) -> Result<Option<u32>, String> {
+    let stored: Option<Option<u32>> = storage
+        .app_group(group_id, HANDSHAKE_OUT_OF_ORDER)
+        .map_err(|e| format!("Failed to read handshake ratchet tolerance: {}", e))?;
+    Ok(stored.flatten())
+}
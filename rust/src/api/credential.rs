@@ -7,6 +7,34 @@ use openmls::prelude::tls_codec::{
     DeserializeBytes as TlsDeserializeBytes, Serialize as TlsSerialize,
 };
 
+/// Characters allowed in identity bytes, as checked by `validate_identity`.
+pub enum MlsIdentityCharset {
+    /// Any bytes.
+    Any,
+    /// Valid UTF-8 without control characters.
+    Utf8,
+    /// Printable ASCII (0x21..=0x7e): no spaces or control characters.
+    PrintableAscii,
+}
+
+/// Rules for identity bytes embedded in a `BasicCredential`.
+pub struct MlsIdentityRules {
+    pub min_length: u32,
+    pub max_length: u32,
+    pub charset: MlsIdentityCharset,
+}
+
+/// Parsed contents of a credential.
+pub struct MlsCredentialInfo {
+    /// Credential type value (1 = Basic, 2 = X509, 0 = other).
+    pub credential_type: u16,
+    /// Identity bytes, for a BasicCredential.
+    pub identity: Option<Vec<u8>>,
+    /// DER-encoded certificates, leaf first, for an X.509 credential.
+    /// Empty for other types.
+    pub certificate_chain: Vec<Vec<u8>>,
+}
+
 /// An opaque wrapper around an OpenMLS Credential.
 pub struct MlsCredential {
     inner: Credential,
@@ -30,6 +58,17 @@ impl MlsCredential {
         })
     }
 
+    /// Create a BasicCredential from identity bytes that pass
+    /// `validate_identity` under `rules`.
+    ///
+    /// Engine APIs accept the result through their `credential_bytes`
+    /// parameter (see `serialize`).
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn basic_validated(identity: Vec<u8>, rules: MlsIdentityRules) -> Result<MlsCredential, String> {
+        validate_identity(identity.clone(), rules)?;
+        Self::basic(identity)
+    }
+
     /// Create an X.509 credential from a certificate chain.
     ///
     /// Each entry in `certificate_chain` is a DER-encoded X.509 certificate.
@@ -87,6 +126,24 @@ impl MlsCredential {
         Ok(certs)
     }
 
+    /// Parse this credential into its type and, depending on the type, its
+    /// identity or certificate chain.
+    ///
+    /// Fails only if an X.509 credential's chain is malformed.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn info(&self) -> Result<MlsCredentialInfo, String> {
+        let credential_type = self.credential_type();
+        let identity = match credential_type {
+            1 => Some(self.identity()?),
+            _ => None,
+        };
+        let certificate_chain = match credential_type {
+            2 => self.certificates()?,
+            _ => Vec::new(),
+        };
+        Ok(MlsCredentialInfo { credential_type, identity, certificate_chain })
+    }
+
     /// Returns the raw serialized content of this credential.
     ///
     /// For BasicCredential, this is the identity bytes.
@@ -106,6 +163,15 @@ impl MlsCredential {
         }
     }
 
+    /// Whether `other` is the same credential (type and content), compared
+    /// in constant time.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn constant_time_eq(&self, other: &MlsCredential) -> bool {
+        let same_type = self.inner.credential_type() == other.inner.credential_type();
+        let same_content = crate::constant_time_eq(self.inner.serialized_content(), other.inner.serialized_content());
+        same_type & same_content
+    }
+
     /// TLS-serialize this credential for wire transmission.
     #[flutter_rust_bridge::frb(sync)]
     pub fn serialize(&self) -> Result<Vec<u8>, String> {
@@ -123,3 +189,53 @@ impl MlsCredential {
         Ok(MlsCredential { inner: credential })
     }
 }
+
+/// Parse a TLS-serialized credential, as found in `MlsMemberInfo` or
+/// `MlsCredentialCheck`, into its type, identity and certificate chain.
+#[flutter_rust_bridge::frb(sync)]
+pub fn credential_info(credential_bytes: Vec<u8>) -> Result<MlsCredentialInfo, String> {
+    MlsCredential::deserialize(credential_bytes)?.info()
+}
+
+/// Compare two byte strings (identities, signature keys, serialized
+/// credentials) in constant time.
+///
+/// The running time depends only on the lengths, which are not treated as
+/// secret. Use this instead of `==` on `Uint8List`s holding identity
+/// material.
+#[flutter_rust_bridge::frb(sync)]
+pub fn constant_time_eq(a: Vec<u8>, b: Vec<u8>) -> bool {
+    crate::constant_time_eq(&a, &b)
+}
+
+/// Check identity bytes against `rules` before they are embedded in a
+/// `BasicCredential`.
+///
+/// Fails with the first violation found: a length outside
+/// `min_length..=max_length`, or a byte sequence outside the charset.
+#[flutter_rust_bridge::frb(sync)]
+pub fn validate_identity(identity: Vec<u8>, rules: MlsIdentityRules) -> Result<(), String> {
+    let len = identity.len();
+    if len < rules.min_length as usize || len > rules.max_length as usize {
+        return Err(format!(
+            "Invalid identity: length {} is outside {}..={}",
+            len, rules.min_length, rules.max_length
+        ));
+    }
+    match rules.charset {
+        MlsIdentityCharset::Any => {}
+        MlsIdentityCharset::Utf8 => {
+            let text = std::str::from_utf8(&identity)
+                .map_err(|e| format!("Invalid identity: not UTF-8 ({})", e))?;
+            if let Some(pos) = text.char_indices().find(|(_, c)| c.is_control()).map(|(pos, _)| pos) {
+                return Err(format!("Invalid identity: control character at byte {}", pos));
+            }
+        }
+        MlsIdentityCharset::PrintableAscii => {
+            if let Some(pos) = identity.iter().position(|b| !(0x21..=0x7e).contains(b)) {
+                return Err(format!("Invalid identity: byte {} is not printable ASCII", pos));
+            }
+        }
+    }
+    Ok(())
+}
@@ -10,19 +10,40 @@
 use openmls::prelude::*;
 use openmls::prelude::tls_codec::{DeserializeBytes as TlsDeserializeBytes, Serialize as TlsSerialize};
 use openmls::ciphersuite::hash_ref::ProposalRef;
-use openmls::schedule::PreSharedKeyId;
+use openmls::messages::proposals_in::ProposalOrRefIn;
+use openmls::schedule::{PreSharedKeyId, Psk};
+use openmls::tree::secret_tree::SecretTreeError;
+use openmls_basic_credential::SignatureKeyPair;
 use openmls_traits::OpenMlsProvider;
+use openmls_traits::crypto::OpenMlsCrypto;
 use openmls_traits::storage::StorageProvider;
+use openmls_traits::types::{HashType, HpkeCiphertext, HpkeKeyPair};
 
-use super::config::MlsGroupConfig;
-use super::keys::signer_from_bytes;
+use super::config::{handshake_out_of_order, MlsGroupConfig};
+use super::keys::{signer_from_bytes, signer_to_bytes};
+use super::v2::{MlsApiError, MlsErrorKind};
 use super::types::{
-    ciphersuite_to_native, native_to_ciphersuite, capabilities_to_native, extensions_from_mls,
-    FlexibleCommitOptions, KeyPackageOptions, MlsCapabilities, MlsCiphersuite, MlsExtension,
-    MlsGroupContextInfo, MlsLeafNodeInfo, MlsMemberInfo, MlsPendingProposalInfo, MlsProposalType,
-    MlsWireFormatPolicy, ProcessedMessageType, StagedCommitInfo, WelcomeInspectResult,
+    ciphersuite_to_native, native_to_ciphersuite, native_to_wire_format, DecryptionFailureKind, capabilities_from_native, capabilities_to_native, extension_to_mls, extensions_from_mls,
+    group_context_extensions_change, group_features_from_extensions, group_features_to_extension, protocol_version_value, FlexibleCommitOptions,
+    KeyPackageInspectResult, KeyPackageOptions, KeyPackageValidationOptions, MessageCompressionInfo, MessagePaddingInfo, MlsAddCandidateIncompatibility, MlsCommitRejection, MlsCommittedProposal, MlsSecurityFinding,
+    MlsSecurityFindingKind, MlsSecurityPosture, MlsSecuritySeverity, MlsCapabilities, MlsCiphersuite, MlsCredentialCheck, MlsCredentialVerdict, MlsExtension, MlsExternalJoinInfo, MlsGroupContextInfo,
+    MlsGroupFeatures, MlsLeafNodeInfo, MlsMemberInfo, MlsPendingProposalInfo, MlsProposalCheck, MlsProposalFilter, MlsProposalType, MlsPskId, MlsPskKind, MlsPublicStateInfo,
+    MlsWireFormat, MlsWireFormatPolicy, ProcessedMessageType, StagedCommitInfo, WelcomeInspectResult, WelcomeMemberPreview,
+    group_topic_from_extensions, group_topic_to_extension, GROUP_FEATURE_COMPRESSION, GROUP_FEATURES_EXTENSION_TYPE,
+    GROUP_TOPIC_EXTENSION_TYPE,
+};
+use crate::audit_log::{AuditEntry, ProposalSummary};
+use crate::redact::{self, Sensitive};
+use crate::encrypted_db::StorageUpdates;
+use crate::frb_generated::StreamSink;
+use flutter_rust_bridge::{DartFnFuture, ZeroCopyBuffer};
+use crate::invite::{sign_content as invite_sign_content, InvitePayload};
+use crate::sandbox::{EngineStore, SandboxStore};
+use crate::snapshot_storage::{
+    encryption_key_pair_key, storage_key_group_id, SnapshotOpenMlsProvider, SnapshotStorageProvider, APP_GLOBAL_LABEL,
+    APP_GROUP_LABEL, ENCRYPTION_KEY_PAIR_LABEL, GROUP_STATE_LABEL, KEY_PACKAGE_LABEL, OPENMLS_LABELS,
+    SIGNATURE_KEY_PAIR_LABEL,
 };
-use crate::snapshot_storage::{SnapshotOpenMlsProvider, SnapshotStorageProvider};
 
 // ═══════════════════════════════════════════════════════════════
 // HELPERS
@@ -47,1696 +68,9448 @@ fn build_credential_with_key(
 }
 
 /// Load an MlsGroup from the provider's storage.
-fn load_group(group_id: &[u8], provider: &SnapshotOpenMlsProvider) -> Result<MlsGroup, String> {
+fn load_group(group_id: &[u8], provider: &SnapshotOpenMlsProvider) -> Result<MlsGroup, MlsApiError> {
     let gid = GroupId::from_slice(group_id);
     MlsGroup::load(provider.storage(), &gid)
-        .map_err(|e| format!("Failed to load group: {}", e))?
-        .ok_or_else(|| "No group found in storage".to_string())
+        .map_err(|e| MlsApiError::new(MlsErrorKind::Storage, format!("Failed to load group: {}", e)))?
+        .ok_or_else(|| MlsApiError::new(MlsErrorKind::NotFound, "No group found in storage"))
 }
 
-// ═══════════════════════════════════════════════════════════════
-// RESULT TYPES
-// ═══════════════════════════════════════════════════════════════
-
-pub struct CreateGroupResult {
-    pub group_id: Vec<u8>,
+/// The HPKE key pair attachment keys are wrapped to in the current epoch.
+fn attachment_key_pair(group: &MlsGroup, provider: &SnapshotOpenMlsProvider) -> Result<HpkeKeyPair, String> {
+    let ikm = group
+        .export_secret(
+            provider.crypto(),
+            crate::attachment::ATTACHMENT_EXPORTER_LABEL,
+            &[],
+            group.ciphersuite().hash_length(),
+        )
+        .map_err(|e| format!("Failed to export secret: {}", e))?;
+    provider
+        .crypto()
+        .derive_hpke_keypair(group.ciphersuite().hpke_config(), &ikm)
+        .map_err(|e| format!("Failed to derive attachment key pair: {:?}", e))
 }
 
-pub struct JoinGroupResult {
-    pub group_id: Vec<u8>,
+/// Whether application messages of `group` are compressed.
+fn compression_enabled(group: &MlsGroup) -> Result<bool, String> {
+    Ok(group_features_from_extensions(group.extensions())?
+        .is_some_and(|f| f.features & GROUP_FEATURE_COMPRESSION != 0))
 }
 
-pub struct ExternalJoinResult {
-    pub group_id: Vec<u8>,
-    pub commit: Vec<u8>,
-    pub group_info: Option<Vec<u8>>,
+/// Group metadata entry holding the epochs at which the compression flag
+/// changed, as `(epoch, enabled)` in ascending order, so application
+/// messages from past epochs are decoded with the flag they were sent
+/// under. See `record_compression_change`.
+const COMPRESSION_HISTORY: &str = "compression_history";
+
+/// Changes kept in `COMPRESSION_HISTORY`; far more epochs than OpenMLS
+/// keeps secrets for.
+const MAX_COMPRESSION_HISTORY: usize = 32;
+
+/// Record the compression flag of the epoch a commit just moved `group`
+/// to, if it differs from `enabled_before`, the flag of the epoch before.
+fn record_compression_change(
+    group: &MlsGroup,
+    provider: &mut SnapshotOpenMlsProvider,
+    enabled_before: bool,
+) -> Result<(), String> {
+    let enabled = compression_enabled(group)?;
+    if enabled == enabled_before {
+        return Ok(());
+    }
+    let group_id = group.group_id().as_slice();
+    let mut history: Vec<(u64, bool)> = provider
+        .storage()
+        .app_group(group_id, COMPRESSION_HISTORY)
+        .map_err(|e| format!("Failed to read compression history: {}", e))?
+        .unwrap_or_default();
+    let epoch = group.epoch().as_u64();
+    if history.last().is_none_or(|&(_, last)| last != enabled_before) {
+        history.push((epoch.saturating_sub(1), enabled_before));
+    }
+    history.push((epoch, enabled));
+    let excess = history.len().saturating_sub(MAX_COMPRESSION_HISTORY);
+    history.drain(..excess);
+    provider
+        .storage_mut()
+        .write_app_group(group_id, COMPRESSION_HISTORY, &history)
+        .map_err(|e| format!("Failed to write compression history: {}", e))
 }
 
-pub struct AddMembersResult {
-    pub commit: Vec<u8>,
-    pub welcome: Vec<u8>,
-    pub group_info: Option<Vec<u8>>,
+/// Whether application messages of `group` sent in `epoch` are compressed.
+fn compression_enabled_at(
+    group: &MlsGroup,
+    storage: &SnapshotStorageProvider,
+    epoch: u64,
+) -> Result<bool, String> {
+    if epoch >= group.epoch().as_u64() {
+        return compression_enabled(group);
+    }
+    let history: Vec<(u64, bool)> = storage
+        .app_group(group.group_id().as_slice(), COMPRESSION_HISTORY)
+        .map_err(|e| format!("Failed to read compression history: {}", e))?
+        .unwrap_or_default();
+    // Before the first recorded change the flag was that change's
+    // `enabled_before`, the first entry.
+    match history.iter().rev().find(|&&(from, _)| from <= epoch).or(history.first()) {
+        Some(&(_, enabled)) => Ok(enabled),
+        None => compression_enabled(group),
+    }
 }
 
-pub struct CommitResult {
-    pub commit: Vec<u8>,
-    pub welcome: Option<Vec<u8>>,
-    pub group_info: Option<Vec<u8>>,
+/// Undo the compression of a received application message, if the group
+/// compressed them in the epoch it was sent in.
+fn decompress_application_message(
+    group: &MlsGroup,
+    storage: &SnapshotStorageProvider,
+    message_epoch: u64,
+    plaintext: Vec<u8>,
+) -> Result<(Vec<u8>, Option<MessageCompressionInfo>), String> {
+    if !compression_enabled_at(group, storage, message_epoch)? {
+        return Ok((plaintext, None));
+    }
+    let (message, compressed) = crate::compression::decompress(&plaintext)?;
+    let info = MessageCompressionInfo {
+        compressed,
+        original_size: message.len() as u64,
+        compressed_size: plaintext.len() as u64,
+    };
+    Ok((message, Some(info)))
 }
 
-pub struct ProposalResult {
-    pub proposal_message: Vec<u8>,
-}
+/// Work out the padding of a received application message from the size of
+/// its encrypted content. `plaintext_len` is the size of the decrypted
+/// application data, before decompression. `None` for PublicMessages.
+fn application_message_padding(
+    group: &MlsGroup,
+    message_bytes: &[u8],
+    plaintext_len: usize,
+) -> Result<Option<MessagePaddingInfo>, String> {
+    fn vl(input: &[u8]) -> Result<(tls_codec::VLBytes, &[u8]), String> {
+        tls_codec::VLBytes::tls_deserialize_bytes(input).map_err(|e| format!("Malformed message: {}", e))
+    }
+    /// Encoded size of an opaque<V> of `len` bytes.
+    fn vl_size(len: usize) -> usize {
+        let prefix = match len {
+            0..=63 => 1,
+            64..=16383 => 2,
+            _ => 4,
+        };
+        prefix + len
+    }
+    /// All MLS AEADs (AES-GCM, ChaCha20-Poly1305) have 16-byte tags.
+    const AEAD_TAG_LEN: usize = 16;
 
-pub struct CreateMessageResult {
-    pub ciphertext: Vec<u8>,
+    // MLSMessage: version (u16) || wire_format (u16), 2 = PrivateMessage
+    if message_bytes.len() < 4 || u16::from_be_bytes([message_bytes[2], message_bytes[3]]) != 2 {
+        return Ok(None);
+    }
+    // PrivateMessage: group_id<V>, epoch (u64), content_type (u8),
+    // authenticated_data<V>, encrypted_sender_data<V>, ciphertext<V>
+    let (_, rest) = vl(&message_bytes[4..])?;
+    if rest.len() < 9 {
+        return Err("Malformed message: truncated".to_string());
+    }
+    let (_, rest) = vl(&rest[9..])?;
+    let (_, rest) = vl(rest)?;
+    let (ciphertext, _) = vl(rest)?;
+    let encrypted_content_size = ciphertext.as_slice().len();
+
+    // PrivateMessageContent: application_data<V>, signature<V>, padding
+    let (signature_len, padding_exact) = match group.ciphersuite().signature_algorithm() {
+        SignatureScheme::ED25519 => (64, true),
+        SignatureScheme::ED448 => (114, true),
+        SignatureScheme::ECDSA_SECP256R1_SHA256 => (72, false),
+        SignatureScheme::ECDSA_SECP384R1_SHA384 => (104, false),
+        _ => (141, false),
+    };
+    let padding_size = encrypted_content_size
+        .saturating_sub(AEAD_TAG_LEN + vl_size(plaintext_len) + vl_size(signature_len));
+    Ok(Some(MessagePaddingInfo {
+        ciphertext_size: message_bytes.len() as u64,
+        encrypted_content_size: encrypted_content_size as u64,
+        padding_size: padding_size as u64,
+        padding_exact,
+    }))
 }
 
-pub struct ProcessedMessageResult {
-    pub message_type: ProcessedMessageType,
-    pub sender_index: Option<u32>,
-    pub epoch: u64,
-    pub application_message: Option<Vec<u8>>,
-    pub has_staged_commit: bool,
-    pub has_proposal: bool,
-    pub proposal_type: Option<MlsProposalType>,
+/// Check whether the member at `leaf_index` may change the group features.
+///
+/// Allowed if the group has no features extension yet, or its authorized
+/// key list is empty, or the member's signature key is in that list.
+fn group_features_change_allowed(group: &MlsGroup, leaf_index: LeafNodeIndex) -> Result<bool, String> {
+    let current = match group_features_from_extensions(group.extensions())? {
+        Some(current) if !current.authorized_signature_keys.is_empty() => current,
+        _ => return Ok(true),
+    };
+    Ok(group
+        .member_at(leaf_index)
+        .is_some_and(|m| current.authorized_signature_keys.contains(&m.signature_key)))
 }
 
-pub struct ProcessedMessageInspectResult {
-    pub message_type: ProcessedMessageType,
-    pub sender_index: Option<u32>,
-    pub epoch: u64,
-    pub application_message: Option<Vec<u8>>,
-    pub staged_commit_info: Option<StagedCommitInfo>,
-    pub proposal_type: Option<MlsProposalType>,
+/// Whether `extensions` set different group features than `group` has.
+fn changes_group_features(group: &MlsGroup, extensions: &Extensions) -> Result<bool, String> {
+    let current = group_features_from_extensions(group.extensions())?;
+    let proposed = group_features_from_extensions(extensions)?;
+    Ok(match (&current, &proposed) {
+        (None, None) => false,
+        (Some(a), Some(b)) => a.features != b.features
+            || a.authorized_signature_keys != b.authorized_signature_keys,
+        _ => true,
+    })
 }
 
-pub struct KeyPackageResult {
-    pub key_package_bytes: Vec<u8>,
+/// Fail unless the local member may set `extensions` as the group context
+/// extensions of `group`.
+fn check_own_group_features_change(group: &MlsGroup, extensions: &Extensions) -> Result<(), String> {
+    if changes_group_features(group, extensions)? && !group_features_change_allowed(group, group.own_leaf_index())? {
+        return Err("Not authorized to change group features".to_string());
+    }
+    Ok(())
 }
 
-pub struct LeaveGroupResult {
-    pub message: Vec<u8>,
+/// Whether `queued` may be committed by `committer`: a proposal changing
+/// the group features needs both its sender and the committer authorized.
+fn group_features_proposal_allowed(
+    group: &MlsGroup,
+    committer: &Sender,
+    queued: &QueuedProposal,
+) -> Result<bool, String> {
+    let Proposal::GroupContextExtensions(gce) = queued.proposal() else { return Ok(true) };
+    if !changes_group_features(group, gce.extensions())? {
+        return Ok(true);
+    }
+    for sender in [queued.sender(), committer] {
+        let allowed = match sender {
+            Sender::Member(idx) => group_features_change_allowed(group, *idx)?,
+            _ => false,
+        };
+        if !allowed {
+            return Ok(false);
+        }
+    }
+    Ok(true)
 }
 
-pub struct GroupConfigurationResult {
-    pub ciphersuite: MlsCiphersuite,
-    pub wire_format_policy: MlsWireFormatPolicy,
-    pub padding_size: u32,
-    pub sender_ratchet_max_out_of_order: u32,
-    pub sender_ratchet_max_forward_distance: u32,
+/// Whether a staged commit changes the group features on behalf of a
+/// member who is not authorized to do so.
+///
+/// The other members merge such a commit all the same, so it is merged here
+/// too and only reported (`unauthorized_features_change`); the policy is
+/// enforced when commits are created (see `refused_proposals`).
+fn unauthorized_group_features_change(
+    group: &MlsGroup,
+    committer: &Sender,
+    staged_commit: &StagedCommit,
+) -> Result<bool, String> {
+    for qp in staged_commit.queued_proposals() {
+        if !group_features_proposal_allowed(group, committer, qp)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
 }
 
-// ═══════════════════════════════════════════════════════════════
-// MLS ENGINE
-// ═══════════════════════════════════════════════════════════════
+/// Collect RFC 9420 requirements a staged commit violates that OpenMLS
+/// itself does not check: duplicate credentials among the resulting members
+/// (§5.3), and added members whose capabilities lack the group's
+/// ciphersuite, extensions or required proposal and credential types
+/// (§11.1), or that do not share credential type support with the other
+/// members (§7.2).
+fn commit_conformance_deviations(group: &MlsGroup, staged_commit: &StagedCommit) -> Result<Vec<String>, String> {
+    let removed: Vec<LeafNodeIndex> = staged_commit.remove_proposals().map(|r| r.remove_proposal().removed()).collect();
+    let mut credentials = Vec::new();
+    for member in group.members().filter(|m| !removed.contains(&m.index)) {
+        credentials.push(member.credential.tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize credential: {}", e))?);
+    }
 
-pub struct MlsEngine {
-    db: parking_lot::RwLock<Option<std::sync::Arc<crate::encrypted_db::EncryptedDb>>>,
-}
+    // (credential type, supported credential types) of the members staying.
+    let tree_bytes = group
+        .export_ratchet_tree()
+        .tls_serialize_detached()
+        .map_err(|e| format!("Failed to serialize ratchet tree: {}", e))?;
+    let members: Vec<(u16, Vec<u16>)> = crate::tree_view::parse_ratchet_tree(&tree_bytes)?
+        .into_iter()
+        .step_by(2)
+        .enumerate()
+        .filter(|(leaf_index, _)| !removed.contains(&LeafNodeIndex::new(*leaf_index as u32)))
+        .filter_map(|(_, node)| match node {
+            Some(crate::tree_view::TreeNode::Leaf { credential_type, credentials, .. }) => {
+                Some((credential_type, credentials))
+            }
+            _ => None,
+        })
+        .collect();
 
-impl MlsEngine {
-    // ═══════════════════════════════════════════════════════════
-    // CONSTRUCTOR
-    // ═══════════════════════════════════════════════════════════
+    let mut deviations = Vec::new();
+    for (i, add) in staged_commit.add_proposals().enumerate() {
+        let key_package = add.add_proposal().key_package();
+        let leaf = key_package.leaf_node();
+        let cred_bytes = leaf.credential()
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize add credential: {}", e))?;
+        if credentials.contains(&cred_bytes) {
+            deviations.push("commit adds a credential already present in the group".to_string());
+        }
+        credentials.push(cred_bytes);
 
-    /// Create a new MlsEngine backed by an encrypted database.
-    ///
-    /// # Arguments
-    ///
-    /// * `db_path` — Database location.
-    ///   - **Native**: file path for SQLCipher (e.g. `"path/to/mls.db"`).
-    ///     Use `":memory:"` for an ephemeral in-memory database (destroyed on drop,
-    ///     useful for tests).
-    ///   - **WASM**: IndexedDB database name (e.g. `"mls_account_123"`).
-    ///     `":memory:"` generates a unique random name per instance to match the
-    ///     native ephemeral behavior.
-    ///   - Tip: include an account identifier in the path to isolate data per user
-    ///     (e.g. `"mls_{account_id}.db"` on native, `"mls_{account_id}"` on web).
-    ///
-    /// * `encryption_key` — 32-byte AES-256 key that protects data at rest.
-    ///   The caller is responsible for generating, storing, and providing this key.
-    ///   Recommended pattern: generate a random key on first launch and persist it
-    ///   in platform secure storage (e.g. Keychain on iOS/macOS, Android Keystore,
-    ///   or `flutter_secure_storage`).
-    pub async fn create(db_path: String, encryption_key: Vec<u8>) -> Result<MlsEngine, String> {
-        let db = crate::encrypted_db::EncryptedDb::open(db_path, encryption_key).await?;
-        Ok(MlsEngine { db: parking_lot::RwLock::new(Some(std::sync::Arc::new(db))) })
+        let capabilities = leaf.capabilities();
+        let ciphersuite = group.ciphersuite() as u16;
+        if !capabilities.ciphersuites().iter().any(|c| c.value() == ciphersuite) {
+            deviations.push(format!("added member does not support the group ciphersuite 0x{:04x}", ciphersuite));
+        }
+        if let Some(missing) = add_candidate_incompatibility(group, i, key_package) {
+            for t in missing.missing_extensions {
+                deviations.push(format!("added member does not support group extension 0x{:04x}", t));
+            }
+            for t in missing.missing_proposals {
+                deviations.push(format!("added member does not support required proposal type 0x{:04x}", t));
+            }
+            for t in missing.missing_credentials {
+                deviations.push(format!("added member does not support required credential type 0x{:04x}", t));
+            }
+        }
+
+        // RFC 9420 §7.2: every member supports every member's credential type.
+        let added_type = u16::from(leaf.credential().credential_type());
+        let mut member_types: Vec<u16> = members.iter().map(|(t, _)| *t).collect();
+        member_types.sort_unstable();
+        member_types.dedup();
+        for t in member_types {
+            if !capabilities.credentials().iter().any(|c| u16::from(*c) == t) {
+                deviations.push(format!("added member does not support member credential type 0x{:04x}", t));
+            }
+        }
+        if members.iter().any(|(_, supported)| !supported.contains(&added_type)) {
+            deviations.push(format!("a member does not support the added credential type 0x{:04x}", added_type));
+        }
     }
+    Ok(deviations)
+}
 
-    // ═══════════════════════════════════════════════════════════
-    // INTERNAL HELPERS
-    // ═══════════════════════════════════════════════════════════
+/// Leaf indices of members whose capabilities do not list the custom
+/// proposal type `proposal_type`.
+fn members_lacking_proposal_type(group: &MlsGroup, proposal_type: u16) -> Result<Vec<u32>, String> {
+    let tree_bytes = group
+        .export_ratchet_tree()
+        .tls_serialize_detached()
+        .map_err(|e| format!("Failed to serialize ratchet tree: {}", e))?;
+    Ok(crate::tree_view::parse_ratchet_tree(&tree_bytes)?
+        .iter()
+        .step_by(2)
+        .enumerate()
+        .filter_map(|(leaf_index, node)| match node {
+            Some(crate::tree_view::TreeNode::Leaf { proposals, .. }) if !proposals.contains(&proposal_type) => {
+                Some(leaf_index as u32)
+            }
+            _ => None,
+        })
+        .collect())
+}
 
-    fn db(&self) -> Result<std::sync::Arc<crate::encrypted_db::EncryptedDb>, String> {
-        self.db.read().as_ref().cloned().ok_or_else(|| "MlsEngine is closed".to_string())
-    }
+/// Global metadata entry listing key packages uploaded to delivery services.
+const PUBLISHED_KEY_PACKAGES: &str = "published_key_packages";
 
-    async fn load_for_group(&self, group_id: &[u8]) -> Result<SnapshotOpenMlsProvider, String> {
-        let entries = self.db()?.load_for_group(group_id).await?;
-        Ok(SnapshotOpenMlsProvider::new(SnapshotStorageProvider::from_entries(entries)))
+#[derive(serde::Serialize, serde::Deserialize)]
+struct PublishedKeyPackage {
+    key_package_ref: Vec<u8>,
+    ds_id: String,
+    consumed: bool,
+}
+
+/// TLS-serialized refs of the key packages a Welcome is encrypted to.
+fn welcome_key_package_refs(welcome: &Welcome) -> Result<Vec<Vec<u8>>, String> {
+    welcome
+        .secrets()
+        .iter()
+        .map(|secrets| {
+            secrets.new_member()
+                .tls_serialize_detached()
+                .map_err(|e| format!("Failed to serialize key package ref: {}", e))
+        })
+        .collect()
+}
+
+/// Local key packages a Welcome is encrypted to, and which one it consumes.
+struct WelcomeKeyPackages {
+    /// Ref of the local key package the join uses.
+    chosen: Option<Vec<u8>>,
+    /// Other matching local key packages, hidden from OpenMLS for the join
+    /// and restored afterwards.
+    hidden: Vec<(openmls::ciphersuite::hash_ref::KeyPackageRef, KeyPackageBundle)>,
+}
+
+/// Pick which local key package a Welcome consumes when it matches several.
+///
+/// Uses `preferred` if given; otherwise the first match (in Welcome order)
+/// that is not last-resort, falling back to the first match. The other
+/// matches are removed from the snapshot so OpenMLS cannot pick them.
+fn select_welcome_key_package(
+    welcome: &Welcome,
+    provider: &SnapshotOpenMlsProvider,
+    preferred: Option<&[u8]>,
+) -> Result<WelcomeKeyPackages, String> {
+    let welcome_refs = welcome_key_package_refs(welcome)?;
+    let mut matches = Vec::new();
+    for ref_bytes in &welcome_refs {
+        let hash_ref = openmls::ciphersuite::hash_ref::KeyPackageRef::tls_deserialize_exact_bytes(ref_bytes)
+            .map_err(|e| format!("Failed to deserialize key package ref: {}", e))?;
+        let bundle: Option<KeyPackageBundle> = provider.storage().key_package(&hash_ref)
+            .map_err(|e| format!("Failed to read key package: {}", e))?;
+        if let Some(bundle) = bundle {
+            matches.push((ref_bytes.clone(), hash_ref, bundle));
+        }
     }
 
-    async fn load_global(&self) -> Result<SnapshotOpenMlsProvider, String> {
-        let entries = self.db()?.load_global().await?;
-        Ok(SnapshotOpenMlsProvider::new(SnapshotStorageProvider::from_entries(entries)))
+    let chosen_index = match preferred {
+        Some(preferred) => Some(
+            matches
+                .iter()
+                .position(|(r, _, _)| r.as_slice() == preferred)
+                .ok_or_else(|| "Preferred key package is not a local recipient of this Welcome".to_string())?,
+        ),
+        None => matches
+            .iter()
+            .position(|(_, _, bundle)| !bundle.key_package().last_resort())
+            .or(if matches.is_empty() { None } else { Some(0) }),
+    };
+    let chosen = chosen_index.map(|i| matches[i].0.clone());
+
+    let mut hidden = Vec::new();
+    for (i, (_, hash_ref, bundle)) in matches.into_iter().enumerate() {
+        if Some(i) != chosen_index {
+            provider.storage().delete_key_package(&hash_ref)
+                .map_err(|e| format!("Failed to delete key package: {}", e))?;
+            hidden.push((hash_ref, bundle));
+        }
     }
+    Ok(WelcomeKeyPackages { chosen, hidden })
+}
 
-    async fn commit(&self, provider: SnapshotOpenMlsProvider, group_id: Option<&[u8]>) -> Result<(), String> {
-        let updates = provider.into_storage().into_updates();
-        if updates.upserts.is_empty() && updates.deletes.is_empty() {
-            return Ok(());
+/// Check a key package's leaf node capabilities against what `group`
+/// requires of its members. `None` if the key package is compatible.
+fn add_candidate_incompatibility(
+    group: &MlsGroup,
+    index: usize,
+    key_package: &KeyPackage,
+) -> Option<MlsAddCandidateIncompatibility> {
+    let capabilities = key_package.leaf_node().capabilities();
+    let mut missing_extensions: Vec<u16> = group
+        .extensions()
+        .iter()
+        .map(|ext| ext.extension_type())
+        .filter(|t| !t.is_default() && !capabilities.contains_extension(*t))
+        .map(u16::from)
+        .collect();
+    let mut missing_proposals = Vec::new();
+    let mut missing_credentials = Vec::new();
+    if let Some(required) = group.extensions().required_capabilities() {
+        for t in required.extension_types() {
+            let value = u16::from(*t);
+            if !t.is_default() && !capabilities.contains_extension(*t) && !missing_extensions.contains(&value) {
+                missing_extensions.push(value);
+            }
         }
-        self.db()?.save_updates(updates, group_id).await
+        missing_proposals = required
+            .proposal_types()
+            .iter()
+            .filter(|t| !t.is_default() && !capabilities.contains_proposal(**t))
+            .map(|t| u16::from(*t))
+            .collect();
+        missing_credentials = required
+            .credential_types()
+            .iter()
+            .filter(|t| !capabilities.contains_credential(**t))
+            .map(|t| u16::from(*t))
+            .collect();
+    }
+    if missing_extensions.is_empty() && missing_proposals.is_empty() && missing_credentials.is_empty() {
+        return None;
     }
+    Some(MlsAddCandidateIncompatibility {
+        index: index as u32,
+        missing_extensions,
+        missing_proposals,
+        missing_credentials,
+    })
+}
 
-    // ═══════════════════════════════════════════════════════════
-    // KEY PACKAGES
-    // ═══════════════════════════════════════════════════════════
+/// Fail with every incompatible candidate listed before OpenMLS gets to
+/// reject the whole commit with an error naming none of them.
+fn check_add_candidates(group: &MlsGroup, key_packages: &[KeyPackage]) -> Result<(), String> {
+    let failures: Vec<String> = key_packages
+        .iter()
+        .enumerate()
+        .filter_map(|(i, kp)| add_candidate_incompatibility(group, i, kp))
+        .map(|f| {
+            format!(
+                "key package {} lacks extensions {:?}, proposals {:?}, credentials {:?}",
+                f.index, f.missing_extensions, f.missing_proposals, f.missing_credentials
+            )
+        })
+        .collect();
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Key packages do not meet the group's required capabilities: {}", failures.join("; ")))
+    }
+}
 
-    pub async fn create_key_package(
-        &self,
-        ciphersuite: MlsCiphersuite,
-        signer_bytes: Vec<u8>,
-        credential_identity: Vec<u8>,
-        signer_public_key: Vec<u8>,
-        credential_bytes: Option<Vec<u8>>,
-    ) -> Result<KeyPackageResult, String> {
-        let cs = ciphersuite_to_native(&ciphersuite);
-        let signer = signer_from_bytes(signer_bytes)?;
-        let credential_with_key = build_credential_with_key(
-            &credential_identity, &signer_public_key, credential_bytes.as_deref(),
-        )?;
+/// Mark the published key package a Welcome join consumed. Other key
+/// packages the Welcome was encrypted to belong to other members, or stay
+/// unused and valid for later invites.
+fn mark_published_consumed(storage: &mut SnapshotStorageProvider, consumed_ref: Option<&[u8]>) -> Result<(), String> {
+    let Some(consumed_ref) = consumed_ref else { return Ok(()) };
+    let mut published: Vec<PublishedKeyPackage> = storage
+        .app_global(PUBLISHED_KEY_PACKAGES)
+        .map_err(|e| format!("Failed to read published key packages: {}", e))?
+        .unwrap_or_default();
+    let Some(entry) = published.iter_mut().find(|p| p.key_package_ref == consumed_ref) else {
+        return Ok(());
+    };
+    entry.consumed = true;
+    storage
+        .write_app_global(PUBLISHED_KEY_PACKAGES, &published)
+        .map_err(|e| format!("Failed to write published key packages: {}", e))?;
+    Ok(())
+}
 
-        let provider = self.load_global().await?;
+/// Group metadata entry holding the last accepted GroupInfo.
+const CACHED_GROUP_INFO: &str = "cached_group_info";
 
-        let key_package_bundle = KeyPackage::builder()
-            .build(cs, &provider, &signer, credential_with_key)
-            .map_err(|e| format!("Failed to create key package: {}", e))?;
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CachedGroupInfo {
+    epoch: u64,
+    group_info: Vec<u8>,
+}
 
-        let kp_bytes = key_package_bundle
-            .key_package()
-            .tls_serialize_detached()
-            .map_err(|e| format!("Failed to serialize key package: {}", e))?;
+/// Fields of a TLS-encoded GroupInfo (RFC 9420 §12.4.3) needed to verify it.
+struct GroupInfoParts<'a> {
+    ciphersuite: u16,
+    group_id: Vec<u8>,
+    epoch: u64,
+    /// Encoded extension lists of the group context and of the GroupInfo,
+    /// without their length prefix.
+    context_extensions: Vec<u8>,
+    extensions: Vec<u8>,
+    signer: u32,
+    /// The GroupInfoTBS bytes covered by the signature.
+    tbs: &'a [u8],
+    signature: Vec<u8>,
+}
 
-        self.commit(provider, None).await?;
+fn parse_group_info(body: &[u8]) -> Result<GroupInfoParts<'_>, String> {
+    fn vl(input: &[u8]) -> Result<(tls_codec::VLBytes, &[u8]), String> {
+        tls_codec::VLBytes::tls_deserialize_bytes(input).map_err(|e| format!("Malformed group info: {}", e))
+    }
+    fn fixed(input: &[u8], len: usize) -> Result<(&[u8], &[u8]), String> {
+        if input.len() < len {
+            return Err("Malformed group info: truncated".to_string());
+        }
+        Ok(input.split_at(len))
+    }
 
-        Ok(KeyPackageResult {
-            key_package_bytes: kp_bytes,
-        })
+    // GroupContext: version, cipher_suite, group_id<V>, epoch, tree_hash<V>,
+    // confirmed_transcript_hash<V>, extensions<V>
+    let (header, rest) = fixed(body, 4)?;
+    let (group_id, rest) = vl(rest)?;
+    let (epoch, rest) = fixed(rest, 8)?;
+    let (_, rest) = vl(rest)?;
+    let (_, rest) = vl(rest)?;
+    let (context_extensions, rest) = vl(rest)?;
+    // GroupInfo: extensions<V>, confirmation_tag<V>, signer (u32), signature<V>
+    let (extensions, rest) = vl(rest)?;
+    let (_, rest) = vl(rest)?;
+    let (signer, rest) = fixed(rest, 4)?;
+    let tbs = &body[..body.len() - rest.len()];
+    let (signature, rest) = vl(rest)?;
+    if !rest.is_empty() {
+        return Err("Malformed group info: trailing bytes".to_string());
     }
 
-    pub async fn create_key_package_with_options(
-        &self,
-        ciphersuite: MlsCiphersuite,
-        signer_bytes: Vec<u8>,
-        credential_identity: Vec<u8>,
-        signer_public_key: Vec<u8>,
-        options: KeyPackageOptions,
-        credential_bytes: Option<Vec<u8>>,
-    ) -> Result<KeyPackageResult, String> {
-        let cs = ciphersuite_to_native(&ciphersuite);
-        let signer = signer_from_bytes(signer_bytes)?;
-        let credential_with_key = build_credential_with_key(
-            &credential_identity, &signer_public_key, credential_bytes.as_deref(),
-        )?;
+    Ok(GroupInfoParts {
+        ciphersuite: u16::from_be_bytes([header[2], header[3]]),
+        group_id: group_id.as_slice().to_vec(),
+        epoch: u64::from_be_bytes(epoch.try_into().expect("fixed(8)")),
+        context_extensions: context_extensions.as_slice().to_vec(),
+        extensions: extensions.as_slice().to_vec(),
+        signer: u32::from_be_bytes(signer.try_into().expect("fixed(4)")),
+        tbs,
+        signature: signature.as_slice().to_vec(),
+    })
+}
 
-        let provider = self.load_global().await?;
-        let mut builder = KeyPackage::builder();
+/// Type of the ratchet_tree extension (RFC 9420 §12.4.3.3).
+const RATCHET_TREE_EXTENSION_TYPE: u16 = 0x0002;
 
-        if let Some(lifetime_secs) = options.lifetime_seconds {
-            builder = builder.key_package_lifetime(Lifetime::new(lifetime_secs));
-        }
-        if options.last_resort {
-            builder = builder.mark_as_last_resort();
-        }
-        if let Some(ref caps) = options.capabilities {
-            builder = builder.leaf_node_capabilities(capabilities_to_native(caps)?);
-        }
-        if let Some(ref leaf_exts) = options.leaf_node_extensions {
-            let extensions = Extensions::from_vec(extensions_from_mls(leaf_exts))
-                .map_err(|e| format!("Failed to create leaf node extensions: {}", e))?;
-            builder = builder.leaf_node_extensions(extensions);
+/// Decode an encoded extension list: extension_type (u16) || extension_data<V>.
+fn parse_extension_list(mut input: &[u8]) -> Result<Vec<MlsExtension>, String> {
+    let mut extensions = Vec::new();
+    while !input.is_empty() {
+        if input.len() < 2 {
+            return Err("Malformed extension: truncated".to_string());
         }
-        if let Some(ref kp_exts) = options.key_package_extensions {
-            let extensions = Extensions::from_vec(extensions_from_mls(kp_exts))
-                .map_err(|e| format!("Failed to create key package extensions: {}", e))?;
-            builder = builder.key_package_extensions(extensions);
+        let (extension_type, rest) = input.split_at(2);
+        let (data, rest) = tls_codec::VLBytes::tls_deserialize_bytes(rest)
+            .map_err(|e| format!("Malformed extension: {}", e))?;
+        extensions.push(MlsExtension {
+            extension_type: u16::from_be_bytes([extension_type[0], extension_type[1]]),
+            data: data.as_slice().to_vec(),
+        });
+        input = rest;
+    }
+    Ok(extensions)
+}
+
+fn group_context_info(ctx: &GroupContext) -> Result<MlsGroupContextInfo, String> {
+    let ext_bytes = ctx
+        .extensions()
+        .tls_serialize_detached()
+        .map_err(|e| format!("Failed to serialize extensions: {}", e))?;
+    Ok(MlsGroupContextInfo {
+        group_id: ctx.group_id().as_slice().to_vec(),
+        epoch: ctx.epoch().as_u64(),
+        ciphersuite: native_to_ciphersuite(ctx.ciphersuite())?,
+        tree_hash: ctx.tree_hash().to_vec(),
+        confirmed_transcript_hash: ctx.confirmed_transcript_hash().to_vec(),
+        extensions: ext_bytes,
+    })
+}
+
+/// Classify a `process_message` failure for `decryption_failure_report`.
+fn decryption_failure_kind(err: &ProcessMessageError) -> DecryptionFailureKind {
+    match err {
+        ProcessMessageError::ValidationError(ValidationError::UnableToDecrypt(
+            MessageDecryptionError::SecretTreeError(e),
+        )) => match e {
+            SecretTreeError::SecretReuseError => DecryptionFailureKind::SecretConsumed,
+            SecretTreeError::TooDistantInThePast => DecryptionFailureKind::GenerationTooOld,
+            SecretTreeError::TooDistantInTheFuture => DecryptionFailureKind::GenerationTooFarAhead,
+            _ => DecryptionFailureKind::Other,
+        },
+        ProcessMessageError::ValidationError(ValidationError::NoPastEpochData) => DecryptionFailureKind::ExpiredEpoch,
+        ProcessMessageError::ValidationError(ValidationError::WrongEpoch) => DecryptionFailureKind::FutureEpoch,
+        ProcessMessageError::ValidationError(ValidationError::CannotDecryptOwnMessage) => {
+            DecryptionFailureKind::OwnMessage
         }
+        _ => DecryptionFailureKind::Other,
+    }
+}
 
-        let key_package_bundle = builder
-            .build(cs, &provider, &signer, credential_with_key)
-            .map_err(|e| format!("Failed to create key package: {}", e))?;
+/// Group metadata entry holding the AAD stamped on outgoing messages.
+const DEFAULT_AAD: &str = "default_aad";
+
+/// Group metadata entry holding the caller's delivery service stream
+/// position (see `set_sync_cursor`).
+const SYNC_CURSOR: &str = "sync_cursor";
+
+/// Set the AAD for the next message created by `group`: the per-call `aad`
+/// if given, otherwise the group's stored default (if any).
+fn apply_aad(
+    group: &mut MlsGroup,
+    storage: &SnapshotStorageProvider,
+    group_id: &[u8],
+    aad: Option<Vec<u8>>,
+) -> Result<(), String> {
+    let aad = match aad {
+        Some(aad) => Some(aad),
+        None => storage
+            .app_group(group_id, DEFAULT_AAD)
+            .map_err(|e| format!("Failed to read default AAD: {}", e))?,
+    };
+    if let Some(aad_bytes) = aad {
+        group.set_aad(aad_bytes);
+    }
+    Ok(())
+}
 
-        let kp_bytes = key_package_bundle
-            .key_package()
-            .tls_serialize_detached()
-            .map_err(|e| format!("Failed to serialize key package: {}", e))?;
+/// `group_info` as produced by a commit or, when `ensure` is set and the
+/// commit produced none, a freshly signed GroupInfo for the epoch the commit
+/// moved `group` into. Like the commit's own, it carries the ratchet tree
+/// only if the group's `use_ratchet_tree_extension` is set. Commit calls
+/// return a bare GroupInfo, i.e. without the MLSMessage header.
+fn ensure_group_info(
+    group: &MlsGroup,
+    provider: &SnapshotOpenMlsProvider,
+    signer: &SignatureKeyPair,
+    group_info: Option<Vec<u8>>,
+    ensure: bool,
+) -> Result<Option<Vec<u8>>, String> {
+    if group_info.is_some() || !ensure {
+        return Ok(group_info);
+    }
+    let with_ratchet_tree = MlsGroupConfig::from_group(group, provider.storage())?.use_ratchet_tree_extension;
+    let message = group
+        .export_group_info(provider.crypto(), signer, with_ratchet_tree)
+        .map_err(|e| format!("Failed to export group info: {}", e))?;
+    let bytes = message.tls_serialize_detached().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+    // Drop the MLSMessage version and wire format.
+    Ok(Some(bytes[4..].to_vec()))
+}
 
-        self.commit(provider, None).await?;
+/// The wire format `group` sends handshake messages in.
+fn outgoing_wire_format(group: &MlsGroup) -> MlsWireFormat {
+    match group.configuration().wire_format_policy().outgoing() {
+        OutgoingWireFormatPolicy::AlwaysPlaintext => MlsWireFormat::Plaintext,
+        OutgoingWireFormatPolicy::AlwaysCiphertext => MlsWireFormat::Ciphertext,
+    }
+}
 
-        Ok(KeyPackageResult {
-            key_package_bytes: kp_bytes,
-        })
+/// Leaf node parameters for a new or updated own leaf. Unset values keep
+/// the defaults.
+fn leaf_node_parameters(
+    capabilities: Option<MlsCapabilities>,
+    extensions: Option<Vec<MlsExtension>>,
+) -> Result<LeafNodeParameters, String> {
+    let mut builder = LeafNodeParameters::builder();
+    if let Some(ref caps) = capabilities {
+        builder = builder.with_capabilities(capabilities_to_native(caps)?);
     }
+    if let Some(ref exts) = extensions {
+        let extensions = Extensions::from_vec(extensions_from_mls(exts))
+            .map_err(|e| format!("Failed to create leaf node extensions: {}", e))?;
+        builder = builder.with_extensions(extensions);
+    }
+    Ok(builder.build())
+}
 
-    // ═══════════════════════════════════════════════════════════
-    // GROUP CREATION
-    // ═══════════════════════════════════════════════════════════
+/// Swap `config` into `group` for the operation at hand without persisting
+/// it: the join config is written to a scratch store, so the stored
+/// configuration is never touched and a failed operation leaves nothing
+/// to undo.
+fn set_transient_configuration(group: &mut MlsGroup, config: &MlsGroupJoinConfig) -> Result<(), String> {
+    let scratch = SnapshotStorageProvider::from_entries(Vec::new());
+    group.set_configuration(&scratch, config)
+        .map_err(|e| format!("Failed to set configuration: {}", e))
+}
 
-    pub async fn create_group(
-        &self,
-        config: MlsGroupConfig,
-        signer_bytes: Vec<u8>,
-        credential_identity: Vec<u8>,
-        signer_public_key: Vec<u8>,
-        group_id: Option<Vec<u8>>,
-        credential_bytes: Option<Vec<u8>>,
+/// Switch `group` to send its next handshake message as `wire_format`.
+///
+/// Returns the configuration to put back with `restore_wire_format` once the
+/// message is created, or `None` if nothing changed. Sending the format the
+/// policy does not default to requires a mixed policy, so members accept it.
+/// The override only lives in memory (see `set_transient_configuration`).
+fn override_wire_format(
+    group: &mut MlsGroup,
+    wire_format: Option<MlsWireFormat>,
+) -> Result<Option<MlsGroupJoinConfig>, String> {
+    let outgoing = match wire_format {
+        None => return Ok(None),
+        Some(MlsWireFormat::Plaintext) => OutgoingWireFormatPolicy::AlwaysPlaintext,
+        Some(MlsWireFormat::Ciphertext) => OutgoingWireFormatPolicy::AlwaysCiphertext,
+    };
+    let original = group.configuration().clone();
+    let policy = original.wire_format_policy();
+    if policy.outgoing() == outgoing {
+        return Ok(None);
+    }
+    if policy.incoming() != IncomingWireFormatPolicy::Mixed {
+        return Err("Group wire format policy does not permit the requested wire format".to_string());
+    }
+
+    // `MlsGroupJoinConfig` cannot be rebuilt from its accessors (not all
+    // settings are exposed), so swap the policy in its serialized form.
+    let mut value = serde_json::to_value(&original)
+        .map_err(|e| format!("Failed to serialize group configuration: {}", e))?;
+    value["wire_format_policy"] = serde_json::to_value(WireFormatPolicy::new(outgoing, policy.incoming()))
+        .map_err(|e| format!("Failed to serialize wire format policy: {}", e))?;
+    let overridden: MlsGroupJoinConfig = serde_json::from_value(value)
+        .map_err(|e| format!("Failed to deserialize group configuration: {}", e))?;
+    set_transient_configuration(group, &overridden)?;
+    Ok(Some(original))
+}
+
+/// Undo `override_wire_format`.
+fn restore_wire_format(
+    group: &mut MlsGroup,
+    original: Option<MlsGroupJoinConfig>,
+) -> Result<(), String> {
+    match original {
+        Some(config) => set_transient_configuration(group, &config),
+        None => Ok(()),
+    }
+}
+
+/// Whether `message` is decrypted with the handshake ratchet: an encrypted
+/// commit or proposal.
+fn uses_handshake_ratchet(message: &ProtocolMessage) -> bool {
+    matches!(message, ProtocolMessage::PrivateMessage(_)) && !matches!(message.content_type(), ContentType::Application)
+}
+
+/// Process `message` with the sender ratchet tolerance configured for its
+/// content type: while the group has a handshake tolerance (see
+/// `MlsGroupConfig::handshake_sender_ratchet_max_out_of_order`), it is
+/// swapped into the in-memory join config for encrypted handshake messages.
+/// The stored configuration keeps the application tolerance throughout.
+fn process_protocol_message(
+    group: &mut MlsGroup,
+    provider: &SnapshotOpenMlsProvider,
+    message: ProtocolMessage,
+) -> Result<Result<ProcessedMessage, ProcessMessageError>, String> {
+    let tolerance = if uses_handshake_ratchet(&message) {
+        handshake_out_of_order(provider.storage(), group.group_id().as_slice())?
+    } else {
+        None
+    };
+    let Some(tolerance) = tolerance else {
+        return Ok(group.process_message(provider, message));
+    };
+    let original = group.configuration().clone();
+    let ratchet = SenderRatchetConfiguration::new(tolerance, original.sender_ratchet_configuration().maximum_forward_distance());
+    // As in `override_wire_format`: not every setting has an accessor, so
+    // swap the ratchet configuration in the serialized join config.
+    let mut value = serde_json::to_value(&original)
+        .map_err(|e| format!("Failed to serialize group configuration: {}", e))?;
+    value["sender_ratchet_configuration"] = serde_json::to_value(ratchet)
+        .map_err(|e| format!("Failed to serialize sender ratchet configuration: {}", e))?;
+    let overridden: MlsGroupJoinConfig = serde_json::from_value(value)
+        .map_err(|e| format!("Failed to deserialize group configuration: {}", e))?;
+    set_transient_configuration(group, &overridden)?;
+    let result = group.process_message(provider, message);
+    set_transient_configuration(group, &original)?;
+    Ok(result)
+}
+
+/// Number of past epochs whose message secrets `group` is configured to
+/// retain.
+fn configured_max_past_epochs(group: &MlsGroup) -> Result<u64, String> {
+    // `max_past_epochs` has no accessor on `MlsGroupJoinConfig`.
+    Ok(serde_json::to_value(group.configuration())
+        .map_err(|e| format!("Failed to serialize group configuration: {}", e))?
+        .get("max_past_epochs")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(0))
+}
+
+/// Group metadata entry holding per-sender application message watermarks.
+/// Its presence turns on deduplication in `process_message`.
+const MESSAGE_WATERMARKS: &str = "message_watermarks";
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoredWatermark {
+    sender_index: u32,
+    epoch: u64,
+    messages: u64,
+}
+
+fn message_watermarks(
+    storage: &SnapshotStorageProvider,
+    group_id: &[u8],
+) -> Result<Option<Vec<StoredWatermark>>, String> {
+    storage
+        .app_group(group_id, MESSAGE_WATERMARKS)
+        .map_err(|e| format!("Failed to read message watermarks: {}", e))
+}
+
+/// Advance the watermark of `sender_index` for an application message
+/// accepted in `epoch`. No-op when deduplication is off for the group.
+fn advance_watermark(
+    storage: &mut SnapshotStorageProvider,
+    group_id: &[u8],
+    sender_index: u32,
+    epoch: u64,
+) -> Result<(), String> {
+    let Some(mut entries) = message_watermarks(storage, group_id)? else {
+        return Ok(());
+    };
+    match entries.iter_mut().find(|entry| entry.sender_index == sender_index) {
+        Some(entry) if entry.epoch == epoch => entry.messages += 1,
+        Some(entry) if entry.epoch < epoch => {
+            entry.epoch = epoch;
+            entry.messages = 1;
+        }
+        Some(_) => {}
+        None => entries.push(StoredWatermark { sender_index, epoch, messages: 1 }),
+    }
+    storage
+        .write_app_group(group_id, MESSAGE_WATERMARKS, &entries)
+        .map_err(|e| format!("Failed to write message watermarks: {}", e))
+}
+
+/// With deduplication on, a message whose key was already consumed is a
+/// redelivery of one we processed: report it instead of failing.
+fn is_duplicate_delivery(
+    storage: &SnapshotStorageProvider,
+    group_id: &[u8],
+    err: &ProcessMessageError,
+) -> Result<bool, String> {
+    Ok(matches!(decryption_failure_kind(err), DecryptionFailureKind::SecretConsumed)
+        && message_watermarks(storage, group_id)?.is_some())
+}
+
+/// Group metadata entry holding the local block list (see `block_member`).
+const BLOCKED_MEMBERS: &str = "blocked_members";
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoredBlockedMember {
+    credential: Vec<u8>,
+    suppress_messages: bool,
+}
+
+fn blocked_members(storage: &SnapshotStorageProvider, group_id: &[u8]) -> Result<Vec<StoredBlockedMember>, String> {
+    Ok(storage
+        .app_group(group_id, BLOCKED_MEMBERS)
+        .map_err(|e| format!("Failed to read blocked members: {}", e))?
+        .unwrap_or_default())
+}
+
+/// The block list entry matching the credential `sender` currently holds
+/// in `group`. Only members can be blocked; other senders never match.
+fn sender_block(
+    group: &MlsGroup,
+    storage: &SnapshotStorageProvider,
+    sender: &Sender,
+) -> Result<Option<StoredBlockedMember>, String> {
+    let Sender::Member(index) = sender else { return Ok(None) };
+    let Some(member) = group.member_at(*index) else { return Ok(None) };
+    let credential = member
+        .credential
+        .tls_serialize_detached()
+        .map_err(|e| format!("Failed to serialize credential: {}", e))?;
+    Ok(blocked_members(storage, group.group_id().as_slice())?
+        .into_iter()
+        .find(|entry| crate::constant_time_eq(&entry.credential, &credential)))
+}
+
+/// Converts a PSK id into its `MlsPskId` form.
+fn psk_id_info(psk_id: &PreSharedKeyId) -> MlsPskId {
+    let (kind, id, epoch) = match psk_id.psk() {
+        Psk::External(external) => (MlsPskKind::External, external.psk_id().to_vec(), None),
+        Psk::Resumption(resumption) => (
+            MlsPskKind::Resumption,
+            resumption.psk_group_id().as_slice().to_vec(),
+            Some(resumption.psk_epoch().as_u64()),
+        ),
+    };
+    MlsPskId { kind, id, epoch, nonce: psk_id.psk_nonce().to_vec() }
+}
+
+/// Refs of the proposals a commit references that are not pending in
+/// `group`, as TLS-serialized `ProposalRef`s.
+///
+/// Only plaintext commits can be inspected; OpenMLS does not expose the
+/// content of a private message it failed to process, so those yield `None`.
+fn missing_proposal_refs(group: &MlsGroup, message_bytes: &[u8]) -> Result<Option<Vec<Vec<u8>>>, String> {
+    fn vl(input: &[u8]) -> Result<&[u8], String> {
+        tls_codec::VLBytes::tls_deserialize_bytes(input)
+            .map(|(_, rest)| rest)
+            .map_err(|e| format!("Malformed commit: {}", e))
+    }
+    fn fixed(input: &[u8], len: usize) -> Result<(&[u8], &[u8]), String> {
+        if input.len() < len {
+            return Err("Malformed commit: truncated".to_string());
+        }
+        Ok(input.split_at(len))
+    }
+
+    // MLSMessage: version (u16) || wire_format (u16), 1 = PublicMessage
+    let (header, rest) = fixed(message_bytes, 4)?;
+    if u16::from_be_bytes([header[2], header[3]]) != 1 {
+        return Ok(None);
+    }
+    // FramedContent: group_id<V>, epoch (u64), sender, authenticated_data<V>,
+    // content_type (u8), then the Commit's proposals<V>
+    let rest = vl(rest)?;
+    let (_, rest) = fixed(rest, 8)?;
+    let (sender_type, rest) = fixed(rest, 1)?;
+    let rest = match sender_type[0] {
+        1 | 2 => fixed(rest, 4)?.1, // member / external: u32 index
+        _ => rest,
+    };
+    let rest = vl(rest)?;
+    let (_, rest) = fixed(rest, 1)?;
+    let (proposals, _) = Vec::<ProposalOrRefIn>::tls_deserialize_bytes(rest)
+        .map_err(|e| format!("Malformed commit: {}", e))?;
+
+    let pending: Vec<ProposalRef> = group.pending_proposals().map(|qp| qp.proposal_reference()).collect();
+    let mut missing = Vec::new();
+    for proposal in proposals {
+        if let ProposalOrRefIn::Reference(proposal_ref) = proposal {
+            if !pending.contains(&*proposal_ref) {
+                missing.push(
+                    proposal_ref
+                        .tls_serialize_detached()
+                        .map_err(|e| format!("Failed to serialize proposal ref: {}", e))?,
+                );
+            }
+        }
+    }
+    Ok(Some(missing))
+}
+
+/// Global metadata entry holding the operations that span several storage
+/// transactions and have not finished yet (see `pending_recovery`).
+const OPERATION_JOURNAL: &str = "operation_journal";
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct JournalEntry {
+    id: u64,
+    operation: JournalOperation,
+    group_id: Vec<u8>,
+    /// Transactions of the operation already committed.
+    phase: u32,
+    started_at: u64,
+    /// Why the last recovery attempt failed.
+    error: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum JournalOperation {
+    /// The group's MLS state is deleted; its remaining rows are not.
+    DeleteGroup,
+}
+
+fn read_journal(storage: &SnapshotStorageProvider) -> Result<Vec<JournalEntry>, String> {
+    Ok(storage
+        .app_global(OPERATION_JOURNAL)
+        .map_err(|e| format!("Failed to read operation journal: {}", e))?
+        .unwrap_or_default())
+}
+
+fn write_journal(storage: &mut SnapshotStorageProvider, entries: &[JournalEntry]) -> Result<(), String> {
+    if entries.is_empty() {
+        storage.delete_app_global(OPERATION_JOURNAL)
+    } else {
+        storage.write_app_global(OPERATION_JOURNAL, &entries)
+    }
+    .map_err(|e| format!("Failed to write operation journal: {}", e))
+}
+
+/// Add an entry to the journal in `storage`, so it is committed together
+/// with the operation's first transaction. Returns the entry's id.
+fn journal_begin(
+    storage: &mut SnapshotStorageProvider,
+    operation: JournalOperation,
+    group_id: &[u8],
+) -> Result<u64, String> {
+    let mut entries = read_journal(storage)?;
+    let id = entries.iter().map(|entry| entry.id + 1).max().unwrap_or(1);
+    entries.push(JournalEntry {
+        id,
+        operation,
+        group_id: group_id.to_vec(),
+        phase: 1,
+        started_at: unix_now()?,
+        error: None,
+    });
+    write_journal(storage, &entries)?;
+    Ok(id)
+}
+
+/// Global metadata entry holding failed joins kept for
+/// `retry_pending_joins`.
+const PENDING_JOINS: &str = "pending_joins";
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoredPendingJoin {
+    id: u64,
+    kind: StoredPendingJoinKind,
+    group_id: Option<Vec<u8>>,
+    /// The TLS-serialized Welcome or GroupInfo message.
+    payload: Vec<u8>,
+    ratchet_tree: Option<Vec<u8>>,
+    error: String,
+    failed_at: u64,
+    attempts: u32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+enum StoredPendingJoinKind {
+    Welcome,
+    ExternalCommit {
+        credential_identity: Vec<u8>,
+        signer_public_key: Vec<u8>,
+        credential_bytes: Option<Vec<u8>>,
+    },
+}
+
+fn read_pending_joins(storage: &SnapshotStorageProvider) -> Result<Vec<StoredPendingJoin>, String> {
+    Ok(storage
+        .app_global(PENDING_JOINS)
+        .map_err(|e| format!("Failed to read pending joins: {}", e))?
+        .unwrap_or_default())
+}
+
+fn write_pending_joins(storage: &mut SnapshotStorageProvider, entries: &[StoredPendingJoin]) -> Result<(), String> {
+    if entries.is_empty() {
+        storage.delete_app_global(PENDING_JOINS)
+    } else {
+        storage.write_app_global(PENDING_JOINS, &entries)
+    }
+    .map_err(|e| format!("Failed to write pending joins: {}", e))
+}
+
+/// Global metadata entry holding Welcomes kept by `stage_welcome`.
+const STAGED_WELCOMES: &str = "staged_welcomes";
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoredStagedWelcome {
+    id: u64,
+    group_id: Vec<u8>,
+    /// The TLS-serialized Welcome message.
+    welcome: Vec<u8>,
+    ratchet_tree: Option<Vec<u8>>,
+    staged_at: u64,
+}
+
+fn read_staged_welcomes(storage: &SnapshotStorageProvider) -> Result<Vec<StoredStagedWelcome>, String> {
+    Ok(storage
+        .app_global(STAGED_WELCOMES)
+        .map_err(|e| format!("Failed to read staged welcomes: {}", e))?
+        .unwrap_or_default())
+}
+
+/// Remove the staged Welcome `staged_id` from `storage`, returning it.
+fn take_staged_welcome(
+    storage: &mut SnapshotStorageProvider,
+    staged_id: u64,
+) -> Result<Option<StoredStagedWelcome>, String> {
+    let mut entries = read_staged_welcomes(storage)?;
+    let Some(index) = entries.iter().position(|entry| entry.id == staged_id) else {
+        return Ok(None);
+    };
+    let entry = entries.remove(index);
+    write_staged_welcomes(storage, &entries)?;
+    Ok(Some(entry))
+}
+
+fn write_staged_welcomes(storage: &mut SnapshotStorageProvider, entries: &[StoredStagedWelcome]) -> Result<(), String> {
+    if entries.is_empty() {
+        storage.delete_app_global(STAGED_WELCOMES)
+    } else {
+        storage.write_app_global(STAGED_WELCOMES, &entries)
+    }
+    .map_err(|e| format!("Failed to write staged welcomes: {}", e))
+}
+
+/// Group id of the Welcome in `welcome_bytes`, if it can be decrypted with a
+/// key package in `provider`.
+fn welcome_group_id(provider: &SnapshotOpenMlsProvider, config: &MlsGroupConfig, welcome_bytes: &[u8]) -> Option<Vec<u8>> {
+    let welcome = match MlsMessageIn::tls_deserialize_exact_bytes(welcome_bytes).ok()?.extract() {
+        MlsMessageBodyIn::Welcome(welcome) => welcome,
+        _ => return None,
+    };
+    let processed = ProcessedWelcome::new_from_welcome(provider, &config.to_join_config(), welcome).ok()?;
+    Some(processed.unverified_group_info().group_id().as_slice().to_vec())
+}
+
+/// Group id of the GroupInfo message in `group_info_bytes`.
+fn group_info_group_id(group_info_bytes: &[u8]) -> Option<Vec<u8>> {
+    match MlsMessageIn::tls_deserialize_exact_bytes(group_info_bytes).ok()?.extract() {
+        MlsMessageBodyIn::GroupInfo(group_info) => Some(group_info.group_id().as_slice().to_vec()),
+        _ => None,
+    }
+}
+
+/// Global metadata entry holding the last `self_heal` attempt per group.
+const SELF_HEAL_ATTEMPTS: &str = "self_heal_attempts";
+
+/// Minimum time between two `self_heal` attempts for the same group.
+const SELF_HEAL_MIN_INTERVAL_SECS: u64 = 3600;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SelfHealAttempt {
+    group_id: Vec<u8>,
+    attempted_at: u64,
+}
+
+/// Group metadata entry holding the commits `process_message_staged` staged
+/// but did not merge.
+const HELD_STAGED_COMMITS: &str = "held_staged_commits";
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct HeldStagedCommit {
+    /// SHA-256 of the commit message.
+    reference: Vec<u8>,
+    /// Epoch the commit was staged in; it cannot be merged in any other.
+    epoch: u64,
+    sender_index: Option<u32>,
+    staged_commit: StagedCommit,
+}
+
+/// Held staged commits of the group that can still be merged, i.e. were
+/// staged in the current epoch.
+fn read_held_staged_commits(storage: &SnapshotStorageProvider, group: &MlsGroup) -> Result<Vec<HeldStagedCommit>, String> {
+    let held: Vec<HeldStagedCommit> = storage
+        .app_group(group.group_id().as_slice(), HELD_STAGED_COMMITS)
+        .map_err(|e| format!("Failed to read held staged commits: {}", e))?
+        .unwrap_or_default();
+    let epoch = group.epoch().as_u64();
+    Ok(held.into_iter().filter(|entry| entry.epoch == epoch).collect())
+}
+
+fn write_held_staged_commits(
+    storage: &mut SnapshotStorageProvider,
+    group_id: &[u8],
+    held: &[HeldStagedCommit],
+) -> Result<(), String> {
+    if held.is_empty() {
+        storage.delete_app_group(group_id, HELD_STAGED_COMMITS)
+    } else {
+        storage.write_app_group(group_id, HELD_STAGED_COMMITS, &held)
+    }
+    .map_err(|e| format!("Failed to write held staged commits: {}", e))
+}
+
+/// Plaintext of a join receipt, a media type string that stands in for a
+/// custom content type.
+const JOIN_RECEIPT_CONTENT: &[u8] = b"application/vnd.openmls-dart.joined";
+
+/// Domain separation prefix of member handles.
+const MEMBER_HANDLE_LABEL: &[u8] = b"openmls_dart member handle";
+
+/// Opaque handle of a member: a hash of its TLS-serialized credential and
+/// signature key. Unlike the leaf index it survives other members leaving,
+/// and only changes when the member changes its credential or key.
+fn member_handle(
+    provider: &SnapshotOpenMlsProvider,
+    credential: &[u8],
+    signature_key: &[u8],
+) -> Result<Vec<u8>, String> {
+    let mut input = MEMBER_HANDLE_LABEL.to_vec();
+    input.extend_from_slice(credential);
+    input.extend_from_slice(signature_key);
+    provider
+        .crypto()
+        .hash(HashType::Sha2_256, &input)
+        .map_err(|e| format!("Failed to compute member handle: {:?}", e))
+}
+
+fn member_info(provider: &SnapshotOpenMlsProvider, member: &Member) -> Result<MlsMemberInfo, String> {
+    let cred_bytes = member.credential
+        .tls_serialize_detached()
+        .map_err(|e| format!("Failed to serialize member credential: {}", e))?;
+    let member_handle = member_handle(provider, &cred_bytes, &member.signature_key)?;
+    Ok(MlsMemberInfo {
+        index: member.index.u32(),
+        credential: cred_bytes,
+        signature_key: member.signature_key.clone(),
+        member_handle,
+    })
+}
+
+/// Member handle of `sender`, resolved from its leaf in the group's
+/// current epoch; `None` for non-member senders.
+///
+/// Messages only carry the sender's credential, not its signature key, so
+/// the handle is that of whoever holds the sender's leaf now. With the
+/// credential the message was signed under (`message_credential`), a leaf
+/// that has since passed to a different credential, e.g. for a message
+/// from a past epoch, resolves to `None` instead of the wrong member. A
+/// sender that rotated its signature key since reports its new handle.
+fn sender_handle(
+    group: &MlsGroup,
+    provider: &SnapshotOpenMlsProvider,
+    sender: &Sender,
+    message_credential: Option<&Credential>,
+) -> Result<Option<Vec<u8>>, String> {
+    let Sender::Member(idx) = sender else {
+        return Ok(None);
+    };
+    let Some(member) = group.member_at(*idx) else {
+        return Ok(None);
+    };
+    if message_credential.is_some_and(|credential| *credential != member.credential) {
+        return Ok(None);
+    }
+    Ok(Some(member_info(provider, &member)?.member_handle))
+}
+
+/// Group metadata entry holding the commit audit log (see `audit_log`).
+const AUDIT_LOG: &str = "audit_log";
+
+/// Group metadata entry holding the epoch of our last commit with an update
+/// path.
+const OWN_UPDATE_EPOCH: &str = "own_update_epoch";
+
+/// Epochs without an own update path after which `security_posture` warns,
+/// and after which it reports the group as critical.
+const STALE_OWN_UPDATE_WARNING_EPOCHS: u64 = 10;
+const STALE_OWN_UPDATE_CRITICAL_EPOCHS: u64 = 50;
+
+fn severity_rank(severity: &MlsSecuritySeverity) -> u8 {
+    match severity {
+        MlsSecuritySeverity::Ok => 0,
+        MlsSecuritySeverity::Info => 1,
+        MlsSecuritySeverity::Warning => 2,
+        MlsSecuritySeverity::Critical => 3,
+    }
+}
+
+/// Whether our leaf still uses the keys of a last-resort key package.
+fn last_resort_key_package_in_use(group: &MlsGroup, provider: &SnapshotOpenMlsProvider) -> Result<bool, String> {
+    let Some(own_leaf) = group.own_leaf_node() else {
+        return Ok(false);
+    };
+    let entries: Vec<KeyPackageMeta> = provider.storage()
+        .app_global(KEY_PACKAGE_META)
+        .map_err(|e| format!("Failed to read key package metadata: {}", e))?
+        .unwrap_or_default();
+    for entry in entries {
+        let hash_ref = openmls::ciphersuite::hash_ref::KeyPackageRef::tls_deserialize_exact_bytes(&entry.key_package_ref)
+            .map_err(|e| format!("Failed to deserialize key package ref: {}", e))?;
+        let bundle: Option<KeyPackageBundle> = provider.storage().key_package(&hash_ref)
+            .map_err(|e| format!("Failed to read key package: {}", e))?;
+        if let Some(bundle) = bundle {
+            let key_package = bundle.key_package();
+            if key_package.last_resort() && key_package.leaf_node().encryption_key() == own_leaf.encryption_key() {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Group metadata entry holding the search index key of every window size
+/// `search_index_key` was called with.
+const SEARCH_INDEX_KEYS: &str = "search_index_keys";
+const SEARCH_INDEX_EXPORTER_LABEL: &str = "search index key";
+const SEARCH_INDEX_KEY_LENGTH: usize = 32;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoredSearchIndexKey {
+    epoch_window: u64,
+    /// Window `key` belongs to; `None` until the group reaches the start of
+    /// a window.
+    window: Option<u64>,
+    key: Vec<u8>,
+}
+
+/// Search index key of the window starting at the group's current epoch.
+fn derive_search_index_key(
+    group: &MlsGroup,
+    provider: &SnapshotOpenMlsProvider,
+    epoch_window: u64,
+) -> Result<Vec<u8>, String> {
+    let mut context = epoch_window.to_be_bytes().to_vec();
+    context.extend_from_slice(&(group.epoch().as_u64() / epoch_window).to_be_bytes());
+    group
+        .export_secret(provider.crypto(), SEARCH_INDEX_EXPORTER_LABEL, &context, SEARCH_INDEX_KEY_LENGTH)
+        .map_err(|e| format!("Failed to export secret: {}", e))
+}
+
+/// Derive the search index keys of windows starting at the group's current
+/// epoch. Called after every merged commit.
+fn cache_search_index_keys(group: &MlsGroup, provider: &mut SnapshotOpenMlsProvider) -> Result<(), String> {
+    let group_id = group.group_id().as_slice();
+    let Some(mut entries): Option<Vec<StoredSearchIndexKey>> = provider.storage()
+        .app_group(group_id, SEARCH_INDEX_KEYS)
+        .map_err(|e| format!("Failed to read search index keys: {}", e))?
+    else {
+        return Ok(());
+    };
+    let epoch = group.epoch().as_u64();
+    let mut changed = false;
+    for entry in entries.iter_mut().filter(|entry| epoch % entry.epoch_window == 0) {
+        entry.window = Some(epoch / entry.epoch_window);
+        entry.key = derive_search_index_key(group, provider, entry.epoch_window)?;
+        changed = true;
+    }
+    if !changed {
+        return Ok(());
+    }
+    provider.storage_mut()
+        .write_app_group(group_id, SEARCH_INDEX_KEYS, &entries)
+        .map_err(|e| format!("Failed to write search index keys: {}", e))
+}
+
+/// Group metadata entry holding the epoch each current member was added in
+/// (see `member_join_epochs`).
+const MEMBER_JOIN_EPOCHS: &str = "member_join_epochs";
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoredMemberJoin {
+    leaf_index: u32,
+    epoch: u64,
+    exact: bool,
+}
+
+/// A member removed from the group (see `former_members`).
+pub struct FormerMember {
+    /// Leaf index the member had. It may since have been reused.
+    pub leaf_index: u32,
+    /// TLS-serialized Credential.
+    pub credential: Vec<u8>,
+    pub signature_key: Vec<u8>,
+    /// The member's handle while it was in the group (see `MlsMemberInfo`).
+    pub member_handle: Vec<u8>,
+    /// First epoch without the member.
+    pub removed_epoch: u64,
+    /// Unix seconds at which this device merged the removal.
+    pub removed_at: u64,
+}
+
+/// Update the join epochs after `group` entered a new epoch. `removed` are
+/// the leaves the merged commit removed, so a leaf refilled by the same
+/// commit counts as a new member. With `joined`, this device just joined
+/// and the members already present only get an upper bound.
+fn record_member_joins(
+    group: &MlsGroup,
+    provider: &mut SnapshotOpenMlsProvider,
+    removed: &[u32],
+    joined: bool,
+) -> Result<(), String> {
+    let group_id = group.group_id().as_slice();
+    let stored: Option<Vec<StoredMemberJoin>> = if joined {
+        None
+    } else {
+        provider.storage()
+            .app_group(group_id, MEMBER_JOIN_EPOCHS)
+            .map_err(|e| format!("Failed to read member join epochs: {}", e))?
+    };
+    let epoch = group.epoch().as_u64();
+    // Everyone present at epoch 0 was there from the start; otherwise,
+    // without a log, we cannot tell when pre-existing members joined.
+    let exact = stored.is_some() || epoch == 0;
+    let mut entries = stored.unwrap_or_default();
+    entries.retain(|entry| {
+        !removed.contains(&entry.leaf_index) && group.member_at(LeafNodeIndex::new(entry.leaf_index)).is_some()
+    });
+    for member in group.members() {
+        let leaf_index = member.index.u32();
+        if !entries.iter().any(|entry| entry.leaf_index == leaf_index) {
+            let own = joined && member.index == group.own_leaf_index();
+            entries.push(StoredMemberJoin { leaf_index, epoch, exact: exact || own });
+        }
+    }
+    provider.storage_mut()
+        .write_app_group(group_id, MEMBER_JOIN_EPOCHS, &entries)
+        .map_err(|e| format!("Failed to write member join epochs: {}", e))
+}
+
+/// Group metadata entry holding tombstones of removed members (see
+/// `former_members`).
+const FORMER_MEMBERS: &str = "former_members";
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct StoredFormerMember {
+    leaf_index: u32,
+    credential: Vec<u8>,
+    signature_key: Vec<u8>,
+    member_handle: Vec<u8>,
+    removed_epoch: u64,
+    removed_at: u64,
+}
+
+/// Members at the `removed` leaves, read before the commit removing them is
+/// merged and their leaves are blanked.
+fn departing_members(
+    group: &MlsGroup,
+    provider: &SnapshotOpenMlsProvider,
+    removed: &[u32],
+) -> Result<Vec<MlsMemberInfo>, String> {
+    let mut departing = Vec::new();
+    for &leaf_index in removed {
+        if let Some(member) = group.member_at(LeafNodeIndex::new(leaf_index)) {
+            departing.push(member_info(provider, &member)?);
+        }
+    }
+    Ok(departing)
+}
+
+/// Store tombstones for `departing`, removed by the commit just merged into
+/// `group`.
+fn record_former_members(
+    group: &MlsGroup,
+    provider: &mut SnapshotOpenMlsProvider,
+    departing: Vec<MlsMemberInfo>,
+) -> Result<(), String> {
+    if departing.is_empty() {
+        return Ok(());
+    }
+    let group_id = group.group_id().as_slice();
+    let mut entries: Vec<StoredFormerMember> = provider.storage()
+        .app_group(group_id, FORMER_MEMBERS)
+        .map_err(|e| format!("Failed to read former members: {}", e))?
+        .unwrap_or_default();
+    let removed_at = unix_now()?;
+    for member in departing {
+        entries.push(StoredFormerMember {
+            leaf_index: member.index,
+            credential: member.credential,
+            signature_key: member.signature_key,
+            member_handle: member.member_handle,
+            removed_epoch: group.epoch().as_u64(),
+            removed_at,
+        });
+    }
+    provider.storage_mut()
+        .write_app_group(group_id, FORMER_MEMBERS, &entries)
+        .map_err(|e| format!("Failed to write former members: {}", e))
+}
+
+/// Group metadata entry holding the group's activity counters (see
+/// `group_activity`).
+const GROUP_ACTIVITY: &str = "group_activity";
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct StoredGroupActivity {
+    messages_sent: u64,
+    messages_received: u64,
+    commits_merged: u64,
+    last_activity: Option<u64>,
+}
+
+enum GroupActivityKind {
+    Sent,
+    Received,
+    Commit,
+}
+
+/// Count one event in the group's activity counters. Written through the
+/// same provider as the operation, so the counters never drift from it.
+fn record_activity(
+    storage: &mut SnapshotStorageProvider,
+    group_id: &[u8],
+    kind: GroupActivityKind,
+) -> Result<(), String> {
+    let mut activity: StoredGroupActivity = storage
+        .app_group(group_id, GROUP_ACTIVITY)
+        .map_err(|e| format!("Failed to read group activity: {}", e))?
+        .unwrap_or_default();
+    match kind {
+        GroupActivityKind::Sent => activity.messages_sent += 1,
+        GroupActivityKind::Received => activity.messages_received += 1,
+        GroupActivityKind::Commit => activity.commits_merged += 1,
+    }
+    activity.last_activity = Some(unix_now()?);
+    storage
+        .write_app_group(group_id, GROUP_ACTIVITY, &activity)
+        .map_err(|e| format!("Failed to write group activity: {}", e))
+}
+
+/// What the bookkeeping after a merge needs from the commit, captured
+/// before the merge (see `MlsEngine::before_merge`).
+struct PendingMerge {
+    proposals: ProposalSummary,
+    removed: Vec<u32>,
+    departing: Vec<MlsMemberInfo>,
+    compressed_before: bool,
+    /// This device joined with the commit (an own external commit).
+    joined: bool,
+    sender: Option<u32>,
+    own: bool,
+}
+
+fn removed_leaves(staged_commit: &StagedCommit) -> Vec<u32> {
+    staged_commit.remove_proposals().map(|r| r.remove_proposal().removed().u32()).collect()
+}
+
+/// PSKs a staged commit injects into the key schedule.
+fn commit_psks(staged_commit: &StagedCommit) -> Result<Vec<MlsPskId>, String> {
+    staged_commit
+        .psk_proposals()
+        .map(|queued| {
+            // A PreSharedKey proposal encodes as exactly its PreSharedKeyID.
+            let bytes = queued.psk_proposal()
+                .tls_serialize_detached()
+                .map_err(|e| format!("Failed to serialize PSK proposal: {}", e))?;
+            let psk_id = PreSharedKeyId::tls_deserialize_exact_bytes(&bytes)
+                .map_err(|e| format!("Failed to deserialize PSK id: {}", e))?;
+            Ok(psk_id_info(&psk_id))
+        })
+        .collect()
+}
+
+/// Describe the joiner of an external commit; None for other commits.
+fn external_join_info(
+    group: &MlsGroup,
+    provider: &SnapshotOpenMlsProvider,
+    sender: &Sender,
+    staged_commit: &StagedCommit,
+) -> Result<Option<MlsExternalJoinInfo>, String> {
+    if !matches!(sender, Sender::NewMemberCommit) {
+        return Ok(None);
+    }
+    let Some(leaf) = staged_commit.update_path_leaf_node() else {
+        return Err("Malformed external commit: no update path".to_string());
+    };
+    let kem_output = match staged_commit.queued_proposals().find_map(|queued| match queued.proposal() {
+        Proposal::ExternalInit(external_init) => Some(external_init),
+        _ => None,
+    }) {
+        Some(external_init) => {
+            // An ExternalInit proposal encodes as exactly its kem_output<V>.
+            let bytes = external_init
+                .tls_serialize_detached()
+                .map_err(|e| format!("Failed to serialize ExternalInit proposal: {}", e))?;
+            tls_codec::VLBytes::tls_deserialize_exact_bytes(&bytes)
+                .map_err(|e| format!("Failed to deserialize kem output: {}", e))?
+                .as_slice()
+                .to_vec()
+        }
+        None => return Err("Malformed external commit: no ExternalInit proposal".to_string()),
+    };
+    let credential = leaf
+        .credential()
+        .tls_serialize_detached()
+        .map_err(|e| format!("Failed to serialize credential: {}", e))?;
+    let member_handle = member_handle(provider, &credential, leaf.signature_key().as_slice())?;
+    // The only Remove an external commit may carry is of the joiner's
+    // previous leaf (RFC 9420 §12.4.3.2).
+    let replaced = staged_commit.remove_proposals().next().map(|r| r.remove_proposal().removed());
+    let replaced_handle = match replaced.and_then(|index| group.member_at(index)) {
+        Some(member) => Some(member_info(provider, &member)?.member_handle),
+        None => None,
+    };
+    Ok(Some(MlsExternalJoinInfo {
+        credential,
+        signature_key: leaf.signature_key().as_slice().to_vec(),
+        member_handle,
+        kem_output,
+        replaced_index: replaced.map(|index| index.u32()),
+        replaced_handle,
+    }))
+}
+
+/// Proposal counts of a staged commit, for its audit log entry.
+fn audit_proposals(staged_commit: &StagedCommit) -> ProposalSummary {
+    let mut summary = ProposalSummary::default();
+    for queued in staged_commit.queued_proposals() {
+        match queued.proposal() {
+            Proposal::Add(_) => summary.add += 1,
+            Proposal::Remove(_) => summary.remove += 1,
+            Proposal::Update(_) => summary.update += 1,
+            Proposal::PreSharedKey(_) => summary.psk += 1,
+            Proposal::GroupContextExtensions(_) => summary.group_context_extensions += 1,
+            _ => summary.other += 1,
+        }
+    }
+    summary
+}
+
+fn proposal_type_of(proposal: &Proposal) -> MlsProposalType {
+    match proposal {
+        Proposal::Add(_) => MlsProposalType::Add,
+        Proposal::Remove(_) => MlsProposalType::Remove,
+        Proposal::Update(_) => MlsProposalType::Update,
+        Proposal::PreSharedKey(_) => MlsProposalType::PreSharedKey,
+        Proposal::ReInit(_) => MlsProposalType::Reinit,
+        Proposal::ExternalInit(_) => MlsProposalType::ExternalInit,
+        Proposal::GroupContextExtensions(_) => MlsProposalType::GroupContextExtensions,
+        _ => MlsProposalType::Custom,
+    }
+}
+
+/// Whether `filter` lets `flexible_commit` commit the pending `queued`.
+fn proposal_filter_accepts(filter: &MlsProposalFilter, queued: &QueuedProposal) -> bool {
+    let kind = std::mem::discriminant(&proposal_type_of(queued.proposal()));
+    if !filter.proposal_types.is_empty()
+        && !filter.proposal_types.iter().any(|t| std::mem::discriminant(t) == kind)
+    {
+        return false;
+    }
+    if !filter.sender_indices.is_empty() {
+        let Sender::Member(idx) = queued.sender() else {
+            return false;
+        };
+        if !filter.sender_indices.contains(&idx.u32()) {
+            return false;
+        }
+    }
+    let Ok(proposal_ref) = queued.proposal_reference().tls_serialize_detached() else {
+        return false;
+    };
+    (filter.include_refs.is_empty() || filter.include_refs.contains(&proposal_ref))
+        && !filter.exclude_refs.contains(&proposal_ref)
+}
+
+/// The proposals covered by an own pending commit, for `CommitResult`.
+fn committed_proposals(staged_commit: &StagedCommit) -> Result<Vec<MlsCommittedProposal>, String> {
+    staged_commit
+        .queued_proposals()
+        .map(|queued| {
+            let added_credential = match queued.proposal() {
+                Proposal::Add(add) => Some(
+                    add.key_package()
+                        .leaf_node()
+                        .credential()
+                        .tls_serialize_detached()
+                        .map_err(|e| format!("Failed to serialize add credential: {}", e))?,
+                ),
+                _ => None,
+            };
+            Ok(MlsCommittedProposal {
+                proposal_type: proposal_type_of(queued.proposal()),
+                sender_index: match queued.sender() {
+                    Sender::Member(idx) => Some(idx.u32()),
+                    _ => None,
+                },
+                by_reference: matches!(queued.proposal_or_ref_type(), ProposalOrRefType::Reference),
+                removed_index: match queued.proposal() {
+                    Proposal::Remove(remove) => Some(remove.removed().u32()),
+                    _ => None,
+                },
+                added_credential,
+            })
+        })
+        .collect()
+}
+
+/// Describe `leaf` for the credential validator.
+fn credential_check(group: &MlsGroup, leaf_index: Option<u32>, leaf: &LeafNode) -> Result<MlsCredentialCheck, String> {
+    Ok(MlsCredentialCheck {
+        group_id: group.group_id().as_slice().to_vec(),
+        leaf_index,
+        credential: leaf
+            .credential()
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize credential: {}", e))?,
+        signature_key: leaf.signature_key().as_slice().to_vec(),
+    })
+}
+
+/// Credentials a staged commit introduces: those of added members, and
+/// those of updated leaves whose credential or signature key changed
+/// (including the committer's own leaf and external joiners).
+fn commit_credential_checks(
+    group: &MlsGroup,
+    sender: &Sender,
+    staged_commit: &StagedCommit,
+) -> Result<Vec<MlsCredentialCheck>, String> {
+    let changed = |index: u32, leaf: &LeafNode| {
+        group.member_at(LeafNodeIndex::new(index)).is_none_or(|member| {
+            &member.credential != leaf.credential() || member.signature_key != leaf.signature_key().as_slice()
+        })
+    };
+    let mut checks = Vec::new();
+    for add in staged_commit.add_proposals() {
+        checks.push(credential_check(group, None, add.add_proposal().key_package().leaf_node())?);
+    }
+    for update in staged_commit.update_proposals() {
+        let leaf = update.update_proposal().leaf_node();
+        if let Sender::Member(index) = update.sender() {
+            if changed(index.u32(), leaf) {
+                checks.push(credential_check(group, Some(index.u32()), leaf)?);
+            }
+        }
+    }
+    if let Some(leaf) = staged_commit.update_path_leaf_node() {
+        match sender {
+            Sender::Member(index) if changed(index.u32(), leaf) => {
+                checks.push(credential_check(group, Some(index.u32()), leaf)?);
+            }
+            Sender::NewMemberCommit => checks.push(credential_check(group, None, leaf)?),
+            _ => {}
+        }
+    }
+    Ok(checks)
+}
+
+/// Credentials of every other member of a group joined from a Welcome.
+fn welcome_credential_checks(group: &MlsGroup) -> Result<Vec<MlsCredentialCheck>, String> {
+    let own_index = group.own_leaf_index();
+    group
+        .members()
+        .filter(|member| member.index != own_index)
+        .map(|member| {
+            Ok(MlsCredentialCheck {
+                group_id: group.group_id().as_slice().to_vec(),
+                leaf_index: Some(member.index.u32()),
+                credential: member
+                    .credential
+                    .tls_serialize_detached()
+                    .map_err(|e| format!("Failed to serialize credential: {}", e))?,
+                signature_key: member.signature_key,
+            })
+        })
+        .collect()
+}
+
+/// `Some` if a handshake message sent at `sent_at` is older than `max_age`
+/// plus `clock_skew` seconds.
+fn handshake_expired(sent_at: u64, max_age: u64, clock_skew: u64) -> Result<Option<HandshakeExpired>, String> {
+    let age_secs = unix_now()?.saturating_sub(sent_at);
+    let max_age_secs = max_age.saturating_add(clock_skew);
+    Ok((age_secs > max_age_secs).then_some(HandshakeExpired { sent_at, age_secs, max_age_secs }))
+}
+
+/// Current Unix time in seconds.
+fn unix_now() -> Result<u64, String> {
+    crate::current_time()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .map_err(|e| format!("System clock before Unix epoch: {}", e))
+}
+
+/// Global metadata entry recording when and where local key packages were
+/// created. Entries whose key package is gone are pruned on the next write.
+const KEY_PACKAGE_META: &str = "key_package_meta";
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct KeyPackageMeta {
+    key_package_ref: Vec<u8>,
+    created_at: u64,
+    origin: Option<String>,
+}
+
+/// Delete a key package from `provider` together with its metadata entry.
+fn forget_key_package(provider: &mut SnapshotOpenMlsProvider, ref_bytes: &[u8]) -> Result<(), String> {
+    let hash_ref = openmls::ciphersuite::hash_ref::KeyPackageRef::tls_deserialize_exact_bytes(ref_bytes)
+        .map_err(|e| format!("Failed to deserialize key package ref: {}", e))?;
+    provider.storage().delete_key_package(&hash_ref)
+        .map_err(|e| format!("Failed to delete key package: {}", e))?;
+
+    let storage = provider.storage_mut();
+    let entries: Option<Vec<KeyPackageMeta>> = storage
+        .app_global(KEY_PACKAGE_META)
+        .map_err(|e| format!("Failed to read key package metadata: {}", e))?;
+    if let Some(mut entries) = entries {
+        entries.retain(|entry| entry.key_package_ref != ref_bytes);
+        storage
+            .write_app_global(KEY_PACKAGE_META, &entries)
+            .map_err(|e| format!("Failed to write key package metadata: {}", e))?;
+    }
+    Ok(())
+}
+
+/// `(not_before, not_after)` of a key package, in Unix seconds.
+fn key_package_lifetime(key_package: &KeyPackage) -> Result<(u64, u64), String> {
+    let lifetime = key_package
+        .leaf_node()
+        .life_time()
+        .ok_or_else(|| "Key package has no lifetime".to_string())?;
+    // Lifetime = struct { uint64 not_before; uint64 not_after; } (RFC 9420 §7.2)
+    let bytes = lifetime.tls_serialize_detached().map_err(|e| format!("Failed to serialize lifetime: {}", e))?;
+    let (not_before, not_after) = bytes.split_at(8);
+    Ok((
+        u64::from_be_bytes(not_before.try_into().map_err(|_| "Malformed lifetime".to_string())?),
+        u64::from_be_bytes(not_after.try_into().map_err(|_| "Malformed lifetime".to_string())?),
+    ))
+}
+
+fn key_package_exists(provider: &SnapshotOpenMlsProvider, ref_bytes: &[u8]) -> Result<bool, String> {
+    let hash_ref = openmls::ciphersuite::hash_ref::KeyPackageRef::tls_deserialize_exact_bytes(ref_bytes)
+        .map_err(|e| format!("Failed to deserialize key package ref: {}", e))?;
+    let bundle: Option<KeyPackageBundle> = provider.storage().key_package(&hash_ref)
+        .map_err(|e| format!("Failed to read key package: {}", e))?;
+    Ok(bundle.is_some())
+}
+
+/// Record a newly created key package in the sidecar metadata and build
+/// its result.
+fn record_key_package(
+    provider: &mut SnapshotOpenMlsProvider,
+    bundle: &KeyPackageBundle,
+    origin: Option<String>,
+) -> Result<KeyPackageResult, String> {
+    let key_package_bytes = bundle
+        .key_package()
+        .tls_serialize_detached()
+        .map_err(|e| format!("Failed to serialize key package: {}", e))?;
+    let key_package_ref = bundle
+        .key_package()
+        .hash_ref(provider.crypto())
+        .map_err(|e| format!("Failed to compute key package ref: {}", e))?
+        .tls_serialize_detached()
+        .map_err(|e| format!("Failed to serialize key package ref: {}", e))?;
+    let created_at = unix_now()?;
+
+    let existing: Vec<KeyPackageMeta> = provider.storage()
+        .app_global(KEY_PACKAGE_META)
+        .map_err(|e| format!("Failed to read key package metadata: {}", e))?
+        .unwrap_or_default();
+    let mut entries = Vec::with_capacity(existing.len() + 1);
+    for entry in existing {
+        if key_package_exists(provider, &entry.key_package_ref)? {
+            entries.push(entry);
+        }
+    }
+    entries.push(KeyPackageMeta { key_package_ref: key_package_ref.clone(), created_at, origin: origin.clone() });
+    provider.storage_mut()
+        .write_app_global(KEY_PACKAGE_META, &entries)
+        .map_err(|e| format!("Failed to write key package metadata: {}", e))?;
+
+    Ok(KeyPackageResult { key_package_bytes, key_package_ref, created_at, origin })
+}
+
+// ═══════════════════════════════════════════════════════════════
+// RESULT TYPES
+// ═══════════════════════════════════════════════════════════════
+
+pub struct CreateGroupResult {
+    pub group_id: Vec<u8>,
+}
+
+pub struct JoinGroupResult {
+    pub group_id: Vec<u8>,
+    /// Ref of the local key package the Welcome consumed.
+    pub key_package_ref: Option<Vec<u8>>,
+    /// Epoch the group was joined at.
+    pub epoch: u64,
+    pub ciphersuite: MlsCiphersuite,
+    pub member_count: u32,
+    pub our_leaf_index: u32,
+    /// Whether the credential validator accepted the credentials of all
+    /// other members.
+    pub credential_verified: bool,
+    /// Join receipt to send to the group, if enabled with
+    /// `set_join_receipts_enabled`.
+    pub join_receipt: Option<Vec<u8>>,
+}
+
+impl JoinGroupResult {
+    /// Summarize a freshly joined group before it is handed to
+    /// `finish_welcome_join`.
+    fn for_group(group: &MlsGroup) -> Result<JoinGroupResult, String> {
+        Ok(JoinGroupResult {
+            group_id: group.group_id().as_slice().to_vec(),
+            key_package_ref: None,
+            epoch: group.epoch().as_u64(),
+            ciphersuite: native_to_ciphersuite(group.ciphersuite())?,
+            member_count: group.members().count() as u32,
+            our_leaf_index: group.own_leaf_index().u32(),
+            credential_verified: false,
+            join_receipt: None,
+        })
+    }
+}
+
+/// One group of an invite bundle (see `bundle_invites`).
+pub struct MlsBundledInvite {
+    pub group_id: Vec<u8>,
+    /// TLS-serialized MlsMessage containing the Welcome.
+    pub welcome: Vec<u8>,
+    /// TLS-serialized ratchet tree, if the group does not use the ratchet
+    /// tree extension.
+    pub ratchet_tree: Option<Vec<u8>>,
+}
+
+/// Outcome of joining one group of an invite bundle.
+pub struct BundleJoinResult {
+    pub group_id: Vec<u8>,
+    /// Set when the group was joined.
+    pub joined: Option<JoinGroupResult>,
+    /// Why the group was not joined.
+    pub error: Option<String>,
+}
+
+/// A raw entry of a legacy callback-based storage backend.
+pub struct MlsStorageEntry {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+/// An operation interrupted by a crash or an error before all of its
+/// storage transactions were committed (see `pending_recovery`).
+pub struct PendingRecovery {
+    pub id: u64,
+    pub operation: MlsRecoveryOperation,
+    pub group_id: Vec<u8>,
+    /// Number of the operation's transactions that were committed.
+    pub phase: u32,
+    /// Unix seconds when the operation started.
+    pub started_at: u64,
+    /// Why rolling the operation forward failed, if it was attempted.
+    pub error: Option<String>,
+}
+
+pub enum MlsRecoveryOperation {
+    /// `delete_group`: the group's MLS state is gone, but engine metadata of
+    /// the group may remain. Rolling forward deletes it.
+    DeleteGroup,
+}
+
+pub enum MlsPendingJoinKind {
+    /// A Welcome passed to `join_group_from_welcome`.
+    Welcome,
+    /// A GroupInfo passed to `join_group_external_commit`.
+    ExternalCommit,
+}
+
+/// A failed join kept for `retry_pending_joins` (see
+/// `set_failed_join_retention`).
+pub struct PendingJoin {
+    pub id: u64,
+    pub kind: MlsPendingJoinKind,
+    /// `None` if the Welcome could not be decrypted far enough to tell.
+    pub group_id: Option<Vec<u8>>,
+    /// Error of the last attempt.
+    pub error: String,
+    /// Unix seconds of the last attempt.
+    pub failed_at: u64,
+    pub attempts: u32,
+}
+
+/// A Welcome kept by `stage_welcome` until it is accepted or declined.
+pub struct MlsStagedWelcome {
+    pub staged_id: u64,
+    pub group_id: Vec<u8>,
+    /// Unix seconds when the Welcome was staged.
+    pub staged_at: u64,
+}
+
+/// Result of `stage_welcome`.
+pub struct StageWelcomeResult {
+    pub staged_id: u64,
+    /// What the invitation is for, to show in an accept/decline prompt.
+    pub preview: WelcomeInspectResult,
+}
+
+/// Outcome of retrying one pending join.
+pub struct PendingJoinRetry {
+    pub id: u64,
+    pub group_id: Option<Vec<u8>>,
+    /// Set when a Welcome was joined.
+    pub joined: Option<JoinGroupResult>,
+    /// Set when a group was joined by external commit; the commit must be
+    /// sent to the group.
+    pub external_join: Option<ExternalJoinResult>,
+    /// Why the retry failed. The join stays pending unless the failure is
+    /// permanent (malformed input, or the group exists locally by now).
+    pub error: Option<String>,
+}
+
+/// What `import_from_provider_storage` or `import_interchange` imported.
+pub struct ProviderImportResult {
+    /// Ids of the imported groups.
+    pub group_ids: Vec<Vec<u8>>,
+    /// Number of global entries (key packages, signers, PSKs) imported.
+    pub global_entries: u32,
+    /// Number of group-scoped entries imported.
+    pub group_entries: u32,
+}
+
+/// A published key package that a Welcome has consumed and that should be
+/// removed from its delivery service.
+pub struct PublishedKeyPackageRef {
+    pub key_package_ref: Vec<u8>,
+    pub ds_id: String,
+}
+
+/// What to do when a Welcome targets a group that already has local state.
+pub enum ExistingGroupPolicy {
+    /// Keep the local state and report `GroupAlreadyExists`.
+    Abort,
+    /// Delete the local state and join from the Welcome.
+    ReplaceLocalState,
+}
+
+/// Local state found for the group a Welcome targets.
+pub struct GroupAlreadyExists {
+    /// Epoch of the locally stored group.
+    pub current_epoch: u64,
+    /// Epoch the Welcome would join at.
+    pub welcome_epoch: u64,
+}
+
+pub struct WelcomeJoinOutcome {
+    pub group_id: Vec<u8>,
+    /// Whether the group was joined (and persisted).
+    pub joined: bool,
+    /// Set when local state for the group existed. With
+    /// `ExistingGroupPolicy::Abort` the join did not happen.
+    pub already_exists: Option<GroupAlreadyExists>,
+    /// Ref of the local key package the Welcome consumed, if joined.
+    pub key_package_ref: Option<Vec<u8>>,
+    /// Set when the Welcome was older than the handshake expiry policy
+    /// allows. The join did not happen.
+    pub expired: Option<HandshakeExpired>,
+    /// Whether the credential validator accepted the credentials of all
+    /// other members. False if the group was not joined.
+    pub credential_verified: bool,
+    /// TLS-serialized MlsMessage announcing the join to the group, if the
+    /// group was joined and join receipts are enabled (see
+    /// `set_join_receipts_enabled`).
+    pub join_receipt: Option<Vec<u8>>,
+}
+
+/// Maximum age of incoming handshake messages, set via
+/// `set_handshake_expiry_policy`.
+///
+/// The age is measured from the send time the caller passes along with the
+/// message (typically the delivery service's envelope timestamp). With
+/// `timestamp_in_aad`, commits without such a time are measured from the
+/// first 8 bytes of their AAD instead, read as big-endian Unix seconds.
+/// Messages with neither are not checked.
+pub struct HandshakeExpiryPolicy {
+    /// Maximum age of a Welcome, in seconds; `None` = no limit.
+    pub max_welcome_age_secs: Option<u64>,
+    /// Maximum age of a commit, in seconds; `None` = no limit.
+    pub max_commit_age_secs: Option<u64>,
+    /// Added to both limits to tolerate clocks running apart.
+    pub clock_skew_secs: u64,
+    pub timestamp_in_aad: bool,
+}
+
+/// Why a handshake message was refused as too old.
+pub struct HandshakeExpired {
+    /// Send time of the message, in Unix seconds.
+    pub sent_at: u64,
+    /// Age of the message when it was processed, in seconds.
+    pub age_secs: u64,
+    /// The limit it exceeded, clock skew tolerance included.
+    pub max_age_secs: u64,
+}
+
+/// Join counters since the engine was opened (or last reset), as returned
+/// by `join_statistics`.
+pub struct JoinStatistics {
+    /// Successful joins from a Welcome.
+    pub welcomes_processed: u64,
+    /// Successful joins by external commit, including invite payloads.
+    pub external_commits: u64,
+    /// Failed joins, by reason.
+    pub failures: Vec<JoinFailureCount>,
+    /// Mean duration of successful joins in milliseconds (0 if none).
+    pub average_join_duration_ms: u64,
+}
+
+pub struct JoinFailureCount {
+    /// One of `malformed_input`, `no_matching_key_package`, `expired`,
+    /// `invalid_signature`, `group_exists`, `strict_mode`, `validation`,
+    /// `other`. `expired` and `invalid_signature` are reported for invite
+    /// payloads; OpenMLS rejections of a Welcome or GroupInfo are
+    /// `validation`.
+    pub reason: String,
+    pub count: u64,
+}
+
+pub struct ExternalJoinResult {
+    pub group_id: Vec<u8>,
+    pub commit: Vec<u8>,
+    pub group_info: Option<Vec<u8>>,
+}
+
+pub struct AddMembersResult {
+    pub commit: Vec<u8>,
+    pub welcome: Vec<u8>,
+    /// GroupInfo for the new epoch; always set when the call was made with
+    /// `ensure_group_info`.
+    pub group_info: Option<Vec<u8>>,
+    /// Whether the credential validator accepted the credentials of all
+    /// added members.
+    pub credential_verified: bool,
+}
+
+pub struct CommitResult {
+    pub commit: Vec<u8>,
+    pub welcome: Option<Vec<u8>>,
+    /// GroupInfo for the new epoch; always set when the call was made with
+    /// `ensure_group_info` (or, for `flexible_commit`, `create_group_info`).
+    pub group_info: Option<Vec<u8>>,
+    /// Wire format the commit was sent in.
+    pub wire_format: MlsWireFormat,
+    /// Proposals covered by the commit, including those pulled in from the
+    /// proposal store.
+    pub proposals: Vec<MlsCommittedProposal>,
+}
+
+pub struct ProposalResult {
+    pub proposal_message: Vec<u8>,
+    /// TLS-serialized `ProposalRef` of the proposal, as in
+    /// `MlsPendingProposalInfo`; pass to `remove_pending_proposal`.
+    pub proposal_ref: Vec<u8>,
+}
+
+pub struct CreateMessageResult {
+    pub ciphertext: Vec<u8>,
+    /// Always `Ciphertext`: application messages are never sent as plaintext.
+    pub wire_format: MlsWireFormat,
+    /// Set when the group compresses application messages.
+    pub compression: Option<MessageCompressionInfo>,
+}
+
+pub struct ProcessedMessageResult {
+    pub message_type: ProcessedMessageType,
+    pub sender_index: Option<u32>,
+    /// Sender's member handle (see `MlsMemberInfo`) as of the group's
+    /// current epoch; `None` if the sender's leaf now holds a different
+    /// credential than the message was signed under.
+    pub sender_handle: Option<Vec<u8>>,
+    pub epoch: u64,
+    pub application_message: Option<Vec<u8>>,
+    /// Authenticated associated data the sender attached (e.g. with
+    /// `set_default_aad`). Empty if there was none or the message was not
+    /// processed.
+    pub aad: Vec<u8>,
+    pub has_staged_commit: bool,
+    pub has_proposal: bool,
+    pub proposal_type: Option<MlsProposalType>,
+    /// PSKs the processed commit injected, e.g. to confirm an expected
+    /// external PSK was used. Empty for other messages.
+    pub commit_psks: Vec<MlsPskId>,
+    /// Set for application messages of groups that compress them.
+    pub compression: Option<MessageCompressionInfo>,
+    /// Set for application messages received as PrivateMessage.
+    pub padding: Option<MessagePaddingInfo>,
+    /// For `MissingProposals`: TLS-serialized refs of the referenced
+    /// proposals not in the pending proposal store. `None` if the commit was
+    /// encrypted, as OpenMLS does not expose its content after a failure, so
+    /// the missing refs are unknown; always `None` for other message types.
+    pub missing_proposal_refs: Option<Vec<Vec<u8>>>,
+    /// For `Expired`: the commit's age and the limit it exceeded.
+    pub expired: Option<HandshakeExpired>,
+    /// Whether the credential validator accepted every credential the
+    /// message introduced (vacuously true if it introduced none). False for
+    /// messages that were not applied.
+    pub credential_verified: bool,
+    /// Whether the sender is on the group's block list (see `block_member`).
+    pub blocked: bool,
+    /// Whether the commit changed the group features without the proposer
+    /// or committer being authorized to (see `MlsGroupFeatures`). The
+    /// commit was applied regardless, as the other members apply it; the
+    /// application decides how to react.
+    pub unauthorized_features_change: bool,
+}
+
+/// Activity counters of a group (see `group_activity`).
+pub struct GroupActivity {
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    pub commits_merged: u64,
+    /// Unix time of the last counted event; `None` if there was none yet.
+    pub last_activity: Option<u64>,
+}
+
+/// When a member was added to the group (see `member_join_epochs`).
+pub struct MemberJoinEpoch {
+    pub leaf_index: u32,
+    pub member_handle: Vec<u8>,
+    /// The member's first epoch in the group, i.e. the one created by the
+    /// commit that added it.
+    pub epoch: u64,
+    /// False if the member was already in the group when this device joined
+    /// (or started tracking joins), making `epoch` only an upper bound.
+    pub exact: bool,
+}
+
+/// Outcome of one message of `process_messages_batch`.
+pub struct BatchMessageResult {
+    /// Position of the message in the batch.
+    pub index: u32,
+    /// Group the message was routed to. `None` if it could not be parsed.
+    pub group_id: Option<Vec<u8>>,
+    /// Set when the message was processed.
+    pub result: Option<ProcessedMessageResult>,
+    /// Why the message could not be processed.
+    pub error: Option<String>,
+}
+
+pub struct ProcessedMessageInspectResult {
+    pub message_type: ProcessedMessageType,
+    pub sender_index: Option<u32>,
+    /// Sender's member handle (see `MlsMemberInfo`) as of the group's
+    /// current epoch; `None` if the sender's leaf now holds a different
+    /// credential than the message was signed under.
+    pub sender_handle: Option<Vec<u8>>,
+    pub epoch: u64,
+    pub application_message: Option<Vec<u8>>,
+    /// Authenticated associated data the sender attached (e.g. with
+    /// `set_default_aad`). Empty if there was none or the message was not
+    /// processed.
+    pub aad: Vec<u8>,
+    pub staged_commit_info: Option<StagedCommitInfo>,
+    pub proposal_type: Option<MlsProposalType>,
+    /// Set for application messages of groups that compress them.
+    pub compression: Option<MessageCompressionInfo>,
+    /// Set for application messages received as PrivateMessage.
+    pub padding: Option<MessagePaddingInfo>,
+    /// For `MissingProposals`: TLS-serialized refs of the referenced
+    /// proposals not in the pending proposal store. `None` if the commit was
+    /// encrypted, as OpenMLS does not expose its content after a failure, so
+    /// the missing refs are unknown; always `None` for other message types.
+    pub missing_proposal_refs: Option<Vec<Vec<u8>>>,
+    /// For `Expired`: the commit's age and the limit it exceeded.
+    pub expired: Option<HandshakeExpired>,
+    /// Whether the credential validator accepted every credential the
+    /// message introduced (vacuously true if it introduced none). False for
+    /// messages that were not applied.
+    pub credential_verified: bool,
+    /// Whether the sender is on the group's block list (see `block_member`).
+    pub blocked: bool,
+    /// Whether the commit changed the group features without the proposer
+    /// or committer being authorized to (see `MlsGroupFeatures`). The
+    /// commit was applied regardless, as the other members apply it; the
+    /// application decides how to react.
+    pub unauthorized_features_change: bool,
+    /// Set by `process_message_staged` for commits: the reference to pass
+    /// to `merge_staged_commit_by_ref` or `reject_staged_commit`.
+    pub staged_commit_ref: Option<Vec<u8>>,
+}
+
+/// Outcome of `validate_message`.
+pub struct MessageValidationResult {
+    /// Whether the message would be accepted by `process_message`.
+    pub valid: bool,
+    /// Why the message was rejected. `None` when valid.
+    pub error: Option<String>,
+    /// Epoch the message was sent in, if it could be parsed.
+    pub epoch: Option<u64>,
+    /// Type of the message content, if it could be processed.
+    pub message_type: Option<ProcessedMessageType>,
+    /// Sender's leaf index, if the sender is a group member.
+    pub sender_index: Option<u32>,
+}
+
+/// Outcome of `decryption_failure_report` for a message that fails.
+pub struct DecryptionFailureReport {
+    pub kind: DecryptionFailureKind,
+    /// The error `process_message` fails with.
+    pub error: String,
+    pub message_epoch: u64,
+    pub group_epoch: u64,
+    /// `message_epoch - group_epoch`.
+    pub epoch_delta: i64,
+    /// Sender's leaf index, for plaintext messages from members. The sender
+    /// of a private message is encrypted and not reported.
+    pub sender_index: Option<u32>,
+    /// Out-of-order tolerance of the sender ratchet, in generations.
+    ///
+    /// The requested generation is encrypted in the sender data and is not
+    /// exposed by OpenMLS; compare against this window and
+    /// `max_forward_distance` instead.
+    pub out_of_order_tolerance: u32,
+    pub max_forward_distance: u32,
+}
+
+/// Outcome of `decrypt_past_message`.
+pub struct PastMessageDecryption {
+    /// Epoch the message was sent in.
+    pub message_epoch: u64,
+    pub group_epoch: u64,
+    /// Whether the keys of `message_epoch` were available, as reported by
+    /// `has_decryption_keys_for_epoch`.
+    pub keys_available: bool,
+    /// The processed message. `None` if it could not be decrypted.
+    pub result: Option<ProcessedMessageResult>,
+    /// Why the message could not be decrypted. `None` on success.
+    pub error: Option<String>,
+}
+
+pub struct KeyPackageResult {
+    pub key_package_bytes: Vec<u8>,
+    /// TLS-serialized `KeyPackageRef`.
+    pub key_package_ref: Vec<u8>,
+    /// Creation time (Unix seconds).
+    pub created_at: u64,
+    /// Caller-supplied origin (e.g. a device or installation id).
+    pub origin: Option<String>,
+}
+
+/// What `maintain_key_packages` changed.
+pub struct KeyPackageMaintenance {
+    /// Key packages created to replenish the stock; upload them to the
+    /// delivery service.
+    pub new_key_packages: Vec<KeyPackageResult>,
+    /// TLS-serialized refs of the expired key packages that were deleted;
+    /// remove them from the delivery service.
+    pub revoked_refs: Vec<Vec<u8>>,
+}
+
+/// A key package held in local storage, as listed by `list_key_packages`.
+pub struct KeyPackageInfo {
+    /// TLS-serialized `KeyPackageRef`.
+    pub key_package_ref: Vec<u8>,
+    /// Creation time (Unix seconds).
+    pub created_at: u64,
+    /// Origin recorded at creation, if any.
+    pub origin: Option<String>,
+    pub last_resort: bool,
+    pub ciphersuite: MlsCiphersuite,
+    /// Validity period of the key package (Unix seconds).
+    pub not_before: u64,
+    pub not_after: u64,
+    /// Serialized content of the key package's credential (the identity,
+    /// for a basic credential).
+    pub credential_identity: Vec<u8>,
+}
+
+/// Key for a client-side encrypted search index, from `search_index_key`.
+pub struct SearchIndexKey {
+    /// `epoch / epoch_window` for every epoch the key is valid in.
+    pub window: u64,
+    /// First epoch of the window, whose exporter secret the key comes from.
+    pub first_epoch: u64,
+    pub key: Vec<u8>,
+}
+
+/// Outcome of `export_audit_log`.
+pub struct AuditLogExport {
+    /// The log as JSON (schema in the `audit_log` module).
+    pub json: String,
+    /// Signature over the UTF-8 bytes of `json`.
+    pub signature: Vec<u8>,
+    pub signer_public_key: Vec<u8>,
+}
+
+/// Result of `self_heal`.
+pub struct SelfHealResult {
+    /// Epoch of the local state that was quarantined.
+    pub archived_epoch: u64,
+    /// The external commit to send, as for `join_group_external_commit`.
+    pub join: ExternalJoinResult,
+}
+
+/// A group set aside by `quarantine_group`.
+pub struct QuarantinedGroupInfo {
+    pub group_id: Vec<u8>,
+    /// The error recorded when the group was quarantined.
+    pub error: String,
+    /// Unix seconds.
+    pub quarantined_at: u64,
+}
+
+/// Progress of one sender's application messages, as tracked when
+/// deduplication is enabled for a group.
+pub struct MessageWatermark {
+    pub sender_index: u32,
+    /// Latest epoch an application message from this sender was accepted in.
+    pub epoch: u64,
+    /// Application messages accepted from this sender in that epoch.
+    pub messages: u64,
+}
+
+/// A credential on the group's local block list.
+pub struct BlockedMember {
+    /// TLS-serialized Credential.
+    pub credential: Vec<u8>,
+    /// Whether application messages from this member are withheld from
+    /// `ProcessedMessageResult::application_message`.
+    pub suppress_messages: bool,
+}
+
+pub struct LeaveGroupResult {
+    pub message: Vec<u8>,
+}
+
+/// Emitted after a group's epoch advances (commit merged or group joined).
+#[derive(Clone)]
+pub struct EpochAdvancedEvent {
+    pub group_id: Vec<u8>,
+    pub epoch: u64,
+    /// Keys derived from the new epoch's exporter secret, one per label
+    /// registered via `register_epoch_exporter`.
+    pub exported_keys: Vec<EpochExportedKey>,
+    /// Number of epoch advances of this group summarized by the event. Above
+    /// 1 only when throttling (see `set_event_throttle`) merged events;
+    /// `epoch` and `exported_keys` are then those of the newest epoch.
+    pub coalesced: u32,
+}
+
+/// An application key derived from an epoch's exporter secret.
+#[derive(Clone)]
+pub struct EpochExportedKey {
+    pub label: String,
+    pub key: Vec<u8>,
+}
+
+/// A change to a group, delivered to `subscribe_group_events` streams.
+#[derive(Clone)]
+pub enum GroupEvent {
+    MemberAdded { leaf_index: u32, credential: Vec<u8>, member_handle: Vec<u8> },
+    MemberRemoved { leaf_index: u32, credential: Vec<u8>, member_handle: Vec<u8> },
+    EpochAdvanced { epoch: u64 },
+    /// This client is no longer a member, e.g. it was removed.
+    SelfRemoved,
+    /// `self_heal` replaced the local state, archived at `archived_epoch`,
+    /// by rejoining at `epoch`.
+    SelfHealed { archived_epoch: u64, epoch: u64 },
+    /// A proposal was added to the pending proposals.
+    ProposalQueued { proposal_ref: Vec<u8>, sender_index: Option<u32> },
+}
+
+impl GroupEvent {
+    /// The throttling slot of the event, if `set_event_throttle` limits it.
+    fn throttle_slot(&self) -> Option<ThrottleSlot> {
+        match self {
+            GroupEvent::MemberAdded { .. } | GroupEvent::MemberRemoved { .. } => Some(ThrottleSlot::GroupMembers),
+            GroupEvent::EpochAdvanced { .. } => Some(ThrottleSlot::GroupEpoch),
+            _ => None,
+        }
+    }
+}
+
+/// Streams of `subscribe_group_events` for one group, with the group state
+/// they last saw.
+struct GroupEventSubscription {
+    sinks: Vec<StreamSink<GroupEvent>>,
+    state: ObservedGroupState,
+    /// The newest group state while some of its events are held back by
+    /// throttling.
+    held: Option<ObservedGroupState>,
+}
+
+/// What `GroupEvent`s are derived from. `epoch` is `None` while the group
+/// does not exist locally.
+#[derive(Default, Clone)]
+struct ObservedGroupState {
+    epoch: Option<u64>,
+    active: bool,
+    members: Vec<MlsMemberInfo>,
+    /// Pending proposals as (reference, sender leaf index).
+    proposals: Vec<(Vec<u8>, Option<u32>)>,
+}
+
+impl ObservedGroupState {
+    fn load(provider: &SnapshotOpenMlsProvider, group_id: &[u8]) -> Result<ObservedGroupState, String> {
+        let Some(group) = MlsGroup::load(provider.storage(), &GroupId::from_slice(group_id))
+            .map_err(|e| format!("Failed to load group: {}", e))?
+        else {
+            return Ok(ObservedGroupState::default());
+        };
+        let mut members = Vec::new();
+        for member in group.members() {
+            members.push(member_info(provider, &member)?);
+        }
+        let mut proposals = Vec::new();
+        for queued in group.pending_proposals() {
+            let proposal_ref = queued.proposal_reference()
+                .tls_serialize_detached()
+                .map_err(|e| format!("Failed to serialize proposal ref: {}", e))?;
+            let sender_index = match queued.sender() {
+                Sender::Member(idx) => Some(idx.u32()),
+                _ => None,
+            };
+            proposals.push((proposal_ref, sender_index));
+        }
+        Ok(ObservedGroupState { epoch: Some(group.epoch().as_u64()), active: group.is_active(), members, proposals })
+    }
+
+    /// Events leading from `self` to `next`. A group that appears (join) or
+    /// disappears (deletion) reports no member changes.
+    fn events_to(&self, next: &ObservedGroupState) -> Vec<GroupEvent> {
+        let mut events = Vec::new();
+        if self.epoch.is_some() && next.epoch.is_some() {
+            let same = |a: &MlsMemberInfo, b: &MlsMemberInfo| a.index == b.index && a.signature_key == b.signature_key;
+            for member in self.members.iter().filter(|m| !next.members.iter().any(|n| same(m, n))) {
+                events.push(GroupEvent::MemberRemoved {
+                    leaf_index: member.index,
+                    credential: member.credential.clone(),
+                    member_handle: member.member_handle.clone(),
+                });
+            }
+            for member in next.members.iter().filter(|n| !self.members.iter().any(|m| same(m, n))) {
+                events.push(GroupEvent::MemberAdded {
+                    leaf_index: member.index,
+                    credential: member.credential.clone(),
+                    member_handle: member.member_handle.clone(),
+                });
+            }
+            if self.active && !next.active {
+                events.push(GroupEvent::SelfRemoved);
+            }
+        }
+        if let Some(epoch) = next.epoch.filter(|epoch| self.epoch != Some(*epoch)) {
+            events.push(GroupEvent::EpochAdvanced { epoch });
+        }
+        for (proposal_ref, sender_index) in &next.proposals {
+            if !self.proposals.iter().any(|(known, _)| known == proposal_ref) {
+                events.push(GroupEvent::ProposalQueued { proposal_ref: proposal_ref.clone(), sender_index: *sender_index });
+            }
+        }
+        events
+    }
+}
+
+/// Result of `update_cached_group_info`.
+pub enum GroupInfoUpdateOutcome {
+    /// The GroupInfo was verified and cached.
+    Accepted,
+    /// The GroupInfo belongs to a different group.
+    WrongGroup,
+    /// The GroupInfo is older than the cached one or the local group state.
+    StaleEpoch,
+    /// The signer's leaf is not in the local tree, so the signature cannot
+    /// be checked.
+    UnknownSigner,
+    /// The signature does not verify under the signer's key.
+    InvalidSignature,
+}
+
+/// Storage backend of an engine, as reported by `engine_info`.
+pub enum MlsStorageBackend {
+    /// SQLCipher database (native platforms).
+    Sqlcipher,
+    /// IndexedDB with per-value AES-GCM (web).
+    IndexedDb,
+    /// In-memory sandbox (see `fork_group_sandbox`).
+    Sandbox,
+    /// Backend registered from Rust (see `create_with_backend`).
+    Custom,
+}
+
+/// Diagnostics about the storage behind an engine.
+pub struct MlsEngineInfo {
+    pub backend: MlsStorageBackend,
+    /// Database file path or IndexedDB name; `None` for sandboxes and
+    /// custom backends.
+    pub db_path: Option<String>,
+    /// Schema version stored in the database; for sandboxes and custom
+    /// backends the version this build writes.
+    pub schema_version: u32,
+    /// SQLite `page_size` in bytes (native only).
+    pub page_size: Option<u32>,
+    /// SQLite `cache_size`: pages if positive, KiB if negative (native only).
+    pub cache_size: Option<i64>,
+    /// SQLite `journal_mode`, lowercase (native only).
+    pub journal_mode: Option<String>,
+    /// Whether the database uses write-ahead logging.
+    pub wal_active: bool,
+}
+
+/// Progress of a backend migration, as reported by `migration_status`.
+pub struct MlsBackendMigrationStatus {
+    /// Unix seconds.
+    pub started_at: u64,
+    /// Unix seconds after which `finalize_backend_migration` may switch
+    /// without `force`.
+    pub dual_write_until: u64,
+    /// Writes applied to the source and mirrored to the target.
+    pub mirrored_writes: u64,
+    /// Mirrored writes the target failed to apply.
+    pub target_write_failures: u64,
+    pub last_target_error: Option<String>,
+    /// Entries whose value differs between source and target, including
+    /// entries only one of them holds.
+    pub mismatched_entries: u64,
+    /// Whether the dual-write period has elapsed and the stores match.
+    pub ready: bool,
+}
+
+pub struct GroupConfigurationResult {
+    pub ciphersuite: MlsCiphersuite,
+    pub wire_format_policy: MlsWireFormatPolicy,
+    pub padding_size: u32,
+    pub sender_ratchet_max_out_of_order: u32,
+    pub sender_ratchet_max_forward_distance: u32,
+}
+
+/// What `MlsEngine::create_with_policy` does when the database is already
+/// open in this process.
+pub enum EngineOpenPolicy {
+    /// Fail with an "MlsEngine already open" error.
+    Fail,
+    /// Return another handle to the open engine, as `from_token` would. The
+    /// encryption key must match the one the engine was opened with.
+    Share,
+}
+
+// ═══════════════════════════════════════════════════════════════
+// MLS ENGINE
+// ═══════════════════════════════════════════════════════════════
+
+pub struct MlsEngine {
+    state: std::sync::Arc<EngineState>,
+}
+
+/// State shared by every handle to the same engine (see `to_token`).
+struct EngineState {
+    db: parking_lot::RwLock<Option<EngineStore>>,
+    epoch_sinks: parking_lot::Mutex<Vec<StreamSink<EpochAdvancedEvent>>>,
+    epoch_exporters: parking_lot::RwLock<Vec<EpochExporter>>,
+    event_throttle: parking_lot::Mutex<EventThrottle>,
+    /// Streams of `subscribe_group_events`, by group id.
+    group_subscriptions: parking_lot::Mutex<std::collections::BTreeMap<Vec<u8>, GroupEventSubscription>>,
+    token: std::sync::OnceLock<u64>,
+    strict_mode: std::sync::atomic::AtomicBool,
+    audit_log: std::sync::atomic::AtomicBool,
+    join_receipts: std::sync::atomic::AtomicBool,
+    failed_join_retention: std::sync::atomic::AtomicBool,
+    member_tombstones: std::sync::atomic::AtomicBool,
+    handshake_expiry: parking_lot::RwLock<Option<HandshakeExpiryPolicy>>,
+    credential_validator: parking_lot::RwLock<Option<CredentialValidator>>,
+    proposal_policy: parking_lot::RwLock<Option<ProposalPolicy>>,
+    join_stats: parking_lot::Mutex<JoinStats>,
+    /// Signers registered with `register_signer`, by public key.
+    signers: parking_lot::RwLock<std::collections::BTreeMap<Vec<u8>, SignatureKeyPair>>,
+    /// Crypto/rand provider reused by every operation of this engine.
+    crypto: std::sync::Arc<crate::hybrid_crypto::HybridCrypto>,
+    /// Sandboxes forked from this engine, by sandbox id.
+    sandboxes: parking_lot::Mutex<std::collections::BTreeMap<u64, std::sync::Arc<EngineState>>>,
+    /// Digests of the encryption keys of the database files this engine is
+    /// registered on in `OPEN_ENGINES`, by normalized path.
+    key_digests: parking_lot::Mutex<std::collections::BTreeMap<String, Vec<u8>>>,
+}
+
+/// Dart callback set with `set_credential_validator`.
+type CredentialValidator = std::sync::Arc<dyn Fn(MlsCredentialCheck) -> DartFnFuture<MlsCredentialVerdict> + Send + Sync>;
+
+/// Dart callback set with `set_proposal_policy`.
+type ProposalPolicy = std::sync::Arc<dyn Fn(MlsProposalCheck) -> DartFnFuture<bool> + Send + Sync>;
+
+/// Live engines reachable via `MlsEngine::from_token`. Holds weak references
+/// so a token never keeps an engine alive on its own.
+static ENGINE_TOKENS: parking_lot::Mutex<std::collections::BTreeMap<u64, std::sync::Weak<EngineState>>> =
+    parking_lot::Mutex::new(std::collections::BTreeMap::new());
+static NEXT_ENGINE_TOKEN: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+static NEXT_SANDBOX_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
+/// Engines opened on a database file, by normalized path. Two connections
+/// to one SQLCipher file would race with independent caches, so `create`
+/// refuses to open it twice. The digests of the files' encryption keys are
+/// kept by the engines themselves (`EngineState::key_digests`).
+static OPEN_ENGINES: parking_lot::Mutex<std::collections::BTreeMap<String, OpenEngine>> =
+    parking_lot::Mutex::new(std::collections::BTreeMap::new());
+
+/// Locks held while a database file is opened and registered, by normalized
+/// path, so concurrent `create` calls on one file run its schema migrations
+/// one after the other and the later ones find the engine already open.
+static OPENING_PATHS: parking_lot::Mutex<
+    std::collections::BTreeMap<String, std::sync::Arc<futures::lock::Mutex<()>>>,
+> = parking_lot::Mutex::new(std::collections::BTreeMap::new());
+
+/// The lock serializing opens of `path` (see `OPENING_PATHS`).
+fn opening_lock(path: &str) -> std::sync::Arc<futures::lock::Mutex<()>> {
+    let mut locks = OPENING_PATHS.lock();
+    locks.retain(|_, lock| std::sync::Arc::strong_count(lock) > 1);
+    locks
+        .entry(path.to_string())
+        .or_insert_with(|| std::sync::Arc::new(futures::lock::Mutex::new(())))
+        .clone()
+}
+
+struct OpenEngine {
+    state: std::sync::Weak<EngineState>,
+}
+
+impl OpenEngine {
+    /// The engine, unless it was dropped or closed.
+    fn live(&self) -> Option<std::sync::Arc<EngineState>> {
+        self.state.upgrade().filter(|state| state.db.read().is_some())
+    }
+}
+
+/// Storage entries `export_backup_stream` reads at a time.
+const BACKUP_PAGE_SIZE: u32 = 256;
+
+const MIGRATION_IN_PROGRESS: &str = "A backend migration is already in progress";
+const NO_MIGRATION: &str = "No backend migration in progress";
+
+/// Close `store` and, during a migration, both of its stores.
+async fn close_store(store: EngineStore) -> Result<(), String> {
+    match store {
+        EngineStore::Db(arc) => match std::sync::Arc::try_unwrap(arc) {
+            Ok(db) => db.close().await,
+            Err(_) => Ok(()), // In-flight operations hold the last ref; cleanup on drop
+        },
+        EngineStore::Sandbox(_) => Ok(()),
+        EngineStore::Custom(backend) => backend.close().await,
+        EngineStore::Migrating(arc) => match std::sync::Arc::try_unwrap(arc) {
+            Ok(migration) => {
+                let source = Box::pin(close_store(migration.source)).await;
+                Box::pin(close_store(migration.target)).await?;
+                source
+            }
+            Err(_) => Ok(()),
+        },
+    }
+}
+
+/// Destroy the data in `store` and, during a migration, in both of its
+/// stores.
+async fn wipe_store(store: EngineStore) -> Result<(), String> {
+    match store {
+        EngineStore::Db(db) => db.secure_wipe().await,
+        // Dropping the last reference zeroizes the sandbox's entries.
+        EngineStore::Sandbox(_) => Ok(()),
+        EngineStore::Custom(backend) => backend.secure_wipe().await,
+        EngineStore::Migrating(migration) => {
+            let source = Box::pin(wipe_store(migration.source.clone())).await;
+            Box::pin(wipe_store(migration.target.clone())).await?;
+            source
+        }
+    }
+}
+
+/// Registry key for `db_path`, `None` for in-memory databases.
+///
+/// Symlinks are resolved even before the file exists, so the first open
+/// and later ones agree (e.g. `/var` and `/private/var` on macOS).
+fn open_engine_key(db_path: &str) -> Option<String> {
+    if db_path == ":memory:" {
+        return None;
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let path = std::path::absolute(db_path).ok()?;
+        let normalized = match std::fs::canonicalize(&path) {
+            Ok(normalized) => normalized,
+            Err(_) => match (path.parent().map(std::fs::canonicalize), path.file_name()) {
+                (Some(Ok(dir)), Some(name)) => dir.join(name),
+                _ => path,
+            },
+        };
+        Some(normalized.to_string_lossy().into_owned())
+    }
+    #[cfg(target_arch = "wasm32")]
+    Some(db_path.to_string())
+}
+
+/// Join counters behind `join_statistics`.
+#[derive(Default)]
+struct JoinStats {
+    welcomes_processed: u64,
+    external_commits: u64,
+    total_duration_ms: u64,
+    failures: std::collections::BTreeMap<&'static str, u64>,
+}
+
+/// Which counter a join feeds in `JoinStats`.
+enum JoinKind {
+    Welcome,
+    ExternalCommit,
+}
+
+/// Why a join failed, assigned where it failed.
+#[derive(Clone, Copy)]
+enum JoinFailure {
+    MalformedInput,
+    NoMatchingKeyPackage,
+    /// An invite payload past its `not_after`.
+    Expired,
+    /// An invite payload signature that does not verify.
+    InvalidSignature,
+    GroupExists,
+    StrictMode,
+    /// OpenMLS rejected the Welcome or GroupInfo.
+    Validation,
+    Other,
+}
+
+impl JoinFailure {
+    /// Reason reported in `JoinFailureCount`.
+    fn reason(self) -> &'static str {
+        match self {
+            JoinFailure::MalformedInput => "malformed_input",
+            JoinFailure::NoMatchingKeyPackage => "no_matching_key_package",
+            JoinFailure::Expired => "expired",
+            JoinFailure::InvalidSignature => "invalid_signature",
+            JoinFailure::GroupExists => "group_exists",
+            JoinFailure::StrictMode => "strict_mode",
+            JoinFailure::Validation => "validation",
+            JoinFailure::Other => "other",
+        }
+    }
+
+    /// Whether retrying the same input cannot fix the join.
+    fn is_permanent(self) -> bool {
+        matches!(self, JoinFailure::MalformedInput | JoinFailure::GroupExists)
+    }
+}
+
+/// Error of a join, for `track_join` and failed join retention.
+struct JoinError {
+    failure: JoinFailure,
+    message: String,
+}
+
+impl JoinError {
+    fn new(failure: JoinFailure, message: impl Into<String>) -> Self {
+        JoinError { failure, message: message.into() }
+    }
+
+    fn malformed(message: impl Into<String>) -> Self {
+        JoinError::new(JoinFailure::MalformedInput, message)
+    }
+
+    /// A Welcome OpenMLS could not process: for want of a local key package
+    /// if none of ours matched it.
+    fn welcome(key_packages: &WelcomeKeyPackages, message: String) -> Self {
+        match key_packages.chosen {
+            Some(_) => JoinError::new(JoinFailure::Validation, message),
+            None => JoinError::new(JoinFailure::NoMatchingKeyPackage, message),
+        }
+    }
+
+    fn strict_mode(error: MlsApiError) -> Self {
+        JoinError::new(JoinFailure::StrictMode, error.message)
+    }
+}
+
+impl From<String> for JoinError {
+    fn from(message: String) -> Self {
+        JoinError::new(JoinFailure::Other, message)
+    }
+}
+
+impl From<MlsApiError> for JoinError {
+    fn from(error: MlsApiError) -> Self {
+        JoinError::new(JoinFailure::Other, error.message)
+    }
+}
+
+impl From<JoinError> for String {
+    fn from(error: JoinError) -> Self {
+        error.message
+    }
+}
+
+/// Event kinds that `set_event_throttle` limits separately.
+pub enum MlsEventKind {
+    /// `EpochAdvancedEvent`s and `GroupEvent::EpochAdvanced`.
+    EpochAdvanced,
+    /// `GroupEvent::MemberAdded` and `GroupEvent::MemberRemoved`.
+    MemberChanged,
+}
+
+/// What a throttle interval is tracked for, per group: each stream keeps
+/// its own pace.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ThrottleSlot {
+    EpochStream,
+    GroupEpoch,
+    GroupMembers,
+}
+
+/// Per-group rate limits on events, set via `set_event_throttle`.
+#[derive(Default)]
+struct EventThrottle {
+    /// Minimum time between two events of a kind for the same group; zero =
+    /// no limit.
+    epoch_interval: std::time::Duration,
+    member_interval: std::time::Duration,
+    /// When each group's last event of a slot was delivered.
+    last_emitted: std::collections::BTreeMap<(ThrottleSlot, Vec<u8>), std::time::SystemTime>,
+    /// Epoch events held back, merged per group.
+    pending: std::collections::BTreeMap<Vec<u8>, EpochAdvancedEvent>,
+    /// Groups with `GroupEvent`s of a slot held back.
+    held: std::collections::BTreeSet<(ThrottleSlot, Vec<u8>)>,
+    /// Whether a flush timer is running.
+    timer_armed: bool,
+}
+
+impl EventThrottle {
+    fn interval(&self, slot: ThrottleSlot) -> std::time::Duration {
+        match slot {
+            ThrottleSlot::EpochStream | ThrottleSlot::GroupEpoch => self.epoch_interval,
+            ThrottleSlot::GroupMembers => self.member_interval,
+        }
+    }
+
+    /// When the next event of `slot` for `group_id` may be delivered.
+    fn due_at(&self, slot: ThrottleSlot, group_id: &[u8]) -> Option<std::time::SystemTime> {
+        self.last_emitted.get(&(slot, group_id.to_vec())).map(|last| *last + self.interval(slot))
+    }
+
+    /// Whether an event of `slot` for `group_id` may be delivered at `now`,
+    /// recording the delivery if so. `force` ignores the interval.
+    fn release(&mut self, slot: ThrottleSlot, group_id: &[u8], now: std::time::SystemTime, force: bool) -> bool {
+        if self.interval(slot).is_zero() {
+            return true;
+        }
+        if !force && self.due_at(slot, group_id).is_some_and(|due| due > now) {
+            return false;
+        }
+        self.last_emitted.insert((slot, group_id.to_vec()), now);
+        true
+    }
+
+    /// Queue `event` and return the epoch events that are due at `now`:
+    /// every pending group whose last event is at least the interval old.
+    fn offer(&mut self, event: EpochAdvancedEvent, now: std::time::SystemTime) -> Vec<EpochAdvancedEvent> {
+        if self.epoch_interval.is_zero() {
+            return vec![event];
+        }
+        let event = match self.pending.remove(&event.group_id) {
+            Some(held) => EpochAdvancedEvent { coalesced: held.coalesced.saturating_add(event.coalesced), ..event },
+            None => event,
+        };
+        self.pending.insert(event.group_id.clone(), event);
+        self.take_due_epoch_events(now, false)
+    }
+
+    /// Take the held-back epoch events that are due at `now`, or all of them
+    /// with `force`.
+    fn take_due_epoch_events(&mut self, now: std::time::SystemTime, force: bool) -> Vec<EpochAdvancedEvent> {
+        let group_ids: Vec<Vec<u8>> = self.pending.keys().cloned().collect();
+        group_ids
+            .into_iter()
+            .filter(|group_id| self.release(ThrottleSlot::EpochStream, group_id, now, force))
+            .filter_map(|group_id| self.pending.remove(&group_id))
+            .collect()
+    }
+
+    /// Take the groups whose held-back `GroupEvent`s are due at `now`, or all
+    /// of them with `force`.
+    fn take_due_groups(&mut self, now: std::time::SystemTime, force: bool) -> std::collections::BTreeSet<Vec<u8>> {
+        let due: Vec<(ThrottleSlot, Vec<u8>)> = self
+            .held
+            .iter()
+            .filter(|(slot, group_id)| force || self.due_at(*slot, group_id).is_none_or(|due| due <= now))
+            .cloned()
+            .collect();
+        due.into_iter()
+            .map(|key| {
+                self.held.remove(&key);
+                key.1
+            })
+            .collect()
+    }
+
+    /// Time from `now` until the first held-back event is due.
+    fn next_due(&self, now: std::time::SystemTime) -> Option<std::time::Duration> {
+        let pending = self.pending.keys().map(|group_id| (ThrottleSlot::EpochStream, group_id));
+        let held = self.held.iter().map(|(slot, group_id)| (*slot, group_id));
+        pending
+            .chain(held)
+            .map(|(slot, group_id)| {
+                self.due_at(slot, group_id)
+                    .and_then(|due| due.duration_since(now).ok())
+                    .unwrap_or_default()
+            })
+            .min()
+    }
+}
+
+/// An exporter registered via `register_epoch_exporter`.
+struct EpochExporter {
+    label: String,
+    context: Vec<u8>,
+    key_length: u32,
+}
+
+impl MlsEngine {
+    // ═══════════════════════════════════════════════════════════
+    // CONSTRUCTOR
+    // ═══════════════════════════════════════════════════════════
+
+    /// Create a new MlsEngine backed by an encrypted database.
+    ///
+    /// # Arguments
+    ///
+    /// * `db_path` — Database location.
+    ///   - **Native**: file path for SQLCipher (e.g. `"path/to/mls.db"`).
+    ///     Use `":memory:"` for an ephemeral in-memory database (destroyed on drop,
+    ///     useful for tests).
+    ///   - **WASM**: IndexedDB database name (e.g. `"mls_account_123"`).
+    ///     `":memory:"` generates a unique random name per instance to match the
+    ///     native ephemeral behavior.
+    ///   - Tip: include an account identifier in the path to isolate data per user
+    ///     (e.g. `"mls_{account_id}.db"` on native, `"mls_{account_id}"` on web).
+    ///
+    /// * `encryption_key` — 32-byte AES-256 key that protects data at rest.
+    ///   The caller is responsible for generating, storing, and providing this key.
+    ///   Recommended pattern: generate a random key on first launch and persist it
+    ///   in platform secure storage (e.g. Keychain on iOS/macOS, Android Keystore,
+    ///   or `flutter_secure_storage`).
+    ///
+    /// Fails if the same database is already open in this process; use
+    /// `create_with_policy` to share the open engine instead.
+    pub async fn create(db_path: String, encryption_key: Vec<u8>) -> Result<MlsEngine, String> {
+        Self::create_with_policy(db_path, encryption_key, EngineOpenPolicy::Fail).await
+    }
+
+    /// Like `create`, with `on_already_open` deciding what happens when the
+    /// same database (by normalized file path on native, by name on web) is
+    /// already open in this process. In-memory databases are never shared.
+    pub async fn create_with_policy(
+        db_path: String,
+        encryption_key: Vec<u8>,
+        on_already_open: EngineOpenPolicy,
+    ) -> Result<MlsEngine, String> {
+        let crypto = std::sync::Arc::new(crate::hybrid_crypto::HybridCrypto::new());
+        let key_digest = crypto
+            .hash(HashType::Sha2_256, &encryption_key)
+            .map_err(|e| format!("Failed to hash encryption key: {:?}", e))?;
+        Self::open_engine(db_path, key_digest, on_already_open, crypto, |db_path| {
+            crate::encrypted_db::EncryptedDb::open(db_path, encryption_key)
+        })
+        .await
+    }
+
+    /// Web only: like `create_with_policy`, with the database key derived
+    /// from a WebAuthn PRF output (e.g. of a passkey) instead of passed in.
+    ///
+    /// The AES-256-GCM key is derived from the 32-byte `prf_output` with
+    /// HKDF-SHA256 as a non-extractable `CryptoKey`, so the derived key
+    /// itself is never exposed as bytes. `prf_output` is not protected the
+    /// same way: it passes through Dart and WASM memory as plain bytes and
+    /// opens the database just like the key, so treat it as secret. The
+    /// WASM copy is zeroized once imported; clearing the Dart copy is up to
+    /// the caller. The same PRF output always opens the same database.
+    /// Fails on native, where SQLCipher needs the raw key.
+    pub async fn create_from_prf(
+        db_path: String,
+        prf_output: Vec<u8>,
+        on_already_open: EngineOpenPolicy,
+    ) -> Result<MlsEngine, String> {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let _ = (db_path, prf_output, on_already_open);
+            Err("create_from_prf is only supported on web".to_string())
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let crypto = std::sync::Arc::new(crate::hybrid_crypto::HybridCrypto::new());
+            // Zeroized on drop, also when an already open engine is shared.
+            let prf_output = zeroize::Zeroizing::new(prf_output);
+            // Domain-separated from raw keys, so `Share` never matches an
+            // engine opened with `create`.
+            let mut digest_input = zeroize::Zeroizing::new(b"prf:".to_vec());
+            digest_input.extend_from_slice(&prf_output);
+            let key_digest = crypto
+                .hash(HashType::Sha2_256, &digest_input)
+                .map_err(|e| format!("Failed to hash PRF output: {:?}", e))?;
+            Self::open_engine(db_path, key_digest, on_already_open, crypto, |db_path| {
+                crate::encrypted_db::EncryptedDb::open_with_prf(db_path, prf_output.to_vec())
+            })
+            .await
+        }
+    }
+
+    /// Open the database with `open` and register the engine, unless
+    /// `on_already_open` resolves to an engine already open on `db_path`.
+    async fn open_engine<F, Fut>(
+        db_path: String,
+        key_digest: Vec<u8>,
+        on_already_open: EngineOpenPolicy,
+        crypto: std::sync::Arc<crate::hybrid_crypto::HybridCrypto>,
+        open: F,
+    ) -> Result<MlsEngine, String>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: std::future::Future<Output = Result<crate::encrypted_db::EncryptedDb, String>>,
+    {
+        let registry_key = open_engine_key(&db_path);
+        let opening = registry_key.as_deref().map(opening_lock);
+        let _opening = match &opening {
+            Some(lock) => Some(lock.lock().await),
+            None => None,
+        };
+        let existing = match &registry_key {
+            Some(path) => Self::already_open(&mut OPEN_ENGINES.lock(), path, &key_digest, &on_already_open)?,
+            None => None,
+        };
+        if let Some(engine) = existing {
+            return Ok(engine);
+        }
+
+        let db = open(db_path).await?;
+        let engine = MlsEngine::with_db(db, crypto);
+        engine.recover_operations().await?;
+        if let Some(path) = registry_key {
+            Self::register_open(&engine.state, path, key_digest);
+        }
+        Ok(engine)
+    }
+
+    /// Register `state` as the engine open on `path` with the key hashing to
+    /// `key_digest`.
+    fn register_open(state: &std::sync::Arc<EngineState>, path: String, key_digest: Vec<u8>) {
+        state.key_digests.lock().insert(path.clone(), key_digest);
+        OPEN_ENGINES.lock().insert(path, OpenEngine { state: std::sync::Arc::downgrade(state) });
+    }
+
+    /// Open an engine on the storage backend a Rust user of this crate
+    /// registered as `backend_name` (see `storage_backend`), e.g. one built
+    /// on RocksDB or a KMS-backed store instead of SQLCipher/IndexedDB.
+    ///
+    /// The backend is responsible for encrypting data at rest. Quarantine is
+    /// not available on custom backends, and `engine_info` reports no path.
+    pub async fn create_with_backend(backend_name: String) -> Result<MlsEngine, String> {
+        let backend = crate::storage_backend::registered_backend(&backend_name)
+            .ok_or_else(|| format!("No storage backend registered as {}", backend_name))?;
+        let crypto = std::sync::Arc::new(crate::hybrid_crypto::HybridCrypto::new());
+        let engine = MlsEngine::with_store(EngineStore::Custom(backend), crypto);
+        engine.recover_operations().await?;
+        Ok(engine)
+    }
+
+    /// Apply `policy` if an engine is open on `path`: a shared handle,
+    /// an error, or `None` if nothing is open there.
+    fn already_open(
+        open: &mut std::collections::BTreeMap<String, OpenEngine>,
+        path: &str,
+        key_digest: &[u8],
+        policy: &EngineOpenPolicy,
+    ) -> Result<Option<MlsEngine>, String> {
+        open.retain(|_, engine| engine.live().is_some());
+        let Some(existing) = open.get(path).and_then(OpenEngine::live) else {
+            return Ok(None);
+        };
+        let same_key = existing
+            .key_digests
+            .lock()
+            .get(path)
+            .is_some_and(|digest| crate::constant_time_eq(digest, key_digest));
+        match policy {
+            EngineOpenPolicy::Fail => Err(format!("MlsEngine already open for {}", path)),
+            EngineOpenPolicy::Share if !same_key => {
+                Err("MlsEngine already open with a different encryption key".to_string())
+            }
+            EngineOpenPolicy::Share => Ok(Some(MlsEngine { state: existing })),
+        }
+    }
+
+    fn with_db(db: crate::encrypted_db::EncryptedDb, crypto: std::sync::Arc<crate::hybrid_crypto::HybridCrypto>) -> MlsEngine {
+        Self::with_store(EngineStore::Db(std::sync::Arc::new(db)), crypto)
+    }
+
+    fn with_store(store: EngineStore, crypto: std::sync::Arc<crate::hybrid_crypto::HybridCrypto>) -> MlsEngine {
+        MlsEngine {
+            state: std::sync::Arc::new(EngineState {
+                db: parking_lot::RwLock::new(Some(store)),
+                epoch_sinks: parking_lot::Mutex::new(Vec::new()),
+                epoch_exporters: parking_lot::RwLock::new(Vec::new()),
+                event_throttle: parking_lot::Mutex::new(EventThrottle::default()),
+                group_subscriptions: parking_lot::Mutex::new(std::collections::BTreeMap::new()),
+                token: std::sync::OnceLock::new(),
+                strict_mode: std::sync::atomic::AtomicBool::new(false),
+                audit_log: std::sync::atomic::AtomicBool::new(true),
+                join_receipts: std::sync::atomic::AtomicBool::new(false),
+                failed_join_retention: std::sync::atomic::AtomicBool::new(false),
+                member_tombstones: std::sync::atomic::AtomicBool::new(false),
+                handshake_expiry: parking_lot::RwLock::new(None),
+                credential_validator: parking_lot::RwLock::new(None),
+                proposal_policy: parking_lot::RwLock::new(None),
+                join_stats: parking_lot::Mutex::new(JoinStats::default()),
+                signers: parking_lot::RwLock::new(std::collections::BTreeMap::new()),
+                crypto,
+                sandboxes: parking_lot::Mutex::new(std::collections::BTreeMap::new()),
+                key_digests: parking_lot::Mutex::new(std::collections::BTreeMap::new()),
+            }),
+        }
+    }
+
+    /// Return a token that another Dart isolate can pass to `from_token` to
+    /// obtain a handle to this same engine.
+    ///
+    /// Handles share the database connection, caches and subscriptions, so
+    /// background isolates don't need to re-open the database. The token is
+    /// stable for the lifetime of the engine and is only valid within the
+    /// current process. It stops resolving once the engine is closed or every
+    /// handle has been dropped.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn to_token(&self) -> Result<u64, String> {
+        if self.is_closed() {
+            return Err("MlsEngine is closed".to_string());
+        }
+        let mut tokens = ENGINE_TOKENS.lock();
+        tokens.retain(|_, state| state.strong_count() > 0);
+        if let Some(token) = self.state.token.get() {
+            return Ok(*token);
+        }
+        let token = *self
+            .state
+            .token
+            .get_or_init(|| NEXT_ENGINE_TOKEN.fetch_add(1, std::sync::atomic::Ordering::Relaxed));
+        tokens.insert(token, std::sync::Arc::downgrade(&self.state));
+        Ok(token)
+    }
+
+    /// Open a handle to the engine identified by a token from `to_token`.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn from_token(token: u64) -> Result<MlsEngine, String> {
+        let state = ENGINE_TOKENS
+            .lock()
+            .get(&token)
+            .and_then(std::sync::Weak::upgrade)
+            .ok_or_else(|| "Unknown or expired engine token".to_string())?;
+        if state.db.read().is_none() {
+            return Err("MlsEngine is closed".to_string());
+        }
+        Ok(MlsEngine { state })
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // INTERNAL HELPERS
+    // ═══════════════════════════════════════════════════════════
+
+    fn db(&self) -> Result<EngineStore, MlsApiError> {
+        self.state.db.read().as_ref().cloned().ok_or_else(|| MlsApiError::new(MlsErrorKind::Closed, "MlsEngine is closed"))
+    }
+
+    fn provider(&self, storage: SnapshotStorageProvider) -> SnapshotOpenMlsProvider {
+        SnapshotOpenMlsProvider::with_crypto(self.state.crypto.clone(), storage)
+    }
+
+    /// Persist a group joined from a Welcome, unless local state for the same
+    /// group id exists and `policy` says to keep it.
+    ///
+    /// The Welcome is processed against global storage only, so without this
+    /// check an old or replayed Welcome would silently overwrite newer state.
+    async fn finish_welcome_join(
+        &self,
+        mut mls_group: MlsGroup,
+        mut provider: SnapshotOpenMlsProvider,
+        config: &MlsGroupConfig,
+        signer: &SignatureKeyPair,
+        policy: ExistingGroupPolicy,
+        key_packages: WelcomeKeyPackages,
+    ) -> Result<WelcomeJoinOutcome, String> {
+        for (hash_ref, bundle) in &key_packages.hidden {
+            provider.storage().write_key_package(hash_ref, bundle)
+                .map_err(|e| format!("Failed to restore key package: {}", e))?;
+        }
+        let key_package_ref = key_packages.chosen;
+        let gid = mls_group.group_id().as_slice().to_vec();
+        let welcome_epoch = mls_group.epoch().as_u64();
+
+        let existing_provider = self.load_for_group(&gid).await?;
+        let existing = MlsGroup::load(existing_provider.storage(), mls_group.group_id())
+            .map_err(|e| format!("Failed to load group: {}", e))?;
+        let already_exists = existing
+            .as_ref()
+            .map(|existing| GroupAlreadyExists { current_epoch: existing.epoch().as_u64(), welcome_epoch });
+        if already_exists.is_some() && matches!(policy, ExistingGroupPolicy::Abort) {
+            return Ok(WelcomeJoinOutcome {
+                group_id: gid,
+                joined: false,
+                already_exists,
+                key_package_ref: None,
+                expired: None,
+                credential_verified: false,
+                join_receipt: None,
+            });
+        }
+        let credential_verified = self.verify_credentials(welcome_credential_checks(&mls_group)?).await?;
+        // Replacing the old state and writing the new one share a
+        // transaction, so a crash cannot leave the group deleted.
+        let mut batches = Vec::new();
+        if let Some(mut existing) = existing {
+            existing.delete(existing_provider.storage())
+                .map_err(|e| format!("Failed to delete group: {}", e))?;
+            batches.push((existing_provider.into_storage().into_updates(), Some(gid.clone())));
+        }
+
+        mark_published_consumed(provider.storage_mut(), key_package_ref.as_deref())?;
+        record_member_joins(&mls_group, &mut provider, &[], true)?;
+        config.store_group_metadata(provider.storage_mut(), &gid)?;
+        let join_receipt = self.join_receipt(&mut mls_group, &mut provider, signer)?;
+
+        let event = self.epoch_event(&mls_group, &provider)?;
+        batches.push((provider.into_storage().into_updates(), Some(gid.clone())));
+        self.save_batches(batches).await?;
+        self.emit_epoch_event(event);
+
+        Ok(WelcomeJoinOutcome {
+            group_id: gid,
+            joined: true,
+            already_exists,
+            key_package_ref,
+            expired: None,
+            credential_verified,
+            join_receipt,
+        })
+    }
+
+    /// Encrypt a join receipt from the freshly joined `group`, if enabled.
+    /// Written to `provider` together with the join, so the receipt's
+    /// generation is never reused.
+    fn join_receipt(
+        &self,
+        group: &mut MlsGroup,
+        provider: &mut SnapshotOpenMlsProvider,
+        signer: &SignatureKeyPair,
+    ) -> Result<Option<Vec<u8>>, String> {
+        if !self.state.join_receipts.load(std::sync::atomic::Ordering::Relaxed) {
+            return Ok(None);
+        }
+        let plaintext = if compression_enabled(group)? {
+            crate::compression::compress(JOIN_RECEIPT_CONTENT).0
+        } else {
+            JOIN_RECEIPT_CONTENT.to_vec()
+        };
+        let msg_out = group.create_message(&*provider, signer, &plaintext)
+            .map_err(|e| format!("Failed to create join receipt: {}", e))?;
+        let receipt = msg_out.tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize join receipt: {}", e))?;
+        record_activity(provider.storage_mut(), group.group_id().as_slice(), GroupActivityKind::Sent)?;
+        Ok(Some(receipt))
+    }
+
+    async fn load_for_group(&self, group_id: &[u8]) -> Result<SnapshotOpenMlsProvider, MlsApiError> {
+        let entries = self.db()?.load_for_group(group_id).await?;
+        Ok(self.provider(SnapshotStorageProvider::from_entries(entries)))
+    }
+
+    async fn load_global(&self) -> Result<SnapshotOpenMlsProvider, MlsApiError> {
+        let entries = self.db()?.load_global().await?;
+        Ok(self.provider(SnapshotStorageProvider::from_entries(entries)))
+    }
+
+    /// Load a snapshot with only the entries under `label` in `group_id`'s
+    /// scope (`None` = global). Enough for reads and writes of engine
+    /// metadata, which do not need the group's MLS state.
+    async fn load_label(&self, label: &[u8], group_id: Option<&[u8]>) -> Result<SnapshotOpenMlsProvider, MlsApiError> {
+        self.load_labels(&[label], group_id).await
+    }
+
+    /// `load_label` for several labels at once.
+    async fn load_labels(
+        &self,
+        labels: &[&[u8]],
+        group_id: Option<&[u8]>,
+    ) -> Result<SnapshotOpenMlsProvider, MlsApiError> {
+        let db = self.db()?;
+        let mut entries = Vec::new();
+        for label in labels {
+            entries.extend(db.load_by_label(label, group_id).await?);
+        }
+        Ok(self.provider(SnapshotStorageProvider::from_entries(entries)))
+    }
+
+    /// `load_label` for a group's metadata, failing like `load_group` if
+    /// the group is not stored. Reads the group's `GroupState` entry
+    /// instead of its whole state to tell.
+    async fn load_group_metadata(&self, group_id: &[u8]) -> Result<SnapshotOpenMlsProvider, MlsApiError> {
+        let provider = self.load_labels(&[GROUP_STATE_LABEL, APP_GROUP_LABEL], Some(group_id)).await?;
+        let stored = provider.storage()
+            .contains_group(&GroupId::from_slice(group_id))
+            .map_err(|e| MlsApiError::new(MlsErrorKind::Storage, format!("Failed to load group: {}", e)))?;
+        if !stored {
+            return Err(MlsApiError::new(MlsErrorKind::NotFound, "No group found in storage"));
+        }
+        Ok(provider)
+    }
+
+    async fn commit(&self, provider: SnapshotOpenMlsProvider, group_id: Option<&[u8]>) -> Result<(), MlsApiError> {
+        let updates = provider.into_storage().into_updates();
+        if updates.upserts.is_empty() && updates.deletes.is_empty() {
+            return Ok(());
+        }
+        self.save_batches(vec![(updates, group_id.map(<[u8]>::to_vec))]).await
+    }
+
+    /// Save sets of updates, each with its own group id, in one transaction,
+    /// then run `after_save` for the groups they touch. Every write of group
+    /// state goes through here, so subscribers see every change.
+    async fn save_batches(&self, batches: Vec<(StorageUpdates, Option<Vec<u8>>)>) -> Result<(), MlsApiError> {
+        let group_ids: std::collections::BTreeSet<Vec<u8>> =
+            batches.iter().filter_map(|(_, group_id)| group_id.clone()).collect();
+        self.db()?.save_updates_batch(batches).await?;
+        self.after_save(group_ids).await;
+        Ok(())
+    }
+
+    /// Post-save hook: deliver `subscribe_group_events` events for the
+    /// saved groups, reading back the state of those with subscribers.
+    /// Failures only cost the events, so they are logged rather than
+    /// failing the operation that already saved.
+    async fn after_save(&self, group_ids: impl IntoIterator<Item = Vec<u8>>) {
+        for group_id in group_ids {
+            if !self.state.group_subscriptions.lock().contains_key(&group_id) {
+                continue;
+            }
+            let observed = match self.load_for_group(&group_id).await {
+                Ok(provider) => ObservedGroupState::load(&provider, &group_id),
+                Err(e) => Err(e.into()),
+            };
+            match observed {
+                Ok(observed) => self.emit_group_events(&group_id, observed),
+                Err(e) => redact::log_warn!("Failed to observe group for group events: {}", e),
+            }
+        }
+    }
+
+    /// Deliver the events leading to `observed` to the group's subscribers,
+    /// dropping closed streams.
+    fn emit_group_events(&self, group_id: &[u8], observed: ObservedGroupState) {
+        self.report_group_events(group_id, Some(observed), false);
+    }
+
+    /// Deliver the events between the state a group's subscribers last saw
+    /// and `observed`, or the state held back by throttling if `None`.
+    ///
+    /// Events of a throttled kind that are not due yet are held back: the
+    /// subscribers' state keeps the old members or epoch, so the next report
+    /// delivers the net change. `force` ignores the throttle intervals.
+    fn report_group_events(&self, group_id: &[u8], observed: Option<ObservedGroupState>, force: bool) {
+        let mut subscriptions = self.state.group_subscriptions.lock();
+        let Some(subscription) = subscriptions.get_mut(group_id) else {
+            return;
+        };
+        let Some(latest) = observed.or_else(|| subscription.held.take()) else {
+            return;
+        };
+        let mut events = subscription.state.events_to(&latest);
+        let mut held_slots = Vec::new();
+        {
+            let now = crate::current_time();
+            let mut throttle = self.state.event_throttle.lock();
+            for slot in [ThrottleSlot::GroupMembers, ThrottleSlot::GroupEpoch] {
+                if events.iter().any(|event| event.throttle_slot() == Some(slot))
+                    && !throttle.release(slot, group_id, now, force)
+                {
+                    throttle.held.insert((slot, group_id.to_vec()));
+                    held_slots.push(slot);
+                }
+            }
+        }
+        events.retain(|event| event.throttle_slot().is_none_or(|slot| !held_slots.contains(&slot)));
+
+        let mut seen = latest.clone();
+        if held_slots.contains(&ThrottleSlot::GroupMembers) {
+            seen.members = std::mem::take(&mut subscription.state.members);
+        }
+        if held_slots.contains(&ThrottleSlot::GroupEpoch) {
+            seen.epoch = subscription.state.epoch;
+        }
+        subscription.state = seen;
+        subscription.held = (!held_slots.is_empty()).then_some(latest);
+        if !events.is_empty() {
+            subscription.sinks.retain(|sink| events.iter().all(|event| sink.add(event.clone()).is_ok()));
+            if subscription.sinks.is_empty() {
+                subscriptions.remove(group_id);
+            }
+        }
+        drop(subscriptions);
+        if !held_slots.is_empty() {
+            self.arm_flush_timer();
+        }
+    }
+
+    /// Deliver the held-back events that are due, or all of them with
+    /// `force`, and re-arm the flush timer for the rest.
+    fn release_held_events(&self, force: bool) {
+        let now = crate::current_time();
+        let (epoch_events, group_ids) = {
+            let mut throttle = self.state.event_throttle.lock();
+            (throttle.take_due_epoch_events(now, force), throttle.take_due_groups(now, force))
+        };
+        self.deliver_epoch_events(epoch_events);
+        for group_id in group_ids {
+            self.report_group_events(&group_id, None, force);
+        }
+        self.arm_flush_timer();
+    }
+
+    /// Schedule `release_held_events` for when the first held-back event is
+    /// due, unless a timer is already running. The timer does not keep the
+    /// engine alive. If no timer can be started, the held events are
+    /// delivered right away instead.
+    fn arm_flush_timer(&self) {
+        let delay = {
+            let mut throttle = self.state.event_throttle.lock();
+            if throttle.timer_armed {
+                return;
+            }
+            let Some(delay) = throttle.next_due(crate::current_time()) else {
+                return;
+            };
+            throttle.timer_armed = true;
+            delay
+        };
+        let state = std::sync::Arc::downgrade(&self.state);
+        let scheduled = crate::utils::run_after(delay, move || {
+            if let Some(state) = state.upgrade() {
+                state.event_throttle.lock().timer_armed = false;
+                MlsEngine { state }.release_held_events(false);
+            }
+        });
+        if let Err(e) = scheduled {
+            redact::log_warn!("Failed to schedule held event delivery: {}", e);
+            self.state.event_throttle.lock().timer_armed = false;
+            self.release_held_events(true);
+        }
+    }
+
+    /// Build the epoch event for `group`, or `None` if nobody is subscribed.
+    ///
+    /// Must be called before `commit()` consumes the provider; deliver the
+    /// result with `emit_epoch_event()` once the commit succeeded.
+    fn epoch_event(
+        &self,
+        group: &MlsGroup,
+        provider: &SnapshotOpenMlsProvider,
+    ) -> Result<Option<EpochAdvancedEvent>, String> {
+        if self.state.epoch_sinks.lock().is_empty() {
+            return Ok(None);
+        }
+        let mut exported_keys = Vec::new();
+        for exporter in self.state.epoch_exporters.read().iter() {
+            let key = group
+                .export_secret(provider.crypto(), &exporter.label, &exporter.context, exporter.key_length as usize)
+                .map_err(|e| format!("Failed to export epoch key '{}': {}", exporter.label, e))?;
+            exported_keys.push(EpochExportedKey { label: exporter.label.clone(), key });
+        }
+        Ok(Some(EpochAdvancedEvent {
+            group_id: group.group_id().as_slice().to_vec(),
+            epoch: group.epoch().as_u64(),
+            exported_keys,
+            coalesced: 1,
+        }))
+    }
+
+    /// Deliver an epoch event to all subscribers, dropping closed streams.
+    /// With throttling enabled the event may be held back and merged with
+    /// later events of the same group.
+    fn emit_epoch_event(&self, event: Option<EpochAdvancedEvent>) {
+        if let Some(event) = event {
+            let ready = self.state.event_throttle.lock().offer(event, crate::current_time());
+            self.deliver_epoch_events(ready);
+            self.arm_flush_timer();
+        }
+    }
+
+    fn deliver_epoch_events(&self, events: Vec<EpochAdvancedEvent>) {
+        if events.is_empty() {
+            return;
+        }
+        self.state.epoch_sinks.lock().retain(|sink| events.iter().all(|event| sink.add(event.clone()).is_ok()));
+    }
+
+    /// Log a deviation from RFC 9420; in strict mode, also fail the operation.
+    fn conformance_deviation(&self, deviation: &str) -> Result<(), MlsApiError> {
+        redact::log_warn!("RFC 9420 deviation: {}", deviation);
+        if self.is_strict_mode() {
+            return Err(MlsApiError::new(MlsErrorKind::PolicyViolation, format!("Strict mode: {}", deviation)));
+        }
+        Ok(())
+    }
+
+    /// Run a join and record its outcome and duration in the join statistics.
+    async fn track_join<T>(
+        &self,
+        kind: JoinKind,
+        join: impl std::future::Future<Output = Result<T, JoinError>>,
+    ) -> Result<T, JoinError> {
+        let started = crate::current_time();
+        let result = join.await;
+        let mut stats = self.state.join_stats.lock();
+        match &result {
+            Ok(_) => {
+                match kind {
+                    JoinKind::Welcome => stats.welcomes_processed += 1,
+                    JoinKind::ExternalCommit => stats.external_commits += 1,
+                }
+                let elapsed = crate::current_time().duration_since(started).unwrap_or_default();
+                stats.total_duration_ms += elapsed.as_millis() as u64;
+            }
+            Err(e) => *stats.failures.entry(e.failure.reason()).or_default() += 1,
+        }
+        result
+    }
+
+    /// Merge our own pending commit and log it in the audit log. Returns the
+    /// proposals the commit covered.
+    fn merge_own_commit(
+        &self,
+        group: &mut MlsGroup,
+        provider: &mut SnapshotOpenMlsProvider,
+    ) -> Result<Vec<MlsCommittedProposal>, String> {
+        let pending = group.pending_commit();
+        let committed = pending.map(committed_proposals).transpose()?.unwrap_or_default();
+        let has_path = pending.is_some_and(|commit| commit.update_path_leaf_node().is_some());
+        let sender = Some(group.own_leaf_index().u32());
+        let merge = pending
+            .map(|commit| self.before_merge(group, provider, commit, sender, true))
+            .transpose()?;
+        let epoch_before = group.epoch();
+        group.merge_pending_commit(&*provider).map_err(|e| format!("Failed to merge pending commit: {}", e))?;
+        if let Some(merge) = merge.filter(|_| group.epoch() != epoch_before) {
+            if has_path {
+                provider.storage_mut()
+                    .write_app_group(group.group_id().as_slice(), OWN_UPDATE_EPOCH, &group.epoch().as_u64())
+                    .map_err(|e| format!("Failed to record own update epoch: {}", e))?;
+            }
+            self.after_merge(group, provider, merge)?;
+        }
+        Ok(committed)
+    }
+
+    /// Capture what `after_merge` needs from `staged_commit` while `group`
+    /// is still in the commit's epoch: merging blanks the removed leaves.
+    fn before_merge(
+        &self,
+        group: &MlsGroup,
+        provider: &SnapshotOpenMlsProvider,
+        staged_commit: &StagedCommit,
+        sender: Option<u32>,
+        own: bool,
+    ) -> Result<PendingMerge, String> {
+        let removed = removed_leaves(staged_commit);
+        Ok(PendingMerge {
+            proposals: audit_proposals(staged_commit),
+            departing: self.departing_members(group, provider, &removed)?,
+            removed,
+            compressed_before: compression_enabled(group)?,
+            joined: own && staged_commit
+                .queued_proposals()
+                .any(|queued| matches!(queued.proposal(), Proposal::ExternalInit(_))),
+            sender,
+            own,
+        })
+    }
+
+    /// Update the per-group records after `group` merged the commit `merge`
+    /// was captured from: compression, search index keys, join epochs,
+    /// former members, activity and the audit log.
+    fn after_merge(
+        &self,
+        group: &MlsGroup,
+        provider: &mut SnapshotOpenMlsProvider,
+        merge: PendingMerge,
+    ) -> Result<(), String> {
+        record_compression_change(group, provider, merge.compressed_before)?;
+        cache_search_index_keys(group, provider)?;
+        record_member_joins(group, provider, &merge.removed, merge.joined)?;
+        record_former_members(group, provider, merge.departing)?;
+        record_activity(provider.storage_mut(), group.group_id().as_slice(), GroupActivityKind::Commit)?;
+        self.append_audit_entry(group, provider, merge.sender, merge.own, merge.proposals)
+    }
+
+    /// Members a commit removes, for `record_former_members`; none unless
+    /// member tombstones are enabled.
+    fn departing_members(
+        &self,
+        group: &MlsGroup,
+        provider: &SnapshotOpenMlsProvider,
+        removed: &[u32],
+    ) -> Result<Vec<MlsMemberInfo>, String> {
+        if !self.is_member_tombstones_enabled() {
+            return Ok(Vec::new());
+        }
+        departing_members(group, provider, removed)
+    }
+
+    /// Append an entry for the commit just merged into `group`, unless the
+    /// audit log is disabled.
+    fn append_audit_entry(
+        &self,
+        group: &MlsGroup,
+        provider: &mut SnapshotOpenMlsProvider,
+        sender: Option<u32>,
+        own: bool,
+        proposals: ProposalSummary,
+    ) -> Result<(), String> {
+        if !self.is_audit_log_enabled() {
+            return Ok(());
+        }
+        let group_id = group.group_id().as_slice();
+        let mut entries: Vec<AuditEntry> = provider.storage()
+            .app_group(group_id, AUDIT_LOG)
+            .map_err(|e| format!("Failed to read audit log: {}", e))?
+            .unwrap_or_default();
+
+        let mut entry = AuditEntry {
+            epoch: group.epoch().as_u64(),
+            commit_hash: group.export_group_context().confirmed_transcript_hash().to_vec(),
+            sender,
+            own,
+            merged_at: unix_now()?,
+            proposals,
+            chain_hash: Vec::new(),
+        };
+        let mut chained = entries.last().map(|last| last.chain_hash.clone()).unwrap_or_default();
+        chained.extend_from_slice(&entry.tbs()?);
+        entry.chain_hash = provider.crypto()
+            .hash(group.ciphersuite().hash_algorithm(), &chained)
+            .map_err(|e| format!("Failed to hash audit entry: {:?}", e))?;
+        entries.push(entry);
+
+        provider.storage_mut()
+            .write_app_group(group_id, AUDIT_LOG, &entries)
+            .map_err(|e| format!("Failed to write audit log: {}", e))
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // CONFORMANCE
+    // ═══════════════════════════════════════════════════════════
+
+    /// Enable or disable strict RFC 9420 conformance mode.
+    ///
+    /// In strict mode the engine refuses `skip_lifetime_validation`, rejects
+    /// commits that would give two members the same credential or add members
+    /// lacking a capability the group relies on, and rejects
+    /// application messages sent as plaintext. Deviations are logged as
+    /// warnings regardless of the mode. Shared by all handles of the engine.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn set_strict_mode(&self, enabled: bool) {
+        self.state.strict_mode.store(enabled, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Whether strict RFC 9420 conformance mode is enabled.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn is_strict_mode(&self) -> bool {
+        self.state.strict_mode.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Abort storage-bound operations that wait longer than `timeout_ms` for
+    /// the database; `None` waits indefinitely (the default).
+    ///
+    /// Best-effort: only waiting for the connection and for locks held by
+    /// other connections is bounded; a query the database is already
+    /// running is not interrupted. A timed-out operation stores nothing, as
+    /// its changes are written in one transaction or not at all. v2
+    /// functions report it as `MlsErrorKind::Timeout`. Shared by all handles
+    /// of the engine.
+    ///
+    /// Not supported on web, where IndexedDB transactions cannot be
+    /// abandoned safely: setting a timeout there fails.
+    pub async fn set_operation_timeout(&self, timeout_ms: Option<u32>) -> Result<(), String> {
+        self.db()?
+            .set_operation_timeout(timeout_ms.map(|ms| std::time::Duration::from_millis(ms as u64)))
+    }
+
+    /// Refuse Welcomes and commits older than `policy` allows; `None`
+    /// disables the check (the default).
+    ///
+    /// Expired commits are reported as `ProcessedMessageType::Expired` and
+    /// expired Welcomes through `WelcomeJoinOutcome::expired`; neither
+    /// changes any state. Shared by all handles of the engine.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn set_handshake_expiry_policy(&self, policy: Option<HandshakeExpiryPolicy>) {
+        *self.state.handshake_expiry.write() = policy;
+    }
+
+    /// The policy set by `set_handshake_expiry_policy`, if any.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn handshake_expiry_policy(&self) -> Option<HandshakeExpiryPolicy> {
+        self.state.handshake_expiry.read().as_ref().map(|policy| HandshakeExpiryPolicy {
+            max_welcome_age_secs: policy.max_welcome_age_secs,
+            max_commit_age_secs: policy.max_commit_age_secs,
+            clock_skew_secs: policy.clock_skew_secs,
+            timestamp_in_aad: policy.timestamp_in_aad,
+        })
+    }
+
+    /// Call `validator` for every credential that first appears in a
+    /// processed commit (added members, changed leaves), in a group joined
+    /// from a Welcome (all other members) or in the key packages passed to
+    /// `add_members`, `add_members_without_update` and `swap_members`, e.g.
+    /// to verify X.509 chains or bind identities to signature keys. Replaces
+    /// any previous validator.
+    ///
+    /// `Reject` refuses the commit or Welcome without applying anything;
+    /// `Unverified` lets it through with `credential_verified: false`.
+    /// Without a validator, results introducing credentials report
+    /// `credential_verified: false`. Shared by all handles of the engine.
+    pub async fn set_credential_validator(
+        &self,
+        validator: impl Fn(MlsCredentialCheck) -> DartFnFuture<MlsCredentialVerdict> + Send + Sync + 'static,
+    ) {
+        *self.state.credential_validator.write() = Some(std::sync::Arc::new(validator));
+    }
+
+    /// Remove the validator set with `set_credential_validator`.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn clear_credential_validator(&self) {
+        *self.state.credential_validator.write() = None;
+    }
+
+    /// Call `policy` for every pending proposal a commit of this engine
+    /// would include by reference. Proposals for which it returns false are
+    /// left out of the commit, e.g. to refuse removals by non-admins or
+    /// unexpected custom proposals. Applies to every call that commits
+    /// pending proposals (`commit_to_pending_proposals`, `self_update`,
+    /// `add_members`, ...), not only `flexible_commit`. Replaces any
+    /// previous policy. Shared by all handles of the engine.
+    ///
+    /// The policy is the engine-wide rule; `MlsProposalFilter` is a
+    /// per-call selection on top of it. `flexible_commit` first applies its
+    /// `proposal_filter`, then asks the policy only about the proposals the
+    /// filter let through: a proposal is committed only if both accept it.
+    pub async fn set_proposal_policy(
+        &self,
+        policy: impl Fn(MlsProposalCheck) -> DartFnFuture<bool> + Send + Sync + 'static,
+    ) {
+        *self.state.proposal_policy.write() = Some(std::sync::Arc::new(policy));
+    }
+
+    /// Remove the policy set with `set_proposal_policy`.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn clear_proposal_policy(&self) {
+        *self.state.proposal_policy.write() = None;
+    }
+
+    /// Run the proposal policy over the pending proposals of `group` that
+    /// pass `filter`. Returns the refs of those it refused, together with
+    /// those changing the group features that the proposer or the local
+    /// member is not authorized to change.
+    async fn refused_proposals(
+        &self,
+        group: &MlsGroup,
+        filter: Option<&MlsProposalFilter>,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        let policy = self.state.proposal_policy.read().clone();
+        let own = Sender::Member(group.own_leaf_index());
+        let mut refused = Vec::new();
+        let mut checks = Vec::new();
+        for queued in group.pending_proposals() {
+            if filter.is_some_and(|filter| !proposal_filter_accepts(filter, queued)) {
+                continue;
+            }
+            let proposal_ref = queued
+                .proposal_reference()
+                .tls_serialize_detached()
+                .map_err(|e| format!("Failed to serialize proposal ref: {}", e))?;
+            if !group_features_proposal_allowed(group, &own, queued)? {
+                refused.push(proposal_ref);
+                continue;
+            }
+            if policy.is_none() {
+                continue;
+            }
+            checks.push(MlsProposalCheck {
+                group_id: group.group_id().as_slice().to_vec(),
+                proposal_ref,
+                proposal_type: proposal_type_of(queued.proposal()),
+                sender_index: match queued.sender() {
+                    Sender::Member(idx) => Some(idx.u32()),
+                    _ => None,
+                },
+                proposal: queued
+                    .proposal()
+                    .tls_serialize_detached()
+                    .map_err(|e| format!("Failed to serialize proposal: {}", e))?,
+            });
+        }
+        let Some(policy) = policy else {
+            return Ok(refused);
+        };
+        for check in checks {
+            let proposal_ref = check.proposal_ref.clone();
+            if !policy(check).await {
+                refused.push(proposal_ref);
+            }
+        }
+        Ok(refused)
+    }
+
+    /// Take the pending proposals the proposal policy refuses out of
+    /// `group`'s proposal store, so that the commit built next leaves them
+    /// out. Run before every commit that can include pending proposals
+    /// except `flexible_commit`, which applies the policy in its builder.
+    async fn drop_refused_proposals(&self, group: &mut MlsGroup, provider: &SnapshotOpenMlsProvider) -> Result<(), String> {
+        for proposal_ref in self.refused_proposals(group, None).await? {
+            let proposal_ref = ProposalRef::tls_deserialize_exact_bytes(&proposal_ref)
+                .map_err(|e| format!("Failed to deserialize proposal ref: {}", e))?;
+            group
+                .remove_pending_proposal(provider.storage(), &proposal_ref)
+                .map_err(|e| format!("Failed to remove pending proposal: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Run the credential validator over `checks`. Returns whether all of
+    /// them were accepted (vacuously true for none).
+    async fn verify_credentials(&self, checks: Vec<MlsCredentialCheck>) -> Result<bool, MlsApiError> {
+        if checks.is_empty() {
+            return Ok(true);
+        }
+        let Some(validator) = self.state.credential_validator.read().clone() else {
+            return Ok(false);
+        };
+        let mut verified = true;
+        for check in checks {
+            let leaf_index = check.leaf_index;
+            match validator(check).await {
+                MlsCredentialVerdict::Accept => {}
+                MlsCredentialVerdict::Unverified => verified = false,
+                MlsCredentialVerdict::Reject => {
+                    let message = match leaf_index {
+                        Some(index) => format!("Credential rejected: leaf {} failed validation", index),
+                        None => "Credential rejected: an added member failed validation".to_string(),
+                    };
+                    return Err(MlsApiError::new(MlsErrorKind::PolicyViolation, message));
+                }
+            }
+        }
+        Ok(verified)
+    }
+
+    /// Check a Welcome sent at `sent_at` against the expiry policy.
+    fn expired_welcome(&self, sent_at: Option<u64>) -> Result<Option<HandshakeExpired>, String> {
+        let policy = self.state.handshake_expiry.read();
+        let Some(policy) = policy.as_ref() else { return Ok(None) };
+        match (sent_at, policy.max_welcome_age_secs) {
+            (Some(sent_at), Some(max_age)) => handshake_expired(sent_at, max_age, policy.clock_skew_secs),
+            _ => Ok(None),
+        }
+    }
+
+    /// Check a processed message against the expiry policy. Only commits
+    /// are checked; they are dated by `sent_at` or, failing that and if the
+    /// policy says so, by their AAD.
+    fn expired_commit(&self, processed: &ProcessedMessage, sent_at: Option<u64>) -> Result<Option<HandshakeExpired>, String> {
+        if !matches!(processed.content(), ProcessedMessageContent::StagedCommitMessage(_)) {
+            return Ok(None);
+        }
+        let policy = self.state.handshake_expiry.read();
+        let Some(policy) = policy.as_ref() else { return Ok(None) };
+        let Some(max_age) = policy.max_commit_age_secs else { return Ok(None) };
+        let sent_at = sent_at.or_else(|| {
+            let stamp = processed.aad().get(..8).filter(|_| policy.timestamp_in_aad)?;
+            Some(u64::from_be_bytes(stamp.try_into().ok()?))
+        });
+        match sent_at {
+            Some(sent_at) => handshake_expired(sent_at, max_age, policy.clock_skew_secs),
+            None => Ok(None),
+        }
+    }
+
+    /// Counters for joins (Welcome and external commit) since the engine was
+    /// created or the statistics were last reset. Shared by all handles of
+    /// the engine and not persisted.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn join_statistics(&self) -> JoinStatistics {
+        let stats = self.state.join_stats.lock();
+        let joins = stats.welcomes_processed + stats.external_commits;
+        JoinStatistics {
+            welcomes_processed: stats.welcomes_processed,
+            external_commits: stats.external_commits,
+            failures: stats
+                .failures
+                .iter()
+                .map(|(reason, count)| JoinFailureCount { reason: reason.to_string(), count: *count })
+                .collect(),
+            average_join_duration_ms: stats.total_duration_ms.checked_div(joins).unwrap_or(0),
+        }
+    }
+
+    /// Reset the counters returned by `join_statistics`.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn reset_join_statistics(&self) {
+        *self.state.join_stats.lock() = JoinStats::default();
+    }
+
+    /// Enable or disable the commit audit log (enabled by default).
+    ///
+    /// While disabled, merged commits are not logged; entries already stored
+    /// are kept until the group is deleted. Shared by all handles of the
+    /// engine.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn set_audit_log_enabled(&self, enabled: bool) {
+        self.state.audit_log.store(enabled, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Whether merged commits are recorded in the audit log.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn is_audit_log_enabled(&self) -> bool {
+        self.state.audit_log.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Enable or disable join receipts (disabled by default).
+    ///
+    /// While enabled, every successful Welcome join also encrypts a small
+    /// application message announcing the join, returned as `join_receipt`
+    /// and persisted in the same storage transaction as the joined group.
+    /// Sending it tells the other members the Welcome was processed before
+    /// the first real message; receivers recognise it with
+    /// `is_join_receipt`. Shared by all handles of the engine.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn set_join_receipts_enabled(&self, enabled: bool) {
+        self.state.join_receipts.store(enabled, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Whether Welcome joins produce join receipts.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn is_join_receipts_enabled(&self) -> bool {
+        self.state.join_receipts.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Eagerly initialize the engine's crypto provider, including the libcrux
+    /// backend used by X-Wing ciphersuites, so the first post-quantum
+    /// operation does not pay the setup cost. Optional: initialization
+    /// otherwise happens lazily.
+    pub fn warm_up_crypto(&self) -> Result<(), String> {
+        self.state.crypto.warm_up().map_err(|e| format!("Failed to initialize crypto provider: {:?}", e))
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // EPOCH EVENTS
+    // ═══════════════════════════════════════════════════════════
+
+    /// Subscribe to epoch changes across all groups of this engine.
+    ///
+    /// An event is emitted after every persisted operation that moves a group
+    /// to a new epoch (merged commits, joins). Dependent subsystems (media
+    /// keys, search index keys) can rotate from the event instead of polling
+    /// `group_epoch`. Use `set_event_throttle` to merge bursts.
+    pub fn subscribe_epoch_events(&self, sink: StreamSink<EpochAdvancedEvent>) -> Result<(), String> {
+        self.state.epoch_sinks.lock().push(sink);
+        Ok(())
+    }
+
+    /// Subscribe to changes of one group: members added or removed, epoch
+    /// changes, this client's removal and newly queued proposals.
+    ///
+    /// Events are derived by comparing the group before and after each
+    /// persisted operation of this engine, including processed messages, so
+    /// UI state can follow the stream instead of polling `group_members`
+    /// and `group_epoch`. Subscribing to a group not joined yet is allowed;
+    /// the join is reported as `EpochAdvanced`.
+    pub async fn subscribe_group_events(
+        &self,
+        group_id_bytes: Vec<u8>,
+        sink: StreamSink<GroupEvent>,
+    ) -> Result<(), String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let state = ObservedGroupState::load(&provider, &group_id_bytes)?;
+        self.state
+            .group_subscriptions
+            .lock()
+            .entry(group_id_bytes)
+            .or_insert_with(|| GroupEventSubscription { sinks: Vec::new(), state, held: None })
+            .sinks
+            .push(sink);
+        Ok(())
+    }
+
+    /// Register an exporter label whose derived key is included in every
+    /// `EpochAdvancedEvent`. Re-registering a label replaces it.
+    ///
+    /// # Security
+    /// Derived keys cross the FFI boundary on every epoch change. Only
+    /// register labels that the application actually needs.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn register_epoch_exporter(&self, label: String, context: Vec<u8>, key_length: u32) {
+        let mut exporters = self.state.epoch_exporters.write();
+        exporters.retain(|e| e.label != label);
+        exporters.push(EpochExporter { label, context, key_length });
+    }
+
+    /// Remove a label registered with `register_epoch_exporter`.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn unregister_epoch_exporter(&self, label: String) {
+        self.state.epoch_exporters.write().retain(|e| e.label != label);
+    }
+
+    /// Limit events of `kind` to one per group every `min_interval_ms`; 0
+    /// disables the limit (the default). Each kind is limited separately,
+    /// so e.g. member changes can be throttled while epoch events are not.
+    ///
+    /// Events arriving sooner are held back and coalesced per group:
+    /// - `EpochAdvancedEvent`s are merged into one event that carries the
+    ///   newest epoch and, in `coalesced`, how many epoch advances it stands
+    ///   for. Keys of the skipped epochs are not exported, so do not
+    ///   throttle if every epoch's exporter keys are needed.
+    /// - `GroupEvent::EpochAdvanced` reports only the newest epoch.
+    /// - `GroupEvent::MemberAdded` / `MemberRemoved` report the net change
+    ///   since the last delivered event: a member added and removed again
+    ///   within the interval is not reported.
+    ///
+    /// Held-back events are delivered by a timer once the interval has
+    /// passed, or earlier by `flush_events`. Changing a limit delivers all
+    /// held-back events.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn set_event_throttle(&self, kind: MlsEventKind, min_interval_ms: u32) {
+        {
+            let mut throttle = self.state.event_throttle.lock();
+            let interval = std::time::Duration::from_millis(min_interval_ms as u64);
+            match kind {
+                MlsEventKind::EpochAdvanced => throttle.epoch_interval = interval,
+                MlsEventKind::MemberChanged => throttle.member_interval = interval,
+            }
+        }
+        self.release_held_events(true);
+    }
+
+    /// Deliver every event held back by `set_event_throttle` now, e.g. at
+    /// the end of a batch of `process_message` calls.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn flush_events(&self) {
+        self.release_held_events(true);
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // SIGNERS
+    // ═══════════════════════════════════════════════════════════
+
+    /// Persist `signer` and register it (see `register_signer`). Returns
+    /// its handle.
+    pub(crate) async fn store_and_register_signer(&self, signer: SignatureKeyPair) -> Result<Vec<u8>, String> {
+        let provider = self.load_label(SIGNATURE_KEY_PAIR_LABEL, None).await?;
+        signer
+            .store(provider.storage())
+            .map_err(|e| format!("Failed to store signer: {}", e))?;
+        self.commit(provider, None).await?;
+
+        let handle = signer.public().to_vec();
+        self.state.signers.write().insert(handle.clone(), signer);
+        Ok(handle)
+    }
+
+    /// The stored signer with `public_key`, as `signer_bytes`, or `None` if
+    /// this engine does not hold it.
+    pub async fn stored_signer(
+        &self,
+        ciphersuite: MlsCiphersuite,
+        public_key: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let cs = ciphersuite_to_native(&ciphersuite);
+        let provider = self.load_label(SIGNATURE_KEY_PAIR_LABEL, None).await?;
+        SignatureKeyPair::read(provider.storage(), &public_key, cs.signature_algorithm())
+            .map(|signer| signer_to_bytes(&signer))
+            .transpose()
+    }
+
+    /// Register a signer with this engine and return its handle: the
+    /// signer's public key.
+    ///
+    /// Every operation taking `signer_bytes` also accepts a registered
+    /// handle, so the private key only has to cross into Rust once. Handles
+    /// live in memory until `unregister_signer` or until the engine is
+    /// dropped; they are not persisted.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn register_signer(&self, signer_bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+        let signer = signer_from_bytes(signer_bytes)?;
+        let handle = signer.public().to_vec();
+        self.state.signers.write().insert(handle.clone(), signer);
+        Ok(handle)
+    }
+
+    /// Forget a signer registered with `register_signer`. Returns whether
+    /// the handle was registered.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn unregister_signer(&self, handle: Vec<u8>) -> bool {
+        self.state.signers.write().remove(&handle).is_some()
+    }
+
+    /// Resolve `signer_bytes`: a handle from `register_signer`, or a
+    /// serialized signer.
+    fn signer(&self, signer_bytes: Vec<u8>) -> Result<SignatureKeyPair, String> {
+        if let Some(signer) = self.state.signers.read().get(&signer_bytes) {
+            return Ok(signer.clone());
+        }
+        signer_from_bytes(signer_bytes)
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // KEY PACKAGES
+    // ═══════════════════════════════════════════════════════════
+
+    pub async fn create_key_package(
+        &self,
+        ciphersuite: MlsCiphersuite,
+        signer_bytes: Vec<u8>,
+        credential_identity: Vec<u8>,
+        signer_public_key: Vec<u8>,
+        credential_bytes: Option<Vec<u8>>,
+        origin: Option<String>,
+    ) -> Result<KeyPackageResult, String> {
+        let cs = ciphersuite_to_native(&ciphersuite);
+        let signer = self.signer(signer_bytes)?;
+        let credential_with_key = build_credential_with_key(
+            &credential_identity, &signer_public_key, credential_bytes.as_deref(),
+        )?;
+
+        let mut provider = self.load_labels(&[KEY_PACKAGE_LABEL, APP_GLOBAL_LABEL], None).await?;
+
+        let key_package_bundle = KeyPackage::builder()
+            .build(cs, &provider, &signer, credential_with_key)
+            .map_err(|e| format!("Failed to create key package: {}", e))?;
+        let result = record_key_package(&mut provider, &key_package_bundle, origin)?;
+
+        self.commit(provider, None).await?;
+
+        Ok(result)
+    }
+
+    pub async fn create_key_package_with_options(
+        &self,
+        ciphersuite: MlsCiphersuite,
+        signer_bytes: Vec<u8>,
+        credential_identity: Vec<u8>,
+        signer_public_key: Vec<u8>,
+        options: KeyPackageOptions,
+        credential_bytes: Option<Vec<u8>>,
+        origin: Option<String>,
+    ) -> Result<KeyPackageResult, String> {
+        let cs = ciphersuite_to_native(&ciphersuite);
+        let signer = self.signer(signer_bytes)?;
+        let credential_with_key = build_credential_with_key(
+            &credential_identity, &signer_public_key, credential_bytes.as_deref(),
+        )?;
+
+        let mut provider = self.load_labels(&[KEY_PACKAGE_LABEL, APP_GLOBAL_LABEL], None).await?;
+        let mut builder = KeyPackage::builder();
+
+        if let Some(lifetime_secs) = options.lifetime_seconds {
+            builder = builder.key_package_lifetime(Lifetime::new(lifetime_secs));
+        }
+        if options.last_resort {
+            builder = builder.mark_as_last_resort();
+        }
+        if let Some(ref caps) = options.capabilities {
+            builder = builder.leaf_node_capabilities(capabilities_to_native(caps)?);
+        }
+        if let Some(ref leaf_exts) = options.leaf_node_extensions {
+            let extensions = Extensions::from_vec(extensions_from_mls(leaf_exts))
+                .map_err(|e| format!("Failed to create leaf node extensions: {}", e))?;
+            builder = builder.leaf_node_extensions(extensions);
+        }
+        if let Some(ref kp_exts) = options.key_package_extensions {
+            let extensions = Extensions::from_vec(extensions_from_mls(kp_exts))
+                .map_err(|e| format!("Failed to create key package extensions: {}", e))?;
+            builder = builder.key_package_extensions(extensions);
+        }
+
+        let key_package_bundle = builder
+            .build(cs, &provider, &signer, credential_with_key)
+            .map_err(|e| format!("Failed to create key package: {}", e))?;
+        let result = record_key_package(&mut provider, &key_package_bundle, origin)?;
+
+        self.commit(provider, None).await?;
+
+        Ok(result)
+    }
+
+    /// Key packages in local storage that were created by this engine, with
+    /// their creation time, origin, ciphersuite, lifetime and credential.
+    /// Key packages consumed by a Welcome or deleted are not listed.
+    pub async fn list_key_packages(&self) -> Result<Vec<KeyPackageInfo>, String> {
+        let provider = self.load_labels(&[KEY_PACKAGE_LABEL, APP_GLOBAL_LABEL], None).await?;
+        let entries: Vec<KeyPackageMeta> = provider.storage()
+            .app_global(KEY_PACKAGE_META)
+            .map_err(|e| format!("Failed to read key package metadata: {}", e))?
+            .unwrap_or_default();
+
+        let mut result = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let hash_ref = openmls::ciphersuite::hash_ref::KeyPackageRef::tls_deserialize_exact_bytes(&entry.key_package_ref)
+                .map_err(|e| format!("Failed to deserialize key package ref: {}", e))?;
+            let bundle: Option<KeyPackageBundle> = provider.storage().key_package(&hash_ref)
+                .map_err(|e| format!("Failed to read key package: {}", e))?;
+            if let Some(bundle) = bundle {
+                let key_package = bundle.key_package();
+                let (not_before, not_after) = key_package_lifetime(key_package)?;
+                result.push(KeyPackageInfo {
+                    key_package_ref: entry.key_package_ref,
+                    created_at: entry.created_at,
+                    origin: entry.origin,
+                    last_resort: key_package.last_resort(),
+                    ciphersuite: native_to_ciphersuite(key_package.ciphersuite())?,
+                    not_before,
+                    not_after,
+                    credential_identity: key_package.leaf_node().credential().serialized_content().to_vec(),
+                });
+            }
+        }
+        Ok(result)
+    }
+
+    /// The listed key packages (see `list_key_packages`) that expire within
+    /// `within_seconds` from now, including those already expired, soonest
+    /// first. Replace these on the delivery service.
+    pub async fn expiring_key_packages(&self, within_seconds: u64) -> Result<Vec<KeyPackageInfo>, String> {
+        let deadline = unix_now()?.saturating_add(within_seconds);
+        let mut expiring: Vec<KeyPackageInfo> = self
+            .list_key_packages()
+            .await?
+            .into_iter()
+            .filter(|info| info.not_after <= deadline)
+            .collect();
+        expiring.sort_by_key(|info| info.not_after);
+        Ok(expiring)
+    }
+
+    /// Bring the stored key packages of `ciphersuite` back to
+    /// `target_count` regular ones plus one last-resort key package.
+    ///
+    /// Expired key packages (of any ciphersuite) are deleted and their refs
+    /// returned for revocation; new ones valid for `lifetime_seconds` are
+    /// created for what is missing, including a last-resort key package if
+    /// no valid one is left. All changes are written in one transaction.
+    /// Run it periodically, e.g. at app start, and sync the delivery service
+    /// with the result.
+    pub async fn maintain_key_packages(
+        &self,
+        ciphersuite: MlsCiphersuite,
+        signer_bytes: Vec<u8>,
+        credential_identity: Vec<u8>,
+        signer_public_key: Vec<u8>,
+        target_count: u32,
+        lifetime_seconds: u64,
+        credential_bytes: Option<Vec<u8>>,
+        origin: Option<String>,
+    ) -> Result<KeyPackageMaintenance, String> {
+        let cs = ciphersuite_to_native(&ciphersuite);
+        let signer = self.signer(signer_bytes)?;
+        let credential_with_key = build_credential_with_key(
+            &credential_identity, &signer_public_key, credential_bytes.as_deref(),
+        )?;
+
+        let mut provider = self.load_labels(&[KEY_PACKAGE_LABEL, APP_GLOBAL_LABEL], None).await?;
+        let entries: Vec<KeyPackageMeta> = provider.storage()
+            .app_global(KEY_PACKAGE_META)
+            .map_err(|e| format!("Failed to read key package metadata: {}", e))?
+            .unwrap_or_default();
+        let now = unix_now()?;
+        let mut kept = Vec::with_capacity(entries.len());
+        let mut revoked_refs = Vec::new();
+        let mut regular = 0u32;
+        let mut has_last_resort = false;
+        for entry in entries {
+            let hash_ref = openmls::ciphersuite::hash_ref::KeyPackageRef::tls_deserialize_exact_bytes(&entry.key_package_ref)
+                .map_err(|e| format!("Failed to deserialize key package ref: {}", e))?;
+            let bundle: Option<KeyPackageBundle> = provider.storage().key_package(&hash_ref)
+                .map_err(|e| format!("Failed to read key package: {}", e))?;
+            let Some(bundle) = bundle else { continue };
+            let key_package = bundle.key_package();
+            if key_package_lifetime(key_package)?.1 <= now {
+                provider.storage().delete_key_package(&hash_ref)
+                    .map_err(|e| format!("Failed to delete key package: {}", e))?;
+                revoked_refs.push(entry.key_package_ref);
+                continue;
+            }
+            if key_package.ciphersuite() == cs {
+                if key_package.last_resort() {
+                    has_last_resort = true;
+                } else {
+                    regular += 1;
+                }
+            }
+            kept.push(entry);
+        }
+        provider.storage_mut()
+            .write_app_global(KEY_PACKAGE_META, &kept)
+            .map_err(|e| format!("Failed to write key package metadata: {}", e))?;
+
+        let missing = target_count.saturating_sub(regular) as usize;
+        let mut new_key_packages = Vec::new();
+        for last_resort in std::iter::repeat_n(false, missing).chain((!has_last_resort).then_some(true)) {
+            let mut builder = KeyPackage::builder().key_package_lifetime(Lifetime::new(lifetime_seconds));
+            if last_resort {
+                builder = builder.mark_as_last_resort();
+            }
+            let bundle = builder
+                .build(cs, &provider, &signer, credential_with_key.clone())
+                .map_err(|e| format!("Failed to create key package: {}", e))?;
+            new_key_packages.push(record_key_package(&mut provider, &bundle, origin.clone())?);
+        }
+
+        self.commit(provider, None).await?;
+        Ok(KeyPackageMaintenance { new_key_packages, revoked_refs })
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // GROUP CREATION
+    // ═══════════════════════════════════════════════════════════
+
+    pub async fn create_group(
+        &self,
+        config: MlsGroupConfig,
+        signer_bytes: Vec<u8>,
+        credential_identity: Vec<u8>,
+        signer_public_key: Vec<u8>,
+        group_id: Option<Vec<u8>>,
+        credential_bytes: Option<Vec<u8>>,
     ) -> Result<CreateGroupResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
+        let signer = self.signer(signer_bytes)?;
+        let credential_with_key = build_credential_with_key(
+            &credential_identity, &signer_public_key, credential_bytes.as_deref(),
+        )?;
+
+        config.native_protocol_version()?;
+        let mut provider = self.load_global().await?;
+        let create_config = config.to_create_config();
+
+        signer
+            .store(provider.storage())
+            .map_err(|e| format!("Failed to store signer: {}", e))?;
+
+        let mls_group = if let Some(gid) = group_id {
+            MlsGroup::new_with_group_id(
+                &provider,
+                &signer,
+                &create_config,
+                GroupId::from_slice(&gid),
+                credential_with_key,
+            )
+        } else {
+            MlsGroup::new(&provider, &signer, &create_config, credential_with_key)
+        };
+
+        let mls_group = mls_group.map_err(|e| format!("Failed to create group: {}", e))?;
+        let gid = mls_group.group_id().as_slice().to_vec();
+        record_member_joins(&mls_group, &mut provider, &[], true)?;
+        config.store_group_metadata(provider.storage_mut(), &gid)?;
+
+        self.commit(provider, Some(&gid)).await?;
+
+        Ok(CreateGroupResult { group_id: gid })
+    }
+
+    pub async fn create_group_with_builder(
+        &self,
+        config: MlsGroupConfig,
+        signer_bytes: Vec<u8>,
+        credential_identity: Vec<u8>,
+        signer_public_key: Vec<u8>,
+        group_id: Option<Vec<u8>>,
+        lifetime_seconds: Option<u64>,
+        group_context_extensions: Option<Vec<MlsExtension>>,
+        leaf_node_extensions: Option<Vec<MlsExtension>>,
+        capabilities: Option<MlsCapabilities>,
+        credential_bytes: Option<Vec<u8>>,
+    ) -> Result<CreateGroupResult, String> {
+        let signer = self.signer(signer_bytes)?;
+        let credential_with_key = build_credential_with_key(
+            &credential_identity, &signer_public_key, credential_bytes.as_deref(),
+        )?;
+
+        config.native_protocol_version()?;
+        let mut provider = self.load_global().await?;
+
+        signer
+            .store(provider.storage())
+            .map_err(|e| format!("Failed to store signer: {}", e))?;
+
+        let cs = super::types::ciphersuite_to_native(&config.ciphersuite);
+        let wf = super::types::wire_format_to_native(&config.wire_format_policy);
+
+        let mut builder = MlsGroup::builder()
+            .ciphersuite(cs)
+            .with_wire_format_policy(wf)
+            .use_ratchet_tree_extension(config.use_ratchet_tree_extension)
+            .max_past_epochs(config.max_past_epochs as usize)
+            .padding_size(config.padding_size as usize)
+            .sender_ratchet_configuration(SenderRatchetConfiguration::new(
+                config.sender_ratchet_max_out_of_order,
+                config.sender_ratchet_max_forward_distance,
+            ));
+
+        if let Some(gid) = group_id {
+            builder = builder.with_group_id(GroupId::from_slice(&gid));
+        }
+        if let Some(lifetime_secs) = lifetime_seconds {
+            builder = builder.lifetime(Lifetime::new(lifetime_secs));
+        }
+        if let Some(ref gc_exts) = group_context_extensions {
+            let extensions = Extensions::from_vec(extensions_from_mls(gc_exts))
+                .map_err(|e| format!("Failed to create group context extensions: {}", e))?;
+            builder = builder.with_group_context_extensions(extensions);
+        }
+        if let Some(ref leaf_exts) = leaf_node_extensions {
+            let extensions = Extensions::from_vec(extensions_from_mls(leaf_exts))
+                .map_err(|e| format!("Failed to create leaf node extensions: {}", e))?;
+            builder = builder
+                .with_leaf_node_extensions(extensions)
+                .map_err(|e| format!("Failed to set leaf node extensions: {}", e))?;
+        }
+        if let Some(ref caps) = capabilities {
+            builder = builder.with_capabilities(capabilities_to_native(caps)?);
+        }
+
+        let mls_group = builder
+            .build(&provider, &signer, credential_with_key)
+            .map_err(|e| format!("Failed to create group: {}", e))?;
+
+        let gid = mls_group.group_id().as_slice().to_vec();
+        record_member_joins(&mls_group, &mut provider, &[], true)?;
+        config.store_group_metadata(provider.storage_mut(), &gid)?;
+
+        self.commit(provider, Some(&gid)).await?;
+
+        Ok(CreateGroupResult { group_id: gid })
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // JOINING A GROUP
+    // ═══════════════════════════════════════════════════════════
+
+    /// Join a group from a Welcome.
+    ///
+    /// With failed join retention enabled, a Welcome that fails to join for
+    /// a reason other than malformed input or existing local state is kept
+    /// for `retry_pending_joins`.
+    pub async fn join_group_from_welcome(
+        &self,
+        config: MlsGroupConfig,
+        welcome_bytes: Vec<u8>,
+        ratchet_tree_bytes: Option<Vec<u8>>,
+        signer_bytes: Vec<u8>,
+    ) -> Result<JoinGroupResult, String> {
+        self.join_from_welcome(config, welcome_bytes, ratchet_tree_bytes, signer_bytes, None).await
+    }
+
+    /// `join_group_from_welcome`, also removing the staged Welcome
+    /// `staged_id` in the transaction that saves the join.
+    async fn join_from_welcome(
+        &self,
+        config: MlsGroupConfig,
+        welcome_bytes: Vec<u8>,
+        ratchet_tree_bytes: Option<Vec<u8>>,
+        signer_bytes: Vec<u8>,
+        staged_id: Option<u64>,
+    ) -> Result<JoinGroupResult, String> {
+        let retained = self
+            .is_failed_join_retention_enabled()
+            .then(|| (welcome_bytes.clone(), ratchet_tree_bytes.clone()));
+        let result = self.welcome_join(&config, welcome_bytes, ratchet_tree_bytes, signer_bytes, staged_id).await;
+        if let (Err(e), Some((payload, ratchet_tree))) = (&result, retained) {
+            self.retain_failed_join(&config, StoredPendingJoinKind::Welcome, payload, ratchet_tree, e).await;
+        }
+        result.map_err(String::from)
+    }
+
+    async fn welcome_join(
+        &self,
+        config: &MlsGroupConfig,
+        welcome_bytes: Vec<u8>,
+        ratchet_tree_bytes: Option<Vec<u8>>,
+        signer_bytes: Vec<u8>,
+        staged_id: Option<u64>,
+    ) -> Result<JoinGroupResult, JoinError> {
+        self.track_join(JoinKind::Welcome, async {
+            let signer = self.signer(signer_bytes)?;
+            let mut provider = self.load_global().await?;
+
+            signer
+                .store(provider.storage())
+                .map_err(|e| format!("Failed to store signer: {}", e))?;
+
+            let welcome_msg = MlsMessageIn::tls_deserialize_exact_bytes(&welcome_bytes)
+                .map_err(|e| JoinError::malformed(format!("Failed to deserialize welcome: {}", e)))?;
+            let welcome = match welcome_msg.extract() {
+                MlsMessageBodyIn::Welcome(w) => w,
+                _ => return Err(JoinError::malformed("Message is not a Welcome")),
+            };
+            let key_packages = select_welcome_key_package(&welcome, &provider, None)?;
+
+            let join_config = config.to_join_config();
+            let ratchet_tree: Option<RatchetTreeIn> = ratchet_tree_bytes
+                .map(|rt_bytes| {
+                    RatchetTreeIn::tls_deserialize_exact_bytes(&rt_bytes)
+                        .map_err(|e| JoinError::malformed(format!("Failed to deserialize ratchet tree: {}", e)))
+                })
+                .transpose()?;
+
+            let staged = StagedWelcome::new_from_welcome(&provider, &join_config, welcome, ratchet_tree)
+                .map_err(|e| JoinError::welcome(&key_packages, format!("Failed to process welcome: {}", e)))?;
+            let mls_group = staged
+                .into_group(&provider)
+                .map_err(|e| JoinError::new(JoinFailure::Validation, format!("Failed to join group from welcome: {}", e)))?;
+            config.check_protocol_version(&mls_group)?;
+            let mut result = JoinGroupResult::for_group(&mls_group)?;
+            if let Some(staged_id) = staged_id {
+                take_staged_welcome(provider.storage_mut(), staged_id)?;
+            }
+
+            let outcome =
+                self.finish_welcome_join(mls_group, provider, &config, &signer, ExistingGroupPolicy::Abort, key_packages).await?;
+            if let Some(existing) = outcome.already_exists {
+                return Err(JoinError::new(
+                    JoinFailure::GroupExists,
+                    format!(
+                        "Group already exists locally at epoch {} (welcome epoch {})",
+                        existing.current_epoch, existing.welcome_epoch
+                    ),
+                ));
+            }
+            result.key_package_ref = outcome.key_package_ref;
+            result.credential_verified = outcome.credential_verified;
+            result.join_receipt = outcome.join_receipt;
+            Ok(result)
+        })
+        .await
+    }
+
+    /// Join every group of an invite bundle built with `bundle_invites`.
+    ///
+    /// Groups are joined in bundle order and reported individually: a group
+    /// that fails, or already has local state, is skipped without affecting
+    /// the others. All joined groups are persisted in one storage
+    /// transaction at the end.
+    pub async fn join_all_from_bundle(
+        &self,
+        config: MlsGroupConfig,
+        bundle_bytes: Vec<u8>,
+        signer_bytes: Vec<u8>,
+    ) -> Result<Vec<BundleJoinResult>, String> {
+        let invites = crate::invite_bundle::decode(&bundle_bytes)?;
+        let signer = self.signer(signer_bytes)?;
+        let mut provider = self.load_global().await?;
+        signer
+            .store(provider.storage())
+            .map_err(|e| format!("Failed to store signer: {}", e))?;
+
+        let mut batches = vec![(provider.storage_mut().checkpoint(), None)];
+        let mut events = Vec::new();
+        let mut results = Vec::with_capacity(invites.len());
+        for invite in invites {
+            let joined = self
+                .track_join(JoinKind::Welcome, self.join_bundled_invite(&config, &invite, &signer, &mut provider))
+                .await;
+            match joined {
+                Ok((result, event)) => {
+                    batches.push((provider.storage_mut().checkpoint(), Some(invite.group_id.clone())));
+                    events.push(event);
+                    results.push(BundleJoinResult { group_id: invite.group_id, joined: Some(result), error: None });
+                }
+                Err(e) => {
+                    provider.storage_mut().rollback();
+                    results.push(BundleJoinResult { group_id: invite.group_id, joined: None, error: Some(e.message) });
+                }
+            }
+        }
+
+        self.save_batches(batches).await?;
+        for event in events {
+            self.emit_epoch_event(event);
+        }
+        Ok(results)
+    }
+
+    /// Join one group of an invite bundle into the shared `provider`.
+    async fn join_bundled_invite(
+        &self,
+        config: &MlsGroupConfig,
+        invite: &crate::invite_bundle::BundledInvite,
+        signer: &SignatureKeyPair,
+        provider: &mut SnapshotOpenMlsProvider,
+    ) -> Result<(JoinGroupResult, Option<EpochAdvancedEvent>), JoinError> {
+        let gid = GroupId::from_slice(&invite.group_id);
+        let joined_earlier = MlsGroup::load(provider.storage(), &gid)
+            .map_err(|e| format!("Failed to load group: {}", e))?
+            .is_some();
+        let existing = MlsGroup::load(self.load_for_group(&invite.group_id).await?.storage(), &gid)
+            .map_err(|e| format!("Failed to load group: {}", e))?;
+        if joined_earlier || existing.is_some() {
+            return Err(JoinError::new(JoinFailure::GroupExists, "Group already exists locally"));
+        }
+
+        let welcome_msg = MlsMessageIn::tls_deserialize_exact_bytes(&invite.welcome)
+            .map_err(|e| JoinError::malformed(format!("Failed to deserialize welcome: {}", e)))?;
+        let welcome = match welcome_msg.extract() {
+            MlsMessageBodyIn::Welcome(w) => w,
+            _ => return Err(JoinError::malformed("Message is not a Welcome")),
+        };
+        let key_packages = select_welcome_key_package(&welcome, provider, None)?;
+
+        let join_config = config.to_join_config();
+        let ratchet_tree: Option<RatchetTreeIn> = invite
+            .ratchet_tree
+            .as_deref()
+            .map(|rt_bytes| {
+                RatchetTreeIn::tls_deserialize_exact_bytes(rt_bytes)
+                    .map_err(|e| JoinError::malformed(format!("Failed to deserialize ratchet tree: {}", e)))
+            })
+            .transpose()?;
+
+        let staged = StagedWelcome::new_from_welcome(&*provider, &join_config, welcome, ratchet_tree)
+            .map_err(|e| JoinError::welcome(&key_packages, format!("Failed to process welcome: {}", e)))?;
+        let mut mls_group = staged
+            .into_group(&*provider)
+            .map_err(|e| JoinError::new(JoinFailure::Validation, format!("Failed to join group from welcome: {}", e)))?;
+        if mls_group.group_id() != &gid {
+            return Err("Welcome is for a different group than its bundle entry".to_string().into());
+        }
+        config.check_protocol_version(&mls_group)?;
+        let credential_verified = self.verify_credentials(welcome_credential_checks(&mls_group)?).await?;
+
+        for (hash_ref, bundle) in &key_packages.hidden {
+            provider.storage().write_key_package(hash_ref, bundle)
+                .map_err(|e| format!("Failed to restore key package: {}", e))?;
+        }
+        mark_published_consumed(provider.storage_mut(), key_packages.chosen.as_deref())?;
+        record_member_joins(&mls_group, provider, &[], true)?;
+        config.store_group_metadata(provider.storage_mut(), &invite.group_id)?;
+
+        let mut result = JoinGroupResult::for_group(&mls_group)?;
+        result.key_package_ref = key_packages.chosen;
+        result.credential_verified = credential_verified;
+        result.join_receipt = self.join_receipt(&mut mls_group, provider, signer)?;
+        let event = self.epoch_event(&mls_group, provider)?;
+        Ok((result, event))
+    }
+
+    pub async fn join_group_from_welcome_with_options(
+        &self,
+        config: MlsGroupConfig,
+        welcome_bytes: Vec<u8>,
+        ratchet_tree_bytes: Option<Vec<u8>>,
+        signer_bytes: Vec<u8>,
+        skip_lifetime_validation: bool,
+    ) -> Result<JoinGroupResult, String> {
+        self.track_join(JoinKind::Welcome, async {
+            let signer = self.signer(signer_bytes)?;
+            let provider = self.load_global().await?;
+
+            signer
+                .store(provider.storage())
+                .map_err(|e| format!("Failed to store signer: {}", e))?;
+
+            let welcome_msg = MlsMessageIn::tls_deserialize_exact_bytes(&welcome_bytes)
+                .map_err(|e| JoinError::malformed(format!("Failed to deserialize welcome: {}", e)))?;
+            let welcome = match welcome_msg.extract() {
+                MlsMessageBodyIn::Welcome(w) => w,
+                _ => return Err(JoinError::malformed("Message is not a Welcome")),
+            };
+            let key_packages = select_welcome_key_package(&welcome, &provider, None)?;
+
+            let join_config = config.to_join_config();
+            let mut join_builder = StagedWelcome::build_from_welcome(&provider, &join_config, welcome)
+                .map_err(|e| JoinError::welcome(&key_packages, format!("Failed to process welcome: {}", e)))?;
+
+            if let Some(rt_bytes) = ratchet_tree_bytes {
+                let ratchet_tree = RatchetTreeIn::tls_deserialize_exact_bytes(&rt_bytes)
+                    .map_err(|e| JoinError::malformed(format!("Failed to deserialize ratchet tree: {}", e)))?;
+                join_builder = join_builder.with_ratchet_tree(ratchet_tree);
+            }
+            if skip_lifetime_validation {
+                self.conformance_deviation("key package lifetime validation skipped").map_err(JoinError::strict_mode)?;
+                join_builder = join_builder.skip_lifetime_validation();
+            }
+
+            let staged = join_builder
+                .build()
+                .map_err(|e| JoinError::new(JoinFailure::Validation, format!("Failed to build staged welcome: {}", e)))?;
+            let mls_group = staged
+                .into_group(&provider)
+                .map_err(|e| JoinError::new(JoinFailure::Validation, format!("Failed to join group from welcome: {}", e)))?;
+            config.check_protocol_version(&mls_group)?;
+            let mut result = JoinGroupResult::for_group(&mls_group)?;
+
+            let outcome =
+                self.finish_welcome_join(mls_group, provider, &config, &signer, ExistingGroupPolicy::Abort, key_packages).await?;
+            if let Some(existing) = outcome.already_exists {
+                return Err(JoinError::new(
+                    JoinFailure::GroupExists,
+                    format!(
+                        "Group already exists locally at epoch {} (welcome epoch {})",
+                        existing.current_epoch, existing.welcome_epoch
+                    ),
+                ));
+            }
+            result.key_package_ref = outcome.key_package_ref;
+            result.credential_verified = outcome.credential_verified;
+            result.join_receipt = outcome.join_receipt;
+            Ok(result)
+        })
+        .await
+        .map_err(String::from)
+    }
+
+    /// Join a group from a Welcome, detecting existing local state for the
+    /// group instead of failing.
+    ///
+    /// If the group is already known, the outcome carries both epochs so the
+    /// caller can tell a legitimate re-add from a replayed old Welcome (a
+    /// welcome epoch at or below the current one). With
+    /// `ExistingGroupPolicy::ReplaceLocalState` the local state is deleted
+    /// and the Welcome is applied.
+    ///
+    /// If the Welcome is encrypted to several local key packages,
+    /// `preferred_key_package_ref` selects the one to consume; by default a
+    /// non-last-resort key package is preferred.
+    ///
+    /// `sent_at` is the Welcome's send time in Unix seconds, checked against
+    /// the handshake expiry policy. An expired Welcome is not joined and
+    /// consumes no key package.
+    pub async fn join_group_from_welcome_with_policy(
+        &self,
+        config: MlsGroupConfig,
+        welcome_bytes: Vec<u8>,
+        ratchet_tree_bytes: Option<Vec<u8>>,
+        signer_bytes: Vec<u8>,
+        on_existing: ExistingGroupPolicy,
+        preferred_key_package_ref: Option<Vec<u8>>,
+        sent_at: Option<u64>,
+    ) -> Result<WelcomeJoinOutcome, String> {
+        self.track_join(JoinKind::Welcome, async {
+            let signer = self.signer(signer_bytes)?;
+            let provider = self.load_global().await?;
+
+            signer
+                .store(provider.storage())
+                .map_err(|e| format!("Failed to store signer: {}", e))?;
+
+            let welcome_msg = MlsMessageIn::tls_deserialize_exact_bytes(&welcome_bytes)
+                .map_err(|e| JoinError::malformed(format!("Failed to deserialize welcome: {}", e)))?;
+            let welcome = match welcome_msg.extract() {
+                MlsMessageBodyIn::Welcome(w) => w,
+                _ => return Err(JoinError::malformed("Message is not a Welcome")),
+            };
+            let key_packages = select_welcome_key_package(&welcome, &provider, preferred_key_package_ref.as_deref())?;
+
+            let join_config = config.to_join_config();
+            let ratchet_tree: Option<RatchetTreeIn> = ratchet_tree_bytes
+                .map(|rt_bytes| {
+                    RatchetTreeIn::tls_deserialize_exact_bytes(&rt_bytes)
+                        .map_err(|e| JoinError::malformed(format!("Failed to deserialize ratchet tree: {}", e)))
+                })
+                .transpose()?;
+
+            let staged = StagedWelcome::new_from_welcome(&provider, &join_config, welcome, ratchet_tree)
+                .map_err(|e| JoinError::welcome(&key_packages, format!("Failed to process welcome: {}", e)))?;
+            let mls_group = staged
+                .into_group(&provider)
+                .map_err(|e| JoinError::new(JoinFailure::Validation, format!("Failed to join group from welcome: {}", e)))?;
+            config.check_protocol_version(&mls_group)?;
+            if let Some(expired) = self.expired_welcome(sent_at)? {
+                return Ok(WelcomeJoinOutcome {
+                    group_id: mls_group.group_id().as_slice().to_vec(),
+                    joined: false,
+                    already_exists: None,
+                    key_package_ref: None,
+                    expired: Some(expired),
+                    credential_verified: false,
+                    join_receipt: None,
+                });
+            }
+
+            Ok(self.finish_welcome_join(mls_group, provider, &config, &signer, on_existing, key_packages).await?)
+        })
+        .await
+        .map_err(String::from)
+    }
+
+    pub async fn inspect_welcome(
+        &self,
+        config: MlsGroupConfig,
+        welcome_bytes: Vec<u8>,
+    ) -> Result<WelcomeInspectResult, String> {
+        let provider = self.load_global().await?;
+
+        let welcome_msg = MlsMessageIn::tls_deserialize_exact_bytes(&welcome_bytes)
+            .map_err(|e| format!("Failed to deserialize welcome: {}", e))?;
+        let welcome = match welcome_msg.extract() {
+            MlsMessageBodyIn::Welcome(w) => w,
+            _ => return Err("Message is not a Welcome".to_string()),
+        };
+
+        let join_config = config.to_join_config();
+        let processed = ProcessedWelcome::new_from_welcome(&provider, &join_config, welcome)
+            .map_err(|e| format!("Failed to process welcome: {}", e))?;
+
+        let vgi = processed.unverified_group_info();
+        let group_info_bytes = vgi.tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let parts = parse_group_info(&group_info_bytes)?;
+        let ratchet_tree = parse_extension_list(&parts.extensions)?
+            .into_iter()
+            .find(|ext| ext.extension_type == RATCHET_TREE_EXTENSION_TYPE);
+        let members = match &ratchet_tree {
+            Some(ext) => crate::tree_view::parse_ratchet_tree(&ext.data)?
+                .into_iter()
+                .step_by(2)
+                .enumerate()
+                .filter_map(|(leaf_index, node)| match node {
+                    Some(crate::tree_view::TreeNode::Leaf { credential_type, identity, .. }) => {
+                        Some(WelcomeMemberPreview { leaf_index: leaf_index as u32, credential_type, identity })
+                    }
+                    _ => None,
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        Ok(WelcomeInspectResult {
+            group_id: vgi.group_id().as_slice().to_vec(),
+            ciphersuite: native_to_ciphersuite(vgi.ciphersuite())?,
+            psk_count: processed.psks().len() as u32,
+            psks: processed.psks().iter().map(psk_id_info).collect(),
+            epoch: vgi.epoch().as_u64(),
+            group_context_extensions: parse_extension_list(&parts.context_extensions)?,
+            has_ratchet_tree: ratchet_tree.is_some(),
+            member_count: ratchet_tree.is_some().then_some(members.len() as u32),
+            members,
+        })
+    }
+
+    /// Keep a Welcome to join later, e.g. once the user accepted the
+    /// invitation.
+    ///
+    /// The Welcome is checked to be addressed to one of our key packages
+    /// and previewed like `inspect_welcome`, but no group is created and
+    /// the key package is not consumed. Finish with
+    /// `complete_staged_welcome` or `discard_staged_welcome`.
+    pub async fn stage_welcome(
+        &self,
+        config: MlsGroupConfig,
+        welcome_bytes: Vec<u8>,
+        ratchet_tree_bytes: Option<Vec<u8>>,
+    ) -> Result<StageWelcomeResult, String> {
+        let preview = self.inspect_welcome(config, welcome_bytes.clone()).await?;
+        let staged_at = unix_now()?;
+
+        let mut provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        let mut entries = read_staged_welcomes(provider.storage())?;
+        let staged_id = entries.iter().map(|entry| entry.id + 1).max().unwrap_or(1);
+        entries.push(StoredStagedWelcome {
+            id: staged_id,
+            group_id: preview.group_id.clone(),
+            welcome: welcome_bytes,
+            ratchet_tree: ratchet_tree_bytes,
+            staged_at,
+        });
+        write_staged_welcomes(provider.storage_mut(), &entries)?;
+        self.commit(provider, None).await?;
+
+        Ok(StageWelcomeResult { staged_id, preview })
+    }
+
+    /// Welcomes kept by `stage_welcome`, oldest first.
+    pub async fn staged_welcomes(&self) -> Result<Vec<MlsStagedWelcome>, String> {
+        let provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        Ok(read_staged_welcomes(provider.storage())?
+            .into_iter()
+            .map(|entry| MlsStagedWelcome { staged_id: entry.id, group_id: entry.group_id, staged_at: entry.staged_at })
+            .collect())
+    }
+
+    /// Join the group of a staged Welcome, as `join_group_from_welcome`.
+    ///
+    /// The staged Welcome is removed in the transaction that saves the
+    /// join; if joining fails it is kept, so it can be retried or
+    /// discarded.
+    pub async fn complete_staged_welcome(
+        &self,
+        staged_id: u64,
+        config: MlsGroupConfig,
+        signer_bytes: Vec<u8>,
+    ) -> Result<JoinGroupResult, String> {
+        let provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        let entry = read_staged_welcomes(provider.storage())?
+            .into_iter()
+            .find(|entry| entry.id == staged_id)
+            .ok_or_else(|| format!("No staged welcome with id {}", staged_id))?;
+
+        self.join_from_welcome(config, entry.welcome, entry.ratchet_tree, signer_bytes, Some(staged_id)).await
+    }
+
+    /// Decline a staged Welcome: forget it and delete the key packages it
+    /// was addressed to, except last-resort ones. Returns whether it
+    /// existed.
+    pub async fn discard_staged_welcome(&self, staged_id: u64) -> Result<bool, String> {
+        // Forgetting the Welcome and deleting its key packages are saved
+        // together, so a crash cannot leave only one of them done.
+        let mut provider = self.load_labels(&[KEY_PACKAGE_LABEL, APP_GLOBAL_LABEL], None).await?;
+        let Some(entry) = take_staged_welcome(provider.storage_mut(), staged_id)? else {
+            return Ok(false);
+        };
+
+        let welcome = match MlsMessageIn::tls_deserialize_exact_bytes(&entry.welcome)
+            .map_err(|e| format!("Failed to deserialize welcome: {}", e))?
+            .extract()
+        {
+            MlsMessageBodyIn::Welcome(w) => w,
+            _ => return Err("Message is not a Welcome".to_string()),
+        };
+        for ref_bytes in welcome_key_package_refs(&welcome)? {
+            let hash_ref = openmls::ciphersuite::hash_ref::KeyPackageRef::tls_deserialize_exact_bytes(&ref_bytes)
+                .map_err(|e| format!("Failed to deserialize key package ref: {}", e))?;
+            let bundle: Option<KeyPackageBundle> = provider.storage().key_package(&hash_ref)
+                .map_err(|e| format!("Failed to read key package: {}", e))?;
+            if bundle.is_some_and(|bundle| !bundle.key_package().last_resort()) {
+                forget_key_package(&mut provider, &ref_bytes)?;
+            }
+        }
+        self.commit(provider, None).await?;
+        Ok(true)
+    }
+
+    /// Join a group by external commit.
+    ///
+    /// With failed join retention enabled, a GroupInfo that fails to join
+    /// for a reason other than malformed input is kept for
+    /// `retry_pending_joins`, together with the credential.
+    pub async fn join_group_external_commit(
+        &self,
+        config: MlsGroupConfig,
+        group_info_bytes: Vec<u8>,
+        ratchet_tree_bytes: Option<Vec<u8>>,
+        signer_bytes: Vec<u8>,
+        credential_identity: Vec<u8>,
+        signer_public_key: Vec<u8>,
+        credential_bytes: Option<Vec<u8>>,
+    ) -> Result<ExternalJoinResult, String> {
+        let retained = self.is_failed_join_retention_enabled().then(|| {
+            let kind = StoredPendingJoinKind::ExternalCommit {
+                credential_identity: credential_identity.clone(),
+                signer_public_key: signer_public_key.clone(),
+                credential_bytes: credential_bytes.clone(),
+            };
+            (kind, group_info_bytes.clone(), ratchet_tree_bytes.clone())
+        });
+        let result = self
+            .external_commit_join(
+                &config,
+                group_info_bytes,
+                ratchet_tree_bytes,
+                signer_bytes,
+                credential_identity,
+                signer_public_key,
+                credential_bytes,
+            )
+            .await;
+        if let (Err(e), Some((kind, payload, ratchet_tree))) = (&result, retained) {
+            self.retain_failed_join(&config, kind, payload, ratchet_tree, e).await;
+        }
+        result.map_err(String::from)
+    }
+
+    #[allow(deprecated)]
+    async fn external_commit_join(
+        &self,
+        config: &MlsGroupConfig,
+        group_info_bytes: Vec<u8>,
+        ratchet_tree_bytes: Option<Vec<u8>>,
+        signer_bytes: Vec<u8>,
+        credential_identity: Vec<u8>,
+        signer_public_key: Vec<u8>,
+        credential_bytes: Option<Vec<u8>>,
+    ) -> Result<ExternalJoinResult, JoinError> {
+        self.track_join(JoinKind::ExternalCommit, async {
+            let (provider, event, join) = self
+                .stage_external_commit_join(
+                    config,
+                    group_info_bytes,
+                    ratchet_tree_bytes,
+                    signer_bytes,
+                    credential_identity,
+                    signer_public_key,
+                    credential_bytes,
+                )
+                .await?;
+            self.commit(provider, Some(&join.group_id)).await?;
+            self.emit_epoch_event(event);
+            Ok(join)
+        })
+        .await
+    }
+
+    /// Join by external commit without saving: returns the provider holding
+    /// the new group, its epoch event and the result to hand out once the
+    /// provider is saved.
+    async fn stage_external_commit_join(
+        &self,
+        config: &MlsGroupConfig,
+        group_info_bytes: Vec<u8>,
+        ratchet_tree_bytes: Option<Vec<u8>>,
+        signer_bytes: Vec<u8>,
+        credential_identity: Vec<u8>,
+        signer_public_key: Vec<u8>,
+        credential_bytes: Option<Vec<u8>>,
+    ) -> Result<(SnapshotOpenMlsProvider, Option<EpochAdvancedEvent>, ExternalJoinResult), JoinError> {
+        let signer = self.signer(signer_bytes)?;
         let credential_with_key = build_credential_with_key(
             &credential_identity, &signer_public_key, credential_bytes.as_deref(),
         )?;
 
-        let provider = self.load_global().await?;
-        let create_config = config.to_create_config();
+        let mut provider = self.load_global().await?;
+        signer
+            .store(provider.storage())
+            .map_err(|e| format!("Failed to store signer: {}", e))?;
+
+        let gi_msg = MlsMessageIn::tls_deserialize_exact_bytes(&group_info_bytes)
+            .map_err(|e| JoinError::malformed(format!("Failed to deserialize group info: {}", e)))?;
+        let verifiable_group_info = match gi_msg.extract() {
+            MlsMessageBodyIn::GroupInfo(gi) => gi,
+            _ => return Err(JoinError::malformed("Not a GroupInfo message")),
+        };
+        let join_config = config.to_join_config();
+
+        let ratchet_tree: Option<RatchetTreeIn> = ratchet_tree_bytes
+            .map(|rt_bytes| {
+                RatchetTreeIn::tls_deserialize_exact_bytes(&rt_bytes)
+                    .map_err(|e| JoinError::malformed(format!("Failed to deserialize ratchet tree: {}", e)))
+            })
+            .transpose()?;
+
+        let (mls_group, commit_out, group_info_opt) = MlsGroup::join_by_external_commit(
+            &provider, &signer, ratchet_tree, verifiable_group_info, &join_config, None, None, &[], credential_with_key,
+        )
+        .map_err(|e| JoinError::new(JoinFailure::Validation, format!("Failed to join group via external commit: {}", e)))?;
+        config.check_protocol_version(&mls_group)?;
+
+        let gid = mls_group.group_id().as_slice().to_vec();
+        config.store_group_metadata(provider.storage_mut(), &gid)?;
+        let commit_bytes = commit_out
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize commit: {}", e))?;
+        let gi_bytes = group_info_opt
+            .map(|gi| gi.tls_serialize_detached())
+            .transpose()
+            .map_err(|e| format!("Failed to serialize group info: {}", e))?;
+
+        let event = self.epoch_event(&mls_group, &provider)?;
+        Ok((provider, event, ExternalJoinResult {
+            group_id: gid,
+            commit: commit_bytes,
+            group_info: gi_bytes,
+        }))
+    }
+
+    /// Join a group by external commit, with options.
+    ///
+    /// `leaf_node_capabilities` and `leaf_node_extensions` set the
+    /// capabilities and extensions (e.g. application_id) of the new leaf;
+    /// unset values use the defaults.
+    pub async fn join_group_external_commit_v2(
+        &self,
+        config: MlsGroupConfig,
+        group_info_bytes: Vec<u8>,
+        ratchet_tree_bytes: Option<Vec<u8>>,
+        signer_bytes: Vec<u8>,
+        credential_identity: Vec<u8>,
+        signer_public_key: Vec<u8>,
+        aad: Option<Vec<u8>>,
+        skip_lifetime_validation: bool,
+        credential_bytes: Option<Vec<u8>>,
+        leaf_node_capabilities: Option<MlsCapabilities>,
+        leaf_node_extensions: Option<Vec<MlsExtension>>,
+    ) -> Result<ExternalJoinResult, String> {
+        self.track_join(JoinKind::ExternalCommit, async {
+            let leaf_node_params = leaf_node_parameters(leaf_node_capabilities, leaf_node_extensions)?;
+            let signer = self.signer(signer_bytes)?;
+            let credential_with_key = build_credential_with_key(
+                &credential_identity, &signer_public_key, credential_bytes.as_deref(),
+            )?;
+
+            let mut provider = self.load_global().await?;
+            signer
+                .store(provider.storage())
+                .map_err(|e| format!("Failed to store signer: {}", e))?;
+
+            let gi_msg = MlsMessageIn::tls_deserialize_exact_bytes(&group_info_bytes)
+                .map_err(|e| JoinError::malformed(format!("Failed to deserialize group info: {}", e)))?;
+            let verifiable_group_info = match gi_msg.extract() {
+                MlsMessageBodyIn::GroupInfo(gi) => gi,
+                _ => return Err(JoinError::malformed("Not a GroupInfo message")),
+            };
+            let join_config = config.to_join_config();
+
+            let mut ext_builder = MlsGroup::external_commit_builder().with_config(join_config);
+            if let Some(rt_bytes) = ratchet_tree_bytes {
+                let ratchet_tree = RatchetTreeIn::tls_deserialize_exact_bytes(&rt_bytes)
+                    .map_err(|e| JoinError::malformed(format!("Failed to deserialize ratchet tree: {}", e)))?;
+                ext_builder = ext_builder.with_ratchet_tree(ratchet_tree);
+            }
+            if let Some(aad_bytes) = aad {
+                ext_builder = ext_builder.with_aad(aad_bytes);
+            }
+            if skip_lifetime_validation {
+                self.conformance_deviation("key package lifetime validation skipped").map_err(JoinError::strict_mode)?;
+                ext_builder = ext_builder.skip_lifetime_validation();
+            }
+
+            let commit_builder = ext_builder
+                .build_group(&provider, verifiable_group_info, credential_with_key)
+                .map_err(|e| JoinError::new(JoinFailure::Validation, format!("Failed to build external commit group: {}", e)))?
+                .leaf_node_parameters(leaf_node_params);
+            let commit_builder = commit_builder
+                .load_psks(provider.storage())
+                .map_err(|e| format!("Failed to load PSKs: {}", e))?;
+            let commit_builder = commit_builder
+                .build(provider.rand(), provider.crypto(), &signer, |_| true)
+                .map_err(|e| JoinError::new(JoinFailure::Validation, format!("Failed to build external commit: {}", e)))?;
+            let (mls_group, bundle) = commit_builder
+                .finalize(&provider)
+                .map_err(|e| format!("Failed to finalize external commit: {}", e))?;
+            config.check_protocol_version(&mls_group)?;
+
+            let gid = mls_group.group_id().as_slice().to_vec();
+            config.store_group_metadata(provider.storage_mut(), &gid)?;
+            let (commit_out, _welcome_opt, gi_opt) = bundle.into_messages();
+            let commit_bytes = commit_out
+                .tls_serialize_detached()
+                .map_err(|e| format!("Failed to serialize commit: {}", e))?;
+            let gi_bytes = gi_opt
+                .map(|gi| gi.tls_serialize_detached())
+                .transpose()
+                .map_err(|e| format!("Failed to serialize group info: {}", e))?;
+
+            let event = self.epoch_event(&mls_group, &provider)?;
+            self.commit(provider, Some(&gid)).await?;
+            self.emit_epoch_event(event);
+
+            Ok(ExternalJoinResult {
+                group_id: gid,
+                commit: commit_bytes,
+                group_info: gi_bytes,
+            })
+        })
+        .await
+        .map_err(String::from)
+    }
+
+    /// Join a group by external commit using a payload from
+    /// `create_invite_payload`.
+    ///
+    /// Rejects the payload if it has expired, if its signature does not
+    /// verify under the embedded inviter key, if that key does not belong to
+    /// the member who signed the GroupInfo, or — when
+    /// `expected_inviter_key` is given — if the inviter is not that key.
+    /// `ratchet_tree_bytes` is used when the payload carries no tree.
+    pub async fn join_from_invite_payload(
+        &self,
+        config: MlsGroupConfig,
+        payload_bytes: Vec<u8>,
+        ratchet_tree_bytes: Option<Vec<u8>>,
+        signer_bytes: Vec<u8>,
+        credential_identity: Vec<u8>,
+        signer_public_key: Vec<u8>,
+        credential_bytes: Option<Vec<u8>>,
+        expected_inviter_key: Option<Vec<u8>>,
+    ) -> Result<ExternalJoinResult, String> {
+        self.track_join(JoinKind::ExternalCommit, async {
+            let (payload, signature) = InvitePayload::decode(&payload_bytes).map_err(JoinError::malformed)?;
+
+            let now = unix_now()?;
+            if now > payload.not_after {
+                return Err(JoinError::new(JoinFailure::Expired, "Invite payload has expired"));
+            }
+            if expected_inviter_key.is_some_and(|key| key != payload.inviter_key) {
+                return Err("Invite payload was not issued by the expected inviter".to_string().into());
+            }
+
+            if payload.group_info.len() < 4 {
+                return Err(JoinError::malformed("Malformed group info: truncated"));
+            }
+            // MLSMessage header: version (u16) || wire_format (u16)
+            let parts = parse_group_info(&payload.group_info[4..]).map_err(JoinError::malformed)?;
+            let ciphersuite = Ciphersuite::try_from(parts.ciphersuite)
+                .map_err(|e| format!("Invalid ciphersuite {}: {}", parts.ciphersuite, e))?;
+            let signer_index = parts.signer;
+
+            let signer = self.signer(signer_bytes)?;
+            let credential_with_key = build_credential_with_key(
+                &credential_identity, &signer_public_key, credential_bytes.as_deref(),
+            )?;
+
+            let mut provider = self.load_global().await?;
+            provider.crypto()
+                .verify_signature(
+                    ciphersuite.signature_algorithm(),
+                    &invite_sign_content(&payload.tbs()?)?,
+                    &payload.inviter_key,
+                    &signature,
+                )
+                .map_err(|_| JoinError::new(JoinFailure::InvalidSignature, "Invalid invite payload signature"))?;
+            signer
+                .store(provider.storage())
+                .map_err(|e| format!("Failed to store signer: {}", e))?;
+
+            let gi_msg = MlsMessageIn::tls_deserialize_exact_bytes(&payload.group_info)
+                .map_err(|e| JoinError::malformed(format!("Failed to deserialize group info: {}", e)))?;
+            let verifiable_group_info = match gi_msg.extract() {
+                MlsMessageBodyIn::GroupInfo(gi) => gi,
+                _ => return Err(JoinError::malformed("Not a GroupInfo message")),
+            };
+            let join_config = config.to_join_config();
+
+            let mut ext_builder = MlsGroup::external_commit_builder().with_config(join_config);
+            if let Some(rt_bytes) = payload.ratchet_tree.or(ratchet_tree_bytes) {
+                let ratchet_tree = RatchetTreeIn::tls_deserialize_exact_bytes(&rt_bytes)
+                    .map_err(|e| JoinError::malformed(format!("Failed to deserialize ratchet tree: {}", e)))?;
+                ext_builder = ext_builder.with_ratchet_tree(ratchet_tree);
+            }
+
+            let commit_builder = ext_builder
+                .build_group(&provider, verifiable_group_info, credential_with_key)
+                .map_err(|e| JoinError::new(JoinFailure::Validation, format!("Failed to build external commit group: {}", e)))?;
+            let commit_builder = commit_builder
+                .load_psks(provider.storage())
+                .map_err(|e| format!("Failed to load PSKs: {}", e))?;
+            let commit_builder = commit_builder
+                .build(provider.rand(), provider.crypto(), &signer, |_| true)
+                .map_err(|e| JoinError::new(JoinFailure::Validation, format!("Failed to build external commit: {}", e)))?;
+            let (mls_group, bundle) = commit_builder
+                .finalize(&provider)
+                .map_err(|e| format!("Failed to finalize external commit: {}", e))?;
+            config.check_protocol_version(&mls_group)?;
+
+            // The GroupInfo signature was verified against the tree during the
+            // join; bind the invite to that same member.
+            let inviter_matches = mls_group
+                .member_at(LeafNodeIndex::new(signer_index))
+                .is_some_and(|m| m.signature_key == payload.inviter_key);
+            if !inviter_matches {
+                return Err("Invite payload signer is not the GroupInfo signer".to_string().into());
+            }
+
+            let gid = mls_group.group_id().as_slice().to_vec();
+            config.store_group_metadata(provider.storage_mut(), &gid)?;
+            let (commit_out, _welcome_opt, gi_opt) = bundle.into_messages();
+            let commit_bytes = commit_out
+                .tls_serialize_detached()
+                .map_err(|e| format!("Failed to serialize commit: {}", e))?;
+            let gi_bytes = gi_opt
+                .map(|gi| gi.tls_serialize_detached())
+                .transpose()
+                .map_err(|e| format!("Failed to serialize group info: {}", e))?;
+
+            let event = self.epoch_event(&mls_group, &provider)?;
+            self.commit(provider, Some(&gid)).await?;
+            self.emit_epoch_event(event);
+
+            Ok(ExternalJoinResult {
+                group_id: gid,
+                commit: commit_bytes,
+                group_info: gi_bytes,
+            })
+        })
+        .await
+        .map_err(String::from)
+    }
+
+    /// Keep failed joins for `retry_pending_joins` (disabled by default).
+    ///
+    /// While enabled, `join_group_from_welcome` and
+    /// `join_group_external_commit` store the payload of a join that failed
+    /// for a possibly transient reason (e.g. a missing PSK or ratchet tree,
+    /// or clock skew), so it can be retried once the cause is fixed without
+    /// the delivery service sending it again. Only the last failure per group
+    /// is kept. Payloads are stored in the engine's encrypted database.
+    /// Shared by all handles of the engine.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn set_failed_join_retention(&self, enabled: bool) {
+        self.state.failed_join_retention.store(enabled, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Whether failed joins are kept for `retry_pending_joins`.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn is_failed_join_retention_enabled(&self) -> bool {
+        self.state.failed_join_retention.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Failed joins kept for `retry_pending_joins`, oldest first.
+    pub async fn pending_joins(&self) -> Result<Vec<PendingJoin>, String> {
+        let provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        Ok(read_pending_joins(provider.storage())?
+            .into_iter()
+            .map(|entry| PendingJoin {
+                id: entry.id,
+                kind: match entry.kind {
+                    StoredPendingJoinKind::Welcome => MlsPendingJoinKind::Welcome,
+                    StoredPendingJoinKind::ExternalCommit { .. } => MlsPendingJoinKind::ExternalCommit,
+                },
+                group_id: entry.group_id,
+                error: entry.error,
+                failed_at: entry.failed_at,
+                attempts: entry.attempts,
+            })
+            .collect())
+    }
+
+    /// Retry every pending join with `config` and `signer_bytes`.
+    ///
+    /// Joins that succeed, or fail for a permanent reason, are removed;
+    /// the others stay pending with the new error. Retries do not need
+    /// failed join retention to be enabled.
+    pub async fn retry_pending_joins(
+        &self,
+        config: MlsGroupConfig,
+        signer_bytes: Vec<u8>,
+    ) -> Result<Vec<PendingJoinRetry>, String> {
+        let provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        let entries = read_pending_joins(provider.storage())?;
+        let mut results = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let id = entry.id;
+            let mut result =
+                PendingJoinRetry { id, group_id: entry.group_id, joined: None, external_join: None, error: None };
+            let outcome = match entry.kind {
+                StoredPendingJoinKind::Welcome => self
+                    .welcome_join(&config, entry.payload, entry.ratchet_tree, signer_bytes.clone())
+                    .await
+                    .map(|joined| result.joined = Some(joined)),
+                StoredPendingJoinKind::ExternalCommit { credential_identity, signer_public_key, credential_bytes } => self
+                    .external_commit_join(
+                        &config,
+                        entry.payload,
+                        entry.ratchet_tree,
+                        signer_bytes.clone(),
+                        credential_identity,
+                        signer_public_key,
+                        credential_bytes,
+                    )
+                    .await
+                    .map(|joined| result.external_join = Some(joined)),
+            };
+            match outcome {
+                Err(e) if !e.failure.is_permanent() => {
+                    let failed_at = unix_now()?;
+                    self.update_pending_joins(|entries| {
+                        if let Some(entry) = entries.iter_mut().find(|entry| entry.id == id) {
+                            entry.error = e.message.clone();
+                            entry.failed_at = failed_at;
+                            entry.attempts += 1;
+                        }
+                    })
+                    .await?;
+                    result.error = Some(e.message);
+                }
+                outcome => {
+                    self.update_pending_joins(|entries| entries.retain(|entry| entry.id != id)).await?;
+                    result.error = outcome.err().map(String::from);
+                }
+            }
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// Forget the pending join `id`. Returns whether it existed.
+    pub async fn discard_pending_join(&self, id: u64) -> Result<bool, String> {
+        let mut found = false;
+        self.update_pending_joins(|entries| {
+            let before = entries.len();
+            entries.retain(|entry| entry.id != id);
+            found = entries.len() != before;
+        })
+        .await?;
+        Ok(found)
+    }
+
+    /// Keep a failed join for `retry_pending_joins`, replacing an earlier
+    /// failure for the same group. Errors are logged, so the caller reports
+    /// the join's own error.
+    async fn retain_failed_join(
+        &self,
+        config: &MlsGroupConfig,
+        kind: StoredPendingJoinKind,
+        payload: Vec<u8>,
+        ratchet_tree: Option<Vec<u8>>,
+        error: &JoinError,
+    ) {
+        if error.failure.is_permanent() {
+            return;
+        }
+        let retained = async {
+            let group_id = match kind {
+                StoredPendingJoinKind::Welcome => welcome_group_id(&self.load_global().await?, config, &payload),
+                StoredPendingJoinKind::ExternalCommit { .. } => group_info_group_id(&payload),
+            };
+            let failed_at = unix_now()?;
+            self.update_pending_joins(|entries| {
+                let previous = entries.iter().position(|entry| match (&entry.group_id, &group_id) {
+                    (Some(stored), Some(group_id)) => stored == group_id,
+                    _ => entry.payload == payload,
+                });
+                let attempts = previous.map(|index| entries.remove(index).attempts).unwrap_or(0) + 1;
+                let id = entries.iter().map(|entry| entry.id + 1).max().unwrap_or(1);
+                entries.push(StoredPendingJoin {
+                    id,
+                    kind,
+                    group_id,
+                    payload,
+                    ratchet_tree,
+                    error: error.message.clone(),
+                    failed_at,
+                    attempts,
+                });
+            })
+            .await
+        };
+        if let Err(e) = retained.await {
+            redact::log_warn!("Failed to keep failed join for retry: {}", e);
+        }
+    }
+
+    async fn update_pending_joins(&self, update: impl FnOnce(&mut Vec<StoredPendingJoin>)) -> Result<(), String> {
+        let mut provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        let mut entries = read_pending_joins(provider.storage())?;
+        update(&mut entries);
+        write_pending_joins(provider.storage_mut(), &entries)?;
+        self.commit(provider, None).await?;
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // STATE QUERIES (read-only)
+    // ═══════════════════════════════════════════════════════════
+
+    pub async fn group_id(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        Ok(group.group_id().as_slice().to_vec())
+    }
+
+    pub async fn group_epoch(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<u64, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        Ok(group.epoch().as_u64())
+    }
+
+    pub async fn group_is_active(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<bool, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        Ok(group.is_active())
+    }
+
+    pub async fn group_members(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Vec<MlsMemberInfo>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        group.members().map(|member| member_info(&provider, &member)).collect()
+    }
+
+    /// The epoch each current member was added in, by leaf index.
+    ///
+    /// Epochs are recorded from the commits this device merges, so members
+    /// that were in the group before this device joined are reported with
+    /// `exact: false` and the epoch we joined in as an upper bound. Useful for showing where a
+    /// member's readable history starts and for deciding which epochs to
+    /// share with them.
+    pub async fn member_join_epochs(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Vec<MemberJoinEpoch>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        let stored: Vec<StoredMemberJoin> = provider.storage()
+            .app_group(&group_id_bytes, MEMBER_JOIN_EPOCHS)
+            .map_err(|e| format!("Failed to read member join epochs: {}", e))?
+            .unwrap_or_default();
+        let epoch = group.epoch().as_u64();
+        let mut joins = Vec::new();
+        for member in group.members() {
+            let leaf_index = member.index.u32();
+            let member_handle = member_info(&provider, &member)?.member_handle;
+            joins.push(match stored.iter().find(|entry| entry.leaf_index == leaf_index) {
+                Some(entry) => MemberJoinEpoch { leaf_index, member_handle, epoch: entry.epoch, exact: entry.exact },
+                // Member of a group that predates join tracking.
+                None => MemberJoinEpoch { leaf_index, member_handle, epoch, exact: false },
+            });
+        }
+        Ok(joins)
+    }
+
+    /// Keep tombstones of removed members for `former_members` (disabled by
+    /// default).
+    ///
+    /// While enabled, merging a commit that removes members stores their
+    /// credential and member handle, so the authors of older messages stay
+    /// resolvable after their leaves are blanked or reused. Shared by all
+    /// handles of the engine.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn set_member_tombstones_enabled(&self, enabled: bool) {
+        self.state.member_tombstones.store(enabled, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Whether removed members are kept for `former_members`.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn is_member_tombstones_enabled(&self) -> bool {
+        self.state.member_tombstones.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Members removed from the group while member tombstones were enabled,
+    /// in order of removal.
+    pub async fn former_members(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Vec<FormerMember>, String> {
+        let provider = self.load_label(APP_GROUP_LABEL, Some(&group_id_bytes)).await?;
+        let stored: Vec<StoredFormerMember> = provider.storage()
+            .app_group(&group_id_bytes, FORMER_MEMBERS)
+            .map_err(|e| format!("Failed to read former members: {}", e))?
+            .unwrap_or_default();
+        Ok(stored
+            .into_iter()
+            .map(|entry| FormerMember {
+                leaf_index: entry.leaf_index,
+                credential: entry.credential,
+                signature_key: entry.signature_key,
+                member_handle: entry.member_handle,
+                removed_epoch: entry.removed_epoch,
+                removed_at: entry.removed_at,
+            })
+            .collect())
+    }
+
+    /// Messages sent and received and commits merged in the group, counted
+    /// by this device since it joined (or since the counters were added).
+    ///
+    /// Counters are updated in the same transaction as the operation they
+    /// count. Useful for sorting conversations by activity and for finding
+    /// unused groups.
+    pub async fn group_activity(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<GroupActivity, String> {
+        let provider = self.load_group_metadata(&group_id_bytes).await?;
+        let activity: StoredGroupActivity = provider.storage()
+            .app_group(&group_id_bytes, GROUP_ACTIVITY)
+            .map_err(|e| format!("Failed to read group activity: {}", e))?
+            .unwrap_or_default();
+        Ok(GroupActivity {
+            messages_sent: activity.messages_sent,
+            messages_received: activity.messages_received,
+            commits_merged: activity.commits_merged,
+            last_activity: activity.last_activity,
+        })
+    }
+
+    pub async fn group_ciphersuite(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<MlsCiphersuite, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        native_to_ciphersuite(group.ciphersuite())
+    }
+
+    /// The MLS protocol version of the group (1 = MLS 1.0).
+    pub async fn protocol_version(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<u16, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        Ok(protocol_version_value(group.version()))
+    }
+
+    pub async fn group_own_index(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<u32, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        Ok(group.own_leaf_index().u32())
+    }
 
-        signer
-            .store(provider.storage())
-            .map_err(|e| format!("Failed to store signer: {}", e))?;
+    pub async fn group_credential(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        let credential = group.credential().map_err(|e| format!("Failed to get credential: {}", e))?;
+        credential
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize credential: {}", e))
+    }
 
-        let mls_group = if let Some(gid) = group_id {
-            MlsGroup::new_with_group_id(
-                &provider,
-                &signer,
-                &create_config,
-                GroupId::from_slice(&gid),
-                credential_with_key,
+    pub async fn group_extensions(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        group
+            .extensions()
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize extensions: {}", e))
+    }
+
+    pub async fn group_pending_proposals(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Vec<MlsPendingProposalInfo>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        let mut proposals = Vec::new();
+        for qp in group.pending_proposals() {
+            let proposal_type = proposal_type_of(qp.proposal());
+            let sender_index = match qp.sender() {
+                Sender::Member(idx) => Some(idx.u32()),
+                _ => None,
+            };
+            let sender_handle = sender_handle(&group, &provider, qp.sender(), None)?;
+            let proposal_ref = qp.proposal_reference()
+                .tls_serialize_detached()
+                .map_err(|e| format!("Failed to serialize proposal ref: {}", e))?;
+            let group_context_extensions = match qp.proposal() {
+                Proposal::GroupContextExtensions(gce) => {
+                    Some(group_context_extensions_change(group.extensions(), gce.extensions())?)
+                }
+                _ => None,
+            };
+            proposals.push(MlsPendingProposalInfo {
+                proposal_type,
+                proposal_ref,
+                sender_index,
+                sender_handle,
+                group_context_extensions,
+            });
+        }
+        Ok(proposals)
+    }
+
+    pub async fn group_has_pending_proposals(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<bool, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        Ok(group.has_pending_proposals())
+    }
+
+    pub async fn group_member_at(
+        &self,
+        group_id_bytes: Vec<u8>,
+        leaf_index: u32,
+    ) -> Result<Option<MlsMemberInfo>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        match group.member_at(LeafNodeIndex::new(leaf_index)) {
+            Some(member) => Ok(Some(member_info(&provider, &member)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The current member with the given handle (see `MlsMemberInfo`),
+    /// including its leaf index in the current epoch. `None` if no member
+    /// has that handle, e.g. because it left or rotated its credential or
+    /// signature key.
+    pub async fn resolve_member(
+        &self,
+        group_id_bytes: Vec<u8>,
+        member_handle: Vec<u8>,
+    ) -> Result<Option<MlsMemberInfo>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        for member in group.members() {
+            let info = member_info(&provider, &member)?;
+            if info.member_handle == member_handle {
+                return Ok(Some(info));
+            }
+        }
+        Ok(None)
+    }
+
+    pub async fn group_member_leaf_index(
+        &self,
+        group_id_bytes: Vec<u8>,
+        credential_bytes: Vec<u8>,
+    ) -> Result<Option<u32>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        let credential = Credential::tls_deserialize_exact_bytes(&credential_bytes)
+            .map_err(|e| format!("Failed to deserialize credential: {}", e))?;
+        Ok(group.member_leaf_index(&credential).map(|idx| idx.u32()))
+    }
+
+    /// Validate a GroupInfo received from the delivery service and cache it.
+    ///
+    /// The GroupInfo must belong to this group, must not be older than the
+    /// cached one or the local epoch, and must be signed by the member at its
+    /// `signer` leaf index in the local tree. Accepted GroupInfos can later be
+    /// retrieved with `cached_group_info`, e.g. to assist external joins.
+    pub async fn update_cached_group_info(
+        &self,
+        group_id_bytes: Vec<u8>,
+        group_info_bytes: Vec<u8>,
+    ) -> Result<GroupInfoUpdateOutcome, String> {
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+
+        let gi_msg = MlsMessageIn::tls_deserialize_exact_bytes(&group_info_bytes)
+            .map_err(|e| format!("Failed to deserialize group info: {}", e))?;
+        if !matches!(gi_msg.extract(), MlsMessageBodyIn::GroupInfo(_)) {
+            return Err("Not a GroupInfo message".to_string());
+        }
+        // MLSMessage header: version (u16) || wire_format (u16)
+        let parts = parse_group_info(&group_info_bytes[4..])?;
+
+        if parts.group_id != group_id_bytes {
+            return Ok(GroupInfoUpdateOutcome::WrongGroup);
+        }
+        let cached: Option<CachedGroupInfo> = provider.storage()
+            .app_group(&group_id_bytes, CACHED_GROUP_INFO)
+            .map_err(|e| format!("Failed to read cached group info: {}", e))?;
+        let min_epoch = cached.map_or(0, |c| c.epoch).max(group.epoch().as_u64());
+        if parts.epoch < min_epoch {
+            return Ok(GroupInfoUpdateOutcome::StaleEpoch);
+        }
+        let Some(signer) = group.member_at(LeafNodeIndex::new(parts.signer)) else {
+            return Ok(GroupInfoUpdateOutcome::UnknownSigner);
+        };
+
+        // SignWithLabel(., "GroupInfoTBS", tbs): sign SignContent { label, content }
+        let mut sign_content = Vec::new();
+        tls_codec::VLBytes::new(b"MLS 1.0 GroupInfoTBS".to_vec())
+            .tls_serialize(&mut sign_content)
+            .map_err(|e| format!("Failed to encode signature content: {}", e))?;
+        tls_codec::VLBytes::new(parts.tbs.to_vec())
+            .tls_serialize(&mut sign_content)
+            .map_err(|e| format!("Failed to encode signature content: {}", e))?;
+        let verified = provider.crypto()
+            .verify_signature(
+                group.ciphersuite().signature_algorithm(),
+                &sign_content,
+                &signer.signature_key,
+                &parts.signature,
             )
-        } else {
-            MlsGroup::new(&provider, &signer, &create_config, credential_with_key)
+            .is_ok();
+        if !verified {
+            return Ok(GroupInfoUpdateOutcome::InvalidSignature);
+        }
+
+        let entry = CachedGroupInfo { epoch: parts.epoch, group_info: group_info_bytes };
+        provider.storage_mut()
+            .write_app_group(&group_id_bytes, CACHED_GROUP_INFO, &entry)
+            .map_err(|e| format!("Failed to write cached group info: {}", e))?;
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        Ok(GroupInfoUpdateOutcome::Accepted)
+    }
+
+    /// The last GroupInfo accepted by `update_cached_group_info`, if any.
+    pub async fn cached_group_info(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let provider = self.load_label(APP_GROUP_LABEL, Some(&group_id_bytes)).await?;
+        let cached: Option<CachedGroupInfo> = provider.storage()
+            .app_group(&group_id_bytes, CACHED_GROUP_INFO)
+            .map_err(|e| format!("Failed to read cached group info: {}", e))?;
+        Ok(cached.map(|c| c.group_info))
+    }
+
+    /// Returns the group's feature flags, or `None` if never set.
+    pub async fn group_features(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Option<MlsGroupFeatures>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        group_features_from_extensions(group.extensions())
+    }
+
+    /// The group's topic, as last committed with `set_group_topic`.
+    pub async fn group_topic(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Option<String>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        group_topic_from_extensions(group.extensions())
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // EXPORT OPERATIONS (read-only)
+    // ═══════════════════════════════════════════════════════════
+
+    /// The TLS-serialized ratchet tree. Returned without a copy across the
+    /// bridge; trees of large groups run to megabytes.
+    pub async fn export_ratchet_tree(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<ZeroCopyBuffer<Vec<u8>>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        group
+            .export_ratchet_tree()
+            .tls_serialize_detached()
+            .map(ZeroCopyBuffer)
+            .map_err(|e| format!("Failed to serialize ratchet tree: {}", e))
+    }
+
+    /// Render the group's ratchet tree as a Graphviz DOT graph for debugging.
+    ///
+    /// Leaves are labelled with their credential identity, parents with their
+    /// unmerged leaves, and blank nodes are drawn dashed.
+    pub async fn export_tree_dot(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<String, String> {
+        let tree_bytes = self.export_ratchet_tree(group_id_bytes).await?;
+        Ok(crate::tree_view::to_dot(&crate::tree_view::parse_ratchet_tree(&tree_bytes.0)?))
+    }
+
+    /// Render the group's ratchet tree as a compact indented ASCII tree.
+    pub async fn export_tree_ascii(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<String, String> {
+        let tree_bytes = self.export_ratchet_tree(group_id_bytes).await?;
+        Ok(crate::tree_view::to_ascii(&crate::tree_view::parse_ratchet_tree(&tree_bytes.0)?))
+    }
+
+    pub async fn export_group_info(
+        &self,
+        group_id_bytes: Vec<u8>,
+        signer_bytes: Vec<u8>,
+    ) -> Result<ZeroCopyBuffer<Vec<u8>>, String> {
+        let signer = self.signer(signer_bytes)?;
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        let group_info = group
+            .export_group_info(provider.crypto(), &signer, true)
+            .map_err(|e| format!("Failed to export group info: {}", e))?;
+        group_info
+            .tls_serialize_detached()
+            .map(ZeroCopyBuffer)
+            .map_err(|e| format!("Failed to serialize group info: {}", e))
+    }
+
+    /// Create a signed invitation payload (e.g. for a QR code or link) that
+    /// lets its holder join the group by external commit until it expires.
+    ///
+    /// The payload bundles a fresh GroupInfo, optionally the ratchet tree, an
+    /// expiry `expires_in_seconds` from now and the inviter's signature key,
+    /// all signed by `signer`, which must be this member's signature key.
+    /// Without the tree, joiners must obtain it separately.
+    pub async fn create_invite_payload(
+        &self,
+        group_id_bytes: Vec<u8>,
+        signer_bytes: Vec<u8>,
+        expires_in_seconds: u64,
+        include_ratchet_tree: bool,
+    ) -> Result<Vec<u8>, String> {
+        let signer = self.signer(signer_bytes)?;
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+
+        let own_leaf = group
+            .own_leaf_node()
+            .ok_or_else(|| "No own leaf node (group not active?)".to_string())?;
+        if own_leaf.signature_key().as_slice() != signer.public() {
+            return Err("Signer does not match own leaf node".to_string());
+        }
+
+        let group_info = group
+            .export_group_info(provider.crypto(), &signer, false)
+            .map_err(|e| format!("Failed to export group info: {}", e))?
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let ratchet_tree = include_ratchet_tree
+            .then(|| group.export_ratchet_tree().tls_serialize_detached())
+            .transpose()
+            .map_err(|e| format!("Failed to serialize ratchet tree: {}", e))?;
+        let now = unix_now()?;
+
+        let payload = InvitePayload {
+            not_after: now.saturating_add(expires_in_seconds),
+            inviter_key: signer.public().to_vec(),
+            group_info,
+            ratchet_tree,
+        };
+        let signature = openmls_traits::signatures::Signer::sign(&signer, &invite_sign_content(&payload.tbs()?)?)
+            .map_err(|e| format!("Failed to sign invite payload: {:?}", e))?;
+        payload.encode(&signature)
+    }
+
+    /// Key for a client-side encrypted search index over this group's
+    /// messages, stable for `epoch_window` consecutive epochs.
+    ///
+    /// Epochs are grouped into windows `epoch / epoch_window`. The key of a
+    /// window is derived in the window's first epoch, so every member that
+    /// was in the group then gets the same key:
+    ///
+    /// ```text
+    /// key = MLS-Exporter("search index key",
+    ///                    uint64(epoch_window) || uint64(window), 32)
+    /// ```
+    ///
+    /// The engine derives keys after each merged commit for every window
+    /// size this was called with, and keeps the latest. Returns `None` if
+    /// the current window's key is not available: on the first call for a
+    /// window size outside a window's first epoch, or if we joined after the
+    /// window started. Keep the keys of past windows with the index
+    /// segments they encrypt; they cannot be derived again.
+    pub async fn search_index_key(
+        &self,
+        group_id_bytes: Vec<u8>,
+        epoch_window: u64,
+    ) -> Result<Option<SearchIndexKey>, String> {
+        if epoch_window == 0 {
+            return Err("epoch_window must be at least 1".to_string());
+        }
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        let mut entries: Vec<StoredSearchIndexKey> = provider.storage()
+            .app_group(&group_id_bytes, SEARCH_INDEX_KEYS)
+            .map_err(|e| format!("Failed to read search index keys: {}", e))?
+            .unwrap_or_default();
+        let window = group.epoch().as_u64() / epoch_window;
+
+        if !entries.iter().any(|entry| entry.epoch_window == epoch_window) {
+            let at_window_start = group.epoch().as_u64() % epoch_window == 0;
+            entries.push(StoredSearchIndexKey {
+                epoch_window,
+                window: at_window_start.then_some(window),
+                key: if at_window_start {
+                    derive_search_index_key(&group, &provider, epoch_window)?
+                } else {
+                    Vec::new()
+                },
+            });
+            provider.storage_mut()
+                .write_app_group(&group_id_bytes, SEARCH_INDEX_KEYS, &entries)
+                .map_err(|e| format!("Failed to write search index keys: {}", e))?;
+            self.commit(provider, Some(&group_id_bytes)).await?;
+        }
+
+        Ok(entries
+            .into_iter()
+            .find(|entry| entry.epoch_window == epoch_window && entry.window == Some(window))
+            .map(|entry| SearchIndexKey { window, first_epoch: window * epoch_window, key: entry.key }))
+    }
+
+    /// Export the group's commit audit log from `from_epoch` on, as JSON
+    /// signed by `signer` (this member's signature key).
+    ///
+    /// The schema and hash chain are documented in the `audit_log` module.
+    /// The log covers commits merged since we joined; it is empty while the
+    /// audit log is disabled.
+    pub async fn export_audit_log(
+        &self,
+        group_id_bytes: Vec<u8>,
+        from_epoch: u64,
+        signer_bytes: Vec<u8>,
+    ) -> Result<AuditLogExport, String> {
+        let signer = self.signer(signer_bytes)?;
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+
+        let own_leaf = group
+            .own_leaf_node()
+            .ok_or_else(|| "No own leaf node (group not active?)".to_string())?;
+        if own_leaf.signature_key().as_slice() != signer.public() {
+            return Err("Signer does not match own leaf node".to_string());
+        }
+
+        let entries: Vec<AuditEntry> = provider.storage()
+            .app_group(&group_id_bytes, AUDIT_LOG)
+            .map_err(|e| format!("Failed to read audit log: {}", e))?
+            .unwrap_or_default();
+        let start = entries.partition_point(|entry| entry.epoch < from_epoch);
+        let prev_chain_hash = start
+            .checked_sub(1)
+            .map(|i| entries[i].chain_hash.clone())
+            .unwrap_or_default();
+
+        let json = crate::audit_log::export_json(&group_id_bytes, &prev_chain_hash, &entries[start..])?;
+        let signature = openmls_traits::signatures::Signer::sign(
+            &signer,
+            &crate::audit_log::sign_content(json.as_bytes())?,
+        )
+        .map_err(|e| format!("Failed to sign audit log: {:?}", e))?;
+        Ok(AuditLogExport { json, signature, signer_public_key: signer.public().to_vec() })
+    }
+
+    pub async fn export_secret(
+        &self,
+        group_id_bytes: Vec<u8>,
+        label: String,
+        context: Vec<u8>,
+        key_length: u32,
+    ) -> Result<Vec<u8>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        group
+            .export_secret(provider.crypto(), &label, &context, key_length as usize)
+            .map_err(|e| format!("Failed to export secret: {}", e))
+    }
+
+    /// Wrap the key of a client-side encrypted attachment for the current
+    /// epoch's members.
+    ///
+    /// The key is HPKE-sealed to a key pair derived from the epoch's exporter
+    /// secret and bound to `message_context` (e.g. the id of the message
+    /// carrying the attachment), so members added in later epochs cannot
+    /// unwrap it. See `crate::attachment` for the format.
+    pub async fn wrap_attachment_key(
+        &self,
+        group_id_bytes: Vec<u8>,
+        attachment_key: Vec<u8>,
+        message_context: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        let key_pair = attachment_key_pair(&group, &provider)?;
+        let ciphertext = provider
+            .crypto()
+            .hpke_seal(
+                group.ciphersuite().hpke_config(),
+                &key_pair.public,
+                &crate::attachment::encrypt_context(&message_context)?,
+                &[],
+                &attachment_key,
+            )
+            .map_err(|e| format!("Failed to wrap attachment key: {:?}", e))?;
+        crate::attachment::WrappedAttachmentKey {
+            epoch: group.epoch().as_u64(),
+            kem_output: ciphertext.kem_output.as_slice().to_vec(),
+            ciphertext: ciphertext.ciphertext.as_slice().to_vec(),
+        }
+        .encode()
+    }
+
+    /// Unwrap a key produced by `wrap_attachment_key` with the same
+    /// `message_context`.
+    ///
+    /// Only possible while the group is still in the epoch the key was
+    /// wrapped in: exporter secrets of past epochs are not retained, so
+    /// unwrap attachment keys on receipt and keep them with the message.
+    pub async fn unwrap_attachment_key(
+        &self,
+        group_id_bytes: Vec<u8>,
+        wrapped_key: Vec<u8>,
+        message_context: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        let wrapped = crate::attachment::WrappedAttachmentKey::decode(&wrapped_key)?;
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        if wrapped.epoch != group.epoch().as_u64() {
+            return Err(format!(
+                "Attachment key was wrapped in epoch {}, group is in epoch {}",
+                wrapped.epoch,
+                group.epoch().as_u64()
+            ));
+        }
+        let key_pair = attachment_key_pair(&group, &provider)?;
+        let ciphertext = HpkeCiphertext {
+            kem_output: wrapped.kem_output.into(),
+            ciphertext: wrapped.ciphertext.into(),
         };
+        provider
+            .crypto()
+            .hpke_open(
+                group.ciphersuite().hpke_config(),
+                &ciphertext,
+                &key_pair.private,
+                &crate::attachment::encrypt_context(&message_context)?,
+                &[],
+            )
+            .map_err(|e| format!("Failed to unwrap attachment key: {:?}", e))
+    }
 
-        let mls_group = mls_group.map_err(|e| format!("Failed to create group: {}", e))?;
-        let gid = mls_group.group_id().as_slice().to_vec();
+    pub async fn export_group_context(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<MlsGroupContextInfo, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        group_context_info(group.export_group_context())
+    }
 
-        self.commit(provider, Some(&gid)).await?;
+    /// Canonical serialization of the group's public state: group context
+    /// (with its extensions), confirmation tag and ratchet tree, behind a
+    /// version header. The format is documented in the `public_state`
+    /// module.
+    ///
+    /// Every member exports identical bytes for the same epoch, so a
+    /// delivery service can mirror the state and check it with
+    /// `verify_public_state`. Contains no secrets.
+    pub async fn export_public_state(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        let group_context = group
+            .export_group_context()
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize group context: {}", e))?;
+        let confirmation_tag = group
+            .confirmation_tag()
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize confirmation tag: {}", e))?;
+        let ratchet_tree = group
+            .export_ratchet_tree()
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize ratchet tree: {}", e))?;
+        Ok(crate::public_state::encode(&group_context, &confirmation_tag, &ratchet_tree))
+    }
 
-        Ok(CreateGroupResult { group_id: gid })
+    pub async fn group_confirmation_tag(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        group
+            .confirmation_tag()
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize confirmation tag: {}", e))
     }
 
-    pub async fn create_group_with_builder(
+    pub async fn group_own_leaf_node(
         &self,
-        config: MlsGroupConfig,
-        signer_bytes: Vec<u8>,
-        credential_identity: Vec<u8>,
-        signer_public_key: Vec<u8>,
-        group_id: Option<Vec<u8>>,
-        lifetime_seconds: Option<u64>,
-        group_context_extensions: Option<Vec<MlsExtension>>,
-        leaf_node_extensions: Option<Vec<MlsExtension>>,
-        capabilities: Option<MlsCapabilities>,
-        credential_bytes: Option<Vec<u8>>,
-    ) -> Result<CreateGroupResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let credential_with_key = build_credential_with_key(
-            &credential_identity, &signer_public_key, credential_bytes.as_deref(),
-        )?;
+        group_id_bytes: Vec<u8>,
+    ) -> Result<MlsLeafNodeInfo, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        let leaf = group
+            .own_leaf_node()
+            .ok_or_else(|| "No own leaf node (group not active?)".to_string())?;
 
-        let provider = self.load_global().await?;
+        let cred_bytes = leaf.credential()
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize credential: {}", e))?;
 
-        signer
-            .store(provider.storage())
-            .map_err(|e| format!("Failed to store signer: {}", e))?;
+        let capabilities = capabilities_from_native(leaf.capabilities());
 
-        let cs = super::types::ciphersuite_to_native(&config.ciphersuite);
-        let wf = super::types::wire_format_to_native(&config.wire_format_policy);
+        let mut extensions = Vec::new();
+        for ext in leaf.extensions().iter() {
+            if let Extension::Unknown(ext_type, data) = ext {
+                extensions.push(MlsExtension {
+                    extension_type: *ext_type,
+                    data: data.0.clone(),
+                });
+            }
+        }
 
-        let mut builder = MlsGroup::builder()
-            .ciphersuite(cs)
-            .with_wire_format_policy(wf)
-            .use_ratchet_tree_extension(config.use_ratchet_tree_extension)
-            .max_past_epochs(config.max_past_epochs as usize)
-            .padding_size(config.padding_size as usize)
-            .sender_ratchet_configuration(SenderRatchetConfiguration::new(
-                config.sender_ratchet_max_out_of_order,
-                config.sender_ratchet_max_forward_distance,
-            ));
+        let encryption_key_bytes = leaf
+            .encryption_key()
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize encryption key: {}", e))?;
 
-        if let Some(gid) = group_id {
-            builder = builder.with_group_id(GroupId::from_slice(&gid));
-        }
-        if let Some(lifetime_secs) = lifetime_seconds {
-            builder = builder.lifetime(Lifetime::new(lifetime_secs));
-        }
-        if let Some(ref gc_exts) = group_context_extensions {
-            let extensions = Extensions::from_vec(extensions_from_mls(gc_exts))
-                .map_err(|e| format!("Failed to create group context extensions: {}", e))?;
-            builder = builder.with_group_context_extensions(extensions);
-        }
-        if let Some(ref leaf_exts) = leaf_node_extensions {
-            let extensions = Extensions::from_vec(extensions_from_mls(leaf_exts))
-                .map_err(|e| format!("Failed to create leaf node extensions: {}", e))?;
-            builder = builder
-                .with_leaf_node_extensions(extensions)
-                .map_err(|e| format!("Failed to set leaf node extensions: {}", e))?;
-        }
-        if let Some(ref caps) = capabilities {
-            builder = builder.with_capabilities(capabilities_to_native(caps)?);
+        let member_handle = member_handle(&provider, &cred_bytes, leaf.signature_key().as_slice())?;
+
+        Ok(MlsLeafNodeInfo {
+            credential: cred_bytes,
+            signature_key: leaf.signature_key().as_slice().to_vec(),
+            member_handle,
+            encryption_key: encryption_key_bytes,
+            capabilities,
+            extensions,
+        })
+    }
+
+    /// List the epochs for which a resumption PSK is retained, oldest first.
+    ///
+    /// Returns epoch numbers only, never the secrets; fetch a secret with
+    /// `get_past_resumption_psk`.
+    pub async fn list_resumption_psks(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Vec<u64>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        // The store holds a contiguous window of epochs ending at the current
+        // one, so walk backwards until the first miss.
+        let mut epochs = Vec::new();
+        let mut epoch = group.epoch().as_u64();
+        while group.get_past_resumption_psk(GroupEpoch::from(epoch)).is_some() {
+            epochs.push(epoch);
+            if epoch == 0 {
+                break;
+            }
+            epoch -= 1;
         }
+        epochs.reverse();
+        Ok(epochs)
+    }
 
-        let mls_group = builder
-            .build(&provider, &signer, credential_with_key)
-            .map_err(|e| format!("Failed to create group: {}", e))?;
+    pub async fn get_past_resumption_psk(
+        &self,
+        group_id_bytes: Vec<u8>,
+        epoch: u64,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        Ok(group
+            .get_past_resumption_psk(GroupEpoch::from(epoch))
+            .map(|psk| psk.as_slice().to_vec()))
+    }
 
-        let gid = mls_group.group_id().as_slice().to_vec();
+    /// Check key packages against the group's required capabilities without
+    /// changing the group. Returns one entry per incompatible key package;
+    /// an empty list means all of them can be added.
+    pub async fn check_add_compatibility(
+        &self,
+        group_id_bytes: Vec<u8>,
+        key_packages_bytes: Vec<Vec<u8>>,
+    ) -> Result<Vec<MlsAddCandidateIncompatibility>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
 
-        self.commit(provider, Some(&gid)).await?;
+        let mut failures = Vec::new();
+        for (i, kp_bytes) in key_packages_bytes.iter().enumerate() {
+            let kp_in = KeyPackageIn::tls_deserialize_exact_bytes(kp_bytes)
+                .map_err(|e| format!("Failed to deserialize key package: {}", e))?;
+            let kp = kp_in.validate(provider.crypto(), group.version())
+                .map_err(|e| format!("Failed to validate key package: {}", e))?;
+            failures.extend(add_candidate_incompatibility(&group, i, &kp));
+        }
+        Ok(failures)
+    }
 
-        Ok(CreateGroupResult { group_id: gid })
+    /// Deserialize and validate key packages to be added to `group`.
+    fn validate_key_packages(
+        &self,
+        group: &MlsGroup,
+        provider: &SnapshotOpenMlsProvider,
+        key_packages_bytes: &[Vec<u8>],
+        options: Option<&KeyPackageValidationOptions>,
+    ) -> Result<Vec<KeyPackage>, String> {
+        let skip_lifetime = options.is_some_and(|o| o.skip_lifetime_validation);
+        if skip_lifetime {
+            self.conformance_deviation("key package lifetime validation skipped")?;
+        }
+        if let Some(options) = options.filter(|o| !o.allowed_ciphersuites.is_empty()) {
+            if !options.allowed_ciphersuites.iter().any(|cs| ciphersuite_to_native(cs) == group.ciphersuite()) {
+                return Err(format!(
+                    "Key package validation: ciphersuite {:?} is not allowed",
+                    group.ciphersuite()
+                ));
+            }
+        }
+        let mut key_packages = Vec::with_capacity(key_packages_bytes.len());
+        for (i, kp_bytes) in key_packages_bytes.iter().enumerate() {
+            let kp_in = KeyPackageIn::tls_deserialize_exact_bytes(kp_bytes)
+                .map_err(|e| format!("Failed to deserialize key package: {}", e))?;
+            let kp = match kp_in.clone().validate(provider.crypto(), group.version()) {
+                Ok(kp) => kp,
+                // OpenMLS checks the lifetime after the signature and
+                // everything else, so this error means only it failed.
+                // Unverified conversion, available with openmls' `test-utils` feature.
+                Err(KeyPackageVerifyError::InvalidLifetime) if skip_lifetime => KeyPackage::from(kp_in),
+                Err(e) => return Err(format!("Failed to validate key package: {}", e)),
+            };
+            if options.is_some_and(|o| o.reject_last_resort) && kp.last_resort() {
+                return Err(format!("Key package validation: key package {} is a last-resort key package", i));
+            }
+            key_packages.push(kp);
+        }
+        Ok(key_packages)
     }
 
     // ═══════════════════════════════════════════════════════════
-    // JOINING A GROUP
+    // MEMBER MANAGEMENT (mutating)
     // ═══════════════════════════════════════════════════════════
 
-    pub async fn join_group_from_welcome(
+    pub async fn add_members(
         &self,
-        config: MlsGroupConfig,
-        welcome_bytes: Vec<u8>,
-        ratchet_tree_bytes: Option<Vec<u8>>,
+        group_id_bytes: Vec<u8>,
         signer_bytes: Vec<u8>,
-    ) -> Result<JoinGroupResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_global().await?;
-
-        signer
-            .store(provider.storage())
-            .map_err(|e| format!("Failed to store signer: {}", e))?;
+        key_packages_bytes: Vec<Vec<u8>>,
+        ensure_group_info: bool,
+        validation: Option<KeyPackageValidationOptions>,
+    ) -> Result<AddMembersResult, String> {
+        let signer = self.signer(signer_bytes)?;
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let mut group = load_group(&group_id_bytes, &provider)?;
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        self.drop_refused_proposals(&mut group, &provider).await?;
 
-        let welcome_msg = MlsMessageIn::tls_deserialize_exact_bytes(&welcome_bytes)
-            .map_err(|e| format!("Failed to deserialize welcome: {}", e))?;
-        let welcome = match welcome_msg.extract() {
-            MlsMessageBodyIn::Welcome(w) => w,
-            _ => return Err("Message is not a Welcome".to_string()),
-        };
+        let key_packages = self.validate_key_packages(&group, &provider, &key_packages_bytes, validation.as_ref())?;
+        check_add_candidates(&group, &key_packages)?;
+        let checks = key_packages.iter().map(|kp| credential_check(&group, None, kp.leaf_node())).collect::<Result<_, _>>()?;
+        let credential_verified = self.verify_credentials(checks).await?;
 
-        let join_config = config.to_join_config();
-        let ratchet_tree: Option<RatchetTreeIn> = ratchet_tree_bytes
-            .map(|rt_bytes| {
-                RatchetTreeIn::tls_deserialize_exact_bytes(&rt_bytes)
-                    .map_err(|e| format!("Failed to deserialize ratchet tree: {}", e))
-            })
-            .transpose()?;
+        let (commit_out, welcome_out, group_info_opt) = group
+            .add_members(&provider, &signer, &key_packages)
+            .map_err(|e| format!("Failed to add members: {}", e))?;
 
-        let staged = StagedWelcome::new_from_welcome(&provider, &join_config, welcome, ratchet_tree)
-            .map_err(|e| format!("Failed to process welcome: {}", e))?;
-        let mls_group = staged
-            .into_group(&provider)
-            .map_err(|e| format!("Failed to join group from welcome: {}", e))?;
+        self.merge_own_commit(&mut group, &mut provider)?;
 
-        let gid = mls_group.group_id().as_slice().to_vec();
+        let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
+        let welcome_bytes = welcome_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
+        let gi_bytes = group_info_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let gi_bytes = ensure_group_info(&group, &provider, &signer, gi_bytes, ensure_group_info)?;
 
-        self.commit(provider, Some(&gid)).await?;
+        let event = self.epoch_event(&group, &provider)?;
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
 
-        Ok(JoinGroupResult { group_id: gid })
+        Ok(AddMembersResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes, credential_verified })
     }
 
-    pub async fn join_group_from_welcome_with_options(
+    pub async fn add_members_without_update(
         &self,
-        config: MlsGroupConfig,
-        welcome_bytes: Vec<u8>,
-        ratchet_tree_bytes: Option<Vec<u8>>,
+        group_id_bytes: Vec<u8>,
         signer_bytes: Vec<u8>,
-        skip_lifetime_validation: bool,
-    ) -> Result<JoinGroupResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_global().await?;
+        key_packages_bytes: Vec<Vec<u8>>,
+        ensure_group_info: bool,
+        validation: Option<KeyPackageValidationOptions>,
+    ) -> Result<AddMembersResult, String> {
+        let signer = self.signer(signer_bytes)?;
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let mut group = load_group(&group_id_bytes, &provider)?;
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        self.drop_refused_proposals(&mut group, &provider).await?;
 
-        signer
-            .store(provider.storage())
-            .map_err(|e| format!("Failed to store signer: {}", e))?;
+        let key_packages = self.validate_key_packages(&group, &provider, &key_packages_bytes, validation.as_ref())?;
+        check_add_candidates(&group, &key_packages)?;
+        let checks = key_packages.iter().map(|kp| credential_check(&group, None, kp.leaf_node())).collect::<Result<_, _>>()?;
+        let credential_verified = self.verify_credentials(checks).await?;
 
-        let welcome_msg = MlsMessageIn::tls_deserialize_exact_bytes(&welcome_bytes)
-            .map_err(|e| format!("Failed to deserialize welcome: {}", e))?;
-        let welcome = match welcome_msg.extract() {
-            MlsMessageBodyIn::Welcome(w) => w,
-            _ => return Err("Message is not a Welcome".to_string()),
-        };
+        let (commit_out, welcome_out, group_info_opt) = group
+            .add_members_without_update(&provider, &signer, &key_packages)
+            .map_err(|e| format!("Failed to add members without update: {}", e))?;
+        self.merge_own_commit(&mut group, &mut provider)?;
 
-        let join_config = config.to_join_config();
-        let mut join_builder = StagedWelcome::build_from_welcome(&provider, &join_config, welcome)
-            .map_err(|e| format!("Failed to process welcome: {}", e))?;
+        let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
+        let welcome_bytes = welcome_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
+        let gi_bytes = group_info_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let gi_bytes = ensure_group_info(&group, &provider, &signer, gi_bytes, ensure_group_info)?;
 
-        if let Some(rt_bytes) = ratchet_tree_bytes {
-            let ratchet_tree = RatchetTreeIn::tls_deserialize_exact_bytes(&rt_bytes)
-                .map_err(|e| format!("Failed to deserialize ratchet tree: {}", e))?;
-            join_builder = join_builder.with_ratchet_tree(ratchet_tree);
-        }
-        if skip_lifetime_validation {
-            join_builder = join_builder.skip_lifetime_validation();
-        }
+        let event = self.epoch_event(&group, &provider)?;
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
+
+        Ok(AddMembersResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes, credential_verified })
+    }
+
+    pub async fn remove_members(
+        &self,
+        group_id_bytes: Vec<u8>,
+        signer_bytes: Vec<u8>,
+        member_indices: Vec<u32>,
+        ensure_group_info: bool,
+    ) -> Result<CommitResult, String> {
+        let signer = self.signer(signer_bytes)?;
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let mut group = load_group(&group_id_bytes, &provider)?;
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        self.drop_refused_proposals(&mut group, &provider).await?;
 
-        let staged = join_builder
-            .build()
-            .map_err(|e| format!("Failed to build staged welcome: {}", e))?;
-        let mls_group = staged
-            .into_group(&provider)
-            .map_err(|e| format!("Failed to join group from welcome: {}", e))?;
+        let indices: Vec<LeafNodeIndex> = member_indices.iter().map(|&i| LeafNodeIndex::new(i)).collect();
+        let (commit_out, welcome_opt, group_info_opt) = group
+            .remove_members(&provider, &signer, &indices)
+            .map_err(|e| format!("Failed to remove members: {}", e))?;
+        let proposals = self.merge_own_commit(&mut group, &mut provider)?;
 
-        let gid = mls_group.group_id().as_slice().to_vec();
+        let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
+        let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w: MlsMessageOut| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
+        let gi_bytes = group_info_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let gi_bytes = ensure_group_info(&group, &provider, &signer, gi_bytes, ensure_group_info)?;
 
-        self.commit(provider, Some(&gid)).await?;
+        let event = self.epoch_event(&group, &provider)?;
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
 
-        Ok(JoinGroupResult { group_id: gid })
+        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes, wire_format: outgoing_wire_format(&group), proposals })
     }
 
-    pub async fn inspect_welcome(
+    pub async fn self_update(
         &self,
-        config: MlsGroupConfig,
-        welcome_bytes: Vec<u8>,
-    ) -> Result<WelcomeInspectResult, String> {
-        let provider = self.load_global().await?;
+        group_id_bytes: Vec<u8>,
+        signer_bytes: Vec<u8>,
+        ensure_group_info: bool,
+    ) -> Result<CommitResult, String> {
+        let signer = self.signer(signer_bytes)?;
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let mut group = load_group(&group_id_bytes, &provider)?;
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        self.drop_refused_proposals(&mut group, &provider).await?;
 
-        let welcome_msg = MlsMessageIn::tls_deserialize_exact_bytes(&welcome_bytes)
-            .map_err(|e| format!("Failed to deserialize welcome: {}", e))?;
-        let welcome = match welcome_msg.extract() {
-            MlsMessageBodyIn::Welcome(w) => w,
-            _ => return Err("Message is not a Welcome".to_string()),
-        };
+        let bundle = group
+            .self_update(&provider, &signer, LeafNodeParameters::default())
+            .map_err(|e| format!("Failed to self-update: {}", e))?;
+        let (commit_out, welcome_opt, group_info_opt) = bundle.into_contents();
+        let proposals = self.merge_own_commit(&mut group, &mut provider)?;
 
-        let join_config = config.to_join_config();
-        let processed = ProcessedWelcome::new_from_welcome(&provider, &join_config, welcome)
-            .map_err(|e| format!("Failed to process welcome: {}", e))?;
+        let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
+        let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w: Welcome| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
+        let gi_bytes = group_info_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let gi_bytes = ensure_group_info(&group, &provider, &signer, gi_bytes, ensure_group_info)?;
 
-        let vgi = processed.unverified_group_info();
-        Ok(WelcomeInspectResult {
-            group_id: vgi.group_id().as_slice().to_vec(),
-            ciphersuite: native_to_ciphersuite(vgi.ciphersuite())?,
-            psk_count: processed.psks().len() as u32,
-            epoch: vgi.epoch().as_u64(),
-        })
+        let event = self.epoch_event(&group, &provider)?;
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
+
+        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes, wire_format: outgoing_wire_format(&group), proposals })
     }
 
-    #[allow(deprecated)]
-    pub async fn join_group_external_commit(
+    /// Respond to a suspected device compromise in one step.
+    ///
+    /// Commits a self-update with a fresh path, so every group secret after
+    /// this epoch is independent of what the device held before, then drops
+    /// the retained past-epoch message secrets and all resumption PSKs but
+    /// the new epoch's. Keys of registered epoch exporters are re-derived and
+    /// emitted as an `EpochAdvancedEvent`. Messages from earlier epochs can
+    /// no longer be decrypted afterwards. If the signature key may have
+    /// leaked too, follow up with `self_update_with_new_signer`.
+    ///
+    /// Distribute the returned commit like any other.
+    pub async fn panic_rotate(
         &self,
-        config: MlsGroupConfig,
-        group_info_bytes: Vec<u8>,
-        ratchet_tree_bytes: Option<Vec<u8>>,
+        group_id_bytes: Vec<u8>,
         signer_bytes: Vec<u8>,
-        credential_identity: Vec<u8>,
-        signer_public_key: Vec<u8>,
-        credential_bytes: Option<Vec<u8>>,
-    ) -> Result<ExternalJoinResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let credential_with_key = build_credential_with_key(
-            &credential_identity, &signer_public_key, credential_bytes.as_deref(),
-        )?;
-
-        let provider = self.load_global().await?;
-        signer
-            .store(provider.storage())
-            .map_err(|e| format!("Failed to store signer: {}", e))?;
-
-        let gi_msg = MlsMessageIn::tls_deserialize_exact_bytes(&group_info_bytes)
-            .map_err(|e| format!("Failed to deserialize group info: {}", e))?;
-        let verifiable_group_info = match gi_msg.extract() {
-            MlsMessageBodyIn::GroupInfo(gi) => gi,
-            _ => return Err("Not a GroupInfo message".to_string()),
-        };
-        let join_config = config.to_join_config();
+        ensure_group_info: bool,
+    ) -> Result<CommitResult, String> {
+        let signer = self.signer(signer_bytes)?;
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let mut group = load_group(&group_id_bytes, &provider)?;
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        self.drop_refused_proposals(&mut group, &provider).await?;
 
-        let ratchet_tree: Option<RatchetTreeIn> = ratchet_tree_bytes
-            .map(|rt_bytes| {
-                RatchetTreeIn::tls_deserialize_exact_bytes(&rt_bytes)
-                    .map_err(|e| format!("Failed to deserialize ratchet tree: {}", e))
-            })
-            .transpose()?;
+        let bundle = group
+            .self_update(&provider, &signer, LeafNodeParameters::default())
+            .map_err(|e| format!("Failed to self-update: {}", e))?;
+        let (commit_out, welcome_opt, group_info_opt) = bundle.into_contents();
+        let proposals = self.merge_own_commit(&mut group, &mut provider)?;
 
-        let (mls_group, commit_out, group_info_opt) = MlsGroup::join_by_external_commit(
-            &provider, &signer, ratchet_tree, verifiable_group_info, &join_config, None, None, &[], credential_with_key,
-        )
-        .map_err(|e| format!("Failed to join group via external commit: {}", e))?;
+        let storage = provider.storage_mut();
+        storage
+            .clear_past_epoch_secrets(group.group_id())
+            .map_err(|e| format!("Failed to clear past epoch secrets: {}", e))?;
+        storage
+            .keep_latest_resumption_psk(group.group_id())
+            .map_err(|e| format!("Failed to clear resumption PSKs: {}", e))?;
 
-        let gid = mls_group.group_id().as_slice().to_vec();
-        let commit_bytes = commit_out
-            .tls_serialize_detached()
-            .map_err(|e| format!("Failed to serialize commit: {}", e))?;
-        let gi_bytes = group_info_opt
-            .map(|gi| gi.tls_serialize_detached())
-            .transpose()
-            .map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
+        let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w: Welcome| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
+        let gi_bytes = group_info_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let gi_bytes = ensure_group_info(&group, &provider, &signer, gi_bytes, ensure_group_info)?;
 
-        self.commit(provider, Some(&gid)).await?;
+        let event = self.epoch_event(&group, &provider)?;
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
 
-        Ok(ExternalJoinResult {
-            group_id: gid,
-            commit: commit_bytes,
-            group_info: gi_bytes,
-        })
+        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes, wire_format: outgoing_wire_format(&group), proposals })
     }
 
-    pub async fn join_group_external_commit_v2(
+    pub async fn self_update_with_new_signer(
         &self,
-        config: MlsGroupConfig,
-        group_info_bytes: Vec<u8>,
-        ratchet_tree_bytes: Option<Vec<u8>>,
-        signer_bytes: Vec<u8>,
-        credential_identity: Vec<u8>,
-        signer_public_key: Vec<u8>,
-        aad: Option<Vec<u8>>,
-        skip_lifetime_validation: bool,
-        credential_bytes: Option<Vec<u8>>,
-    ) -> Result<ExternalJoinResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
+        group_id_bytes: Vec<u8>,
+        old_signer_bytes: Vec<u8>,
+        new_signer_bytes: Vec<u8>,
+        new_credential_identity: Vec<u8>,
+        new_signer_public_key: Vec<u8>,
+        new_credential_bytes: Option<Vec<u8>>,
+        ensure_group_info: bool,
+    ) -> Result<CommitResult, String> {
+        let old_signer = self.signer(old_signer_bytes)?;
+        let new_signer = self.signer(new_signer_bytes)?;
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let mut group = load_group(&group_id_bytes, &provider)?;
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        self.drop_refused_proposals(&mut group, &provider).await?;
+
+        new_signer.store(provider.storage()).map_err(|e| format!("Failed to store new signer: {}", e))?;
+
         let credential_with_key = build_credential_with_key(
-            &credential_identity, &signer_public_key, credential_bytes.as_deref(),
+            &new_credential_identity, &new_signer_public_key, new_credential_bytes.as_deref(),
         )?;
+        let new_signer_bundle = NewSignerBundle { signer: &new_signer, credential_with_key };
 
-        let provider = self.load_global().await?;
-        signer
-            .store(provider.storage())
-            .map_err(|e| format!("Failed to store signer: {}", e))?;
+        let bundle = group
+            .self_update_with_new_signer(&provider, &old_signer, new_signer_bundle, LeafNodeParameters::default())
+            .map_err(|e| format!("Failed to self-update with new signer: {}", e))?;
+        let (commit_out, welcome_opt, group_info_opt) = bundle.into_contents();
+        let proposals = self.merge_own_commit(&mut group, &mut provider)?;
 
-        let gi_msg = MlsMessageIn::tls_deserialize_exact_bytes(&group_info_bytes)
-            .map_err(|e| format!("Failed to deserialize group info: {}", e))?;
-        let verifiable_group_info = match gi_msg.extract() {
-            MlsMessageBodyIn::GroupInfo(gi) => gi,
-            _ => return Err("Not a GroupInfo message".to_string()),
-        };
-        let join_config = config.to_join_config();
+        let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
+        let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w: Welcome| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
+        let gi_bytes = group_info_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let gi_bytes = ensure_group_info(&group, &provider, &new_signer, gi_bytes, ensure_group_info)?;
 
-        let mut ext_builder = MlsGroup::external_commit_builder().with_config(join_config);
-        if let Some(rt_bytes) = ratchet_tree_bytes {
-            let ratchet_tree = RatchetTreeIn::tls_deserialize_exact_bytes(&rt_bytes)
-                .map_err(|e| format!("Failed to deserialize ratchet tree: {}", e))?;
-            ext_builder = ext_builder.with_ratchet_tree(ratchet_tree);
-        }
-        if let Some(aad_bytes) = aad {
-            ext_builder = ext_builder.with_aad(aad_bytes);
-        }
-        if skip_lifetime_validation {
-            ext_builder = ext_builder.skip_lifetime_validation();
-        }
+        let event = self.epoch_event(&group, &provider)?;
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
 
-        let commit_builder = ext_builder
-            .build_group(&provider, verifiable_group_info, credential_with_key)
-            .map_err(|e| format!("Failed to build external commit group: {}", e))?;
-        let commit_builder = commit_builder
-            .load_psks(provider.storage())
-            .map_err(|e| format!("Failed to load PSKs: {}", e))?;
-        let commit_builder = commit_builder
-            .build(provider.rand(), provider.crypto(), &signer, |_| true)
-            .map_err(|e| format!("Failed to build external commit: {}", e))?;
-        let (mls_group, bundle) = commit_builder
-            .finalize(&provider)
-            .map_err(|e| format!("Failed to finalize external commit: {}", e))?;
+        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes, wire_format: outgoing_wire_format(&group), proposals })
+    }
 
-        let gid = mls_group.group_id().as_slice().to_vec();
-        let (commit_out, _welcome_opt, gi_opt) = bundle.into_messages();
-        let commit_bytes = commit_out
-            .tls_serialize_detached()
-            .map_err(|e| format!("Failed to serialize commit: {}", e))?;
-        let gi_bytes = gi_opt
-            .map(|gi| gi.tls_serialize_detached())
-            .transpose()
-            .map_err(|e| format!("Failed to serialize group info: {}", e))?;
+    pub async fn swap_members(
+        &self,
+        group_id_bytes: Vec<u8>,
+        signer_bytes: Vec<u8>,
+        remove_indices: Vec<u32>,
+        add_key_packages_bytes: Vec<Vec<u8>>,
+        ensure_group_info: bool,
+        validation: Option<KeyPackageValidationOptions>,
+    ) -> Result<AddMembersResult, String> {
+        let signer = self.signer(signer_bytes)?;
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let mut group = load_group(&group_id_bytes, &provider)?;
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        self.drop_refused_proposals(&mut group, &provider).await?;
 
-        self.commit(provider, Some(&gid)).await?;
+        let indices: Vec<LeafNodeIndex> = remove_indices.iter().map(|&i| LeafNodeIndex::new(i)).collect();
+        let key_packages = self.validate_key_packages(&group, &provider, &add_key_packages_bytes, validation.as_ref())?;
+        check_add_candidates(&group, &key_packages)?;
+        let checks = key_packages.iter().map(|kp| credential_check(&group, None, kp.leaf_node())).collect::<Result<_, _>>()?;
+        let credential_verified = self.verify_credentials(checks).await?;
 
-        Ok(ExternalJoinResult {
-            group_id: gid,
-            commit: commit_bytes,
-            group_info: gi_bytes,
-        })
-    }
+        let result = group.swap_members(&provider, &signer, &indices, &key_packages)
+            .map_err(|e| format!("Failed to swap members: {}", e))?;
+        self.merge_own_commit(&mut group, &mut provider)?;
 
-    // ═══════════════════════════════════════════════════════════
-    // STATE QUERIES (read-only)
-    // ═══════════════════════════════════════════════════════════
+        let commit_bytes = result.commit.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
+        let welcome_bytes = result.welcome.tls_serialize_detached().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
+        let gi_bytes = result.group_info.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let gi_bytes = ensure_group_info(&group, &provider, &signer, gi_bytes, ensure_group_info)?;
 
-    pub async fn group_id(
-        &self,
-        group_id_bytes: Vec<u8>,
-    ) -> Result<Vec<u8>, String> {
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        Ok(group.group_id().as_slice().to_vec())
-    }
+        let event = self.epoch_event(&group, &provider)?;
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
 
-    pub async fn group_epoch(
-        &self,
-        group_id_bytes: Vec<u8>,
-    ) -> Result<u64, String> {
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        Ok(group.epoch().as_u64())
+        Ok(AddMembersResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes, credential_verified })
     }
 
-    pub async fn group_is_active(
+    pub async fn leave_group(
         &self,
         group_id_bytes: Vec<u8>,
-    ) -> Result<bool, String> {
+        signer_bytes: Vec<u8>,
+    ) -> Result<LeaveGroupResult, String> {
+        let signer = self.signer(signer_bytes)?;
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        Ok(group.is_active())
-    }
+        let mut group = load_group(&group_id_bytes, &provider)?;
 
-    pub async fn group_members(
-        &self,
-        group_id_bytes: Vec<u8>,
-    ) -> Result<Vec<MlsMemberInfo>, String> {
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        let mut members = Vec::new();
-        for member in group.members() {
-            let cred_bytes = member.credential
-                .tls_serialize_detached()
-                .map_err(|e| format!("Failed to serialize member credential: {}", e))?;
-            members.push(MlsMemberInfo {
-                index: member.index.u32(),
-                credential: cred_bytes,
-                signature_key: member.signature_key.clone(),
-            });
-        }
-        Ok(members)
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        let msg = group.leave_group(&provider, &signer).map_err(|e| format!("Failed to leave group: {}", e))?;
+        let msg_bytes = msg.tls_serialize_detached().map_err(|e| format!("Failed to serialize leave message: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+
+        Ok(LeaveGroupResult { message: msg_bytes })
     }
 
-    pub async fn group_ciphersuite(
+    pub async fn leave_group_via_self_remove(
         &self,
         group_id_bytes: Vec<u8>,
-    ) -> Result<MlsCiphersuite, String> {
+        signer_bytes: Vec<u8>,
+    ) -> Result<LeaveGroupResult, String> {
+        let signer = self.signer(signer_bytes)?;
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        native_to_ciphersuite(group.ciphersuite())
+        let mut group = load_group(&group_id_bytes, &provider)?;
+
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        let msg = group.leave_group_via_self_remove(&provider, &signer).map_err(|e| format!("Failed to leave group via self-remove: {}", e))?;
+        let msg_bytes = msg.tls_serialize_detached().map_err(|e| format!("Failed to serialize leave message: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+
+        Ok(LeaveGroupResult { message: msg_bytes })
     }
 
-    pub async fn group_own_index(
+    // ═══════════════════════════════════════════════════════════
+    // PROPOSALS (mutating)
+    // ═══════════════════════════════════════════════════════════
+
+    pub async fn propose_add(
         &self,
         group_id_bytes: Vec<u8>,
-    ) -> Result<u32, String> {
+        signer_bytes: Vec<u8>,
+        key_package_bytes: Vec<u8>,
+        validation: Option<KeyPackageValidationOptions>,
+    ) -> Result<ProposalResult, String> {
+        let signer = self.signer(signer_bytes)?;
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        Ok(group.own_leaf_index().u32())
+        let mut group = load_group(&group_id_bytes, &provider)?;
+
+        let kp = self
+            .validate_key_packages(&group, &provider, std::slice::from_ref(&key_package_bytes), validation.as_ref())?
+            .remove(0);
+        check_add_candidates(&group, std::slice::from_ref(&kp))?;
+
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        let (proposal_out, proposal_ref) = group.propose_add_member(&provider, &signer, &kp)
+            .map_err(|e| format!("Failed to propose add: {}", e))?;
+        let msg_bytes = proposal_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal: {}", e))?;
+        let ref_bytes = proposal_ref.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal ref: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+
+        Ok(ProposalResult { proposal_message: msg_bytes, proposal_ref: ref_bytes })
     }
 
-    pub async fn group_credential(
+    pub async fn propose_remove(
         &self,
         group_id_bytes: Vec<u8>,
-    ) -> Result<Vec<u8>, String> {
+        signer_bytes: Vec<u8>,
+        member_index: u32,
+    ) -> Result<ProposalResult, String> {
+        let signer = self.signer(signer_bytes)?;
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        let credential = group.credential().map_err(|e| format!("Failed to get credential: {}", e))?;
-        credential
-            .tls_serialize_detached()
-            .map_err(|e| format!("Failed to serialize credential: {}", e))
+        let mut group = load_group(&group_id_bytes, &provider)?;
+
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        let (proposal_out, proposal_ref) = group.propose_remove_member(&provider, &signer, LeafNodeIndex::new(member_index))
+            .map_err(|e| format!("Failed to propose remove: {}", e))?;
+        let msg_bytes = proposal_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal: {}", e))?;
+        let ref_bytes = proposal_ref.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal ref: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+
+        Ok(ProposalResult { proposal_message: msg_bytes, proposal_ref: ref_bytes })
     }
 
-    pub async fn group_extensions(
+    pub async fn propose_self_update(
         &self,
         group_id_bytes: Vec<u8>,
-    ) -> Result<Vec<u8>, String> {
+        signer_bytes: Vec<u8>,
+        leaf_node_capabilities: Option<MlsCapabilities>,
+        leaf_node_extensions: Option<Vec<MlsExtension>>,
+    ) -> Result<ProposalResult, String> {
+        let signer = self.signer(signer_bytes)?;
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        group
-            .extensions()
-            .tls_serialize_detached()
-            .map_err(|e| format!("Failed to serialize extensions: {}", e))
+        let mut group = load_group(&group_id_bytes, &provider)?;
+
+        let leaf_node_params = leaf_node_parameters(leaf_node_capabilities, leaf_node_extensions)?;
+
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        let (proposal_out, proposal_ref) = group.propose_self_update(&provider, &signer, leaf_node_params)
+            .map_err(|e| format!("Failed to propose self-update: {}", e))?;
+        let msg_bytes = proposal_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal: {}", e))?;
+        let ref_bytes = proposal_ref.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal ref: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+
+        Ok(ProposalResult { proposal_message: msg_bytes, proposal_ref: ref_bytes })
     }
 
-    pub async fn group_pending_proposals(
+    pub async fn propose_external_psk(
         &self,
         group_id_bytes: Vec<u8>,
-    ) -> Result<Vec<MlsPendingProposalInfo>, String> {
+        signer_bytes: Vec<u8>,
+        psk_id: Vec<u8>,
+        psk_nonce: Vec<u8>,
+    ) -> Result<ProposalResult, String> {
+        let signer = self.signer(signer_bytes)?;
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        let mut proposals = Vec::new();
-        for qp in group.pending_proposals() {
-            let proposal_type = match qp.proposal() {
-                Proposal::Add(_) => MlsProposalType::Add,
-                Proposal::Remove(_) => MlsProposalType::Remove,
-                Proposal::Update(_) => MlsProposalType::Update,
-                Proposal::PreSharedKey(_) => MlsProposalType::PreSharedKey,
-                Proposal::ReInit(_) => MlsProposalType::Reinit,
-                Proposal::ExternalInit(_) => MlsProposalType::ExternalInit,
-                Proposal::GroupContextExtensions(_) => MlsProposalType::GroupContextExtensions,
-                _ => MlsProposalType::Custom,
-            };
-            let sender_index = match qp.sender() {
-                Sender::Member(idx) => Some(idx.u32()),
-                _ => None,
-            };
-            proposals.push(MlsPendingProposalInfo { proposal_type, sender_index });
-        }
-        Ok(proposals)
+        let mut group = load_group(&group_id_bytes, &provider)?;
+
+        let psk = PreSharedKeyId::external(psk_id, psk_nonce);
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        let (proposal_out, proposal_ref) = group.propose_external_psk(&provider, &signer, psk)
+            .map_err(|e| format!("Failed to propose external PSK: {}", e))?;
+        let msg_bytes = proposal_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal: {}", e))?;
+        let ref_bytes = proposal_ref.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal ref: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+
+        Ok(ProposalResult { proposal_message: msg_bytes, proposal_ref: ref_bytes })
     }
 
-    pub async fn group_has_pending_proposals(
+    pub async fn propose_group_context_extensions(
         &self,
         group_id_bytes: Vec<u8>,
-    ) -> Result<bool, String> {
+        signer_bytes: Vec<u8>,
+        extensions: Vec<MlsExtension>,
+    ) -> Result<ProposalResult, String> {
+        let signer = self.signer(signer_bytes)?;
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        Ok(group.has_pending_proposals())
+        let mut group = load_group(&group_id_bytes, &provider)?;
+
+        let ext_vec: Vec<Extension> = extensions.iter().map(|ext| Extension::Unknown(ext.extension_type, UnknownExtension(ext.data.clone()))).collect();
+        let gc_extensions = Extensions::from_vec(ext_vec).map_err(|e| format!("Failed to create extensions: {}", e))?;
+
+        check_own_group_features_change(&group, &gc_extensions)?;
+
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        let (proposal_out, proposal_ref) = group.propose_group_context_extensions(&provider, gc_extensions, &signer)
+            .map_err(|e| format!("Failed to propose group context extensions: {}", e))?;
+        let msg_bytes = proposal_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal: {}", e))?;
+        let ref_bytes = proposal_ref.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal ref: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+
+        Ok(ProposalResult { proposal_message: msg_bytes, proposal_ref: ref_bytes })
     }
 
-    pub async fn group_member_at(
+    pub async fn propose_custom_proposal(
         &self,
         group_id_bytes: Vec<u8>,
-        leaf_index: u32,
-    ) -> Result<Option<MlsMemberInfo>, String> {
+        signer_bytes: Vec<u8>,
+        proposal_type: u16,
+        payload: Vec<u8>,
+    ) -> Result<ProposalResult, String> {
+        let signer = self.signer(signer_bytes)?;
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        match group.member_at(LeafNodeIndex::new(leaf_index)) {
-            Some(member) => {
-                let cred_bytes = member.credential
-                    .tls_serialize_detached()
-                    .map_err(|e| format!("Failed to serialize member credential: {}", e))?;
-                Ok(Some(MlsMemberInfo {
-                    index: member.index.u32(),
-                    credential: cred_bytes,
-                    signature_key: member.signature_key.clone(),
-                }))
-            }
-            None => Ok(None),
+        let mut group = load_group(&group_id_bytes, &provider)?;
+
+        // Receivers reject proposals of types not every member advertises;
+        // fail here with the members at fault instead.
+        let lacking = members_lacking_proposal_type(&group, proposal_type)?;
+        if !lacking.is_empty() {
+            return Err(format!(
+                "Custom proposal type 0x{:04x} is not advertised in the capabilities of members {:?}",
+                proposal_type, lacking
+            ));
         }
+
+        let custom = CustomProposal::new(proposal_type, payload);
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        let (proposal_out, proposal_ref) = group.propose_custom_proposal_by_reference(&provider, &signer, custom)
+            .map_err(|e| format!("Failed to propose custom proposal: {}", e))?;
+        let msg_bytes = proposal_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal: {}", e))?;
+        let ref_bytes = proposal_ref.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal ref: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+
+        Ok(ProposalResult { proposal_message: msg_bytes, proposal_ref: ref_bytes })
     }
 
-    pub async fn group_member_leaf_index(
+    pub async fn propose_remove_member_by_credential(
         &self,
         group_id_bytes: Vec<u8>,
+        signer_bytes: Vec<u8>,
         credential_bytes: Vec<u8>,
-    ) -> Result<Option<u32>, String> {
+    ) -> Result<ProposalResult, String> {
+        let signer = self.signer(signer_bytes)?;
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
+        let mut group = load_group(&group_id_bytes, &provider)?;
+
         let credential = Credential::tls_deserialize_exact_bytes(&credential_bytes)
             .map_err(|e| format!("Failed to deserialize credential: {}", e))?;
-        Ok(group.member_leaf_index(&credential).map(|idx| idx.u32()))
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        let (proposal_out, proposal_ref) = group.propose_remove_member_by_credential(&provider, &signer, &credential)
+            .map_err(|e| format!("Failed to propose remove by credential: {}", e))?;
+        let msg_bytes = proposal_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal: {}", e))?;
+        let ref_bytes = proposal_ref.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal ref: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+
+        Ok(ProposalResult { proposal_message: msg_bytes, proposal_ref: ref_bytes })
     }
 
     // ═══════════════════════════════════════════════════════════
-    // EXPORT OPERATIONS (read-only)
+    // COMMIT / MERGE OPERATIONS (mutating)
     // ═══════════════════════════════════════════════════════════
 
-    pub async fn export_ratchet_tree(
+    /// Commit the pending proposals. `wire_format` overrides the group's
+    /// outgoing wire format for this commit (see `FlexibleCommitOptions`).
+    /// With `ensure_group_info`, the result always carries a signed
+    /// GroupInfo, as for the other commit calls.
+    pub async fn commit_to_pending_proposals(
         &self,
         group_id_bytes: Vec<u8>,
-    ) -> Result<Vec<u8>, String> {
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        group
-            .export_ratchet_tree()
-            .tls_serialize_detached()
-            .map_err(|e| format!("Failed to serialize ratchet tree: {}", e))
+        signer_bytes: Vec<u8>,
+        wire_format: Option<MlsWireFormat>,
+        ensure_group_info: bool,
+    ) -> Result<CommitResult, String> {
+        let signer = self.signer(signer_bytes)?;
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let mut group = load_group(&group_id_bytes, &provider)?;
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        self.drop_refused_proposals(&mut group, &provider).await?;
+        let original_config = override_wire_format(&mut group, wire_format)?;
+
+        let (commit_out, welcome_opt, group_info_opt) = group
+            .commit_to_pending_proposals(&provider, &signer)
+            .map_err(|e| format!("Failed to commit to pending proposals: {}", e))?;
+        let proposals = self.merge_own_commit(&mut group, &mut provider)?;
+        let wire_format = outgoing_wire_format(&group);
+        restore_wire_format(&mut group, original_config)?;
+
+        let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
+        let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w: MlsMessageOut| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
+        let gi_bytes = group_info_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let gi_bytes = ensure_group_info(&group, &provider, &signer, gi_bytes, ensure_group_info)?;
+
+        let event = self.epoch_event(&group, &provider)?;
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
+
+        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes, wire_format, proposals })
     }
 
-    pub async fn export_group_info(
+    pub async fn merge_pending_commit(
         &self,
         group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-    ) -> Result<Vec<u8>, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        let group_info = group
-            .export_group_info(provider.crypto(), &signer, true)
-            .map_err(|e| format!("Failed to export group info: {}", e))?;
-        group_info
-            .tls_serialize_detached()
-            .map_err(|e| format!("Failed to serialize group info: {}", e))
+    ) -> Result<(), String> {
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let mut group = load_group(&group_id_bytes, &provider)?;
+        let epoch_before = group.epoch();
+        self.merge_own_commit(&mut group, &mut provider)?;
+
+        let event = if group.epoch() != epoch_before {
+            self.epoch_event(&group, &provider)?
+        } else {
+            None
+        };
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
+        Ok(())
     }
 
-    pub async fn export_secret(
+    pub async fn clear_pending_commit(
         &self,
         group_id_bytes: Vec<u8>,
-        label: String,
-        context: Vec<u8>,
-        key_length: u32,
-    ) -> Result<Vec<u8>, String> {
+    ) -> Result<(), String> {
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        group
-            .export_secret(provider.crypto(), &label, &context, key_length as usize)
-            .map_err(|e| format!("Failed to export secret: {}", e))
+        let mut group = load_group(&group_id_bytes, &provider)?;
+        group.clear_pending_commit(provider.storage()).map_err(|e| format!("Failed to clear pending commit: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        Ok(())
     }
 
-    pub async fn export_group_context(
+    /// Discard our own pending commit after the delivery service refused it.
+    ///
+    /// The group stays in its current epoch. Proposals the commit pulled from
+    /// the proposal store by reference are kept (and put back if missing),
+    /// so a later `commit_to_pending_proposals` covers them again. Inline
+    /// proposals are returned in `re_propose`, as nothing else retains them.
+    ///
+    /// Commit APIs on this engine merge immediately, so a pending commit is
+    /// left only by external commit joins or by an interrupted operation.
+    pub async fn handle_commit_rejected(
         &self,
         group_id_bytes: Vec<u8>,
-    ) -> Result<MlsGroupContextInfo, String> {
+    ) -> Result<MlsCommitRejection, String> {
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        let cs = native_to_ciphersuite(group.ciphersuite())?;
-        let ctx = group.export_group_context();
-        let ext_bytes = ctx
-            .extensions()
-            .tls_serialize_detached()
-            .map_err(|e| format!("Failed to serialize extensions: {}", e))?;
-        Ok(MlsGroupContextInfo {
-            group_id: group.group_id().as_slice().to_vec(),
-            epoch: group.epoch().as_u64(),
-            ciphersuite: cs,
-            tree_hash: ctx.tree_hash().to_vec(),
-            confirmed_transcript_hash: ctx.confirmed_transcript_hash().to_vec(),
-            extensions: ext_bytes,
-        })
+        let mut group = load_group(&group_id_bytes, &provider)?;
+        let staged_commit = group
+            .pending_commit()
+            .ok_or_else(|| "Failed to handle commit rejection: no pending commit".to_string())?;
+        let re_propose = committed_proposals(staged_commit)?
+            .into_iter()
+            .filter(|proposal| !proposal.by_reference)
+            .collect();
+        let pending: Vec<ProposalRef> = group.pending_proposals().map(|qp| qp.proposal_reference()).collect();
+        let missing: Vec<QueuedProposal> = staged_commit
+            .queued_proposals()
+            .filter(|queued| matches!(queued.proposal_or_ref_type(), ProposalOrRefType::Reference))
+            .filter(|queued| !pending.contains(&queued.proposal_reference()))
+            .cloned()
+            .collect();
+
+        group.clear_pending_commit(provider.storage()).map_err(|e| format!("Failed to clear pending commit: {}", e))?;
+        let restored_proposals = missing.len() as u32;
+        for queued in missing {
+            group.store_pending_proposal(provider.storage(), queued)
+                .map_err(|e| format!("Failed to restore pending proposal: {}", e))?;
+        }
+        let epoch = group.epoch().as_u64();
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        Ok(MlsCommitRejection { epoch, restored_proposals, re_propose })
     }
 
-    pub async fn group_confirmation_tag(
+    pub async fn clear_pending_proposals(
         &self,
         group_id_bytes: Vec<u8>,
-    ) -> Result<Vec<u8>, String> {
+    ) -> Result<(), String> {
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        group
-            .confirmation_tag()
-            .tls_serialize_detached()
-            .map_err(|e| format!("Failed to serialize confirmation tag: {}", e))
+        let mut group = load_group(&group_id_bytes, &provider)?;
+        group.clear_pending_proposals(provider.storage()).map_err(|e| format!("Failed to clear pending proposals: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        Ok(())
     }
 
-    pub async fn group_own_leaf_node(
+    /// Replace the group's join config, and the settings kept beside it
+    /// in the group's metadata, with `config`.
+    ///
+    /// Fails if `config` names another ciphersuite or protocol version than
+    /// the group's, which cannot change. A changed
+    /// `number_of_resumption_psks` is stored but does not resize the PSK
+    /// store and is logged as a warning. Use `group_config` to start from
+    /// the stored settings.
+    pub async fn set_configuration(
         &self,
         group_id_bytes: Vec<u8>,
-    ) -> Result<MlsLeafNodeInfo, String> {
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        let leaf = group
-            .own_leaf_node()
-            .ok_or_else(|| "No own leaf node (group not active?)".to_string())?;
-
-        let cred_bytes = leaf.credential()
-            .tls_serialize_detached()
-            .map_err(|e| format!("Failed to serialize credential: {}", e))?;
-
-        let caps = leaf.capabilities();
-        let capabilities = MlsCapabilities {
-            versions: caps.versions().iter().map(|v| match v {
-                ProtocolVersion::Mls10 => 1u16,
-                ProtocolVersion::Other(n) => *n,
-            }).collect(),
-            ciphersuites: caps.ciphersuites().iter().map(|c| c.value()).collect(),
-            extensions: caps.extensions().iter().map(|e| u16::from(*e)).collect(),
-            proposals: caps.proposals().iter().map(|p| u16::from(*p)).collect(),
-            credentials: caps.credentials().iter().map(|c| u16::from(*c)).collect(),
-        };
-
-        let mut extensions = Vec::new();
-        for ext in leaf.extensions().iter() {
-            if let Extension::Unknown(ext_type, data) = ext {
-                extensions.push(MlsExtension {
-                    extension_type: *ext_type,
-                    data: data.0.clone(),
-                });
-            }
+        config: MlsGroupConfig,
+    ) -> Result<(), String> {
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let mut group = load_group(&group_id_bytes, &provider)?;
+        config.check_protocol_version(&group)?;
+        let conflicts = config.conflicts_with(&group, provider.storage())?;
+        if conflicts.iter().any(|name| name == "ciphersuite") {
+            return Err(format!(
+                "Config ciphersuite {:?} does not match group ciphersuite {:?}",
+                ciphersuite_to_native(&config.ciphersuite),
+                group.ciphersuite()
+            ));
         }
+        if conflicts.iter().any(|name| name == "number_of_resumption_psks") {
+            redact::log_warn!("set_configuration: number_of_resumption_psks does not resize the existing resumption PSK store");
+        }
+        let join_config = config.to_join_config();
+        group.set_configuration(provider.storage(), &join_config).map_err(|e| format!("Failed to set configuration: {}", e))?;
+        config.store_group_metadata(provider.storage_mut(), &group_id_bytes)?;
 
-        let encryption_key_bytes = leaf
-            .encryption_key()
-            .tls_serialize_detached()
-            .map_err(|e| format!("Failed to serialize encryption key: {}", e))?;
-
-        Ok(MlsLeafNodeInfo {
-            credential: cred_bytes,
-            signature_key: leaf.signature_key().as_slice().to_vec(),
-            encryption_key: encryption_key_bytes,
-            capabilities,
-            extensions,
-        })
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        Ok(())
     }
 
-    pub async fn get_past_resumption_psk(
+    /// The group's stored configuration.
+    ///
+    /// Pass this, modified as needed, wherever a config for the group is
+    /// required instead of rebuilding one, so the settings the group was
+    /// created or joined with are kept.
+    pub async fn group_config(
         &self,
         group_id_bytes: Vec<u8>,
-        epoch: u64,
-    ) -> Result<Option<Vec<u8>>, String> {
+    ) -> Result<MlsGroupConfig, String> {
         let provider = self.load_for_group(&group_id_bytes).await?;
         let group = load_group(&group_id_bytes, &provider)?;
-        Ok(group
-            .get_past_resumption_psk(GroupEpoch::from(epoch))
-            .map(|psk| psk.as_slice().to_vec()))
+        MlsGroupConfig::from_group(&group, provider.storage())
     }
 
-    // ═══════════════════════════════════════════════════════════
-    // MEMBER MANAGEMENT (mutating)
-    // ═══════════════════════════════════════════════════════════
+    /// Names of the settings in which `config` differs from the group's
+    /// stored configuration, e.g. `wire_format_policy`. Empty if `config`
+    /// matches.
+    ///
+    /// A member whose settings differ from the others', such as a plaintext
+    /// policy in a ciphertext group, produces messages the others reject.
+    pub async fn check_group_config(
+        &self,
+        group_id_bytes: Vec<u8>,
+        config: MlsGroupConfig,
+    ) -> Result<Vec<String>, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        config.conflicts_with(&group, provider.storage())
+    }
 
-    pub async fn add_members(
+    pub async fn update_group_context_extensions(
         &self,
         group_id_bytes: Vec<u8>,
         signer_bytes: Vec<u8>,
-        key_packages_bytes: Vec<Vec<u8>>,
-    ) -> Result<AddMembersResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_for_group(&group_id_bytes).await?;
+        extensions: Vec<MlsExtension>,
+        ensure_group_info: bool,
+    ) -> Result<CommitResult, String> {
+        let signer = self.signer(signer_bytes)?;
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
         let mut group = load_group(&group_id_bytes, &provider)?;
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        self.drop_refused_proposals(&mut group, &provider).await?;
 
-        let mut key_packages = Vec::with_capacity(key_packages_bytes.len());
-        for kp_bytes in key_packages_bytes {
-            let kp_in = KeyPackageIn::tls_deserialize_exact_bytes(&kp_bytes)
-                .map_err(|e| format!("Failed to deserialize key package: {}", e))?;
-            let kp = kp_in
-                .validate(provider.crypto(), ProtocolVersion::Mls10)
-                .map_err(|e| format!("Failed to validate key package: {}", e))?;
-            key_packages.push(kp);
-        }
-
-        let (commit_out, welcome_out, group_info_opt) = group
-            .add_members(&provider, &signer, &key_packages)
-            .map_err(|e| format!("Failed to add members: {}", e))?;
+        let ext_vec: Vec<Extension> = extensions.iter().map(|ext| Extension::Unknown(ext.extension_type, UnknownExtension(ext.data.clone()))).collect();
+        let gc_extensions = Extensions::from_vec(ext_vec).map_err(|e| format!("Failed to create extensions: {}", e))?;
+        check_own_group_features_change(&group, &gc_extensions)?;
 
-        group
-            .merge_pending_commit(&provider)
-            .map_err(|e| format!("Failed to merge pending commit: {}", e))?;
+        let (commit_out, welcome_opt, group_info_opt) = group
+            .update_group_context_extensions(&provider, gc_extensions, &signer)
+            .map_err(|e| format!("Failed to update group context extensions: {}", e))?;
+        let proposals = self.merge_own_commit(&mut group, &mut provider)?;
 
         let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
-        let welcome_bytes = welcome_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
+        let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w: MlsMessageOut| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
         let gi_bytes = group_info_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let gi_bytes = ensure_group_info(&group, &provider, &signer, gi_bytes, ensure_group_info)?;
 
+        let event = self.epoch_event(&group, &provider)?;
         self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
 
-        Ok(AddMembersResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes })
+        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes, wire_format: outgoing_wire_format(&group), proposals })
     }
 
-    pub async fn add_members_without_update(
+    /// Commit new group feature flags as a group context extension update.
+    ///
+    /// Fails if the group already restricts feature changes to a set of
+    /// authorized members and the local member is not one of them. Other
+    /// extension updates are held to the same rule, and commits leave out
+    /// pending proposals that change the features without authorization. A
+    /// received commit that does is still merged, since the rest of the
+    /// group merges it, and flagged with `unauthorized_features_change`.
+    pub async fn set_group_features(
         &self,
         group_id_bytes: Vec<u8>,
         signer_bytes: Vec<u8>,
-        key_packages_bytes: Vec<Vec<u8>>,
-    ) -> Result<AddMembersResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_for_group(&group_id_bytes).await?;
+        features: MlsGroupFeatures,
+        ensure_group_info: bool,
+    ) -> Result<CommitResult, String> {
+        let signer = self.signer(signer_bytes)?;
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
         let mut group = load_group(&group_id_bytes, &provider)?;
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        self.drop_refused_proposals(&mut group, &provider).await?;
 
-        let mut key_packages = Vec::with_capacity(key_packages_bytes.len());
-        for kp_bytes in key_packages_bytes {
-            let kp_in = KeyPackageIn::tls_deserialize_exact_bytes(&kp_bytes)
-                .map_err(|e| format!("Failed to deserialize key package: {}", e))?;
-            let kp = kp_in.validate(provider.crypto(), ProtocolVersion::Mls10)
-                .map_err(|e| format!("Failed to validate key package: {}", e))?;
-            key_packages.push(kp);
+        if !group_features_change_allowed(&group, group.own_leaf_index())? {
+            return Err("Not authorized to change group features".to_string());
         }
 
-        let (commit_out, welcome_out, group_info_opt) = group
-            .add_members_without_update(&provider, &signer, &key_packages)
-            .map_err(|e| format!("Failed to add members without update: {}", e))?;
-        group.merge_pending_commit(&provider).map_err(|e| format!("Failed to merge pending commit: {}", e))?;
+        let mut ext_vec: Vec<Extension> = group
+            .extensions()
+            .iter()
+            .filter(|ext| u16::from(ext.extension_type()) != GROUP_FEATURES_EXTENSION_TYPE)
+            .cloned()
+            .collect();
+        ext_vec.push(group_features_to_extension(&features)?);
+        let gc_extensions = Extensions::from_vec(ext_vec).map_err(|e| format!("Failed to create extensions: {}", e))?;
+
+        let (commit_out, welcome_opt, group_info_opt) = group
+            .update_group_context_extensions(&provider, gc_extensions, &signer)
+            .map_err(|e| format!("Failed to update group features: {}", e))?;
+        let proposals = self.merge_own_commit(&mut group, &mut provider)?;
 
         let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
-        let welcome_bytes = welcome_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
+        let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w: MlsMessageOut| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
         let gi_bytes = group_info_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let gi_bytes = ensure_group_info(&group, &provider, &signer, gi_bytes, ensure_group_info)?;
 
+        let event = self.epoch_event(&group, &provider)?;
         self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
 
-        Ok(AddMembersResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes })
+        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes, wire_format: outgoing_wire_format(&group), proposals })
     }
 
-    pub async fn remove_members(
+    /// Commit a new group topic (nickname) as a group context extension
+    /// update, or remove it with `None`.
+    ///
+    /// Every member converges on the topic by processing the commit, and
+    /// joiners receive it with the group context. It is as confidential as
+    /// the commit and the GroupInfo: published GroupInfos reveal it. Members
+    /// must advertise `group_topic_extension_type` in their capabilities.
+    pub async fn set_group_topic(
         &self,
         group_id_bytes: Vec<u8>,
         signer_bytes: Vec<u8>,
-        member_indices: Vec<u32>,
+        topic: Option<String>,
+        ensure_group_info: bool,
     ) -> Result<CommitResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_for_group(&group_id_bytes).await?;
+        let signer = self.signer(signer_bytes)?;
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
         let mut group = load_group(&group_id_bytes, &provider)?;
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
+        self.drop_refused_proposals(&mut group, &provider).await?;
+
+        let mut ext_vec: Vec<Extension> = group
+            .extensions()
+            .iter()
+            .filter(|ext| u16::from(ext.extension_type()) != GROUP_TOPIC_EXTENSION_TYPE)
+            .cloned()
+            .collect();
+        if let Some(topic) = &topic {
+            ext_vec.push(group_topic_to_extension(topic)?);
+        }
+        let gc_extensions = Extensions::from_vec(ext_vec).map_err(|e| format!("Failed to create extensions: {}", e))?;
 
-        let indices: Vec<LeafNodeIndex> = member_indices.iter().map(|&i| LeafNodeIndex::new(i)).collect();
         let (commit_out, welcome_opt, group_info_opt) = group
-            .remove_members(&provider, &signer, &indices)
-            .map_err(|e| format!("Failed to remove members: {}", e))?;
-        group.merge_pending_commit(&provider).map_err(|e| format!("Failed to merge pending commit: {}", e))?;
+            .update_group_context_extensions(&provider, gc_extensions, &signer)
+            .map_err(|e| format!("Failed to update group topic: {}", e))?;
+        let proposals = self.merge_own_commit(&mut group, &mut provider)?;
 
         let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
         let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w: MlsMessageOut| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
         let gi_bytes = group_info_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let gi_bytes = ensure_group_info(&group, &provider, &signer, gi_bytes, ensure_group_info)?;
 
+        let event = self.epoch_event(&group, &provider)?;
         self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
 
-        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes })
+        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes, wire_format: outgoing_wire_format(&group), proposals })
     }
 
-    pub async fn self_update(
+    /// Advance the group to a new epoch without changing its membership.
+    ///
+    /// Issues a commit without proposals, rotating the group's epoch secrets.
+    /// With `force_self_update` the commit also carries an update path,
+    /// refreshing this member's leaf key material; without it the commit is
+    /// empty and only the key schedule advances.
+    ///
+    /// Refuses if proposals are pending, since an empty commit must not
+    /// silently drop or apply them — unless `consume_pending_proposals` is
+    /// set, in which case they are committed along with the epoch change.
+    pub async fn advance_epoch(
         &self,
         group_id_bytes: Vec<u8>,
         signer_bytes: Vec<u8>,
+        force_self_update: bool,
+        consume_pending_proposals: bool,
+        ensure_group_info: bool,
     ) -> Result<CommitResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_for_group(&group_id_bytes).await?;
+        let signer = self.signer(signer_bytes)?;
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
         let mut group = load_group(&group_id_bytes, &provider)?;
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, None)?;
 
-        let bundle = group
-            .self_update(&provider, &signer, LeafNodeParameters::default())
-            .map_err(|e| format!("Failed to self-update: {}", e))?;
-        let (commit_out, welcome_opt, group_info_opt) = bundle.into_contents();
-        group.merge_pending_commit(&provider).map_err(|e| format!("Failed to merge pending commit: {}", e))?;
+        if !consume_pending_proposals && group.pending_proposals().next().is_some() {
+            return Err("Group has pending proposals; commit them or set consume_pending_proposals".to_string());
+        }
+        if consume_pending_proposals {
+            self.drop_refused_proposals(&mut group, &provider).await?;
+        }
+
+        let commit_builder = group.commit_builder()
+            .consume_proposal_store(consume_pending_proposals)
+            .force_self_update(force_self_update);
+        let commit_builder = commit_builder.load_psks(provider.storage()).map_err(|e| format!("Failed to load PSKs: {}", e))?;
+        let commit_builder = commit_builder.build(provider.rand(), provider.crypto(), &signer, |_| true).map_err(|e| format!("Failed to build commit: {}", e))?;
+        let bundle = commit_builder.stage_commit(&provider).map_err(|e| format!("Failed to stage commit: {}", e))?;
+        let proposals = self.merge_own_commit(&mut group, &mut provider)?;
 
+        let (commit_out, welcome_opt, gi_opt) = bundle.into_messages();
         let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
-        let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w: Welcome| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
-        let gi_bytes = group_info_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
+        let gi_bytes = gi_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let gi_bytes = ensure_group_info(&group, &provider, &signer, gi_bytes, ensure_group_info)?;
 
+        let event = self.epoch_event(&group, &provider)?;
         self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
 
-        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes })
+        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes, wire_format: outgoing_wire_format(&group), proposals })
     }
 
-    pub async fn self_update_with_new_signer(
+    pub async fn flexible_commit(
         &self,
         group_id_bytes: Vec<u8>,
-        old_signer_bytes: Vec<u8>,
-        new_signer_bytes: Vec<u8>,
-        new_credential_identity: Vec<u8>,
-        new_signer_public_key: Vec<u8>,
-        new_credential_bytes: Option<Vec<u8>>,
+        signer_bytes: Vec<u8>,
+        options: FlexibleCommitOptions,
     ) -> Result<CommitResult, String> {
-        let old_signer = signer_from_bytes(old_signer_bytes)?;
-        let new_signer = signer_from_bytes(new_signer_bytes)?;
-        let provider = self.load_for_group(&group_id_bytes).await?;
+        let signer = self.signer(signer_bytes)?;
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
         let mut group = load_group(&group_id_bytes, &provider)?;
 
-        new_signer.store(provider.storage()).map_err(|e| format!("Failed to store new signer: {}", e))?;
+        let refused = if options.consume_pending_proposals {
+            self.refused_proposals(&group, options.proposal_filter.as_ref()).await?
+        } else {
+            Vec::new()
+        };
+        let gc_extensions = match options.group_context_extensions {
+            Some(ref gc_exts) => {
+                let extensions = Extensions::from_vec(extensions_from_mls(gc_exts))
+                    .map_err(|e| format!("Failed to create group context extensions: {}", e))?;
+                check_own_group_features_change(&group, &extensions)?;
+                Some(extensions)
+            }
+            None => None,
+        };
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, options.aad)?;
+        let original_config = override_wire_format(&mut group, options.wire_format)?;
 
-        let credential_with_key = build_credential_with_key(
-            &new_credential_identity, &new_signer_public_key, new_credential_bytes.as_deref(),
-        )?;
-        let new_signer_bundle = NewSignerBundle { signer: &new_signer, credential_with_key };
+        // Validated before the builder borrows the group.
+        let key_packages = if options.add_key_packages.is_empty() {
+            Vec::new()
+        } else {
+            let key_packages = self.validate_key_packages(
+                &group,
+                &provider,
+                &options.add_key_packages,
+                options.key_package_validation.as_ref(),
+            )?;
+            check_add_candidates(&group, &key_packages)?;
+            key_packages
+        };
 
-        let bundle = group
-            .self_update_with_new_signer(&provider, &old_signer, new_signer_bundle, LeafNodeParameters::default())
-            .map_err(|e| format!("Failed to self-update with new signer: {}", e))?;
-        let (commit_out, welcome_opt, group_info_opt) = bundle.into_contents();
-        group.merge_pending_commit(&provider).map_err(|e| format!("Failed to merge pending commit: {}", e))?;
+        let mut commit_builder = group.commit_builder()
+            .consume_proposal_store(options.consume_pending_proposals)
+            .force_self_update(options.force_self_update);
+
+        if !key_packages.is_empty() {
+            commit_builder = commit_builder.propose_adds(key_packages);
+        }
 
+        if !options.remove_indices.is_empty() {
+            commit_builder = commit_builder.propose_removals(options.remove_indices.iter().map(|&i| LeafNodeIndex::new(i)));
+        }
+
+        if let Some(extensions) = gc_extensions {
+            commit_builder = commit_builder.propose_group_context_extensions(extensions).map_err(|e| format!("Failed to propose group context extensions: {}", e))?;
+        }
+
+        let commit_builder = commit_builder.load_psks(provider.storage()).map_err(|e| format!("Failed to load PSKs: {}", e))?;
+        let commit_builder = commit_builder.create_group_info(options.create_group_info).use_ratchet_tree_extension(options.use_ratchet_tree_extension);
+        let filter = options.proposal_filter;
+        let commit_builder = commit_builder
+            .build(provider.rand(), provider.crypto(), &signer, |queued| {
+                filter.as_ref().is_none_or(|filter| proposal_filter_accepts(filter, queued))
+                    && queued
+                        .proposal_reference()
+                        .tls_serialize_detached()
+                        .is_ok_and(|proposal_ref| !refused.contains(&proposal_ref))
+            })
+            .map_err(|e| format!("Failed to build commit: {}", e))?;
+        let bundle = commit_builder.stage_commit(&provider).map_err(|e| format!("Failed to stage commit: {}", e))?;
+        let proposals = self.merge_own_commit(&mut group, &mut provider)?;
+        let wire_format = outgoing_wire_format(&group);
+        restore_wire_format(&mut group, original_config)?;
+
+        let (commit_out, welcome_opt, gi_opt) = bundle.into_messages();
         let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
-        let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w: Welcome| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
-        let gi_bytes = group_info_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
+        let gi_bytes = gi_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+
+        let event = self.epoch_event(&group, &provider)?;
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
+
+        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes, wire_format, proposals })
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // MESSAGES (mutating)
+    // ═══════════════════════════════════════════════════════════
+
+    /// Set (or with `None`, clear) the AAD applied to every message, commit,
+    /// proposal and leave message created in this group when the call does
+    /// not pass its own AAD. Stored with the group, so it persists across
+    /// restarts.
+    pub async fn set_default_aad(
+        &self,
+        group_id_bytes: Vec<u8>,
+        aad: Option<Vec<u8>>,
+    ) -> Result<(), String> {
+        let mut provider = self.load_group_metadata(&group_id_bytes).await?;
+
+        match aad {
+            Some(aad_bytes) => provider.storage_mut().write_app_group(&group_id_bytes, DEFAULT_AAD, &aad_bytes),
+            None => provider.storage_mut().delete_app_group(&group_id_bytes, DEFAULT_AAD),
+        }
+        .map_err(|e| format!("Failed to write default AAD: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        Ok(())
+    }
+
+    /// The group's default AAD, or `None` if not set.
+    pub async fn default_aad(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let provider = self.load_label(APP_GROUP_LABEL, Some(&group_id_bytes)).await?;
+        provider.storage()
+            .app_group(&group_id_bytes, DEFAULT_AAD)
+            .map_err(|e| format!("Failed to read default AAD: {}", e))
+    }
+
+    /// Record the caller's position in the delivery service's message stream
+    /// for this group, e.g. the id of the last message fetched.
+    ///
+    /// `process_message` can store the cursor together with the message it
+    /// processes, so after a crash the cursor never points before a message
+    /// whose effects were persisted, nor past one whose effects were lost.
+    /// Use this for messages that were not applied (e.g. ones reported as
+    /// `Expired` and dropped).
+    pub async fn set_sync_cursor(
+        &self,
+        group_id_bytes: Vec<u8>,
+        cursor_bytes: Vec<u8>,
+    ) -> Result<(), String> {
+        let mut provider = self.load_group_metadata(&group_id_bytes).await?;
+
+        provider.storage_mut()
+            .write_app_group(&group_id_bytes, SYNC_CURSOR, &cursor_bytes)
+            .map_err(|e| format!("Failed to write sync cursor: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        Ok(())
+    }
+
+    /// The group's sync cursor, or `None` if none was stored.
+    pub async fn sync_cursor(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, String> {
+        let provider = self.load_label(APP_GROUP_LABEL, Some(&group_id_bytes)).await?;
+        provider.storage()
+            .app_group(&group_id_bytes, SYNC_CURSOR)
+            .map_err(|e| format!("Failed to read sync cursor: {}", e))
+    }
+
+    /// Turn deduplication of application messages on or off for a group.
+    ///
+    /// While on, `process_message` reports a redelivered application message
+    /// as `ProcessedMessageType::Duplicate` instead of failing on the
+    /// consumed ratchet key, and keeps per-sender watermarks. Turning it off
+    /// drops the watermarks.
+    pub async fn set_message_deduplication(
+        &self,
+        group_id_bytes: Vec<u8>,
+        enabled: bool,
+    ) -> Result<(), String> {
+        let mut provider = self.load_group_metadata(&group_id_bytes).await?;
+
+        let storage = provider.storage_mut();
+        if !enabled {
+            storage
+                .delete_app_group(&group_id_bytes, MESSAGE_WATERMARKS)
+                .map_err(|e| format!("Failed to write message watermarks: {}", e))?;
+        } else if message_watermarks(storage, &group_id_bytes)?.is_none() {
+            storage
+                .write_app_group(&group_id_bytes, MESSAGE_WATERMARKS, &Vec::<StoredWatermark>::new())
+                .map_err(|e| format!("Failed to write message watermarks: {}", e))?;
+        }
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        Ok(())
+    }
+
+    /// Per-sender watermarks, or `None` if deduplication is off for the group.
+    pub async fn message_watermarks(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Option<Vec<MessageWatermark>>, String> {
+        let provider = self.load_label(APP_GROUP_LABEL, Some(&group_id_bytes)).await?;
+        Ok(message_watermarks(provider.storage(), &group_id_bytes)?.map(|entries| {
+            entries
+                .into_iter()
+                .map(|entry| MessageWatermark {
+                    sender_index: entry.sender_index,
+                    epoch: entry.epoch,
+                    messages: entry.messages,
+                })
+                .collect()
+        }))
+    }
+
+    /// Add a credential (TLS-serialized, as in `MlsMemberInfo`) to the
+    /// group's local block list, or update its entry.
+    ///
+    /// Messages from a blocked member are still processed, so the group
+    /// state stays in sync, but reported with `blocked: true`. With
+    /// `suppress_messages`, their application messages are also returned
+    /// without content. The list is local and never sent to the group.
+    pub async fn block_member(
+        &self,
+        group_id_bytes: Vec<u8>,
+        credential: Vec<u8>,
+        suppress_messages: bool,
+    ) -> Result<(), String> {
+        let mut provider = self.load_group_metadata(&group_id_bytes).await?;
+
+        let mut entries = blocked_members(provider.storage(), &group_id_bytes)?;
+        match entries.iter_mut().find(|entry| entry.credential == credential) {
+            Some(entry) => entry.suppress_messages = suppress_messages,
+            None => entries.push(StoredBlockedMember { credential, suppress_messages }),
+        }
+        provider.storage_mut()
+            .write_app_group(&group_id_bytes, BLOCKED_MEMBERS, &entries)
+            .map_err(|e| format!("Failed to write blocked members: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        Ok(())
+    }
+
+    /// Remove a credential from the group's block list. Returns whether it
+    /// was on it.
+    pub async fn unblock_member(
+        &self,
+        group_id_bytes: Vec<u8>,
+        credential: Vec<u8>,
+    ) -> Result<bool, String> {
+        let mut provider = self.load_group_metadata(&group_id_bytes).await?;
+
+        let mut entries = blocked_members(provider.storage(), &group_id_bytes)?;
+        let before = entries.len();
+        entries.retain(|entry| entry.credential != credential);
+        if entries.len() == before {
+            return Ok(false);
+        }
+        let storage = provider.storage_mut();
+        if entries.is_empty() {
+            storage.delete_app_group(&group_id_bytes, BLOCKED_MEMBERS)
+        } else {
+            storage.write_app_group(&group_id_bytes, BLOCKED_MEMBERS, &entries)
+        }
+        .map_err(|e| format!("Failed to write blocked members: {}", e))?;
+
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        Ok(true)
+    }
+
+    /// The group's block list.
+    pub async fn blocked_members(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Vec<BlockedMember>, String> {
+        let provider = self.load_label(APP_GROUP_LABEL, Some(&group_id_bytes)).await?;
+        Ok(blocked_members(provider.storage(), &group_id_bytes)?
+            .into_iter()
+            .map(|entry| BlockedMember { credential: entry.credential, suppress_messages: entry.suppress_messages })
+            .collect())
+    }
+
+    /// Encrypt an application message. `wire_format` may only be
+    /// `Ciphertext`: RFC 9420 forbids application data in `PublicMessage`.
+    pub async fn create_message(
+        &self,
+        group_id_bytes: Vec<u8>,
+        signer_bytes: Vec<u8>,
+        message: Vec<u8>,
+        aad: Option<Vec<u8>>,
+        wire_format: Option<MlsWireFormat>,
+    ) -> Result<CreateMessageResult, String> {
+        if matches!(wire_format, Some(MlsWireFormat::Plaintext)) {
+            return Err("Application messages cannot be sent as plaintext".to_string());
+        }
+        let signer = self.signer(signer_bytes)?;
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let mut group = load_group(&group_id_bytes, &provider)?;
+
+        apply_aad(&mut group, provider.storage(), &group_id_bytes, aad)?;
+
+        let (plaintext, compression) = if compression_enabled(&group)? {
+            let (plaintext, compressed) = crate::compression::compress(&message);
+            let info = MessageCompressionInfo {
+                compressed,
+                original_size: message.len() as u64,
+                compressed_size: plaintext.len() as u64,
+            };
+            (plaintext, Some(info))
+        } else {
+            (message, None)
+        };
+
+        let msg_out = group.create_message(&provider, &signer, &plaintext)
+            .map_err(|e| format!("Failed to create message: {}", e))?;
+        let ciphertext = msg_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize message: {}", e))?;
+        record_activity(provider.storage_mut(), &group_id_bytes, GroupActivityKind::Sent)?;
 
         self.commit(provider, Some(&group_id_bytes)).await?;
 
-        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes })
+        Ok(CreateMessageResult { ciphertext, wire_format: MlsWireFormat::Ciphertext, compression })
+    }
+
+    /// Process protocol messages of any number of groups, routed by the
+    /// group ID each message carries.
+    ///
+    /// Messages are processed in the order of `mls_message_processing_order`,
+    /// so a batch fetched out of order still applies, and reported
+    /// individually in list order: a message that fails leaves its group as
+    /// it was and does not affect the others. Each group is loaded once,
+    /// and all changes are persisted in one storage transaction at the end.
+    pub async fn process_messages_batch(
+        &self,
+        messages_bytes: Vec<Vec<u8>>,
+    ) -> Result<Vec<BatchMessageResult>, String> {
+        let mut providers: std::collections::BTreeMap<Vec<u8>, SnapshotOpenMlsProvider> =
+            std::collections::BTreeMap::new();
+        let mut batches = Vec::new();
+        let mut events = Vec::new();
+        let mut results = Vec::with_capacity(messages_bytes.len());
+        for index in processing_order(&messages_bytes) {
+            let message_bytes = &messages_bytes[index as usize];
+            let group_id = match mls_message_extract_group_id(message_bytes.clone()) {
+                Ok(group_id) => group_id,
+                Err(e) => {
+                    results.push(BatchMessageResult { index, group_id: None, result: None, error: Some(e) });
+                    continue;
+                }
+            };
+            if !providers.contains_key(&group_id) {
+                let provider = self.load_for_group(&group_id).await?;
+                providers.insert(group_id.clone(), provider);
+            }
+            let provider = providers.get_mut(&group_id).expect("provider loaded above");
+            match self.process_loaded_message(provider, &group_id, message_bytes, None, None).await {
+                Ok((result, event)) => {
+                    batches.push((provider.storage_mut().checkpoint(), Some(group_id.clone())));
+                    events.push(event);
+                    results.push(BatchMessageResult { index, group_id: Some(group_id), result: Some(result), error: None });
+                }
+                Err(e) => {
+                    provider.storage_mut().rollback();
+                    results.push(BatchMessageResult { index, group_id: Some(group_id), result: None, error: Some(e.message) });
+                }
+            }
+        }
+
+        results.sort_by_key(|result| result.index);
+        batches.retain(|(updates, _)| !updates.upserts.is_empty() || !updates.deletes.is_empty());
+        if !batches.is_empty() {
+            self.save_batches(batches).await?;
+        }
+        for event in events {
+            self.emit_epoch_event(event);
+        }
+        Ok(results)
+    }
+
+    /// `sent_at` is the message's send time in Unix seconds, e.g. from the
+    /// delivery service envelope, used by the handshake expiry policy.
+    /// `sync_cursor`, if given, is stored as the group's sync cursor in the
+    /// same transaction as the processed message (see `set_sync_cursor`).
+    pub async fn process_message(
+        &self,
+        group_id_bytes: Vec<u8>,
+        message_bytes: Vec<u8>,
+        sent_at: Option<u64>,
+        sync_cursor: Option<Vec<u8>>,
+    ) -> Result<ProcessedMessageResult, String> {
+        super::v2::v1_shim("process_message")?;
+        Ok(self.process_and_commit(group_id_bytes, message_bytes, sent_at, sync_cursor).await?)
     }
 
-    pub async fn swap_members(
+    pub(crate) async fn process_and_commit(
         &self,
         group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-        remove_indices: Vec<u32>,
-        add_key_packages_bytes: Vec<Vec<u8>>,
-    ) -> Result<AddMembersResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let mut group = load_group(&group_id_bytes, &provider)?;
+        message_bytes: Vec<u8>,
+        sent_at: Option<u64>,
+        sync_cursor: Option<Vec<u8>>,
+    ) -> Result<ProcessedMessageResult, MlsApiError> {
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let (result, event) = self
+            .process_loaded_message(&mut provider, &group_id_bytes, &message_bytes, sent_at, sync_cursor)
+            .await?;
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
+        Ok(result)
+    }
 
-        let indices: Vec<LeafNodeIndex> = remove_indices.iter().map(|&i| LeafNodeIndex::new(i)).collect();
-        let mut key_packages = Vec::with_capacity(add_key_packages_bytes.len());
-        for kp_bytes in add_key_packages_bytes {
-            let kp_in = KeyPackageIn::tls_deserialize_exact_bytes(&kp_bytes)
-                .map_err(|e| format!("Failed to deserialize key package: {}", e))?;
-            let kp = kp_in.validate(provider.crypto(), ProtocolVersion::Mls10)
-                .map_err(|e| format!("Failed to validate key package: {}", e))?;
-            key_packages.push(kp);
+    /// Process one message against `provider`, which holds the group's
+    /// state. Outcomes that apply nothing (`Duplicate`, `MissingProposals`,
+    /// `Expired`) roll the provider back to its last checkpoint; on error
+    /// the caller must discard or roll back the provider itself.
+    ///
+    /// Returns the epoch event to emit once the changes are persisted.
+    async fn process_loaded_message(
+        &self,
+        provider: &mut SnapshotOpenMlsProvider,
+        group_id_bytes: &[u8],
+        message_bytes: &[u8],
+        sent_at: Option<u64>,
+        sync_cursor: Option<Vec<u8>>,
+    ) -> Result<(ProcessedMessageResult, Option<EpochAdvancedEvent>), MlsApiError> {
+        let mut group = load_group(group_id_bytes, provider)?;
+
+        let msg_in = MlsMessageIn::tls_deserialize_exact_bytes(message_bytes)
+            .map_err(|e| MlsApiError::new(MlsErrorKind::InvalidInput, format!("Failed to deserialize message: {}", e)))?;
+        let protocol_msg = msg_in.try_into_protocol_message()
+            .map_err(|e| MlsApiError::new(MlsErrorKind::InvalidInput, format!("Not a protocol message: {}", e)))?;
+        let is_plaintext = matches!(protocol_msg, ProtocolMessage::PublicMessage(_));
+        let message_epoch = protocol_msg.epoch().as_u64();
+
+        let processed = match process_protocol_message(&mut group, provider, protocol_msg)? {
+            Ok(processed) => processed,
+            Err(e) if is_duplicate_delivery(provider.storage(), group_id_bytes, &e)? => {
+                provider.storage_mut().rollback();
+                return Ok((ProcessedMessageResult {
+                    message_type: ProcessedMessageType::Duplicate,
+                    sender_index: None,
+                    sender_handle: None,
+                    epoch: message_epoch,
+                    application_message: None,
+                    aad: Vec::new(),
+                    has_staged_commit: false,
+                    has_proposal: false,
+                    proposal_type: None,
+                    commit_psks: Vec::new(),
+                    compression: None,
+                    padding: None,
+                    missing_proposal_refs: None,
+                    expired: None,
+                    credential_verified: false,
+                    blocked: false,
+                    unauthorized_features_change: false,
+                }, None));
+            }
+            Err(ProcessMessageError::InvalidCommit(StageCommitError::MissingProposal)) => {
+                provider.storage_mut().rollback();
+                return Ok((ProcessedMessageResult {
+                    message_type: ProcessedMessageType::MissingProposals,
+                    sender_index: None,
+                    sender_handle: None,
+                    epoch: message_epoch,
+                    application_message: None,
+                    aad: Vec::new(),
+                    has_staged_commit: false,
+                    has_proposal: false,
+                    proposal_type: None,
+                    commit_psks: Vec::new(),
+                    compression: None,
+                    padding: None,
+                    missing_proposal_refs: missing_proposal_refs(&group, message_bytes)?,
+                    expired: None,
+                    credential_verified: false,
+                    blocked: false,
+                    unauthorized_features_change: false,
+                }, None));
+            }
+            Err(e) => {
+                return Err(MlsApiError::new(MlsErrorKind::Rejected, format!("Failed to process message: {}", e)));
+            }
+        };
+        if is_plaintext && matches!(processed.content(), ProcessedMessageContent::ApplicationMessage(_)) {
+            self.conformance_deviation("application message sent as plaintext")?;
+        }
+        if let Some(expired) = self.expired_commit(&processed, sent_at)? {
+            let sender_index = match processed.sender() {
+                Sender::Member(idx) => Some(idx.u32()),
+                _ => None,
+            };
+            let sender_handle = sender_handle(&group, provider, processed.sender(), Some(processed.credential()))?;
+            provider.storage_mut().rollback();
+            return Ok((ProcessedMessageResult {
+                message_type: ProcessedMessageType::Expired,
+                sender_index,
+                sender_handle,
+                epoch: message_epoch,
+                application_message: None,
+                aad: processed.aad().to_vec(),
+                has_staged_commit: false,
+                has_proposal: false,
+                proposal_type: None,
+                commit_psks: Vec::new(),
+                compression: None,
+                padding: None,
+                missing_proposal_refs: None,
+                expired: Some(expired),
+                credential_verified: false,
+                blocked: false,
+                unauthorized_features_change: false,
+            }, None));
         }
 
-        let result = group.swap_members(&provider, &signer, &indices, &key_packages)
-            .map_err(|e| format!("Failed to swap members: {}", e))?;
-        group.merge_pending_commit(&provider).map_err(|e| format!("Failed to merge pending commit: {}", e))?;
-
-        let commit_bytes = result.commit.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
-        let welcome_bytes = result.welcome.tls_serialize_detached().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
-        let gi_bytes = result.group_info.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        let sender = processed.sender().clone();
+        let aad = processed.aad().to_vec();
+        let sender_index = match &sender {
+            Sender::Member(idx) => Some(idx.u32()),
+            _ => None,
+        };
+        // Before merging: a commit may remove or move the sender.
+        let sender_handle = sender_handle(&group, provider, &sender, Some(processed.credential()))?;
+        let epoch = group.epoch().as_u64();
+        let block = sender_block(&group, provider.storage(), &sender)?;
+        let blocked = block.is_some();
+        let suppress = block.is_some_and(|entry| entry.suppress_messages);
+
+        let mut compression = None;
+        let mut padding = None;
+        let mut credential_verified = true;
+        let mut unauthorized_features_change = false;
+        let mut psks = Vec::new();
+        let (message_type, application_message, has_staged_commit, has_proposal, proposal_type) =
+            match processed.into_content() {
+                ProcessedMessageContent::ApplicationMessage(app_msg) => {
+                    let plaintext = app_msg.into_bytes();
+                    padding = application_message_padding(&group, message_bytes, plaintext.len())?;
+                    let (message, info) =
+                        decompress_application_message(&group, provider.storage(), message_epoch, plaintext)?;
+                    compression = info;
+                    if let Some(idx) = sender_index {
+                        advance_watermark(provider.storage_mut(), group_id_bytes, idx, message_epoch)?;
+                    }
+                    record_activity(provider.storage_mut(), group_id_bytes, GroupActivityKind::Received)?;
+                    (ProcessedMessageType::Application, (!suppress).then_some(message), false, false, None)
+                }
+                ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
+                    unauthorized_features_change = unauthorized_group_features_change(&group, &sender, &staged_commit)?;
+                    credential_verified = self
+                        .verify_credentials(commit_credential_checks(&group, &sender, &staged_commit)?)
+                        .await?;
+                    for deviation in commit_conformance_deviations(&group, &staged_commit)? {
+                        self.conformance_deviation(&deviation)?;
+                    }
+                    psks = commit_psks(&staged_commit)?;
+                    let merge = self.before_merge(&group, provider, &staged_commit, sender_index, false)?;
+                    group.merge_staged_commit(&*provider, *staged_commit)
+                        .map_err(|e| MlsApiError::new(MlsErrorKind::Rejected, format!("Failed to merge staged commit: {}", e)))?;
+                    self.after_merge(&group, provider, merge)?;
+                    (ProcessedMessageType::StagedCommit, None, true, false, None)
+                }
+                ProcessedMessageContent::ProposalMessage(queued_proposal) => {
+                    let prop_type = proposal_type_of(queued_proposal.proposal());
+                    group.store_pending_proposal(provider.storage(), *queued_proposal)
+                        .map_err(|e| format!("Failed to store pending proposal: {}", e))?;
+                    (ProcessedMessageType::Proposal, None, false, true, Some(prop_type))
+                }
+                _ => return Err("Unknown processed message content type".to_string()),
+            };
 
-        self.commit(provider, Some(&group_id_bytes)).await?;
+        let event = match message_type {
+            ProcessedMessageType::StagedCommit => self.epoch_event(&group, provider)?,
+            _ => None,
+        };
+        if let Some(cursor) = sync_cursor {
+            provider.storage_mut().write_app_group(group_id_bytes, SYNC_CURSOR, &cursor)
+                .map_err(|e| format!("Failed to write sync cursor: {}", e))?;
+        }
 
-        Ok(AddMembersResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes })
+        let result = ProcessedMessageResult {
+            message_type, sender_index, sender_handle, epoch, application_message, aad, has_staged_commit, has_proposal, proposal_type, compression, padding,
+            commit_psks: psks,
+            missing_proposal_refs: None,
+            expired: None,
+            credential_verified,
+            blocked,
+            unauthorized_features_change,
+        };
+        Ok((result, event))
     }
 
-    pub async fn leave_group(
+    /// `sent_at` is the message's send time in Unix seconds, e.g. from the
+    /// delivery service envelope, used by the handshake expiry policy.
+    /// `sync_cursor`, if given, is stored as the group's sync cursor in the
+    /// same transaction as the processed message (see `set_sync_cursor`).
+    pub async fn process_message_with_inspect(
         &self,
         group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-    ) -> Result<LeaveGroupResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let mut group = load_group(&group_id_bytes, &provider)?;
-
-        let msg = group.leave_group(&provider, &signer).map_err(|e| format!("Failed to leave group: {}", e))?;
-        let msg_bytes = msg.tls_serialize_detached().map_err(|e| format!("Failed to serialize leave message: {}", e))?;
-
-        self.commit(provider, Some(&group_id_bytes)).await?;
+        message_bytes: Vec<u8>,
+        sent_at: Option<u64>,
+        sync_cursor: Option<Vec<u8>>,
+    ) -> Result<ProcessedMessageInspectResult, String> {
+        super::v2::v1_shim("process_message_with_inspect")?;
+        Ok(self.inspect_message(group_id_bytes, message_bytes, sent_at, sync_cursor, false).await?)
+    }
 
-        Ok(LeaveGroupResult { message: msg_bytes })
+    /// Like `process_message_with_inspect`, except that commits are staged
+    /// and held instead of merged, so the application can review
+    /// `staged_commit_info` (e.g. check that the sender may remove members)
+    /// before accepting the commit with `merge_staged_commit_by_ref` or
+    /// dropping it with `reject_staged_commit`. Other messages are processed
+    /// as usual.
+    ///
+    /// A held commit can only be merged in the epoch it was staged in; it is
+    /// discarded once the group advances by other means, e.g. by merging a
+    /// competing commit.
+    pub async fn process_message_staged(
+        &self,
+        group_id_bytes: Vec<u8>,
+        message_bytes: Vec<u8>,
+        sent_at: Option<u64>,
+        sync_cursor: Option<Vec<u8>>,
+    ) -> Result<ProcessedMessageInspectResult, String> {
+        super::v2::v1_shim("process_message_staged")?;
+        Ok(self.inspect_message(group_id_bytes, message_bytes, sent_at, sync_cursor, true).await?)
     }
 
-    pub async fn leave_group_via_self_remove(
+    /// Merge a commit held by `process_message_staged`. Other commits held
+    /// for the same epoch become stale and are discarded.
+    pub async fn merge_staged_commit_by_ref(
         &self,
         group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-    ) -> Result<LeaveGroupResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_for_group(&group_id_bytes).await?;
+        staged_commit_ref: Vec<u8>,
+    ) -> Result<(), String> {
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
         let mut group = load_group(&group_id_bytes, &provider)?;
-
-        let msg = group.leave_group_via_self_remove(&provider, &signer).map_err(|e| format!("Failed to leave group via self-remove: {}", e))?;
-        let msg_bytes = msg.tls_serialize_detached().map_err(|e| format!("Failed to serialize leave message: {}", e))?;
-
+        let entry = read_held_staged_commits(provider.storage(), &group)?
+            .into_iter()
+            .find(|entry| entry.reference == staged_commit_ref)
+            .ok_or_else(|| "No staged commit held under this reference".to_string())?;
+
+        let merge = self.before_merge(&group, &provider, &entry.staged_commit, entry.sender_index, false)?;
+        group.merge_staged_commit(&provider, entry.staged_commit)
+            .map_err(|e| format!("Failed to merge staged commit: {}", e))?;
+        write_held_staged_commits(provider.storage_mut(), &group_id_bytes, &[])?;
+        self.after_merge(&group, &mut provider, merge)?;
+
+        let event = self.epoch_event(&group, &provider)?;
         self.commit(provider, Some(&group_id_bytes)).await?;
-
-        Ok(LeaveGroupResult { message: msg_bytes })
+        self.emit_epoch_event(event);
+        Ok(())
     }
 
-    // ═══════════════════════════════════════════════════════════
-    // PROPOSALS (mutating)
-    // ═══════════════════════════════════════════════════════════
+    /// Discard a commit held by `process_message_staged`; the group stays in
+    /// its epoch. Returns false if no commit is held under `staged_commit_ref`.
+    pub async fn reject_staged_commit(
+        &self,
+        group_id_bytes: Vec<u8>,
+        staged_commit_ref: Vec<u8>,
+    ) -> Result<bool, String> {
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        let mut held = read_held_staged_commits(provider.storage(), &group)?;
+        let count = held.len();
+        held.retain(|entry| entry.reference != staged_commit_ref);
+        if held.len() == count {
+            return Ok(false);
+        }
+        write_held_staged_commits(provider.storage_mut(), &group_id_bytes, &held)?;
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        Ok(true)
+    }
 
-    pub async fn propose_add(
+    pub(crate) async fn inspect_message(
         &self,
         group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-        key_package_bytes: Vec<u8>,
-    ) -> Result<ProposalResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_for_group(&group_id_bytes).await?;
+        message_bytes: Vec<u8>,
+        sent_at: Option<u64>,
+        sync_cursor: Option<Vec<u8>>,
+        hold_commits: bool,
+    ) -> Result<ProcessedMessageInspectResult, MlsApiError> {
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
         let mut group = load_group(&group_id_bytes, &provider)?;
 
-        let kp_in = KeyPackageIn::tls_deserialize_exact_bytes(&key_package_bytes)
-            .map_err(|e| format!("Failed to deserialize key package: {}", e))?;
-        let kp = kp_in.validate(provider.crypto(), ProtocolVersion::Mls10)
-            .map_err(|e| format!("Failed to validate key package: {}", e))?;
+        let msg_in = MlsMessageIn::tls_deserialize_exact_bytes(&message_bytes)
+            .map_err(|e| MlsApiError::new(MlsErrorKind::InvalidInput, format!("Failed to deserialize message: {}", e)))?;
+        let protocol_msg = msg_in.try_into_protocol_message()
+            .map_err(|e| MlsApiError::new(MlsErrorKind::InvalidInput, format!("Not a protocol message: {}", e)))?;
+        let is_plaintext = matches!(protocol_msg, ProtocolMessage::PublicMessage(_));
+        let message_epoch = protocol_msg.epoch().as_u64();
+
+        let processed = match process_protocol_message(&mut group, &provider, protocol_msg)? {
+            Ok(processed) => processed,
+            Err(e) if is_duplicate_delivery(provider.storage(), &group_id_bytes, &e)? => {
+                return Ok(ProcessedMessageInspectResult {
+                    message_type: ProcessedMessageType::Duplicate,
+                    sender_index: None,
+                    sender_handle: None,
+                    epoch: message_epoch,
+                    application_message: None,
+                    aad: Vec::new(),
+                    staged_commit_info: None,
+                    proposal_type: None,
+                    compression: None,
+                    padding: None,
+                    missing_proposal_refs: None,
+                    expired: None,
+                    credential_verified: false,
+                    blocked: false,
+                    unauthorized_features_change: false,
+                    staged_commit_ref: None,
+                });
+            }
+            Err(ProcessMessageError::InvalidCommit(StageCommitError::MissingProposal)) => {
+                return Ok(ProcessedMessageInspectResult {
+                    message_type: ProcessedMessageType::MissingProposals,
+                    sender_index: None,
+                    sender_handle: None,
+                    epoch: message_epoch,
+                    application_message: None,
+                    aad: Vec::new(),
+                    staged_commit_info: None,
+                    proposal_type: None,
+                    compression: None,
+                    padding: None,
+                    missing_proposal_refs: missing_proposal_refs(&group, &message_bytes)?,
+                    expired: None,
+                    credential_verified: false,
+                    blocked: false,
+                    unauthorized_features_change: false,
+                    staged_commit_ref: None,
+                });
+            }
+            Err(e) => {
+                return Err(MlsApiError::new(MlsErrorKind::Rejected, format!("Failed to process message: {}", e)));
+            }
+        };
+        if is_plaintext && matches!(processed.content(), ProcessedMessageContent::ApplicationMessage(_)) {
+            self.conformance_deviation("application message sent as plaintext")?;
+        }
+        if let Some(expired) = self.expired_commit(&processed, sent_at)? {
+            let sender_index = match processed.sender() {
+                Sender::Member(idx) => Some(idx.u32()),
+                _ => None,
+            };
+            let sender_handle = sender_handle(&group, &provider, processed.sender(), Some(processed.credential()))?;
+            return Ok(ProcessedMessageInspectResult {
+                message_type: ProcessedMessageType::Expired,
+                sender_index,
+                sender_handle,
+                epoch: message_epoch,
+                application_message: None,
+                aad: processed.aad().to_vec(),
+                staged_commit_info: None,
+                proposal_type: None,
+                compression: None,
+                padding: None,
+                missing_proposal_refs: None,
+                expired: Some(expired),
+                credential_verified: false,
+                blocked: false,
+                unauthorized_features_change: false,
+                staged_commit_ref: None,
+            });
+        }
 
-        let (proposal_out, _) = group.propose_add_member(&provider, &signer, &kp)
-            .map_err(|e| format!("Failed to propose add: {}", e))?;
-        let msg_bytes = proposal_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal: {}", e))?;
+        let sender = processed.sender().clone();
+        let aad = processed.aad().to_vec();
+        let sender_index = match &sender {
+            Sender::Member(idx) => Some(idx.u32()),
+            _ => None,
+        };
+        // Before merging: a commit may remove or move the sender.
+        let sender_handle = sender_handle(&group, &provider, &sender, Some(processed.credential()))?;
+        let epoch = group.epoch().as_u64();
+        let block = sender_block(&group, provider.storage(), &sender)?;
+        let blocked = block.is_some();
+        let suppress = block.is_some_and(|entry| entry.suppress_messages);
+
+        let mut compression = None;
+        let mut padding = None;
+        let mut credential_verified = true;
+        let mut unauthorized_features_change = false;
+        let mut staged_commit_ref = None;
+        let (message_type, application_message, staged_commit_info, proposal_type) =
+            match processed.into_content() {
+                ProcessedMessageContent::ApplicationMessage(app_msg) => {
+                    let plaintext = app_msg.into_bytes();
+                    padding = application_message_padding(&group, &message_bytes, plaintext.len())?;
+                    let (message, info) =
+                        decompress_application_message(&group, provider.storage(), message_epoch, plaintext)?;
+                    compression = info;
+                    if let Some(idx) = sender_index {
+                        advance_watermark(provider.storage_mut(), &group_id_bytes, idx, message_epoch)?;
+                    }
+                    record_activity(provider.storage_mut(), &group_id_bytes, GroupActivityKind::Received)?;
+                    (ProcessedMessageType::Application, (!suppress).then_some(message), None, None)
+                }
+                ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
+                    let mut add_credentials = Vec::new();
+                    for add in staged_commit.add_proposals() {
+                        let kp = add.add_proposal().key_package();
+                        let cred_bytes = kp.leaf_node().credential()
+                            .tls_serialize_detached()
+                            .map_err(|e| format!("Failed to serialize add credential: {}", e))?;
+                        add_credentials.push(cred_bytes);
+                    }
+                    let mut remove_indices = Vec::new();
+                    let mut remove_handles = Vec::new();
+                    for remove in staged_commit.remove_proposals() {
+                        let removed = remove.remove_proposal().removed();
+                        let member = group
+                            .member_at(removed)
+                            .ok_or_else(|| format!("Removed leaf {} is not a member", removed.u32()))?;
+                        remove_indices.push(removed.u32());
+                        remove_handles.push(member_info(&provider, &member)?.member_handle);
+                    }
+                    let has_update = staged_commit.update_proposals().next().is_some();
+                    let self_removed = staged_commit.self_removed();
+                    let psks = commit_psks(&staged_commit)?;
+                    let psk_count = psks.len() as u32;
+                    let external_join = external_join_info(&group, &provider, &sender, &staged_commit)?;
+                    let info = StagedCommitInfo {
+                        add_credentials,
+                        remove_indices,
+                        remove_handles,
+                        has_update,
+                        self_removed,
+                        psk_count,
+                        psks,
+                        external_join,
+                    };
+                    unauthorized_features_change = unauthorized_group_features_change(&group, &sender, &staged_commit)?;
+                    credential_verified = self
+                        .verify_credentials(commit_credential_checks(&group, &sender, &staged_commit)?)
+                        .await?;
+                    for deviation in commit_conformance_deviations(&group, &staged_commit)? {
+                        self.conformance_deviation(&deviation)?;
+                    }
+
+                    if hold_commits {
+                        let reference = provider.crypto()
+                            .hash(HashType::Sha2_256, &message_bytes)
+                            .map_err(|e| format!("Failed to hash commit: {:?}", e))?;
+                        let mut held = read_held_staged_commits(provider.storage(), &group)?;
+                        // A re-delivered commit replaces its earlier copy.
+                        held.retain(|entry| entry.reference != reference);
+                        held.push(HeldStagedCommit {
+                            reference: reference.clone(),
+                            epoch: group.epoch().as_u64(),
+                            sender_index,
+                            staged_commit: *staged_commit,
+                        });
+                        write_held_staged_commits(provider.storage_mut(), &group_id_bytes, &held)?;
+                        staged_commit_ref = Some(reference);
+                    } else {
+                        let merge = self.before_merge(&group, &provider, &staged_commit, sender_index, false)?;
+                        group.merge_staged_commit(&provider, *staged_commit)
+                            .map_err(|e| MlsApiError::new(MlsErrorKind::Rejected, format!("Failed to merge staged commit: {}", e)))?;
+                        self.after_merge(&group, &mut provider, merge)?;
+                    }
+                    (ProcessedMessageType::StagedCommit, None, Some(info), None)
+                }
+                ProcessedMessageContent::ProposalMessage(queued_proposal) => {
+                    let prop_type = proposal_type_of(queued_proposal.proposal());
+                    group.store_pending_proposal(provider.storage(), *queued_proposal)
+                        .map_err(|e| format!("Failed to store pending proposal: {}", e))?;
+                    (ProcessedMessageType::Proposal, None, None, Some(prop_type))
+                }
+                _ => return Err("Unknown processed message content type".to_string()),
+            };
 
+        let event = match message_type {
+            ProcessedMessageType::StagedCommit if staged_commit_ref.is_none() => self.epoch_event(&group, &provider)?,
+            _ => None,
+        };
+        if let Some(cursor) = sync_cursor {
+            provider.storage_mut().write_app_group(&group_id_bytes, SYNC_CURSOR, &cursor)
+                .map_err(|e| format!("Failed to write sync cursor: {}", e))?;
+        }
         self.commit(provider, Some(&group_id_bytes)).await?;
+        self.emit_epoch_event(event);
 
-        Ok(ProposalResult { proposal_message: msg_bytes })
+        Ok(ProcessedMessageInspectResult {
+            message_type, sender_index, sender_handle, epoch, application_message, aad, staged_commit_info, proposal_type, compression, padding,
+            missing_proposal_refs: None,
+            expired: None,
+            credential_verified,
+            blocked,
+            unauthorized_features_change,
+            staged_commit_ref,
+        })
     }
 
-    pub async fn propose_remove(
+    /// Store a proposal message that a commit referenced but we never
+    /// received (see `ProcessedMessageType::MissingProposals`), then process
+    /// the commit again.
+    ///
+    /// Returns the TLS-serialized ref of the stored proposal. Fails for
+    /// anything but a proposal of the group's current epoch.
+    pub async fn inject_missing_proposal(
         &self,
         group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-        member_index: u32,
-    ) -> Result<ProposalResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
+        proposal_bytes: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
         let provider = self.load_for_group(&group_id_bytes).await?;
         let mut group = load_group(&group_id_bytes, &provider)?;
 
-        let (proposal_out, _) = group.propose_remove_member(&provider, &signer, LeafNodeIndex::new(member_index))
-            .map_err(|e| format!("Failed to propose remove: {}", e))?;
-        let msg_bytes = proposal_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal: {}", e))?;
+        let msg_in = MlsMessageIn::tls_deserialize_exact_bytes(&proposal_bytes)
+            .map_err(|e| format!("Failed to deserialize message: {}", e))?;
+        let protocol_msg = msg_in.try_into_protocol_message()
+            .map_err(|e| format!("Not a protocol message: {}", e))?;
+        if !matches!(protocol_msg.content_type(), ContentType::Proposal) {
+            return Err("Message is not a proposal".to_string());
+        }
+        let processed = process_protocol_message(&mut group, &provider, protocol_msg)?
+            .map_err(|e| format!("Failed to process proposal: {}", e))?;
+        let ProcessedMessageContent::ProposalMessage(queued_proposal) = processed.into_content() else {
+            return Err("Message is not a proposal".to_string());
+        };
+        let proposal_ref = queued_proposal.proposal_reference()
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize proposal ref: {}", e))?;
+        group.store_pending_proposal(provider.storage(), *queued_proposal)
+            .map_err(|e| format!("Failed to store pending proposal: {}", e))?;
 
         self.commit(provider, Some(&group_id_bytes)).await?;
-
-        Ok(ProposalResult { proposal_message: msg_bytes })
+        Ok(proposal_ref)
     }
 
-    pub async fn propose_self_update(
+    /// Check whether `process_message` would accept a message, without
+    /// changing any state.
+    ///
+    /// Covers framing, the epoch window, membership and signatures, and for
+    /// private messages decryption. The message is processed against a
+    /// scratch copy of the group state that is discarded afterwards, so no
+    /// ratchet keys are consumed and nothing is stored. Rejections are
+    /// reported in the result; `Err` is reserved for engine failures such as
+    /// an unknown group.
+    pub async fn validate_message(
         &self,
         group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-        leaf_node_capabilities: Option<MlsCapabilities>,
-        leaf_node_extensions: Option<Vec<MlsExtension>>,
-    ) -> Result<ProposalResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
+        message_bytes: Vec<u8>,
+    ) -> Result<MessageValidationResult, String> {
         let provider = self.load_for_group(&group_id_bytes).await?;
         let mut group = load_group(&group_id_bytes, &provider)?;
 
-        let mut ln_builder = LeafNodeParameters::builder();
-        if let Some(ref caps) = leaf_node_capabilities {
-            ln_builder = ln_builder.with_capabilities(capabilities_to_native(caps)?);
+        let mut result = MessageValidationResult {
+            valid: false,
+            error: None,
+            epoch: None,
+            message_type: None,
+            sender_index: None,
+        };
+
+        let protocol_msg = match MlsMessageIn::tls_deserialize_exact_bytes(&message_bytes)
+            .map_err(|e| format!("Failed to deserialize message: {}", e))
+            .and_then(|msg_in| msg_in.try_into_protocol_message().map_err(|e| format!("Not a protocol message: {}", e)))
+        {
+            Ok(msg) => msg,
+            Err(e) => {
+                result.error = Some(e);
+                return Ok(result);
+            }
+        };
+        result.epoch = Some(protocol_msg.epoch().as_u64());
+        if protocol_msg.group_id().as_slice() != group_id_bytes.as_slice() {
+            result.error = Some("Message is for a different group".to_string());
+            return Ok(result);
         }
-        if let Some(ref exts) = leaf_node_extensions {
-            let extensions = Extensions::from_vec(extensions_from_mls(exts))
-                .map_err(|e| format!("Failed to create leaf node extensions: {}", e))?;
-            ln_builder = ln_builder.with_extensions(extensions);
+        if protocol_msg.epoch().as_u64() > group.epoch().as_u64() {
+            result.error = Some(format!(
+                "Message epoch {} is ahead of group epoch {}",
+                protocol_msg.epoch().as_u64(),
+                group.epoch().as_u64()
+            ));
+            return Ok(result);
         }
-        let leaf_node_params = ln_builder.build();
+        let is_plaintext = matches!(protocol_msg, ProtocolMessage::PublicMessage(_));
 
-        let (proposal_out, _) = group.propose_self_update(&provider, &signer, leaf_node_params)
-            .map_err(|e| format!("Failed to propose self-update: {}", e))?;
-        let msg_bytes = proposal_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal: {}", e))?;
-
-        self.commit(provider, Some(&group_id_bytes)).await?;
+        let processed = match process_protocol_message(&mut group, &provider, protocol_msg)? {
+            Ok(processed) => processed,
+            Err(e) => {
+                result.error = Some(format!("Failed to process message: {}", e));
+                return Ok(result);
+            }
+        };
+        let sender = processed.sender().clone();
+        if let Sender::Member(idx) = &sender {
+            result.sender_index = Some(idx.u32());
+        }
 
-        Ok(ProposalResult { proposal_message: msg_bytes })
+        let check = match processed.content() {
+            ProcessedMessageContent::ApplicationMessage(_) => {
+                result.message_type = Some(ProcessedMessageType::Application);
+                if is_plaintext {
+                    self.conformance_deviation("application message sent as plaintext").map_err(String::from)
+                } else {
+                    Ok(())
+                }
+            }
+            ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
+                result.message_type = Some(ProcessedMessageType::StagedCommit);
+                commit_conformance_deviations(&group, staged_commit).and_then(|deviations| {
+                    deviations
+                        .iter()
+                        .try_for_each(|deviation| self.conformance_deviation(deviation).map_err(String::from))
+                })
+            }
+            ProcessedMessageContent::ProposalMessage(_) => {
+                result.message_type = Some(ProcessedMessageType::Proposal);
+                Ok(())
+            }
+            _ => Err("Unknown processed message content type".to_string()),
+        };
+        match check {
+            Ok(()) => result.valid = true,
+            Err(e) => result.error = Some(e),
+        }
+        // `provider` is dropped without `commit`: the scratch state is discarded.
+        Ok(result)
     }
 
-    pub async fn propose_external_psk(
+    /// Whether messages from `epoch` can still be decrypted: true for the
+    /// current epoch and for past epochs whose message secrets are retained
+    /// (see `max_past_epochs`).
+    ///
+    /// Lets the app tell an undecryptable old message apart before calling
+    /// `process_message`. A retained epoch can still reject individual
+    /// messages, e.g. replays or generations outside the sender ratchet
+    /// window; `decryption_failure_report` explains those.
+    pub async fn has_decryption_keys_for_epoch(
         &self,
         group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-        psk_id: Vec<u8>,
-        psk_nonce: Vec<u8>,
-    ) -> Result<ProposalResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
+        epoch: u64,
+    ) -> Result<bool, String> {
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let mut group = load_group(&group_id_bytes, &provider)?;
-
-        let psk = PreSharedKeyId::external(psk_id, psk_nonce);
-        let (proposal_out, _) = group.propose_external_psk(&provider, &signer, psk)
-            .map_err(|e| format!("Failed to propose external PSK: {}", e))?;
-        let msg_bytes = proposal_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal: {}", e))?;
-
-        self.commit(provider, Some(&group_id_bytes)).await?;
-
-        Ok(ProposalResult { proposal_message: msg_bytes })
+        let group = load_group(&group_id_bytes, &provider)?;
+        if epoch == group.epoch().as_u64() {
+            return Ok(true);
+        }
+        let retained = provider.storage()
+            .past_epoch_secret_epochs(group.group_id())
+            .map_err(|e| format!("Failed to read past epoch secrets: {}", e))?;
+        Ok(retained.contains(&epoch))
     }
 
-    pub async fn propose_group_context_extensions(
+    /// Explain why `process_message` fails for a message.
+    ///
+    /// Call after `process_message` rejected a message to tell replays,
+    /// out-of-window generations and forks apart. Like `validate_message`
+    /// this runs against a scratch copy of the group state and changes
+    /// nothing. Returns `None` if the message would be processed.
+    pub async fn decryption_failure_report(
         &self,
         group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-        extensions: Vec<MlsExtension>,
-    ) -> Result<ProposalResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
+        message_bytes: Vec<u8>,
+    ) -> Result<Option<DecryptionFailureReport>, String> {
         let provider = self.load_for_group(&group_id_bytes).await?;
         let mut group = load_group(&group_id_bytes, &provider)?;
 
-        let ext_vec: Vec<Extension> = extensions.iter().map(|ext| Extension::Unknown(ext.extension_type, UnknownExtension(ext.data.clone()))).collect();
-        let gc_extensions = Extensions::from_vec(ext_vec).map_err(|e| format!("Failed to create extensions: {}", e))?;
+        let msg_in = MlsMessageIn::tls_deserialize_exact_bytes(&message_bytes)
+            .map_err(|e| format!("Failed to deserialize message: {}", e))?;
+        let protocol_msg = msg_in.try_into_protocol_message()
+            .map_err(|e| format!("Not a protocol message: {}", e))?;
 
-        let (proposal_out, _) = group.propose_group_context_extensions(&provider, gc_extensions, &signer)
-            .map_err(|e| format!("Failed to propose group context extensions: {}", e))?;
-        let msg_bytes = proposal_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal: {}", e))?;
+        let message_epoch = protocol_msg.epoch().as_u64();
+        let group_epoch = group.epoch().as_u64();
+        let ratchet = group.configuration().sender_ratchet_configuration();
+        let handshake_tolerance = if uses_handshake_ratchet(&protocol_msg) {
+            handshake_out_of_order(provider.storage(), &group_id_bytes)?
+        } else {
+            None
+        };
+        let mut report = DecryptionFailureReport {
+            kind: DecryptionFailureKind::Other,
+            error: String::new(),
+            message_epoch,
+            group_epoch,
+            epoch_delta: message_epoch as i64 - group_epoch as i64,
+            sender_index: match &protocol_msg {
+                ProtocolMessage::PublicMessage(pm) => match pm.sender() {
+                    Sender::Member(idx) => Some(idx.u32()),
+                    _ => None,
+                },
+                _ => None,
+            },
+            out_of_order_tolerance: handshake_tolerance.unwrap_or(ratchet.out_of_order_tolerance()),
+            max_forward_distance: ratchet.maximum_forward_distance(),
+        };
 
-        self.commit(provider, Some(&group_id_bytes)).await?;
+        if protocol_msg.group_id().as_slice() != group_id_bytes.as_slice() {
+            report.kind = DecryptionFailureKind::WrongGroup;
+            report.error = "Message is for a different group".to_string();
+            return Ok(Some(report));
+        }
+        if message_epoch > group_epoch {
+            report.kind = DecryptionFailureKind::FutureEpoch;
+            report.error = format!("Message epoch {} is ahead of group epoch {}", message_epoch, group_epoch);
+            return Ok(Some(report));
+        }
+
+        // `provider` is dropped without `commit`: the scratch state is discarded.
+        match process_protocol_message(&mut group, &provider, protocol_msg)? {
+            Ok(_) => Ok(None),
+            Err(e) => {
+                report.kind = decryption_failure_kind(&e);
+                report.error = format!("Failed to process message: {}", e);
+                Ok(Some(report))
+            }
+        }
+    }
 
-        Ok(ProposalResult { proposal_message: msg_bytes })
+    /// Number of past epochs whose message secrets the group retains, so
+    /// that late messages from them can still be decrypted.
+    pub async fn max_past_epochs(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<u32, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        Ok(configured_max_past_epochs(&group)? as u32)
     }
 
-    pub async fn propose_custom_proposal(
+    /// Change `max_past_epochs` of an existing group.
+    ///
+    /// Lowering it immediately drops the message secrets of the oldest
+    /// retained epochs beyond the new limit. Raising it keeps more epochs
+    /// from the next epoch change on; epochs already dropped stay lost.
+    pub async fn set_max_past_epochs(
         &self,
         group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-        proposal_type: u16,
-        payload: Vec<u8>,
-    ) -> Result<ProposalResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_for_group(&group_id_bytes).await?;
+        max_past_epochs: u32,
+    ) -> Result<(), String> {
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
         let mut group = load_group(&group_id_bytes, &provider)?;
 
-        let custom = CustomProposal::new(proposal_type, payload);
-        let (proposal_out, _) = group.propose_custom_proposal_by_reference(&provider, &signer, custom)
-            .map_err(|e| format!("Failed to propose custom proposal: {}", e))?;
-        let msg_bytes = proposal_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal: {}", e))?;
+        // As in `override_wire_format`, the setting is only reachable in
+        // the serialized configuration.
+        let mut value = serde_json::to_value(group.configuration())
+            .map_err(|e| format!("Failed to serialize group configuration: {}", e))?;
+        value["max_past_epochs"] = serde_json::Value::from(max_past_epochs);
+        let config: MlsGroupJoinConfig = serde_json::from_value(value)
+            .map_err(|e| format!("Failed to deserialize group configuration: {}", e))?;
+        group.set_configuration(provider.storage(), &config)
+            .map_err(|e| format!("Failed to set configuration: {}", e))?;
+        provider.storage_mut()
+            .set_past_epoch_limit(group.group_id(), max_past_epochs as usize)
+            .map_err(|e| format!("Failed to write past epoch secrets: {}", e))?;
 
         self.commit(provider, Some(&group_id_bytes)).await?;
-
-        Ok(ProposalResult { proposal_message: msg_bytes })
+        Ok(())
     }
 
-    pub async fn propose_remove_member_by_credential(
+    /// Decrypt an application message from the current or a past epoch and
+    /// report which epoch it was sent in.
+    ///
+    /// Processes the message like `process_message`. A message the group
+    /// no longer holds keys for (see `max_past_epochs`), or that fails to
+    /// decrypt, is reported in `error` rather than failing the call; only
+    /// unparsable input, messages for another group and messages that are
+    /// not application messages are errors.
+    pub async fn decrypt_past_message(
         &self,
         group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-        credential_bytes: Vec<u8>,
-    ) -> Result<ProposalResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let mut group = load_group(&group_id_bytes, &provider)?;
+        message_bytes: Vec<u8>,
+    ) -> Result<PastMessageDecryption, String> {
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
 
-        let credential = Credential::tls_deserialize_exact_bytes(&credential_bytes)
-            .map_err(|e| format!("Failed to deserialize credential: {}", e))?;
-        let (proposal_out, _) = group.propose_remove_member_by_credential(&provider, &signer, &credential)
-            .map_err(|e| format!("Failed to propose remove by credential: {}", e))?;
-        let msg_bytes = proposal_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize proposal: {}", e))?;
+        let msg_in = MlsMessageIn::tls_deserialize_exact_bytes(&message_bytes)
+            .map_err(|e| format!("Failed to deserialize message: {}", e))?;
+        let protocol_msg = msg_in.try_into_protocol_message()
+            .map_err(|e| format!("Not a protocol message: {}", e))?;
+        if protocol_msg.group_id().as_slice() != group_id_bytes.as_slice() {
+            return Err("Message is for a different group".to_string());
+        }
+        if !matches!(protocol_msg.content_type(), ContentType::Application) {
+            return Err("Message is not an application message".to_string());
+        }
 
-        self.commit(provider, Some(&group_id_bytes)).await?;
+        let message_epoch = protocol_msg.epoch().as_u64();
+        let group_epoch = group.epoch().as_u64();
+        let keys_available = message_epoch == group_epoch
+            || provider.storage()
+                .past_epoch_secret_epochs(group.group_id())
+                .map_err(|e| format!("Failed to read past epoch secrets: {}", e))?
+                .contains(&message_epoch);
+        let mut outcome = PastMessageDecryption {
+            message_epoch,
+            group_epoch,
+            keys_available,
+            result: None,
+            error: None,
+        };
+        if message_epoch > group_epoch {
+            outcome.error = Some(format!("Message epoch {} is ahead of group epoch {}", message_epoch, group_epoch));
+            return Ok(outcome);
+        }
+        if !keys_available {
+            outcome.error = Some(format!(
+                "No message secrets retained for epoch {} (max_past_epochs is {})",
+                message_epoch,
+                configured_max_past_epochs(&group)?,
+            ));
+            return Ok(outcome);
+        }
 
-        Ok(ProposalResult { proposal_message: msg_bytes })
+        match self.process_loaded_message(&mut provider, &group_id_bytes, &message_bytes, None, None).await {
+            Ok((result, event)) => {
+                self.commit(provider, Some(&group_id_bytes)).await?;
+                self.emit_epoch_event(event);
+                outcome.result = Some(result);
+            }
+            // `provider` is dropped without `commit`.
+            Err(e) => outcome.error = Some(e.message),
+        }
+        Ok(outcome)
     }
 
     // ═══════════════════════════════════════════════════════════
-    // COMMIT / MERGE OPERATIONS (mutating)
+    // STORAGE CLEANUP (mutating)
     // ═══════════════════════════════════════════════════════════
 
-    pub async fn commit_to_pending_proposals(
+    pub async fn delete_group(
         &self,
         group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-    ) -> Result<CommitResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_for_group(&group_id_bytes).await?;
+    ) -> Result<(), String> {
+        let mut provider = self.load_for_group(&group_id_bytes).await?;
         let mut group = load_group(&group_id_bytes, &provider)?;
-
-        let (commit_out, welcome_opt, group_info_opt) = group
-            .commit_to_pending_proposals(&provider, &signer)
-            .map_err(|e| format!("Failed to commit to pending proposals: {}", e))?;
-        group.merge_pending_commit(&provider).map_err(|e| format!("Failed to merge pending commit: {}", e))?;
-
-        let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
-        let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w: MlsMessageOut| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
-        let gi_bytes = group_info_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+        group.delete(provider.storage()).map_err(|e| format!("Failed to delete group: {}", e))?;
+        let journal_id = journal_begin(provider.storage_mut(), JournalOperation::DeleteGroup, &group_id_bytes)?;
 
         self.commit(provider, Some(&group_id_bytes)).await?;
+        self.db()?.delete_group(&group_id_bytes).await?;
+        self.journal_end(journal_id).await
+    }
 
-        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes })
+    pub async fn delete_key_package(
+        &self,
+        key_package_ref_bytes: Vec<u8>,
+    ) -> Result<(), String> {
+        let mut provider = self.load_labels(&[KEY_PACKAGE_LABEL, APP_GLOBAL_LABEL], None).await?;
+        forget_key_package(&mut provider, &key_package_ref_bytes)?;
+        self.commit(provider, None).await?;
+        Ok(())
     }
 
-    pub async fn merge_pending_commit(
+    /// Move a group's stored state out of the way instead of deleting it.
+    ///
+    /// For groups whose processing fails persistently: the group's entries
+    /// are moved into a quarantine table together with `error`, so the
+    /// group no longer loads but its state can still be exported with
+    /// `export_quarantined_group` for offline analysis. Quarantining the
+    /// same group again replaces the earlier record.
+    pub async fn quarantine_group(
         &self,
         group_id_bytes: Vec<u8>,
+        error: String,
     ) -> Result<(), String> {
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let mut group = load_group(&group_id_bytes, &provider)?;
-        group.merge_pending_commit(&provider).map_err(|e| format!("Failed to merge pending commit: {}", e))?;
+        let now = unix_now()?;
+        self.db()?.quarantine_group(&group_id_bytes, &error, now).await
+    }
 
-        self.commit(provider, Some(&group_id_bytes)).await
+    pub async fn quarantined_groups(&self) -> Result<Vec<QuarantinedGroupInfo>, String> {
+        Ok(self
+            .db()?
+            .quarantined_groups()
+            .await?
+            .into_iter()
+            .map(|(group_id, error, quarantined_at)| QuarantinedGroupInfo { group_id, error, quarantined_at })
+            .collect())
     }
 
-    pub async fn clear_pending_commit(
+    /// Export a quarantined group as JSON (group id, error, time and the raw
+    /// storage entries).
+    ///
+    /// The export is NOT encrypted and contains the group's secrets; handle
+    /// it like the database itself.
+    pub async fn export_quarantined_group(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        let record = self
+            .db()?
+            .quarantined_group(&group_id_bytes)
+            .await?
+            .ok_or_else(|| "Group is not quarantined".to_string())?;
+        serde_json::to_vec(&record).map_err(|e| format!("Failed to serialize quarantined group: {}", e))
+    }
+
+    /// Recover a group whose local state diverged from the other members',
+    /// e.g. when `verify_public_state` reports a mismatch or incoming
+    /// commits keep failing with wrong-epoch errors.
+    ///
+    /// Rejoins by external commit from `group_info_bytes` with the same
+    /// credential and configuration, then quarantines the local state (see
+    /// `quarantine_group`) and saves the rejoined group in one transaction.
+    /// Send the returned commit like any external join.
+    /// `subscribe_group_events` streams receive `SelfHealed`. Successful
+    /// attempts are limited to one per group per hour; a failed attempt
+    /// changes nothing and can be retried right away.
+    pub async fn self_heal(
         &self,
         group_id_bytes: Vec<u8>,
+        group_info_bytes: Vec<u8>,
+        ratchet_tree_bytes: Option<Vec<u8>>,
+        signer_bytes: Vec<u8>,
+    ) -> Result<SelfHealResult, String> {
+        let now = unix_now()?;
+        let mut attempts_provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        let mut attempts: Vec<SelfHealAttempt> = attempts_provider.storage()
+            .app_global(SELF_HEAL_ATTEMPTS)
+            .map_err(|e| format!("Failed to read self-heal attempts: {}", e))?
+            .unwrap_or_default();
+        if let Some(last) = attempts.iter().find(|attempt| attempt.group_id == group_id_bytes) {
+            let elapsed = now.saturating_sub(last.attempted_at);
+            if elapsed < SELF_HEAL_MIN_INTERVAL_SECS {
+                return Err(format!("Self-heal rate limited: last attempt for this group was {} seconds ago", elapsed));
+            }
+        }
+        if group_info_group_id(&group_info_bytes).as_deref() != Some(group_id_bytes.as_slice()) {
+            return Err("Malformed group info: not a GroupInfo of this group".to_string());
+        }
+
+        let group_provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &group_provider)?;
+        let config = MlsGroupConfig::from_group(&group, group_provider.storage())?;
+        let archived_epoch = group.epoch().as_u64();
+        let own_leaf = group.own_leaf_node().ok_or_else(|| "Own leaf node not found".to_string())?;
+        let credential_bytes = own_leaf.credential()
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize credential: {}", e))?;
+        let signer_public_key = own_leaf.signature_key().as_slice().to_vec();
+
+        let (provider, epoch_event, join) = self
+            .track_join(JoinKind::ExternalCommit, self.stage_external_commit_join(
+                &config,
+                group_info_bytes,
+                ratchet_tree_bytes,
+                signer_bytes,
+                Vec::new(),
+                signer_public_key,
+                Some(credential_bytes),
+            ))
+            .await?;
+        let epoch = load_group(&group_id_bytes, &provider)?.epoch().as_u64();
+
+        attempts.retain(|attempt| attempt.group_id != group_id_bytes);
+        attempts.push(SelfHealAttempt { group_id: group_id_bytes.clone(), attempted_at: now });
+        attempts_provider.storage_mut()
+            .write_app_global(SELF_HEAL_ATTEMPTS, &attempts)
+            .map_err(|e| format!("Failed to write self-heal attempts: {}", e))?;
+        let batches = vec![
+            (provider.into_storage().into_updates(), Some(group_id_bytes.clone())),
+            (attempts_provider.into_storage().into_updates(), None),
+        ];
+        self.db()?
+            .quarantine_and_replace(&group_id_bytes, &format!("Self-heal from epoch {}", archived_epoch), now, batches)
+            .await?;
+        self.emit_epoch_event(epoch_event);
+
+        // The rejoined group starts a new history for subscribers.
+        if let Some(subscription) = self.state.group_subscriptions.lock().get_mut(&group_id_bytes) {
+            subscription.state = ObservedGroupState::default();
+            subscription.held = None;
+        }
+        self.after_save([group_id_bytes.clone()]).await;
+        let mut subscriptions = self.state.group_subscriptions.lock();
+        if let Some(subscription) = subscriptions.get_mut(&group_id_bytes) {
+            let event = GroupEvent::SelfHealed { archived_epoch, epoch };
+            subscription.sinks.retain(|sink| sink.add(event.clone()).is_ok());
+        }
+        Ok(SelfHealResult { archived_epoch, join })
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // PUBLISHED KEY PACKAGES
+    // ═══════════════════════════════════════════════════════════
+
+    /// Record that a key package was uploaded to a delivery service.
+    ///
+    /// When a Welcome later consumes it, the ref shows up in
+    /// `published_refs_to_revoke` so the client can remove it from `ds_id`.
+    /// Use `key_package_ref` to compute the ref from key package bytes.
+    pub async fn mark_key_package_published(
+        &self,
+        key_package_ref_bytes: Vec<u8>,
+        ds_id: String,
     ) -> Result<(), String> {
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let mut group = load_group(&group_id_bytes, &provider)?;
-        group.clear_pending_commit(provider.storage()).map_err(|e| format!("Failed to clear pending commit: {}", e))?;
+        let mut provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        let storage = provider.storage_mut();
+        let mut published: Vec<PublishedKeyPackage> = storage
+            .app_global(PUBLISHED_KEY_PACKAGES)
+            .map_err(|e| format!("Failed to read published key packages: {}", e))?
+            .unwrap_or_default();
+        if !published.iter().any(|p| p.key_package_ref == key_package_ref_bytes && p.ds_id == ds_id) {
+            published.push(PublishedKeyPackage { key_package_ref: key_package_ref_bytes, ds_id, consumed: false });
+        }
+        storage
+            .write_app_global(PUBLISHED_KEY_PACKAGES, &published)
+            .map_err(|e| format!("Failed to write published key packages: {}", e))?;
 
-        self.commit(provider, Some(&group_id_bytes)).await
+        self.commit(provider, None).await?;
+        Ok(())
     }
 
-    pub async fn clear_pending_proposals(
+    /// Published key packages that a Welcome has consumed and that should be
+    /// deleted from their delivery service.
+    pub async fn published_refs_to_revoke(&self) -> Result<Vec<PublishedKeyPackageRef>, String> {
+        let provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        let published: Vec<PublishedKeyPackage> = provider
+            .storage()
+            .app_global(PUBLISHED_KEY_PACKAGES)
+            .map_err(|e| format!("Failed to read published key packages: {}", e))?
+            .unwrap_or_default();
+        Ok(published
+            .into_iter()
+            .filter(|p| p.consumed)
+            .map(|p| PublishedKeyPackageRef { key_package_ref: p.key_package_ref, ds_id: p.ds_id })
+            .collect())
+    }
+
+    /// Forget a published key package once it has been removed from `ds_id`.
+    pub async fn mark_key_package_revoked(
         &self,
-        group_id_bytes: Vec<u8>,
+        key_package_ref_bytes: Vec<u8>,
+        ds_id: String,
     ) -> Result<(), String> {
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let mut group = load_group(&group_id_bytes, &provider)?;
-        group.clear_pending_proposals(provider.storage()).map_err(|e| format!("Failed to clear pending proposals: {}", e))?;
+        let mut provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        let storage = provider.storage_mut();
+        let mut published: Vec<PublishedKeyPackage> = storage
+            .app_global(PUBLISHED_KEY_PACKAGES)
+            .map_err(|e| format!("Failed to read published key packages: {}", e))?
+            .unwrap_or_default();
+        published.retain(|p| !(p.key_package_ref == key_package_ref_bytes && p.ds_id == ds_id));
+        if published.is_empty() {
+            storage.delete_app_global(PUBLISHED_KEY_PACKAGES)
+        } else {
+            storage.write_app_global(PUBLISHED_KEY_PACKAGES, &published)
+        }
+        .map_err(|e| format!("Failed to write published key packages: {}", e))?;
 
-        self.commit(provider, Some(&group_id_bytes)).await
+        self.commit(provider, None).await?;
+        Ok(())
     }
 
-    pub async fn set_configuration(
+    // ═══════════════════════════════════════════════════════════
+    // ADDITIONAL STATE QUERIES / MUTATING
+    // ═══════════════════════════════════════════════════════════
+
+    pub async fn remove_pending_proposal(
         &self,
         group_id_bytes: Vec<u8>,
-        config: MlsGroupConfig,
+        proposal_ref_bytes: Vec<u8>,
     ) -> Result<(), String> {
         let provider = self.load_for_group(&group_id_bytes).await?;
         let mut group = load_group(&group_id_bytes, &provider)?;
-        let join_config = config.to_join_config();
-        group.set_configuration(provider.storage(), &join_config).map_err(|e| format!("Failed to set configuration: {}", e))?;
+        let proposal_ref = ProposalRef::tls_deserialize_exact_bytes(&proposal_ref_bytes)
+            .map_err(|e| format!("Failed to deserialize proposal ref: {}", e))?;
+        group.remove_pending_proposal(provider.storage(), &proposal_ref)
+            .map_err(|e| format!("Failed to remove pending proposal: {}", e))?;
 
-        self.commit(provider, Some(&group_id_bytes)).await
+        self.commit(provider, Some(&group_id_bytes)).await?;
+        Ok(())
     }
 
-    pub async fn update_group_context_extensions(
+    pub async fn group_epoch_authenticator(
         &self,
         group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-        extensions: Vec<MlsExtension>,
-    ) -> Result<CommitResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
+    ) -> Result<Vec<u8>, String> {
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let mut group = load_group(&group_id_bytes, &provider)?;
-
-        let ext_vec: Vec<Extension> = extensions.iter().map(|ext| Extension::Unknown(ext.extension_type, UnknownExtension(ext.data.clone()))).collect();
-        let gc_extensions = Extensions::from_vec(ext_vec).map_err(|e| format!("Failed to create extensions: {}", e))?;
-
-        let (commit_out, welcome_opt, group_info_opt) = group
-            .update_group_context_extensions(&provider, gc_extensions, &signer)
-            .map_err(|e| format!("Failed to update group context extensions: {}", e))?;
-        group.merge_pending_commit(&provider).map_err(|e| format!("Failed to merge pending commit: {}", e))?;
-
-        let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
-        let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w: MlsMessageOut| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
-        let gi_bytes = group_info_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
-
-        self.commit(provider, Some(&group_id_bytes)).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        Ok(group.epoch_authenticator().as_slice().to_vec())
+    }
 
-        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes })
+    pub async fn group_configuration(
+        &self,
+        group_id_bytes: Vec<u8>,
+    ) -> Result<GroupConfigurationResult, String> {
+        let provider = self.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        let join_config = group.configuration();
+        let cs = native_to_ciphersuite(group.ciphersuite())?;
+        let wf = native_to_wire_format(join_config.wire_format_policy());
+        let sr_config = join_config.sender_ratchet_configuration();
+        Ok(GroupConfigurationResult {
+            ciphersuite: cs,
+            wire_format_policy: wf,
+            padding_size: join_config.padding_size() as u32,
+            sender_ratchet_max_out_of_order: sr_config.out_of_order_tolerance(),
+            sender_ratchet_max_forward_distance: sr_config.maximum_forward_distance(),
+        })
     }
 
-    pub async fn flexible_commit(
+    /// Summarize the group's security health for display, e.g. as a
+    /// per-conversation indicator.
+    ///
+    /// Each check that finds something adds a finding with a severity; the
+    /// overall severity is the highest of them. Staleness of our own update
+    /// is only known for commits made since this was tracked.
+    pub async fn security_posture(
         &self,
         group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-        options: FlexibleCommitOptions,
-    ) -> Result<CommitResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
+    ) -> Result<MlsSecurityPosture, String> {
         let provider = self.load_for_group(&group_id_bytes).await?;
-        let mut group = load_group(&group_id_bytes, &provider)?;
+        let group = load_group(&group_id_bytes, &provider)?;
+        let epoch = group.epoch().as_u64();
+        let mut findings = Vec::new();
+
+        let own_update_epoch: Option<u64> = provider.storage()
+            .app_group(&group_id_bytes, OWN_UPDATE_EPOCH)
+            .map_err(|e| format!("Failed to read own update epoch: {}", e))?;
+        let epochs_since_own_update = own_update_epoch.map(|updated| epoch.saturating_sub(updated));
+        if let Some(stale) = epochs_since_own_update {
+            if stale >= STALE_OWN_UPDATE_CRITICAL_EPOCHS {
+                findings.push(MlsSecurityFinding { kind: MlsSecurityFindingKind::StaleOwnUpdate, severity: MlsSecuritySeverity::Critical });
+            } else if stale >= STALE_OWN_UPDATE_WARNING_EPOCHS {
+                findings.push(MlsSecurityFinding { kind: MlsSecurityFindingKind::StaleOwnUpdate, severity: MlsSecuritySeverity::Warning });
+            }
+        }
 
-        if let Some(aad_bytes) = options.aad {
-            group.set_aad(aad_bytes);
+        let tree_bytes = group
+            .export_ratchet_tree()
+            .tls_serialize_detached()
+            .map_err(|e| format!("Failed to serialize ratchet tree: {}", e))?;
+        let never_updated_members: Vec<u32> = crate::tree_view::parse_ratchet_tree(&tree_bytes)?
+            .iter()
+            .step_by(2)
+            .enumerate()
+            .filter_map(|(leaf_index, node)| match node {
+                Some(crate::tree_view::TreeNode::Leaf { source, .. })
+                    if *source == crate::tree_view::LEAF_SOURCE_KEY_PACKAGE => Some(leaf_index as u32),
+                _ => None,
+            })
+            .collect();
+        if !never_updated_members.is_empty() {
+            findings.push(MlsSecurityFinding { kind: MlsSecurityFindingKind::MembersNeverUpdated, severity: MlsSecuritySeverity::Info });
         }
 
-        let mut commit_builder = group.commit_builder()
-            .consume_proposal_store(options.consume_pending_proposals)
-            .force_self_update(options.force_self_update);
+        let max_past_epochs = configured_max_past_epochs(&group)?;
+        let retained_past_epochs = max_past_epochs.min(epoch) as u32;
+        if retained_past_epochs > 0 {
+            findings.push(MlsSecurityFinding { kind: MlsSecurityFindingKind::PastEpochsRetained, severity: MlsSecuritySeverity::Info });
+        }
 
-        if !options.add_key_packages.is_empty() {
-            let mut key_packages = Vec::with_capacity(options.add_key_packages.len());
-            for kp_bytes in &options.add_key_packages {
-                let kp_in = KeyPackageIn::tls_deserialize_exact_bytes(kp_bytes)
-                    .map_err(|e| format!("Failed to deserialize key package: {}", e))?;
-                let kp = kp_in.validate(provider.crypto(), ProtocolVersion::Mls10)
-                    .map_err(|e| format!("Failed to validate key package: {}", e))?;
-                key_packages.push(kp);
-            }
-            commit_builder = commit_builder.propose_adds(key_packages);
+        let last_resort_key_package_in_use = last_resort_key_package_in_use(&group, &provider)?;
+        if last_resort_key_package_in_use {
+            findings.push(MlsSecurityFinding { kind: MlsSecurityFindingKind::LastResortKeyPackageInUse, severity: MlsSecuritySeverity::Warning });
         }
 
-        if !options.remove_indices.is_empty() {
-            commit_builder = commit_builder.propose_removals(options.remove_indices.iter().map(|&i| LeafNodeIndex::new(i)));
+        let policy = group.configuration().wire_format_policy();
+        let sends_plaintext = policy.outgoing() == OutgoingWireFormatPolicy::AlwaysPlaintext;
+        let plaintext_allowed = sends_plaintext || policy.incoming() != IncomingWireFormatPolicy::AlwaysCiphertext;
+        if plaintext_allowed {
+            let severity = if sends_plaintext { MlsSecuritySeverity::Warning } else { MlsSecuritySeverity::Info };
+            findings.push(MlsSecurityFinding { kind: MlsSecurityFindingKind::PlaintextAllowed, severity });
         }
 
-        if let Some(ref gc_exts) = options.group_context_extensions {
-            let ext_vec = extensions_from_mls(gc_exts);
-            let extensions = Extensions::from_vec(ext_vec).map_err(|e| format!("Failed to create group context extensions: {}", e))?;
-            commit_builder = commit_builder.propose_group_context_extensions(extensions).map_err(|e| format!("Failed to propose group context extensions: {}", e))?;
+        let severity = match findings.iter().map(|finding| severity_rank(&finding.severity)).max() {
+            None | Some(0) => MlsSecuritySeverity::Ok,
+            Some(1) => MlsSecuritySeverity::Info,
+            Some(2) => MlsSecuritySeverity::Warning,
+            Some(_) => MlsSecuritySeverity::Critical,
+        };
+
+        Ok(MlsSecurityPosture {
+            epoch,
+            epochs_since_own_update,
+            never_updated_members,
+            retained_past_epochs,
+            last_resort_key_package_in_use,
+            plaintext_allowed,
+            findings,
+            severity,
+        })
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // CRASH RECOVERY
+    // ═══════════════════════════════════════════════════════════
+
+    /// Operations that did not finish, e.g. because the app was killed
+    /// between their storage transactions.
+    ///
+    /// Every other operation commits in a single transaction and cannot be
+    /// interrupted halfway. Interrupted operations are rolled forward when
+    /// the engine is opened; those that failed to are listed here, with the
+    /// error, until `recover_operation` succeeds or `discard_recovery`
+    /// drops them.
+    pub async fn pending_recovery(&self) -> Result<Vec<PendingRecovery>, String> {
+        let provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        Ok(read_journal(provider.storage())?
+            .into_iter()
+            .map(|entry| PendingRecovery {
+                id: entry.id,
+                operation: match entry.operation {
+                    JournalOperation::DeleteGroup => MlsRecoveryOperation::DeleteGroup,
+                },
+                group_id: entry.group_id,
+                phase: entry.phase,
+                started_at: entry.started_at,
+                error: entry.error,
+            })
+            .collect())
+    }
+
+    /// Roll the interrupted operation `id` forward. On failure the error is
+    /// recorded for `pending_recovery`.
+    pub async fn recover_operation(&self, id: u64) -> Result<(), String> {
+        let provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        let entry = read_journal(provider.storage())?
+            .into_iter()
+            .find(|entry| entry.id == id)
+            .ok_or_else(|| format!("No pending recovery with id {}", id))?;
+        let result = match entry.operation {
+            JournalOperation::DeleteGroup => self.db()?.delete_group(&entry.group_id).await,
+        };
+        match result {
+            Ok(()) => self.journal_end(id).await,
+            Err(e) => {
+                self.journal_update(id, |entry| entry.error = Some(e.clone())).await?;
+                Err(e)
+            }
         }
+    }
 
-        let commit_builder = commit_builder.load_psks(provider.storage()).map_err(|e| format!("Failed to load PSKs: {}", e))?;
-        let commit_builder = commit_builder.create_group_info(options.create_group_info).use_ratchet_tree_extension(options.use_ratchet_tree_extension);
-        let commit_builder = commit_builder.build(provider.rand(), provider.crypto(), &signer, |_| true).map_err(|e| format!("Failed to build commit: {}", e))?;
-        let bundle = commit_builder.stage_commit(&provider).map_err(|e| format!("Failed to stage commit: {}", e))?;
-        group.merge_pending_commit(&provider).map_err(|e| format!("Failed to merge pending commit: {}", e))?;
+    /// Forget the interrupted operation `id` without recovering it.
+    pub async fn discard_recovery(&self, id: u64) -> Result<(), String> {
+        self.journal_end(id).await
+    }
 
-        let (commit_out, welcome_opt, gi_opt) = bundle.into_messages();
-        let commit_bytes = commit_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize commit: {}", e))?;
-        let welcome_bytes: Option<Vec<u8>> = welcome_opt.map(|w| w.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize welcome: {}", e))?;
-        let gi_bytes = gi_opt.map(|gi| gi.tls_serialize_detached()).transpose().map_err(|e| format!("Failed to serialize group info: {}", e))?;
+    /// Roll forward every interrupted operation. Called when the engine is
+    /// opened; failures stay listed in `pending_recovery`.
+    async fn recover_operations(&self) -> Result<(), String> {
+        let provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        for entry in read_journal(provider.storage())? {
+            if let Err(e) = self.recover_operation(entry.id).await {
+                redact::log_warn!(
+                    "Failed to recover interrupted operation {} on {}: {}",
+                    entry.id,
+                    Sensitive::GroupId(&entry.group_id),
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
 
-        self.commit(provider, Some(&group_id_bytes)).await?;
+    /// Remove the journal entry `id` once its operation has finished.
+    async fn journal_end(&self, id: u64) -> Result<(), String> {
+        let mut provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        let mut entries = read_journal(provider.storage())?;
+        entries.retain(|entry| entry.id != id);
+        write_journal(provider.storage_mut(), &entries)?;
+        self.commit(provider, None).await?;
+        Ok(())
+    }
 
-        Ok(CommitResult { commit: commit_bytes, welcome: welcome_bytes, group_info: gi_bytes })
+    async fn journal_update(&self, id: u64, update: impl FnOnce(&mut JournalEntry)) -> Result<(), String> {
+        let mut provider = self.load_label(APP_GLOBAL_LABEL, None).await?;
+        let mut entries = read_journal(provider.storage())?;
+        if let Some(entry) = entries.iter_mut().find(|entry| entry.id == id) {
+            update(entry);
+        }
+        write_journal(provider.storage_mut(), &entries)?;
+        self.commit(provider, None).await?;
+        Ok(())
     }
 
     // ═══════════════════════════════════════════════════════════
-    // MESSAGES (mutating)
+    // BACKUP
     // ═══════════════════════════════════════════════════════════
 
-    pub async fn create_message(
-        &self,
-        group_id_bytes: Vec<u8>,
-        signer_bytes: Vec<u8>,
-        message: Vec<u8>,
-        aad: Option<Vec<u8>>,
-    ) -> Result<CreateMessageResult, String> {
-        let signer = signer_from_bytes(signer_bytes)?;
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let mut group = load_group(&group_id_bytes, &provider)?;
+    /// Export every storage entry of the engine as one backup blob (format
+    /// in the `backup` module).
+    ///
+    /// The backup is NOT encrypted and contains the secrets of every group;
+    /// handle it like the database itself. For large databases prefer
+    /// `export_backup_stream`.
+    pub async fn export_backup(&self) -> Result<ZeroCopyBuffer<Vec<u8>>, String> {
+        let mut out = vec![crate::backup::BACKUP_VERSION];
+        self.for_each_entry(|group_id, key, value| crate::backup::write_entry(&mut out, group_id.as_deref(), &key, &value))
+            .await?;
+        Ok(ZeroCopyBuffer(out))
+    }
 
-        if let Some(aad_bytes) = aad {
-            group.set_aad(aad_bytes);
+    /// Like `export_backup`, but delivers the blob to `sink` in chunks of
+    /// about `chunk_size` bytes, so the full blob is never held in memory
+    /// on either side of the bridge. The stream closes after the last chunk.
+    ///
+    /// Entries are read from storage a page at a time, not as one snapshot:
+    /// don't write to the engine while the export runs, or the backup may
+    /// mix states from before and after the write.
+    pub async fn export_backup_stream(
+        &self,
+        sink: StreamSink<Vec<u8>>,
+        chunk_size: u32,
+    ) -> Result<(), String> {
+        let chunk_size = (chunk_size as usize).max(1);
+        let mut chunk = vec![crate::backup::BACKUP_VERSION];
+        self.for_each_entry(|group_id, key, value| {
+            crate::backup::write_entry(&mut chunk, group_id.as_deref(), &key, &value)?;
+            if chunk.len() >= chunk_size {
+                sink.add(std::mem::take(&mut chunk))
+                    .map_err(|_| "Backup stream closed".to_string())?;
+            }
+            Ok(())
+        })
+        .await?;
+        if !chunk.is_empty() {
+            sink.add(chunk).map_err(|_| "Backup stream closed".to_string())?;
         }
+        Ok(())
+    }
 
-        let msg_out = group.create_message(&provider, &signer, &message)
-            .map_err(|e| format!("Failed to create message: {}", e))?;
-        let ciphertext = msg_out.tls_serialize_detached().map_err(|e| format!("Failed to serialize message: {}", e))?;
-
-        self.commit(provider, Some(&group_id_bytes)).await?;
-
-        Ok(CreateMessageResult { ciphertext })
+    /// Pass every storage entry to `visit` in key order, reading storage
+    /// `BACKUP_PAGE_SIZE` entries at a time.
+    async fn for_each_entry(
+        &self,
+        mut visit: impl FnMut(Option<Vec<u8>>, Vec<u8>, Vec<u8>) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let db = self.db()?;
+        let mut after: Option<Vec<u8>> = None;
+        loop {
+            let page = db.load_page(after.as_deref(), BACKUP_PAGE_SIZE).await?;
+            let last_page = page.len() < BACKUP_PAGE_SIZE as usize;
+            for (group_id, key, value) in page {
+                after = Some(key.clone());
+                visit(group_id, key, value)?;
+            }
+            if last_page {
+                return Ok(());
+            }
+        }
     }
 
-    pub async fn process_message(
+    /// Export one group's storage entries, encrypted under `passphrase`
+    /// (format in the `group_export` module), to move the group to another
+    /// device with `import_group_state`.
+    ///
+    /// Of the global entries only the own leaf's encryption key pair is
+    /// included; signers are passed to every operation anyway. After
+    /// importing on the new device, stop using the group here: two devices
+    /// advancing the same member's state fork the group.
+    pub async fn export_group_state(
         &self,
         group_id_bytes: Vec<u8>,
-        message_bytes: Vec<u8>,
-    ) -> Result<ProcessedMessageResult, String> {
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let mut group = load_group(&group_id_bytes, &provider)?;
+        passphrase: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        let all = self.db()?.load_for_group(&group_id_bytes).await?;
+        let group = load_group(&group_id_bytes, &self.provider(SnapshotStorageProvider::from_entries(all.clone())))?;
+        let leaf_key_pair = match group.own_leaf_node() {
+            Some(leaf) => Some(encryption_key_pair_key(leaf.encryption_key())?),
+            None => None,
+        };
+        let mut entries = Vec::new();
+        for (key, value) in all {
+            if Some(&key) == leaf_key_pair.as_ref()
+                || storage_key_group_id(&key)?.as_deref() == Some(group_id_bytes.as_slice())
+            {
+                entries.push((key, value));
+            }
+        }
+        crate::group_export::seal(&self.state.crypto, &passphrase, &group_id_bytes, &entries)
+    }
 
-        let msg_in = MlsMessageIn::tls_deserialize_exact_bytes(&message_bytes)
-            .map_err(|e| format!("Failed to deserialize message: {}", e))?;
-        let protocol_msg = msg_in.try_into_protocol_message()
-            .map_err(|e| format!("Not a protocol message: {}", e))?;
+    /// Restore a group exported with `export_group_state` and return its
+    /// group id.
+    ///
+    /// The entries are checked to form a loadable group before anything is
+    /// written. Fails without writing if the group already exists here.
+    pub async fn import_group_state(
+        &self,
+        blob: Vec<u8>,
+        passphrase: Vec<u8>,
+    ) -> Result<Vec<u8>, String> {
+        let (group_id, entries) = crate::group_export::open(&self.state.crypto, &passphrase, &blob)?;
+        for (key, _) in &entries {
+            match storage_key_group_id(key)? {
+                Some(entry_group) if entry_group == group_id => {}
+                None if key.starts_with(ENCRYPTION_KEY_PAIR_LABEL) => {}
+                _ => return Err("Group state export contains an entry of another group".to_string()),
+            }
+        }
 
-        let processed = group.process_message(&provider, protocol_msg)
-            .map_err(|e| format!("Failed to process message: {}", e))?;
+        let gid = GroupId::from_slice(&group_id);
+        let existing = MlsGroup::load(self.load_for_group(&group_id).await?.storage(), &gid)
+            .map_err(|e| format!("Failed to load group: {}", e))?;
+        if existing.is_some() {
+            return Err("Group already exists in the database".to_string());
+        }
+        let imported = SnapshotStorageProvider::from_entries(entries.clone());
+        MlsGroup::load(&imported, &gid)
+            .map_err(|e| format!("Imported group does not load: {}", e))?
+            .ok_or_else(|| "Imported group state is incomplete".to_string())?;
+
+        self.save_batches(vec![(StorageUpdates { upserts: entries, deletes: Vec::new() }, Some(group_id.clone()))])
+            .await?;
+        Ok(group_id)
+    }
 
-        let sender_index = match processed.sender() {
-            Sender::Member(idx) => Some(idx.u32()),
-            _ => None,
+    /// Export every storage entry as a passphrase-encrypted backup in the
+    /// platform-independent interchange format (CBOR/COSE, documented in the
+    /// `interchange` module).
+    ///
+    /// Unlike `export_backup`, the result restores on any platform: a backup
+    /// made on native can be imported on web and vice versa, in full or per
+    /// group, with `import_interchange`.
+    pub async fn export_interchange(&self, passphrase: Vec<u8>) -> Result<Vec<u8>, String> {
+        let mut payload = crate::interchange::InterchangePayload {
+            created_at: unix_now()?,
+            global: Vec::new(),
+            groups: std::collections::BTreeMap::new(),
         };
-        let epoch = group.epoch().as_u64();
+        for (_, key, value) in self.db()?.load_all().await? {
+            match storage_key_group_id(&key)? {
+                None => payload.global.push((key, value)),
+                Some(group_id) => payload.groups.entry(group_id).or_default().push((key, value)),
+            }
+        }
+        crate::interchange::seal(&self.state.crypto, &passphrase, &payload)
+    }
 
-        let (message_type, application_message, has_staged_commit, has_proposal, proposal_type) =
-            match processed.into_content() {
-                ProcessedMessageContent::ApplicationMessage(app_msg) => {
-                    (ProcessedMessageType::Application, Some(app_msg.into_bytes()), false, false, None)
+    /// Restore a backup made with `export_interchange`, on any platform.
+    ///
+    /// With `group_ids`, only those groups are restored; otherwise every
+    /// group in the backup. Global entries (signers, key packages, leaf
+    /// keys, app metadata) are not tied to a group, so the backup's are
+    /// restored as a whole with `include_global_entries`, whichever groups
+    /// are selected, and not at all without it. They are only added where
+    /// this engine has no entry under the same key, so existing state is
+    /// never overwritten. Before anything is
+    /// written, the backup's integrity is verified and every restored group
+    /// must load; everything is then written in one transaction. Fails
+    /// without writing if one of the groups already exists here.
+    pub async fn import_interchange(
+        &self,
+        blob: Vec<u8>,
+        passphrase: Vec<u8>,
+        group_ids: Option<Vec<Vec<u8>>>,
+        include_global_entries: bool,
+    ) -> Result<ProviderImportResult, String> {
+        let payload = crate::interchange::open(&self.state.crypto, &passphrase, &blob)?;
+        let mut groups = payload.groups;
+        if let Some(wanted) = group_ids {
+            if wanted.iter().any(|id| !groups.contains_key(id)) {
+                return Err("Backup does not contain a requested group".to_string());
+            }
+            groups.retain(|id, _| wanted.contains(id));
+        }
+        for (group_id, entries) in &groups {
+            for (key, _) in entries {
+                if storage_key_group_id(key)?.as_deref() != Some(group_id.as_slice()) {
+                    return Err("Backup entry is filed under the wrong group".to_string());
                 }
-                ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
-                    group.merge_staged_commit(&provider, *staged_commit)
-                        .map_err(|e| format!("Failed to merge staged commit: {}", e))?;
-                    (ProcessedMessageType::StagedCommit, None, true, false, None)
+            }
+        }
+        let existing: std::collections::BTreeSet<Vec<u8>> =
+            self.db()?.load_global().await?.into_iter().map(|(key, _)| key).collect();
+        let mut global = Vec::new();
+        for (key, value) in payload.global {
+            if storage_key_group_id(&key)?.is_some() {
+                return Err("Backup entry is filed under the wrong group".to_string());
+            }
+            if include_global_entries && !existing.contains(&key) {
+                global.push((key, value));
+            }
+        }
+        self.check_importable_groups(&groups).await?;
+
+        let result = ProviderImportResult {
+            group_ids: groups.keys().cloned().collect(),
+            global_entries: global.len() as u32,
+            group_entries: groups.values().map(|entries| entries.len() as u32).sum(),
+        };
+        let mut batches = vec![(StorageUpdates { upserts: global, deletes: Vec::new() }, None)];
+        for (group_id, entries) in groups {
+            batches.push((StorageUpdates { upserts: entries, deletes: Vec::new() }, Some(group_id)));
+        }
+        self.save_batches(batches).await?;
+        Ok(result)
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // LEGACY STORAGE IMPORT
+    // ═══════════════════════════════════════════════════════════
+
+    /// Move the state of an app still on the callback-based storage API
+    /// (Dart `StorageProvider`) into this engine's encrypted database.
+    ///
+    /// `read_prefix` must return every entry whose key starts with the given
+    /// prefix; it is called once per OpenMLS storage label. The legacy keys
+    /// use the same format as the engine, so entries are copied unchanged.
+    /// Before anything is written, every key must parse and every imported
+    /// group must load; all entries are then written in one transaction.
+    /// Fails without writing if one of the groups already exists here.
+    pub async fn import_from_provider_storage(
+        &self,
+        read_prefix: impl Fn(Vec<u8>) -> DartFnFuture<Vec<MlsStorageEntry>>,
+    ) -> Result<ProviderImportResult, String> {
+        let mut global = Vec::new();
+        let mut groups: std::collections::BTreeMap<Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>> =
+            std::collections::BTreeMap::new();
+        for label in OPENMLS_LABELS {
+            for entry in read_prefix(label.to_vec()).await {
+                if !entry.key.starts_with(label) {
+                    return Err("Storage callback returned a key outside the requested prefix".to_string());
                 }
-                ProcessedMessageContent::ProposalMessage(queued_proposal) => {
-                    let prop_type = match queued_proposal.proposal() {
-                        Proposal::Add(_) => MlsProposalType::Add,
-                        Proposal::Remove(_) => MlsProposalType::Remove,
-                        Proposal::Update(_) => MlsProposalType::Update,
-                        Proposal::PreSharedKey(_) => MlsProposalType::PreSharedKey,
-                        Proposal::ReInit(_) => MlsProposalType::Reinit,
-                        Proposal::ExternalInit(_) => MlsProposalType::ExternalInit,
-                        Proposal::GroupContextExtensions(_) => MlsProposalType::GroupContextExtensions,
-                        _ => MlsProposalType::Custom,
-                    };
-                    group.store_pending_proposal(provider.storage(), *queued_proposal)
-                        .map_err(|e| format!("Failed to store pending proposal: {}", e))?;
-                    (ProcessedMessageType::Proposal, None, false, true, Some(prop_type))
+                match storage_key_group_id(&entry.key)? {
+                    None => global.push((entry.key, entry.value)),
+                    Some(group_id) => groups.entry(group_id).or_default().push((entry.key, entry.value)),
                 }
-                _ => return Err("Unknown processed message content type".to_string()),
-            };
+            }
+        }
 
-        self.commit(provider, Some(&group_id_bytes)).await?;
+        self.check_importable_groups(&groups).await?;
 
-        Ok(ProcessedMessageResult {
-            message_type, sender_index, epoch, application_message, has_staged_commit, has_proposal, proposal_type,
-        })
+        let result = ProviderImportResult {
+            group_ids: groups.keys().cloned().collect(),
+            global_entries: global.len() as u32,
+            group_entries: groups.values().map(|entries| entries.len() as u32).sum(),
+        };
+        let mut batches = vec![(StorageUpdates { upserts: global, deletes: Vec::new() }, None)];
+        for (group_id, entries) in groups {
+            batches.push((StorageUpdates { upserts: entries, deletes: Vec::new() }, Some(group_id)));
+        }
+        self.save_batches(batches).await?;
+        Ok(result)
     }
 
-    pub async fn process_message_with_inspect(
+    /// Fail unless every group in `groups` is new to this engine and loads
+    /// from its entries alone.
+    async fn check_importable_groups(
         &self,
-        group_id_bytes: Vec<u8>,
-        message_bytes: Vec<u8>,
-    ) -> Result<ProcessedMessageInspectResult, String> {
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let mut group = load_group(&group_id_bytes, &provider)?;
-
-        let msg_in = MlsMessageIn::tls_deserialize_exact_bytes(&message_bytes)
-            .map_err(|e| format!("Failed to deserialize message: {}", e))?;
-        let protocol_msg = msg_in.try_into_protocol_message()
-            .map_err(|e| format!("Not a protocol message: {}", e))?;
+        groups: &std::collections::BTreeMap<Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>>,
+    ) -> Result<(), String> {
+        for (group_id, entries) in groups {
+            let gid = GroupId::from_slice(group_id);
+            let existing = MlsGroup::load(self.load_for_group(group_id).await?.storage(), &gid)
+                .map_err(|e| format!("Failed to load group: {}", e))?;
+            if existing.is_some() {
+                return Err("Group already exists in the database".to_string());
+            }
+            let imported = SnapshotStorageProvider::from_entries(entries.clone());
+            MlsGroup::load(&imported, &gid)
+                .map_err(|e| format!("Imported group does not load: {}", e))?
+                .ok_or_else(|| "Imported group state is incomplete".to_string())?;
+        }
+        Ok(())
+    }
 
-        let processed = group.process_message(&provider, protocol_msg)
-            .map_err(|e| format!("Failed to process message: {}", e))?;
+    // ═══════════════════════════════════════════════════════════
+    // SANDBOXES
+    // ═══════════════════════════════════════════════════════════
 
-        let sender_index = match processed.sender() {
-            Sender::Member(idx) => Some(idx.u32()),
-            _ => None,
+    /// Copy a group's state into an in-memory sandbox and return its id.
+    ///
+    /// Use `sandbox` to get an engine handle on the copy: every engine
+    /// operation works on it, but nothing is ever persisted, so multi-step
+    /// what-if sequences (remove one member, add another, inspect the
+    /// roster) can be tried without touching the real group. Only the
+    /// forked group and the global entries (signers, key packages) are
+    /// copied. Messages created in a sandbox must not be sent.
+    pub async fn fork_group_sandbox(&self, group_id_bytes: Vec<u8>) -> Result<u64, String> {
+        let entries = self.db()?.load_for_group(&group_id_bytes).await?;
+        let store = SandboxStore::from_group_entries(&group_id_bytes, entries);
+        let sandbox = MlsEngine {
+            state: std::sync::Arc::new(EngineState {
+                db: parking_lot::RwLock::new(Some(EngineStore::Sandbox(std::sync::Arc::new(store)))),
+                epoch_sinks: parking_lot::Mutex::new(Vec::new()),
+                epoch_exporters: parking_lot::RwLock::new(Vec::new()),
+                event_throttle: parking_lot::Mutex::new(EventThrottle::default()),
+                group_subscriptions: parking_lot::Mutex::new(std::collections::BTreeMap::new()),
+                token: std::sync::OnceLock::new(),
+                strict_mode: std::sync::atomic::AtomicBool::new(
+                    self.state.strict_mode.load(std::sync::atomic::Ordering::Relaxed),
+                ),
+                audit_log: std::sync::atomic::AtomicBool::new(
+                    self.state.audit_log.load(std::sync::atomic::Ordering::Relaxed),
+                ),
+                join_receipts: std::sync::atomic::AtomicBool::new(
+                    self.state.join_receipts.load(std::sync::atomic::Ordering::Relaxed),
+                ),
+                failed_join_retention: std::sync::atomic::AtomicBool::new(
+                    self.state.failed_join_retention.load(std::sync::atomic::Ordering::Relaxed),
+                ),
+                member_tombstones: std::sync::atomic::AtomicBool::new(
+                    self.state.member_tombstones.load(std::sync::atomic::Ordering::Relaxed),
+                ),
+                handshake_expiry: parking_lot::RwLock::new(self.handshake_expiry_policy()),
+                credential_validator: parking_lot::RwLock::new(self.state.credential_validator.read().clone()),
+                proposal_policy: parking_lot::RwLock::new(self.state.proposal_policy.read().clone()),
+                join_stats: parking_lot::Mutex::new(JoinStats::default()),
+                signers: parking_lot::RwLock::new(self.state.signers.read().clone()),
+                crypto: self.state.crypto.clone(),
+                sandboxes: parking_lot::Mutex::new(std::collections::BTreeMap::new()),
+                key_digests: parking_lot::Mutex::new(std::collections::BTreeMap::new()),
+            }),
         };
-        let epoch = group.epoch().as_u64();
+        sandbox.load_group_metadata(&group_id_bytes).await?;
 
-        let (message_type, application_message, staged_commit_info, proposal_type) =
-            match processed.into_content() {
-                ProcessedMessageContent::ApplicationMessage(app_msg) => {
-                    (ProcessedMessageType::Application, Some(app_msg.into_bytes()), None, None)
-                }
-                ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
-                    let mut add_credentials = Vec::new();
-                    for add in staged_commit.add_proposals() {
-                        let kp = add.add_proposal().key_package();
-                        let cred_bytes = kp.leaf_node().credential()
-                            .tls_serialize_detached()
-                            .map_err(|e| format!("Failed to serialize add credential: {}", e))?;
-                        add_credentials.push(cred_bytes);
-                    }
-                    let remove_indices: Vec<u32> = staged_commit.remove_proposals().map(|r| r.remove_proposal().removed().u32()).collect();
-                    let has_update = staged_commit.update_proposals().next().is_some();
-                    let self_removed = staged_commit.self_removed();
-                    let psk_count = staged_commit.psk_proposals().count() as u32;
-                    let info = StagedCommitInfo { add_credentials, remove_indices, has_update, self_removed, psk_count };
+        let sandbox_id = NEXT_SANDBOX_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        self.state.sandboxes.lock().insert(sandbox_id, sandbox.state);
+        Ok(sandbox_id)
+    }
 
-                    group.merge_staged_commit(&provider, *staged_commit)
-                        .map_err(|e| format!("Failed to merge staged commit: {}", e))?;
-                    (ProcessedMessageType::StagedCommit, None, Some(info), None)
-                }
-                ProcessedMessageContent::ProposalMessage(queued_proposal) => {
-                    let prop_type = match queued_proposal.proposal() {
-                        Proposal::Add(_) => MlsProposalType::Add,
-                        Proposal::Remove(_) => MlsProposalType::Remove,
-                        Proposal::Update(_) => MlsProposalType::Update,
-                        Proposal::PreSharedKey(_) => MlsProposalType::PreSharedKey,
-                        Proposal::ReInit(_) => MlsProposalType::Reinit,
-                        Proposal::ExternalInit(_) => MlsProposalType::ExternalInit,
-                        Proposal::GroupContextExtensions(_) => MlsProposalType::GroupContextExtensions,
-                        _ => MlsProposalType::Custom,
-                    };
-                    group.store_pending_proposal(provider.storage(), *queued_proposal)
-                        .map_err(|e| format!("Failed to store pending proposal: {}", e))?;
-                    (ProcessedMessageType::Proposal, None, None, Some(prop_type))
-                }
-                _ => return Err("Unknown processed message content type".to_string()),
-            };
+    /// Engine handle on a sandbox created by `fork_group_sandbox`.
+    ///
+    /// Epoch events of the sandbox are only delivered to subscriptions made
+    /// on this handle.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn sandbox(&self, sandbox_id: u64) -> Result<MlsEngine, String> {
+        let state = self
+            .state
+            .sandboxes
+            .lock()
+            .get(&sandbox_id)
+            .cloned()
+            .ok_or_else(|| "Unknown sandbox".to_string())?;
+        Ok(MlsEngine { state })
+    }
 
-        self.commit(provider, Some(&group_id_bytes)).await?;
+    /// Discard a sandbox and all changes made in it. Handles obtained via
+    /// `sandbox` behave like closed engines afterwards. Dropping an unknown
+    /// sandbox is a no-op. Closing the engine drops all of its sandboxes.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn drop_sandbox(&self, sandbox_id: u64) {
+        let state = self.state.sandboxes.lock().remove(&sandbox_id);
+        if let Some(state) = state {
+            MlsEngine { state }.close_sandbox();
+        }
+    }
 
-        Ok(ProcessedMessageInspectResult {
-            message_type, sender_index, epoch, application_message, staged_commit_info, proposal_type,
-        })
+    /// Close a sandbox's storage and, recursively, the sandboxes forked
+    /// from it.
+    fn close_sandbox(self) {
+        self.drop_all_sandboxes();
+        self.state.db.write().take();
+    }
+
+    fn drop_all_sandboxes(&self) {
+        let sandboxes = std::mem::take(&mut *self.state.sandboxes.lock());
+        for (_, state) in sandboxes {
+            MlsEngine { state }.close_sandbox();
+        }
     }
 
     // ═══════════════════════════════════════════════════════════
-    // STORAGE CLEANUP (mutating)
+    // BACKEND MIGRATION
     // ═══════════════════════════════════════════════════════════
 
-    pub async fn delete_group(
+    /// Start moving this engine's state to a new database, e.g. to rotate
+    /// the encryption key or relocate the file.
+    ///
+    /// The target is opened like `create` and must be empty. All entries are
+    /// copied to it; from then on every write goes to the current database
+    /// and then to the target, while reads stay on the current database.
+    /// Failed target writes do not fail the operation; they are counted in
+    /// `migration_status`. The engine keeps using both until
+    /// `finalize_backend_migration`, not before `dual_write_seconds` have
+    /// passed, or `abort_backend_migration`.
+    ///
+    /// Not available on sandboxes, while a migration is in progress or while
+    /// groups are quarantined. Writes already in flight when the migration
+    /// starts reach only the current database and show up as mismatches
+    /// until `finalize_backend_migration` copies them over.
+    pub async fn begin_backend_migration(
         &self,
-        group_id_bytes: Vec<u8>,
+        target_db_path: String,
+        target_encryption_key: Vec<u8>,
+        dual_write_seconds: u64,
     ) -> Result<(), String> {
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let mut group = load_group(&group_id_bytes, &provider)?;
-        group.delete(provider.storage()).map_err(|e| format!("Failed to delete group: {}", e))?;
-
-        self.commit(provider, Some(&group_id_bytes)).await?;
-        self.db()?.delete_group(&group_id_bytes).await
+        let key_digest = self
+            .state
+            .crypto
+            .hash(HashType::Sha2_256, &target_encryption_key)
+            .map_err(|e| format!("Failed to hash encryption key: {:?}", e))?;
+        let target_path = open_engine_key(&target_db_path);
+        let opening = target_path.as_deref().map(opening_lock);
+        let _opening = match &opening {
+            Some(lock) => Some(lock.lock().await),
+            None => None,
+        };
+        if let Some(path) = &target_path {
+            Self::already_open(&mut OPEN_ENGINES.lock(), path, &key_digest, &EngineOpenPolicy::Fail)?;
+        }
+        let db = crate::encrypted_db::EncryptedDb::open(target_db_path, target_encryption_key).await?;
+        let target = EngineStore::Db(std::sync::Arc::new(db));
+        self.start_migration(target, target_path.clone(), dual_write_seconds).await?;
+        if let Some(path) = target_path {
+            Self::register_open(&self.state, path, key_digest);
+        }
+        Ok(())
     }
 
-    pub async fn delete_key_package(
+    /// Like `begin_backend_migration`, with the storage backend registered
+    /// from Rust as `target_backend_name` as the target (see
+    /// `create_with_backend`).
+    pub async fn begin_backend_migration_to_backend(
         &self,
-        key_package_ref_bytes: Vec<u8>,
+        target_backend_name: String,
+        dual_write_seconds: u64,
     ) -> Result<(), String> {
-        let provider = self.load_global().await?;
-        let hash_ref = openmls::ciphersuite::hash_ref::KeyPackageRef::tls_deserialize_exact_bytes(&key_package_ref_bytes)
-            .map_err(|e| format!("Failed to deserialize key package ref: {}", e))?;
-        provider.storage().delete_key_package(&hash_ref)
-            .map_err(|e| format!("Failed to delete key package: {}", e))?;
-
-        self.commit(provider, None).await
+        let backend = crate::storage_backend::registered_backend(&target_backend_name)
+            .ok_or_else(|| format!("No storage backend registered as {}", target_backend_name))?;
+        self.start_migration(EngineStore::Custom(backend), None, dual_write_seconds).await
     }
 
-    // ═══════════════════════════════════════════════════════════
-    // ADDITIONAL STATE QUERIES / MUTATING
-    // ═══════════════════════════════════════════════════════════
-
-    pub async fn remove_pending_proposal(
+    async fn start_migration(
         &self,
-        group_id_bytes: Vec<u8>,
-        proposal_ref_bytes: Vec<u8>,
+        target: EngineStore,
+        target_path: Option<String>,
+        dual_write_seconds: u64,
     ) -> Result<(), String> {
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let mut group = load_group(&group_id_bytes, &provider)?;
-        let proposal_ref = ProposalRef::tls_deserialize_exact_bytes(&proposal_ref_bytes)
-            .map_err(|e| format!("Failed to deserialize proposal ref: {}", e))?;
-        group.remove_pending_proposal(provider.storage(), &proposal_ref)
-            .map_err(|e| format!("Failed to remove pending proposal: {}", e))?;
+        let source = self.db()?;
+        match source {
+            EngineStore::Sandbox(_) => return Err("Sandboxes cannot be migrated".to_string()),
+            EngineStore::Migrating(_) => return Err(MIGRATION_IN_PROGRESS.to_string()),
+            EngineStore::Db(_) | EngineStore::Custom(_) => {}
+        }
+        let started_at = unix_now()?;
+        let dual_write_until = started_at.saturating_add(dual_write_seconds);
+        let migration =
+            crate::backend_migration::MigratingStore::start(source, target, target_path, started_at, dual_write_until)
+                .await?;
+        let mut db = self.state.db.write();
+        match db.as_ref() {
+            None => Err("MlsEngine is closed".to_string()),
+            Some(EngineStore::Migrating(_)) => Err(MIGRATION_IN_PROGRESS.to_string()),
+            Some(_) => {
+                *db = Some(EngineStore::Migrating(std::sync::Arc::new(migration)));
+                Ok(())
+            }
+        }
+    }
 
-        self.commit(provider, Some(&group_id_bytes)).await
+    /// Progress of the running backend migration, `None` if there is none.
+    /// Compares every entry of both stores, so it costs about as much as
+    /// reading the whole database twice.
+    pub async fn migration_status(&self) -> Result<Option<MlsBackendMigrationStatus>, String> {
+        let EngineStore::Migrating(migration) = self.db()? else {
+            return Ok(None);
+        };
+        let mismatched_entries = migration.mismatched_entries().await?;
+        Ok(Some(MlsBackendMigrationStatus {
+            started_at: migration.started_at,
+            dual_write_until: migration.dual_write_until,
+            mirrored_writes: migration.mirrored_writes(),
+            target_write_failures: migration.target_failures(),
+            last_target_error: migration.last_target_error(),
+            mismatched_entries,
+            ready: mismatched_entries == 0 && unix_now()? >= migration.dual_write_until,
+        }))
     }
 
-    pub async fn group_epoch_authenticator(
-        &self,
-        group_id_bytes: Vec<u8>,
-    ) -> Result<Vec<u8>, String> {
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        Ok(group.epoch_authenticator().as_slice().to_vec())
+    /// Switch the engine to the migration target and close the previous
+    /// database, whose data is left in place for the caller to delete.
+    ///
+    /// Fails while the dual-write period is running unless `force` is set,
+    /// and whenever source and target still differ after the target is
+    /// brought up to date with writes that reached only the source.
+    pub async fn finalize_backend_migration(&self, force: bool) -> Result<(), String> {
+        let EngineStore::Migrating(migration) = self.db()? else {
+            return Err(NO_MIGRATION.to_string());
+        };
+        if !force && unix_now()? < migration.dual_write_until {
+            return Err(format!("Dual-write period runs until {}", migration.dual_write_until));
+        }
+        migration.resync().await?;
+        let mismatched = migration.mismatched_entries().await?;
+        if mismatched > 0 {
+            return Err(format!("Migration target differs from source in {} entries", mismatched));
+        }
+        self.end_migration(&migration, migration.target.clone())?;
+        // The previous database may be opened again, by another engine.
+        OPEN_ENGINES.lock().retain(|path, engine| {
+            engine.state.as_ptr() != std::sync::Arc::as_ptr(&self.state) || Some(path) == migration.target_path.as_ref()
+        });
+        self.state.key_digests.lock().retain(|path, _| Some(path) == migration.target_path.as_ref());
+        let source = migration.source.clone();
+        drop(migration);
+        close_store(source).await
     }
 
-    pub async fn group_configuration(
-        &self,
-        group_id_bytes: Vec<u8>,
-    ) -> Result<GroupConfigurationResult, String> {
-        let provider = self.load_for_group(&group_id_bytes).await?;
-        let group = load_group(&group_id_bytes, &provider)?;
-        let join_config = group.configuration();
-        let cs = native_to_ciphersuite(group.ciphersuite())?;
-        let wf = if join_config.wire_format_policy() == PURE_PLAINTEXT_WIRE_FORMAT_POLICY {
-            super::types::MlsWireFormatPolicy::Plaintext
-        } else {
-            super::types::MlsWireFormatPolicy::Ciphertext
+    /// Stop the running backend migration and keep using the current
+    /// database. Everything written to the target is deleted, so a
+    /// migration to it can be started again.
+    pub async fn abort_backend_migration(&self) -> Result<(), String> {
+        let EngineStore::Migrating(migration) = self.db()? else {
+            return Err(NO_MIGRATION.to_string());
         };
-        let sr_config = join_config.sender_ratchet_configuration();
-        Ok(GroupConfigurationResult {
-            ciphersuite: cs,
-            wire_format_policy: wf,
-            padding_size: join_config.padding_size() as u32,
-            sender_ratchet_max_out_of_order: sr_config.out_of_order_tolerance(),
-            sender_ratchet_max_forward_distance: sr_config.maximum_forward_distance(),
-        })
+        self.end_migration(&migration, migration.source.clone())?;
+        if let Some(path) = &migration.target_path {
+            OPEN_ENGINES.lock().remove(path);
+            self.state.key_digests.lock().remove(path);
+        }
+        let target = migration.target.clone();
+        drop(migration);
+        let keys = target.load_all().await?.into_iter().map(|(_, key, _)| key).collect();
+        let clear = StorageUpdates { upserts: Vec::new(), deletes: keys };
+        target.save_updates_batch(vec![(clear, None)]).await?;
+        close_store(target).await
+    }
+
+    /// Replace `migration` with `next` as the engine's store.
+    fn end_migration(
+        &self,
+        migration: &std::sync::Arc<crate::backend_migration::MigratingStore>,
+        next: EngineStore,
+    ) -> Result<(), String> {
+        let mut db = self.state.db.write();
+        match db.as_ref() {
+            Some(EngineStore::Migrating(current)) if std::sync::Arc::ptr_eq(current, migration) => {
+                *db = Some(next);
+                Ok(())
+            }
+            None => Err("MlsEngine is closed".to_string()),
+            Some(_) => Err("Backend migration ended concurrently".to_string()),
+        }
     }
 
     // ═══════════════════════════════════════════════════════════
@@ -1752,25 +9525,88 @@ impl MlsEngine {
         crate::encrypted_db::LATEST_SCHEMA_VERSION
     }
 
+    /// Backend, database path, schema version and SQLite settings of the
+    /// engine's storage, for support diagnostics (e.g. checking that the
+    /// expected account database was opened).
+    ///
+    /// # Security
+    /// The path can reveal account identifiers; treat it like other
+    /// diagnostics before sending it off-device.
+    pub async fn engine_info(&self) -> Result<MlsEngineInfo, String> {
+        let store = self.db()?;
+        let Some(info) = store.info().await? else {
+            return Ok(MlsEngineInfo {
+                backend: match store {
+                    // A migration reports its source, which is never a sandbox.
+                    EngineStore::Custom(_) | EngineStore::Migrating(_) => MlsStorageBackend::Custom,
+                    EngineStore::Db(_) | EngineStore::Sandbox(_) => MlsStorageBackend::Sandbox,
+                },
+                db_path: None,
+                schema_version: crate::encrypted_db::LATEST_SCHEMA_VERSION,
+                page_size: None,
+                cache_size: None,
+                journal_mode: None,
+                wal_active: false,
+            });
+        };
+        let backend = if cfg!(target_arch = "wasm32") {
+            MlsStorageBackend::IndexedDb
+        } else {
+            MlsStorageBackend::Sqlcipher
+        };
+        Ok(MlsEngineInfo {
+            backend,
+            db_path: Some(info.path),
+            schema_version: info.schema_version,
+            page_size: info.page_size,
+            cache_size: info.cache_size,
+            wal_active: info.journal_mode.as_deref() == Some("wal"),
+            journal_mode: info.journal_mode,
+        })
+    }
+
     /// Close the engine, wiping the encryption key from memory and closing the
     /// database connection. After calling this, all operations will fail with
     /// "MlsEngine is closed". Idempotent — calling close on an already-closed
-    /// engine is a no-op.
+    /// engine is a no-op. Closing affects every handle obtained via
+    /// `from_token` and invalidates the engine's token.
     pub async fn close(&self) -> Result<(), String> {
-        let arc = { self.db.write().take() };
-        match arc {
-            Some(arc) => match std::sync::Arc::try_unwrap(arc) {
-                Ok(db) => db.close().await,
-                Err(_) => Ok(()), // In-flight operations hold the last ref; cleanup on drop
-            },
+        if let Some(token) = self.state.token.get() {
+            ENGINE_TOKENS.lock().remove(token);
+        }
+        self.drop_all_sandboxes();
+        let store = { self.state.db.write().take() };
+        self.state.key_digests.lock().clear();
+        match store {
+            Some(store) => close_store(store).await,
             None => Ok(()), // Already closed — idempotent
         }
     }
 
+    /// Irrecoverably destroy all data of this engine and close it.
+    ///
+    /// Intended for remote "wipe this device" commands. On native the
+    /// SQLCipher database is rekeyed to a random, discarded key and its files
+    /// are deleted; on web the IndexedDB database is deleted. Returns only
+    /// after the wipe has been verified. Affects every handle obtained via
+    /// `from_token`.
+    pub async fn secure_wipe(&self) -> Result<(), String> {
+        if let Some(token) = self.state.token.get() {
+            ENGINE_TOKENS.lock().remove(token);
+        }
+        self.drop_all_sandboxes();
+        let store = { self.state.db.write().take() }.ok_or_else(|| "MlsEngine is closed".to_string())?;
+        self.state.key_digests.lock().clear();
+        self.state.epoch_sinks.lock().clear();
+        self.state.group_subscriptions.lock().clear();
+        *self.state.event_throttle.lock() = EventThrottle::default();
+        wipe_store(store).await
+    }
+
     /// Check whether this engine has been closed.
     #[flutter_rust_bridge::frb(sync)]
     pub fn is_closed(&self) -> bool {
-        self.db.read().is_none()
+        self.state.db.read().is_none()
     }
 }
 
@@ -1778,6 +9614,62 @@ impl MlsEngine {
 // MESSAGE UTILITIES (standalone, no storage needed)
 // ═══════════════════════════════════════════════════════════════
 
+/// Compute the key package ref (TLS-serialized `KeyPackageRef`) of a
+/// serialized key package.
+///
+/// This is the identifier accepted by `delete_key_package` and
+/// `mark_key_package_published`.
+#[flutter_rust_bridge::frb(sync)]
+pub fn key_package_ref(key_package_bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+    let crypto = crate::hybrid_crypto::HybridCrypto::new();
+    let kp_in = KeyPackageIn::tls_deserialize_exact_bytes(&key_package_bytes)
+        .map_err(|e| format!("Failed to deserialize key package: {}", e))?;
+    let kp = kp_in
+        .validate(&crypto, ProtocolVersion::Mls10)
+        .map_err(|e| format!("Failed to validate key package: {}", e))?;
+    kp.hash_ref(&crypto)
+        .map_err(|e| format!("Failed to compute key package ref: {}", e))?
+        .tls_serialize_detached()
+        .map_err(|e| format!("Failed to serialize key package ref: {}", e))
+}
+
+/// Decode a serialized key package without adding it to a group, e.g. to
+/// show who an invite is for.
+///
+/// Fails only if the bytes are not a key package; one that does not
+/// validate is still decoded and reported with `signature_valid: false`.
+/// Lifetime is reported, not checked.
+#[flutter_rust_bridge::frb(sync)]
+pub fn inspect_key_package(key_package_bytes: Vec<u8>) -> Result<KeyPackageInspectResult, String> {
+    let crypto = crate::hybrid_crypto::HybridCrypto::new();
+    let kp_in = KeyPackageIn::tls_deserialize_exact_bytes(&key_package_bytes)
+        .map_err(|e| format!("Failed to deserialize key package: {}", e))?;
+    let (kp, signature_valid) = match kp_in.clone().validate(&crypto, ProtocolVersion::Mls10) {
+        Ok(kp) => (kp, true),
+        // Unverified conversion, available with openmls' `test-utils` feature.
+        Err(_) => (KeyPackage::from(kp_in), false),
+    };
+    let leaf_node = kp.leaf_node();
+    let credential = leaf_node.credential();
+    let (not_before, not_after) = key_package_lifetime(&kp)?;
+    Ok(KeyPackageInspectResult {
+        ciphersuite: native_to_ciphersuite(kp.ciphersuite())?,
+        credential_identity: credential.serialized_content().to_vec(),
+        credential_type: match credential.credential_type() {
+            CredentialType::Basic => 1,
+            CredentialType::X509 => 2,
+            _ => 0,
+        },
+        signature_key: leaf_node.signature_key().as_slice().to_vec(),
+        not_before,
+        not_after,
+        key_package_extensions: kp.extensions().iter().map(extension_to_mls).collect::<Result<_, _>>()?,
+        leaf_node_extensions: leaf_node.extensions().iter().map(extension_to_mls).collect::<Result<_, _>>()?,
+        last_resort: kp.last_resort(),
+        signature_valid,
+    })
+}
+
 /// Extract the group ID from an MLS protocol message.
 ///
 /// Useful for routing incoming messages to the right group before calling
@@ -1806,6 +9698,13 @@ pub fn mls_message_extract_epoch(message_bytes: Vec<u8>) -> Result<u64, String>
     Ok(protocol_msg.epoch().as_u64())
 }
 
+/// Whether decrypted application data is a join receipt (see
+/// `MlsEngine::set_join_receipts_enabled`).
+#[flutter_rust_bridge::frb(sync)]
+pub fn is_join_receipt(application_data: Vec<u8>) -> bool {
+    application_data == JOIN_RECEIPT_CONTENT
+}
+
 /// Get the content type of an MLS protocol message as a string.
 ///
 /// Returns one of: "application", "proposal", "commit".
@@ -1824,3 +9723,132 @@ pub fn mls_message_content_type(message_bytes: Vec<u8>) -> Result<String, String
     };
     Ok(ct.to_string())
 }
+
+/// Order in which to process a set of queued protocol messages, as indices
+/// into `messages_bytes`.
+///
+/// Within each group, messages are sorted by epoch, and within an epoch
+/// proposals come first, then application messages, then commits. A commit
+/// is thus processed only after everything it may depend on, and before any
+/// application message of the epoch it creates. Groups keep the order in
+/// which they first appear; messages that cannot be parsed go last, in their
+/// original order, so processing them still reports the error.
+#[flutter_rust_bridge::frb(sync)]
+pub fn mls_message_processing_order(messages_bytes: Vec<Vec<u8>>) -> Vec<u32> {
+    processing_order(&messages_bytes)
+}
+
+fn processing_order(messages_bytes: &[Vec<u8>]) -> Vec<u32> {
+    let mut groups: Vec<Vec<u8>> = Vec::new();
+    let mut keys: Vec<(usize, u64, u8, u32)> = messages_bytes
+        .iter()
+        .enumerate()
+        .map(|(index, bytes)| {
+            let protocol_msg = MlsMessageIn::tls_deserialize_exact_bytes(bytes)
+                .ok()
+                .and_then(|msg_in| msg_in.try_into_protocol_message().ok());
+            let Some(protocol_msg) = protocol_msg else {
+                return (usize::MAX, u64::MAX, u8::MAX, index as u32);
+            };
+            let group_id = protocol_msg.group_id().as_slice();
+            let group = groups.iter().position(|g| g == group_id).unwrap_or_else(|| {
+                groups.push(group_id.to_vec());
+                groups.len() - 1
+            });
+            let lane = match protocol_msg.content_type() {
+                ContentType::Proposal => 0,
+                ContentType::Application => 1,
+                ContentType::Commit => 2,
+            };
+            (group, protocol_msg.epoch().as_u64(), lane, index as u32)
+        })
+        .collect();
+    keys.sort();
+    keys.into_iter().map(|(_, _, _, index)| index).collect()
+}
+
+/// Pack the Welcomes inviting one user to several groups into a single
+/// bundle for `join_all_from_bundle`. The format is documented in the
+/// `invite_bundle` module.
+#[flutter_rust_bridge::frb(sync)]
+pub fn bundle_invites(invites: Vec<MlsBundledInvite>) -> Result<Vec<u8>, String> {
+    let invites: Vec<_> = invites
+        .into_iter()
+        .map(|invite| crate::invite_bundle::BundledInvite {
+            group_id: invite.group_id,
+            welcome: invite.welcome,
+            ratchet_tree: invite.ratchet_tree,
+        })
+        .collect();
+    crate::invite_bundle::encode(&invites)
+}
+
+/// Unpack a bundle built with `bundle_invites`.
+#[flutter_rust_bridge::frb(sync)]
+pub fn unbundle_invites(bundle_bytes: Vec<u8>) -> Result<Vec<MlsBundledInvite>, String> {
+    Ok(crate::invite_bundle::decode(&bundle_bytes)?
+        .into_iter()
+        .map(|invite| MlsBundledInvite {
+            group_id: invite.group_id,
+            welcome: invite.welcome,
+            ratchet_tree: invite.ratchet_tree,
+        })
+        .collect())
+}
+
+/// Check a snapshot from `export_public_state` without any group state.
+///
+/// Verifies that the snapshot decodes, uses a known ciphersuite, carries a
+/// ratchet tree OpenMLS accepts whose tree hash matches the group context,
+/// and a confirmation tag of the ciphersuite's MAC length. Leaf signatures
+/// and the confirmation tag itself are not verified; the latter needs the
+/// epoch's secrets.
+#[flutter_rust_bridge::frb(sync)]
+pub fn verify_public_state(public_state_bytes: Vec<u8>) -> Result<MlsPublicStateInfo, String> {
+    let state = crate::public_state::decode(&public_state_bytes)?;
+    let ciphersuite = state.group_context.ciphersuite();
+    let context = group_context_info(&state.group_context)?;
+
+    RatchetTreeIn::tls_deserialize_exact_bytes(&state.ratchet_tree)
+        .map_err(|e| format!("Failed to deserialize ratchet tree: {}", e))?;
+    let crypto = crate::hybrid_crypto::HybridCrypto::new();
+    let tree_hash = crate::tree_view::tree_hash(&state.ratchet_tree, |input| {
+        crypto
+            .hash(ciphersuite.hash_algorithm(), input)
+            .map_err(|e| format!("Failed to compute tree hash: {:?}", e))
+    })?;
+    if tree_hash != context.tree_hash {
+        return Err("Ratchet tree does not match the group context tree hash".to_string());
+    }
+
+    let (mac, rest) = tls_codec::VLBytes::tls_deserialize_bytes(&state.confirmation_tag)
+        .map_err(|e| format!("Malformed confirmation tag: {}", e))?;
+    if !rest.is_empty() || mac.as_slice().len() != ciphersuite.hash_length() {
+        return Err("Confirmation tag has the wrong length".to_string());
+    }
+
+    let member_count = crate::tree_view::parse_ratchet_tree(&state.ratchet_tree)?
+        .iter()
+        .step_by(2)
+        .filter(|node| node.is_some())
+        .count() as u32;
+    Ok(MlsPublicStateInfo {
+        context,
+        confirmation_tag: state.confirmation_tag,
+        ratchet_tree: state.ratchet_tree,
+        member_count,
+    })
+}
+
+/// Wire format of a protocol message (plaintext `PublicMessage` or
+/// encrypted `PrivateMessage`).
+#[flutter_rust_bridge::frb(sync)]
+pub fn mls_message_wire_format(message_bytes: Vec<u8>) -> Result<MlsWireFormat, String> {
+    let msg_in = MlsMessageIn::tls_deserialize_exact_bytes(&message_bytes)
+        .map_err(|e| format!("Failed to deserialize message: {}", e))?;
+    match msg_in.try_into_protocol_message() {
+        Ok(ProtocolMessage::PublicMessage(_)) => Ok(MlsWireFormat::Plaintext),
+        Ok(ProtocolMessage::PrivateMessage(_)) => Ok(MlsWireFormat::Ciphertext),
+        Err(e) => Err(format!("Not a protocol message: {}", e)),
+    }
+}
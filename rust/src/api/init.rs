@@ -19,3 +19,31 @@ pub fn is_openmls_initialized() -> bool {
     // Add initialization state check logic here
     true
 }
+
+/// How group ids, credentials and message contents appear in log output.
+///
+/// Message plaintext and AAD are never logged, only their length.
+pub enum MlsLogRedaction {
+    /// Values are logged in full. Only honoured by debug builds; release
+    /// builds treat it as `Standard`.
+    Off,
+    /// Group ids are replaced by a salted hash that is stable for the
+    /// lifetime of the process; credentials are truncated to a short prefix.
+    Standard,
+    /// As `Standard`, but credentials are reduced to their length.
+    Strict,
+}
+
+/// Set the process-wide log redaction policy.
+///
+/// Debug builds default to `Off`, release builds to `Standard`.
+#[flutter_rust_bridge::frb(sync)]
+pub fn set_log_redaction(policy: MlsLogRedaction) {
+    crate::redact::set_policy(policy);
+}
+
+/// The log redaction policy currently in effect.
+#[flutter_rust_bridge::frb(sync)]
+pub fn log_redaction() -> MlsLogRedaction {
+    crate::redact::policy()
+}
@@ -7,6 +7,7 @@ use openmls::prelude::*;
 use openmls_basic_credential::SignatureKeyPair;
 use zeroize::Zeroize;
 
+use super::engine::MlsEngine;
 use super::types::{ciphersuite_to_native, MlsCiphersuite};
 
 /// An opaque wrapper around an OpenMLS SignatureKeyPair.
@@ -106,6 +107,33 @@ impl MlsSignatureKeyPair {
     }
 }
 
+/// A signature key pair created by `MlsEngine::generate_signature_key_pair`.
+pub struct GeneratedSigner {
+    /// Public signature key, e.g. for `create_group` and key packages.
+    pub public_key: Vec<u8>,
+    /// Handle of the registered signer, to pass as `signer_bytes`. Holds no
+    /// key material.
+    pub signer_handle: Vec<u8>,
+}
+
+impl MlsEngine {
+    /// Generate a signature key pair for `ciphersuite`'s signature scheme,
+    /// store it in the engine and register it (see `register_signer`).
+    ///
+    /// The private key never leaves Rust: operations take the returned
+    /// `signer_handle` as `signer_bytes`. Like every registered signer, the
+    /// handle is forgotten when the engine is dropped; the stored key can
+    /// be exported again with `stored_signer` from its public key.
+    pub async fn generate_signature_key_pair(&self, ciphersuite: MlsCiphersuite) -> Result<GeneratedSigner, String> {
+        let cs = ciphersuite_to_native(&ciphersuite);
+        let signer = SignatureKeyPair::new(cs.signature_algorithm())
+            .map_err(|e| format!("Failed to generate signature key pair: {}", e))?;
+        let public_key = signer.public().to_vec();
+        let signer_handle = self.store_and_register_signer(signer).await?;
+        Ok(GeneratedSigner { public_key, signer_handle })
+    }
+}
+
 /// Helper for serde serialization of key pair fields.
 #[derive(serde::Serialize, serde::Deserialize)]
 struct SerializableKeyPair {
@@ -143,6 +171,19 @@ pub fn serialize_signer(
     result
 }
 
+/// Serialize `signer` the way `serialize_signer` does, for `signer_bytes`.
+///
+/// # Security
+/// The returned bytes contain private key material.
+pub(crate) fn signer_to_bytes(signer: &SignatureKeyPair) -> Result<Vec<u8>, String> {
+    let serializable = SerializableSigner {
+        private: signer.private().to_vec(),
+        public: signer.public().to_vec(),
+        scheme: signer.signature_scheme() as u16,
+    };
+    serde_json::to_vec(&serializable).map_err(|e| format!("Failed to serialize signer: {}", e))
+}
+
 /// Reconstruct a `SignatureKeyPair` from raw signer bytes (JSON-serialized).
 /// Zeroizes the input bytes regardless of success or failure.
 pub(crate) fn signer_from_bytes(mut signer_bytes: Vec<u8>) -> Result<SignatureKeyPair, String> {
@@ -6,3 +6,4 @@ pub mod init;
 pub mod keys;
 pub mod engine;
 pub mod types;
+pub mod v2;
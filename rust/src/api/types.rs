@@ -28,6 +28,18 @@ pub enum MlsCiphersuite {
 pub enum MlsWireFormatPolicy {
     Plaintext,
     Ciphertext,
+    /// Send handshake messages as plaintext by default; accept both.
+    MixedPlaintext,
+    /// Send handshake messages as ciphertext by default; accept both.
+    MixedCiphertext,
+}
+
+/// Wire format of a single outgoing message.
+pub enum MlsWireFormat {
+    /// `PublicMessage`.
+    Plaintext,
+    /// `PrivateMessage`.
+    Ciphertext,
 }
 
 /// Type of a processed incoming message.
@@ -35,6 +47,39 @@ pub enum ProcessedMessageType {
     Application,
     Proposal,
     StagedCommit,
+    /// An application message that was already processed, reported instead
+    /// of an error when deduplication is enabled for the group.
+    Duplicate,
+    /// A commit referencing proposals we never received. Nothing was
+    /// applied; pass the proposals to `inject_missing_proposal` and process
+    /// the commit again.
+    MissingProposals,
+    /// A commit older than the engine's handshake expiry policy allows.
+    /// Nothing was applied; see `expired` for its age.
+    Expired,
+}
+
+/// Why a message could not be decrypted, as classified by
+/// `decryption_failure_report`.
+pub enum DecryptionFailureKind {
+    /// The message is for another group.
+    WrongGroup,
+    /// The message is from an epoch we have not reached: we missed commits
+    /// or our state has forked from the sender's.
+    FutureEpoch,
+    /// The message is from a past epoch whose secrets are no longer kept
+    /// (see `max_past_epochs`).
+    ExpiredEpoch,
+    /// The message key was already used: a replay or duplicate delivery.
+    SecretConsumed,
+    /// The generation is further behind than the out-of-order window.
+    GenerationTooOld,
+    /// The generation is further ahead than the forward-distance window.
+    GenerationTooFarAhead,
+    /// We sent the message; own messages cannot be decrypted.
+    OwnMessage,
+    /// Any other failure (AEAD, malformed content, signature, ...).
+    Other,
 }
 
 /// MLS proposal types.
@@ -50,11 +95,17 @@ pub enum MlsProposalType {
 }
 
 /// Information about a group member.
+#[derive(Clone)]
 pub struct MlsMemberInfo {
+    /// Leaf index. Only valid in the current epoch: leaves are reused and
+    /// the tree is truncated as members come and go.
     pub index: u32,
     /// TLS-serialized Credential. Deserialize with `MlsCredential.deserialize()`.
     pub credential: Vec<u8>,
     pub signature_key: Vec<u8>,
+    /// Opaque handle of the member, stable across epochs until it changes
+    /// its credential or signature key. Resolve with `resolve_member`.
+    pub member_handle: Vec<u8>,
 }
 
 /// An MLS extension (type + data).
@@ -67,25 +118,278 @@ pub struct MlsExtension {
 pub struct MlsPendingProposalInfo {
     /// The type of proposal.
     pub proposal_type: MlsProposalType,
+    /// TLS-serialized `ProposalRef`, for `remove_pending_proposal`.
+    pub proposal_ref: Vec<u8>,
+    /// Sender's leaf index (if sender is a group member).
+    pub sender_index: Option<u32>,
+    /// Sender's member handle (if sender is a group member).
+    pub sender_handle: Option<Vec<u8>>,
+    /// For GroupContextExtensions proposals: the proposed extension set and
+    /// how it differs from the current one. `None` for other proposal types.
+    pub group_context_extensions: Option<MlsGroupContextExtensionsChange>,
+}
+
+/// A proposal covered by a commit we created.
+pub struct MlsCommittedProposal {
+    /// The type of proposal.
+    pub proposal_type: MlsProposalType,
+    /// Sender's leaf index (if sender is a group member). For proposals we
+    /// added inline this is our own leaf index.
+    pub sender_index: Option<u32>,
+    /// Whether the proposal was committed by reference, i.e. pulled from the
+    /// proposal store rather than created inline for this commit.
+    pub by_reference: bool,
+    /// For Remove proposals: leaf index of the removed member.
+    pub removed_index: Option<u32>,
+    /// For Add proposals: TLS-serialized Credential of the added member.
+    pub added_credential: Option<Vec<u8>>,
+}
+
+/// Outcome of discarding an own commit that the delivery service rejected.
+pub struct MlsCommitRejection {
+    /// Epoch the group stays in.
+    pub epoch: u64,
+    /// By-reference proposals that were put back into the proposal store
+    /// because they had gone missing from it.
+    pub restored_proposals: u32,
+    /// Proposals the rejected commit created inline. They existed only in
+    /// that commit, so they must be proposed or committed again.
+    pub re_propose: Vec<MlsCommittedProposal>,
+}
+
+/// A key package that cannot join a group because its leaf node lacks
+/// capabilities the group requires.
+///
+/// Covers the types listed in the group's `required_capabilities` extension
+/// and the group context extensions in use. Default extension and proposal
+/// types (RFC 9420 §7.2) are always treated as supported.
+pub struct MlsAddCandidateIncompatibility {
+    /// Position of the key package in the list passed in.
+    pub index: u32,
+    /// Required extension types the leaf node does not support.
+    pub missing_extensions: Vec<u16>,
+    /// Required proposal types the leaf node does not support.
+    pub missing_proposals: Vec<u16>,
+    /// Required credential types the leaf node does not support.
+    pub missing_credentials: Vec<u16>,
+}
+
+/// Decoded content of a GroupContextExtensions proposal.
+///
+/// A GroupContextExtensions proposal replaces the whole extension set, so
+/// `proposed` is the complete set the group will have after the commit. The
+/// type lists describe the difference to the current set.
+pub struct MlsGroupContextExtensionsChange {
+    /// The complete proposed extension set.
+    pub proposed: Vec<MlsExtension>,
+    /// Extension types present in the proposal but not in the group.
+    pub added_types: Vec<u16>,
+    /// Extension types present in the group but dropped by the proposal.
+    pub removed_types: Vec<u16>,
+    /// Extension types present in both, with different data.
+    pub changed_types: Vec<u16>,
+}
+
+/// Extension types defined by RFC 9420 and OpenMLS.
+pub enum MlsExtensionType {
+    ApplicationId,
+    RatchetTree,
+    RequiredCapabilities,
+    ExternalPub,
+    ExternalSenders,
+    LastResort,
+}
+
+/// Credential types defined by RFC 9420.
+pub enum MlsCredentialType {
+    Basic,
+    X509,
+}
+
+/// A credential newly seen in a processed commit or Welcome, passed to the
+/// validator set with `MlsEngine::set_credential_validator`.
+pub struct MlsCredentialCheck {
+    pub group_id: Vec<u8>,
+    /// Leaf index of the member presenting the credential; `None` for
+    /// members being added, whose index is assigned when the commit merges.
+    pub leaf_index: Option<u32>,
+    /// TLS-serialized Credential. Deserialize with `MlsCredential.deserialize()`.
+    pub credential: Vec<u8>,
+    /// Signature key bound to the credential by the leaf node.
+    pub signature_key: Vec<u8>,
+}
+
+/// A pending proposal a commit is about to include, passed to the policy
+/// set with `MlsEngine::set_proposal_policy`.
+pub struct MlsProposalCheck {
+    pub group_id: Vec<u8>,
+    /// TLS-serialized `ProposalRef`.
+    pub proposal_ref: Vec<u8>,
+    pub proposal_type: MlsProposalType,
     /// Sender's leaf index (if sender is a group member).
     pub sender_index: Option<u32>,
+    /// TLS-serialized Proposal.
+    pub proposal: Vec<u8>,
+}
+
+/// A credential validator's decision.
+pub enum MlsCredentialVerdict {
+    /// The credential was verified.
+    Accept,
+    /// The credential could not be verified; processing goes on, with the
+    /// result marked `credential_verified: false`.
+    Unverified,
+    /// Refuse the commit or Welcome. Nothing is applied.
+    Reject,
 }
 
 /// Capabilities advertised by a leaf node.
 ///
-/// All fields are lists of u16 values representing the supported types.
-/// Empty lists mean "use defaults".
+/// Known values are listed by their typed enum; values without a name here
+/// (custom or GREASE types) go in the matching `other_*` list. A category is
+/// left at its default when both its typed and `other_*` lists are empty.
 pub struct MlsCapabilities {
     /// Supported protocol versions (1 = MLS 1.0).
     pub versions: Vec<u16>,
     /// Supported ciphersuites.
-    pub ciphersuites: Vec<u16>,
+    pub ciphersuites: Vec<MlsCiphersuite>,
     /// Supported extension types.
-    pub extensions: Vec<u16>,
-    /// Supported proposal types.
-    pub proposals: Vec<u16>,
+    pub extensions: Vec<MlsExtensionType>,
+    /// Supported proposal types. `Custom` is not allowed here; list custom
+    /// proposal types in `other_proposals`.
+    pub proposals: Vec<MlsProposalType>,
     /// Supported credential types.
-    pub credentials: Vec<u16>,
+    pub credentials: Vec<MlsCredentialType>,
+    /// Ciphersuite values not covered by `MlsCiphersuite`.
+    pub other_ciphersuites: Vec<u16>,
+    /// Extension types not covered by `MlsExtensionType`.
+    pub other_extensions: Vec<u16>,
+    /// Proposal types not covered by `MlsProposalType`.
+    pub other_proposals: Vec<u16>,
+    /// Credential types not covered by `MlsCredentialType`.
+    pub other_credentials: Vec<u16>,
+}
+
+impl MlsCapabilities {
+    /// Build capabilities from raw u16 code points, sorting each value into
+    /// its typed list or, if unknown, the matching `other_*` list.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn from_raw(
+        versions: Vec<u16>,
+        ciphersuites: Vec<u16>,
+        extensions: Vec<u16>,
+        proposals: Vec<u16>,
+        credentials: Vec<u16>,
+    ) -> MlsCapabilities {
+        let mut caps = MlsCapabilities {
+            versions,
+            ciphersuites: Vec::new(),
+            extensions: Vec::new(),
+            proposals: Vec::new(),
+            credentials: Vec::new(),
+            other_ciphersuites: Vec::new(),
+            other_extensions: Vec::new(),
+            other_proposals: Vec::new(),
+            other_credentials: Vec::new(),
+        };
+        for value in ciphersuites {
+            match Ciphersuite::try_from(value).ok().and_then(|cs| native_to_ciphersuite(cs).ok()) {
+                Some(cs) => caps.ciphersuites.push(cs),
+                None => caps.other_ciphersuites.push(value),
+            }
+        }
+        for value in extensions {
+            match extension_type_from_value(value) {
+                Some(ext) => caps.extensions.push(ext),
+                None => caps.other_extensions.push(value),
+            }
+        }
+        for value in proposals {
+            match proposal_type_from_value(value) {
+                Some(p) => caps.proposals.push(p),
+                None => caps.other_proposals.push(value),
+            }
+        }
+        for value in credentials {
+            match credential_type_from_value(value) {
+                Some(c) => caps.credentials.push(c),
+                None => caps.other_credentials.push(value),
+            }
+        }
+        caps
+    }
+
+    /// Default capabilities that additionally advertise the given custom
+    /// proposal types. Shorthand for `add_custom_proposals` on an empty set.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn with_custom_proposals(proposal_types: Vec<u16>) -> Result<MlsCapabilities, String> {
+        MlsCapabilities::from_raw(Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new())
+            .add_custom_proposals(proposal_types)
+    }
+
+    /// Copy of these capabilities that also advertises the given custom
+    /// proposal types. Types already listed are kept once; RFC 9420 proposal
+    /// types are rejected, as they are always supported.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn add_custom_proposals(&self, proposal_types: Vec<u16>) -> Result<MlsCapabilities, String> {
+        let mut proposals = self.raw_proposals()?;
+        for value in proposal_types {
+            if proposal_type_from_value(value).is_some() {
+                return Err(format!("Proposal type 0x{:04x} is not a custom proposal type", value));
+            }
+            if !proposals.contains(&value) {
+                proposals.push(value);
+            }
+        }
+        Ok(MlsCapabilities::from_raw(
+            self.versions.clone(),
+            self.raw_ciphersuites(),
+            self.raw_extensions(),
+            proposals,
+            self.raw_credentials(),
+        ))
+    }
+
+    /// All supported ciphersuites as u16 code points.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn raw_ciphersuites(&self) -> Vec<u16> {
+        self.ciphersuites
+            .iter()
+            .map(|cs| ciphersuite_to_native(cs) as u16)
+            .chain(self.other_ciphersuites.iter().copied())
+            .collect()
+    }
+
+    /// All supported extension types as u16 code points.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn raw_extensions(&self) -> Vec<u16> {
+        self.extensions
+            .iter()
+            .map(extension_type_value)
+            .chain(self.other_extensions.iter().copied())
+            .collect()
+    }
+
+    /// All supported proposal types as u16 code points.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn raw_proposals(&self) -> Result<Vec<u16>, String> {
+        let mut values = Vec::with_capacity(self.proposals.len() + self.other_proposals.len());
+        for p in &self.proposals {
+            values.push(proposal_type_value(p)?);
+        }
+        values.extend_from_slice(&self.other_proposals);
+        Ok(values)
+    }
+
+    /// All supported credential types as u16 code points.
+    #[flutter_rust_bridge::frb(sync)]
+    pub fn raw_credentials(&self) -> Vec<u16> {
+        self.credentials
+            .iter()
+            .map(credential_type_value)
+            .chain(self.other_credentials.iter().copied())
+            .collect()
+    }
 }
 
 /// Options for creating a key package with the builder API.
@@ -110,8 +414,74 @@ pub struct WelcomeInspectResult {
     pub ciphersuite: MlsCiphersuite,
     /// Number of PSKs required to join.
     pub psk_count: u32,
+    /// The PSKs required to join, in the order the Welcome lists them.
+    pub psks: Vec<MlsPskId>,
     /// The group epoch at time of Welcome.
     pub epoch: u64,
+    /// Extensions of the group context.
+    pub group_context_extensions: Vec<MlsExtension>,
+    /// Whether the GroupInfo carries the ratchet tree. Without it, the tree
+    /// must be passed to the join and the member list is unknown.
+    pub has_ratchet_tree: bool,
+    /// Number of members, if the ratchet tree is included.
+    pub member_count: Option<u32>,
+    /// Members listed in the ratchet tree, including the invitee; empty if
+    /// the tree is not included.
+    pub members: Vec<WelcomeMemberPreview>,
+}
+
+/// A member of the group a Welcome invites to, from its ratchet tree.
+///
+/// Taken from the GroupInfo before it is verified; treat it as a preview.
+pub struct WelcomeMemberPreview {
+    pub leaf_index: u32,
+    /// Credential type value (1 = Basic, 2 = X509).
+    pub credential_type: u16,
+    /// Serialized content of the credential (the identity, for a basic
+    /// credential).
+    pub identity: Vec<u8>,
+}
+
+/// Contents of a standalone KeyPackage, from `inspect_key_package`.
+pub struct KeyPackageInspectResult {
+    pub ciphersuite: MlsCiphersuite,
+    /// Serialized content of the credential (the identity, for a basic
+    /// credential).
+    pub credential_identity: Vec<u8>,
+    /// Credential type value (1 = Basic, 2 = X509, 0 = other).
+    pub credential_type: u16,
+    pub signature_key: Vec<u8>,
+    /// Validity period of the key package (Unix seconds).
+    pub not_before: u64,
+    pub not_after: u64,
+    /// Extensions on the key package itself.
+    pub key_package_extensions: Vec<MlsExtension>,
+    /// Extensions on the leaf node.
+    pub leaf_node_extensions: Vec<MlsExtension>,
+    pub last_resort: bool,
+    /// Whether the key package passes validation, including its leaf node
+    /// and key package signatures. The other fields are unverified when
+    /// this is false.
+    pub signature_valid: bool,
+}
+
+/// Kind of a pre-shared key.
+pub enum MlsPskKind {
+    /// Provided out of band by the application.
+    External,
+    /// Derived from a past epoch of a group (see `get_past_resumption_psk`).
+    Resumption,
+}
+
+/// Identifies a pre-shared key referenced by a Welcome.
+pub struct MlsPskId {
+    pub kind: MlsPskKind,
+    /// External: the PSK ID. Resumption: the ID of the group the PSK is
+    /// taken from.
+    pub id: Vec<u8>,
+    /// Resumption: the epoch the PSK is taken from.
+    pub epoch: Option<u64>,
+    pub nonce: Vec<u8>,
 }
 
 /// Full information about the own leaf node.
@@ -119,6 +489,8 @@ pub struct MlsLeafNodeInfo {
     /// TLS-serialized Credential. Deserialize with `MlsCredential.deserialize()`.
     pub credential: Vec<u8>,
     pub signature_key: Vec<u8>,
+    /// Own member handle, as other members see it in `MlsMemberInfo`.
+    pub member_handle: Vec<u8>,
     pub encryption_key: Vec<u8>,
     pub capabilities: MlsCapabilities,
     pub extensions: Vec<MlsExtension>,
@@ -134,18 +506,153 @@ pub struct MlsGroupContextInfo {
     pub extensions: Vec<u8>,
 }
 
+/// Public group state checked by `verify_public_state`.
+pub struct MlsPublicStateInfo {
+    pub context: MlsGroupContextInfo,
+    /// TLS-serialized confirmation tag of the epoch.
+    pub confirmation_tag: Vec<u8>,
+    /// TLS-serialized ratchet tree.
+    pub ratchet_tree: Vec<u8>,
+    /// Number of non-blank leaves in the tree.
+    pub member_count: u32,
+}
+
 /// Information about a staged commit before merging.
 pub struct StagedCommitInfo {
     /// TLS-serialized Credentials of members being added.
     pub add_credentials: Vec<Vec<u8>>,
     /// Leaf indices of members being removed.
     pub remove_indices: Vec<u32>,
+    /// Member handles (see `MlsMemberInfo`) of the members being removed,
+    /// in the order of `remove_indices`.
+    pub remove_handles: Vec<Vec<u8>>,
     /// Whether a self-update is included.
     pub has_update: bool,
     /// Whether the local member was removed.
     pub self_removed: bool,
     /// Number of PSK proposals.
     pub psk_count: u32,
+    /// The PSKs those proposals inject, in commit order.
+    pub psks: Vec<MlsPskId>,
+    /// Set when the commit is an external commit, i.e. someone joining
+    /// without a Welcome (e.g. via an invite link or to resync).
+    pub external_join: Option<MlsExternalJoinInfo>,
+}
+
+/// The joiner of an external commit, as seen by existing members before
+/// the commit is merged.
+pub struct MlsExternalJoinInfo {
+    /// TLS-serialized Credential of the joiner's new leaf.
+    pub credential: Vec<u8>,
+    /// Signature key bound to the credential by the new leaf.
+    pub signature_key: Vec<u8>,
+    /// Member handle (see `MlsMemberInfo`) of the new leaf.
+    pub member_handle: Vec<u8>,
+    /// KEM output of the ExternalInit proposal, from which the new epoch's
+    /// init secret is derived.
+    pub kem_output: Vec<u8>,
+    /// Leaf the joiner removes: its own previous membership, when the
+    /// external commit resyncs a member that lost its state.
+    pub replaced_index: Option<u32>,
+    /// Member handle of the replaced leaf, to match the joiner against the
+    /// member it was before.
+    pub replaced_handle: Option<Vec<u8>>,
+}
+
+/// Application feature flags negotiated through the group context.
+///
+/// Stored in a custom group context extension (see
+/// `group_features_extension_type()`), so every member converges on the same
+/// set through regular commits.
+pub struct MlsGroupFeatures {
+    /// Application-defined feature bitset. The bit returned by
+    /// `group_feature_compression()` is reserved and enables compression of
+    /// application messages.
+    pub features: u64,
+    /// Signature public keys of members allowed to change the features.
+    /// Empty = any member may change them.
+    pub authorized_signature_keys: Vec<Vec<u8>>,
+}
+
+/// Compression applied to an application message.
+///
+/// Reported for messages of groups with the compression feature enabled.
+pub struct MessageCompressionInfo {
+    /// Whether the plaintext was DEFLATE-compressed. `false` when compressing
+    /// would not have made it smaller.
+    pub compressed: bool,
+    /// Size of the application message.
+    pub original_size: u64,
+    /// Size of the plaintext that was encrypted, including the one-byte
+    /// compression header.
+    pub compressed_size: u64,
+}
+
+/// Size and padding of a received application message, for bandwidth
+/// accounting and tuning `padding_size`.
+pub struct MessagePaddingInfo {
+    /// Size of the serialized MlsMessage as received.
+    pub ciphertext_size: u64,
+    /// Size of the encrypted content: application data, signature, padding
+    /// and AEAD tag.
+    pub encrypted_content_size: u64,
+    /// Zero bytes the sender appended to the content before encrypting it.
+    pub padding_size: u64,
+    /// False for ECDSA ciphersuites, whose DER signatures vary by a few
+    /// bytes: `padding_size` then assumes the longest signature and may be
+    /// that much too low.
+    pub padding_exact: bool,
+}
+
+/// How urgently a security posture finding should be acted on.
+pub enum MlsSecuritySeverity {
+    /// Nothing to report.
+    Ok,
+    /// Worth knowing, no action needed.
+    Info,
+    /// Weakens forward secrecy or post-compromise security; act soon.
+    Warning,
+    /// Security guarantees are substantially degraded; act now.
+    Critical,
+}
+
+/// A check of `security_posture` that found something.
+pub enum MlsSecurityFindingKind {
+    /// We have not sent a commit with an update path for many epochs.
+    StaleOwnUpdate,
+    /// Some members still use the leaf from their key package.
+    MembersNeverUpdated,
+    /// Secrets of past epochs are kept for late messages.
+    PastEpochsRetained,
+    /// Our leaf still uses the keys of a last-resort key package, which may
+    /// be shared with other groups.
+    LastResortKeyPackageInUse,
+    /// The wire format policy allows handshake messages as plaintext.
+    PlaintextAllowed,
+}
+
+pub struct MlsSecurityFinding {
+    pub kind: MlsSecurityFindingKind,
+    pub severity: MlsSecuritySeverity,
+}
+
+/// Security health summary of a group, see `security_posture`.
+pub struct MlsSecurityPosture {
+    pub epoch: u64,
+    /// Epochs since our last commit with an update path. `None` if no such
+    /// commit was recorded for this group.
+    pub epochs_since_own_update: Option<u64>,
+    /// Leaf indices of members (possibly including us) whose leaf has not
+    /// changed since they joined.
+    pub never_updated_members: Vec<u32>,
+    /// Number of past epochs whose secrets are kept (`max_past_epochs`,
+    /// capped at the current epoch).
+    pub retained_past_epochs: u32,
+    pub last_resort_key_package_in_use: bool,
+    pub plaintext_allowed: bool,
+    pub findings: Vec<MlsSecurityFinding>,
+    /// Highest severity among `findings`, `Ok` if there are none.
+    pub severity: MlsSecuritySeverity,
 }
 
 /// Options for the flexible commit builder.
@@ -162,10 +669,55 @@ pub struct FlexibleCommitOptions {
     pub group_context_extensions: Option<Vec<MlsExtension>>,
     /// Additional authenticated data.
     pub aad: Option<Vec<u8>>,
-    /// Whether to create a GroupInfo message (default: true).
+    /// Whether to create a GroupInfo message (default: true). When set, the
+    /// result always carries one, like `ensure_group_info` on the other
+    /// commit calls.
     pub create_group_info: bool,
     /// Whether to include the ratchet tree extension in GroupInfo.
     pub use_ratchet_tree_extension: bool,
+    /// Wire format of the commit. None = the group's outgoing policy; the
+    /// other format requires a mixed policy.
+    pub wire_format: Option<MlsWireFormat>,
+    /// Which pending proposals to commit when consuming the proposal store.
+    /// None = all of them.
+    pub proposal_filter: Option<MlsProposalFilter>,
+    /// Validation of `add_key_packages`. None = OpenMLS's default checks.
+    pub key_package_validation: Option<KeyPackageValidationOptions>,
+}
+
+/// How key packages to be added to a group are validated. Calls that take
+/// these options as `None` apply OpenMLS's default checks only.
+pub struct KeyPackageValidationOptions {
+    /// Accept key packages outside their lifetime, e.g. in tests or when
+    /// the creator's clock runs apart from ours. Signatures and all other
+    /// checks still apply. Refused in strict mode.
+    pub skip_lifetime_validation: bool,
+    /// Refuse last-resort key packages, e.g. to keep them for members who
+    /// have no other key package left.
+    pub reject_last_resort: bool,
+    /// Ciphersuites key packages may use; empty allows any. Key packages
+    /// must match the group's ciphersuite regardless, so this refuses
+    /// additions to groups whose ciphersuite is not listed.
+    pub allowed_ciphersuites: Vec<MlsCiphersuite>,
+}
+
+/// Selects pending proposals for `flexible_commit`. A proposal is committed
+/// only if it passes every criterion; empty lists do not restrict. How the
+/// filter combines with the engine's proposal policy is described at
+/// `MlsEngine::set_proposal_policy`.
+///
+/// Proposals left out are not committed, and like every pending proposal
+/// of the old epoch they are dropped once the commit is merged.
+pub struct MlsProposalFilter {
+    /// Only proposals of these types.
+    pub proposal_types: Vec<MlsProposalType>,
+    /// Only proposals sent by these leaf indices. Proposals from
+    /// non-members never match a non-empty list.
+    pub sender_indices: Vec<u32>,
+    /// Only these proposals, as TLS-serialized proposal refs.
+    pub include_refs: Vec<Vec<u8>>,
+    /// Never these proposals, as TLS-serialized proposal refs.
+    pub exclude_refs: Vec<Vec<u8>>,
 }
 
 // ═══════════════════════════════════════════════════════════════
@@ -207,10 +759,40 @@ pub(crate) fn native_to_ciphersuite(cs: Ciphersuite) -> Result<MlsCiphersuite, S
     }
 }
 
+/// Convert a protocol version number, rejecting versions OpenMLS cannot run.
+pub(crate) fn protocol_version_to_native(version: u16) -> Result<ProtocolVersion, String> {
+    match ProtocolVersion::from(version) {
+        ProtocolVersion::Mls10 => Ok(ProtocolVersion::Mls10),
+        ProtocolVersion::Other(v) => Err(format!("Unsupported MLS protocol version {}", v)),
+    }
+}
+
+pub(crate) fn protocol_version_value(version: ProtocolVersion) -> u16 {
+    match version {
+        ProtocolVersion::Mls10 => 1,
+        ProtocolVersion::Other(v) => v,
+    }
+}
+
 pub(crate) fn wire_format_to_native(wf: &MlsWireFormatPolicy) -> WireFormatPolicy {
     match wf {
         MlsWireFormatPolicy::Plaintext => PURE_PLAINTEXT_WIRE_FORMAT_POLICY,
         MlsWireFormatPolicy::Ciphertext => PURE_CIPHERTEXT_WIRE_FORMAT_POLICY,
+        MlsWireFormatPolicy::MixedPlaintext => MIXED_PLAINTEXT_WIRE_FORMAT_POLICY,
+        MlsWireFormatPolicy::MixedCiphertext => MIXED_CIPHERTEXT_WIRE_FORMAT_POLICY,
+    }
+}
+
+pub(crate) fn native_to_wire_format(wf: WireFormatPolicy) -> MlsWireFormatPolicy {
+    match (wf.outgoing(), wf.incoming()) {
+        (OutgoingWireFormatPolicy::AlwaysPlaintext, IncomingWireFormatPolicy::Mixed) => {
+            MlsWireFormatPolicy::MixedPlaintext
+        }
+        (OutgoingWireFormatPolicy::AlwaysCiphertext, IncomingWireFormatPolicy::Mixed) => {
+            MlsWireFormatPolicy::MixedCiphertext
+        }
+        (OutgoingWireFormatPolicy::AlwaysPlaintext, _) => MlsWireFormatPolicy::Plaintext,
+        (OutgoingWireFormatPolicy::AlwaysCiphertext, _) => MlsWireFormatPolicy::Ciphertext,
     }
 }
 
@@ -220,29 +802,33 @@ pub(crate) fn capabilities_to_native(caps: &MlsCapabilities) -> Result<Capabilit
     } else {
         Some(caps.versions.iter().map(|&v| ProtocolVersion::from(v)).collect())
     };
-    let ciphersuites: Option<Vec<Ciphersuite>> = if caps.ciphersuites.is_empty() {
+    let raw_ciphersuites = caps.raw_ciphersuites();
+    let ciphersuites: Option<Vec<Ciphersuite>> = if raw_ciphersuites.is_empty() {
         None
     } else {
-        let cs: Result<Vec<_>, _> = caps.ciphersuites
+        let cs: Result<Vec<_>, _> = raw_ciphersuites
             .iter()
             .map(|&c| Ciphersuite::try_from(c).map_err(|e| format!("Invalid ciphersuite {}: {}", c, e)))
             .collect();
         Some(cs?)
     };
-    let extensions: Option<Vec<ExtensionType>> = if caps.extensions.is_empty() {
+    let raw_extensions = caps.raw_extensions();
+    let extensions: Option<Vec<ExtensionType>> = if raw_extensions.is_empty() {
         None
     } else {
-        Some(caps.extensions.iter().map(|&e| ExtensionType::from(e)).collect())
+        Some(raw_extensions.iter().map(|&e| ExtensionType::from(e)).collect())
     };
-    let proposals: Option<Vec<ProposalType>> = if caps.proposals.is_empty() {
+    let raw_proposals = caps.raw_proposals()?;
+    let proposals: Option<Vec<ProposalType>> = if raw_proposals.is_empty() {
         None
     } else {
-        Some(caps.proposals.iter().map(|&p| ProposalType::from(p)).collect())
+        Some(raw_proposals.iter().map(|&p| ProposalType::from(p)).collect())
     };
-    let credentials: Option<Vec<CredentialType>> = if caps.credentials.is_empty() {
+    let raw_credentials = caps.raw_credentials();
+    let credentials: Option<Vec<CredentialType>> = if raw_credentials.is_empty() {
         None
     } else {
-        Some(caps.credentials.iter().map(|&c| CredentialType::from(c)).collect())
+        Some(raw_credentials.iter().map(|&c| CredentialType::from(c)).collect())
     };
 
     Ok(Capabilities::new(
@@ -254,12 +840,241 @@ pub(crate) fn capabilities_to_native(caps: &MlsCapabilities) -> Result<Capabilit
     ))
 }
 
+pub(crate) fn capabilities_from_native(caps: &Capabilities) -> MlsCapabilities {
+    MlsCapabilities::from_raw(
+        caps.versions().iter().map(|v| protocol_version_value(*v)).collect(),
+        caps.ciphersuites().iter().map(|c| c.value()).collect(),
+        caps.extensions().iter().map(|e| u16::from(*e)).collect(),
+        caps.proposals().iter().map(|p| u16::from(*p)).collect(),
+        caps.credentials().iter().map(|c| u16::from(*c)).collect(),
+    )
+}
+
+fn extension_type_value(ext: &MlsExtensionType) -> u16 {
+    match ext {
+        MlsExtensionType::ApplicationId => 0x0001,
+        MlsExtensionType::RatchetTree => 0x0002,
+        MlsExtensionType::RequiredCapabilities => 0x0003,
+        MlsExtensionType::ExternalPub => 0x0004,
+        MlsExtensionType::ExternalSenders => 0x0005,
+        MlsExtensionType::LastResort => 0x000a,
+    }
+}
+
+fn extension_type_from_value(value: u16) -> Option<MlsExtensionType> {
+    match value {
+        0x0001 => Some(MlsExtensionType::ApplicationId),
+        0x0002 => Some(MlsExtensionType::RatchetTree),
+        0x0003 => Some(MlsExtensionType::RequiredCapabilities),
+        0x0004 => Some(MlsExtensionType::ExternalPub),
+        0x0005 => Some(MlsExtensionType::ExternalSenders),
+        0x000a => Some(MlsExtensionType::LastResort),
+        _ => None,
+    }
+}
+
+fn proposal_type_value(proposal: &MlsProposalType) -> Result<u16, String> {
+    match proposal {
+        MlsProposalType::Add => Ok(0x0001),
+        MlsProposalType::Update => Ok(0x0002),
+        MlsProposalType::Remove => Ok(0x0003),
+        MlsProposalType::PreSharedKey => Ok(0x0004),
+        MlsProposalType::Reinit => Ok(0x0005),
+        MlsProposalType::ExternalInit => Ok(0x0006),
+        MlsProposalType::GroupContextExtensions => Ok(0x0007),
+        MlsProposalType::Custom => Err("Custom proposal types must be listed in other_proposals".to_string()),
+    }
+}
+
+fn proposal_type_from_value(value: u16) -> Option<MlsProposalType> {
+    match value {
+        0x0001 => Some(MlsProposalType::Add),
+        0x0002 => Some(MlsProposalType::Update),
+        0x0003 => Some(MlsProposalType::Remove),
+        0x0004 => Some(MlsProposalType::PreSharedKey),
+        0x0005 => Some(MlsProposalType::Reinit),
+        0x0006 => Some(MlsProposalType::ExternalInit),
+        0x0007 => Some(MlsProposalType::GroupContextExtensions),
+        _ => None,
+    }
+}
+
+fn credential_type_value(credential: &MlsCredentialType) -> u16 {
+    match credential {
+        MlsCredentialType::Basic => 0x0001,
+        MlsCredentialType::X509 => 0x0002,
+    }
+}
+
+fn credential_type_from_value(value: u16) -> Option<MlsCredentialType> {
+    match value {
+        0x0001 => Some(MlsCredentialType::Basic),
+        0x0002 => Some(MlsCredentialType::X509),
+        _ => None,
+    }
+}
+
 pub(crate) fn extensions_from_mls(exts: &[MlsExtension]) -> Vec<Extension> {
     exts.iter()
         .map(|ext| Extension::Unknown(ext.extension_type, UnknownExtension(ext.data.clone())))
         .collect()
 }
 
+/// Convert any extension (known or unknown) to its type and encoded data.
+pub(crate) fn extension_to_mls(ext: &Extension) -> Result<MlsExtension, String> {
+    use tls_codec::{DeserializeBytes as TlsDeserializeBytes, Serialize as TlsSerialize};
+    // Wire format: extension_type (u16) || opaque extension_data<V>
+    let bytes = ext
+        .tls_serialize_detached()
+        .map_err(|e| format!("Failed to serialize extension: {}", e))?;
+    let (data, _) = tls_codec::VLBytes::tls_deserialize_bytes(&bytes[2..])
+        .map_err(|e| format!("Failed to decode extension data: {}", e))?;
+    Ok(MlsExtension {
+        extension_type: u16::from(ext.extension_type()),
+        data: data.as_slice().to_vec(),
+    })
+}
+
+/// Decode a proposed group context extension set and diff it against the
+/// current one.
+pub(crate) fn group_context_extensions_change(
+    current: &Extensions,
+    proposed: &Extensions,
+) -> Result<MlsGroupContextExtensionsChange, String> {
+    let current = current.iter().map(extension_to_mls).collect::<Result<Vec<_>, _>>()?;
+    let proposed = proposed.iter().map(extension_to_mls).collect::<Result<Vec<_>, _>>()?;
+
+    let mut added_types = Vec::new();
+    let mut changed_types = Vec::new();
+    for ext in &proposed {
+        match current.iter().find(|c| c.extension_type == ext.extension_type) {
+            None => added_types.push(ext.extension_type),
+            Some(c) if c.data != ext.data => changed_types.push(ext.extension_type),
+            Some(_) => {}
+        }
+    }
+    let removed_types = current
+        .iter()
+        .filter(|c| !proposed.iter().any(|p| p.extension_type == c.extension_type))
+        .map(|c| c.extension_type)
+        .collect();
+
+    Ok(MlsGroupContextExtensionsChange { proposed, added_types, removed_types, changed_types })
+}
+
+/// Extension type carrying `MlsGroupFeatures` (private-use range).
+pub(crate) const GROUP_FEATURES_EXTENSION_TYPE: u16 = 0xff00;
+
+/// Encode group features as a group context extension.
+///
+/// Wire format: `features (u64 BE) || VLBytes(signature_key)*`.
+pub(crate) fn group_features_to_extension(features: &MlsGroupFeatures) -> Result<Extension, String> {
+    use tls_codec::Serialize as TlsSerialize;
+    let mut data = features.features.to_be_bytes().to_vec();
+    for key in &features.authorized_signature_keys {
+        tls_codec::VLBytes::new(key.clone())
+            .tls_serialize(&mut data)
+            .map_err(|e| format!("Failed to serialize authorized key: {}", e))?;
+    }
+    Ok(Extension::Unknown(GROUP_FEATURES_EXTENSION_TYPE, UnknownExtension(data)))
+}
+
+/// Decode group features from a set of group context extensions.
+///
+/// Returns `None` if the features extension is not present.
+pub(crate) fn group_features_from_extensions(exts: &Extensions) -> Result<Option<MlsGroupFeatures>, String> {
+    use tls_codec::DeserializeBytes as TlsDeserializeBytes;
+    for ext in exts.iter() {
+        if let Extension::Unknown(ext_type, data) = ext {
+            if *ext_type != GROUP_FEATURES_EXTENSION_TYPE {
+                continue;
+            }
+            if data.0.len() < 8 {
+                return Err("Malformed group features extension".to_string());
+            }
+            let (bits, mut remaining) = data.0.split_at(8);
+            let mut authorized_signature_keys = Vec::new();
+            while !remaining.is_empty() {
+                let (key, rest) = tls_codec::VLBytes::tls_deserialize_bytes(remaining)
+                    .map_err(|e| format!("Malformed group features extension: {}", e))?;
+                authorized_signature_keys.push(key.as_slice().to_vec());
+                remaining = rest;
+            }
+            return Ok(Some(MlsGroupFeatures {
+                features: u64::from_be_bytes(bits.try_into().expect("split_at(8)")),
+                authorized_signature_keys,
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the extension type used for group features.
+///
+/// Members must advertise this type in their leaf capabilities
+/// (`MlsCapabilities.other_extensions`) before features can be set on a group.
+#[flutter_rust_bridge::frb(sync)]
+pub fn group_features_extension_type() -> u16 {
+    GROUP_FEATURES_EXTENSION_TYPE
+}
+
+/// Extension type carrying the group topic (private-use range).
+pub(crate) const GROUP_TOPIC_EXTENSION_TYPE: u16 = 0xff01;
+
+/// Encode a group topic as a group context extension.
+///
+/// Wire format: `VLBytes(topic as UTF-8)`.
+pub(crate) fn group_topic_to_extension(topic: &str) -> Result<Extension, String> {
+    use tls_codec::Serialize as TlsSerialize;
+    let data = tls_codec::VLBytes::new(topic.as_bytes().to_vec())
+        .tls_serialize_detached()
+        .map_err(|e| format!("Failed to serialize group topic: {}", e))?;
+    Ok(Extension::Unknown(GROUP_TOPIC_EXTENSION_TYPE, UnknownExtension(data)))
+}
+
+/// Decode the group topic from a set of group context extensions.
+///
+/// Returns `None` if the topic extension is not present.
+pub(crate) fn group_topic_from_extensions(exts: &Extensions) -> Result<Option<String>, String> {
+    use tls_codec::DeserializeBytes as TlsDeserializeBytes;
+    for ext in exts.iter() {
+        if let Extension::Unknown(GROUP_TOPIC_EXTENSION_TYPE, data) = ext {
+            let (topic, rest) = tls_codec::VLBytes::tls_deserialize_bytes(&data.0)
+                .map_err(|e| format!("Malformed group topic extension: {}", e))?;
+            if !rest.is_empty() {
+                return Err("Malformed group topic extension: trailing bytes".to_string());
+            }
+            return String::from_utf8(topic.as_slice().to_vec())
+                .map(Some)
+                .map_err(|_| "Malformed group topic extension: not UTF-8".to_string());
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the extension type used for the group topic.
+///
+/// Members must advertise this type in their leaf capabilities
+/// (`MlsCapabilities.other_extensions`) before a topic can be set.
+#[flutter_rust_bridge::frb(sync)]
+pub fn group_topic_extension_type() -> u16 {
+    GROUP_TOPIC_EXTENSION_TYPE
+}
+
+/// Group feature bit that enables application message compression.
+pub(crate) const GROUP_FEATURE_COMPRESSION: u64 = 1 << 63;
+
+/// Returns the `MlsGroupFeatures.features` bit that enables compression.
+///
+/// With the bit set, `create_message` DEFLATE-compresses application
+/// messages before encryption and `process_message` decompresses them.
+/// Every member must run a version that understands the bit before it is
+/// set, and applications must not use it for their own features.
+#[flutter_rust_bridge::frb(sync)]
+pub fn group_feature_compression() -> u64 {
+    GROUP_FEATURE_COMPRESSION
+}
+
 /// Returns the list of supported ciphersuites.
 #[flutter_rust_bridge::frb(sync)]
 pub fn supported_ciphersuites() -> Vec<MlsCiphersuite> {
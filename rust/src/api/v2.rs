@@ -0,0 +1,158 @@
+//! Version 2 of the engine API.
+//!
+//! v2 functions report failures as `MlsApiError`, whose `kind` can be
+//! matched on, instead of a bare message string. They are free functions
+//! taking the engine, so a function keeps its name across versions and Dart
+//! code can move to `package:openmls/v2.dart` one call site at a time. The
+//! v1 methods on `MlsEngine` they replace remain as thin shims over the same
+//! implementation; `set_compat_mode` reports or refuses remaining v1 calls.
+//!
+//! The kind is assigned where a failure happens, never derived from the
+//! message, so rewording a message does not change it. Failures that are not
+//! classified yet are reported as `Other`.
+//!
+//! Functions are added here as their v1 counterparts are reworked. Anything
+//! not listed here is still only available as a v1 method.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::redact;
+
+use super::engine::{MlsEngine, ProcessedMessageInspectResult, ProcessedMessageResult};
+
+/// Broad category of a v2 failure.
+pub enum MlsErrorKind {
+    /// An argument could not be parsed (malformed message, key, ...).
+    InvalidInput,
+    /// The group, proposal or entry referred to does not exist.
+    NotFound,
+    /// The input was well-formed but MLS validation rejected it.
+    Rejected,
+    /// Refused by a policy of this engine, e.g. strict mode or the
+    /// credential validator.
+    PolicyViolation,
+    /// The engine's storage failed.
+    Storage,
+    /// The storage did not respond within the operation timeout.
+    Timeout,
+    /// The engine was closed.
+    Closed,
+    Other,
+}
+
+/// Error returned by v2 functions.
+pub struct MlsApiError {
+    pub kind: MlsErrorKind,
+    /// Human-readable description, as v1 functions report it.
+    pub message: String,
+}
+
+impl std::fmt::Display for MlsApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl MlsApiError {
+    pub(crate) fn new(kind: MlsErrorKind, message: impl Into<String>) -> Self {
+        MlsApiError { kind, message: message.into() }
+    }
+}
+
+/// A failure that was not given a kind where it happened.
+impl From<String> for MlsApiError {
+    fn from(message: String) -> Self {
+        MlsApiError::new(MlsErrorKind::Other, message)
+    }
+}
+
+/// For v1 methods, which report only the message.
+impl From<MlsApiError> for String {
+    fn from(error: MlsApiError) -> Self {
+        error.message
+    }
+}
+
+/// How v1 methods that have a v2 replacement behave.
+pub enum MlsCompatMode {
+    /// As before (the default).
+    V1,
+    /// Log a deprecation warning on each call.
+    Warn,
+    /// Fail, to find call sites not yet migrated.
+    V2Only,
+}
+
+static COMPAT_MODE: AtomicU8 = AtomicU8::new(0);
+
+/// Set how v1 methods with a v2 replacement behave, process-wide.
+#[flutter_rust_bridge::frb(sync)]
+pub fn set_compat_mode(mode: MlsCompatMode) {
+    let value = match mode {
+        MlsCompatMode::V1 => 0,
+        MlsCompatMode::Warn => 1,
+        MlsCompatMode::V2Only => 2,
+    };
+    COMPAT_MODE.store(value, Ordering::Relaxed);
+}
+
+#[flutter_rust_bridge::frb(sync)]
+pub fn compat_mode() -> MlsCompatMode {
+    match COMPAT_MODE.load(Ordering::Relaxed) {
+        0 => MlsCompatMode::V1,
+        1 => MlsCompatMode::Warn,
+        _ => MlsCompatMode::V2Only,
+    }
+}
+
+/// Called by v1 methods that have a v2 replacement named `name`.
+pub(crate) fn v1_shim(name: &str) -> Result<(), String> {
+    match compat_mode() {
+        MlsCompatMode::V1 => Ok(()),
+        MlsCompatMode::Warn => {
+            redact::log_warn!("MlsEngine::{} is deprecated; use the v2 function instead", name);
+            Ok(())
+        }
+        MlsCompatMode::V2Only => Err(format!("Compat mode: MlsEngine::{} is disabled, use the v2 function", name)),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// MESSAGE PROCESSING
+// ═══════════════════════════════════════════════════════════
+
+/// v2 of `MlsEngine::process_message`.
+pub async fn process_message(
+    engine: &MlsEngine,
+    group_id_bytes: Vec<u8>,
+    message_bytes: Vec<u8>,
+    sent_at: Option<u64>,
+    sync_cursor: Option<Vec<u8>>,
+) -> Result<ProcessedMessageResult, MlsApiError> {
+    engine.process_and_commit(group_id_bytes, message_bytes, sent_at, sync_cursor)
+        .await
+}
+
+/// v2 of `MlsEngine::process_message_with_inspect`.
+pub async fn process_message_with_inspect(
+    engine: &MlsEngine,
+    group_id_bytes: Vec<u8>,
+    message_bytes: Vec<u8>,
+    sent_at: Option<u64>,
+    sync_cursor: Option<Vec<u8>>,
+) -> Result<ProcessedMessageInspectResult, MlsApiError> {
+    engine.inspect_message(group_id_bytes, message_bytes, sent_at, sync_cursor, false)
+        .await
+}
+
+/// v2 of `MlsEngine::process_message_staged`.
+pub async fn process_message_staged(
+    engine: &MlsEngine,
+    group_id_bytes: Vec<u8>,
+    message_bytes: Vec<u8>,
+    sent_at: Option<u64>,
+    sync_cursor: Option<Vec<u8>>,
+) -> Result<ProcessedMessageInspectResult, MlsApiError> {
+    engine.inspect_message(group_id_bytes, message_bytes, sent_at, sync_cursor, true)
+        .await
+}
@@ -0,0 +1,84 @@
+//! Attachment keys wrapped to the members of one epoch.
+//!
+//! The recipient key pair is derived from the epoch's exporter secret, so
+//! only members of that epoch can unwrap; members added later never learn it:
+//!
+//! ```text
+//! ikm     = MLS-Exporter("attachment key wrap", "", Nh)
+//! (sk, pk) = DeriveKeyPair(ikm)
+//! info    = EncryptContext("AttachmentKey", message_context)
+//! ```
+//!
+//! `EncryptContext` is the labelled HPKE info of RFC 9420 §5.1.3. Wire
+//! format (TLS presentation language, RFC 9420 §2.1):
+//!
+//! ```text
+//! struct {
+//!     uint8 version = 1;
+//!     uint64 epoch;                      // epoch whose exporter was used
+//!     opaque kem_output<V>;
+//!     opaque ciphertext<V>;              // HPKE-sealed attachment key
+//! } WrappedAttachmentKey;
+//! ```
+
+use openmls::prelude::tls_codec::{DeserializeBytes, Serialize, VLBytes};
+
+const WRAPPED_KEY_VERSION: u8 = 1;
+const ATTACHMENT_INFO_LABEL: &[u8] = b"MLS 1.0 AttachmentKey";
+pub(crate) const ATTACHMENT_EXPORTER_LABEL: &str = "attachment key wrap";
+
+pub(crate) struct WrappedAttachmentKey {
+    pub epoch: u64,
+    pub kem_output: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+impl WrappedAttachmentKey {
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        let mut out = vec![WRAPPED_KEY_VERSION];
+        out.extend_from_slice(&self.epoch.to_be_bytes());
+        write_vl(&mut out, &self.kem_output)?;
+        write_vl(&mut out, &self.ciphertext)?;
+        Ok(out)
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<WrappedAttachmentKey, String> {
+        let (version, rest) = bytes.split_first().ok_or_else(|| "Truncated wrapped attachment key".to_string())?;
+        if *version != WRAPPED_KEY_VERSION {
+            return Err(format!("Unsupported wrapped attachment key version {}", version));
+        }
+        if rest.len() < 8 {
+            return Err("Truncated wrapped attachment key".to_string());
+        }
+        let (epoch, rest) = rest.split_at(8);
+        let (kem_output, rest) = read_vl(rest)?;
+        let (ciphertext, rest) = read_vl(rest)?;
+        if !rest.is_empty() {
+            return Err("Trailing bytes after wrapped attachment key".to_string());
+        }
+        Ok(WrappedAttachmentKey {
+            epoch: u64::from_be_bytes(epoch.try_into().expect("split_at(8)")),
+            kem_output: kem_output.as_slice().to_vec(),
+            ciphertext: ciphertext.as_slice().to_vec(),
+        })
+    }
+}
+
+/// HPKE info binding the wrapped key to `message_context`.
+pub(crate) fn encrypt_context(message_context: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    write_vl(&mut out, ATTACHMENT_INFO_LABEL)?;
+    write_vl(&mut out, message_context)?;
+    Ok(out)
+}
+
+fn write_vl(out: &mut Vec<u8>, bytes: &[u8]) -> Result<(), String> {
+    VLBytes::new(bytes.to_vec())
+        .tls_serialize(out)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to encode wrapped attachment key: {}", e))
+}
+
+fn read_vl(input: &[u8]) -> Result<(VLBytes, &[u8]), String> {
+    VLBytes::tls_deserialize_bytes(input).map_err(|e| format!("Malformed wrapped attachment key: {}", e))
+}
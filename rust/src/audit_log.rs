@@ -0,0 +1,151 @@
+//! Append-only, hash-chained log of the commits merged into a group.
+//!
+//! Each entry's `chain_hash` covers the previous entry's chain hash and the
+//! entry itself, so dropping, reordering or editing an entry breaks every
+//! later hash:
+//!
+//! ```text
+//! chain_hash = Hash(prev_chain_hash || tbs)
+//!
+//! struct {
+//!     uint64 epoch;
+//!     opaque commit_hash<V>;
+//!     optional<uint32> sender;
+//!     uint8 own;                         // 1 if we created the commit
+//!     uint64 merged_at;                  // Unix seconds
+//!     uint32 add, remove, update, psk, group_context_extensions, other;
+//! } tbs;
+//! ```
+//!
+//! `Hash` is the group ciphersuite's hash function; the first entry chains
+//! from an empty `prev_chain_hash`.
+//!
+//! Exports are JSON, signed with the exporting member's signature key:
+//!
+//! ```text
+//! {
+//!   "version": 1,
+//!   "group_id": "<hex>",
+//!   "prev_chain_hash": "<hex>",        // chain hash before the first entry
+//!   "entries": [{
+//!     "epoch": 5,                      // epoch the commit moved the group to
+//!     "commit_hash": "<hex>",          // confirmed transcript hash after it
+//!     "sender": 0,                     // leaf index, null for external commits
+//!     "own": true,
+//!     "merged_at": 1760000000,
+//!     "proposals": { "add": 1, "remove": 0, "update": 0, "psk": 0,
+//!                    "group_context_extensions": 0, "other": 0 },
+//!     "chain_hash": "<hex>"
+//!   }]
+//! }
+//! ```
+//!
+//! The signature is `SignWithLabel(., "AuditLog", json)` over the exact bytes.
+
+use openmls::prelude::tls_codec::{Serialize, VLBytes};
+
+pub(crate) const AUDIT_LOG_VERSION: u8 = 1;
+const AUDIT_LOG_SIGN_LABEL: &[u8] = b"MLS 1.0 AuditLog";
+
+/// Proposal counts of one commit.
+#[derive(Clone, Default, serde::Serialize, serde::Deserialize)]
+pub(crate) struct ProposalSummary {
+    pub add: u32,
+    pub remove: u32,
+    pub update: u32,
+    pub psk: u32,
+    pub group_context_extensions: u32,
+    pub other: u32,
+}
+
+/// A stored log entry.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub(crate) struct AuditEntry {
+    pub epoch: u64,
+    pub commit_hash: Vec<u8>,
+    pub sender: Option<u32>,
+    pub own: bool,
+    pub merged_at: u64,
+    pub proposals: ProposalSummary,
+    pub chain_hash: Vec<u8>,
+}
+
+impl AuditEntry {
+    /// The bytes chained into `chain_hash`, after the previous chain hash.
+    pub fn tbs(&self) -> Result<Vec<u8>, String> {
+        let mut out = self.epoch.to_be_bytes().to_vec();
+        VLBytes::new(self.commit_hash.clone())
+            .tls_serialize(&mut out)
+            .map_err(|e| format!("Failed to encode audit entry: {}", e))?;
+        match self.sender {
+            Some(sender) => {
+                out.push(1);
+                out.extend_from_slice(&sender.to_be_bytes());
+            }
+            None => out.push(0),
+        }
+        out.push(self.own as u8);
+        out.extend_from_slice(&self.merged_at.to_be_bytes());
+        let p = &self.proposals;
+        for count in [p.add, p.remove, p.update, p.psk, p.group_context_extensions, p.other] {
+            out.extend_from_slice(&count.to_be_bytes());
+        }
+        Ok(out)
+    }
+}
+
+#[derive(serde::Serialize)]
+struct ExportedEntry<'a> {
+    epoch: u64,
+    commit_hash: String,
+    sender: Option<u32>,
+    own: bool,
+    merged_at: u64,
+    proposals: &'a ProposalSummary,
+    chain_hash: String,
+}
+
+#[derive(serde::Serialize)]
+struct Export<'a> {
+    version: u8,
+    group_id: String,
+    prev_chain_hash: String,
+    entries: Vec<ExportedEntry<'a>>,
+}
+
+/// JSON export of `entries`, which follow the entry with `prev_chain_hash`.
+pub(crate) fn export_json(group_id: &[u8], prev_chain_hash: &[u8], entries: &[AuditEntry]) -> Result<String, String> {
+    let export = Export {
+        version: AUDIT_LOG_VERSION,
+        group_id: to_hex(group_id),
+        prev_chain_hash: to_hex(prev_chain_hash),
+        entries: entries
+            .iter()
+            .map(|entry| ExportedEntry {
+                epoch: entry.epoch,
+                commit_hash: to_hex(&entry.commit_hash),
+                sender: entry.sender,
+                own: entry.own,
+                merged_at: entry.merged_at,
+                proposals: &entry.proposals,
+                chain_hash: to_hex(&entry.chain_hash),
+            })
+            .collect(),
+    };
+    serde_json::to_string(&export).map_err(|e| format!("Failed to serialize audit log: {}", e))
+}
+
+/// SignContent for an export (RFC 9420 §5.1.2), labelled for audit logs.
+pub(crate) fn sign_content(json: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    for bytes in [AUDIT_LOG_SIGN_LABEL, json] {
+        VLBytes::new(bytes.to_vec())
+            .tls_serialize(&mut out)
+            .map_err(|e| format!("Failed to encode audit log: {}", e))?;
+    }
+    Ok(out)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
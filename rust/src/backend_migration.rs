@@ -0,0 +1,178 @@
+//! Dual-write migration of an engine's state to another storage backend.
+//!
+//! While a migration runs (see `MlsEngine::begin_backend_migration`), the
+//! engine keeps reading from its current store, the source, and applies
+//! every write to the source and then to the target. The source stays
+//! authoritative: a write the target fails to apply is counted, not
+//! returned, and shows up as a mismatch when the two are compared. The
+//! migration ends by switching the engine to the target once both hold the
+//! same entries, or by dropping the target.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::encrypted_db::{DbError, StorageUpdates};
+use crate::sandbox::EngineStore;
+
+pub(crate) struct MigratingStore {
+    pub source: EngineStore,
+    pub target: EngineStore,
+    /// Normalized path under which the target database is registered as
+    /// open, `None` for in-memory databases and custom backends.
+    pub target_path: Option<String>,
+    /// Unix seconds.
+    pub started_at: u64,
+    /// Unix seconds before which the migration is not finalized.
+    pub dual_write_until: u64,
+    /// Serializes writes with the initial copy and with verification, so
+    /// both stores always see writes in the same order.
+    write_lock: futures::lock::Mutex<()>,
+    mirrored_writes: AtomicU64,
+    target_failures: AtomicU64,
+    last_target_error: parking_lot::Mutex<Option<String>>,
+}
+
+impl MigratingStore {
+    /// Copy every entry of `source` into `target`, which must be empty.
+    ///
+    /// Refused while `source` holds quarantined groups: stores other than
+    /// the engine database cannot hold them, so they would be lost when the
+    /// migration is finalized.
+    pub async fn start(
+        source: EngineStore,
+        target: EngineStore,
+        target_path: Option<String>,
+        started_at: u64,
+        dual_write_until: u64,
+    ) -> Result<MigratingStore, String> {
+        if !target.load_all().await?.is_empty() {
+            return Err("Migration target storage is not empty".to_string());
+        }
+        if let EngineStore::Db(db) = &source {
+            let quarantined = db.quarantined_groups().await?;
+            if !quarantined.is_empty() {
+                return Err(format!(
+                    "Cannot migrate while {} groups are quarantined; export and discard them first",
+                    quarantined.len()
+                ));
+            }
+        }
+        let store = MigratingStore {
+            source,
+            target,
+            target_path,
+            started_at,
+            dual_write_until,
+            write_lock: futures::lock::Mutex::new(()),
+            mirrored_writes: AtomicU64::new(0),
+            target_failures: AtomicU64::new(0),
+            last_target_error: parking_lot::Mutex::new(None),
+        };
+        let guard = store.write_lock.lock().await;
+        let batches = by_group(store.source.load_all().await?);
+        store.target.save_updates_batch(batches).await?;
+        drop(guard);
+        Ok(store)
+    }
+
+    /// Make the target match the source again: copy entries it lacks or
+    /// holds with another value, and delete those the source no longer has.
+    ///
+    /// Operations that took the engine's store before the migration began
+    /// write to the source only; without this, their writes would remain
+    /// mismatches forever.
+    pub async fn resync(&self) -> Result<(), String> {
+        let _guard = self.write_lock.lock().await;
+        let mut target: BTreeMap<Vec<u8>, Vec<u8>> =
+            self.target.load_all().await?.into_iter().map(|(_, key, value)| (key, value)).collect();
+        let mut changed = Vec::new();
+        for (group_id, key, value) in self.source.load_all().await? {
+            if target.remove(&key).as_ref() != Some(&value) {
+                changed.push((group_id, key, value));
+            }
+        }
+        let mut batches = by_group(changed);
+        if !target.is_empty() {
+            let stale = StorageUpdates { upserts: Vec::new(), deletes: target.into_keys().collect() };
+            batches.push((stale, None));
+        }
+        if batches.is_empty() {
+            return Ok(());
+        }
+        Ok(self.target.save_updates_batch(batches).await?)
+    }
+
+    pub async fn save_updates_batch(&self, batches: Vec<(StorageUpdates, Option<Vec<u8>>)>) -> Result<(), DbError> {
+        let mirror = batches
+            .iter()
+            .map(|(updates, group_id)| {
+                let updates = StorageUpdates { upserts: updates.upserts.clone(), deletes: updates.deletes.clone() };
+                (updates, group_id.clone())
+            })
+            .collect();
+        let _guard = self.write_lock.lock().await;
+        self.source.save_updates_batch(batches).await?;
+        let mirrored = self.target.save_updates_batch(mirror).await.map_err(String::from);
+        self.record_mirror(mirrored);
+        Ok(())
+    }
+
+    pub async fn delete_group(&self, group_id: &[u8]) -> Result<(), String> {
+        let _guard = self.write_lock.lock().await;
+        self.source.delete_group(group_id).await?;
+        let mirrored = self.target.delete_group(group_id).await;
+        self.record_mirror(mirrored);
+        Ok(())
+    }
+
+    fn record_mirror(&self, result: Result<(), String>) {
+        self.mirrored_writes.fetch_add(1, Ordering::Relaxed);
+        if let Err(e) = result {
+            self.target_failures.fetch_add(1, Ordering::Relaxed);
+            *self.last_target_error.lock() = Some(e);
+        }
+    }
+
+    /// Number of keys whose value differs between source and target,
+    /// including keys only one of them holds.
+    pub async fn mismatched_entries(&self) -> Result<u64, String> {
+        let _guard = self.write_lock.lock().await;
+        // Compared by key only: web stores keep no group ids.
+        let source: BTreeMap<Vec<u8>, Vec<u8>> =
+            self.source.load_all().await?.into_iter().map(|(_, key, value)| (key, value)).collect();
+        let mut target: BTreeMap<Vec<u8>, Vec<u8>> =
+            self.target.load_all().await?.into_iter().map(|(_, key, value)| (key, value)).collect();
+        let mut mismatched = 0;
+        for (key, value) in &source {
+            if target.remove(key).as_ref() != Some(value) {
+                mismatched += 1;
+            }
+        }
+        Ok(mismatched + target.len() as u64)
+    }
+
+    pub fn mirrored_writes(&self) -> u64 {
+        self.mirrored_writes.load(Ordering::Relaxed)
+    }
+
+    pub fn target_failures(&self) -> u64 {
+        self.target_failures.load(Ordering::Relaxed)
+    }
+
+    pub fn last_target_error(&self) -> Option<String> {
+        self.last_target_error.lock().clone()
+    }
+}
+
+/// Group `(group_id, key, value)` entries into one upsert batch per group.
+fn by_group(entries: Vec<(Option<Vec<u8>>, Vec<u8>, Vec<u8>)>) -> Vec<(StorageUpdates, Option<Vec<u8>>)> {
+    let mut batches: BTreeMap<Option<Vec<u8>>, StorageUpdates> = BTreeMap::new();
+    for (group_id, key, value) in entries {
+        batches
+            .entry(group_id)
+            .or_insert_with(|| StorageUpdates { upserts: Vec::new(), deletes: Vec::new() })
+            .upserts
+            .push((key, value));
+    }
+    batches.into_iter().map(|(group_id, updates)| (updates, group_id)).collect()
+}
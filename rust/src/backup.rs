@@ -0,0 +1,44 @@
+//! Engine backup blobs: every storage entry, written as a stream of records
+//! so large databases can be exported in chunks.
+//!
+//! Wire format (TLS presentation language, RFC 9420 §2.1):
+//!
+//! ```text
+//! struct {
+//!     optional<opaque group_id<V>>;      // absent for global entries
+//!     opaque key<V>;
+//!     opaque value<V>;
+//! } BackupEntry;
+//!
+//! struct {
+//!     uint8 version = 1;
+//!     BackupEntry entries[];             // until the end of the blob
+//! } Backup;
+//! ```
+//!
+//! Entries are the decrypted storage rows. On web the storage carries no
+//! group ids, so `group_id` is always absent there.
+
+use openmls::prelude::tls_codec::{Serialize, VLBytes};
+
+pub(crate) const BACKUP_VERSION: u8 = 1;
+
+/// Append one `BackupEntry` to `out`.
+pub(crate) fn write_entry(out: &mut Vec<u8>, group_id: Option<&[u8]>, key: &[u8], value: &[u8]) -> Result<(), String> {
+    match group_id {
+        Some(group_id) => {
+            out.push(1);
+            write_vl(out, group_id)?;
+        }
+        None => out.push(0),
+    }
+    write_vl(out, key)?;
+    write_vl(out, value)
+}
+
+fn write_vl(out: &mut Vec<u8>, bytes: &[u8]) -> Result<(), String> {
+    VLBytes::new(bytes.to_vec())
+        .tls_serialize(out)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to encode backup entry: {}", e))
+}
@@ -0,0 +1,50 @@
+//! Compression of application message plaintext.
+//!
+//! Used in groups with the compression feature bit set (see
+//! `group_feature_compression()`). Every application message plaintext in
+//! such a group is framed as:
+//!
+//! ```text
+//! struct {
+//!     uint8 algorithm;                   // 0 = none, 1 = DEFLATE (RFC 1951)
+//!     opaque body[];                     // until the end of the plaintext
+//! } CompressedPlaintext;
+//! ```
+//!
+//! Senders fall back to `none` when DEFLATE would not make the body smaller.
+//! zstd is not offered: it needs a C library, which the web build cannot use.
+
+/// Upper bound on a decompressed body, so a small message cannot expand
+/// into an arbitrary amount of memory.
+const MAX_DECOMPRESSED_LEN: usize = 16 * 1024 * 1024;
+
+const ALGORITHM_NONE: u8 = 0;
+const ALGORITHM_DEFLATE: u8 = 1;
+const DEFLATE_LEVEL: u8 = 6;
+
+/// Frame `message`, compressed if that makes it smaller.
+/// Returns the framed plaintext and whether DEFLATE was used.
+pub(crate) fn compress(message: &[u8]) -> (Vec<u8>, bool) {
+    let deflated = miniz_oxide::deflate::compress_to_vec(message, DEFLATE_LEVEL);
+    let (algorithm, body) =
+        if deflated.len() < message.len() { (ALGORITHM_DEFLATE, deflated.as_slice()) } else { (ALGORITHM_NONE, message) };
+    let mut out = Vec::with_capacity(body.len() + 1);
+    out.push(algorithm);
+    out.extend_from_slice(body);
+    (out, algorithm == ALGORITHM_DEFLATE)
+}
+
+/// Unframe a plaintext produced by `compress`.
+/// Returns the original message and whether DEFLATE was used.
+pub(crate) fn decompress(plaintext: &[u8]) -> Result<(Vec<u8>, bool), String> {
+    match plaintext.split_first() {
+        Some((&ALGORITHM_NONE, body)) => Ok((body.to_vec(), false)),
+        Some((&ALGORITHM_DEFLATE, body)) => {
+            let message = miniz_oxide::inflate::decompress_to_vec_with_limit(body, MAX_DECOMPRESSED_LEN)
+                .map_err(|e| format!("Failed to decompress message: {}", e))?;
+            Ok((message, true))
+        }
+        Some((algorithm, _)) => Err(format!("Unsupported compression algorithm {}", algorithm)),
+        None => Err("Missing compression header".to_string()),
+    }
+}
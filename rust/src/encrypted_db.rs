@@ -4,15 +4,61 @@
 //! WASM: IndexedDB via `idb` crate + AES-256-GCM per-value encryption
 //!       (via `crypto.subtle` — non-extractable CryptoKey).
 //!
+//! WASM values are stored in a versioned envelope, with the row's key as
+//! AES-GCM additional data so a value cannot be swapped onto another key:
+//! ```text
+//! [version: u8 = 1][nonce_len: u8][nonce][ciphertext + 16-byte tag]
+//! ```
+//! Databases before schema v4 stored `[12-byte IV][ciphertext + tag]`
+//! without additional data; the v3 → v4 migration re-encrypts them.
+//!
 //! Schema:
 //! ```sql
 //! CREATE TABLE mls_storage (key BLOB PRIMARY KEY, value BLOB NOT NULL, group_id BLOB);
-//! CREATE INDEX idx_group_id ON mls_storage(group_id);
+//! CREATE INDEX idx_group_key ON mls_storage(group_id, key);
 //! CREATE TABLE db_meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+//! CREATE TABLE mls_quarantine (group_id BLOB NOT NULL, key BLOB NOT NULL, value BLOB NOT NULL,
+//!                              PRIMARY KEY (group_id, key));
+//! CREATE TABLE quarantined_groups (group_id BLOB PRIMARY KEY, error TEXT NOT NULL,
+//!                                  quarantined_at INTEGER NOT NULL);
 //! ```
 
 use zeroize::Zeroize;
 
+use crate::api::v2::{MlsApiError, MlsErrorKind};
+
+/// Failure of an `EncryptedDb` load or save.
+#[derive(Debug)]
+pub enum DbError {
+    /// The operation timeout ran out while waiting for the connection or
+    /// for a lock another connection holds.
+    Timeout(String),
+    Failed(String),
+}
+
+impl From<String> for DbError {
+    fn from(message: String) -> Self {
+        DbError::Failed(message)
+    }
+}
+
+impl From<DbError> for String {
+    fn from(e: DbError) -> Self {
+        match e {
+            DbError::Timeout(message) | DbError::Failed(message) => message,
+        }
+    }
+}
+
+impl From<DbError> for MlsApiError {
+    fn from(e: DbError) -> Self {
+        match e {
+            DbError::Timeout(message) => MlsApiError::new(MlsErrorKind::Timeout, message),
+            DbError::Failed(message) => MlsApiError::new(MlsErrorKind::Storage, message),
+        }
+    }
+}
+
 /// Current database schema version.
 ///
 /// **When to bump:** Increment this when the storage schema or data format changes:
@@ -26,7 +72,7 @@ use zeroize::Zeroize;
 ///
 /// **Adding a migration:** Use the `/add-db-migration` Claude skill for a guided walkthrough,
 /// or follow the template in `run_migrations()` comments.
-pub(crate) const LATEST_SCHEMA_VERSION: u32 = 1;
+pub(crate) const LATEST_SCHEMA_VERSION: u32 = 4;
 
 /// Key in the native `db_meta` table that stores the schema version.
 #[cfg(not(target_arch = "wasm32"))]
@@ -39,7 +85,19 @@ const WASM_META_KEY: &[u8] = b"__openmls_schema_version__";
 
 /// IDB structural version — bump only when adding/removing object stores.
 #[cfg(target_arch = "wasm32")]
-const IDB_STRUCTURAL_VERSION: u32 = 1;
+const IDB_STRUCTURAL_VERSION: u32 = 2;
+
+/// IDB object store holding one encrypted `QuarantinedGroup` per group id.
+#[cfg(target_arch = "wasm32")]
+const IDB_QUARANTINE_STORE: &str = "mls_quarantine";
+
+/// Version byte of the WASM value envelope.
+#[cfg(target_arch = "wasm32")]
+const WASM_ENVELOPE_VERSION: u8 = 1;
+
+/// AES-GCM nonce length used for new envelopes.
+#[cfg(target_arch = "wasm32")]
+const WASM_NONCE_LEN: usize = 12;
 
 /// Labels for globally-scoped keys (not tied to a specific group).
 const GLOBAL_LABELS: &[&[u8]] = &[
@@ -47,6 +105,7 @@ const GLOBAL_LABELS: &[&[u8]] = &[
     b"Psk",
     b"EncryptionKeyPair",
     b"SignatureKeyPair",
+    b"AppGlobal",
 ];
 
 /// Check if a storage key belongs to the global scope (not group-specific).
@@ -54,12 +113,51 @@ pub fn is_global_key(key: &[u8]) -> bool {
     GLOBAL_LABELS.iter().any(|label| key.starts_with(label))
 }
 
+/// Exclusive upper bound for keys starting with `label`.
+///
+/// Labels are ASCII, so incrementing the last byte never overflows.
+fn label_upper_bound(label: &[u8]) -> Vec<u8> {
+    let mut upper = label.to_vec();
+    if let Some(last) = upper.last_mut() {
+        *last += 1;
+    }
+    upper
+}
+
 /// Updates to persist after a snapshot operation.
 pub struct StorageUpdates {
     pub upserts: Vec<(Vec<u8>, Vec<u8>)>,
     pub deletes: Vec<Vec<u8>>,
 }
 
+/// A group's stored state set aside by `quarantine_group`.
+///
+/// Serialized as JSON for `export_quarantined_group`. `rows` holds the
+/// decrypted storage entries, including the group's secrets.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct QuarantinedGroup {
+    pub group_id: Vec<u8>,
+    pub error: String,
+    /// Unix seconds.
+    pub quarantined_at: u64,
+    pub rows: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// Backend details reported by `EncryptedDb::info`.
+pub struct DbInfo {
+    /// Database file path (native, `":memory:"` for in-memory databases) or
+    /// IndexedDB database name (web).
+    pub path: String,
+    /// Schema version stored in the database.
+    pub schema_version: u32,
+    /// SQLite `page_size` in bytes; `None` on web.
+    pub page_size: Option<u32>,
+    /// SQLite `cache_size` (pages if positive, KiB if negative); `None` on web.
+    pub cache_size: Option<i64>,
+    /// SQLite `journal_mode`, e.g. `"delete"` or `"wal"`; `None` on web.
+    pub journal_mode: Option<String>,
+}
+
 /// Wrapper around `web_sys::CryptoKey` that is `Send + Sync`.
 ///
 /// WASM is single-threaded, so this is safe. FRB requires opaque types to be
@@ -74,7 +172,10 @@ unsafe impl Sync for WasmCryptoKey {}
 
 pub struct EncryptedDb {
     #[cfg(not(target_arch = "wasm32"))]
-    conn: std::sync::Mutex<rusqlite::Connection>,
+    conn: parking_lot::Mutex<rusqlite::Connection>,
+    /// Operation timeout in milliseconds; 0 = wait indefinitely.
+    #[cfg(not(target_arch = "wasm32"))]
+    timeout_ms: std::sync::atomic::AtomicU64,
     #[cfg(target_arch = "wasm32")]
     db_name: String,
     #[cfg(target_arch = "wasm32")]
@@ -114,14 +215,15 @@ impl EncryptedDb {
             .map_err(|e| format!("Encryption key verification failed (wrong key?): {e}"))?;
 
         let db = Self {
-            conn: std::sync::Mutex::new(conn),
+            conn: parking_lot::Mutex::new(conn),
+            timeout_ms: std::sync::atomic::AtomicU64::new(0),
         };
         db.run_migrations()?;
         Ok(db)
     }
 
     fn run_migrations(&self) -> Result<(), String> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.conn.lock();
 
         // Ensure the metadata table exists (needed to read version).
         conn.execute_batch(
@@ -154,9 +256,18 @@ impl EncryptedDb {
         if version < 1 {
             Self::migrate_native_v0_to_v1(&conn)?;
         }
+        if version < 2 {
+            Self::migrate_native_v1_to_v2(&conn)?;
+        }
+        if version < 3 {
+            Self::migrate_native_v2_to_v3(&conn)?;
+        }
+        if version < 4 {
+            Self::migrate_native_v3_to_v4(&conn)?;
+        }
 
         // Future migrations:
-        // if version < 2 { Self::migrate_native_v1_to_v2(&conn)?; }
+        // if version < 5 { Self::migrate_native_v4_to_v5(&conn)?; }
 
         Ok(())
     }
@@ -185,42 +296,212 @@ impl EncryptedDb {
         Ok(())
     }
 
+    /// v1 → v2: Replace the `group_id` index with a `(group_id, key)` index,
+    /// so label-prefix reads within a group are index range scans.
+    fn migrate_native_v1_to_v2(conn: &rusqlite::Connection) -> Result<(), String> {
+        let tx = conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Migration v1→v2: failed to begin transaction: {e}"))?;
+        tx.execute_batch(
+            "CREATE INDEX IF NOT EXISTS idx_group_key ON mls_storage(group_id, key);
+            DROP INDEX IF EXISTS idx_group_id;",
+        )
+        .map_err(|e| format!("Migration v1→v2 failed: {e}"))?;
+        tx.execute(
+            &format!("INSERT OR REPLACE INTO db_meta (key, value) VALUES ('{META_SCHEMA_VERSION}', '2')"),
+            [],
+        )
+        .map_err(|e| format!("Migration v1→v2: failed to write version: {e}"))?;
+        tx.commit()
+            .map_err(|e| format!("Migration v1→v2: commit failed: {e}"))?;
+        Ok(())
+    }
+
+    /// v2 → v3: Add the quarantine tables used by `quarantine_group`.
+    fn migrate_native_v2_to_v3(conn: &rusqlite::Connection) -> Result<(), String> {
+        let tx = conn
+            .unchecked_transaction()
+            .map_err(|e| format!("Migration v2→v3: failed to begin transaction: {e}"))?;
+        tx.execute_batch(
+            "CREATE TABLE IF NOT EXISTS mls_quarantine (
+                group_id BLOB NOT NULL,
+                key BLOB NOT NULL,
+                value BLOB NOT NULL,
+                PRIMARY KEY (group_id, key)
+            );
+            CREATE TABLE IF NOT EXISTS quarantined_groups (
+                group_id BLOB PRIMARY KEY,
+                error TEXT NOT NULL,
+                quarantined_at INTEGER NOT NULL
+            );",
+        )
+        .map_err(|e| format!("Migration v2→v3 failed: {e}"))?;
+        tx.execute(
+            &format!("INSERT OR REPLACE INTO db_meta (key, value) VALUES ('{META_SCHEMA_VERSION}', '3')"),
+            [],
+        )
+        .map_err(|e| format!("Migration v2→v3: failed to write version: {e}"))?;
+        tx.commit()
+            .map_err(|e| format!("Migration v2→v3: commit failed: {e}"))?;
+        Ok(())
+    }
+
+    /// v3 → v4: WASM-only value envelope. SQLCipher encrypts whole pages,
+    /// so native data is unchanged.
+    fn migrate_native_v3_to_v4(conn: &rusqlite::Connection) -> Result<(), String> {
+        conn.execute(
+            &format!("INSERT OR REPLACE INTO db_meta (key, value) VALUES ('{META_SCHEMA_VERSION}', '4')"),
+            [],
+        )
+        .map_err(|e| format!("Migration v3→v4: failed to write version: {e}"))?;
+        Ok(())
+    }
+
+    /// Bound how long an operation waits for the connection and for SQLite
+    /// locks held by other connections; `None` waits indefinitely for the
+    /// connection and uses rusqlite's default 5 s for locks.
+    ///
+    /// Best-effort: only waiting is bounded. A statement that already holds
+    /// the connection runs to completion. Loads and saves that run out of
+    /// time fail with `DbError::Timeout`; writes take the write lock before
+    /// changing anything, so a timed-out write leaves nothing behind.
+    pub fn set_operation_timeout(&self, timeout: Option<std::time::Duration>) -> Result<(), String> {
+        let ms = timeout.map_or(0, |t| t.as_millis().max(1) as u64);
+        self.timeout_ms.store(ms, std::sync::atomic::Ordering::Relaxed);
+        self.with_conn(|conn| {
+            conn.busy_timeout(timeout.unwrap_or(DEFAULT_BUSY_TIMEOUT))
+                .map_err(|e| format!("Failed to set busy timeout: {e}"))
+        })
+    }
+
+    /// Run `op` on the connection, waiting for it no longer than the
+    /// operation timeout.
+    fn with_conn<T, E: From<DbError>>(
+        &self,
+        op: impl FnOnce(&mut rusqlite::Connection) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let mut conn = match self.timeout_ms.load(std::sync::atomic::Ordering::Relaxed) {
+            0 => self.conn.lock(),
+            ms => {
+                let timeout = std::time::Duration::from_millis(ms);
+                self.conn.try_lock_for(timeout).ok_or_else(|| DbError::Timeout(timeout_message(timeout)))?
+            }
+        };
+        op(&mut conn)
+    }
+
     /// Load all entries with `group_id IS NULL` (global entries).
-    pub async fn load_global(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>, String> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn
-            .prepare("SELECT key, value FROM mls_storage WHERE group_id IS NULL")
-            .map_err(|e| format!("Failed to prepare load_global: {e}"))?;
-        let rows = stmt
-            .query_map([], |row| {
-                Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?))
-            })
-            .map_err(|e| format!("Failed to query load_global: {e}"))?;
-        let mut result = Vec::new();
-        for row in rows {
-            result.push(row.map_err(|e| format!("Row error: {e}"))?);
-        }
-        Ok(result)
+    pub async fn load_global(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DbError> {
+        self.with_conn(|conn| {
+            let mut stmt = conn
+                .prepare_cached("SELECT key, value FROM mls_storage WHERE group_id IS NULL")
+                .map_err(|e| sqlite_error("Failed to prepare load_global", e))?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?))
+                })
+                .map_err(|e| sqlite_error("Failed to query load_global", e))?;
+            let mut result = Vec::new();
+            for row in rows {
+                result.push(row.map_err(|e| sqlite_error("Row error", e))?);
+            }
+            Ok(result)
+        })
     }
 
     /// Load all entries for a group (group-specific + global).
-    pub async fn load_for_group(&self, group_id: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, String> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn
-            .prepare(
-                "SELECT key, value FROM mls_storage WHERE group_id = ?1 OR group_id IS NULL",
-            )
-            .map_err(|e| format!("Failed to prepare load_for_group: {e}"))?;
-        let rows = stmt
-            .query_map(rusqlite::params![group_id], |row| {
-                Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?))
-            })
-            .map_err(|e| format!("Failed to query load_for_group: {e}"))?;
-        let mut result = Vec::new();
-        for row in rows {
-            result.push(row.map_err(|e| format!("Row error: {e}"))?);
-        }
-        Ok(result)
+    pub async fn load_for_group(&self, group_id: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DbError> {
+        self.with_conn(|conn| {
+            let mut stmt = conn
+                .prepare_cached(
+                    "SELECT key, value FROM mls_storage WHERE group_id = ?1 OR group_id IS NULL",
+                )
+                .map_err(|e| sqlite_error("Failed to prepare load_for_group", e))?;
+            let rows = stmt
+                .query_map(rusqlite::params![group_id], |row| {
+                    Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?))
+                })
+                .map_err(|e| sqlite_error("Failed to query load_for_group", e))?;
+            let mut result = Vec::new();
+            for row in rows {
+                result.push(row.map_err(|e| sqlite_error("Row error", e))?);
+            }
+            Ok(result)
+        })
+    }
+
+    /// Load every entry as `(group_id, key, value)`, global entries first.
+    pub async fn load_all(&self) -> Result<Vec<(Option<Vec<u8>>, Vec<u8>, Vec<u8>)>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn
+                .prepare_cached("SELECT group_id, key, value FROM mls_storage ORDER BY group_id, key")
+                .map_err(|e| format!("Failed to prepare load_all: {e}"))?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, Option<Vec<u8>>>(0)?, row.get::<_, Vec<u8>>(1)?, row.get::<_, Vec<u8>>(2)?))
+                })
+                .map_err(|e| format!("Failed to query load_all: {e}"))?;
+            let mut result = Vec::new();
+            for row in rows {
+                result.push(row.map_err(|e| format!("Row error: {e}"))?);
+            }
+            Ok(result)
+        })
+    }
+
+    /// Load up to `limit` entries as `(group_id, key, value)` in key order,
+    /// starting after the key `after` (from the first key for `None`).
+    pub async fn load_page(
+        &self,
+        after: Option<&[u8]>,
+        limit: u32,
+    ) -> Result<Vec<(Option<Vec<u8>>, Vec<u8>, Vec<u8>)>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn
+                .prepare_cached(
+                    "SELECT group_id, key, value FROM mls_storage WHERE ?1 IS NULL OR key > ?1 ORDER BY key LIMIT ?2",
+                )
+                .map_err(|e| format!("Failed to prepare load_page: {e}"))?;
+            let rows = stmt
+                .query_map(rusqlite::params![after, limit], |row| {
+                    Ok((row.get::<_, Option<Vec<u8>>>(0)?, row.get::<_, Vec<u8>>(1)?, row.get::<_, Vec<u8>>(2)?))
+                })
+                .map_err(|e| format!("Failed to query load_page: {e}"))?;
+            let mut result = Vec::new();
+            for row in rows {
+                result.push(row.map_err(|e| format!("Row error: {e}"))?);
+            }
+            Ok(result)
+        })
+    }
+
+    /// Load the entries whose key starts with `label`, in `group_id`'s scope
+    /// (`None` = global entries).
+    ///
+    /// For operations that only touch a few known entries; avoids reading
+    /// the whole group state like `load_for_group` does.
+    pub async fn load_by_label(
+        &self,
+        label: &[u8],
+        group_id: Option<&[u8]>,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DbError> {
+        self.with_conn(|conn| {
+            let mut stmt = conn
+                .prepare_cached(
+                    "SELECT key, value FROM mls_storage WHERE group_id IS ?1 AND key >= ?2 AND key < ?3",
+                )
+                .map_err(|e| sqlite_error("Failed to prepare load_by_label", e))?;
+            let rows = stmt
+                .query_map(rusqlite::params![group_id, label, label_upper_bound(label)], |row| {
+                    Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?))
+                })
+                .map_err(|e| sqlite_error("Failed to query load_by_label", e))?;
+            let mut result = Vec::new();
+            for row in rows {
+                result.push(row.map_err(|e| sqlite_error("Row error", e))?);
+            }
+            Ok(result)
+        })
     }
 
     /// Save updates (upserts + deletes) in a transaction.
@@ -228,47 +509,170 @@ impl EncryptedDb {
         &self,
         updates: StorageUpdates,
         group_id: Option<&[u8]>,
-    ) -> Result<(), String> {
-        let conn = self.conn.lock().unwrap();
-        let tx = conn
-            .unchecked_transaction()
-            .map_err(|e| format!("Failed to begin transaction: {e}"))?;
+    ) -> Result<(), DbError> {
+        self.save_updates_batch(vec![(updates, group_id.map(<[u8]>::to_vec))]).await
+    }
 
-        for (key, value) in &updates.upserts {
-            let gid: Option<&[u8]> = if is_global_key(key) {
-                None
-            } else {
-                group_id
-            };
+    /// Save several sets of updates, each with its own group id, in one
+    /// transaction. Applied in order, so later sets win.
+    pub async fn save_updates_batch(
+        &self,
+        batches: Vec<(StorageUpdates, Option<Vec<u8>>)>,
+    ) -> Result<(), DbError> {
+        self.with_conn(|conn| {
+            // Immediate, so waiting for the write lock happens here rather
+            // than halfway through the writes.
+            let tx = conn
+                .transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)
+                .map_err(|e| sqlite_error("Failed to begin transaction", e))?;
+            apply_batches(&tx, &batches)?;
+            tx.commit()
+                .map_err(|e| sqlite_error("Failed to commit transaction", e))?;
+            Ok(())
+        })
+    }
+
+    /// Delete all entries for a specific group.
+    pub async fn delete_group(&self, group_id: &[u8]) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.prepare_cached("DELETE FROM mls_storage WHERE group_id = ?1")
+                .and_then(|mut stmt| stmt.execute(rusqlite::params![group_id]))
+                .map_err(|e| format!("Failed to delete group: {e}"))?;
+            Ok(())
+        })
+    }
+
+    /// Move all entries of a group into `mls_quarantine` and record why.
+    ///
+    /// Global entries are left in place. Fails, changing nothing, if the
+    /// group has no stored entries.
+    pub async fn quarantine_group(&self, group_id: &[u8], error: &str, quarantined_at: u64) -> Result<(), String> {
+        self.quarantine_and_replace(group_id, error, quarantined_at, Vec::new()).await
+    }
+
+    /// Quarantine a group like `quarantine_group`, then save `batches` in
+    /// the same transaction, so the group's new state never exists without
+    /// the old one being quarantined, and vice versa.
+    pub async fn quarantine_and_replace(
+        &self,
+        group_id: &[u8],
+        error: &str,
+        quarantined_at: u64,
+        batches: Vec<(StorageUpdates, Option<Vec<u8>>)>,
+    ) -> Result<(), String> {
+        self.with_conn(|conn| {
+            let tx = conn
+                .unchecked_transaction()
+                .map_err(|e| format!("Failed to begin transaction: {e}"))?;
             tx.execute(
-                "INSERT OR REPLACE INTO mls_storage (key, value, group_id) VALUES (?1, ?2, ?3)",
-                rusqlite::params![key, value, gid],
+                "INSERT OR REPLACE INTO mls_quarantine (group_id, key, value)
+                 SELECT group_id, key, value FROM mls_storage WHERE group_id = ?1",
+                rusqlite::params![group_id],
             )
-            .map_err(|e| format!("Failed to upsert: {e}"))?;
-        }
-
-        for key in &updates.deletes {
+            .map_err(|e| format!("Failed to quarantine group: {e}"))?;
+            let moved = tx
+                .execute("DELETE FROM mls_storage WHERE group_id = ?1", rusqlite::params![group_id])
+                .map_err(|e| format!("Failed to quarantine group: {e}"))?;
+            if moved == 0 {
+                return Err("No stored state for group".to_string());
+            }
             tx.execute(
-                "DELETE FROM mls_storage WHERE key = ?1",
-                rusqlite::params![key],
+                "INSERT OR REPLACE INTO quarantined_groups (group_id, error, quarantined_at) VALUES (?1, ?2, ?3)",
+                rusqlite::params![group_id, error, quarantined_at as i64],
             )
-            .map_err(|e| format!("Failed to delete: {e}"))?;
-        }
+            .map_err(|e| format!("Failed to record quarantine: {e}"))?;
+            apply_batches(&tx, &batches)?;
+            tx.commit()
+                .map_err(|e| format!("Failed to commit transaction: {e}"))?;
+            Ok(())
+        })
+    }
 
-        tx.commit()
-            .map_err(|e| format!("Failed to commit transaction: {e}"))?;
-        Ok(())
+    /// List quarantined groups as `(group_id, error, quarantined_at)`.
+    pub async fn quarantined_groups(&self) -> Result<Vec<(Vec<u8>, String, u64)>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn
+                .prepare_cached("SELECT group_id, error, quarantined_at FROM quarantined_groups ORDER BY quarantined_at")
+                .map_err(|e| format!("Failed to prepare quarantined_groups: {e}"))?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)? as u64))
+                })
+                .map_err(|e| format!("Failed to query quarantined_groups: {e}"))?;
+            let mut result = Vec::new();
+            for row in rows {
+                result.push(row.map_err(|e| format!("Row error: {e}"))?);
+            }
+            Ok(result)
+        })
     }
 
-    /// Delete all entries for a specific group.
-    pub async fn delete_group(&self, group_id: &[u8]) -> Result<(), String> {
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
-            "DELETE FROM mls_storage WHERE group_id = ?1",
-            rusqlite::params![group_id],
-        )
-        .map_err(|e| format!("Failed to delete group: {e}"))?;
-        Ok(())
+    /// Read back a quarantined group, or `None` if it is not quarantined.
+    pub async fn quarantined_group(&self, group_id: &[u8]) -> Result<Option<QuarantinedGroup>, String> {
+        use rusqlite::OptionalExtension;
+
+        self.with_conn(|conn| {
+            let meta = conn
+                .query_row(
+                    "SELECT error, quarantined_at FROM quarantined_groups WHERE group_id = ?1",
+                    rusqlite::params![group_id],
+                    |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64)),
+                )
+                .optional()
+                .map_err(|e| format!("Failed to read quarantine record: {e}"))?;
+            let Some((error, quarantined_at)) = meta else {
+                return Ok(None);
+            };
+
+            let mut stmt = conn
+                .prepare_cached("SELECT key, value FROM mls_quarantine WHERE group_id = ?1")
+                .map_err(|e| format!("Failed to prepare quarantined_group: {e}"))?;
+            let rows = stmt
+                .query_map(rusqlite::params![group_id], |row| {
+                    Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?))
+                })
+                .map_err(|e| format!("Failed to query quarantined_group: {e}"))?;
+            let mut entries = Vec::new();
+            for row in rows {
+                entries.push(row.map_err(|e| format!("Row error: {e}"))?);
+            }
+            Ok(Some(QuarantinedGroup { group_id: group_id.to_vec(), error, quarantined_at, rows: entries }))
+        })
+    }
+
+    /// Path, schema version and SQLite settings of the open database.
+    pub async fn info(&self) -> Result<DbInfo, String> {
+        self.with_conn(|conn| {
+            let path = match conn.path() {
+                Some(path) if !path.is_empty() => path.to_string(),
+                _ => ":memory:".to_string(),
+            };
+            let schema_version: u32 = conn
+                .query_row(
+                    &format!(
+                        "SELECT COALESCE((SELECT CAST(value AS INTEGER) FROM db_meta WHERE key = '{META_SCHEMA_VERSION}'), 0)"
+                    ),
+                    [],
+                    |row| row.get(0),
+                )
+                .map_err(|e| format!("Failed to read schema version: {e}"))?;
+            let page_size: u32 = conn
+                .pragma_query_value(None, "page_size", |row| row.get(0))
+                .map_err(|e| format!("Failed to read page_size: {e}"))?;
+            let cache_size: i64 = conn
+                .pragma_query_value(None, "cache_size", |row| row.get(0))
+                .map_err(|e| format!("Failed to read cache_size: {e}"))?;
+            let journal_mode: String = conn
+                .pragma_query_value(None, "journal_mode", |row| row.get(0))
+                .map_err(|e| format!("Failed to read journal_mode: {e}"))?;
+            Ok(DbInfo {
+                path,
+                schema_version,
+                page_size: Some(page_size),
+                cache_size: Some(cache_size),
+                journal_mode: Some(journal_mode.to_lowercase()),
+            })
+        })
     }
 
     /// Close the database connection explicitly.
@@ -276,6 +680,77 @@ impl EncryptedDb {
         // Dropping self closes the connection.
         Ok(())
     }
+
+    /// Irrecoverably destroy the database.
+    ///
+    /// Re-encrypts every page under a random key that is discarded
+    /// immediately, then deletes the database file and its journal files.
+    /// Even if a file cannot be deleted, its contents are unreadable.
+    /// Returns an error if any file still exists afterwards.
+    pub async fn secure_wipe(&self) -> Result<(), String> {
+        use openmls_traits::random::OpenMlsRand;
+
+        self.with_conn(|conn| {
+            let path = conn.path().filter(|p| !p.is_empty()).map(std::path::PathBuf::from);
+
+            let mut random_key: [u8; 32] = openmls_rust_crypto::RustCrypto::default()
+                .random_array()
+                .map_err(|e| format!("Failed to generate wipe key: {e:?}"))?;
+            let hex_key = hex_string(&random_key);
+            random_key.zeroize();
+            conn.execute_batch(
+                "DELETE FROM mls_storage; DELETE FROM mls_quarantine; DELETE FROM quarantined_groups; DELETE FROM db_meta;",
+            )
+                .map_err(|e| format!("Failed to clear database: {e}"))?;
+            conn.pragma_update(None, "rekey", format!("x'{hex_key}'"))
+                .map_err(|e| format!("Failed to rekey database: {e}"))?;
+
+            // Swap in a throwaway connection so the file handle is released.
+            let file_conn = std::mem::replace(
+                &mut *conn,
+                rusqlite::Connection::open_in_memory().map_err(|e| format!("Failed to open database: {e}"))?,
+            );
+            file_conn.close().map_err(|(_, e)| format!("Failed to close database: {e}"))?;
+
+            let Some(path) = path else {
+                return Ok(()); // In-memory: nothing left once the connection is closed.
+            };
+            for suffix in ["", "-journal", "-wal", "-shm"] {
+                let mut file = path.clone().into_os_string();
+                file.push(suffix);
+                let file = std::path::PathBuf::from(file);
+                match std::fs::remove_file(&file) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(format!("Failed to delete {}: {e}", file.display())),
+                }
+                if file.exists() {
+                    return Err(format!("Database file {} still exists after wipe", file.display()));
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+/// rusqlite's busy timeout, restored when the operation timeout is cleared.
+#[cfg(not(target_arch = "wasm32"))]
+const DEFAULT_BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[cfg(not(target_arch = "wasm32"))]
+fn timeout_message(timeout: std::time::Duration) -> String {
+    format!("Timeout: database did not respond within {} ms", timeout.as_millis())
+}
+
+/// `SQLITE_BUSY` means the busy timeout ran out while another connection
+/// held a lock.
+#[cfg(not(target_arch = "wasm32"))]
+fn sqlite_error(context: &str, e: rusqlite::Error) -> DbError {
+    if e.sqlite_error_code() == Some(rusqlite::ErrorCode::DatabaseBusy) {
+        DbError::Timeout(format!("Timeout: {context}: {e}"))
+    } else {
+        DbError::Failed(format!("{context}: {e}"))
+    }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -283,6 +758,40 @@ fn hex_string(bytes: &[u8]) -> String {
     bytes.iter().map(|b| format!("{b:02x}")).collect()
 }
 
+/// Apply sets of updates, each with its own group id, within `tx`.
+#[cfg(not(target_arch = "wasm32"))]
+fn apply_batches(
+    tx: &rusqlite::Transaction<'_>,
+    batches: &[(StorageUpdates, Option<Vec<u8>>)],
+) -> Result<(), String> {
+    for (updates, group_id) in batches {
+        let group_id = group_id.as_deref();
+        let mut upsert = tx
+            .prepare_cached("INSERT OR REPLACE INTO mls_storage (key, value, group_id) VALUES (?1, ?2, ?3)")
+            .map_err(|e| format!("Failed to prepare upsert: {e}"))?;
+        for (key, value) in &updates.upserts {
+            let gid: Option<&[u8]> = if is_global_key(key) {
+                None
+            } else {
+                group_id
+            };
+            upsert
+                .execute(rusqlite::params![key, value, gid])
+                .map_err(|e| format!("Failed to upsert: {e}"))?;
+        }
+
+        let mut delete = tx
+            .prepare_cached("DELETE FROM mls_storage WHERE key = ?1")
+            .map_err(|e| format!("Failed to prepare delete: {e}"))?;
+        for key in &updates.deletes {
+            delete
+                .execute(rusqlite::params![key])
+                .map_err(|e| format!("Failed to delete: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
 // ═══════════════════════════════════════════════════════════════
 // WASM IMPLEMENTATION (IndexedDB + Web Crypto AES-256-GCM)
 // ═══════════════════════════════════════════════════════════════
@@ -315,10 +824,30 @@ impl EncryptedDb {
                 return Err(e);
             }
         };
+        Self::open_with_key(db_path, crypto_key).await
+    }
 
+    /// Open or create an encrypted database whose key is derived from a
+    /// WebAuthn PRF output.
+    ///
+    /// - `prf_output`: 32-byte PRF extension result. Imported as non-extractable
+    ///   HKDF key material and zeroized; the AES-256-GCM key is derived from it
+    ///   inside `crypto.subtle`, so the derived key never exists as raw bytes.
+    ///   The PRF output itself does, and is as sensitive as the key.
+    pub async fn open_with_prf(db_path: String, mut prf_output: Vec<u8>) -> Result<Self, String> {
+        if prf_output.len() != 32 {
+            prf_output.zeroize();
+            return Err(format!("prf_output must be 32 bytes, got {}", prf_output.len()));
+        }
+        let crypto_key = wasm_derive_prf_key(&prf_output).await;
+        prf_output.zeroize();
+        Self::open_with_key(db_path, crypto_key?).await
+    }
+
+    async fn open_with_key(db_path: String, crypto_key: web_sys::CryptoKey) -> Result<Self, String> {
         // Validate key works by encrypting/decrypting a test value.
-        let test_ct = wasm_encrypt(&crypto_key, b"key_validation_test").await?;
-        let test_pt = wasm_decrypt(&crypto_key, &test_ct).await?;
+        let test_ct = wasm_encrypt(&crypto_key, WASM_META_KEY, b"key_validation_test").await?;
+        let test_pt = wasm_decrypt(&crypto_key, WASM_META_KEY, &test_ct).await?;
         if test_pt != b"key_validation_test" {
             return Err("Key validation failed".into());
         }
@@ -365,13 +894,89 @@ impl EncryptedDb {
         if version < 1 {
             self.idb_write_schema_version(1).await?;
         }
+        // v1 → v2: Native-only index change. IDB keys are already ordered,
+        // so label-prefix reads need no structural change.
+        if version < 2 {
+            self.idb_write_schema_version(2).await?;
+        }
+        // v2 → v3: The quarantine object store is created structurally
+        // (IDB_STRUCTURAL_VERSION 2); no data transform.
+        if version < 3 {
+            self.idb_write_schema_version(3).await?;
+        }
+        if version < 4 {
+            self.migrate_wasm_v3_to_v4().await?;
+        }
 
         // Future migrations:
-        // if version < 2 { self.migrate_wasm_v1_to_v2().await?; }
+        // if version < 5 { self.migrate_wasm_v4_to_v5().await?; }
 
         Ok(())
     }
 
+    /// v3 → v4: Re-encrypt every value from the raw `[IV][ciphertext]`
+    /// format into the versioned envelope bound to its key.
+    async fn migrate_wasm_v3_to_v4(&self) -> Result<(), String> {
+        use idb::TransactionMode;
+        use js_sys::Uint8Array;
+
+        // Pre-encrypt everything before opening the transaction
+        // (IDB auto-commits on idle).
+        let mut rows = Vec::new();
+        for (k, enc_v) in self.idb_get_all().await? {
+            let v = wasm_decrypt_legacy(&self.key.0, &enc_v).await?;
+            let enc_new = wasm_encrypt(&self.key.0, &k, &v).await?;
+            rows.push((k, enc_new));
+        }
+        let mut quarantined = Vec::new();
+        for (group_id, enc_v) in self.idb_get_quarantine().await? {
+            let v = wasm_decrypt_legacy(&self.key.0, &enc_v).await?;
+            let enc_new = wasm_encrypt(&self.key.0, &quarantine_aad(&group_id), &v).await?;
+            quarantined.push((group_id, enc_new));
+        }
+        let enc_version = wasm_encrypt(&self.key.0, WASM_META_KEY, &4u32.to_be_bytes()).await?;
+
+        let db = self.idb_open().await?;
+        let txn = db
+            .transaction(&["mls_storage", IDB_QUARANTINE_STORE], TransactionMode::ReadWrite)
+            .map_err(|e| format!("Migration v3→v4: transaction failed: {e}"))?;
+        let store = txn
+            .object_store("mls_storage")
+            .map_err(|e| format!("Migration v3→v4: object_store failed: {e}"))?;
+        let quarantine = txn
+            .object_store(IDB_QUARANTINE_STORE)
+            .map_err(|e| format!("Migration v3→v4: object_store failed: {e}"))?;
+
+        // The version is written in the same transaction, so a failure leaves
+        // the database entirely in the old format.
+        rows.push((WASM_META_KEY.to_vec(), enc_version));
+        for (key, enc_value) in &rows {
+            let js_key = Uint8Array::from(key.as_slice());
+            let js_val = Uint8Array::from(enc_value.as_slice());
+            store
+                .put(&js_val, Some(&js_key.into()))
+                .map_err(|e| format!("Migration v3→v4: put failed: {e}"))?
+                .await
+                .map_err(|e| format!("Migration v3→v4: put.await failed: {e}"))?;
+        }
+        for (group_id, enc_value) in &quarantined {
+            let js_key = Uint8Array::from(group_id.as_slice());
+            let js_val = Uint8Array::from(enc_value.as_slice());
+            quarantine
+                .put(&js_val, Some(&js_key.into()))
+                .map_err(|e| format!("Migration v3→v4: put failed: {e}"))?
+                .await
+                .map_err(|e| format!("Migration v3→v4: put.await failed: {e}"))?;
+        }
+
+        txn.commit()
+            .map_err(|e| format!("Migration v3→v4: commit failed: {e}"))?
+            .await
+            .map_err(|e| format!("Migration v3→v4: commit.await failed: {e}"))?;
+        db.close();
+        Ok(())
+    }
+
     /// Phase A: Ensure all required IDB object stores exist.
     async fn idb_ensure_stores(&self) -> Result<(), String> {
         use idb::{DatabaseEvent, Factory, ObjectStoreParams};
@@ -392,8 +997,15 @@ impl EncryptedDb {
                 }
             }
 
+            if old_version < 2 {
+                if !db.store_names().contains(&IDB_QUARANTINE_STORE.to_string()) {
+                    let params = ObjectStoreParams::new();
+                    db.create_object_store(IDB_QUARANTINE_STORE, params).unwrap();
+                }
+            }
+
             // Future structural changes:
-            // if old_version < 2.0 { db.create_object_store("new_store", ...); }
+            // if old_version < 3 { db.create_object_store("new_store", ...); }
         });
 
         let db = open_req
@@ -430,7 +1042,12 @@ impl EncryptedDb {
             None => Ok(0),
             Some(val) => {
                 let enc_bytes = Uint8Array::new(&val).to_vec();
-                let plain = wasm_decrypt(&self.key.0, &enc_bytes).await?;
+                // Databases before v4 stored the version in the raw format.
+                // AES-GCM authentication makes the fallback unambiguous.
+                let plain = match wasm_decrypt(&self.key.0, WASM_META_KEY, &enc_bytes).await {
+                    Ok(plain) => plain,
+                    Err(_) => wasm_decrypt_legacy(&self.key.0, &enc_bytes).await?,
+                };
                 if plain.len() != 4 {
                     return Err(format!(
                         "Corrupt schema version: expected 4 bytes, got {}",
@@ -448,7 +1065,11 @@ impl EncryptedDb {
         use js_sys::Uint8Array;
 
         // Pre-encrypt before opening transaction (IDB auto-commits on idle).
-        let enc_version = wasm_encrypt(&self.key.0, &version.to_be_bytes()).await?;
+        let enc_version = if version < 4 {
+            wasm_encrypt_legacy(&self.key.0, &version.to_be_bytes()).await?
+        } else {
+            wasm_encrypt(&self.key.0, WASM_META_KEY, &version.to_be_bytes()).await?
+        };
 
         let db = self.idb_open().await?;
         let txn = db
@@ -476,12 +1097,12 @@ impl EncryptedDb {
     }
 
     /// Load all global entries (key starts with a global label prefix).
-    pub async fn load_global(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>, String> {
+    pub async fn load_global(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DbError> {
         let all = self.idb_get_all().await?;
         let mut result = Vec::new();
         for (k, enc_v) in all {
             if is_global_key(&k) {
-                let v = wasm_decrypt(&self.key.0, &enc_v).await?;
+                let v = wasm_decrypt(&self.key.0, &k, &enc_v).await?;
                 result.push((k, v));
             }
         }
@@ -496,13 +1117,66 @@ impl EncryptedDb {
     ///
     /// Since OpenMLS storage keys are opaque, we must load everything and filter by prefix.
     /// For WASM with typical MLS group sizes this is efficient enough.
-    pub async fn load_for_group(&self, _group_id: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, String> {
+    pub async fn load_for_group(&self, _group_id: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DbError> {
         let all = self.idb_get_all().await?;
         let mut result = Vec::new();
         for (k, enc_v) in all {
             // On WASM we load everything — the SnapshotStorageProvider only
             // accesses keys relevant to its operations.
-            let v = wasm_decrypt(&self.key.0, &enc_v).await?;
+            let v = wasm_decrypt(&self.key.0, &k, &enc_v).await?;
+            result.push((k, v));
+        }
+        Ok(result)
+    }
+
+    /// Load every entry as `(group_id, key, value)`.
+    ///
+    /// IDB rows carry no group id, so it is always `None`.
+    pub async fn load_all(&self) -> Result<Vec<(Option<Vec<u8>>, Vec<u8>, Vec<u8>)>, String> {
+        let all = self.idb_get_all().await?;
+        let mut result = Vec::with_capacity(all.len());
+        for (k, enc_v) in all {
+            let v = wasm_decrypt(&self.key.0, &k, &enc_v).await?;
+            result.push((None, k, v));
+        }
+        Ok(result)
+    }
+
+    /// Load up to `limit` entries as `(group_id, key, value)` in key order,
+    /// starting after the key `after` (from the first key for `None`).
+    ///
+    /// IDB rows carry no group id, so it is always `None`.
+    pub async fn load_page(
+        &self,
+        after: Option<&[u8]>,
+        limit: u32,
+    ) -> Result<Vec<(Option<Vec<u8>>, Vec<u8>, Vec<u8>)>, String> {
+        // One more than asked for, in case the page holds the metadata key.
+        let mut page = self.idb_get_page(after, limit.saturating_add(1)).await?;
+        page.retain(|(k, _)| k != WASM_META_KEY);
+        page.truncate(limit as usize);
+        let mut result = Vec::with_capacity(page.len());
+        for (k, enc_v) in page {
+            let v = wasm_decrypt(&self.key.0, &k, &enc_v).await?;
+            result.push((None, k, v));
+        }
+        Ok(result)
+    }
+
+    /// Load the entries whose key starts with `label`.
+    ///
+    /// IDB rows carry no group id, so `group_id` is not used to filter and
+    /// group-scoped labels return the entries of all groups. That is a
+    /// superset of what the caller needs, which the snapshot provider
+    /// tolerates: it only persists entries that actually change.
+    pub async fn load_by_label(
+        &self,
+        label: &[u8],
+        _group_id: Option<&[u8]>,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DbError> {
+        let mut result = Vec::new();
+        for (k, enc_v) in self.idb_get_range(label, &label_upper_bound(label)).await? {
+            let v = wasm_decrypt(&self.key.0, &k, &enc_v).await?;
             result.push((k, v));
         }
         Ok(result)
@@ -513,7 +1187,7 @@ impl EncryptedDb {
         &self,
         updates: StorageUpdates,
         _group_id: Option<&[u8]>,
-    ) -> Result<(), String> {
+    ) -> Result<(), DbError> {
         use idb::TransactionMode;
         use js_sys::Uint8Array;
         use wasm_bindgen::JsValue;
@@ -523,7 +1197,7 @@ impl EncryptedDb {
         // avoid any await (like crypto.subtle) between transaction open and commit.
         let mut encrypted_upserts = Vec::with_capacity(updates.upserts.len());
         for (key, value) in &updates.upserts {
-            let enc_value = wasm_encrypt(&self.key.0, value).await?;
+            let enc_value = wasm_encrypt(&self.key.0, key, value).await?;
             encrypted_upserts.push((key, enc_value));
         }
 
@@ -562,6 +1236,15 @@ impl EncryptedDb {
         Ok(())
     }
 
+    /// Save several sets of updates in one transaction. Applied in order, so
+    /// later sets win. IDB rows carry no group id, so the sets are merged.
+    pub async fn save_updates_batch(
+        &self,
+        batches: Vec<(StorageUpdates, Option<Vec<u8>>)>,
+    ) -> Result<(), DbError> {
+        self.save_updates(merge_batches(batches), None).await
+    }
+
     /// Delete all entries for a specific group.
     /// On WASM, deletes all non-global entries (since we can't filter by group_id column).
     pub async fn delete_group(&self, _group_id: &[u8]) -> Result<(), String> {
@@ -600,11 +1283,203 @@ impl EncryptedDb {
         Ok(())
     }
 
+    /// Move a group's entries into the quarantine store and record why.
+    ///
+    /// IDB rows carry no group id column, so the group's entries are picked
+    /// by the group id encoded in their keys. Global entries and those of
+    /// other groups are left in place. Fails, changing nothing, if the group
+    /// has no stored entries.
+    pub async fn quarantine_group(&self, group_id: &[u8], error: &str, quarantined_at: u64) -> Result<(), String> {
+        self.quarantine_and_replace(group_id, error, quarantined_at, Vec::new()).await
+    }
+
+    /// Quarantine a group like `quarantine_group`, then save `batches` in
+    /// the same transaction.
+    pub async fn quarantine_and_replace(
+        &self,
+        group_id: &[u8],
+        error: &str,
+        quarantined_at: u64,
+        batches: Vec<(StorageUpdates, Option<Vec<u8>>)>,
+    ) -> Result<(), String> {
+        use idb::TransactionMode;
+        use js_sys::Uint8Array;
+        use wasm_bindgen::JsValue;
+
+        let mut rows = Vec::new();
+        for (k, enc_v) in self.idb_get_all().await? {
+            if matches!(crate::snapshot_storage::storage_key_group_id(&k), Ok(Some(g)) if g == group_id) {
+                rows.push((k, enc_v));
+            }
+        }
+        if rows.is_empty() {
+            return Err("No stored state for group".to_string());
+        }
+        let keys: Vec<Vec<u8>> = rows.iter().map(|(k, _)| k.clone()).collect();
+        let mut plain_rows = Vec::with_capacity(rows.len());
+        for (k, enc_v) in rows {
+            let v = wasm_decrypt(&self.key.0, &k, &enc_v).await?;
+            plain_rows.push((k, v));
+        }
+        let record = QuarantinedGroup {
+            group_id: group_id.to_vec(),
+            error: error.to_string(),
+            quarantined_at,
+            rows: plain_rows,
+        };
+        let json = serde_json::to_vec(&record).map_err(|e| format!("Failed to serialize quarantine record: {e}"))?;
+        // Pre-encrypt before opening the transaction (IDB auto-commits on idle).
+        let enc_record = wasm_encrypt(&self.key.0, &quarantine_aad(group_id), &json).await?;
+        let replacement = merge_batches(batches);
+        let mut encrypted_upserts = Vec::with_capacity(replacement.upserts.len());
+        for (key, value) in &replacement.upserts {
+            encrypted_upserts.push((key, wasm_encrypt(&self.key.0, key, value).await?));
+        }
+
+        let db = self.idb_open().await?;
+        let txn = db
+            .transaction(&["mls_storage", IDB_QUARANTINE_STORE], TransactionMode::ReadWrite)
+            .map_err(|e| format!("transaction failed: {e}"))?;
+        let store = txn
+            .object_store("mls_storage")
+            .map_err(|e| format!("object_store failed: {e}"))?;
+        let quarantine = txn
+            .object_store(IDB_QUARANTINE_STORE)
+            .map_err(|e| format!("object_store failed: {e}"))?;
+
+        let js_key = Uint8Array::from(group_id);
+        let js_val = Uint8Array::from(enc_record.as_slice());
+        quarantine
+            .put(&js_val, Some(&js_key.into()))
+            .map_err(|e| format!("put failed: {e}"))?
+            .await
+            .map_err(|e| format!("put.await failed: {e}"))?;
+        for key in keys.iter().chain(&replacement.deletes) {
+            let js_key: JsValue = Uint8Array::from(key.as_slice()).into();
+            store
+                .delete(js_key)
+                .map_err(|e| format!("delete failed: {e}"))?
+                .await
+                .map_err(|e| format!("delete.await failed: {e}"))?;
+        }
+        for (key, enc_value) in &encrypted_upserts {
+            let js_key = Uint8Array::from(key.as_slice());
+            let js_val = Uint8Array::from(enc_value.as_slice());
+            store
+                .put(&js_val, Some(&js_key.into()))
+                .map_err(|e| format!("put failed: {e}"))?
+                .await
+                .map_err(|e| format!("put.await failed: {e}"))?;
+        }
+
+        txn.commit()
+            .map_err(|e| format!("commit failed: {e}"))?
+            .await
+            .map_err(|e| format!("commit.await failed: {e}"))?;
+        db.close();
+        Ok(())
+    }
+
+    /// List quarantined groups as `(group_id, error, quarantined_at)`.
+    pub async fn quarantined_groups(&self) -> Result<Vec<(Vec<u8>, String, u64)>, String> {
+        let rows = self.idb_get_quarantine().await?;
+        let mut result = Vec::with_capacity(rows.len());
+        for (group_id, enc) in &rows {
+            let record = self.decrypt_quarantine_record(group_id, enc).await?;
+            result.push((record.group_id, record.error, record.quarantined_at));
+        }
+        result.sort_by_key(|(_, _, at)| *at);
+        Ok(result)
+    }
+
+    /// Read back a quarantined group, or `None` if it is not quarantined.
+    pub async fn quarantined_group(&self, group_id: &[u8]) -> Result<Option<QuarantinedGroup>, String> {
+        use idb::TransactionMode;
+        use js_sys::Uint8Array;
+
+        let db = self.idb_open().await?;
+        let txn = db
+            .transaction(&[IDB_QUARANTINE_STORE], TransactionMode::ReadOnly)
+            .map_err(|e| format!("transaction failed: {e}"))?;
+        let quarantine = txn
+            .object_store(IDB_QUARANTINE_STORE)
+            .map_err(|e| format!("object_store failed: {e}"))?;
+        let js_key = wasm_bindgen::JsValue::from(Uint8Array::from(group_id));
+        let js_val = quarantine
+            .get(js_key)
+            .map_err(|e| format!("get failed: {e}"))?
+            .await
+            .map_err(|e| format!("get.await failed: {e}"))?;
+        db.close();
+
+        match js_val {
+            None => Ok(None),
+            Some(val) => Ok(Some(self.decrypt_quarantine_record(group_id, &Uint8Array::new(&val).to_vec()).await?)),
+        }
+    }
+
+    async fn decrypt_quarantine_record(&self, group_id: &[u8], enc: &[u8]) -> Result<QuarantinedGroup, String> {
+        let json = wasm_decrypt(&self.key.0, &quarantine_aad(group_id), enc).await?;
+        serde_json::from_slice(&json).map_err(|e| format!("Corrupt quarantine record: {e}"))
+    }
+
     /// Close the database. On WASM, this is a no-op (IDB connections are per-operation).
     pub async fn close(self) -> Result<(), String> {
         Ok(())
     }
 
+    /// Database name and schema version; web has no page or journal
+    /// settings.
+    pub async fn info(&self) -> Result<DbInfo, String> {
+        Ok(DbInfo {
+            path: self.db_name.clone(),
+            schema_version: self.idb_read_schema_version().await?,
+            page_size: None,
+            cache_size: None,
+            journal_mode: None,
+        })
+    }
+
+    /// Not supported on web: an IndexedDB transaction keeps running after
+    /// its future is dropped, so abandoning it could not guarantee that
+    /// nothing was written. Setting a timeout fails; clearing it succeeds.
+    pub fn set_operation_timeout(&self, timeout: Option<std::time::Duration>) -> Result<(), String> {
+        match timeout {
+            Some(_) => Err("Operation timeouts are not supported on web".to_string()),
+            None => Ok(()),
+        }
+    }
+
+    /// Irrecoverably destroy the database.
+    ///
+    /// Deletes the IndexedDB database; values were only ever stored encrypted
+    /// under the non-extractable `CryptoKey`, which is released when the
+    /// engine drops this instance. Verifies by reopening the database and
+    /// checking that it is empty.
+    pub async fn secure_wipe(&self) -> Result<(), String> {
+        use idb::Factory;
+
+        let factory = Factory::new().map_err(|e| format!("Factory::new failed: {e}"))?;
+        factory
+            .delete(&self.db_name)
+            .map_err(|e| format!("Factory::delete failed: {e}"))?
+            .await
+            .map_err(|e| format!("delete_database.await failed: {e}"))?;
+
+        // Reopening recreates an empty database; anything in it means the
+        // delete did not take effect.
+        let remaining = self.idb_get_all_keys().await?;
+        factory
+            .delete(&self.db_name)
+            .map_err(|e| format!("Factory::delete failed: {e}"))?
+            .await
+            .map_err(|e| format!("delete_database.await failed: {e}"))?;
+        if !remaining.is_empty() {
+            return Err("IndexedDB database still contains data after wipe".to_string());
+        }
+        Ok(())
+    }
+
     // -- IDB helpers --
 
     async fn idb_open(&self) -> Result<idb::Database, String> {
@@ -617,9 +1492,11 @@ impl EncryptedDb {
 
         open_req.on_upgrade_needed(|event| {
             let db = event.database().unwrap();
-            if !db.store_names().contains(&"mls_storage".to_string()) {
-                let params = ObjectStoreParams::new();
-                db.create_object_store("mls_storage", params).unwrap();
+            for store in ["mls_storage", IDB_QUARANTINE_STORE] {
+                if !db.store_names().contains(&store.to_string()) {
+                    let params = ObjectStoreParams::new();
+                    db.create_object_store(store, params).unwrap();
+                }
             }
         });
 
@@ -669,6 +1546,128 @@ impl EncryptedDb {
         Ok(result)
     }
 
+    /// Get all rows with `lower <= key < upper`.
+    async fn idb_get_range(&self, lower: &[u8], upper: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, String> {
+        use idb::{KeyRange, Query, TransactionMode};
+        use js_sys::Uint8Array;
+
+        let range = || {
+            KeyRange::bound(
+                &Uint8Array::from(lower).into(),
+                &Uint8Array::from(upper).into(),
+                Some(false),
+                Some(true),
+            )
+            .map(Query::KeyRange)
+            .map_err(|e| format!("KeyRange::bound failed: {e}"))
+        };
+
+        let db = self.idb_open().await?;
+        let txn = db
+            .transaction(&["mls_storage"], TransactionMode::ReadOnly)
+            .map_err(|e| format!("transaction failed: {e}"))?;
+        let store = txn
+            .object_store("mls_storage")
+            .map_err(|e| format!("object_store failed: {e}"))?;
+
+        let keys = store
+            .get_all_keys(Some(range()?), None)
+            .map_err(|e| format!("get_all_keys failed: {e}"))?
+            .await
+            .map_err(|e| format!("get_all_keys.await failed: {e}"))?;
+        let values = store
+            .get_all(Some(range()?), None)
+            .map_err(|e| format!("get_all failed: {e}"))?
+            .await
+            .map_err(|e| format!("get_all.await failed: {e}"))?;
+
+        // Both requests return rows in key order within one transaction.
+        let result = keys
+            .iter()
+            .zip(values.iter())
+            .map(|(k, v)| (Uint8Array::new(k).to_vec(), Uint8Array::new(v).to_vec()))
+            .collect();
+        db.close();
+        Ok(result)
+    }
+
+    /// Get up to `limit` rows with `key > after` (all keys for `None`).
+    async fn idb_get_page(&self, after: Option<&[u8]>, limit: u32) -> Result<Vec<(Vec<u8>, Vec<u8>)>, String> {
+        use idb::{KeyRange, Query, TransactionMode};
+        use js_sys::Uint8Array;
+
+        let range = || {
+            after
+                .map(|after| {
+                    KeyRange::lower_bound(&Uint8Array::from(after).into(), Some(true))
+                        .map(Query::KeyRange)
+                        .map_err(|e| format!("KeyRange::lower_bound failed: {e}"))
+                })
+                .transpose()
+        };
+
+        let db = self.idb_open().await?;
+        let txn = db
+            .transaction(&["mls_storage"], TransactionMode::ReadOnly)
+            .map_err(|e| format!("transaction failed: {e}"))?;
+        let store = txn
+            .object_store("mls_storage")
+            .map_err(|e| format!("object_store failed: {e}"))?;
+
+        let keys = store
+            .get_all_keys(range()?, Some(limit))
+            .map_err(|e| format!("get_all_keys failed: {e}"))?
+            .await
+            .map_err(|e| format!("get_all_keys.await failed: {e}"))?;
+        let values = store
+            .get_all(range()?, Some(limit))
+            .map_err(|e| format!("get_all failed: {e}"))?
+            .await
+            .map_err(|e| format!("get_all.await failed: {e}"))?;
+
+        // Both requests return rows in key order within one transaction.
+        let result = keys
+            .iter()
+            .zip(values.iter())
+            .map(|(k, v)| (Uint8Array::new(k).to_vec(), Uint8Array::new(v).to_vec()))
+            .collect();
+        db.close();
+        Ok(result)
+    }
+
+    /// Get all `(group_id, encrypted record)` rows of the quarantine store.
+    async fn idb_get_quarantine(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>, String> {
+        use idb::TransactionMode;
+        use js_sys::Uint8Array;
+
+        let db = self.idb_open().await?;
+        let txn = db
+            .transaction(&[IDB_QUARANTINE_STORE], TransactionMode::ReadOnly)
+            .map_err(|e| format!("transaction failed: {e}"))?;
+        let quarantine = txn
+            .object_store(IDB_QUARANTINE_STORE)
+            .map_err(|e| format!("object_store failed: {e}"))?;
+        let keys = quarantine
+            .get_all_keys(None, None)
+            .map_err(|e| format!("get_all_keys failed: {e}"))?
+            .await
+            .map_err(|e| format!("get_all_keys.await failed: {e}"))?;
+        let values = quarantine
+            .get_all(None, None)
+            .map_err(|e| format!("get_all failed: {e}"))?
+            .await
+            .map_err(|e| format!("get_all.await failed: {e}"))?;
+
+        // Both requests return rows in key order within one transaction.
+        let result = keys
+            .iter()
+            .zip(values.iter())
+            .map(|(k, v)| (Uint8Array::new(k).to_vec(), Uint8Array::new(v).to_vec()))
+            .collect();
+        db.close();
+        Ok(result)
+    }
+
     async fn idb_get_all_keys(&self) -> Result<Vec<Vec<u8>>, String> {
         use idb::TransactionMode;
         use js_sys::Uint8Array;
@@ -735,19 +1734,174 @@ async fn wasm_import_key(raw: &[u8]) -> Result<web_sys::CryptoKey, String> {
         .map_err(|e| format!("importKey result is not CryptoKey: {e:?}"))
 }
 
-/// Encrypt plaintext with AES-256-GCM via `crypto.subtle`.
-/// Output format: `[12-byte IV || ciphertext + 16-byte tag]`.
+/// HKDF info for the database key derived from a WebAuthn PRF output.
 #[cfg(target_arch = "wasm32")]
-async fn wasm_encrypt(key: &web_sys::CryptoKey, plaintext: &[u8]) -> Result<Vec<u8>, String> {
-    use js_sys::Uint8Array;
+const PRF_KEY_INFO: &[u8] = b"openmls_dart database key";
+
+/// Derive a non-extractable AES-GCM CryptoKey from a WebAuthn PRF output
+/// with HKDF-SHA256.
+#[cfg(target_arch = "wasm32")]
+async fn wasm_derive_prf_key(prf_output: &[u8]) -> Result<web_sys::CryptoKey, String> {
+    use js_sys::{Array, Object, Reflect, Uint8Array};
     use wasm_bindgen::JsCast;
     use wasm_bindgen_futures::JsFuture;
 
-    // Generate 12-byte random IV.
+    let subtle = web_sys::window()
+        .ok_or("crypto.subtle requires a secure context (HTTPS or localhost)")?
+        .crypto()
+        .map_err(|_| "crypto.subtle requires a secure context (HTTPS or localhost)")?
+        .subtle();
+
+    // Key material: the PRF output, usable only for deriveKey.
+    let derive_usages = Array::new();
+    derive_usages.push(&"deriveKey".into());
+    let promise = subtle
+        .import_key_with_str("raw", &Uint8Array::from(prf_output).into(), "HKDF", false, &derive_usages)
+        .map_err(|e| format!("importKey failed: {e:?}"))?;
+    let base_key = JsFuture::from(promise)
+        .await
+        .map_err(|e| format!("importKey promise rejected: {e:?}"))?
+        .dyn_into::<web_sys::CryptoKey>()
+        .map_err(|e| format!("importKey result is not CryptoKey: {e:?}"))?;
+
+    // Algorithm: { name: "HKDF", hash: "SHA-256", salt: [], info: PRF_KEY_INFO }
+    let hkdf = Object::new();
+    for (name, value) in [
+        ("name", wasm_bindgen::JsValue::from("HKDF")),
+        ("hash", "SHA-256".into()),
+        ("salt", Uint8Array::new_with_length(0).into()),
+        ("info", Uint8Array::from(PRF_KEY_INFO).into()),
+    ] {
+        Reflect::set(&hkdf, &name.into(), &value).map_err(|e| format!("Reflect::set failed: {e:?}"))?;
+    }
+
+    // Derived key: { name: "AES-GCM", length: 256 }
+    let aes = Object::new();
+    Reflect::set(&aes, &"name".into(), &"AES-GCM".into())
+        .map_err(|e| format!("Reflect::set failed: {e:?}"))?;
+    Reflect::set(&aes, &"length".into(), &256.into())
+        .map_err(|e| format!("Reflect::set failed: {e:?}"))?;
+
+    let usages = Array::new();
+    usages.push(&"encrypt".into());
+    usages.push(&"decrypt".into());
+
+    let promise = subtle
+        .derive_key_with_object_and_object(&hkdf, &base_key, &aes, false, &usages)
+        .map_err(|e| format!("deriveKey failed: {e:?}"))?;
+    JsFuture::from(promise)
+        .await
+        .map_err(|e| format!("deriveKey promise rejected: {e:?}"))?
+        .dyn_into::<web_sys::CryptoKey>()
+        .map_err(|e| format!("deriveKey result is not CryptoKey: {e:?}"))
+}
+
+/// Additional data binding a quarantine record to its group.
+/// Merge sets of updates into one, applied in order so later sets win. IDB
+/// rows carry no group id, so the ids are dropped.
+#[cfg(target_arch = "wasm32")]
+fn merge_batches(batches: Vec<(StorageUpdates, Option<Vec<u8>>)>) -> StorageUpdates {
+    let mut upserts: std::collections::HashMap<Vec<u8>, Vec<u8>> = std::collections::HashMap::new();
+    let mut deletes: std::collections::HashSet<Vec<u8>> = std::collections::HashSet::new();
+    for (updates, _group_id) in batches {
+        for (key, value) in updates.upserts {
+            deletes.remove(&key);
+            upserts.insert(key, value);
+        }
+        for key in updates.deletes {
+            upserts.remove(&key);
+            deletes.insert(key);
+        }
+    }
+    StorageUpdates { upserts: upserts.into_iter().collect(), deletes: deletes.into_iter().collect() }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn quarantine_aad(group_id: &[u8]) -> Vec<u8> {
+    let mut aad = IDB_QUARANTINE_STORE.as_bytes().to_vec();
+    aad.extend_from_slice(group_id);
+    aad
+}
+
+/// Encrypt a value into the envelope, bound to `aad` (the row's key).
+/// Output format: `[version][nonce_len][nonce][ciphertext + 16-byte tag]`.
+#[cfg(target_arch = "wasm32")]
+async fn wasm_encrypt(key: &web_sys::CryptoKey, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let mut nonce = [0u8; WASM_NONCE_LEN];
+    getrandom::fill(&mut nonce).map_err(|e| format!("getrandom failed: {e}"))?;
+
+    let ct = aes_gcm_encrypt(key, &nonce, Some(aad), plaintext).await?;
+    let mut out = Vec::with_capacity(2 + nonce.len() + ct.len());
+    out.push(WASM_ENVELOPE_VERSION);
+    out.push(nonce.len() as u8);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ct);
+    Ok(out)
+}
+
+/// Decrypt an envelope produced by `wasm_encrypt` with the same `aad`.
+#[cfg(target_arch = "wasm32")]
+async fn wasm_decrypt(key: &web_sys::CryptoKey, aad: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    let (version, rest) = data.split_first().ok_or("ciphertext too short")?;
+    if *version != WASM_ENVELOPE_VERSION {
+        return Err(format!("Unsupported storage envelope version {version}"));
+    }
+    let (nonce_len, rest) = rest.split_first().ok_or("ciphertext too short")?;
+    if rest.len() < *nonce_len as usize {
+        return Err("ciphertext too short".into());
+    }
+    let (nonce, ciphertext) = rest.split_at(*nonce_len as usize);
+    aes_gcm_decrypt(key, nonce, Some(aad), ciphertext).await
+}
+
+/// Encrypt in the pre-v4 format: `[12-byte IV || ciphertext + 16-byte tag]`.
+/// Only used to write the schema version while migrating up to v4.
+#[cfg(target_arch = "wasm32")]
+async fn wasm_encrypt_legacy(key: &web_sys::CryptoKey, plaintext: &[u8]) -> Result<Vec<u8>, String> {
     let mut iv = [0u8; 12];
     getrandom::fill(&mut iv).map_err(|e| format!("getrandom failed: {e}"))?;
 
-    let params = web_sys::AesGcmParams::new("AES-GCM", &Uint8Array::from(&iv[..]));
+    let ct = aes_gcm_encrypt(key, &iv, None, plaintext).await?;
+    let mut out = Vec::with_capacity(12 + ct.len());
+    out.extend_from_slice(&iv);
+    out.extend_from_slice(&ct);
+    Ok(out)
+}
+
+/// Decrypt the pre-v4 format: `[12-byte IV || ciphertext + 16-byte tag]`.
+#[cfg(target_arch = "wasm32")]
+async fn wasm_decrypt_legacy(key: &web_sys::CryptoKey, data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < 12 {
+        return Err("ciphertext too short".into());
+    }
+    let (iv, ciphertext) = data.split_at(12);
+    aes_gcm_decrypt(key, iv, None, ciphertext).await
+}
+
+#[cfg(target_arch = "wasm32")]
+fn aes_gcm_params(nonce: &[u8], aad: Option<&[u8]>) -> web_sys::AesGcmParams {
+    use js_sys::Uint8Array;
+
+    let params = web_sys::AesGcmParams::new("AES-GCM", &Uint8Array::from(nonce));
+    if let Some(aad) = aad {
+        params.set_additional_data(&Uint8Array::from(aad));
+    }
+    params
+}
+
+/// Encrypt with AES-256-GCM via `crypto.subtle`. Returns `ciphertext + tag`.
+#[cfg(target_arch = "wasm32")]
+async fn aes_gcm_encrypt(
+    key: &web_sys::CryptoKey,
+    nonce: &[u8],
+    aad: Option<&[u8]>,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, String> {
+    use js_sys::Uint8Array;
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+
+    let params = aes_gcm_params(nonce, aad);
     let subtle = web_sys::window()
         .ok_or("window unavailable")?
         .crypto()
@@ -762,27 +1916,22 @@ async fn wasm_encrypt(key: &web_sys::CryptoKey, plaintext: &[u8]) -> Result<Vec<
         .await
         .map_err(|e| format!("encrypt promise rejected: {e:?}"))?;
 
-    let ct_array = Uint8Array::new(&result.unchecked_into::<js_sys::ArrayBuffer>());
-    let mut out = Vec::with_capacity(12 + ct_array.length() as usize);
-    out.extend_from_slice(&iv);
-    out.extend_from_slice(&ct_array.to_vec());
-    Ok(out)
+    Ok(Uint8Array::new(&result.unchecked_into::<js_sys::ArrayBuffer>()).to_vec())
 }
 
-/// Decrypt ciphertext with AES-256-GCM via `crypto.subtle`.
-/// Input format: `[12-byte IV || ciphertext + 16-byte tag]`.
+/// Decrypt `ciphertext + tag` with AES-256-GCM via `crypto.subtle`.
 #[cfg(target_arch = "wasm32")]
-async fn wasm_decrypt(key: &web_sys::CryptoKey, data: &[u8]) -> Result<Vec<u8>, String> {
+async fn aes_gcm_decrypt(
+    key: &web_sys::CryptoKey,
+    nonce: &[u8],
+    aad: Option<&[u8]>,
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, String> {
     use js_sys::Uint8Array;
     use wasm_bindgen::JsCast;
     use wasm_bindgen_futures::JsFuture;
 
-    if data.len() < 12 {
-        return Err("ciphertext too short".into());
-    }
-    let (iv_bytes, ciphertext) = data.split_at(12);
-
-    let params = web_sys::AesGcmParams::new("AES-GCM", &Uint8Array::from(iv_bytes));
+    let params = aes_gcm_params(nonce, aad);
     let subtle = web_sys::window()
         .ok_or("window unavailable")?
         .crypto()
@@ -797,6 +1946,26 @@ async fn wasm_decrypt(key: &web_sys::CryptoKey, data: &[u8]) -> Result<Vec<u8>,
         .await
         .map_err(|e| format!("decrypt failed: {e:?}"))?;
 
-    let pt_array = Uint8Array::new(&result.unchecked_into::<js_sys::ArrayBuffer>());
-    Ok(pt_array.to_vec())
+    Ok(Uint8Array::new(&result.unchecked_into::<js_sys::ArrayBuffer>()).to_vec())
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waiting_past_the_operation_timeout_is_a_timeout() {
+        futures::executor::block_on(async {
+            let db = EncryptedDb::open(":memory:".to_string(), vec![7; 32]).await.unwrap();
+            db.set_operation_timeout(Some(std::time::Duration::from_millis(20))).unwrap();
+
+            let held = db.conn.lock();
+            assert!(matches!(db.load_global().await, Err(DbError::Timeout(_))));
+            let write = db.save_updates(StorageUpdates { upserts: Vec::new(), deletes: Vec::new() }, None).await;
+            assert!(matches!(write, Err(DbError::Timeout(_))));
+            drop(held);
+
+            assert!(db.load_global().await.is_ok());
+        });
+    }
 }
@@ -0,0 +1,162 @@
+//! Passphrase-protected exports of one group's storage entries, for moving
+//! a group to another device (see `MlsEngine::export_group_state`).
+//!
+//! The key is derived from the passphrase with PBKDF2-HMAC-SHA256 (RFC 8018)
+//! and the entries are sealed with ChaCha20-Poly1305. Wire format (TLS
+//! presentation language, RFC 9420 §2.1):
+//!
+//! ```text
+//! struct {
+//!     opaque key<V>;
+//!     opaque value<V>;
+//! } GroupStateEntry;
+//!
+//! struct {
+//!     opaque group_id<V>;
+//!     GroupStateEntry entries[];         // until the end of the plaintext
+//! } GroupState;
+//!
+//! struct {
+//!     uint8 version = 1;
+//!     uint32 iterations;                 // PBKDF2 iteration count
+//!     opaque salt[16];
+//!     opaque nonce[12];
+//!     opaque ciphertext<V>;              // sealed GroupState
+//! } GroupStateExport;
+//! ```
+//!
+//! Every byte before `ciphertext` is authenticated as AAD, so the iteration
+//! count cannot be lowered without the passphrase.
+
+use openmls::prelude::tls_codec::{DeserializeBytes, Serialize, VLBytes};
+use openmls_traits::crypto::OpenMlsCrypto;
+use openmls_traits::random::OpenMlsRand;
+use openmls_traits::types::{AeadType, HashType};
+use zeroize::Zeroize;
+
+use crate::hybrid_crypto::HybridCrypto;
+
+const GROUP_EXPORT_VERSION: u8 = 1;
+pub(crate) const PBKDF2_ITERATIONS: u32 = 600_000;
+/// Upper bound accepted on import, so a crafted blob cannot stall the
+/// derivation before the AEAD check rejects it.
+const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+const HEADER_LEN: usize = 1 + 4 + SALT_LEN + NONCE_LEN;
+
+/// Seal `entries` of `group_id` under `passphrase`.
+pub(crate) fn seal(
+    crypto: &HybridCrypto,
+    passphrase: &[u8],
+    group_id: &[u8],
+    entries: &[(Vec<u8>, Vec<u8>)],
+) -> Result<Vec<u8>, String> {
+    if passphrase.is_empty() {
+        return Err("Passphrase must not be empty".to_string());
+    }
+    let mut plaintext = Vec::new();
+    write_vl(&mut plaintext, group_id)?;
+    for (key, value) in entries {
+        write_vl(&mut plaintext, key)?;
+        write_vl(&mut plaintext, value)?;
+    }
+
+    let salt = crypto.random_vec(SALT_LEN).map_err(|e| format!("Failed to generate salt: {:?}", e))?;
+    let nonce = crypto.random_vec(NONCE_LEN).map_err(|e| format!("Failed to generate nonce: {:?}", e))?;
+    let mut out = vec![GROUP_EXPORT_VERSION];
+    out.extend_from_slice(&PBKDF2_ITERATIONS.to_be_bytes());
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+
+    let mut key = pbkdf2(crypto, passphrase, &salt, PBKDF2_ITERATIONS)?;
+    let sealed = crypto.aead_encrypt(AeadType::ChaCha20Poly1305, &key, &plaintext, &nonce, &out);
+    key.zeroize();
+    plaintext.zeroize();
+    let ciphertext = sealed.map_err(|e| format!("Failed to encrypt group state: {:?}", e))?;
+    write_vl(&mut out, &ciphertext)?;
+    Ok(out)
+}
+
+/// Open a blob built by `seal`, returning the group id and its entries.
+pub(crate) fn open(
+    crypto: &HybridCrypto,
+    passphrase: &[u8],
+    blob: &[u8],
+) -> Result<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>), String> {
+    if blob.len() < HEADER_LEN {
+        return Err("Truncated group state export".to_string());
+    }
+    let (header, rest) = blob.split_at(HEADER_LEN);
+    if header[0] != GROUP_EXPORT_VERSION {
+        return Err(format!("Unsupported group state export version {}", header[0]));
+    }
+    let iterations = u32::from_be_bytes(header[1..5].try_into().expect("header length checked"));
+    let salt = &header[5..5 + SALT_LEN];
+    let nonce = &header[5 + SALT_LEN..];
+    let (ciphertext, rest) = read_vl(rest)?;
+    if !rest.is_empty() {
+        return Err("Trailing bytes after group state export".to_string());
+    }
+
+    let mut key = pbkdf2(crypto, passphrase, salt, iterations)?;
+    let opened = crypto.aead_decrypt(AeadType::ChaCha20Poly1305, &key, ciphertext.as_slice(), nonce, header);
+    key.zeroize();
+    let mut plaintext =
+        opened.map_err(|_| "Failed to decrypt group state: wrong passphrase or corrupted export".to_string())?;
+
+    let decoded = decode_state(&plaintext);
+    plaintext.zeroize();
+    decoded
+}
+
+fn decode_state(plaintext: &[u8]) -> Result<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>), String> {
+    let (group_id, mut rest) = read_vl(plaintext)?;
+    let mut entries = Vec::new();
+    while !rest.is_empty() {
+        let (key, after_key) = read_vl(rest)?;
+        let (value, after_value) = read_vl(after_key)?;
+        entries.push((key.as_slice().to_vec(), value.as_slice().to_vec()));
+        rest = after_value;
+    }
+    Ok((group_id.as_slice().to_vec(), entries))
+}
+
+/// PBKDF2-HMAC-SHA256 with a single output block, which is all a 32-byte
+/// key needs.
+pub(crate) fn pbkdf2(crypto: &HybridCrypto, passphrase: &[u8], salt: &[u8], iterations: u32) -> Result<Vec<u8>, String> {
+    if iterations == 0 || iterations > MAX_PBKDF2_ITERATIONS {
+        return Err("Invalid PBKDF2 iteration count".to_string());
+    }
+    let hmac = |message: &[u8]| {
+        crypto
+            .hmac(HashType::Sha2_256, passphrase, message)
+            .map(|mac| mac.as_slice().to_vec())
+            .map_err(|e| format!("Failed to derive export key: {:?}", e))
+    };
+    let mut block = salt.to_vec();
+    block.extend_from_slice(&1u32.to_be_bytes());
+    let mut u = hmac(&block)?;
+    let mut key = u.clone();
+    for _ in 1..iterations {
+        let next = hmac(&u)?;
+        u.zeroize();
+        u = next;
+        key.iter_mut().zip(&u).for_each(|(k, x)| *k ^= x);
+    }
+    u.zeroize();
+    key.truncate(KEY_LEN);
+    Ok(key)
+}
+
+fn write_vl(out: &mut Vec<u8>, bytes: &[u8]) -> Result<(), String> {
+    VLBytes::new(bytes.to_vec())
+        .tls_serialize(out)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to encode group state export: {}", e))
+}
+
+fn read_vl(input: &[u8]) -> Result<(VLBytes, &[u8]), String> {
+    VLBytes::tls_deserialize_bytes(input).map_err(|e| format!("Malformed group state export: {}", e))
+}
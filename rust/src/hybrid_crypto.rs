@@ -26,10 +26,10 @@ use openmls_traits::{
 
 pub struct HybridCrypto {
     rust: openmls_rust_crypto::RustCrypto,
-    /// Lazily-initialized libcrux provider. `Err` is cached per instance, but
-    /// providers are recreated per engine operation, so the next operation
-    /// retries initialization.
-    libcrux: OnceLock<Result<openmls_libcrux_crypto::CryptoProvider, CryptoError>>,
+    /// Lazily-initialized libcrux provider. Only a successful initialization
+    /// is cached: the instance is shared by all operations of an engine, so a
+    /// failure must leave the next X-Wing operation free to retry.
+    libcrux: OnceLock<openmls_libcrux_crypto::CryptoProvider>,
 }
 
 // HybridCrypto crosses FRB's async task boundary inside SnapshotOpenMlsProvider,
@@ -47,10 +47,17 @@ impl HybridCrypto {
 
     /// Returns the libcrux provider, initializing it on first use.
     fn libcrux(&self) -> Result<&openmls_libcrux_crypto::CryptoProvider, CryptoError> {
-        self.libcrux
-            .get_or_init(openmls_libcrux_crypto::CryptoProvider::new)
-            .as_ref()
-            .map_err(|e| e.clone())
+        if let Some(provider) = self.libcrux.get() {
+            return Ok(provider);
+        }
+        // A concurrent initializer may win the race; the spare is dropped.
+        let provider = openmls_libcrux_crypto::CryptoProvider::new()?;
+        Ok(self.libcrux.get_or_init(|| provider))
+    }
+
+    /// Initialize the libcrux provider ahead of the first X-Wing operation.
+    pub fn warm_up(&self) -> Result<(), CryptoError> {
+        self.libcrux().map(|_| ())
     }
 }
 
@@ -381,6 +388,21 @@ mod tests {
         assert!(hybrid.libcrux.get().is_some(), "X-Wing op must initialize libcrux");
     }
 
+    /// `warm_up` initializes libcrux up front, and the instance is reused by
+    /// later operations rather than rebuilt.
+    #[test]
+    fn warm_up_initializes_libcrux_once() {
+        let hybrid = HybridCrypto::new();
+        hybrid.warm_up().expect("warm up");
+        let first = hybrid.libcrux.get().expect("libcrux initialized") as *const _;
+
+        hybrid
+            .supports(Ciphersuite::MLS_256_XWING_CHACHA20POLY1305_SHA256_Ed25519)
+            .expect("xwing supported");
+        let second = hybrid.libcrux.get().expect("libcrux initialized") as *const _;
+        assert_eq!(first, second, "libcrux must not be re-initialized");
+    }
+
     /// The public API ciphersuite list and the provider must stay in sync:
     /// every suite advertised by `supported_ciphersuites()` (api/types.rs)
     /// must be supported by the shipped crypto provider, and every provider
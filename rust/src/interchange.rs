@@ -0,0 +1,366 @@
+//! Platform-independent engine backups (see `MlsEngine::export_interchange`):
+//! a backup made on native (SQLCipher) restores on web (IndexedDB) and vice
+//! versa.
+//!
+//! A backup is a tagged COSE_Encrypt0 message (RFC 9052 §5.2) sealed with
+//! ChaCha20/Poly1305 (COSE algorithm 24) under a key derived from a
+//! passphrase with PBKDF2-HMAC-SHA256 (RFC 8018). In CDDL (RFC 8610):
+//!
+//! ```text
+//! Backup = #6.16([
+//!     protected: bstr .cbor {
+//!         1: 24,                              ; alg: ChaCha20/Poly1305
+//!         "pbkdf2-salt": bstr .size 16,
+//!         "pbkdf2-iterations": uint,
+//!     },
+//!     unprotected: { 5: bstr .size 12 },     ; IV
+//!     ciphertext: bstr,                      ; sealed Payload
+//! ])
+//!
+//! Payload = {
+//!     "version": 1,
+//!     "created_at": uint,                    ; Unix seconds
+//!     "global": [* Entry],
+//!     "groups": [* { "id": bstr, "entries": [* Entry] }],
+//! }
+//!
+//! Entry = [key: bstr, value: bstr]           ; decrypted storage entry
+//! ```
+//!
+//! The AAD is the COSE Enc_structure `["Encrypt0", protected, h'']`, so the
+//! KDF parameters cannot be altered without the passphrase. Entries are
+//! filed under the group id encoded in their storage key rather than the
+//! SQLCipher `group_id` column, which IndexedDB does not have; importing
+//! derives the column again. Only definite-length items are produced and
+//! accepted.
+
+use std::collections::BTreeMap;
+
+use openmls_traits::crypto::OpenMlsCrypto;
+use openmls_traits::random::OpenMlsRand;
+use openmls_traits::types::AeadType;
+use zeroize::Zeroize;
+
+use crate::group_export::{pbkdf2, PBKDF2_ITERATIONS};
+use crate::hybrid_crypto::HybridCrypto;
+
+const PAYLOAD_VERSION: u64 = 1;
+const COSE_ENCRYPT0_TAG: u64 = 16;
+const COSE_ALG: u64 = 1;
+const COSE_IV: u64 = 5;
+const COSE_ALG_CHACHA20_POLY1305: u64 = 24;
+const SALT_LABEL: &str = "pbkdf2-salt";
+const ITERATIONS_LABEL: &str = "pbkdf2-iterations";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// Deepest nesting a valid backup needs is 4; anything deeper is rejected
+/// before it can exhaust the stack.
+const MAX_DEPTH: usize = 8;
+
+const MAJOR_UINT: u8 = 0;
+const MAJOR_BYTES: u8 = 2;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+
+/// Storage entries of a backup.
+pub(crate) struct InterchangePayload {
+    pub(crate) created_at: u64,
+    pub(crate) global: Vec<(Vec<u8>, Vec<u8>)>,
+    pub(crate) groups: BTreeMap<Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>>,
+}
+
+/// Seal `payload` under `passphrase` as a COSE_Encrypt0 backup.
+pub(crate) fn seal(crypto: &HybridCrypto, passphrase: &[u8], payload: &InterchangePayload) -> Result<Vec<u8>, String> {
+    if passphrase.is_empty() {
+        return Err("Passphrase must not be empty".to_string());
+    }
+    let mut plaintext = encode_payload(payload);
+
+    let salt = crypto.random_vec(SALT_LEN).map_err(|e| format!("Failed to generate salt: {:?}", e))?;
+    let nonce = crypto.random_vec(NONCE_LEN).map_err(|e| format!("Failed to generate nonce: {:?}", e))?;
+    let mut protected = Vec::new();
+    write_head(&mut protected, MAJOR_MAP, 3);
+    write_head(&mut protected, MAJOR_UINT, COSE_ALG);
+    write_head(&mut protected, MAJOR_UINT, COSE_ALG_CHACHA20_POLY1305);
+    write_text(&mut protected, SALT_LABEL);
+    write_bytes(&mut protected, &salt);
+    write_text(&mut protected, ITERATIONS_LABEL);
+    write_head(&mut protected, MAJOR_UINT, PBKDF2_ITERATIONS as u64);
+
+    let mut key = pbkdf2(crypto, passphrase, &salt, PBKDF2_ITERATIONS)?;
+    let sealed = crypto.aead_encrypt(AeadType::ChaCha20Poly1305, &key, &plaintext, &nonce, &enc_structure(&protected));
+    key.zeroize();
+    plaintext.zeroize();
+    let ciphertext = sealed.map_err(|e| format!("Failed to encrypt backup: {:?}", e))?;
+
+    let mut out = Vec::with_capacity(ciphertext.len() + protected.len() + 32);
+    write_head(&mut out, MAJOR_TAG, COSE_ENCRYPT0_TAG);
+    write_head(&mut out, MAJOR_ARRAY, 3);
+    write_bytes(&mut out, &protected);
+    write_head(&mut out, MAJOR_MAP, 1);
+    write_head(&mut out, MAJOR_UINT, COSE_IV);
+    write_bytes(&mut out, &nonce);
+    write_bytes(&mut out, &ciphertext);
+    Ok(out)
+}
+
+/// Open a backup built by `seal`.
+pub(crate) fn open(crypto: &HybridCrypto, passphrase: &[u8], blob: &[u8]) -> Result<InterchangePayload, String> {
+    let message = match decode(blob)? {
+        Value::Tag(COSE_ENCRYPT0_TAG, message) => *message,
+        _ => return Err("Backup is not a COSE_Encrypt0 message".to_string()),
+    };
+    let [protected_bytes, unprotected, ciphertext] = <[Value; 3]>::try_from(into_array(message)?)
+        .map_err(|_| "Malformed COSE_Encrypt0 message".to_string())?;
+    let protected_bytes = into_bytes(protected_bytes)?;
+    let protected = into_map(decode(&protected_bytes)?)?;
+    if field(&protected, &Value::Uint(COSE_ALG)) != Some(&Value::Uint(COSE_ALG_CHACHA20_POLY1305)) {
+        return Err("Unsupported backup algorithm".to_string());
+    }
+    let salt = match field(&protected, &Value::Text(SALT_LABEL.to_string())) {
+        Some(Value::Bytes(salt)) if salt.len() == SALT_LEN => salt,
+        _ => return Err("Backup has no valid PBKDF2 salt".to_string()),
+    };
+    let iterations = match field(&protected, &Value::Text(ITERATIONS_LABEL.to_string())) {
+        Some(Value::Uint(n)) => u32::try_from(*n).map_err(|_| "Invalid PBKDF2 iteration count".to_string())?,
+        _ => return Err("Backup has no PBKDF2 iteration count".to_string()),
+    };
+    let unprotected = into_map(unprotected)?;
+    let nonce = match field(&unprotected, &Value::Uint(COSE_IV)) {
+        Some(Value::Bytes(nonce)) if nonce.len() == NONCE_LEN => nonce,
+        _ => return Err("Backup has no valid IV".to_string()),
+    };
+    let ciphertext = into_bytes(ciphertext)?;
+
+    let mut key = pbkdf2(crypto, passphrase, salt, iterations)?;
+    let opened =
+        crypto.aead_decrypt(AeadType::ChaCha20Poly1305, &key, &ciphertext, nonce, &enc_structure(&protected_bytes));
+    key.zeroize();
+    let mut plaintext = opened.map_err(|_| "Failed to decrypt backup: wrong passphrase or corrupted backup".to_string())?;
+
+    let decoded = decode(&plaintext).and_then(decode_payload);
+    plaintext.zeroize();
+    decoded
+}
+
+/// COSE Enc_structure for a COSE_Encrypt0 message without external AAD.
+fn enc_structure(protected: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_head(&mut out, MAJOR_ARRAY, 3);
+    write_text(&mut out, "Encrypt0");
+    write_bytes(&mut out, protected);
+    write_bytes(&mut out, &[]);
+    out
+}
+
+fn encode_payload(payload: &InterchangePayload) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_head(&mut out, MAJOR_MAP, 4);
+    write_text(&mut out, "version");
+    write_head(&mut out, MAJOR_UINT, PAYLOAD_VERSION);
+    write_text(&mut out, "created_at");
+    write_head(&mut out, MAJOR_UINT, payload.created_at);
+    write_text(&mut out, "global");
+    write_entries(&mut out, &payload.global);
+    write_text(&mut out, "groups");
+    write_head(&mut out, MAJOR_ARRAY, payload.groups.len() as u64);
+    for (group_id, entries) in &payload.groups {
+        write_head(&mut out, MAJOR_MAP, 2);
+        write_text(&mut out, "id");
+        write_bytes(&mut out, group_id);
+        write_text(&mut out, "entries");
+        write_entries(&mut out, entries);
+    }
+    out
+}
+
+fn decode_payload(value: Value) -> Result<InterchangePayload, String> {
+    let mut payload = into_map(value)?;
+    if take_field(&mut payload, "version")? != Value::Uint(PAYLOAD_VERSION) {
+        return Err("Unsupported backup version".to_string());
+    }
+    let created_at = match take_field(&mut payload, "created_at")? {
+        Value::Uint(created_at) => created_at,
+        _ => return Err("Malformed backup: created_at".to_string()),
+    };
+    let global = decode_entries(take_field(&mut payload, "global")?)?;
+    let mut groups = BTreeMap::new();
+    for group in into_array(take_field(&mut payload, "groups")?)? {
+        let mut group = into_map(group)?;
+        let id = into_bytes(take_field(&mut group, "id")?)?;
+        let entries = decode_entries(take_field(&mut group, "entries")?)?;
+        if groups.insert(id, entries).is_some() {
+            return Err("Malformed backup: duplicate group".to_string());
+        }
+    }
+    Ok(InterchangePayload { created_at, global, groups })
+}
+
+fn decode_entries(value: Value) -> Result<Vec<(Vec<u8>, Vec<u8>)>, String> {
+    into_array(value)?
+        .into_iter()
+        .map(|entry| match <[Value; 2]>::try_from(into_array(entry)?) {
+            Ok([key, value]) => Ok((into_bytes(key)?, into_bytes(value)?)),
+            Err(_) => Err("Malformed backup entry".to_string()),
+        })
+        .collect()
+}
+
+fn write_entries(out: &mut Vec<u8>, entries: &[(Vec<u8>, Vec<u8>)]) {
+    write_head(out, MAJOR_ARRAY, entries.len() as u64);
+    for (key, value) in entries {
+        write_head(out, MAJOR_ARRAY, 2);
+        write_bytes(out, key);
+        write_bytes(out, value);
+    }
+}
+
+// ─── Minimal CBOR (RFC 8949) ───
+
+#[derive(PartialEq)]
+enum Value {
+    Uint(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    Tag(u64, Box<Value>),
+}
+
+fn write_head(out: &mut Vec<u8>, major: u8, n: u64) {
+    let major = major << 5;
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if n <= u8::MAX as u64 {
+        out.push(major | 24);
+        out.push(n as u8);
+    } else if n <= u16::MAX as u64 {
+        out.push(major | 25);
+        out.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= u32::MAX as u64 {
+        out.push(major | 26);
+        out.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn write_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_head(out, MAJOR_BYTES, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+fn write_text(out: &mut Vec<u8>, text: &str) {
+    write_head(out, MAJOR_TEXT, text.len() as u64);
+    out.extend_from_slice(text.as_bytes());
+}
+
+/// Decode exactly one CBOR item.
+fn decode(input: &[u8]) -> Result<Value, String> {
+    let (value, rest) = decode_item(input, 0)?;
+    if !rest.is_empty() {
+        return Err("Trailing bytes after CBOR item".to_string());
+    }
+    Ok(value)
+}
+
+fn decode_item(input: &[u8], depth: usize) -> Result<(Value, &[u8]), String> {
+    if depth > MAX_DEPTH {
+        return Err("CBOR nesting too deep".to_string());
+    }
+    let (&initial, rest) = input.split_first().ok_or_else(|| "Truncated CBOR item".to_string())?;
+    let (n, mut rest) = match initial & 0x1f {
+        info @ 0..=23 => (info as u64, rest),
+        24 => take(rest, 1).map(|(b, rest)| (b[0] as u64, rest))?,
+        25 => take(rest, 2).map(|(b, rest)| (u16::from_be_bytes([b[0], b[1]]) as u64, rest))?,
+        26 => take(rest, 4).map(|(b, rest)| (u32::from_be_bytes(b.try_into().expect("4 bytes")) as u64, rest))?,
+        27 => take(rest, 8).map(|(b, rest)| (u64::from_be_bytes(b.try_into().expect("8 bytes")), rest))?,
+        _ => return Err("Unsupported CBOR length encoding".to_string()),
+    };
+    let value = match initial >> 5 {
+        MAJOR_UINT => Value::Uint(n),
+        MAJOR_BYTES => {
+            let (bytes, after) = take(rest, n)?;
+            rest = after;
+            Value::Bytes(bytes.to_vec())
+        }
+        MAJOR_TEXT => {
+            let (bytes, after) = take(rest, n)?;
+            rest = after;
+            Value::Text(String::from_utf8(bytes.to_vec()).map_err(|_| "Invalid UTF-8 in CBOR text".to_string())?)
+        }
+        MAJOR_ARRAY => {
+            // Every item takes at least one byte, which bounds the allocation.
+            let mut items = Vec::with_capacity((n as usize).min(rest.len()));
+            for _ in 0..n {
+                let (item, after) = decode_item(rest, depth + 1)?;
+                items.push(item);
+                rest = after;
+            }
+            Value::Array(items)
+        }
+        MAJOR_MAP => {
+            let mut pairs = Vec::with_capacity((n as usize).min(rest.len() / 2));
+            for _ in 0..n {
+                let (key, after) = decode_item(rest, depth + 1)?;
+                let (value, after) = decode_item(after, depth + 1)?;
+                if pairs.iter().any(|(existing, _)| *existing == key) {
+                    return Err("Duplicate CBOR map key".to_string());
+                }
+                pairs.push((key, value));
+                rest = after;
+            }
+            Value::Map(pairs)
+        }
+        MAJOR_TAG => {
+            let (item, after) = decode_item(rest, depth + 1)?;
+            rest = after;
+            Value::Tag(n, Box::new(item))
+        }
+        _ => return Err("Unsupported CBOR item".to_string()),
+    };
+    Ok((value, rest))
+}
+
+fn take(input: &[u8], n: u64) -> Result<(&[u8], &[u8]), String> {
+    if n > input.len() as u64 {
+        return Err("Truncated CBOR item".to_string());
+    }
+    Ok(input.split_at(n as usize))
+}
+
+fn into_bytes(value: Value) -> Result<Vec<u8>, String> {
+    match value {
+        Value::Bytes(bytes) => Ok(bytes),
+        _ => Err("Malformed backup: expected a byte string".to_string()),
+    }
+}
+
+fn into_array(value: Value) -> Result<Vec<Value>, String> {
+    match value {
+        Value::Array(items) => Ok(items),
+        _ => Err("Malformed backup: expected an array".to_string()),
+    }
+}
+
+fn into_map(value: Value) -> Result<Vec<(Value, Value)>, String> {
+    match value {
+        Value::Map(pairs) => Ok(pairs),
+        _ => Err("Malformed backup: expected a map".to_string()),
+    }
+}
+
+fn field<'a>(map: &'a [(Value, Value)], label: &Value) -> Option<&'a Value> {
+    map.iter().find(|(key, _)| key == label).map(|(_, value)| value)
+}
+
+fn take_field(map: &mut Vec<(Value, Value)>, label: &str) -> Result<Value, String> {
+    let index = map
+        .iter()
+        .position(|(key, _)| matches!(key, Value::Text(text) if text == label))
+        .ok_or_else(|| format!("Malformed backup: missing {}", label))?;
+    Ok(map.swap_remove(index).1)
+}
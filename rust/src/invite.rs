@@ -0,0 +1,107 @@
+//! Compact, signed invitation payloads for joining a group by external commit.
+//!
+//! Wire format (TLS presentation language, RFC 9420 §2.1):
+//!
+//! ```text
+//! struct {
+//!     uint8 version = 1;
+//!     uint64 not_after;                  // Unix seconds
+//!     opaque inviter_key<V>;             // inviter's signature public key
+//!     opaque group_info<V>;              // MLSMessage containing a GroupInfo
+//!     optional<opaque ratchet_tree<V>>;
+//!     opaque signature<V>;               // SignWithLabel(., "InvitePayload", tbs)
+//! } InvitePayload;
+//! ```
+//!
+//! `tbs` is everything before the signature.
+
+use openmls::prelude::tls_codec::{DeserializeBytes, Serialize, VLBytes};
+
+const INVITE_VERSION: u8 = 1;
+const INVITE_SIGN_LABEL: &[u8] = b"MLS 1.0 InvitePayload";
+
+pub(crate) struct InvitePayload {
+    pub not_after: u64,
+    pub inviter_key: Vec<u8>,
+    pub group_info: Vec<u8>,
+    pub ratchet_tree: Option<Vec<u8>>,
+}
+
+impl InvitePayload {
+    /// The bytes covered by the signature.
+    pub fn tbs(&self) -> Result<Vec<u8>, String> {
+        let mut out = vec![INVITE_VERSION];
+        out.extend_from_slice(&self.not_after.to_be_bytes());
+        write_vl(&mut out, &self.inviter_key)?;
+        write_vl(&mut out, &self.group_info)?;
+        match &self.ratchet_tree {
+            Some(tree) => {
+                out.push(1);
+                write_vl(&mut out, tree)?;
+            }
+            None => out.push(0),
+        }
+        Ok(out)
+    }
+
+    /// Serialize with the given signature over `tbs()`.
+    pub fn encode(&self, signature: &[u8]) -> Result<Vec<u8>, String> {
+        let mut out = self.tbs()?;
+        write_vl(&mut out, signature)?;
+        Ok(out)
+    }
+
+    /// Parse an encoded payload into its fields and signature.
+    pub fn decode(bytes: &[u8]) -> Result<(InvitePayload, Vec<u8>), String> {
+        let (version, rest) = bytes.split_first().ok_or_else(|| "Truncated invite payload".to_string())?;
+        if *version != INVITE_VERSION {
+            return Err(format!("Unsupported invite payload version {}", version));
+        }
+        if rest.len() < 8 {
+            return Err("Truncated invite payload".to_string());
+        }
+        let (not_after, rest) = rest.split_at(8);
+        let (inviter_key, rest) = read_vl(rest)?;
+        let (group_info, rest) = read_vl(rest)?;
+        let (present, rest) = rest.split_first().ok_or_else(|| "Truncated invite payload".to_string())?;
+        let (ratchet_tree, rest) = match present {
+            0 => (None, rest),
+            1 => {
+                let (tree, rest) = read_vl(rest)?;
+                (Some(tree.as_slice().to_vec()), rest)
+            }
+            _ => return Err("Invalid optional ratchet tree marker".to_string()),
+        };
+        let (signature, rest) = read_vl(rest)?;
+        if !rest.is_empty() {
+            return Err("Trailing bytes after invite payload".to_string());
+        }
+
+        let payload = InvitePayload {
+            not_after: u64::from_be_bytes(not_after.try_into().expect("split_at(8)")),
+            inviter_key: inviter_key.as_slice().to_vec(),
+            group_info: group_info.as_slice().to_vec(),
+            ratchet_tree,
+        };
+        Ok((payload, signature.as_slice().to_vec()))
+    }
+}
+
+/// SignContent for `tbs` (RFC 9420 §5.1.2), labelled for invite payloads.
+pub(crate) fn sign_content(tbs: &[u8]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    write_vl(&mut out, INVITE_SIGN_LABEL)?;
+    write_vl(&mut out, tbs)?;
+    Ok(out)
+}
+
+fn write_vl(out: &mut Vec<u8>, bytes: &[u8]) -> Result<(), String> {
+    VLBytes::new(bytes.to_vec())
+        .tls_serialize(out)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to encode invite payload: {}", e))
+}
+
+fn read_vl(input: &[u8]) -> Result<(VLBytes, &[u8]), String> {
+    VLBytes::tls_deserialize_bytes(input).map_err(|e| format!("Malformed invite payload: {}", e))
+}
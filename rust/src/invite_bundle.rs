@@ -0,0 +1,81 @@
+//! Containers inviting one user to several groups at once.
+//!
+//! Wire format (TLS presentation language, RFC 9420 §2.1):
+//!
+//! ```text
+//! struct {
+//!     opaque group_id<V>;
+//!     opaque welcome<V>;                 // MLSMessage containing a Welcome
+//!     optional<opaque ratchet_tree<V>>;
+//! } BundledInvite;
+//!
+//! struct {
+//!     uint8 version = 1;
+//!     BundledInvite invites[];           // until the end of the bundle
+//! } InviteBundle;
+//! ```
+
+use openmls::prelude::tls_codec::{DeserializeBytes, Serialize, VLBytes};
+
+const INVITE_BUNDLE_VERSION: u8 = 1;
+
+pub(crate) struct BundledInvite {
+    pub group_id: Vec<u8>,
+    pub welcome: Vec<u8>,
+    pub ratchet_tree: Option<Vec<u8>>,
+}
+
+pub(crate) fn encode(invites: &[BundledInvite]) -> Result<Vec<u8>, String> {
+    let mut out = vec![INVITE_BUNDLE_VERSION];
+    for invite in invites {
+        write_vl(&mut out, &invite.group_id)?;
+        write_vl(&mut out, &invite.welcome)?;
+        match &invite.ratchet_tree {
+            Some(tree) => {
+                out.push(1);
+                write_vl(&mut out, tree)?;
+            }
+            None => out.push(0),
+        }
+    }
+    Ok(out)
+}
+
+pub(crate) fn decode(bytes: &[u8]) -> Result<Vec<BundledInvite>, String> {
+    let (version, mut rest) = bytes.split_first().ok_or_else(|| "Truncated invite bundle".to_string())?;
+    if *version != INVITE_BUNDLE_VERSION {
+        return Err(format!("Unsupported invite bundle version {}", version));
+    }
+    let mut invites = Vec::new();
+    while !rest.is_empty() {
+        let (group_id, tail) = read_vl(rest)?;
+        let (welcome, tail) = read_vl(tail)?;
+        let (present, tail) = tail.split_first().ok_or_else(|| "Truncated invite bundle".to_string())?;
+        let (ratchet_tree, tail) = match present {
+            0 => (None, tail),
+            1 => {
+                let (tree, tail) = read_vl(tail)?;
+                (Some(tree.as_slice().to_vec()), tail)
+            }
+            _ => return Err("Invalid optional ratchet tree marker".to_string()),
+        };
+        invites.push(BundledInvite {
+            group_id: group_id.as_slice().to_vec(),
+            welcome: welcome.as_slice().to_vec(),
+            ratchet_tree,
+        });
+        rest = tail;
+    }
+    Ok(invites)
+}
+
+fn write_vl(out: &mut Vec<u8>, bytes: &[u8]) -> Result<(), String> {
+    VLBytes::new(bytes.to_vec())
+        .tls_serialize(out)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to encode invite bundle: {}", e))
+}
+
+fn read_vl(input: &[u8]) -> Result<(VLBytes, &[u8]), String> {
+    VLBytes::tls_deserialize_bytes(input).map_err(|e| format!("Malformed invite bundle: {}", e))
+}
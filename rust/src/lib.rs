@@ -4,12 +4,27 @@
 
 #![allow(dead_code)]
 
+mod attachment;
+mod audit_log;
+mod backend_migration;
+mod backup;
+mod compression;
 mod encrypted_db;
+mod group_export;
 mod hybrid_crypto;
+mod interchange;
+mod invite;
+mod invite_bundle;
+mod public_state;
+mod redact;
+mod sandbox;
 mod snapshot_storage;
 mod frb_generated;
+mod tree_view;
 mod utils;
 
 pub mod api;
+pub mod storage_backend;
 
 pub use utils::current_time;
+pub(crate) use utils::constant_time_eq;
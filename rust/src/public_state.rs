@@ -0,0 +1,56 @@
+//! Public-only snapshot of a group, for delivery services mirroring group state.
+//!
+//! Wire format (TLS presentation language, RFC 9420 §2.1):
+//!
+//! ```text
+//! struct {
+//!     uint8 version = 1;
+//!     GroupContext group_context;        // includes the group context extensions
+//!     MAC confirmation_tag;
+//!     optional<Node> ratchet_tree<V>;    // as exported by the group
+//! } PublicGroupState;
+//! ```
+//!
+//! Every field has a single TLS encoding, so two members exporting the same
+//! epoch produce identical bytes.
+
+use openmls::prelude::tls_codec::{DeserializeBytes, VLBytes};
+use openmls::prelude::GroupContext;
+
+const PUBLIC_STATE_VERSION: u8 = 1;
+
+pub(crate) struct PublicState {
+    pub group_context: GroupContext,
+    /// TLS-serialized `ConfirmationTag`.
+    pub confirmation_tag: Vec<u8>,
+    /// TLS-serialized ratchet tree.
+    pub ratchet_tree: Vec<u8>,
+}
+
+/// Concatenate already TLS-serialized parts behind the version header.
+pub(crate) fn encode(group_context: &[u8], confirmation_tag: &[u8], ratchet_tree: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(1 + group_context.len() + confirmation_tag.len() + ratchet_tree.len());
+    out.push(PUBLIC_STATE_VERSION);
+    out.extend_from_slice(group_context);
+    out.extend_from_slice(confirmation_tag);
+    out.extend_from_slice(ratchet_tree);
+    out
+}
+
+pub(crate) fn decode(bytes: &[u8]) -> Result<PublicState, String> {
+    let (version, rest) = bytes.split_first().ok_or_else(|| "Truncated public state".to_string())?;
+    if *version != PUBLIC_STATE_VERSION {
+        return Err(format!("Unsupported public state version {}", version));
+    }
+    let (group_context, rest) = GroupContext::tls_deserialize_bytes(rest)
+        .map_err(|e| format!("Malformed public state group context: {}", e))?;
+    let (_, tree) = VLBytes::tls_deserialize_bytes(rest)
+        .map_err(|e| format!("Malformed public state confirmation tag: {}", e))?;
+    let confirmation_tag = rest[..rest.len() - tree.len()].to_vec();
+    let (_, trailing) = VLBytes::tls_deserialize_bytes(tree)
+        .map_err(|e| format!("Malformed public state ratchet tree: {}", e))?;
+    if !trailing.is_empty() {
+        return Err("Malformed public state: trailing bytes".to_string());
+    }
+    Ok(PublicState { group_context, confirmation_tag, ratchet_tree: tree.to_vec() })
+}
@@ -0,0 +1,178 @@
+//! Redaction of sensitive values in log messages.
+//!
+//! The crate logs only through [`log_warn!`] (`log::warn!` and friends are
+//! disallowed in `clippy.toml`), which passes every message through the
+//! process-wide policy (see `set_log_redaction`) before it reaches the
+//! logger: byte strings embedded in the message, such as the group ids and
+//! credentials error messages carry, are redacted whatever their source.
+//! Call sites that log such a value themselves wrap it in a [`Sensitive`]
+//! value to get a correlatable form instead. Message plaintext and AAD are
+//! only ever logged as their length, whatever the policy.
+
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use openmls_rust_crypto::RustCrypto;
+use openmls_traits::crypto::OpenMlsCrypto;
+use openmls_traits::random::OpenMlsRand;
+use openmls_traits::types::HashType;
+
+use crate::api::init::MlsLogRedaction;
+
+const OFF: u8 = 0;
+const STANDARD: u8 = 1;
+const STRICT: u8 = 2;
+
+/// Debug builds log values in full by default; release builds hash and
+/// truncate them.
+const DEFAULT_POLICY: u8 = if cfg!(debug_assertions) { OFF } else { STANDARD };
+
+static POLICY: AtomicU8 = AtomicU8::new(DEFAULT_POLICY);
+
+/// Hex digits in a row from which a message is taken to embed a byte
+/// string: 16 bytes, longer than the hash a `Sensitive` group id shows.
+const MIN_HEX_RUN: usize = 32;
+/// Elements of a `Debug`-formatted byte list from which it is redacted.
+const MIN_BYTE_LIST: usize = 8;
+
+/// Bytes of a credential kept by the standard policy.
+const CREDENTIAL_PREFIX_LEN: usize = 4;
+/// Bytes of the salted group id hash shown in logs.
+const GROUP_ID_HASH_LEN: usize = 8;
+
+/// Set the policy. Release builds never log values in full, so `Off` is
+/// raised to `Standard` there.
+pub(crate) fn set_policy(policy: MlsLogRedaction) {
+    let value = match policy {
+        MlsLogRedaction::Off if cfg!(debug_assertions) => OFF,
+        MlsLogRedaction::Off | MlsLogRedaction::Standard => STANDARD,
+        MlsLogRedaction::Strict => STRICT,
+    };
+    POLICY.store(value, Ordering::Relaxed);
+}
+
+pub(crate) fn policy() -> MlsLogRedaction {
+    match POLICY.load(Ordering::Relaxed) {
+        OFF => MlsLogRedaction::Off,
+        STANDARD => MlsLogRedaction::Standard,
+        _ => MlsLogRedaction::Strict,
+    }
+}
+
+/// A sensitive value to include in a log message.
+pub(crate) enum Sensitive<'a> {
+    /// Shown as a hash salted per process, so log lines of one run can be
+    /// correlated without revealing the id.
+    GroupId(&'a [u8]),
+    /// Truncated, or with the strict policy reduced to its length.
+    Credential(&'a [u8]),
+    /// Message plaintext or AAD: only the length is ever shown.
+    Content(&'a [u8]),
+}
+
+impl fmt::Display for Sensitive<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let policy = POLICY.load(Ordering::Relaxed);
+        match *self {
+            Sensitive::GroupId(id) if policy == OFF => write!(f, "{}", hex(id)),
+            Sensitive::GroupId(id) => write!(f, "group#{}", hex(&salted_hash(id))),
+            Sensitive::Credential(cred) if policy == OFF => write!(f, "{}", hex(cred)),
+            Sensitive::Credential(cred) if policy == STANDARD => {
+                let prefix = &cred[..cred.len().min(CREDENTIAL_PREFIX_LEN)];
+                write!(f, "{}…({} bytes)", hex(prefix), cred.len())
+            }
+            Sensitive::Credential(cred) => write!(f, "<credential, {} bytes>", cred.len()),
+            Sensitive::Content(content) => write!(f, "<redacted, {} bytes>", content.len()),
+        }
+    }
+}
+
+fn salted_hash(bytes: &[u8]) -> Vec<u8> {
+    static SALT: std::sync::OnceLock<(RustCrypto, Vec<u8>)> = std::sync::OnceLock::new();
+    let (crypto, salt) = SALT.get_or_init(|| {
+        let crypto = RustCrypto::default();
+        let salt = crypto.random_vec(16).unwrap_or_default();
+        (crypto, salt)
+    });
+    let mut input = salt.clone();
+    input.extend_from_slice(bytes);
+    let mut digest = crypto.hash(HashType::Sha2_256, &input).unwrap_or_default();
+    digest.truncate(GROUP_ID_HASH_LEN);
+    digest
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Log a warning through the redaction policy.
+macro_rules! log_warn {
+    ($($arg:tt)+) => {
+        $crate::redact::emit_warn(module_path!(), format_args!($($arg)+))
+    };
+}
+pub(crate) use log_warn;
+
+#[doc(hidden)]
+#[allow(clippy::disallowed_macros)]
+pub(crate) fn emit_warn(target: &str, message: fmt::Arguments<'_>) {
+    if log::log_enabled!(target: target, log::Level::Warn) {
+        log::warn!(target: target, "{}", scrub(&fmt::format(message)));
+    }
+}
+
+/// `message` with the byte strings it embeds redacted: runs of hex digits
+/// and `Debug`-formatted byte lists. Left as is under the `Off` policy.
+fn scrub(message: &str) -> String {
+    if POLICY.load(Ordering::Relaxed) == OFF {
+        return message.to_string();
+    }
+    let mut out = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(c) = rest.chars().next() {
+        let hex_len = rest.find(|c: char| !c.is_ascii_hexdigit()).unwrap_or(rest.len());
+        if hex_len >= MIN_HEX_RUN {
+            out.push_str(&format!("<redacted, {} bytes>", hex_len / 2));
+            rest = &rest[hex_len..];
+            continue;
+        }
+        if c == '[' {
+            if let Some(end) = rest.find(']') {
+                let elements: Vec<&str> = rest[1..end].split(',').map(str::trim).collect();
+                if elements.len() >= MIN_BYTE_LIST && elements.iter().all(|e| e.parse::<u8>().is_ok()) {
+                    out.push_str(&format!("<redacted, {} bytes>", elements.len()));
+                    rest = &rest[end + 1..];
+                    continue;
+                }
+            }
+        }
+        // Skip a whole short hex run so its tail is not matched on its own
+        let skip = if hex_len > 0 { hex_len } else { c.len_utf8() };
+        out.push_str(&rest[..skip]);
+        rest = &rest[skip..];
+    }
+    out
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scrub_redacts_embedded_byte_strings() {
+        set_policy(MlsLogRedaction::Standard);
+        let group_id = [0xabu8; 16];
+        let message = format!(
+            "Failed on {} ({}): {:?} at epoch 12 [1, 2]",
+            hex(&group_id),
+            Sensitive::GroupId(&group_id),
+            group_id.to_vec()
+        );
+        let scrubbed = scrub(&message);
+        assert!(!scrubbed.contains(&hex(&group_id)));
+        assert!(!scrubbed.contains("171, 171"));
+        assert!(scrubbed.contains(&Sensitive::GroupId(&group_id).to_string()));
+        assert!(scrubbed.contains("<redacted, 16 bytes>"));
+        assert!(scrubbed.ends_with("at epoch 12 [1, 2]"));
+    }
+}
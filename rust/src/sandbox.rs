@@ -0,0 +1,280 @@
+//! Storage behind an engine: the encrypted database, an in-memory sandbox,
+//! a backend registered by a Rust user of this crate (see
+//! `storage_backend`), or two of these during a backend migration (see
+//! `backend_migration`).
+//!
+//! A sandbox (see `MlsEngine::fork_group_sandbox`) starts as a copy of one
+//! group's entries plus the global entries. Engine operations on it behave
+//! exactly as on the database, but nothing is ever written back; dropping
+//! the sandbox discards every change.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use zeroize::Zeroize;
+
+use crate::backend_migration::MigratingStore;
+use crate::encrypted_db::{is_global_key, DbError, DbInfo, EncryptedDb, QuarantinedGroup, StorageUpdates};
+use crate::storage_backend::StorageBackend;
+
+/// In-memory replacement for `EncryptedDb`, keyed like `mls_storage`.
+pub(crate) struct SandboxStore {
+    /// key → (group_id, value); `None` group id = global entry.
+    entries: parking_lot::Mutex<BTreeMap<Vec<u8>, (Option<Vec<u8>>, Vec<u8>)>>,
+}
+
+impl SandboxStore {
+    /// Build a sandbox from `EncryptedDb::load_for_group` output.
+    pub fn from_group_entries(group_id: &[u8], entries: Vec<(Vec<u8>, Vec<u8>)>) -> SandboxStore {
+        let entries = entries
+            .into_iter()
+            .map(|(key, value)| {
+                let gid = if is_global_key(&key) { None } else { Some(group_id.to_vec()) };
+                (key, (gid, value))
+            })
+            .collect();
+        SandboxStore { entries: parking_lot::Mutex::new(entries) }
+    }
+
+    fn load_where(&self, filter: impl Fn(&[u8], Option<&[u8]>) -> bool) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.entries
+            .lock()
+            .iter()
+            .filter(|(key, (gid, _))| filter(key, gid.as_deref()))
+            .map(|(key, (_, value))| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    fn load_global(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.load_where(|_, gid| gid.is_none())
+    }
+
+    fn load_for_group(&self, group_id: &[u8]) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.load_where(|_, gid| gid.is_none_or(|gid| gid == group_id))
+    }
+
+    fn load_all(&self) -> Vec<(Option<Vec<u8>>, Vec<u8>, Vec<u8>)> {
+        let mut all: Vec<_> = self
+            .entries
+            .lock()
+            .iter()
+            .map(|(key, (gid, value))| (gid.clone(), key.clone(), value.clone()))
+            .collect();
+        all.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+        all
+    }
+
+    fn load_page(&self, after: Option<&[u8]>, limit: u32) -> Vec<(Option<Vec<u8>>, Vec<u8>, Vec<u8>)> {
+        let lower = match after {
+            Some(after) => std::ops::Bound::Excluded(after.to_vec()),
+            None => std::ops::Bound::Unbounded,
+        };
+        self.entries
+            .lock()
+            .range((lower, std::ops::Bound::Unbounded))
+            .take(limit as usize)
+            .map(|(key, (gid, value))| (gid.clone(), key.clone(), value.clone()))
+            .collect()
+    }
+
+    fn load_by_label(&self, label: &[u8], group_id: Option<&[u8]>) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.load_where(|key, gid| gid == group_id && key.starts_with(label))
+    }
+
+    fn save_updates_batch(&self, batches: Vec<(StorageUpdates, Option<Vec<u8>>)>) {
+        let mut entries = self.entries.lock();
+        for (mut updates, group_id) in batches {
+            for (key, value) in updates.upserts.drain(..) {
+                let gid = if is_global_key(&key) { None } else { group_id.clone() };
+                if let Some((_, mut old)) = entries.insert(key, (gid, value)) {
+                    old.zeroize();
+                }
+            }
+            for key in &updates.deletes {
+                if let Some((_, mut old)) = entries.remove(key) {
+                    old.zeroize();
+                }
+            }
+        }
+    }
+
+    fn delete_group(&self, group_id: &[u8]) {
+        self.entries.lock().retain(|_, (gid, value)| {
+            let keep = gid.as_deref() != Some(group_id);
+            if !keep {
+                value.zeroize();
+            }
+            keep
+        });
+    }
+}
+
+impl Drop for SandboxStore {
+    fn drop(&mut self) {
+        for (_, value) in self.entries.get_mut().values_mut() {
+            value.zeroize();
+        }
+    }
+}
+
+/// Where an engine reads and writes its state.
+#[derive(Clone)]
+pub(crate) enum EngineStore {
+    Db(Arc<EncryptedDb>),
+    Sandbox(Arc<SandboxStore>),
+    Custom(Arc<dyn StorageBackend>),
+    Migrating(Arc<MigratingStore>),
+}
+
+const NOT_IN_SANDBOX: &str = "Quarantine is not available in a sandbox";
+const NOT_IN_CUSTOM: &str = "Quarantine is not available with a custom storage backend";
+const NOT_WHILE_MIGRATING: &str = "Quarantine is not available during a backend migration";
+
+impl EngineStore {
+    pub async fn load_global(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DbError> {
+        match self {
+            EngineStore::Db(db) => db.load_global().await,
+            EngineStore::Sandbox(sandbox) => Ok(sandbox.load_global()),
+            EngineStore::Custom(backend) => backend.load_global().await.map_err(DbError::Failed),
+            EngineStore::Migrating(migration) => Box::pin(migration.source.load_global()).await,
+        }
+    }
+
+    pub async fn load_for_group(&self, group_id: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DbError> {
+        match self {
+            EngineStore::Db(db) => db.load_for_group(group_id).await,
+            EngineStore::Sandbox(sandbox) => Ok(sandbox.load_for_group(group_id)),
+            EngineStore::Custom(backend) => backend.load_for_group(group_id).await.map_err(DbError::Failed),
+            EngineStore::Migrating(migration) => Box::pin(migration.source.load_for_group(group_id)).await,
+        }
+    }
+
+    pub async fn load_all(&self) -> Result<Vec<(Option<Vec<u8>>, Vec<u8>, Vec<u8>)>, String> {
+        match self {
+            EngineStore::Db(db) => db.load_all().await,
+            EngineStore::Sandbox(sandbox) => Ok(sandbox.load_all()),
+            EngineStore::Custom(backend) => backend.load_all().await,
+            EngineStore::Migrating(migration) => Box::pin(migration.source.load_all()).await,
+        }
+    }
+
+    pub async fn load_page(
+        &self,
+        after: Option<&[u8]>,
+        limit: u32,
+    ) -> Result<Vec<(Option<Vec<u8>>, Vec<u8>, Vec<u8>)>, String> {
+        match self {
+            EngineStore::Db(db) => db.load_page(after, limit).await,
+            EngineStore::Sandbox(sandbox) => Ok(sandbox.load_page(after, limit)),
+            EngineStore::Custom(backend) => backend.load_page(after, limit).await,
+            EngineStore::Migrating(migration) => Box::pin(migration.source.load_page(after, limit)).await,
+        }
+    }
+
+    pub async fn load_by_label(
+        &self,
+        label: &[u8],
+        group_id: Option<&[u8]>,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>, DbError> {
+        match self {
+            EngineStore::Db(db) => db.load_by_label(label, group_id).await,
+            EngineStore::Sandbox(sandbox) => Ok(sandbox.load_by_label(label, group_id)),
+            EngineStore::Custom(backend) => backend.load_by_label(label, group_id).await.map_err(DbError::Failed),
+            EngineStore::Migrating(migration) => Box::pin(migration.source.load_by_label(label, group_id)).await,
+        }
+    }
+
+    pub async fn save_updates(&self, updates: StorageUpdates, group_id: Option<&[u8]>) -> Result<(), DbError> {
+        self.save_updates_batch(vec![(updates, group_id.map(<[u8]>::to_vec))]).await
+    }
+
+    pub async fn save_updates_batch(&self, batches: Vec<(StorageUpdates, Option<Vec<u8>>)>) -> Result<(), DbError> {
+        match self {
+            EngineStore::Db(db) => db.save_updates_batch(batches).await,
+            EngineStore::Sandbox(sandbox) => {
+                sandbox.save_updates_batch(batches);
+                Ok(())
+            }
+            EngineStore::Custom(backend) => backend.save_updates_batch(batches).await.map_err(DbError::Failed),
+            EngineStore::Migrating(migration) => Box::pin(migration.save_updates_batch(batches)).await,
+        }
+    }
+
+    pub async fn delete_group(&self, group_id: &[u8]) -> Result<(), String> {
+        match self {
+            EngineStore::Db(db) => db.delete_group(group_id).await,
+            EngineStore::Sandbox(sandbox) => {
+                sandbox.delete_group(group_id);
+                Ok(())
+            }
+            EngineStore::Custom(backend) => backend.delete_group(group_id).await,
+            EngineStore::Migrating(migration) => Box::pin(migration.delete_group(group_id)).await,
+        }
+    }
+
+    pub async fn info(&self) -> Result<Option<DbInfo>, String> {
+        match self {
+            EngineStore::Db(db) => db.info().await.map(Some),
+            EngineStore::Sandbox(_) | EngineStore::Custom(_) => Ok(None),
+            EngineStore::Migrating(migration) => Box::pin(migration.source.info()).await,
+        }
+    }
+
+    pub fn set_operation_timeout(&self, timeout: Option<std::time::Duration>) -> Result<(), String> {
+        match self {
+            EngineStore::Db(db) => db.set_operation_timeout(timeout),
+            // Sandboxes live in memory and never wait.
+            EngineStore::Sandbox(_) => Ok(()),
+            EngineStore::Custom(backend) => backend.set_operation_timeout(timeout),
+            EngineStore::Migrating(migration) => {
+                migration.source.set_operation_timeout(timeout)?;
+                migration.target.set_operation_timeout(timeout)
+            }
+        }
+    }
+
+    pub async fn quarantine_group(&self, group_id: &[u8], error: &str, quarantined_at: u64) -> Result<(), String> {
+        match self {
+            EngineStore::Db(db) => db.quarantine_group(group_id, error, quarantined_at).await,
+            EngineStore::Sandbox(_) => Err(NOT_IN_SANDBOX.to_string()),
+            EngineStore::Custom(_) => Err(NOT_IN_CUSTOM.to_string()),
+            EngineStore::Migrating(_) => Err(NOT_WHILE_MIGRATING.to_string()),
+        }
+    }
+
+    pub async fn quarantine_and_replace(
+        &self,
+        group_id: &[u8],
+        error: &str,
+        quarantined_at: u64,
+        batches: Vec<(StorageUpdates, Option<Vec<u8>>)>,
+    ) -> Result<(), String> {
+        match self {
+            EngineStore::Db(db) => db.quarantine_and_replace(group_id, error, quarantined_at, batches).await,
+            EngineStore::Sandbox(_) => Err(NOT_IN_SANDBOX.to_string()),
+            EngineStore::Custom(_) => Err(NOT_IN_CUSTOM.to_string()),
+            EngineStore::Migrating(_) => Err(NOT_WHILE_MIGRATING.to_string()),
+        }
+    }
+
+    pub async fn quarantined_groups(&self) -> Result<Vec<(Vec<u8>, String, u64)>, String> {
+        match self {
+            EngineStore::Db(db) => db.quarantined_groups().await,
+            EngineStore::Sandbox(_) => Err(NOT_IN_SANDBOX.to_string()),
+            EngineStore::Custom(_) => Err(NOT_IN_CUSTOM.to_string()),
+            EngineStore::Migrating(_) => Err(NOT_WHILE_MIGRATING.to_string()),
+        }
+    }
+
+    pub async fn quarantined_group(
+        &self,
+        group_id: &[u8],
+    ) -> Result<Option<QuarantinedGroup>, String> {
+        match self {
+            EngineStore::Db(db) => db.quarantined_group(group_id).await,
+            EngineStore::Sandbox(_) => Err(NOT_IN_SANDBOX.to_string()),
+            EngineStore::Custom(_) => Err(NOT_IN_CUSTOM.to_string()),
+            EngineStore::Migrating(_) => Err(NOT_WHILE_MIGRATING.to_string()),
+        }
+    }
+}
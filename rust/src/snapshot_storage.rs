@@ -27,10 +27,10 @@ pub enum SnapshotStorageError {
 // LABELS (matching MemoryStorage exactly)
 // ═══════════════════════════════════════════════════════════════
 
-const KEY_PACKAGE_LABEL: &[u8] = b"KeyPackage";
+pub(crate) const KEY_PACKAGE_LABEL: &[u8] = b"KeyPackage";
 const PSK_LABEL: &[u8] = b"Psk";
-const ENCRYPTION_KEY_PAIR_LABEL: &[u8] = b"EncryptionKeyPair";
-const SIGNATURE_KEY_PAIR_LABEL: &[u8] = b"SignatureKeyPair";
+pub(crate) const ENCRYPTION_KEY_PAIR_LABEL: &[u8] = b"EncryptionKeyPair";
+pub(crate) const SIGNATURE_KEY_PAIR_LABEL: &[u8] = b"SignatureKeyPair";
 const EPOCH_KEY_PAIRS_LABEL: &[u8] = b"EpochKeyPairs";
 const TREE_LABEL: &[u8] = b"Tree";
 const GROUP_CONTEXT_LABEL: &[u8] = b"GroupContext";
@@ -38,7 +38,7 @@ const INTERIM_TRANSCRIPT_HASH_LABEL: &[u8] = b"InterimTranscriptHash";
 const CONFIRMATION_TAG_LABEL: &[u8] = b"ConfirmationTag";
 const JOIN_CONFIG_LABEL: &[u8] = b"MlsGroupJoinConfig";
 const OWN_LEAF_NODES_LABEL: &[u8] = b"OwnLeafNodes";
-const GROUP_STATE_LABEL: &[u8] = b"GroupState";
+pub(crate) const GROUP_STATE_LABEL: &[u8] = b"GroupState";
 const QUEUED_PROPOSAL_LABEL: &[u8] = b"QueuedProposal";
 const PROPOSAL_QUEUE_REFS_LABEL: &[u8] = b"ProposalQueueRefs";
 const OWN_LEAF_NODE_INDEX_LABEL: &[u8] = b"OwnLeafNodeIndex";
@@ -46,6 +46,87 @@ const EPOCH_SECRETS_LABEL: &[u8] = b"EpochSecrets";
 const RESUMPTION_PSK_STORE_LABEL: &[u8] = b"ResumptionPsk";
 const MESSAGE_SECRETS_LABEL: &[u8] = b"MessageSecrets";
 
+// Engine-level metadata (not OpenMLS state), stored alongside it so updates
+// are persisted in the same transaction as the MLS operation that caused them.
+pub(crate) const APP_GLOBAL_LABEL: &[u8] = b"AppGlobal";
+pub(crate) const APP_GROUP_LABEL: &[u8] = b"AppGroup";
+
+/// Every OpenMLS label, for importing the entries of another storage
+/// provider that uses the MemoryStorage key format.
+pub(crate) const OPENMLS_LABELS: &[&[u8]] = &[
+    KEY_PACKAGE_LABEL,
+    PSK_LABEL,
+    ENCRYPTION_KEY_PAIR_LABEL,
+    SIGNATURE_KEY_PAIR_LABEL,
+    EPOCH_KEY_PAIRS_LABEL,
+    TREE_LABEL,
+    GROUP_CONTEXT_LABEL,
+    INTERIM_TRANSCRIPT_HASH_LABEL,
+    CONFIRMATION_TAG_LABEL,
+    JOIN_CONFIG_LABEL,
+    OWN_LEAF_NODES_LABEL,
+    GROUP_STATE_LABEL,
+    QUEUED_PROPOSAL_LABEL,
+    PROPOSAL_QUEUE_REFS_LABEL,
+    OWN_LEAF_NODE_INDEX_LABEL,
+    EPOCH_SECRETS_LABEL,
+    RESUMPTION_PSK_STORE_LABEL,
+    MESSAGE_SECRETS_LABEL,
+];
+
+/// Group id of an OpenMLS storage key, `None` for global keys.
+///
+/// Group-scoped keys start with the serialized group id, either alone or
+/// as the first element of a composite key. Engine metadata keys are
+/// understood too. Fails for unknown labels and keys written by another
+/// storage version.
+pub(crate) fn storage_key_group_id(key: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    let label = OPENMLS_LABELS
+        .iter()
+        .chain([&APP_GLOBAL_LABEL, &APP_GROUP_LABEL])
+        .filter(|label| key.starts_with(label))
+        .max_by_key(|label| label.len())
+        .ok_or_else(|| "Storage key has an unknown label".to_string())?;
+    if key.len() < label.len() + 2 {
+        return Err("Truncated storage key".to_string());
+    }
+    let (body, version) = key[label.len()..].split_at(key.len() - label.len() - 2);
+    let version = u16::from_be_bytes([version[0], version[1]]);
+    if version != CURRENT_VERSION {
+        return Err(format!("Unsupported storage version {}", version));
+    }
+    if crate::encrypted_db::is_global_key(key) {
+        return Ok(None);
+    }
+    if *label == APP_GROUP_LABEL {
+        let (group_id, _name): (Vec<u8>, String) =
+            serde_json::from_slice(body).map_err(|e| format!("Failed to parse storage key: {}", e))?;
+        return Ok(Some(group_id));
+    }
+
+    let first: serde_json::Value = serde_json::Deserializer::from_slice(body)
+        .into_iter()
+        .next()
+        .ok_or_else(|| "Storage key has no group id".to_string())?
+        .map_err(|e| format!("Failed to parse storage key: {}", e))?;
+    let group_id = match first {
+        serde_json::Value::Array(mut parts) if !parts.is_empty() => parts.swap_remove(0),
+        other => other,
+    };
+    let group_id: openmls::prelude::GroupId =
+        serde_json::from_value(group_id).map_err(|e| format!("Failed to parse storage key group id: {}", e))?;
+    Ok(Some(group_id.as_slice().to_vec()))
+}
+
+/// Storage key of the leaf encryption key pair for `public_key`.
+///
+/// The pair is a global entry, yet a group cannot decrypt commits to its
+/// own leaf without it.
+pub(crate) fn encryption_key_pair_key(public_key: &impl serde::Serialize) -> Result<Vec<u8>, String> {
+    build_key_serde::<{ CURRENT_VERSION }>(ENCRYPTION_KEY_PAIR_LABEL, public_key)
+        .map_err(|e| format!("Failed to build storage key: {}", e))
+}
+
 // ═══════════════════════════════════════════════════════════════
 // SNAPSHOT STORAGE PROVIDER
 // ═══════════════════════════════════════════════════════════════
@@ -110,6 +191,39 @@ impl SnapshotStorageProvider {
 
         StorageUpdates { upserts, deletes }
     }
+
+    /// Take the changes made since the snapshot was loaded or last
+    /// checkpointed, keeping the current state as the new baseline.
+    pub fn checkpoint(&mut self) -> StorageUpdates {
+        let mut upserts = Vec::new();
+        let mut deletes = Vec::new();
+        for (key, value) in &self.current {
+            match self.initial.get(key) {
+                Some(old_value) if old_value == value => {}
+                _ => upserts.push((key.clone(), value.clone())),
+            }
+        }
+        for key in self.initial.keys() {
+            if !self.current.contains_key(key) {
+                deletes.push(key.clone());
+            }
+        }
+        let previous = std::mem::replace(&mut self.initial, self.current.clone());
+        for (_k, v) in previous {
+            let mut v = v;
+            v.zeroize();
+        }
+        StorageUpdates { upserts, deletes }
+    }
+
+    /// Discard the changes made since the last checkpoint.
+    pub fn rollback(&mut self) {
+        let discarded = std::mem::replace(&mut self.current, self.initial.clone());
+        for (_k, v) in discarded {
+            let mut v = v;
+            v.zeroize();
+        }
+    }
 }
 
 // ═══════════════════════════════════════════════════════════════
@@ -273,6 +387,193 @@ impl SnapshotStorageProvider {
     }
 }
 
+// ═══════════════════════════════════════════════════════════════
+// APPLICATION METADATA
+// ═══════════════════════════════════════════════════════════════
+
+impl SnapshotStorageProvider {
+    /// Read a global engine metadata value.
+    pub fn app_global<Val: serde::de::DeserializeOwned>(
+        &self,
+        name: &str,
+    ) -> Result<Option<Val>, SnapshotStorageError> {
+        self.read_val::<{ CURRENT_VERSION }, Val>(APP_GLOBAL_LABEL, &name)
+    }
+
+    /// Write a global engine metadata value.
+    pub fn write_app_global(
+        &mut self,
+        name: &str,
+        value: &impl serde::Serialize,
+    ) -> Result<(), SnapshotStorageError> {
+        self.write_val::<{ CURRENT_VERSION }>(APP_GLOBAL_LABEL, &name, value)
+    }
+
+    /// Delete a global engine metadata value.
+    pub fn delete_app_global(&mut self, name: &str) -> Result<(), SnapshotStorageError> {
+        self.delete_val::<{ CURRENT_VERSION }>(APP_GLOBAL_LABEL, &name)
+    }
+
+    /// Read a group-scoped engine metadata value.
+    pub fn app_group<Val: serde::de::DeserializeOwned>(
+        &self,
+        group_id: &[u8],
+        name: &str,
+    ) -> Result<Option<Val>, SnapshotStorageError> {
+        self.read_val::<{ CURRENT_VERSION }, Val>(APP_GROUP_LABEL, &(group_id, name))
+    }
+
+    /// Write a group-scoped engine metadata value.
+    pub fn write_app_group(
+        &mut self,
+        group_id: &[u8],
+        name: &str,
+        value: &impl serde::Serialize,
+    ) -> Result<(), SnapshotStorageError> {
+        self.write_val::<{ CURRENT_VERSION }>(APP_GROUP_LABEL, &(group_id, name), value)
+    }
+
+    /// Delete a group-scoped engine metadata value.
+    pub fn delete_app_group(&mut self, group_id: &[u8], name: &str) -> Result<(), SnapshotStorageError> {
+        self.delete_val::<{ CURRENT_VERSION }>(APP_GROUP_LABEL, &(group_id, name))
+    }
+
+    /// Whether the state of a group is stored, read from its `GroupState`
+    /// entry alone.
+    pub fn contains_group(&self, group_id: &impl serde::Serialize) -> Result<bool, SnapshotStorageError> {
+        Ok(self.read_val::<{ CURRENT_VERSION }, serde_json::Value>(GROUP_STATE_LABEL, group_id)?.is_some())
+    }
+
+    /// Drop the past-epoch message secrets OpenMLS retains for a group
+    /// (`max_past_epochs`), keeping the current epoch's.
+    ///
+    /// OpenMLS has no API for this, so it empties the past-epoch queue of
+    /// the stored `MessageSecretsStore` (see `past_epoch_queue`).
+    pub fn clear_past_epoch_secrets(
+        &mut self,
+        group_id: &impl serde::Serialize,
+    ) -> Result<(), SnapshotStorageError> {
+        let Some(mut store) = self.read_val::<{ CURRENT_VERSION }, serde_json::Value>(MESSAGE_SECRETS_LABEL, group_id)? else {
+            return Ok(());
+        };
+        past_epoch_queue(&mut store)?.clear();
+        self.write_val::<{ CURRENT_VERSION }>(MESSAGE_SECRETS_LABEL, group_id, &store)
+    }
+
+    /// Epochs whose message secrets OpenMLS still retains for a group
+    /// (`max_past_epochs`), not including the current epoch.
+    pub fn past_epoch_secret_epochs(
+        &self,
+        group_id: &impl serde::Serialize,
+    ) -> Result<Vec<u64>, SnapshotStorageError> {
+        let Some(mut store) = self.read_val::<{ CURRENT_VERSION }, serde_json::Value>(MESSAGE_SECRETS_LABEL, group_id)? else {
+            return Ok(Vec::new());
+        };
+        past_epoch_queue(&mut store)?.iter().map(past_epoch_of).collect()
+    }
+
+    /// Change how many past epochs' message secrets OpenMLS retains for a
+    /// group, dropping the oldest retained epochs beyond `max_epochs`.
+    ///
+    /// The `MessageSecretsStore` copies the limit from the join config when
+    /// it is created, so changing the config alone has no effect; this sets
+    /// the store's own `max_epochs` too.
+    pub fn set_past_epoch_limit(
+        &mut self,
+        group_id: &impl serde::Serialize,
+        max_epochs: usize,
+    ) -> Result<(), SnapshotStorageError> {
+        let Some(mut store) = self.read_val::<{ CURRENT_VERSION }, serde_json::Value>(MESSAGE_SECRETS_LABEL, group_id)? else {
+            return Ok(());
+        };
+        let limit = store
+            .get_mut(MAX_EPOCHS_FIELD)
+            .filter(|value| value.is_u64())
+            .ok_or_else(|| layout_mismatch("MessageSecretsStore", MAX_EPOCHS_FIELD))?;
+        *limit = serde_json::Value::from(max_epochs);
+        // The queue is oldest first: OpenMLS pushes to the back and evicts
+        // from the front.
+        let queue = past_epoch_queue(&mut store)?;
+        let epochs = queue.iter().map(past_epoch_of).collect::<Result<Vec<_>, _>>()?;
+        if !epochs.is_sorted() {
+            return Err(layout_mismatch("MessageSecretsStore", "past_epoch_deque order"));
+        }
+        let excess = queue.len().saturating_sub(max_epochs);
+        queue.drain(..excess);
+        self.write_val::<{ CURRENT_VERSION }>(MESSAGE_SECRETS_LABEL, group_id, &store)
+    }
+
+    /// Drop all resumption PSKs of a group except the newest.
+    ///
+    /// Edits the stored `ResumptionPskStore`: its `resumption_psk` list of
+    /// `(epoch, secret)` pairs is cut down to the newest pair and the ring
+    /// buffer `cursor` is moved past it.
+    pub fn keep_latest_resumption_psk(
+        &mut self,
+        group_id: &impl serde::Serialize,
+    ) -> Result<(), SnapshotStorageError> {
+        let Some(mut store) = self.read_val::<{ CURRENT_VERSION }, serde_json::Value>(RESUMPTION_PSK_STORE_LABEL, group_id)? else {
+            return Ok(());
+        };
+        let entries = store
+            .get_mut(RESUMPTION_PSKS_FIELD)
+            .and_then(serde_json::Value::as_array_mut)
+            .ok_or_else(|| layout_mismatch("ResumptionPskStore", RESUMPTION_PSKS_FIELD))?;
+        let mut latest: Option<(u64, serde_json::Value)> = None;
+        for entry in entries.drain(..) {
+            let epoch = entry
+                .get(0)
+                .and_then(serde_json::Value::as_u64)
+                .ok_or_else(|| layout_mismatch("ResumptionPskStore", "resumption_psk[].0"))?;
+            if latest.as_ref().is_none_or(|(newest, _)| epoch > *newest) {
+                latest = Some((epoch, entry));
+            }
+        }
+        entries.extend(latest.map(|(_, entry)| entry));
+        let kept = entries.len();
+        let cursor = store
+            .get_mut(RESUMPTION_CURSOR_FIELD)
+            .filter(|value| value.is_u64())
+            .ok_or_else(|| layout_mismatch("ResumptionPskStore", RESUMPTION_CURSOR_FIELD))?;
+        *cursor = serde_json::Value::from(kept);
+        self.write_val::<{ CURRENT_VERSION }>(RESUMPTION_PSK_STORE_LABEL, group_id, &store)
+    }
+}
+
+// Fields of the OpenMLS structures edited above, as serialized by OpenMLS
+// 0.8. The edits check every path they touch and fail on any other layout
+// rather than guess; the tests below pin them against real group state.
+
+/// `MessageSecretsStore.max_epochs`: the retention limit.
+const MAX_EPOCHS_FIELD: &str = "max_epochs";
+/// `MessageSecretsStore.past_epoch_deque`: the `{ epoch, .. }` entries of
+/// the retained past epochs, oldest first.
+const PAST_EPOCHS_FIELD: &str = "past_epoch_deque";
+/// `ResumptionPskStore.resumption_psk`: `(epoch, secret)` pairs.
+const RESUMPTION_PSKS_FIELD: &str = "resumption_psk";
+/// `ResumptionPskStore.cursor`: next slot of the ring buffer.
+const RESUMPTION_CURSOR_FIELD: &str = "cursor";
+
+fn layout_mismatch(structure: &str, path: &str) -> SnapshotStorageError {
+    SnapshotStorageError::Serialization(format!("Unexpected OpenMLS {} layout at `{}`", structure, path))
+}
+
+/// The past-epoch queue of a serialized `MessageSecretsStore`.
+fn past_epoch_queue(store: &mut serde_json::Value) -> Result<&mut Vec<serde_json::Value>, SnapshotStorageError> {
+    store
+        .get_mut(PAST_EPOCHS_FIELD)
+        .and_then(serde_json::Value::as_array_mut)
+        .ok_or_else(|| layout_mismatch("MessageSecretsStore", PAST_EPOCHS_FIELD))
+}
+
+/// The epoch of an entry of the past-epoch queue.
+fn past_epoch_of(entry: &serde_json::Value) -> Result<u64, SnapshotStorageError> {
+    entry
+        .get("epoch")
+        .and_then(serde_json::Value::as_u64)
+        .ok_or_else(|| layout_mismatch("MessageSecretsStore", "past_epoch_deque[].epoch"))
+}
+
 // ═══════════════════════════════════════════════════════════════
 // STORAGE PROVIDER TRAIT IMPLEMENTATION
 // ═══════════════════════════════════════════════════════════════
@@ -957,16 +1258,26 @@ impl StorageProvider<{ CURRENT_VERSION }> for SnapshotStorageProvider {
 // ═══════════════════════════════════════════════════════════════
 
 pub struct SnapshotOpenMlsProvider {
-    crypto: crate::hybrid_crypto::HybridCrypto,
+    crypto: std::sync::Arc<crate::hybrid_crypto::HybridCrypto>,
     storage: SnapshotStorageProvider,
 }
 
 impl SnapshotOpenMlsProvider {
+    /// Create a provider with its own, freshly constructed crypto provider.
     pub fn new(storage: SnapshotStorageProvider) -> Self {
-        Self {
-            crypto: crate::hybrid_crypto::HybridCrypto::new(),
-            storage,
-        }
+        Self::with_crypto(std::sync::Arc::new(crate::hybrid_crypto::HybridCrypto::new()), storage)
+    }
+
+    /// Create a provider that reuses a long-lived crypto provider (one per
+    /// engine) instead of constructing a new one per operation.
+    pub fn with_crypto(crypto: std::sync::Arc<crate::hybrid_crypto::HybridCrypto>, storage: SnapshotStorageProvider) -> Self {
+        Self { crypto, storage }
+    }
+
+    /// Mutable access to the storage, for engine metadata writes that don't
+    /// go through the OpenMLS `StorageProvider` trait.
+    pub fn storage_mut(&mut self) -> &mut SnapshotStorageProvider {
+        &mut self.storage
     }
 
     /// Extract the storage provider for diffing.
@@ -992,3 +1303,109 @@ impl OpenMlsProvider for SnapshotOpenMlsProvider {
         &self.crypto
     }
 }
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use openmls::prelude::*;
+    use openmls_basic_credential::SignatureKeyPair;
+
+    const CS: Ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+
+    /// A single-member group that has advanced `epochs` times, retaining
+    /// three past epochs and four resumption PSKs.
+    fn advanced_group(epochs: usize) -> (SnapshotOpenMlsProvider, MlsGroup) {
+        let provider = SnapshotOpenMlsProvider::new(SnapshotStorageProvider::from_entries(vec![]));
+        let signer = SignatureKeyPair::new(CS.signature_algorithm()).expect("signature keypair gen");
+        signer.store(provider.storage()).expect("store signer");
+        let cwk = CredentialWithKey {
+            credential: BasicCredential::new(b"alice".to_vec()).into(),
+            signature_key: signer.public().into(),
+        };
+        let mut group = MlsGroup::builder()
+            .ciphersuite(CS)
+            .max_past_epochs(3)
+            .number_of_resumption_psks(4)
+            .build(&provider, &signer, cwk)
+            .expect("create group");
+        for _ in 0..epochs {
+            group
+                .self_update(&provider, &signer, LeafNodeParameters::default())
+                .expect("self update");
+            group.merge_pending_commit(&provider).expect("merge commit");
+        }
+        (provider, group)
+    }
+
+    fn reload(provider: &SnapshotOpenMlsProvider, group: &MlsGroup) -> MlsGroup {
+        MlsGroup::load(provider.storage(), group.group_id())
+            .expect("load group")
+            .expect("group stored")
+    }
+
+    #[test]
+    fn past_epoch_secret_epochs_reads_real_group_state() {
+        let (mut provider, group) = advanced_group(5);
+        let group_id = group.group_id().clone();
+
+        assert_eq!(provider.storage().past_epoch_secret_epochs(&group_id).unwrap(), vec![2, 3, 4]);
+
+        provider.storage_mut().clear_past_epoch_secrets(&group_id).unwrap();
+        assert!(provider.storage().past_epoch_secret_epochs(&group_id).unwrap().is_empty());
+        reload(&provider, &group);
+    }
+
+    #[test]
+    fn set_past_epoch_limit_trims_real_group_state() {
+        let (mut provider, group) = advanced_group(5);
+        let group_id = group.group_id().clone();
+
+        provider.storage_mut().set_past_epoch_limit(&group_id, 1).unwrap();
+        assert_eq!(provider.storage().past_epoch_secret_epochs(&group_id).unwrap(), vec![4]);
+        let store: serde_json::Value = provider
+            .storage()
+            .read_val::<{ CURRENT_VERSION }, _>(MESSAGE_SECRETS_LABEL, &group_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(store[MAX_EPOCHS_FIELD], 1);
+        reload(&provider, &group);
+    }
+
+    #[test]
+    fn keep_latest_resumption_psk_matches_real_group_state() {
+        let (mut provider, group) = advanced_group(5);
+        let group_id = group.group_id().clone();
+
+        provider.storage_mut().keep_latest_resumption_psk(&group_id).unwrap();
+        let store: serde_json::Value = provider
+            .storage()
+            .read_val::<{ CURRENT_VERSION }, _>(RESUMPTION_PSK_STORE_LABEL, &group_id)
+            .unwrap()
+            .unwrap();
+        let entries = store[RESUMPTION_PSKS_FIELD].as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0][0], 5);
+        assert_eq!(store[RESUMPTION_CURSOR_FIELD], 1);
+        reload(&provider, &group);
+    }
+
+    #[test]
+    fn edits_reject_an_unknown_layout() {
+        let (mut provider, group) = advanced_group(2);
+        let group_id = group.group_id().clone();
+        let bogus = serde_json::json!({ "max_epochs": 3, "past_epochs": [] });
+        provider
+            .storage_mut()
+            .write_val::<{ CURRENT_VERSION }>(MESSAGE_SECRETS_LABEL, &group_id, &bogus)
+            .unwrap();
+        provider
+            .storage_mut()
+            .write_val::<{ CURRENT_VERSION }>(RESUMPTION_PSK_STORE_LABEL, &group_id, &bogus)
+            .unwrap();
+
+        assert!(provider.storage().past_epoch_secret_epochs(&group_id).is_err());
+        assert!(provider.storage_mut().set_past_epoch_limit(&group_id, 1).is_err());
+        assert!(provider.storage_mut().clear_past_epoch_secrets(&group_id).is_err());
+        assert!(provider.storage_mut().keep_latest_resumption_psk(&group_id).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+//! Pluggable storage for engines, for Rust users of this crate.
+//!
+//! An engine normally stores its state in `EncryptedDb` (SQLCipher on
+//! native, IndexedDB on web). A downstream crate can instead implement
+//! [`StorageBackend`] (e.g. on RocksDB, sled or a KMS-backed store), register
+//! an instance under a name with [`register_storage_backend`], and open an
+//! engine on it from Dart with `MlsEngine::create_with_backend(name)`.
+//!
+//! Backends store entries exactly as `EncryptedDb` does: a key-value map
+//! where every entry is either global or belongs to one group. Global keys
+//! are recognised with [`is_global_key`]; everything else is stored under
+//! the group id it was written with. Encryption at rest is the backend's
+//! responsibility.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+pub use crate::encrypted_db::{is_global_key, StorageUpdates};
+
+/// Future returned by [`StorageBackend`] methods.
+#[cfg(not(target_arch = "wasm32"))]
+pub type BackendFuture<'a, T> = futures::future::BoxFuture<'a, Result<T, String>>;
+/// Future returned by [`StorageBackend`] methods.
+#[cfg(target_arch = "wasm32")]
+pub type BackendFuture<'a, T> = futures::future::LocalBoxFuture<'a, Result<T, String>>;
+
+/// Key-value storage behind an engine.
+///
+/// Entries are `(key, value)` pairs; `load_all` also returns each entry's
+/// group id (`None` for global entries). Errors are reported as strings and
+/// passed through to the engine's caller unchanged.
+pub trait StorageBackend: Send + Sync {
+    /// All global entries.
+    fn load_global(&self) -> BackendFuture<'_, Vec<(Vec<u8>, Vec<u8>)>>;
+
+    /// All global entries plus the entries of `group_id`.
+    fn load_for_group<'a>(&'a self, group_id: &'a [u8]) -> BackendFuture<'a, Vec<(Vec<u8>, Vec<u8>)>>;
+
+    /// Every entry as `(group_id, key, value)`, ordered by group id, then key.
+    fn load_all(&self) -> BackendFuture<'_, Vec<(Option<Vec<u8>>, Vec<u8>, Vec<u8>)>>;
+
+    /// Up to `limit` entries in key order, starting after the key `after`
+    /// (from the first key for `None`). Defaults to paging through
+    /// `load_all`; override it to avoid loading every entry per page.
+    fn load_page<'a>(
+        &'a self,
+        after: Option<&'a [u8]>,
+        limit: u32,
+    ) -> BackendFuture<'a, Vec<(Option<Vec<u8>>, Vec<u8>, Vec<u8>)>> {
+        Box::pin(async move {
+            let mut all = self.load_all().await?;
+            all.retain(|(_, key, _)| after.is_none_or(|after| key.as_slice() > after));
+            all.sort_by(|a, b| a.1.cmp(&b.1));
+            all.truncate(limit as usize);
+            Ok(all)
+        })
+    }
+
+    /// Entries of `group_id` (global entries for `None`) whose key starts
+    /// with `label`. Defaults to filtering `load_all`.
+    fn load_by_label<'a>(
+        &'a self,
+        label: &'a [u8],
+        group_id: Option<&'a [u8]>,
+    ) -> BackendFuture<'a, Vec<(Vec<u8>, Vec<u8>)>> {
+        Box::pin(async move {
+            Ok(self
+                .load_all()
+                .await?
+                .into_iter()
+                .filter(|(gid, key, _)| gid.as_deref() == group_id && key.starts_with(label))
+                .map(|(_, key, value)| (key, value))
+                .collect())
+        })
+    }
+
+    /// Apply every batch in one transaction: all of them or none. Upserts
+    /// with a global key are stored as global entries whatever the batch's
+    /// group id.
+    fn save_updates_batch(&self, batches: Vec<(StorageUpdates, Option<Vec<u8>>)>) -> BackendFuture<'_, ()>;
+
+    /// Delete every entry of `group_id`.
+    fn delete_group<'a>(&'a self, group_id: &'a [u8]) -> BackendFuture<'a, ()>;
+
+    /// Called once when the engine is closed. Defaults to doing nothing.
+    fn close(&self) -> BackendFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+
+    /// Irrecoverably destroy all data, for `MlsEngine::secure_wipe`.
+    /// Unsupported by default.
+    fn secure_wipe(&self) -> BackendFuture<'_, ()> {
+        Box::pin(async { Err("Secure wipe is not supported by this storage backend".to_string()) })
+    }
+
+    /// Limit how long an operation may wait for the store, for
+    /// `MlsEngine::set_operation_timeout`. Ignored by default.
+    fn set_operation_timeout(&self, _timeout: Option<std::time::Duration>) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+static BACKENDS: parking_lot::Mutex<BTreeMap<String, Arc<dyn StorageBackend>>> =
+    parking_lot::Mutex::new(BTreeMap::new());
+
+/// Make `backend` available to `MlsEngine::create_with_backend(name)`,
+/// replacing any backend registered under the same name.
+pub fn register_storage_backend(name: impl Into<String>, backend: Arc<dyn StorageBackend>) {
+    BACKENDS.lock().insert(name.into(), backend);
+}
+
+/// Remove the backend registered under `name`. Engines already open on it
+/// keep using it. Returns whether a backend was registered.
+pub fn unregister_storage_backend(name: &str) -> bool {
+    BACKENDS.lock().remove(name).is_some()
+}
+
+pub(crate) fn registered_backend(name: &str) -> Option<Arc<dyn StorageBackend>> {
+    BACKENDS.lock().get(name).cloned()
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use crate::api::config::MlsGroupConfig;
+    use crate::api::engine::MlsEngine;
+    use crate::api::types::MlsCiphersuite;
+
+    /// Unencrypted in-memory backend: key → (group id, value).
+    #[derive(Default)]
+    struct MemoryBackend {
+        entries: parking_lot::Mutex<BTreeMap<Vec<u8>, (Option<Vec<u8>>, Vec<u8>)>>,
+    }
+
+    impl MemoryBackend {
+        fn load_where(&self, filter: impl Fn(Option<&[u8]>) -> bool) -> Vec<(Vec<u8>, Vec<u8>)> {
+            self.entries
+                .lock()
+                .iter()
+                .filter(|(_, (gid, _))| filter(gid.as_deref()))
+                .map(|(key, (_, value))| (key.clone(), value.clone()))
+                .collect()
+        }
+    }
+
+    impl StorageBackend for MemoryBackend {
+        fn load_global(&self) -> BackendFuture<'_, Vec<(Vec<u8>, Vec<u8>)>> {
+            Box::pin(async { Ok(self.load_where(|gid| gid.is_none())) })
+        }
+
+        fn load_for_group<'a>(&'a self, group_id: &'a [u8]) -> BackendFuture<'a, Vec<(Vec<u8>, Vec<u8>)>> {
+            Box::pin(async move { Ok(self.load_where(|gid| gid.is_none_or(|gid| gid == group_id))) })
+        }
+
+        fn load_all(&self) -> BackendFuture<'_, Vec<(Option<Vec<u8>>, Vec<u8>, Vec<u8>)>> {
+            Box::pin(async {
+                let mut all: Vec<_> = self
+                    .entries
+                    .lock()
+                    .iter()
+                    .map(|(key, (gid, value))| (gid.clone(), key.clone(), value.clone()))
+                    .collect();
+                all.sort_by(|a, b| (&a.0, &a.1).cmp(&(&b.0, &b.1)));
+                Ok(all)
+            })
+        }
+
+        fn save_updates_batch(&self, batches: Vec<(StorageUpdates, Option<Vec<u8>>)>) -> BackendFuture<'_, ()> {
+            Box::pin(async move {
+                let mut entries = self.entries.lock();
+                for (updates, group_id) in batches {
+                    for (key, value) in updates.upserts {
+                        let gid = if is_global_key(&key) { None } else { group_id.clone() };
+                        entries.insert(key, (gid, value));
+                    }
+                    for key in &updates.deletes {
+                        entries.remove(key);
+                    }
+                }
+                Ok(())
+            })
+        }
+
+        fn delete_group<'a>(&'a self, group_id: &'a [u8]) -> BackendFuture<'a, ()> {
+            Box::pin(async move {
+                self.entries.lock().retain(|_, (gid, _)| gid.as_deref() != Some(group_id));
+                Ok(())
+            })
+        }
+    }
+
+    #[test]
+    fn engine_state_persists_in_registered_backend() {
+        futures::executor::block_on(async {
+            register_storage_backend("memory-test", Arc::new(MemoryBackend::default()));
+            let ciphersuite = || MlsCiphersuite::Mls128DhkemX25519Aes128gcmSha256Ed25519;
+
+            let engine = MlsEngine::create_with_backend("memory-test".to_string()).await.unwrap();
+            let signer = engine.generate_signature_key_pair(ciphersuite()).await.unwrap();
+            let group = engine
+                .create_group(
+                    MlsGroupConfig::default_config(ciphersuite()),
+                    signer.signer_handle,
+                    b"alice".to_vec(),
+                    signer.public_key,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap();
+            engine.close().await.unwrap();
+
+            let reopened = MlsEngine::create_with_backend("memory-test".to_string()).await.unwrap();
+            assert_eq!(reopened.group_epoch(group.group_id).await.unwrap(), 0);
+            assert!(unregister_storage_backend("memory-test"));
+            assert!(MlsEngine::create_with_backend("memory-test".to_string()).await.is_err());
+        });
+    }
+}
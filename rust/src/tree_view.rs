@@ -0,0 +1,291 @@
+//! Debug rendering of an exported ratchet tree (Graphviz DOT / ASCII), and
+//! its tree hash.
+//!
+//! Works on the TLS encoding from `MlsGroup::export_ratchet_tree()` (RFC 9420
+//! §12.4.3.3) so it depends only on the wire format, not on OpenMLS tree
+//! internals. Nodes are laid out in the array representation of RFC 9420
+//! Appendix C: leaves at even indices, parents at odd indices.
+
+use openmls::prelude::tls_codec::{DeserializeBytes, Serialize, VLBytes};
+
+/// `leaf_node_source` of a leaf that has not changed since its key package.
+pub(crate) const LEAF_SOURCE_KEY_PACKAGE: u8 = 1;
+
+/// A node of the ratchet tree, reduced to what the views display.
+pub(crate) enum TreeNode {
+    Leaf {
+        credential_type: u16,
+        identity: Vec<u8>,
+        /// `leaf_node_source`: 1 = key_package, 2 = update, 3 = commit.
+        source: u8,
+        /// Proposal types listed in the leaf's capabilities.
+        proposals: Vec<u16>,
+        /// Credential types listed in the leaf's capabilities.
+        credentials: Vec<u16>,
+    },
+    Parent {
+        unmerged_leaves: Vec<u32>,
+    },
+}
+
+/// Decode a TLS-serialized ratchet tree into its node array.
+///
+/// `None` entries are blank nodes. The array is padded with blanks to the
+/// full width of a complete tree.
+pub(crate) fn parse_ratchet_tree(bytes: &[u8]) -> Result<Vec<Option<TreeNode>>, String> {
+    let (nodes_bytes, rest) = read_vl(bytes)?;
+    if !rest.is_empty() {
+        return Err("Trailing bytes after ratchet tree".to_string());
+    }
+
+    let mut nodes = Vec::new();
+    let mut input = nodes_bytes.as_slice();
+    while !input.is_empty() {
+        let (present, rest) = read_u8(input)?;
+        input = rest;
+        match present {
+            0 => nodes.push(None),
+            1 => {
+                let (node, rest) = read_node(input)?;
+                nodes.push(Some(node));
+                input = rest;
+            }
+            _ => return Err("Invalid optional node marker".to_string()),
+        }
+    }
+
+    let leaf_count = nodes.len().div_ceil(2).next_power_of_two();
+    nodes.resize_with(2 * leaf_count - 1, || None);
+    Ok(nodes)
+}
+
+/// Compute the tree hash of a TLS-serialized ratchet tree (RFC 9420 §7.8),
+/// i.e. the `tree_hash` its group context must carry.
+pub(crate) fn tree_hash(bytes: &[u8], hash: impl Fn(&[u8]) -> Result<Vec<u8>, String>) -> Result<Vec<u8>, String> {
+    let (nodes_bytes, rest) = read_vl(bytes)?;
+    if !rest.is_empty() {
+        return Err("Trailing bytes after ratchet tree".to_string());
+    }
+
+    // Raw `LeafNode` / `ParentNode` encodings, without the node type.
+    let mut nodes: Vec<Option<&[u8]>> = Vec::new();
+    let mut input = nodes_bytes.as_slice();
+    while !input.is_empty() {
+        let (present, rest) = read_u8(input)?;
+        input = rest;
+        match present {
+            0 => nodes.push(None),
+            1 => {
+                let (node_type, body) = read_u8(input)?;
+                let expected = if nodes.len() % 2 == 0 { 1 } else { 2 };
+                if node_type != expected {
+                    return Err(format!("Node {} has the wrong node type", nodes.len()));
+                }
+                let (_, rest) = read_node(input)?;
+                nodes.push(Some(&body[..body.len() - rest.len()]));
+                input = rest;
+            }
+            _ => return Err("Invalid optional node marker".to_string()),
+        }
+    }
+    if nodes.is_empty() {
+        return Err("Empty ratchet tree".to_string());
+    }
+
+    let leaf_count = nodes.len().div_ceil(2).next_power_of_two();
+    nodes.resize(2 * leaf_count - 1, None);
+    subtree_hash(&nodes, root(nodes.len()), &hash)
+}
+
+fn subtree_hash(
+    nodes: &[Option<&[u8]>],
+    index: usize,
+    hash: &impl Fn(&[u8]) -> Result<Vec<u8>, String>,
+) -> Result<Vec<u8>, String> {
+    fn optional(out: &mut Vec<u8>, node: Option<&[u8]>) {
+        match node {
+            Some(body) => {
+                out.push(1);
+                out.extend_from_slice(body);
+            }
+            None => out.push(0),
+        }
+    }
+
+    let mut input = Vec::new();
+    if index % 2 == 0 {
+        // TreeHashInput { leaf, LeafNodeHashInput { leaf_index, optional<LeafNode> } }
+        input.push(1);
+        input.extend_from_slice(&((index / 2) as u32).to_be_bytes());
+        optional(&mut input, nodes[index]);
+    } else {
+        // TreeHashInput { parent, ParentNodeHashInput { optional<ParentNode>, left_hash<V>, right_hash<V> } }
+        let (left, right) = children(index);
+        input.push(2);
+        optional(&mut input, nodes[index]);
+        for child in [left, right] {
+            VLBytes::new(subtree_hash(nodes, child, hash)?)
+                .tls_serialize(&mut input)
+                .map_err(|e| format!("Failed to encode tree hash input: {}", e))?;
+        }
+    }
+    hash(&input)
+}
+
+/// Render the node array as a Graphviz DOT digraph.
+pub(crate) fn to_dot(nodes: &[Option<TreeNode>]) -> String {
+    let mut out = String::from("digraph ratchet_tree {\n  node [fontname=\"monospace\"];\n");
+    for (index, node) in nodes.iter().enumerate() {
+        let (label, style) = match node {
+            None => (format!("{}: blank", index), "shape=box, style=dashed, color=gray"),
+            Some(TreeNode::Leaf { .. }) => (format!("{}: {}", index, node_label(node)), "shape=box"),
+            Some(TreeNode::Parent { .. }) => (format!("{}: {}", index, node_label(node)), "shape=ellipse"),
+        };
+        out.push_str(&format!("  n{} [label=\"{}\", {}];\n", index, escape_dot(&label), style));
+    }
+    for index in (1..nodes.len()).step_by(2) {
+        let (left, right) = children(index);
+        out.push_str(&format!("  n{} -> n{};\n  n{} -> n{};\n", index, left, index, right));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Render the node array as an indented ASCII tree, root first.
+pub(crate) fn to_ascii(nodes: &[Option<TreeNode>]) -> String {
+    let mut out = String::new();
+    if !nodes.is_empty() {
+        write_ascii(nodes, root(nodes.len()), "", true, true, &mut out);
+    }
+    out
+}
+
+fn write_ascii(nodes: &[Option<TreeNode>], index: usize, prefix: &str, is_root: bool, is_last: bool, out: &mut String) {
+    let (line_prefix, child_prefix) = if is_root {
+        (String::new(), String::new())
+    } else if is_last {
+        (format!("{}└── ", prefix), format!("{}    ", prefix))
+    } else {
+        (format!("{}├── ", prefix), format!("{}│   ", prefix))
+    };
+    out.push_str(&format!("{}{}: {}\n", line_prefix, index, node_label(&nodes[index])));
+    if index % 2 == 1 {
+        let (left, right) = children(index);
+        write_ascii(nodes, left, &child_prefix, false, false, out);
+        write_ascii(nodes, right, &child_prefix, false, true, out);
+    }
+}
+
+fn node_label(node: &Option<TreeNode>) -> String {
+    match node {
+        None => "blank".to_string(),
+        Some(TreeNode::Leaf { credential_type, identity, .. }) => {
+            format!("leaf {} (credential type {})", display_identity(identity), credential_type)
+        }
+        Some(TreeNode::Parent { unmerged_leaves }) if unmerged_leaves.is_empty() => "parent".to_string(),
+        Some(TreeNode::Parent { unmerged_leaves }) => format!("parent (unmerged leaves {:?})", unmerged_leaves),
+    }
+}
+
+/// Identity as text when it is printable UTF-8, otherwise as hex.
+fn display_identity(identity: &[u8]) -> String {
+    match std::str::from_utf8(identity) {
+        Ok(s) if !s.is_empty() && s.chars().all(|c| !c.is_control()) => s.to_string(),
+        _ => identity.iter().map(|b| format!("{:02x}", b)).collect(),
+    }
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Children of parent node `index` (RFC 9420 Appendix C).
+fn children(index: usize) -> (usize, usize) {
+    let level = index.trailing_ones();
+    (index ^ (1 << (level - 1)), index ^ (3 << (level - 1)))
+}
+
+/// Root index of a complete tree with `width` nodes.
+fn root(width: usize) -> usize {
+    width / 2
+}
+
+// -- wire format --
+
+fn read_u8(input: &[u8]) -> Result<(u8, &[u8]), String> {
+    input.split_first().map(|(b, rest)| (*b, rest)).ok_or_else(|| "Truncated ratchet tree".to_string())
+}
+
+fn read_u16(input: &[u8]) -> Result<(u16, &[u8]), String> {
+    if input.len() < 2 {
+        return Err("Truncated ratchet tree".to_string());
+    }
+    let (value, rest) = input.split_at(2);
+    Ok((u16::from_be_bytes([value[0], value[1]]), rest))
+}
+
+fn read_vl(input: &[u8]) -> Result<(VLBytes, &[u8]), String> {
+    VLBytes::tls_deserialize_bytes(input).map_err(|e| format!("Malformed ratchet tree: {}", e))
+}
+
+fn skip(input: &[u8], len: usize) -> Result<&[u8], String> {
+    input.get(len..).ok_or_else(|| "Truncated ratchet tree".to_string())
+}
+
+fn read_node(input: &[u8]) -> Result<(TreeNode, &[u8]), String> {
+    let (node_type, input) = read_u8(input)?;
+    match node_type {
+        1 => read_leaf(input),
+        2 => read_parent(input),
+        _ => Err(format!("Unknown node type {}", node_type)),
+    }
+}
+
+fn read_leaf(input: &[u8]) -> Result<(TreeNode, &[u8]), String> {
+    let (_encryption_key, input) = read_vl(input)?;
+    let (_signature_key, input) = read_vl(input)?;
+    let (credential_type, input) = read_u16(input)?;
+    // Basic: identity<V>; X.509: certificates<V>. Either way one VL field.
+    let (identity, input) = read_vl(input)?;
+    // capabilities: versions, cipher_suites, extensions, proposals, credentials
+    let input = read_vl(input)?.1;
+    let input = read_vl(input)?.1;
+    let input = read_vl(input)?.1;
+    let (proposals, input) = read_vl(input)?;
+    let (credentials, input) = read_vl(input)?;
+    let proposals = read_u16_list(proposals.as_slice()).ok_or("Malformed proposal capabilities")?;
+    let credentials = read_u16_list(credentials.as_slice()).ok_or("Malformed credential capabilities")?;
+    let (source, input) = read_u8(input)?;
+    let input = match source {
+        LEAF_SOURCE_KEY_PACKAGE => skip(input, 16)?, // key_package: lifetime (not_before, not_after)
+        2 => input,            // update
+        3 => read_vl(input)?.1, // commit: parent_hash
+        _ => return Err(format!("Unknown leaf node source {}", source)),
+    };
+    let (_extensions, input) = read_vl(input)?;
+    let (_signature, input) = read_vl(input)?;
+    Ok((TreeNode::Leaf { credential_type, identity: identity.as_slice().to_vec(), source, proposals, credentials }, input))
+}
+
+/// Decode a capabilities list of `uint16` values.
+fn read_u16_list(bytes: &[u8]) -> Option<Vec<u16>> {
+    if bytes.len() % 2 != 0 {
+        return None;
+    }
+    Some(bytes.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect())
+}
+
+fn read_parent(input: &[u8]) -> Result<(TreeNode, &[u8]), String> {
+    let (_encryption_key, input) = read_vl(input)?;
+    let (_parent_hash, input) = read_vl(input)?;
+    let (unmerged, input) = read_vl(input)?;
+    let unmerged = unmerged.as_slice();
+    if unmerged.len() % 4 != 0 {
+        return Err("Malformed unmerged leaves".to_string());
+    }
+    let unmerged_leaves = unmerged
+        .chunks_exact(4)
+        .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]))
+        .collect();
+    Ok((TreeNode::Parent { unmerged_leaves }, input))
+}
@@ -23,3 +23,45 @@ pub fn current_time() -> SystemTime {
     let millis = js_sys::Date::now() as u64;
     UNIX_EPOCH + Duration::from_millis(millis)
 }
+
+/// Run `f` once after `delay`, on a thread of its own. Fails, dropping
+/// `f`, if the thread cannot be started.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn run_after(delay: std::time::Duration, f: impl FnOnce() + Send + 'static) -> Result<(), String> {
+    std::thread::Builder::new()
+        .spawn(move || {
+            std::thread::sleep(delay);
+            f();
+        })
+        .map(|_| ())
+        .map_err(|e| format!("Failed to start timer thread: {}", e))
+}
+
+/// WASM implementation of run_after().
+///
+/// Uses the global `setTimeout`, available in windows and workers alike.
+/// Fails, dropping `f`, if there is none or it throws.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn run_after(delay: std::time::Duration, f: impl FnOnce() + 'static) -> Result<(), String> {
+    use wasm_bindgen::JsCast;
+    let set_timeout = js_sys::Reflect::get(&js_sys::global(), &"setTimeout".into())
+        .ok()
+        .and_then(|value| value.dyn_into::<js_sys::Function>().ok())
+        .ok_or_else(|| "setTimeout is not available".to_string())?;
+    let callback = wasm_bindgen::closure::Closure::once_into_js(f);
+    let millis = delay.as_millis().min(i32::MAX as u128) as f64;
+    set_timeout
+        .call2(&wasm_bindgen::JsValue::NULL, &callback, &millis.into())
+        .map(|_| ())
+        .map_err(|e| format!("setTimeout failed: {:?}", e))
+}
+
+/// Compare two byte strings in time that depends only on their lengths,
+/// never on where they differ.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
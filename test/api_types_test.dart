@@ -16,10 +16,12 @@ void main() {
   final bOther = Uint8List.fromList([99]);
   final u32 = Uint32List.fromList([0, 1]);
   final u16a = Uint16List.fromList([1]);
-  final u16b = Uint16List.fromList([1]);
   final u16c = Uint16List(0);
   final u16d = Uint16List(0);
-  final u16e = Uint16List.fromList([1]);
+  final csList = [MlsCiphersuite.mls128DhkemX25519Aes128GcmSha256Ed25519];
+  final extList = <MlsExtensionType>[];
+  final propList = <MlsProposalType>[];
+  final credList = [MlsCredentialType.basic];
 
   group('api/init', () {
     test('initOpenmls accepts any library path', () {
@@ -185,17 +187,25 @@ void main() {
     test('equal capabilities', () {
       final c1 = MlsCapabilities(
         versions: u16a,
-        ciphersuites: u16b,
-        extensions: u16c,
-        proposals: u16d,
-        credentials: u16e,
+        ciphersuites: csList,
+        extensions: extList,
+        proposals: propList,
+        credentials: credList,
+        otherCiphersuites: u16c,
+        otherExtensions: u16c,
+        otherProposals: u16d,
+        otherCredentials: u16d,
       );
       final c2 = MlsCapabilities(
         versions: u16a,
-        ciphersuites: u16b,
-        extensions: u16c,
-        proposals: u16d,
-        credentials: u16e,
+        ciphersuites: csList,
+        extensions: extList,
+        proposals: propList,
+        credentials: credList,
+        otherCiphersuites: u16c,
+        otherExtensions: u16c,
+        otherProposals: u16d,
+        otherCredentials: u16d,
       );
       expect(c1, equals(c2));
       expect(c1.hashCode, equals(c2.hashCode));
@@ -205,22 +215,84 @@ void main() {
     test('unequal capabilities', () {
       final c1 = MlsCapabilities(
         versions: u16a,
-        ciphersuites: u16b,
-        extensions: u16c,
-        proposals: u16d,
-        credentials: u16e,
+        ciphersuites: csList,
+        extensions: extList,
+        proposals: propList,
+        credentials: credList,
+        otherCiphersuites: u16c,
+        otherExtensions: u16c,
+        otherProposals: u16d,
+        otherCredentials: u16d,
       );
       final c2 = MlsCapabilities(
         versions: Uint16List.fromList([99]),
-        ciphersuites: u16b,
-        extensions: u16c,
-        proposals: u16d,
-        credentials: u16e,
+        ciphersuites: csList,
+        extensions: extList,
+        proposals: propList,
+        credentials: credList,
+        otherCiphersuites: u16c,
+        otherExtensions: u16c,
+        otherProposals: u16d,
+        otherCredentials: u16d,
       );
       expect(c1, isNot(equals(c2)));
     });
   });
 
+  group('MlsCapabilities conversion', () {
+    test('fromRaw sorts known and unknown values', () {
+      final caps = MlsCapabilities.fromRaw(
+        versions: Uint16List.fromList([1]),
+        ciphersuites: Uint16List.fromList([0x0001, 0xFFFE]),
+        extensions: Uint16List.fromList([0x0002, 0xFF01]),
+        proposals: Uint16List.fromList([0x0001, 0x0007, 0xF000]),
+        credentials: Uint16List.fromList([0x0001, 0xF001]),
+      );
+      expect(caps.ciphersuites, [
+        MlsCiphersuite.mls128DhkemX25519Aes128GcmSha256Ed25519,
+      ]);
+      expect(caps.otherCiphersuites, [0xFFFE]);
+      expect(caps.extensions, [MlsExtensionType.ratchetTree]);
+      expect(caps.otherExtensions, [0xFF01]);
+      expect(caps.proposals, [
+        MlsProposalType.add,
+        MlsProposalType.groupContextExtensions,
+      ]);
+      expect(caps.otherProposals, [0xF000]);
+      expect(caps.credentials, [MlsCredentialType.basic]);
+      expect(caps.otherCredentials, [0xF001]);
+    });
+
+    test('raw values round-trip', () {
+      final caps = MlsCapabilities.fromRaw(
+        versions: Uint16List.fromList([1]),
+        ciphersuites: Uint16List.fromList([0x0003]),
+        extensions: Uint16List.fromList([0x000a, 0xFF01]),
+        proposals: Uint16List.fromList([0x0004]),
+        credentials: Uint16List.fromList([0x0002]),
+      );
+      expect(caps.rawCiphersuites(), [0x0003]);
+      expect(caps.rawExtensions(), [0x000a, 0xFF01]);
+      expect(caps.rawProposals(), [0x0004]);
+      expect(caps.rawCredentials(), [0x0002]);
+    });
+
+    test('custom proposal type is rejected in typed list', () {
+      final caps = MlsCapabilities(
+        versions: u16a,
+        ciphersuites: csList,
+        extensions: extList,
+        proposals: [MlsProposalType.custom],
+        credentials: credList,
+        otherCiphersuites: u16c,
+        otherExtensions: u16c,
+        otherProposals: u16d,
+        otherCredentials: u16d,
+      );
+      expect(() => caps.rawProposals(), throwsA(isA<Object>()));
+    });
+  });
+
   group('MlsExtension equality', () {
     test('equal extensions', () {
       final e1 = MlsExtension(extensionType: 1, data: b1);
@@ -285,15 +357,20 @@ void main() {
     test('equal leaf nodes', () {
       final caps = MlsCapabilities(
         versions: u16a,
-        ciphersuites: u16b,
-        extensions: u16c,
-        proposals: u16d,
-        credentials: u16e,
+        ciphersuites: csList,
+        extensions: extList,
+        proposals: propList,
+        credentials: credList,
+        otherCiphersuites: u16c,
+        otherExtensions: u16c,
+        otherProposals: u16d,
+        otherCredentials: u16d,
       );
       final exts = <MlsExtension>[];
       final n1 = MlsLeafNodeInfo(
         credential: b1,
         signatureKey: b2,
+        memberHandle: b1,
         encryptionKey: b3,
         capabilities: caps,
         extensions: exts,
@@ -301,6 +378,7 @@ void main() {
       final n2 = MlsLeafNodeInfo(
         credential: b1,
         signatureKey: b2,
+        memberHandle: b1,
         encryptionKey: b3,
         capabilities: caps,
         extensions: exts,
@@ -313,14 +391,19 @@ void main() {
     test('unequal leaf nodes', () {
       final caps = MlsCapabilities(
         versions: u16a,
-        ciphersuites: u16b,
-        extensions: u16c,
-        proposals: u16d,
-        credentials: u16e,
+        ciphersuites: csList,
+        extensions: extList,
+        proposals: propList,
+        credentials: credList,
+        otherCiphersuites: u16c,
+        otherExtensions: u16c,
+        otherProposals: u16d,
+        otherCredentials: u16d,
       );
       final n1 = MlsLeafNodeInfo(
         credential: b1,
         signatureKey: b2,
+        memberHandle: b1,
         encryptionKey: b3,
         capabilities: caps,
         extensions: [],
@@ -328,6 +411,7 @@ void main() {
       final n2 = MlsLeafNodeInfo(
         credential: bOther,
         signatureKey: b2,
+        memberHandle: b1,
         encryptionKey: b3,
         capabilities: caps,
         extensions: [],
@@ -338,16 +422,36 @@ void main() {
 
   group('MlsMemberInfo equality', () {
     test('equal members', () {
-      final m1 = MlsMemberInfo(index: 0, credential: b1, signatureKey: b2);
-      final m2 = MlsMemberInfo(index: 0, credential: b1, signatureKey: b2);
+      final m1 = MlsMemberInfo(
+        index: 0,
+        credential: b1,
+        signatureKey: b2,
+        memberHandle: b3,
+      );
+      final m2 = MlsMemberInfo(
+        index: 0,
+        credential: b1,
+        signatureKey: b2,
+        memberHandle: b3,
+      );
       expect(m1, equals(m2));
       expect(m1.hashCode, equals(m2.hashCode));
       expect(m1, equals(m1));
     });
 
     test('unequal members', () {
-      final m1 = MlsMemberInfo(index: 0, credential: b1, signatureKey: b2);
-      final m2 = MlsMemberInfo(index: 1, credential: b1, signatureKey: b2);
+      final m1 = MlsMemberInfo(
+        index: 0,
+        credential: b1,
+        signatureKey: b2,
+        memberHandle: b3,
+      );
+      final m2 = MlsMemberInfo(
+        index: 1,
+        credential: b1,
+        signatureKey: b2,
+        memberHandle: b3,
+      );
       expect(m1, isNot(equals(m2)));
     });
   });
@@ -356,10 +460,12 @@ void main() {
     test('equal proposals', () {
       final p1 = MlsPendingProposalInfo(
         proposalType: MlsProposalType.add,
+        proposalRef: b2,
         senderIndex: 0,
       );
       final p2 = MlsPendingProposalInfo(
         proposalType: MlsProposalType.add,
+        proposalRef: b2,
         senderIndex: 0,
       );
       expect(p1, equals(p2));
@@ -370,10 +476,12 @@ void main() {
     test('unequal proposals', () {
       final p1 = MlsPendingProposalInfo(
         proposalType: MlsProposalType.add,
+        proposalRef: b2,
         senderIndex: 0,
       );
       final p2 = MlsPendingProposalInfo(
         proposalType: MlsProposalType.remove,
+        proposalRef: b2,
         senderIndex: 0,
       );
       expect(p1, isNot(equals(p2)));
@@ -386,16 +494,20 @@ void main() {
       final s1 = StagedCommitInfo(
         addCredentials: creds,
         removeIndices: u32,
+        removeHandles: const [],
         hasUpdate: false,
         selfRemoved: false,
         pskCount: 0,
+        psks: const [],
       );
       final s2 = StagedCommitInfo(
         addCredentials: creds,
         removeIndices: u32,
+        removeHandles: const [],
         hasUpdate: false,
         selfRemoved: false,
         pskCount: 0,
+        psks: const [],
       );
       expect(s1, equals(s2));
       expect(s1.hashCode, equals(s2.hashCode));
@@ -408,16 +520,20 @@ void main() {
       final s1 = StagedCommitInfo(
         addCredentials: creds,
         removeIndices: idx,
+        removeHandles: const [],
         hasUpdate: false,
         selfRemoved: false,
         pskCount: 0,
+        psks: const [],
       );
       final s2 = StagedCommitInfo(
         addCredentials: creds,
         removeIndices: idx,
+        removeHandles: const [],
         hasUpdate: true,
         selfRemoved: false,
         pskCount: 0,
+        psks: const [],
       );
       expect(s1, isNot(equals(s2)));
     });
@@ -429,12 +545,14 @@ void main() {
         groupId: b1,
         ciphersuite: MlsCiphersuite.mls128DhkemX25519Aes128GcmSha256Ed25519,
         pskCount: 0,
+        psks: const [],
         epoch: BigInt.one,
       );
       final w2 = WelcomeInspectResult(
         groupId: b1,
         ciphersuite: MlsCiphersuite.mls128DhkemX25519Aes128GcmSha256Ed25519,
         pskCount: 0,
+        psks: const [],
         epoch: BigInt.one,
       );
       expect(w1, equals(w2));
@@ -447,12 +565,14 @@ void main() {
         groupId: b1,
         ciphersuite: MlsCiphersuite.mls128DhkemX25519Aes128GcmSha256Ed25519,
         pskCount: 0,
+        psks: const [],
         epoch: BigInt.one,
       );
       final w2 = WelcomeInspectResult(
         groupId: bOther,
         ciphersuite: MlsCiphersuite.mls128DhkemX25519Aes128GcmSha256Ed25519,
         pskCount: 0,
+        psks: const [],
         epoch: BigInt.one,
       );
       expect(w1, isNot(equals(w2)));
@@ -463,32 +583,64 @@ void main() {
 
   group('AddMembersResult equality', () {
     test('equal results', () {
-      final r1 = AddMembersResult(commit: b1, welcome: b2);
-      final r2 = AddMembersResult(commit: b1, welcome: b2);
+      final r1 = AddMembersResult(
+        commit: b1,
+        welcome: b2,
+        credentialVerified: true,
+      );
+      final r2 = AddMembersResult(
+        commit: b1,
+        welcome: b2,
+        credentialVerified: true,
+      );
       expect(r1, equals(r2));
       expect(r1.hashCode, equals(r2.hashCode));
       expect(r1, equals(r1));
     });
 
     test('unequal results', () {
-      final r1 = AddMembersResult(commit: b1, welcome: b2);
-      final r2 = AddMembersResult(commit: bOther, welcome: b2);
+      final r1 = AddMembersResult(
+        commit: b1,
+        welcome: b2,
+        credentialVerified: true,
+      );
+      final r2 = AddMembersResult(
+        commit: bOther,
+        welcome: b2,
+        credentialVerified: true,
+      );
       expect(r1, isNot(equals(r2)));
     });
   });
 
   group('CommitResult equality', () {
     test('equal results', () {
-      final r1 = CommitResult(commit: b1);
-      final r2 = CommitResult(commit: b1);
+      final r1 = CommitResult(
+        commit: b1,
+        wireFormat: MlsWireFormat.ciphertext,
+        proposals: const [],
+      );
+      final r2 = CommitResult(
+        commit: b1,
+        wireFormat: MlsWireFormat.ciphertext,
+        proposals: const [],
+      );
       expect(r1, equals(r2));
       expect(r1.hashCode, equals(r2.hashCode));
       expect(r1, equals(r1));
     });
 
     test('unequal results', () {
-      final r1 = CommitResult(commit: b1);
-      final r2 = CommitResult(commit: bOther);
+      final r1 = CommitResult(
+        commit: b1,
+        wireFormat: MlsWireFormat.ciphertext,
+        proposals: const [],
+      );
+      final r2 = CommitResult(
+        commit: bOther,
+        wireFormat: MlsWireFormat.ciphertext,
+        proposals: const [],
+      );
       expect(r1, isNot(equals(r2)));
     });
   });
@@ -511,16 +663,28 @@ void main() {
 
   group('CreateMessageResult equality', () {
     test('equal results', () {
-      final r1 = CreateMessageResult(ciphertext: b1);
-      final r2 = CreateMessageResult(ciphertext: b1);
+      final r1 = CreateMessageResult(
+        ciphertext: b1,
+        wireFormat: MlsWireFormat.ciphertext,
+      );
+      final r2 = CreateMessageResult(
+        ciphertext: b1,
+        wireFormat: MlsWireFormat.ciphertext,
+      );
       expect(r1, equals(r2));
       expect(r1.hashCode, equals(r2.hashCode));
       expect(r1, equals(r1));
     });
 
     test('unequal results', () {
-      final r1 = CreateMessageResult(ciphertext: b1);
-      final r2 = CreateMessageResult(ciphertext: bOther);
+      final r1 = CreateMessageResult(
+        ciphertext: b1,
+        wireFormat: MlsWireFormat.ciphertext,
+      );
+      final r2 = CreateMessageResult(
+        ciphertext: bOther,
+        wireFormat: MlsWireFormat.ciphertext,
+      );
       expect(r1, isNot(equals(r2)));
     });
   });
@@ -542,33 +706,57 @@ void main() {
   });
 
   group('JoinGroupResult equality', () {
+    JoinGroupResult joinResult(Uint8List groupId) => JoinGroupResult(
+      groupId: groupId,
+      epoch: BigInt.one,
+      ciphersuite: MlsCiphersuite.mls128DhkemX25519Aes128GcmSha256Ed25519,
+      memberCount: 2,
+      ourLeafIndex: 1,
+    );
+
     test('equal results', () {
-      final r1 = JoinGroupResult(groupId: b1);
-      final r2 = JoinGroupResult(groupId: b1);
+      final r1 = joinResult(b1);
+      final r2 = joinResult(b1);
       expect(r1, equals(r2));
       expect(r1.hashCode, equals(r2.hashCode));
       expect(r1, equals(r1));
     });
 
     test('unequal results', () {
-      final r1 = JoinGroupResult(groupId: b1);
-      final r2 = JoinGroupResult(groupId: bOther);
+      final r1 = joinResult(b1);
+      final r2 = joinResult(bOther);
       expect(r1, isNot(equals(r2)));
     });
   });
 
   group('KeyPackageResult equality', () {
     test('equal results', () {
-      final r1 = KeyPackageResult(keyPackageBytes: b1);
-      final r2 = KeyPackageResult(keyPackageBytes: b1);
+      final r1 = KeyPackageResult(
+        keyPackageBytes: b1,
+        keyPackageRef: b2,
+        createdAt: BigInt.one,
+      );
+      final r2 = KeyPackageResult(
+        keyPackageBytes: b1,
+        keyPackageRef: b2,
+        createdAt: BigInt.one,
+      );
       expect(r1, equals(r2));
       expect(r1.hashCode, equals(r2.hashCode));
       expect(r1, equals(r1));
     });
 
     test('unequal results', () {
-      final r1 = KeyPackageResult(keyPackageBytes: b1);
-      final r2 = KeyPackageResult(keyPackageBytes: bOther);
+      final r1 = KeyPackageResult(
+        keyPackageBytes: b1,
+        keyPackageRef: b2,
+        createdAt: BigInt.one,
+      );
+      final r2 = KeyPackageResult(
+        keyPackageBytes: bOther,
+        keyPackageRef: b2,
+        createdAt: BigInt.one,
+      );
       expect(r1, isNot(equals(r2)));
     });
   });
@@ -657,16 +845,16 @@ void main() {
 
   group('ProposalResult equality', () {
     test('equal results', () {
-      final r1 = ProposalResult(proposalMessage: b1);
-      final r2 = ProposalResult(proposalMessage: b1);
+      final r1 = ProposalResult(proposalMessage: b1, proposalRef: b2);
+      final r2 = ProposalResult(proposalMessage: b1, proposalRef: b2);
       expect(r1, equals(r2));
       expect(r1.hashCode, equals(r2.hashCode));
       expect(r1, equals(r1));
     });
 
     test('unequal results', () {
-      final r1 = ProposalResult(proposalMessage: b1);
-      final r2 = ProposalResult(proposalMessage: bOther);
+      final r1 = ProposalResult(proposalMessage: b1, proposalRef: b2);
+      final r2 = ProposalResult(proposalMessage: bOther, proposalRef: b2);
       expect(r1, isNot(equals(r2)));
     });
   });
@@ -674,7 +862,13 @@ void main() {
   group('cross-type equality', () {
     test('different types are not equal', () {
       final create = CreateGroupResult(groupId: b1);
-      final join = JoinGroupResult(groupId: b1);
+      final join = JoinGroupResult(
+        groupId: b1,
+        epoch: BigInt.one,
+        ciphersuite: MlsCiphersuite.mls128DhkemX25519Aes128GcmSha256Ed25519,
+        memberCount: 2,
+        ourLeafIndex: 1,
+      );
       // ignore: unrelated_type_equality_checks
       expect(create == join, isFalse);
       // ignore: unrelated_type_equality_checks
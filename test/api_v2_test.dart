@@ -0,0 +1,104 @@
+import 'dart:typed_data';
+
+import 'package:openmls/openmls.dart';
+import 'package:openmls/v2.dart' as v2;
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late TestIdentity aliceId;
+  late Uint8List groupId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    final result = await alice.createGroup(
+      config: defaultConfig(),
+      signerBytes: aliceId.signerBytes,
+      credentialIdentity: aliceId.credentialIdentity,
+      signerPublicKey: aliceId.publicKey,
+    );
+    groupId = result.groupId;
+  });
+
+  tearDown(() {
+    setCompatMode(mode: MlsCompatMode.v1);
+  });
+
+  group('v2 API', () {
+    test('malformed input is reported as invalidInput', () async {
+      await expectLater(
+        v2.processMessage(
+          engine: alice,
+          groupIdBytes: groupId,
+          messageBytes: Uint8List.fromList([1, 2, 3]),
+        ),
+        throwsA(
+          isA<v2.MlsApiError>().having(
+            (e) => e.kind,
+            'kind',
+            v2.MlsErrorKind.invalidInput,
+          ),
+        ),
+      );
+    });
+
+    test('unknown group is reported as notFound', () async {
+      await expectLater(
+        v2.processMessage(
+          engine: alice,
+          groupIdBytes: Uint8List.fromList([9, 9, 9]),
+          messageBytes: Uint8List.fromList([1, 2, 3]),
+        ),
+        throwsA(
+          isA<v2.MlsApiError>().having(
+            (e) => e.kind,
+            'kind',
+            v2.MlsErrorKind.notFound,
+          ),
+        ),
+      );
+    });
+
+    test('a closed engine is reported as closed', () async {
+      await alice.close();
+      await expectLater(
+        v2.processMessage(
+          engine: alice,
+          groupIdBytes: groupId,
+          messageBytes: Uint8List.fromList([1, 2, 3]),
+        ),
+        throwsA(
+          isA<v2.MlsApiError>().having(
+            (e) => e.kind,
+            'kind',
+            v2.MlsErrorKind.closed,
+          ),
+        ),
+      );
+    });
+
+    test('v2Only compat mode disables replaced v1 methods', () async {
+      expect(compatMode(), MlsCompatMode.v1);
+      setCompatMode(mode: MlsCompatMode.v2Only);
+      expect(compatMode(), MlsCompatMode.v2Only);
+      await expectLater(
+        alice.processMessage(
+          groupIdBytes: groupId,
+          messageBytes: Uint8List.fromList([1, 2, 3]),
+        ),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('Compat mode')),
+        ),
+      );
+      // Methods without a v2 replacement are unaffected.
+      expect(await alice.groupEpoch(groupIdBytes: groupId), BigInt.zero);
+    });
+  });
+}
@@ -0,0 +1,85 @@
+import 'dart:typed_data';
+
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late MlsEngine bob;
+  late TestIdentity aliceId;
+  late TestIdentity bobId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    bob = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    bobId = TestIdentity.create('bob');
+  });
+
+  group('attachment key wrapping', () {
+    final attachmentKey = Uint8List.fromList(List.generate(32, (i) => i));
+    final context = Uint8List.fromList([1, 2, 3]);
+
+    test('current members unwrap the key', () async {
+      final (groupId, _) = await addBob(alice, aliceId, bob, bobId);
+      final wrapped = await alice.wrapAttachmentKey(
+        groupIdBytes: groupId,
+        attachmentKey: attachmentKey,
+        messageContext: context,
+      );
+      expect(wrapped, isNotEmpty);
+
+      final unwrapped = await bob.unwrapAttachmentKey(
+        groupIdBytes: groupId,
+        wrappedKey: wrapped,
+        messageContext: context,
+      );
+      expect(unwrapped, equals(attachmentKey));
+    });
+
+    test('rejects a different message context', () async {
+      final (groupId, _) = await addBob(alice, aliceId, bob, bobId);
+      final wrapped = await alice.wrapAttachmentKey(
+        groupIdBytes: groupId,
+        attachmentKey: attachmentKey,
+        messageContext: context,
+      );
+      expect(
+        () => bob.unwrapAttachmentKey(
+          groupIdBytes: groupId,
+          wrappedKey: wrapped,
+          messageContext: Uint8List.fromList([9]),
+        ),
+        throwsA(anything),
+      );
+    });
+
+    test('cannot be unwrapped in a later epoch', () async {
+      final (groupId, _) = await addBob(alice, aliceId, bob, bobId);
+      final wrapped = await alice.wrapAttachmentKey(
+        groupIdBytes: groupId,
+        attachmentKey: attachmentKey,
+        messageContext: context,
+      );
+      await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      expect(
+        () => alice.unwrapAttachmentKey(
+          groupIdBytes: groupId,
+          wrappedKey: wrapped,
+          messageContext: context,
+        ),
+        throwsA(anything),
+      );
+    });
+  });
+}
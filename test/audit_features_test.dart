@@ -46,6 +46,24 @@ void main() {
       );
     });
 
+    test('leaves no pending recovery behind', () async {
+      final result = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      await alice.deleteGroup(groupIdBytes: result.groupId);
+
+      expect(await alice.pendingRecovery(), isEmpty);
+      await expectLater(
+        alice.recoverOperation(id: BigInt.one),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('No pending')),
+        ),
+      );
+    });
+
     test('delete non-existent group throws', () async {
       expect(
         () => alice.deleteGroup(groupIdBytes: [1, 2, 3]),
@@ -96,24 +114,26 @@ void main() {
       );
 
       // Alice proposes adding Bob
-      await alice.proposeAdd(
+      final proposal = await alice.proposeAdd(
         groupIdBytes: aliceGroup.groupId,
         signerBytes: aliceId.signerBytes,
         keyPackageBytes: bobKp.keyPackageBytes,
       );
 
-      // Verify proposal exists
+      // Verify proposal exists under the returned ref
       final proposals = await alice.groupPendingProposals(
         groupIdBytes: aliceGroup.groupId,
       );
       expect(proposals, hasLength(1));
       expect(proposals.first.proposalType, MlsProposalType.add);
+      expect(proposals.first.proposalRef, equals(proposal.proposalRef));
 
-      // Clear all pending proposals (since we can't easily get the
-      // proposal ref from Dart side without it being returned by propose)
-      await alice.clearPendingProposals(groupIdBytes: aliceGroup.groupId);
+      await alice.removePendingProposal(
+        groupIdBytes: aliceGroup.groupId,
+        proposalRefBytes: proposal.proposalRef,
+      );
 
-      // Verify proposals cleared
+      // Verify proposal removed
       final after = await alice.groupPendingProposals(
         groupIdBytes: aliceGroup.groupId,
       );
@@ -170,6 +190,7 @@ void main() {
       await alice.selfUpdate(
         groupIdBytes: result.groupId,
         signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
       );
 
       final auth1 = await alice.groupEpochAuthenticator(
@@ -329,6 +350,7 @@ void main() {
         newCredentialIdentity: newAlice.credentialIdentity,
         newSignerPublicKey: newAlice.publicKey,
         newCredentialBytes: newCred.serialize(),
+        ensureGroupInfo: false,
       );
       expect(commitResult.commit, isNotEmpty);
     });
@@ -372,10 +394,14 @@ void main() {
         signerPublicKey: aliceId.publicKey,
         capabilities: MlsCapabilities(
           versions: Uint16List.fromList([1]),
-          ciphersuites: Uint16List.fromList([1]),
-          extensions: Uint16List.fromList([0xFF01]),
-          proposals: Uint16List(0),
-          credentials: Uint16List(0),
+          ciphersuites: [ciphersuite],
+          extensions: [],
+          proposals: [],
+          credentials: [],
+          otherCiphersuites: Uint16List(0),
+          otherExtensions: Uint16List.fromList([0xFF01]),
+          otherProposals: Uint16List(0),
+          otherCredentials: Uint16List(0),
         ),
       );
 
@@ -0,0 +1,126 @@
+import 'dart:convert';
+
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late MlsEngine bob;
+  late TestIdentity aliceId;
+  late TestIdentity bobId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    bob = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    bobId = TestIdentity.create('bob');
+  });
+
+  group('audit log', () {
+    test('records own and received commits in a hash chain', () async {
+      final (groupId, _) = await addBob(alice, aliceId, bob, bobId);
+      final update = await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: update.commit,
+      );
+
+      final aliceLog = await alice.exportAuditLog(
+        groupIdBytes: groupId,
+        fromEpoch: BigInt.zero,
+        signerBytes: aliceId.signerBytes,
+      );
+      expect(aliceLog.signature, isNotEmpty);
+      expect(aliceLog.signerPublicKey, equals(aliceId.publicKey));
+      final aliceJson = jsonDecode(aliceLog.json) as Map<String, dynamic>;
+      expect(aliceJson['version'], equals(1));
+      expect(aliceJson['prev_chain_hash'], isEmpty);
+      final aliceEntries = aliceJson['entries'] as List;
+      expect(aliceEntries, hasLength(2));
+      expect(aliceEntries[0]['epoch'], equals(1));
+      expect(aliceEntries[0]['own'], isTrue);
+      expect(aliceEntries[0]['sender'], equals(0));
+      expect(aliceEntries[0]['proposals']['add'], equals(1));
+      expect(aliceEntries[1]['epoch'], equals(2));
+
+      // Bob joined at epoch 1 and only saw the update commit.
+      final bobLog = await bob.exportAuditLog(
+        groupIdBytes: groupId,
+        fromEpoch: BigInt.zero,
+        signerBytes: bobId.signerBytes,
+      );
+      final bobEntries = (jsonDecode(bobLog.json) as Map)['entries'] as List;
+      expect(bobEntries, hasLength(1));
+      expect(bobEntries[0]['own'], isFalse);
+      expect(bobEntries[0]['sender'], equals(0));
+      expect(
+        bobEntries[0]['commit_hash'],
+        equals(aliceEntries[1]['commit_hash']),
+      );
+    });
+
+    test('export from an epoch links to the preceding entry', () async {
+      final (groupId, _) = await addBob(alice, aliceId, bob, bobId);
+      await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+
+      final full = jsonDecode(
+        (await alice.exportAuditLog(
+          groupIdBytes: groupId,
+          fromEpoch: BigInt.zero,
+          signerBytes: aliceId.signerBytes,
+        )).json,
+      ) as Map<String, dynamic>;
+      final tail = jsonDecode(
+        (await alice.exportAuditLog(
+          groupIdBytes: groupId,
+          fromEpoch: BigInt.from(2),
+          signerBytes: aliceId.signerBytes,
+        )).json,
+      ) as Map<String, dynamic>;
+      expect(tail['entries'], hasLength(1));
+      expect(
+        tail['prev_chain_hash'],
+        equals((full['entries'] as List)[0]['chain_hash']),
+      );
+    });
+
+    test('is not recorded while disabled', () async {
+      expect(alice.isAuditLogEnabled(), isTrue);
+      alice.setAuditLogEnabled(enabled: false);
+      final (groupId, _) = await addBob(alice, aliceId, bob, bobId);
+
+      final log = await alice.exportAuditLog(
+        groupIdBytes: groupId,
+        fromEpoch: BigInt.zero,
+        signerBytes: aliceId.signerBytes,
+      );
+      expect((jsonDecode(log.json) as Map)['entries'], isEmpty);
+    });
+
+    test('rejects a signer that is not the own leaf', () async {
+      final (groupId, _) = await addBob(alice, aliceId, bob, bobId);
+      expect(
+        () => alice.exportAuditLog(
+          groupIdBytes: groupId,
+          fromEpoch: BigInt.zero,
+          signerBytes: bobId.signerBytes,
+        ),
+        throwsA(anything),
+      );
+    });
+  });
+}
@@ -0,0 +1,154 @@
+import 'dart:convert';
+import 'dart:typed_data';
+
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late MlsEngine bob;
+  late TestIdentity aliceId;
+  late TestIdentity bobId;
+  late Uint8List groupId;
+  late Uint8List aliceCredential;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    bob = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    bobId = TestIdentity.create('bob');
+    final result = await alice.createGroup(
+      config: defaultConfig(),
+      signerBytes: aliceId.signerBytes,
+      credentialIdentity: aliceId.credentialIdentity,
+      signerPublicKey: aliceId.publicKey,
+    );
+    groupId = result.groupId;
+    final bobKp = await bob.createKeyPackage(
+      ciphersuite: ciphersuite,
+      signerBytes: bobId.signerBytes,
+      credentialIdentity: bobId.credentialIdentity,
+      signerPublicKey: bobId.publicKey,
+    );
+    final add = await alice.addMembers(
+      groupIdBytes: groupId,
+      signerBytes: aliceId.signerBytes,
+      keyPackagesBytes: [bobKp.keyPackageBytes],
+      ensureGroupInfo: false,
+    );
+    await bob.joinGroupFromWelcome(
+      config: defaultConfig(),
+      welcomeBytes: add.welcome,
+      signerBytes: bobId.signerBytes,
+    );
+    final members = await bob.groupMembers(groupIdBytes: groupId);
+    aliceCredential = members.firstWhere((m) => m.index == 0).credential;
+  });
+
+  Future<Uint8List> send(String text) async => (await alice.createMessage(
+    groupIdBytes: groupId,
+    signerBytes: aliceId.signerBytes,
+    message: Uint8List.fromList(utf8.encode(text)),
+  )).ciphertext;
+
+  group('block list', () {
+    test('is empty by default and messages are not flagged', () async {
+      expect(await bob.blockedMembers(groupIdBytes: groupId), isEmpty);
+      final result = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: await send('hi'),
+      );
+      expect(result.blocked, isFalse);
+    });
+
+    test('flags messages from blocked members', () async {
+      await bob.blockMember(
+        groupIdBytes: groupId,
+        credential: aliceCredential,
+        suppressMessages: false,
+      );
+      final message = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: await send('flagged'),
+      );
+      expect(message.blocked, isTrue);
+      expect(utf8.decode(message.applicationMessage!), 'flagged');
+
+      final commit = await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      final processed = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: commit.commit,
+      );
+      expect(processed.blocked, isTrue);
+      expect(processed.messageType, ProcessedMessageType.stagedCommit);
+      expect(await bob.groupEpoch(groupIdBytes: groupId), BigInt.two);
+    });
+
+    test('suppressed messages keep the ratchet in step', () async {
+      await bob.blockMember(
+        groupIdBytes: groupId,
+        credential: aliceCredential,
+        suppressMessages: true,
+      );
+      final suppressed = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: await send('hidden'),
+      );
+      expect(suppressed.blocked, isTrue);
+      expect(suppressed.applicationMessage, isNull);
+
+      expect(
+        await bob.unblockMember(
+          groupIdBytes: groupId,
+          credential: aliceCredential,
+        ),
+        isTrue,
+      );
+      final visible = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: await send('visible'),
+      );
+      expect(visible.blocked, isFalse);
+      expect(utf8.decode(visible.applicationMessage!), 'visible');
+    });
+
+    test('blocking again updates the entry', () async {
+      await bob.blockMember(
+        groupIdBytes: groupId,
+        credential: aliceCredential,
+        suppressMessages: false,
+      );
+      await bob.blockMember(
+        groupIdBytes: groupId,
+        credential: aliceCredential,
+        suppressMessages: true,
+      );
+      final blocked = await bob.blockedMembers(groupIdBytes: groupId);
+      expect(blocked, hasLength(1));
+      expect(blocked.single.suppressMessages, isTrue);
+
+      await bob.unblockMember(
+        groupIdBytes: groupId,
+        credential: aliceCredential,
+      );
+      expect(await bob.blockedMembers(groupIdBytes: groupId), isEmpty);
+      expect(
+        await bob.unblockMember(
+          groupIdBytes: groupId,
+          credential: aliceCredential,
+        ),
+        isFalse,
+      );
+    });
+  });
+}
@@ -0,0 +1,91 @@
+import 'dart:typed_data';
+
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late TestIdentity aliceId;
+  late Uint8List groupIdBytes;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    final result = await alice.createGroup(
+      config: defaultConfig(),
+      signerBytes: aliceId.signerBytes,
+      credentialIdentity: aliceId.credentialIdentity,
+      signerPublicKey: aliceId.publicKey,
+    );
+    groupIdBytes = result.groupId;
+  });
+
+  Future<Uint8List> exportGroupInfo(Uint8List groupId) => alice.exportGroupInfo(
+    groupIdBytes: groupId,
+    signerBytes: aliceId.signerBytes,
+  );
+
+  group('cached group info', () {
+    test('nothing cached initially', () async {
+      expect(await alice.cachedGroupInfo(groupIdBytes: groupIdBytes), isNull);
+    });
+
+    test('accepts and caches a valid group info', () async {
+      final info = await exportGroupInfo(groupIdBytes);
+      final outcome = await alice.updateCachedGroupInfo(
+        groupIdBytes: groupIdBytes,
+        groupInfoBytes: info,
+      );
+      expect(outcome, GroupInfoUpdateOutcome.accepted);
+      expect(
+        await alice.cachedGroupInfo(groupIdBytes: groupIdBytes),
+        equals(info),
+      );
+    });
+
+    test('rejects a group info with a forged signature', () async {
+      final info = Uint8List.fromList(await exportGroupInfo(groupIdBytes));
+      info[info.length - 1] ^= 0xff;
+      final outcome = await alice.updateCachedGroupInfo(
+        groupIdBytes: groupIdBytes,
+        groupInfoBytes: info,
+      );
+      expect(outcome, GroupInfoUpdateOutcome.invalidSignature);
+      expect(await alice.cachedGroupInfo(groupIdBytes: groupIdBytes), isNull);
+    });
+
+    test('rejects a group info for another group', () async {
+      final other = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final outcome = await alice.updateCachedGroupInfo(
+        groupIdBytes: groupIdBytes,
+        groupInfoBytes: await exportGroupInfo(other.groupId),
+      );
+      expect(outcome, GroupInfoUpdateOutcome.wrongGroup);
+    });
+
+    test('rejects a stale group info', () async {
+      final old = await exportGroupInfo(groupIdBytes);
+      await alice.selfUpdate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      final outcome = await alice.updateCachedGroupInfo(
+        groupIdBytes: groupIdBytes,
+        groupInfoBytes: old,
+      );
+      expect(outcome, GroupInfoUpdateOutcome.staleEpoch);
+    });
+  });
+}
@@ -0,0 +1,205 @@
+import 'dart:typed_data';
+
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late MlsEngine bob;
+  late MlsEngine charlie;
+  late TestIdentity aliceId;
+  late TestIdentity bobId;
+  late TestIdentity charlieId;
+  late Uint8List groupId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    bob = await createTestEngine();
+    charlie = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    bobId = TestIdentity.create('bob');
+    charlieId = TestIdentity.create('charlie');
+    final result = await alice.createGroup(
+      config: defaultConfig(),
+      signerBytes: aliceId.signerBytes,
+      credentialIdentity: aliceId.credentialIdentity,
+      signerPublicKey: aliceId.publicKey,
+    );
+    groupId = result.groupId;
+  });
+
+  Future<Uint8List> addMember(
+    MlsEngine engine,
+    TestIdentity id, {
+    bool join = false,
+  }) async {
+    final kp = await engine.createKeyPackage(
+      ciphersuite: ciphersuite,
+      signerBytes: id.signerBytes,
+      credentialIdentity: id.credentialIdentity,
+      signerPublicKey: id.publicKey,
+    );
+    final add = await alice.addMembers(
+      groupIdBytes: groupId,
+      signerBytes: aliceId.signerBytes,
+      keyPackagesBytes: [kp.keyPackageBytes],
+      ensureGroupInfo: false,
+    );
+    return join ? add.welcome : add.commit;
+  }
+
+  group('credential validation', () {
+    test('without a validator new credentials are unverified', () async {
+      final welcome = await addMember(bob, bobId, join: true);
+      final joined = await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+      );
+      expect(joined.credentialVerified, isFalse);
+    });
+
+    test('validator sees every other member on join', () async {
+      final checks = <MlsCredentialCheck>[];
+      await bob.setCredentialValidator(
+        validator: (check) {
+          checks.add(check);
+          return MlsCredentialVerdict.accept;
+        },
+      );
+      final welcome = await addMember(bob, bobId, join: true);
+      final joined = await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+      );
+
+      expect(joined.credentialVerified, isTrue);
+      expect(checks, hasLength(1));
+      expect(checks.single.groupId, equals(groupId));
+      expect(checks.single.leafIndex, 0);
+      expect(checks.single.signatureKey, equals(aliceId.publicKey));
+    });
+
+    test('unverified credentials are applied but flagged', () async {
+      final welcome = await addMember(bob, bobId, join: true);
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+      );
+      final checks = <MlsCredentialCheck>[];
+      await bob.setCredentialValidator(
+        validator: (check) {
+          checks.add(check);
+          return MlsCredentialVerdict.unverified;
+        },
+      );
+
+      final commit = await addMember(charlie, charlieId);
+      final result = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: commit,
+      );
+      expect(result.messageType, ProcessedMessageType.stagedCommit);
+      expect(result.credentialVerified, isFalse);
+      expect(checks.single.leafIndex, isNull);
+      expect(checks.single.signatureKey, equals(charlieId.publicKey));
+      expect(await bob.groupEpoch(groupIdBytes: groupId), BigInt.two);
+    });
+
+    test('rejected credentials leave the group untouched', () async {
+      final welcome = await addMember(bob, bobId, join: true);
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+      );
+      await bob.setCredentialValidator(
+        validator: (_) => MlsCredentialVerdict.reject,
+      );
+
+      final commit = await addMember(charlie, charlieId);
+      await expectLater(
+        bob.processMessage(groupIdBytes: groupId, messageBytes: commit),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('Credential rejected')),
+        ),
+      );
+      expect(await bob.groupEpoch(groupIdBytes: groupId), BigInt.one);
+
+      bob.clearCredentialValidator();
+      final result = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: commit,
+      );
+      expect(result.credentialVerified, isFalse);
+      expect(await bob.groupEpoch(groupIdBytes: groupId), BigInt.two);
+    });
+
+    test('validator sees added members before the commit', () async {
+      final checks = <MlsCredentialCheck>[];
+      await alice.setCredentialValidator(
+        validator: (check) {
+          checks.add(check);
+          return MlsCredentialVerdict.accept;
+        },
+      );
+      final kp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final add = await alice.addMembers(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [kp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      expect(add.credentialVerified, isTrue);
+      expect(checks.single.leafIndex, isNull);
+      expect(checks.single.signatureKey, equals(bobId.publicKey));
+      final info = credentialInfo(credentialBytes: checks.single.credential);
+      expect(info.identity, equals(bobId.credentialIdentity));
+    });
+
+    test('rejected key packages are not added', () async {
+      await alice.setCredentialValidator(
+        validator: (_) => MlsCredentialVerdict.reject,
+      );
+      await expectLater(
+        addMember(bob, bobId),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('Credential rejected')),
+        ),
+      );
+      expect(await alice.groupEpoch(groupIdBytes: groupId), BigInt.zero);
+    });
+
+    test('self updates without key changes need no validation', () async {
+      final welcome = await addMember(bob, bobId, join: true);
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+      );
+      final commit = await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      final result = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: commit.commit,
+      );
+      expect(result.credentialVerified, isTrue);
+    });
+  });
+}
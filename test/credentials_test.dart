@@ -56,5 +56,117 @@ void main() {
 
       expect(cred.certificates, throwsA(isA<Object>()));
     });
+
+    test('info() parses the chain or the identity', () {
+      final cert1 = Uint8List.fromList([0x30, 0x82, 0x01, 0x22]);
+      final cert2 = Uint8List.fromList([0x30, 0x82, 0x01, 0x33]);
+      final x509 = MlsCredential.x509(certificateChain: [cert1, cert2]);
+      final x509Info = credentialInfo(credentialBytes: x509.serialize());
+      expect(x509Info.credentialType, 2);
+      expect(x509Info.identity, isNull);
+      expect(x509Info.certificateChain, equals([cert1, cert2]));
+
+      final basic = MlsCredential.basic(identity: utf8.encode('alice'));
+      final basicInfo = basic.info();
+      expect(basicInfo.credentialType, 1);
+      expect(basicInfo.identity, equals(utf8.encode('alice')));
+      expect(basicInfo.certificateChain, isEmpty);
+    });
+  });
+
+  group('hardened identity handling', () {
+    MlsIdentityRules rules(MlsIdentityCharset charset) =>
+        MlsIdentityRules(minLength: 1, maxLength: 16, charset: charset);
+
+    test('constant-time comparison matches equality', () {
+      final a = Uint8List.fromList(utf8.encode('alice'));
+      expect(constantTimeEq(a: a, b: Uint8List.fromList(a)), isTrue);
+      expect(constantTimeEq(a: a, b: utf8.encode('alicf')), isFalse);
+      expect(constantTimeEq(a: a, b: utf8.encode('alice2')), isFalse);
+      expect(constantTimeEq(a: Uint8List(0), b: Uint8List(0)), isTrue);
+    });
+
+    test('credentials compare by type and content', () {
+      final alice = MlsCredential.basic(identity: utf8.encode('alice'));
+      final again = MlsCredential.basic(identity: utf8.encode('alice'));
+      final bob = MlsCredential.basic(identity: utf8.encode('bob'));
+      final cert = MlsCredential.x509(
+        certificateChain: [Uint8List.fromList(utf8.encode('alice'))],
+      );
+      expect(alice.constantTimeEq(other: again), isTrue);
+      expect(alice.constantTimeEq(other: bob), isFalse);
+      expect(alice.constantTimeEq(other: cert), isFalse);
+    });
+
+    test('identity length is enforced', () {
+      expect(
+        () => validateIdentity(
+          identity: Uint8List(0),
+          rules: rules(MlsIdentityCharset.any),
+        ),
+        throwsA(predicate<Object>((e) => e.toString().contains('length'))),
+      );
+      expect(
+        () => validateIdentity(
+          identity: Uint8List(17),
+          rules: rules(MlsIdentityCharset.any),
+        ),
+        throwsA(predicate<Object>((e) => e.toString().contains('length'))),
+      );
+      validateIdentity(
+        identity: Uint8List(16),
+        rules: rules(MlsIdentityCharset.any),
+      );
+    });
+
+    test('identity charset is enforced', () {
+      validateIdentity(
+        identity: utf8.encode('zoë@example'),
+        rules: rules(MlsIdentityCharset.utf8),
+      );
+      expect(
+        () => validateIdentity(
+          identity: Uint8List.fromList([0xff, 0xfe]),
+          rules: rules(MlsIdentityCharset.utf8),
+        ),
+        throwsA(anything),
+      );
+      expect(
+        () => validateIdentity(
+          identity: utf8.encode('a\nb'),
+          rules: rules(MlsIdentityCharset.utf8),
+        ),
+        throwsA(anything),
+      );
+      expect(
+        () => validateIdentity(
+          identity: utf8.encode('zoë'),
+          rules: rules(MlsIdentityCharset.printableAscii),
+        ),
+        throwsA(anything),
+      );
+      expect(
+        () => validateIdentity(
+          identity: utf8.encode('a b'),
+          rules: rules(MlsIdentityCharset.printableAscii),
+        ),
+        throwsA(anything),
+      );
+    });
+
+    test('validated basic credential', () {
+      final cred = MlsCredential.basicValidated(
+        identity: utf8.encode('alice'),
+        rules: rules(MlsIdentityCharset.printableAscii),
+      );
+      expect(utf8.decode(cred.identity()), 'alice');
+      expect(
+        () => MlsCredential.basicValidated(
+          identity: utf8.encode('alice smith'),
+          rules: rules(MlsIdentityCharset.printableAscii),
+        ),
+        throwsA(anything),
+      );
+    });
   });
 }
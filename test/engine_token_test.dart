@@ -0,0 +1,49 @@
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late TestIdentity aliceId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() {
+    aliceId = TestIdentity.create('alice');
+  });
+
+  group('engine token', () {
+    test('token resolves to a handle sharing the same state', () async {
+      final engine = await createTestEngine();
+      final token = engine.toToken();
+      expect(engine.toToken(), equals(token));
+
+      final handle = MlsEngine.fromToken(token: token);
+      final result = await engine.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+
+      expect(await handle.groupIsActive(groupIdBytes: result.groupId), isTrue);
+    });
+
+    test('unknown token throws', () {
+      expect(() => MlsEngine.fromToken(token: BigInt.one << 62), throwsA(anything));
+    });
+
+    test('close invalidates token and all handles', () async {
+      final engine = await createTestEngine();
+      final token = engine.toToken();
+      final handle = MlsEngine.fromToken(token: token);
+
+      await engine.close();
+
+      expect(handle.isClosed(), isTrue);
+      expect(() => MlsEngine.fromToken(token: token), throwsA(anything));
+    });
+  });
+}
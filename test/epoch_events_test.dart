@@ -0,0 +1,319 @@
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late TestIdentity aliceId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+  });
+
+  Future<List<int>> createGroup() async {
+    final result = await alice.createGroup(
+      config: defaultConfig(),
+      signerBytes: aliceId.signerBytes,
+      credentialIdentity: aliceId.credentialIdentity,
+      signerPublicKey: aliceId.publicKey,
+    );
+    return result.groupId;
+  }
+
+  group('epoch events', () {
+    test('self update emits event with registered exporter keys', () async {
+      final groupId = await createGroup();
+      alice.registerEpochExporter(
+        label: 'media',
+        context: [1, 2, 3],
+        keyLength: 32,
+      );
+      final events = alice.subscribeEpochEvents();
+      final first = events.first;
+
+      await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+
+      final event = await first;
+      expect(event.groupId, equals(groupId));
+      expect(event.epoch, BigInt.one);
+      expect(event.exportedKeys, hasLength(1));
+      expect(event.exportedKeys.first.label, 'media');
+
+      final expected = await alice.exportSecret(
+        groupIdBytes: groupId,
+        label: 'media',
+        context: [1, 2, 3],
+        keyLength: 32,
+      );
+      expect(event.exportedKeys.first.key, equals(expected));
+    });
+
+    test('unregistered exporter is not included', () async {
+      final groupId = await createGroup();
+      alice.registerEpochExporter(label: 'media', context: [], keyLength: 16);
+      alice.unregisterEpochExporter(label: 'media');
+      final first = alice.subscribeEpochEvents().first;
+
+      await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+
+      final event = await first;
+      expect(event.exportedKeys, isEmpty);
+    });
+  });
+
+  group('epoch event throttle', () {
+    test('unthrottled events are not coalesced', () async {
+      final groupId = await createGroup();
+      final first = alice.subscribeEpochEvents().first;
+
+      await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+
+      expect((await first).coalesced, 1);
+    });
+
+    test('bursts are merged into one event with a count', () async {
+      final groupId = await createGroup();
+      alice.setEventThrottle(
+        kind: MlsEventKind.epochAdvanced,
+        minIntervalMs: 60000,
+      );
+      final events = <EpochAdvancedEvent>[];
+      final subscription = alice.subscribeEpochEvents().listen(events.add);
+
+      for (var i = 0; i < 5; i++) {
+        await alice.selfUpdate(
+          groupIdBytes: groupId,
+          signerBytes: aliceId.signerBytes,
+          ensureGroupInfo: false,
+        );
+      }
+      await Future<void>.delayed(Duration.zero);
+      // The first event of the group goes out immediately.
+      expect(events, hasLength(1));
+      expect(events.first.epoch, BigInt.one);
+
+      alice.flushEvents();
+      await Future<void>.delayed(Duration.zero);
+      expect(events, hasLength(2));
+      expect(events.last.epoch, BigInt.from(5));
+      expect(events.last.coalesced, 4);
+
+      await subscription.cancel();
+    });
+
+    test('disabling the throttle delivers held-back events', () async {
+      final groupId = await createGroup();
+      alice.setEventThrottle(
+        kind: MlsEventKind.epochAdvanced,
+        minIntervalMs: 60000,
+      );
+      final events = <EpochAdvancedEvent>[];
+      final subscription = alice.subscribeEpochEvents().listen(events.add);
+
+      for (var i = 0; i < 3; i++) {
+        await alice.selfUpdate(
+          groupIdBytes: groupId,
+          signerBytes: aliceId.signerBytes,
+          ensureGroupInfo: false,
+        );
+      }
+      alice.setEventThrottle(
+        kind: MlsEventKind.epochAdvanced,
+        minIntervalMs: 0,
+      );
+      await Future<void>.delayed(Duration.zero);
+      expect(events.map((e) => e.coalesced), [1, 2]);
+
+      await subscription.cancel();
+    });
+
+    test('the last held-back event is delivered by a timer', () async {
+      final groupId = await createGroup();
+      alice.setEventThrottle(
+        kind: MlsEventKind.epochAdvanced,
+        minIntervalMs: 100,
+      );
+      final events = <EpochAdvancedEvent>[];
+      final subscription = alice.subscribeEpochEvents().listen(events.add);
+
+      for (var i = 0; i < 3; i++) {
+        await alice.selfUpdate(
+          groupIdBytes: groupId,
+          signerBytes: aliceId.signerBytes,
+          ensureGroupInfo: false,
+        );
+      }
+      await Future<void>.delayed(Duration.zero);
+      expect(events, hasLength(1));
+
+      await Future<void>.delayed(const Duration(milliseconds: 500));
+      expect(events, hasLength(2));
+      expect(events.last.epoch, BigInt.from(3));
+      expect(events.last.coalesced, 2);
+
+      await subscription.cancel();
+    });
+
+    test('member changes are coalesced separately from epochs', () async {
+      final groupId = await createGroup();
+      alice.setEventThrottle(
+        kind: MlsEventKind.memberChanged,
+        minIntervalMs: 60000,
+      );
+      final events = <GroupEvent>[];
+      final subscription = alice
+          .subscribeGroupEvents(groupIdBytes: groupId)
+          .listen(events.add);
+
+      Future<void> addMember(String name) async {
+        final engine = await createTestEngine();
+        final id = TestIdentity.create(name);
+        final kp = await engine.createKeyPackage(
+          ciphersuite: ciphersuite,
+          signerBytes: id.signerBytes,
+          credentialIdentity: id.credentialIdentity,
+          signerPublicKey: id.publicKey,
+        );
+        await alice.addMembers(
+          groupIdBytes: groupId,
+          signerBytes: aliceId.signerBytes,
+          keyPackagesBytes: [kp.keyPackageBytes],
+          ensureGroupInfo: false,
+        );
+        await alice.mergePendingCommit(groupIdBytes: groupId);
+        await engine.close();
+      }
+
+      await addMember('bob');
+      await addMember('charlie');
+      await addMember('dave');
+      await Future<void>.delayed(Duration.zero);
+      // Epochs are not throttled; only bob's addition went out at once.
+      expect(events.whereType<GroupEvent_EpochAdvanced>(), hasLength(3));
+      expect(events.whereType<GroupEvent_MemberAdded>(), hasLength(1));
+
+      alice.flushEvents();
+      await Future<void>.delayed(Duration.zero);
+      final added = events.whereType<GroupEvent_MemberAdded>().toList();
+      expect(added.map((e) => e.leafIndex), [1, 2, 3]);
+
+      await subscription.cancel();
+    });
+  });
+
+  group('group events', () {
+    test('reports added members, epochs and queued proposals', () async {
+      final groupId = await createGroup();
+      final events = <GroupEvent>[];
+      final subscription = alice
+          .subscribeGroupEvents(groupIdBytes: groupId)
+          .listen(events.add);
+
+      final bob = await createTestEngine();
+      final bobId = TestIdentity.create('bob');
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      await alice.addMembers(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupId);
+      final proposal = await alice.proposeRemove(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        memberIndex: 1,
+      );
+      await Future<void>.delayed(Duration.zero);
+
+      expect(events, hasLength(3));
+      final added = events[0] as GroupEvent_MemberAdded;
+      expect(added.leafIndex, 1);
+      expect(identityFromCredential(added.credential), bobId.credentialIdentity);
+      expect((events[1] as GroupEvent_EpochAdvanced).epoch, BigInt.one);
+      final queued = events[2] as GroupEvent_ProposalQueued;
+      expect(queued.proposalRef, equals(proposal.proposalRef));
+      expect(queued.senderIndex, 0);
+
+      await subscription.cancel();
+      await bob.close();
+    });
+
+    test('batch processing and Welcome joins emit events', () async {
+      final groupId = await createGroup();
+      final bob = await createTestEngine();
+      final bobId = TestIdentity.create('bob');
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final bobEvents = <GroupEvent>[];
+      final bobSubscription = bob
+          .subscribeGroupEvents(groupIdBytes: groupId)
+          .listen(bobEvents.add);
+      final add = await alice.addMembers(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupId);
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: add.welcome,
+        signerBytes: bobId.signerBytes,
+      );
+      await Future<void>.delayed(Duration.zero);
+      expect(bobEvents.whereType<GroupEvent_MemberAdded>(), hasLength(2));
+
+      final aliceEvents = <GroupEvent>[];
+      final aliceSubscription = alice
+          .subscribeGroupEvents(groupIdBytes: groupId)
+          .listen(aliceEvents.add);
+      final update = await bob.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: bobId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      await bob.mergePendingCommit(groupIdBytes: groupId);
+      final results = await alice.processMessagesBatch(
+        messagesBytes: [update.commit],
+      );
+      expect(results.single.error, isNull);
+      await Future<void>.delayed(Duration.zero);
+      expect(
+        aliceEvents.whereType<GroupEvent_EpochAdvanced>().single.epoch,
+        BigInt.two,
+      );
+
+      await aliceSubscription.cancel();
+      await bobSubscription.cancel();
+      await bob.close();
+    });
+  });
+}
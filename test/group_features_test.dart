@@ -0,0 +1,340 @@
+import 'dart:convert';
+import 'dart:typed_data';
+
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late TestIdentity aliceId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+  });
+
+  MlsCapabilities featureCapabilities() => MlsCapabilities(
+    versions: Uint16List(0),
+    ciphersuites: [],
+    extensions: [],
+    proposals: [],
+    credentials: [],
+    otherCiphersuites: Uint16List(0),
+    otherExtensions: Uint16List.fromList([groupFeaturesExtensionType()]),
+    otherProposals: Uint16List(0),
+    otherCredentials: Uint16List(0),
+  );
+
+  Future<List<int>> createFeatureGroup() async {
+    final result = await alice.createGroupWithBuilder(
+      config: defaultConfig(),
+      signerBytes: aliceId.signerBytes,
+      credentialIdentity: aliceId.credentialIdentity,
+      signerPublicKey: aliceId.publicKey,
+      capabilities: featureCapabilities(),
+    );
+    return result.groupId;
+  }
+
+  group('group features', () {
+    test('no features before first set', () async {
+      final groupId = await createFeatureGroup();
+      final features = await alice.groupFeatures(groupIdBytes: groupId);
+      expect(features, isNull);
+    });
+
+    test('set and read features', () async {
+      final groupId = await createFeatureGroup();
+      await alice.setGroupFeatures(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        features: MlsGroupFeatures(
+          features: BigInt.from(0x5),
+          authorizedSignatureKeys: [aliceId.publicKey],
+        ),
+        ensureGroupInfo: false,
+      );
+
+      final features = await alice.groupFeatures(groupIdBytes: groupId);
+      expect(features, isNotNull);
+      expect(features!.features, equals(BigInt.from(0x5)));
+      expect(features.authorizedSignatureKeys, hasLength(1));
+      expect(features.authorizedSignatureKeys.first, equals(aliceId.publicKey));
+      expect(await alice.groupEpoch(groupIdBytes: groupId), BigInt.one);
+    });
+
+    test('unauthorized member cannot change features', () async {
+      final groupId = await createFeatureGroup();
+      final other = TestIdentity.create('other');
+      await alice.setGroupFeatures(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        features: MlsGroupFeatures(
+          features: BigInt.one,
+          authorizedSignatureKeys: [other.publicKey],
+        ),
+        ensureGroupInfo: false,
+      );
+
+      expect(
+        () => alice.setGroupFeatures(
+          groupIdBytes: groupId,
+          signerBytes: aliceId.signerBytes,
+          features: MlsGroupFeatures(
+            features: BigInt.two,
+            authorizedSignatureKeys: [],
+          ),
+          ensureGroupInfo: false,
+        ),
+        throwsA(isA<Object>()),
+      );
+    });
+
+    test('unauthorized member cannot drop features via extensions', () async {
+      final groupId = await createFeatureGroup();
+      final other = TestIdentity.create('other');
+      await alice.setGroupFeatures(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        features: MlsGroupFeatures(
+          features: BigInt.one,
+          authorizedSignatureKeys: [other.publicKey],
+        ),
+        ensureGroupInfo: false,
+      );
+
+      await expectLater(
+        alice.updateGroupContextExtensions(
+          groupIdBytes: groupId,
+          signerBytes: aliceId.signerBytes,
+          extensions: [],
+          ensureGroupInfo: false,
+        ),
+        throwsA(
+          predicate<Object>(
+            (e) => e.toString().contains('Not authorized to change group'),
+          ),
+        ),
+      );
+      await expectLater(
+        alice.proposeGroupContextExtensions(
+          groupIdBytes: groupId,
+          signerBytes: aliceId.signerBytes,
+          extensions: [],
+        ),
+        throwsA(isA<Object>()),
+      );
+      expect(await alice.groupEpoch(groupIdBytes: groupId), BigInt.one);
+      final features = await alice.groupFeatures(groupIdBytes: groupId);
+      expect(features!.features, equals(BigInt.one));
+    });
+  });
+
+  group('message compression', () {
+    late MlsEngine bob;
+    late TestIdentity bobId;
+    late Uint8List groupId;
+
+    setUp(() async {
+      bob = await createTestEngine();
+      bobId = TestIdentity.create('bob');
+      groupId = Uint8List.fromList(await createFeatureGroup());
+      final bobKp = await bob.createKeyPackageWithOptions(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+        options: KeyPackageOptions(
+          lastResort: false,
+          capabilities: featureCapabilities(),
+        ),
+      );
+      final add = await alice.addMembers(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: add.welcome,
+        signerBytes: bobId.signerBytes,
+      );
+    });
+
+    Future<void> enableCompression() async {
+      final commit = await alice.setGroupFeatures(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        features: MlsGroupFeatures(
+          features: groupFeatureCompression(),
+          authorizedSignatureKeys: [],
+        ),
+        ensureGroupInfo: false,
+      );
+      await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: commit.commit,
+      );
+    }
+
+    test('is off unless the feature bit is set', () async {
+      final sent = await alice.createMessage(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList(utf8.encode('hi')),
+      );
+      expect(sent.compression, isNull);
+      final received = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: sent.ciphertext,
+      );
+      expect(received.compression, isNull);
+    });
+
+    test('compresses and transparently decompresses', () async {
+      await enableCompression();
+      final payload = Uint8List.fromList(
+        utf8.encode(jsonEncode(List.filled(200, {'key': 'value'}))),
+      );
+      final sent = await alice.createMessage(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        message: payload,
+      );
+      expect(sent.compression!.compressed, isTrue);
+      expect(
+        sent.compression!.originalSize,
+        equals(BigInt.from(payload.length)),
+      );
+      expect(
+        sent.compression!.compressedSize,
+        lessThan(sent.compression!.originalSize),
+      );
+
+      final received = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: sent.ciphertext,
+      );
+      expect(received.applicationMessage, equals(payload));
+      expect(received.compression!.compressed, isTrue);
+      expect(
+        received.compression!.compressedSize,
+        equals(sent.compression!.compressedSize),
+      );
+    });
+
+    test('sends incompressible messages uncompressed', () async {
+      await enableCompression();
+      final payload = Uint8List.fromList([42]);
+      final sent = await alice.createMessage(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        message: payload,
+      );
+      expect(sent.compression!.compressed, isFalse);
+      expect(sent.compression!.compressedSize, equals(BigInt.two));
+
+      final received = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: sent.ciphertext,
+      );
+      expect(received.applicationMessage, equals(payload));
+    });
+
+    test('decodes past-epoch messages with that epoch\'s flag', () async {
+      await bob.setMaxPastEpochs(groupIdBytes: groupId, maxPastEpochs: 2);
+      final payload = Uint8List.fromList(utf8.encode('sent before'));
+      final sent = await alice.createMessage(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        message: payload,
+      );
+      expect(sent.compression, isNull);
+      await enableCompression();
+
+      final received = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: sent.ciphertext,
+      );
+      expect(received.applicationMessage, equals(payload));
+      expect(received.compression, isNull);
+    });
+  });
+
+  group('add compatibility', () {
+    late Uint8List groupId;
+
+    setUp(() async {
+      groupId = Uint8List.fromList(await createFeatureGroup());
+      await alice.setGroupFeatures(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        features: MlsGroupFeatures(
+          features: BigInt.one,
+          authorizedSignatureKeys: [],
+        ),
+        ensureGroupInfo: false,
+      );
+    });
+
+    Future<Uint8List> keyPackage(String name, {bool features = true}) async {
+      final engine = await createTestEngine();
+      final id = TestIdentity.create(name);
+      final kp = await engine.createKeyPackageWithOptions(
+        ciphersuite: ciphersuite,
+        signerBytes: id.signerBytes,
+        credentialIdentity: id.credentialIdentity,
+        signerPublicKey: id.publicKey,
+        options: KeyPackageOptions(
+          lastResort: false,
+          capabilities: features ? featureCapabilities() : null,
+        ),
+      );
+      return kp.keyPackageBytes;
+    }
+
+    test('lists each incompatible candidate', () async {
+      final failures = await alice.checkAddCompatibility(
+        groupIdBytes: groupId,
+        keyPackagesBytes: [
+          await keyPackage('bob'),
+          await keyPackage('carol', features: false),
+        ],
+      );
+      expect(failures, hasLength(1));
+      expect(failures.single.index, 1);
+      expect(
+        failures.single.missingExtensions,
+        equals([groupFeaturesExtensionType()]),
+      );
+      expect(failures.single.missingProposals, isEmpty);
+      expect(failures.single.missingCredentials, isEmpty);
+    });
+
+    test('add fails before building a commit', () async {
+      final candidates = [
+        await keyPackage('bob'),
+        await keyPackage('carol', features: false),
+      ];
+      await expectLater(
+        alice.addMembers(
+          groupIdBytes: groupId,
+          signerBytes: aliceId.signerBytes,
+          keyPackagesBytes: candidates,
+          ensureGroupInfo: false,
+        ),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('key package 1')),
+        ),
+      );
+      expect(await alice.groupEpoch(groupIdBytes: groupId), BigInt.one);
+    });
+  });
+}
@@ -81,6 +81,7 @@ void main() {
         groupIdBytes: groupResult.groupId,
         signerBytes: aliceId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await alice.mergePendingCommit(groupIdBytes: groupResult.groupId);
 
@@ -92,6 +93,215 @@ void main() {
       expect(info.groupId, equals(groupResult.groupId));
       expect(info.ciphersuite, equals(ciphersuite));
       expect(info.epoch, equals(BigInt.from(1)));
+      expect(info.pskCount, 0);
+      expect(info.psks, isEmpty);
+      expect(info.hasRatchetTree, isTrue);
+      expect(info.memberCount, 2);
+      expect(
+        info.members.map((m) => m.identity),
+        [aliceId.credentialIdentity, bobId.credentialIdentity],
+      );
+      expect(info.members.map((m) => m.leafIndex), [0, 1]);
+      expect(info.members.first.credentialType, 1);
+    });
+  });
+
+  group('staged welcome', () {
+    Future<(Uint8List, Uint8List)> invite() async {
+      final groupResult = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final addResult = await alice.addMembers(
+        groupIdBytes: groupResult.groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupResult.groupId);
+      return (groupResult.groupId, addResult.welcome);
+    }
+
+    test('accepted welcome joins the group', () async {
+      final (groupId, welcome) = await invite();
+
+      final staged = await bob.stageWelcome(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+      );
+      expect(staged.preview.groupId, equals(groupId));
+      // Nothing is joined before the welcome is accepted.
+      await expectLater(
+        bob.groupIsActive(groupIdBytes: groupId),
+        throwsA(anything),
+      );
+      final pending = await bob.stagedWelcomes();
+      expect(pending.map((w) => w.stagedId), [staged.stagedId]);
+
+      final joined = await bob.completeStagedWelcome(
+        stagedId: staged.stagedId,
+        config: defaultConfig(),
+        signerBytes: bobId.signerBytes,
+      );
+      expect(joined.groupId, equals(groupId));
+      expect(await bob.groupMembers(groupIdBytes: groupId), hasLength(2));
+      expect(await bob.stagedWelcomes(), isEmpty);
+    });
+
+    test('declined welcome deletes its key package', () async {
+      final (_, welcome) = await invite();
+      final staged = await bob.stageWelcome(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+      );
+
+      expect(await bob.discardStagedWelcome(stagedId: staged.stagedId), isTrue);
+      expect(await bob.discardStagedWelcome(stagedId: staged.stagedId), isFalse);
+      expect(await bob.stagedWelcomes(), isEmpty);
+      await expectLater(
+        bob.joinGroupFromWelcome(
+          config: defaultConfig(),
+          welcomeBytes: welcome,
+          signerBytes: bobId.signerBytes,
+        ),
+        throwsA(anything),
+      );
+    });
+  });
+
+  group('self heal', () {
+    test('rejoins by external commit and is rate limited', () async {
+      final groupResult = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final groupIdBytes = groupResult.groupId;
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final addResult = await alice.addMembers(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: addResult.welcome,
+        signerBytes: bobId.signerBytes,
+      );
+
+      // Alice moves on without Bob seeing the commit.
+      await alice.selfUpdate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      final groupInfo = await alice.exportGroupInfo(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+      );
+      final ratchetTree = await alice.exportRatchetTree(
+        groupIdBytes: groupIdBytes,
+      );
+
+      final healed = await bob.selfHeal(
+        groupIdBytes: groupIdBytes,
+        groupInfoBytes: groupInfo,
+        ratchetTreeBytes: ratchetTree,
+        signerBytes: bobId.signerBytes,
+      );
+      expect(healed.archivedEpoch, BigInt.one);
+      expect(
+        (await bob.quarantinedGroups()).map((g) => g.groupId),
+        [groupIdBytes],
+      );
+
+      await alice.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: healed.join.commit,
+      );
+      expect(
+        await bob.groupEpoch(groupIdBytes: groupIdBytes),
+        await alice.groupEpoch(groupIdBytes: groupIdBytes),
+      );
+      expect(await alice.groupMembers(groupIdBytes: groupIdBytes), hasLength(2));
+
+      await expectLater(
+        bob.selfHeal(
+          groupIdBytes: groupIdBytes,
+          groupInfoBytes: groupInfo,
+          ratchetTreeBytes: ratchetTree,
+          signerBytes: bobId.signerBytes,
+        ),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('rate limited')),
+        ),
+      );
+    });
+
+    test('a failed rejoin changes nothing and is not rate limited', () async {
+      final groupResult = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final groupIdBytes = groupResult.groupId;
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final addResult = await alice.addMembers(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: addResult.welcome,
+        signerBytes: bobId.signerBytes,
+      );
+      final groupInfo = await alice.exportGroupInfo(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+      );
+
+      await expectLater(
+        bob.selfHeal(
+          groupIdBytes: groupIdBytes,
+          groupInfoBytes: groupInfo,
+          signerBytes: Uint8List(0),
+        ),
+        throwsA(anything),
+      );
+      expect(await bob.quarantinedGroups(), isEmpty);
+      expect(await bob.groupEpoch(groupIdBytes: groupIdBytes), BigInt.one);
+
+      final healed = await bob.selfHeal(
+        groupIdBytes: groupIdBytes,
+        groupInfoBytes: groupInfo,
+        signerBytes: bobId.signerBytes,
+      );
+      expect(healed.archivedEpoch, BigInt.one);
     });
   });
 
@@ -116,6 +326,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
 
@@ -132,6 +343,77 @@ void main() {
     });
   });
 
+  group('pending joins', () {
+    test('failed welcome joins are kept for retry', () async {
+      final config = MlsGroupConfig(
+        ciphersuite: ciphersuite,
+        wireFormatPolicy: MlsWireFormatPolicy.ciphertext,
+        useRatchetTreeExtension: false,
+        maxPastEpochs: 0,
+        paddingSize: 0,
+        senderRatchetMaxOutOfOrder: 5,
+        senderRatchetMaxForwardDistance: 1000,
+        numberOfResumptionPsks: 0,
+      );
+      final groupResult = await alice.createGroup(
+        config: config,
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final addResult = await alice.addMembers(
+        groupIdBytes: groupResult.groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupResult.groupId);
+
+      bob.setFailedJoinRetention(enabled: true);
+      // Without the ratchet tree extension the tree must be passed along.
+      await expectLater(
+        bob.joinGroupFromWelcome(
+          config: config,
+          welcomeBytes: addResult.welcome,
+          signerBytes: bobId.signerBytes,
+        ),
+        throwsA(anything),
+      );
+      // Malformed input is never retried.
+      await expectLater(
+        bob.joinGroupFromWelcome(
+          config: config,
+          welcomeBytes: Uint8List.fromList([1, 2, 3]),
+          signerBytes: bobId.signerBytes,
+        ),
+        throwsA(anything),
+      );
+
+      final pending = await bob.pendingJoins();
+      expect(pending, hasLength(1));
+      expect(pending.single.kind, MlsPendingJoinKind.welcome);
+      expect(pending.single.groupId, equals(groupResult.groupId));
+      expect(pending.single.attempts, 1);
+
+      final retried = await bob.retryPendingJoins(
+        config: config,
+        signerBytes: bobId.signerBytes,
+      );
+      expect(retried.single.joined, isNull);
+      expect(retried.single.error, isNotNull);
+      expect((await bob.pendingJoins()).single.attempts, 2);
+
+      expect(await bob.discardPendingJoin(id: pending.single.id), isTrue);
+      expect(await bob.pendingJoins(), isEmpty);
+    });
+  });
+
   group('external commit join', () {
     late Uint8List groupIdBytes;
 
@@ -171,13 +453,68 @@ void main() {
         messageBytes: joinResult.commit,
       );
       expect(processed.messageType, ProcessedMessageType.stagedCommit);
+      final externalJoin = processed.stagedCommitInfo!.externalJoin!;
+      expect(externalJoin.signatureKey, equals(bobId.publicKey));
+      expect(
+        credentialInfo(credentialBytes: externalJoin.credential).identity,
+        equals(bobId.credentialIdentity),
+      );
+      expect(externalJoin.kemOutput, isNotEmpty);
+      expect(externalJoin.replacedIndex, isNull);
+      expect(externalJoin.replacedHandle, isNull);
 
       final aliceMembers = await alice.groupMembers(groupIdBytes: groupIdBytes);
       final bobMembers = await bob.groupMembers(groupIdBytes: groupIdBytes);
       expect(aliceMembers, hasLength(2));
+      expect(externalJoin.memberHandle, equals(aliceMembers[1].memberHandle));
       expect(bobMembers, hasLength(2));
     });
 
+    test('external commit resync replaces the old leaf', () async {
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      await alice.addMembers(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      final oldLeaf = (await alice.groupMembers(groupIdBytes: groupIdBytes))
+          .singleWhere((m) => m.index != 0);
+
+      // Bob lost his state and rejoins with the same identity.
+      final restored = await createTestEngine();
+      final joinResult = await restored.joinGroupExternalCommit(
+        config: defaultConfig(),
+        groupInfoBytes: await alice.exportGroupInfo(
+          groupIdBytes: groupIdBytes,
+          signerBytes: aliceId.signerBytes,
+        ),
+        ratchetTreeBytes: await alice.exportRatchetTree(
+          groupIdBytes: groupIdBytes,
+        ),
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+
+      final processed = await alice.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: joinResult.commit,
+      );
+      final externalJoin = processed.stagedCommitInfo!.externalJoin!;
+      expect(externalJoin.replacedIndex, oldLeaf.index);
+      expect(externalJoin.replacedHandle, equals(oldLeaf.memberHandle));
+      expect(
+        await alice.groupMembers(groupIdBytes: groupIdBytes),
+        hasLength(2),
+      );
+    });
+
     test('join group via external commit v2', () async {
       final groupInfo = await alice.exportGroupInfo(
         groupIdBytes: groupIdBytes,
@@ -208,6 +545,50 @@ void main() {
       final aliceMembers = await alice.groupMembers(groupIdBytes: groupIdBytes);
       expect(aliceMembers, hasLength(2));
     });
+
+    test('external commit v2 sets leaf capabilities and extensions', () async {
+      final groupInfo = await alice.exportGroupInfo(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+      );
+      final ratchetTree = await alice.exportRatchetTree(
+        groupIdBytes: groupIdBytes,
+      );
+
+      await bob.joinGroupExternalCommitV2(
+        config: defaultConfig(),
+        groupInfoBytes: groupInfo,
+        ratchetTreeBytes: ratchetTree,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+        skipLifetimeValidation: false,
+        leafNodeCapabilities: MlsCapabilities(
+          versions: Uint16List.fromList([1]),
+          ciphersuites: [ciphersuite],
+          extensions: [],
+          proposals: [],
+          credentials: [],
+          otherCiphersuites: Uint16List(0),
+          otherExtensions: Uint16List.fromList([0xFF01]),
+          otherProposals: Uint16List(0),
+          otherCredentials: Uint16List(0),
+        ),
+        leafNodeExtensions: [
+          MlsExtension(
+            extensionType: 0xFF01,
+            data: Uint8List.fromList([1, 2, 3]),
+          ),
+        ],
+      );
+
+      final leaf = await bob.groupOwnLeafNode(groupIdBytes: groupIdBytes);
+      expect(leaf.capabilities.otherExtensions, contains(0xFF01));
+      expect(
+        leaf.extensions.map((e) => e.extensionType),
+        contains(0xFF01),
+      );
+    });
   });
 
   group('post-quantum ciphersuite (X-Wing)', () {
@@ -249,6 +630,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceXId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
 
@@ -404,6 +786,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceXId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await aliceFs.mergePendingCommit(groupIdBytes: groupIdBytes);
       await bob.joinGroupFromWelcome(
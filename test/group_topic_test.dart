@@ -0,0 +1,138 @@
+import 'dart:typed_data';
+
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late MlsEngine bob;
+  late TestIdentity aliceId;
+  late TestIdentity bobId;
+  late Uint8List groupId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  MlsCapabilities topicCapabilities() => MlsCapabilities(
+    versions: Uint16List(0),
+    ciphersuites: [],
+    extensions: [],
+    proposals: [],
+    credentials: [],
+    otherCiphersuites: Uint16List(0),
+    otherExtensions: Uint16List.fromList([groupTopicExtensionType()]),
+    otherProposals: Uint16List(0),
+    otherCredentials: Uint16List(0),
+  );
+
+  setUp(() async {
+    alice = await createTestEngine();
+    bob = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    bobId = TestIdentity.create('bob');
+    final result = await alice.createGroupWithBuilder(
+      config: defaultConfig(),
+      signerBytes: aliceId.signerBytes,
+      credentialIdentity: aliceId.credentialIdentity,
+      signerPublicKey: aliceId.publicKey,
+      capabilities: topicCapabilities(),
+    );
+    groupId = result.groupId;
+    final bobKp = await bob.createKeyPackageWithOptions(
+      ciphersuite: ciphersuite,
+      signerBytes: bobId.signerBytes,
+      credentialIdentity: bobId.credentialIdentity,
+      signerPublicKey: bobId.publicKey,
+      options: KeyPackageOptions(
+        lastResort: false,
+        capabilities: topicCapabilities(),
+      ),
+    );
+    final add = await alice.addMembers(
+      groupIdBytes: groupId,
+      signerBytes: aliceId.signerBytes,
+      keyPackagesBytes: [bobKp.keyPackageBytes],
+      ensureGroupInfo: false,
+    );
+    await bob.joinGroupFromWelcome(
+      config: defaultConfig(),
+      welcomeBytes: add.welcome,
+      signerBytes: bobId.signerBytes,
+    );
+  });
+
+  group('group topic', () {
+    test('no topic before first set', () async {
+      expect(await alice.groupTopic(groupIdBytes: groupId), isNull);
+    });
+
+    test('members converge on the committed topic', () async {
+      final commit = await alice.setGroupTopic(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        topic: 'Weekend hike 🥾',
+        ensureGroupInfo: false,
+      );
+      expect(await alice.groupTopic(groupIdBytes: groupId), 'Weekend hike 🥾');
+
+      await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: commit.commit,
+      );
+      expect(await bob.groupTopic(groupIdBytes: groupId), 'Weekend hike 🥾');
+    });
+
+    test('a new topic replaces the previous one', () async {
+      await alice.setGroupTopic(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        topic: 'first',
+        ensureGroupInfo: false,
+      );
+      await alice.setGroupTopic(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        topic: 'second',
+        ensureGroupInfo: false,
+      );
+      expect(await alice.groupTopic(groupIdBytes: groupId), 'second');
+      expect(await alice.groupEpoch(groupIdBytes: groupId), BigInt.from(3));
+    });
+
+    test('removing the topic', () async {
+      await alice.setGroupTopic(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        topic: 'temporary',
+        ensureGroupInfo: false,
+      );
+      await alice.setGroupTopic(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      expect(await alice.groupTopic(groupIdBytes: groupId), isNull);
+    });
+
+    test('requires members to advertise the extension', () async {
+      final plain = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      await expectLater(
+        alice.setGroupTopic(
+          groupIdBytes: plain.groupId,
+          signerBytes: aliceId.signerBytes,
+          topic: 'nope',
+          ensureGroupInfo: false,
+        ),
+        throwsA(anything),
+      );
+    });
+  });
+}
@@ -0,0 +1,173 @@
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late MlsEngine bob;
+  late TestIdentity aliceId;
+  late TestIdentity bobId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    bob = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    bobId = TestIdentity.create('bob');
+  });
+
+  BigInt secondsAgo(int seconds) => BigInt.from(
+    DateTime.now().millisecondsSinceEpoch ~/ 1000 - seconds,
+  );
+
+  group('handshake expiry', () {
+    test('policy is unset by default and can be cleared', () {
+      expect(alice.handshakeExpiryPolicy(), isNull);
+      alice.setHandshakeExpiryPolicy(
+        policy: HandshakeExpiryPolicy(
+          maxCommitAgeSecs: BigInt.from(60),
+          clockSkewSecs: BigInt.from(5),
+          timestampInAad: false,
+        ),
+      );
+      final policy = alice.handshakeExpiryPolicy()!;
+      expect(policy.maxCommitAgeSecs, BigInt.from(60));
+      expect(policy.maxWelcomeAgeSecs, isNull);
+
+      alice.setHandshakeExpiryPolicy();
+      expect(alice.handshakeExpiryPolicy(), isNull);
+    });
+
+    test('stale welcome is refused without consuming state', () async {
+      final (groupId, welcome) = await addBob(
+        alice,
+        aliceId,
+        bob,
+        bobId,
+        join: false,
+        lastResort: true,
+      );
+      bob.setHandshakeExpiryPolicy(
+        policy: HandshakeExpiryPolicy(
+          maxWelcomeAgeSecs: BigInt.from(3600),
+          clockSkewSecs: BigInt.from(60),
+          timestampInAad: false,
+        ),
+      );
+
+      final refused = await bob.joinGroupFromWelcomeWithPolicy(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+        onExisting: ExistingGroupPolicy.abort,
+        sentAt: secondsAgo(7200),
+      );
+      expect(refused.joined, isFalse);
+      expect(refused.groupId, equals(groupId));
+      expect(refused.expired!.maxAgeSecs, BigInt.from(3660));
+      expect(refused.expired!.ageSecs >= BigInt.from(7200), isTrue);
+
+      final joined = await bob.joinGroupFromWelcomeWithPolicy(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+        onExisting: ExistingGroupPolicy.abort,
+        sentAt: secondsAgo(3620),
+      );
+      expect(joined.joined, isTrue);
+      expect(joined.expired, isNull);
+    });
+
+    test('stale commit is reported as expired and not applied', () async {
+      final (groupId, welcome) = await addBob(
+        alice,
+        aliceId,
+        bob,
+        bobId,
+        join: false,
+        lastResort: true,
+      );
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+      );
+      bob.setHandshakeExpiryPolicy(
+        policy: HandshakeExpiryPolicy(
+          maxCommitAgeSecs: BigInt.from(60),
+          clockSkewSecs: BigInt.zero,
+          timestampInAad: false,
+        ),
+      );
+      final commit = await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+
+      final sentAt = secondsAgo(600);
+      final result = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: commit.commit,
+        sentAt: sentAt,
+      );
+      expect(result.messageType, ProcessedMessageType.expired);
+      expect(result.senderIndex, 0);
+      expect(result.expired!.sentAt, sentAt);
+      expect(await bob.groupEpoch(groupIdBytes: groupId), BigInt.one);
+
+      final applied = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: commit.commit,
+        sentAt: secondsAgo(10),
+      );
+      expect(applied.messageType, ProcessedMessageType.stagedCommit);
+      expect(await bob.groupEpoch(groupIdBytes: groupId), BigInt.two);
+    });
+
+    test('commit can be dated by its AAD', () async {
+      final (groupId, welcome) = await addBob(
+        alice,
+        aliceId,
+        bob,
+        bobId,
+        join: false,
+        lastResort: true,
+      );
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+      );
+      bob.setHandshakeExpiryPolicy(
+        policy: HandshakeExpiryPolicy(
+          maxCommitAgeSecs: BigInt.from(60),
+          clockSkewSecs: BigInt.zero,
+          timestampInAad: true,
+        ),
+      );
+      final sentAt = secondsAgo(600);
+      final stamp = ByteData(8)..setUint64(0, sentAt.toInt(), Endian.big);
+      await alice.setDefaultAad(
+        groupIdBytes: groupId,
+        aad: stamp.buffer.asUint8List(),
+      );
+      final commit = await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+
+      final result = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: commit.commit,
+      );
+      expect(result.messageType, ProcessedMessageType.expired);
+      expect(result.expired!.sentAt, sentAt);
+    });
+  });
+}
@@ -0,0 +1,126 @@
+import 'dart:typed_data';
+
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late MlsEngine bob;
+  late TestIdentity aliceId;
+  late TestIdentity bobId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    bob = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    bobId = TestIdentity.create('bob');
+  });
+
+  Future<MlsBundledInvite> inviteBob() async {
+    final groupResult = await alice.createGroup(
+      config: defaultConfig(),
+      signerBytes: aliceId.signerBytes,
+      credentialIdentity: aliceId.credentialIdentity,
+      signerPublicKey: aliceId.publicKey,
+    );
+    final bobKp = await bob.createKeyPackage(
+      ciphersuite: ciphersuite,
+      signerBytes: bobId.signerBytes,
+      credentialIdentity: bobId.credentialIdentity,
+      signerPublicKey: bobId.publicKey,
+    );
+    final addResult = await alice.addMembers(
+      groupIdBytes: groupResult.groupId,
+      signerBytes: aliceId.signerBytes,
+      keyPackagesBytes: [bobKp.keyPackageBytes],
+      ensureGroupInfo: false,
+    );
+    return MlsBundledInvite(
+      groupId: groupResult.groupId,
+      welcome: addResult.welcome,
+    );
+  }
+
+  group('invite bundles', () {
+    test('round-trips through bundle and unbundle', () {
+      final invites = [
+        MlsBundledInvite(
+          groupId: Uint8List.fromList([1]),
+          welcome: Uint8List.fromList([2, 3]),
+        ),
+        MlsBundledInvite(
+          groupId: Uint8List.fromList([4]),
+          welcome: Uint8List.fromList([5]),
+          ratchetTree: Uint8List.fromList([6, 7, 8]),
+        ),
+      ];
+      final unbundled = unbundleInvites(
+        bundleBytes: bundleInvites(invites: invites),
+      );
+      expect(unbundled.length, 2);
+      expect(unbundled[0].groupId, equals([1]));
+      expect(unbundled[0].welcome, equals([2, 3]));
+      expect(unbundled[0].ratchetTree, isNull);
+      expect(unbundled[1].ratchetTree, equals([6, 7, 8]));
+    });
+
+    test('rejects an unknown bundle version', () {
+      expect(
+        () => unbundleInvites(bundleBytes: Uint8List.fromList([9])),
+        throwsA(anything),
+      );
+    });
+
+    test('joins every group in the bundle', () async {
+      final first = await inviteBob();
+      final second = await inviteBob();
+      final bundle = bundleInvites(invites: [first, second]);
+
+      final results = await bob.joinAllFromBundle(
+        config: defaultConfig(),
+        bundleBytes: bundle,
+        signerBytes: bobId.signerBytes,
+      );
+      expect(results.length, 2);
+      for (final result in results) {
+        expect(result.error, isNull);
+        expect(result.joined, isNotNull);
+      }
+      expect(results[0].groupId, equals(first.groupId));
+      expect(results[1].groupId, equals(second.groupId));
+
+      for (final invite in [first, second]) {
+        final members = await bob.groupMembers(groupIdBytes: invite.groupId);
+        expect(members.length, 2);
+      }
+    });
+
+    test('reports a failing group without affecting the others', () async {
+      final good = await inviteBob();
+      final bad = MlsBundledInvite(
+        groupId: Uint8List.fromList([0xff]),
+        welcome: Uint8List.fromList([0, 1, 2]),
+      );
+      final bundle = bundleInvites(invites: [bad, good]);
+
+      final results = await bob.joinAllFromBundle(
+        config: defaultConfig(),
+        bundleBytes: bundle,
+        signerBytes: bobId.signerBytes,
+      );
+      expect(results[0].joined, isNull);
+      expect(results[0].error, isNotNull);
+      expect(results[1].error, isNull);
+      expect(
+        await bob.groupEpoch(groupIdBytes: good.groupId),
+        BigInt.one,
+      );
+    });
+  });
+}
@@ -0,0 +1,109 @@
+import 'dart:typed_data';
+
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late MlsEngine bob;
+  late TestIdentity aliceId;
+  late TestIdentity bobId;
+  late Uint8List groupIdBytes;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    bob = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    bobId = TestIdentity.create('bob');
+    final result = await alice.createGroup(
+      config: defaultConfig(),
+      signerBytes: aliceId.signerBytes,
+      credentialIdentity: aliceId.credentialIdentity,
+      signerPublicKey: aliceId.publicKey,
+    );
+    groupIdBytes = result.groupId;
+  });
+
+  Future<Uint8List> createInvite({bool includeRatchetTree = true}) =>
+      alice.createInvitePayload(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        expiresInSeconds: BigInt.from(3600),
+        includeRatchetTree: includeRatchetTree,
+      );
+
+  Future<ExternalJoinResult> bobJoins(
+    Uint8List payload, {
+    Uint8List? ratchetTreeBytes,
+    Uint8List? expectedInviterKey,
+  }) => bob.joinFromInvitePayload(
+    config: defaultConfig(),
+    payloadBytes: payload,
+    ratchetTreeBytes: ratchetTreeBytes,
+    signerBytes: bobId.signerBytes,
+    credentialIdentity: bobId.credentialIdentity,
+    signerPublicKey: bobId.publicKey,
+    expectedInviterKey: expectedInviterKey,
+  );
+
+  group('invite payload', () {
+    test('joins the group via external commit', () async {
+      final payload = await createInvite();
+      final joinResult = await bobJoins(
+        payload,
+        expectedInviterKey: aliceId.publicKey,
+      );
+      expect(joinResult.groupId, equals(groupIdBytes));
+
+      await alice.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: joinResult.commit,
+      );
+      final members = await alice.groupMembers(groupIdBytes: groupIdBytes);
+      expect(members, hasLength(2));
+    });
+
+    test('payload without tree uses the supplied tree', () async {
+      final payload = await createInvite(includeRatchetTree: false);
+      final tree = await alice.exportRatchetTree(groupIdBytes: groupIdBytes);
+      final joinResult = await bobJoins(payload, ratchetTreeBytes: tree);
+      expect(joinResult.groupId, equals(groupIdBytes));
+    });
+
+    test('rejects a tampered payload', () async {
+      final payload = Uint8List.fromList(await createInvite());
+      payload[payload.length - 1] ^= 0xff;
+      await expectLater(bobJoins(payload), throwsA(isA<Object>()));
+      expect(
+        () => bob.groupIsActive(groupIdBytes: groupIdBytes),
+        throwsA(isA<Object>()),
+      );
+    });
+
+    test('rejects an unexpected inviter', () async {
+      final payload = await createInvite();
+      expect(
+        () => bobJoins(payload, expectedInviterKey: bobId.publicKey),
+        throwsA(isA<Object>()),
+      );
+    });
+
+    test('rejects a signer that is not the own leaf', () async {
+      expect(
+        () => alice.createInvitePayload(
+          groupIdBytes: groupIdBytes,
+          signerBytes: bobId.signerBytes,
+          expiresInSeconds: BigInt.from(3600),
+          includeRatchetTree: true,
+        ),
+        throwsA(isA<Object>()),
+      );
+    });
+  });
+}
@@ -1,3 +1,5 @@
+import 'dart:typed_data';
+
 import 'package:openmls/openmls.dart';
 import 'package:test/test.dart';
 
@@ -48,6 +50,63 @@ void main() {
       expect(reconstructed.publicKey(), equals(pubKey));
       expect(reconstructed.privateKey(), equals(privKey));
     });
+
+    test('engine generates and stores a signer', () async {
+      final generated = await alice.generateSignatureKeyPair(
+        ciphersuite: ciphersuite,
+      );
+      expect(generated.publicKey, isNotEmpty);
+
+      final group = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: generated.signerHandle,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: generated.publicKey,
+      );
+      expect(group.groupId, isNotEmpty);
+
+      final stored = await alice.storedSigner(
+        ciphersuite: ciphersuite,
+        publicKey: generated.publicKey,
+      );
+      expect(stored, isNotNull);
+      expect(
+        alice.registerSigner(signerBytes: stored!),
+        equals(generated.signerHandle),
+      );
+      expect(
+        await alice.storedSigner(
+          ciphersuite: ciphersuite,
+          publicKey: aliceId.publicKey,
+        ),
+        isNull,
+      );
+    });
+
+    test('registered signer handle replaces signer bytes', () async {
+      final handle = alice.registerSigner(signerBytes: aliceId.signerBytes);
+      expect(handle, equals(aliceId.publicKey));
+
+      final result = await alice.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: handle,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      expect(result.keyPackageBytes, isNotEmpty);
+
+      expect(alice.unregisterSigner(handle: handle), isTrue);
+      expect(alice.unregisterSigner(handle: handle), isFalse);
+      await expectLater(
+        alice.createKeyPackage(
+          ciphersuite: ciphersuite,
+          signerBytes: handle,
+          credentialIdentity: aliceId.credentialIdentity,
+          signerPublicKey: aliceId.publicKey,
+        ),
+        throwsA(predicate<Object>((e) => e.toString().contains('signer'))),
+      );
+    });
   });
 
   group('key packages', () {
@@ -75,6 +134,146 @@ void main() {
       );
       expect(result.keyPackageBytes, isNotEmpty);
     });
+
+    test('stamps key packages with creation time and origin', () async {
+      final before = DateTime.now().millisecondsSinceEpoch ~/ 1000;
+      final result = await alice.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+        origin: 'device-1',
+      );
+      expect(result.origin, equals('device-1'));
+      expect(result.createdAt.toInt(), greaterThanOrEqualTo(before));
+      expect(
+        result.keyPackageRef,
+        equals(keyPackageRef(keyPackageBytes: result.keyPackageBytes)),
+      );
+
+      final listed = await alice.listKeyPackages();
+      expect(listed, hasLength(1));
+      expect(listed.single.keyPackageRef, equals(result.keyPackageRef));
+      expect(listed.single.createdAt, equals(result.createdAt));
+      expect(listed.single.origin, equals('device-1'));
+      expect(listed.single.lastResort, isFalse);
+    });
+
+    test('keeps the metadata of earlier key packages', () async {
+      Future<KeyPackageResult> create(String origin) => alice.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+        origin: origin,
+      );
+      final first = await create('device-1');
+      final second = await create('device-2');
+
+      final listed = await alice.listKeyPackages();
+      expect(listed, hasLength(2));
+      for (final created in [first, second]) {
+        final info = listed.singleWhere((k) => k.origin == created.origin);
+        expect(info.keyPackageRef, equals(created.keyPackageRef));
+        expect(info.createdAt, equals(created.createdAt));
+      }
+    });
+
+    test('lists lifetime, ciphersuite and credential', () async {
+      final short = await alice.createKeyPackageWithOptions(
+        ciphersuite: ciphersuite,
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+        options: KeyPackageOptions(lifetimeSeconds: BigInt.from(3600)),
+      );
+      await alice.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+
+      final listed = await alice.listKeyPackages();
+      expect(listed, hasLength(2));
+      for (final info in listed) {
+        expect(info.ciphersuite, equals(ciphersuite));
+        expect(info.credentialIdentity, equals(aliceId.credentialIdentity));
+        expect(info.notAfter, greaterThan(info.notBefore));
+      }
+
+      final expiring = await alice.expiringKeyPackages(
+        withinSeconds: BigInt.from(86400),
+      );
+      expect(expiring.map((k) => k.keyPackageRef), [short.keyPackageRef]);
+    });
+
+    test('maintenance tops up the stock and adds a last resort', () async {
+      Future<KeyPackageMaintenance> maintain() => alice.maintainKeyPackages(
+        ciphersuite: ciphersuite,
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+        targetCount: 3,
+        lifetimeSeconds: BigInt.from(86400),
+      );
+
+      final first = await maintain();
+      expect(first.newKeyPackages, hasLength(4));
+      expect(first.revokedRefs, isEmpty);
+      final listed = await alice.listKeyPackages();
+      expect(listed.where((k) => k.lastResort), hasLength(1));
+
+      final second = await maintain();
+      expect(second.newKeyPackages, isEmpty);
+      expect(second.revokedRefs, isEmpty);
+    });
+
+    test('inspects a key package without joining', () async {
+      final result = await alice.createKeyPackageWithOptions(
+        ciphersuite: ciphersuite,
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+        options: KeyPackageOptions(lastResort: true),
+      );
+
+      final info = inspectKeyPackage(keyPackageBytes: result.keyPackageBytes);
+      expect(info.ciphersuite, equals(ciphersuite));
+      expect(info.credentialIdentity, equals(aliceId.credentialIdentity));
+      expect(info.credentialType, equals(1));
+      expect(info.signatureKey, equals(aliceId.publicKey));
+      expect(info.notAfter, greaterThan(info.notBefore));
+      expect(info.lastResort, isTrue);
+      expect(info.signatureValid, isTrue);
+
+      final tampered = Uint8List.fromList(result.keyPackageBytes);
+      tampered[tampered.length - 1] ^= 0x01;
+      final forged = inspectKeyPackage(keyPackageBytes: tampered);
+      expect(forged.credentialIdentity, equals(aliceId.credentialIdentity));
+      expect(forged.signatureValid, isFalse);
+    });
+
+    test('deleted key packages are not listed', () async {
+      final kept = await alice.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final deleted = await alice.createKeyPackageWithOptions(
+        ciphersuite: ciphersuite,
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+        options: KeyPackageOptions(lastResort: true),
+      );
+      await alice.deleteKeyPackage(keyPackageRefBytes: deleted.keyPackageRef);
+
+      final listed = await alice.listKeyPackages();
+      expect(listed.map((k) => k.keyPackageRef), [kept.keyPackageRef]);
+      expect(listed.single.origin, isNull);
+    });
   });
 
   group('X-Wing post-quantum keys', () {
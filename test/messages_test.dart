@@ -23,6 +23,163 @@ void main() {
     bobId = TestIdentity.create('bob');
   });
 
+  group('past epoch decryption keys', () {
+    test('follow max past epochs', () async {
+      final result = await alice.createGroup(
+        config: MlsGroupConfig(
+          ciphersuite: ciphersuite,
+          wireFormatPolicy: MlsWireFormatPolicy.ciphertext,
+          useRatchetTreeExtension: true,
+          maxPastEpochs: 2,
+          paddingSize: 0,
+          senderRatchetMaxOutOfOrder: 5,
+          senderRatchetMaxForwardDistance: 1000,
+          numberOfResumptionPsks: 0,
+        ),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      for (var i = 0; i < 3; i++) {
+        await alice.selfUpdate(
+          groupIdBytes: result.groupId,
+          signerBytes: aliceId.signerBytes,
+          ensureGroupInfo: false,
+        );
+        await alice.mergePendingCommit(groupIdBytes: result.groupId);
+      }
+      expect(
+        await alice.groupEpoch(groupIdBytes: result.groupId),
+        BigInt.from(3),
+      );
+
+      final decryptable = <int>[];
+      for (var epoch = 0; epoch <= 4; epoch++) {
+        if (await alice.hasDecryptionKeysForEpoch(
+          groupIdBytes: result.groupId,
+          epoch: BigInt.from(epoch),
+        )) {
+          decryptable.add(epoch);
+        }
+      }
+      expect(decryptable, [1, 2, 3]);
+    });
+  });
+
+  group('handshake ratchet tolerance', () {
+    MlsGroupConfig toleranceConfig({int? handshake}) => MlsGroupConfig(
+      ciphersuite: ciphersuite,
+      wireFormatPolicy: MlsWireFormatPolicy.ciphertext,
+      useRatchetTreeExtension: true,
+      maxPastEpochs: 0,
+      paddingSize: 0,
+      senderRatchetMaxOutOfOrder: 5,
+      senderRatchetMaxForwardDistance: 1000,
+      handshakeSenderRatchetMaxOutOfOrder: handshake,
+      numberOfResumptionPsks: 0,
+    );
+
+    test('applies to handshake messages only', () async {
+      final (groupId, welcome) = await addBob(
+        alice,
+        aliceId,
+        bob,
+        bobId,
+        join: false,
+      );
+      await bob.joinGroupFromWelcome(
+        config: toleranceConfig(handshake: 0),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+      );
+      final stored = await bob.groupConfig(groupIdBytes: groupId);
+      expect(stored.handshakeSenderRatchetMaxOutOfOrder, equals(0));
+      expect(stored.senderRatchetMaxOutOfOrder, equals(5));
+
+      Future<Uint8List> send(String text) async => (await alice.createMessage(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList(utf8.encode(text)),
+      )).ciphertext;
+      Future<Uint8List> propose() async => (await alice.proposeSelfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+      )).proposalMessage;
+
+      // Application messages keep the tolerance of 5.
+      final first = await send('first');
+      final second = await send('second');
+      await bob.processMessage(groupIdBytes: groupId, messageBytes: second);
+      final reordered = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: first,
+      );
+      expect(utf8.decode(reordered.applicationMessage!), equals('first'));
+
+      // Handshake messages may not arrive out of order at all.
+      final skipped = await propose();
+      await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: await propose(),
+      );
+      final report = await bob.decryptionFailureReport(
+        groupIdBytes: groupId,
+        messageBytes: skipped,
+      );
+      expect(report, isNotNull);
+      expect(report!.outOfOrderTolerance, equals(0));
+      await expectLater(
+        bob.processMessage(groupIdBytes: groupId, messageBytes: skipped),
+        throwsA(anything),
+      );
+
+      // Unset, handshake messages share the application tolerance.
+      await bob.setConfiguration(
+        groupIdBytes: groupId,
+        config: toleranceConfig(),
+      );
+      expect(
+        (await bob.groupConfig(
+          groupIdBytes: groupId,
+        )).handshakeSenderRatchetMaxOutOfOrder,
+        isNull,
+      );
+      final earlier = await propose();
+      await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: await propose(),
+      );
+      final processed = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: earlier,
+      );
+      expect(processed.messageType, ProcessedMessageType.proposal);
+    });
+
+    test('is reported as a config conflict', () async {
+      final result = await alice.createGroup(
+        config: toleranceConfig(handshake: 2),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      expect(
+        await alice.checkGroupConfig(
+          groupIdBytes: result.groupId,
+          config: toleranceConfig(handshake: 2),
+        ),
+        isEmpty,
+      );
+      expect(
+        await alice.checkGroupConfig(
+          groupIdBytes: result.groupId,
+          config: toleranceConfig(),
+        ),
+        equals(['handshake_sender_ratchet_max_out_of_order']),
+      );
+    });
+  });
+
   group('message utilities', () {
     late Uint8List groupIdBytes;
 
@@ -46,6 +203,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
       await bob.joinGroupFromWelcome(
@@ -86,6 +244,535 @@ void main() {
       final ct = mlsMessageContentType(messageBytes: msg.ciphertext);
       expect(ct, equals('application'));
     });
+
+    test('processing order puts dependencies first', () async {
+      Future<Uint8List> send(String text) async => (await alice.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList(utf8.encode(text)),
+      )).ciphertext;
+
+      final early = await send('epoch 1');
+      final proposal = await alice.proposeSelfUpdate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+      );
+      final commit = await alice.commitToPendingProposals(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      final later = await send('epoch 2');
+
+      final queued = [
+        later,
+        commit.commit,
+        early,
+        proposal.proposalMessage,
+        Uint8List.fromList([0, 1, 2]),
+      ];
+      final order = mlsMessageProcessingOrder(messagesBytes: queued);
+      expect(order, [3, 2, 1, 0, 4]);
+
+      final received = <String>[];
+      for (final index in order.take(4)) {
+        final result = await bob.processMessage(
+          groupIdBytes: groupIdBytes,
+          messageBytes: queued[index],
+        );
+        if (result.applicationMessage case final text?) {
+          received.add(utf8.decode(text));
+        }
+      }
+      expect(received, ['epoch 1', 'epoch 2']);
+      expect(await bob.groupEpoch(groupIdBytes: groupIdBytes), BigInt.two);
+    });
+
+    test('sync cursor advances with processed messages', () async {
+      expect(await bob.syncCursor(groupIdBytes: groupIdBytes), isNull);
+      await bob.setSyncCursor(
+        groupIdBytes: groupIdBytes,
+        cursorBytes: Uint8List.fromList([1]),
+      );
+      expect(await bob.syncCursor(groupIdBytes: groupIdBytes), equals([1]));
+
+      final msg = await alice.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList(utf8.encode('cursor')),
+      );
+      await bob.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: msg.ciphertext,
+        syncCursor: Uint8List.fromList([2]),
+      );
+      expect(await bob.syncCursor(groupIdBytes: groupIdBytes), equals([2]));
+
+      await expectLater(
+        bob.processMessage(
+          groupIdBytes: groupIdBytes,
+          messageBytes: Uint8List.fromList([0, 1, 2]),
+          syncCursor: Uint8List.fromList([3]),
+        ),
+        throwsA(anything),
+      );
+      expect(await bob.syncCursor(groupIdBytes: groupIdBytes), equals([2]));
+    });
+
+    test('activity counters follow sent and received messages', () async {
+      final msg = await alice.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList(utf8.encode('counted')),
+      );
+      await bob.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: msg.ciphertext,
+      );
+      final update = await alice.selfUpdate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
+      await bob.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: update.commit,
+      );
+
+      final sender = await alice.groupActivity(groupIdBytes: groupIdBytes);
+      expect(sender.messagesSent, BigInt.one);
+      expect(sender.messagesReceived, BigInt.zero);
+      expect(sender.commitsMerged, BigInt.two);
+      expect(sender.lastActivity, isNotNull);
+
+      final receiver = await bob.groupActivity(groupIdBytes: groupIdBytes);
+      expect(receiver.messagesSent, BigInt.zero);
+      expect(receiver.messagesReceived, BigInt.one);
+      expect(receiver.commitsMerged, BigInt.one);
+    });
+
+    test('reports the size and padding of received messages', () async {
+      final msg = await alice.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList(utf8.encode('measured')),
+      );
+      final received = await bob.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: msg.ciphertext,
+      );
+
+      final padding = received.padding!;
+      expect(padding.ciphertextSize, BigInt.from(msg.ciphertext.length));
+      expect(padding.encryptedContentSize, lessThan(padding.ciphertextSize));
+      // The default config does not pad.
+      expect(padding.paddingSize, BigInt.zero);
+      expect(padding.paddingExact, isTrue);
+    });
+
+    test('batch processing routes messages to their groups', () async {
+      final other = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final add = await alice.addMembers(
+        groupIdBytes: other.groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: other.groupId);
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: add.welcome,
+        signerBytes: bobId.signerBytes,
+      );
+
+      Future<Uint8List> send(Uint8List groupId, String text) async =>
+          (await alice.createMessage(
+            groupIdBytes: groupId,
+            signerBytes: aliceId.signerBytes,
+            message: Uint8List.fromList(utf8.encode(text)),
+          )).ciphertext;
+
+      final first = await send(groupIdBytes, 'first');
+      final second = await send(other.groupId, 'second');
+      final commit = await alice.selfUpdate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
+      final third = await send(groupIdBytes, 'third');
+
+      final results = await bob.processMessagesBatch(
+        messagesBytes: [
+          first,
+          second,
+          Uint8List.fromList([0, 1, 2]),
+          commit.commit,
+          third,
+        ],
+      );
+      expect(results.map((r) => r.index), [0, 1, 2, 3, 4]);
+      expect(results[0].groupId, equals(groupIdBytes));
+      expect(results[1].groupId, equals(other.groupId));
+      expect(utf8.decode(results[1].result!.applicationMessage!), 'second');
+      expect(results[2].groupId, isNull);
+      expect(results[2].error, isNotNull);
+      expect(
+        results[3].result!.messageType,
+        ProcessedMessageType.stagedCommit,
+      );
+      expect(utf8.decode(results[4].result!.applicationMessage!), 'third');
+
+      expect(await bob.groupEpoch(groupIdBytes: groupIdBytes), BigInt.two);
+      expect(await bob.groupEpoch(groupIdBytes: other.groupId), BigInt.one);
+    });
+
+    test('batch applies messages fetched out of order', () async {
+      final commit = await alice.selfUpdate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
+      final after = (await alice.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList(utf8.encode('after')),
+      )).ciphertext;
+
+      // The new epoch's message arrives before the commit creating it.
+      final results = await bob.processMessagesBatch(
+        messagesBytes: [after, commit.commit],
+      );
+      expect(results.map((r) => r.index), [0, 1]);
+      expect(results.every((r) => r.error == null), isTrue);
+      expect(utf8.decode(results[0].result!.applicationMessage!), 'after');
+      expect(
+        results[1].result!.messageType,
+        ProcessedMessageType.stagedCommit,
+      );
+    });
+
+    test('default AAD is stored per group and can be cleared', () async {
+      expect(await alice.defaultAad(groupIdBytes: groupIdBytes), isNull);
+
+      final aad = Uint8List.fromList(utf8.encode('tenant-42'));
+      await alice.setDefaultAad(groupIdBytes: groupIdBytes, aad: aad);
+      expect(await alice.defaultAad(groupIdBytes: groupIdBytes), equals(aad));
+
+      await alice.setDefaultAad(groupIdBytes: groupIdBytes);
+      expect(await alice.defaultAad(groupIdBytes: groupIdBytes), isNull);
+    });
+
+    test('messages and commits with default AAD are processed', () async {
+      await alice.setDefaultAad(
+        groupIdBytes: groupIdBytes,
+        aad: Uint8List.fromList(utf8.encode('tenant-42')),
+      );
+
+      final msg = Uint8List.fromList(utf8.encode('stamped'));
+      final encrypted = await alice.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        message: msg,
+      );
+      final received = await bob.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: encrypted.ciphertext,
+      );
+      expect(received.applicationMessage, equals(msg));
+
+      final commit = await alice.selfUpdate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      final processed = await bob.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: commit.commit,
+      );
+      expect(processed.hasStagedCommit, isTrue);
+    });
+
+    test('proposals and leave messages carry the default AAD', () async {
+      final aad = Uint8List.fromList(utf8.encode('tenant-42'));
+      await bob.setDefaultAad(groupIdBytes: groupIdBytes, aad: aad);
+
+      final proposal = await bob.proposeSelfUpdate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: bobId.signerBytes,
+      );
+      final processedProposal = await alice.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: proposal.proposalMessage,
+      );
+      expect(processedProposal.aad, equals(aad));
+
+      final leave = await bob.leaveGroup(
+        groupIdBytes: groupIdBytes,
+        signerBytes: bobId.signerBytes,
+      );
+      final processedLeave = await alice.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: leave.message,
+      );
+      expect(processedLeave.aad, equals(aad));
+    });
+
+    test('validate message leaves state untouched', () async {
+      final msg = Uint8List.fromList(utf8.encode('check me'));
+      final encrypted = await alice.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        message: msg,
+      );
+
+      for (var i = 0; i < 2; i++) {
+        final validation = await bob.validateMessage(
+          groupIdBytes: groupIdBytes,
+          messageBytes: encrypted.ciphertext,
+        );
+        expect(validation.valid, isTrue);
+        expect(validation.error, isNull);
+        expect(validation.messageType, ProcessedMessageType.application);
+        expect(validation.senderIndex, equals(0));
+      }
+
+      // The message can still be decrypted after validation.
+      final received = await bob.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: encrypted.ciphertext,
+      );
+      expect(received.applicationMessage, equals(msg));
+    });
+
+    test('validate commit does not advance the epoch', () async {
+      final commit = await alice.selfUpdate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      final epochBefore = await bob.groupEpoch(groupIdBytes: groupIdBytes);
+      final validation = await bob.validateMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: commit.commit,
+      );
+      expect(validation.valid, isTrue);
+      expect(validation.messageType, ProcessedMessageType.stagedCommit);
+      expect(
+        await bob.groupEpoch(groupIdBytes: groupIdBytes),
+        equals(epochBefore),
+      );
+    });
+
+    test('validate message reports malformed input', () async {
+      final validation = await bob.validateMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: Uint8List.fromList([1, 2, 3]),
+      );
+      expect(validation.valid, isFalse);
+      expect(validation.error, isNotNull);
+      expect(validation.epoch, isNull);
+    });
+
+    test('failure report is null for a processable message', () async {
+      final encrypted = await alice.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList(utf8.encode('fine')),
+      );
+      final report = await bob.decryptionFailureReport(
+        groupIdBytes: groupIdBytes,
+        messageBytes: encrypted.ciphertext,
+      );
+      expect(report, isNull);
+    });
+
+    test('failure report identifies a replayed message', () async {
+      final encrypted = await alice.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList(utf8.encode('once')),
+      );
+      await bob.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: encrypted.ciphertext,
+      );
+
+      final report = await bob.decryptionFailureReport(
+        groupIdBytes: groupIdBytes,
+        messageBytes: encrypted.ciphertext,
+      );
+      expect(report, isNotNull);
+      expect(report!.kind, DecryptionFailureKind.secretConsumed);
+      expect(report.epochDelta, equals(0));
+      expect(report.senderIndex, isNull);
+    });
+
+    test('decryption keys are held for the current epoch only', () async {
+      expect(
+        await bob.hasDecryptionKeysForEpoch(
+          groupIdBytes: groupIdBytes,
+          epoch: BigInt.one,
+        ),
+        isTrue,
+      );
+      for (final epoch in [BigInt.zero, BigInt.two]) {
+        expect(
+          await bob.hasDecryptionKeysForEpoch(
+            groupIdBytes: groupIdBytes,
+            epoch: epoch,
+          ),
+          isFalse,
+        );
+      }
+    });
+
+    test('past message is decrypted while its epoch is retained', () async {
+      await bob.setMaxPastEpochs(groupIdBytes: groupIdBytes, maxPastEpochs: 2);
+      expect(await bob.maxPastEpochs(groupIdBytes: groupIdBytes), 2);
+
+      final late = await alice.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList(utf8.encode('late')),
+      );
+      final commit = await alice.selfUpdate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      await bob.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: commit.commit,
+      );
+
+      final decrypted = await bob.decryptPastMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: late.ciphertext,
+      );
+      expect(decrypted.messageEpoch, BigInt.one);
+      expect(decrypted.groupEpoch, BigInt.two);
+      expect(decrypted.keysAvailable, isTrue);
+      expect(decrypted.error, isNull);
+      expect(utf8.decode(decrypted.result!.applicationMessage!), 'late');
+    });
+
+    test('lowering max past epochs drops retained epochs', () async {
+      await bob.setMaxPastEpochs(groupIdBytes: groupIdBytes, maxPastEpochs: 2);
+      final late = await alice.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList(utf8.encode('too late')),
+      );
+      final commit = await alice.selfUpdate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      await bob.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: commit.commit,
+      );
+
+      await bob.setMaxPastEpochs(groupIdBytes: groupIdBytes, maxPastEpochs: 0);
+      final decrypted = await bob.decryptPastMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: late.ciphertext,
+      );
+      expect(decrypted.messageEpoch, BigInt.one);
+      expect(decrypted.keysAvailable, isFalse);
+      expect(decrypted.result, isNull);
+      expect(decrypted.error, contains('No message secrets retained'));
+    });
+
+    test('failure report identifies a message from a future epoch', () async {
+      // Alice advances without Bob processing the commit.
+      await alice.selfUpdate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      final encrypted = await alice.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList(utf8.encode('ahead')),
+      );
+
+      final report = await bob.decryptionFailureReport(
+        groupIdBytes: groupIdBytes,
+        messageBytes: encrypted.ciphertext,
+      );
+      expect(report!.kind, DecryptionFailureKind.futureEpoch);
+      expect(report.epochDelta, equals(1));
+    });
+
+    test('redelivered message is reported as duplicate', () async {
+      expect(await bob.messageWatermarks(groupIdBytes: groupIdBytes), isNull);
+      await bob.setMessageDeduplication(
+        groupIdBytes: groupIdBytes,
+        enabled: true,
+      );
+
+      final msg = Uint8List.fromList(utf8.encode('at least once'));
+      final encrypted = await alice.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        message: msg,
+      );
+      final first = await bob.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: encrypted.ciphertext,
+      );
+      expect(first.messageType, ProcessedMessageType.application);
+      expect(first.applicationMessage, equals(msg));
+
+      final second = await bob.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: encrypted.ciphertext,
+      );
+      expect(second.messageType, ProcessedMessageType.duplicate);
+      expect(second.applicationMessage, isNull);
+
+      final watermarks = await bob.messageWatermarks(
+        groupIdBytes: groupIdBytes,
+      );
+      expect(watermarks, hasLength(1));
+      expect(watermarks!.single.senderIndex, equals(0));
+      expect(watermarks.single.epoch, equals(BigInt.from(1)));
+      expect(watermarks.single.messages, equals(BigInt.from(1)));
+    });
+
+    test('redelivered message fails without deduplication', () async {
+      final encrypted = await alice.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList(utf8.encode('once')),
+      );
+      await bob.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: encrypted.ciphertext,
+      );
+      expect(
+        () => bob.processMessage(
+          groupIdBytes: groupIdBytes,
+          messageBytes: encrypted.ciphertext,
+        ),
+        throwsA(anything),
+      );
+    });
   });
 
   group('message with AAD', () {
@@ -111,6 +798,7 @@ void main() {
           groupIdBytes: groupIdBytes,
           signerBytes: aliceId.signerBytes,
           keyPackagesBytes: [bobKp.keyPackageBytes],
+          ensureGroupInfo: false,
         );
         await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
         await bob.joinGroupFromWelcome(
@@ -137,6 +825,21 @@ void main() {
         );
         expect(received.messageType, ProcessedMessageType.application);
         expect(received.applicationMessage, equals(msg));
+        expect(received.aad, equals(aad));
+
+        // Commits carry the group's default AAD
+        await alice.setDefaultAad(groupIdBytes: groupIdBytes, aad: aad);
+        final commit = await alice.selfUpdate(
+          groupIdBytes: groupIdBytes,
+          signerBytes: aliceId.signerBytes,
+          ensureGroupInfo: false,
+        );
+        final processed = await bob.processMessage(
+          groupIdBytes: groupIdBytes,
+          messageBytes: commit.commit,
+        );
+        expect(processed.messageType, ProcessedMessageType.stagedCommit);
+        expect(processed.aad, equals(aad));
       },
     );
   });
@@ -164,6 +867,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
       await bob.joinGroupFromWelcome(
@@ -178,6 +882,7 @@ void main() {
       final updateResult = await alice.selfUpdate(
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
       );
 
       // Bob processes the commit
@@ -186,12 +891,14 @@ void main() {
         messageBytes: updateResult.commit,
       );
       expect(processed.messageType, ProcessedMessageType.stagedCommit);
+      expect(processed.commitPsks, isEmpty);
     });
 
     test('Bob processes Alice self-update commit with inspect', () async {
       final updateResult = await alice.selfUpdate(
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
       );
 
       // Bob processes with inspection
@@ -205,6 +912,27 @@ void main() {
       // Update proposal), so hasUpdate may be false. The key assertion
       // is that stagedCommitInfo is present and the commit is processed.
       expect(processed.stagedCommitInfo!.selfRemoved, isFalse);
+      expect(processed.stagedCommitInfo!.psks, isEmpty);
+      expect(processed.stagedCommitInfo!.externalJoin, isNull);
+    });
+
+    test('inspected removals carry the removed member handles', () async {
+      final bobMember = (await bob.groupMembers(groupIdBytes: groupIdBytes))
+          .singleWhere((m) => m.index == 1);
+      final removeResult = await alice.removeMembers(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        memberIndices: [1],
+        ensureGroupInfo: false,
+      );
+
+      final processed = await bob.processMessageWithInspect(
+        groupIdBytes: groupIdBytes,
+        messageBytes: removeResult.commit,
+      );
+      final info = processed.stagedCommitInfo!;
+      expect(info.removeIndices, [1]);
+      expect(info.removeHandles.single, equals(bobMember.memberHandle));
     });
 
     test('Bob processes Alice proposal message', () async {
@@ -246,6 +974,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
       await bob.joinGroupFromWelcome(
@@ -267,6 +996,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
         keyPackagesBytes: [charlieKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
 
@@ -314,4 +1044,55 @@ void main() {
       expect(charlieReceived.applicationMessage, equals(msg));
     });
   });
+
+  group('join receipts', () {
+    test('welcome join produces a receipt the group can read', () async {
+      final result = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final groupIdBytes = result.groupId;
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final addBob = await alice.addMembers(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
+
+      expect(bob.isJoinReceiptsEnabled(), isFalse);
+      bob.setJoinReceiptsEnabled(enabled: true);
+      final joined = await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: addBob.welcome,
+        signerBytes: bobId.signerBytes,
+      );
+      expect(joined.joinReceipt, isNotNull);
+      expect(
+        (await bob.groupActivity(groupIdBytes: groupIdBytes)).messagesSent,
+        BigInt.one,
+      );
+
+      final received = await alice.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: joined.joinReceipt!,
+      );
+      expect(
+        isJoinReceipt(applicationData: received.applicationMessage!),
+        isTrue,
+      );
+      expect(
+        isJoinReceipt(applicationData: Uint8List.fromList(utf8.encode('hi'))),
+        isFalse,
+      );
+    });
+  });
 }
@@ -48,6 +48,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       expect(addResult.commit, isNotEmpty);
       expect(addResult.welcome, isNotEmpty);
@@ -60,6 +61,10 @@ void main() {
         signerBytes: bobId.signerBytes,
       );
       expect(joinResult.groupId, equals(groupIdBytes));
+      expect(joinResult.epoch, equals(BigInt.one));
+      expect(joinResult.ciphersuite, equals(ciphersuite));
+      expect(joinResult.memberCount, equals(2));
+      expect(joinResult.ourLeafIndex, equals(1));
 
       final aliceMembers = await alice.groupMembers(groupIdBytes: groupIdBytes);
       final bobMembers = await bob.groupMembers(
@@ -69,6 +74,97 @@ void main() {
       expect(bobMembers, hasLength(2));
     });
 
+    test('member join epochs', () async {
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final addResult = await alice.addMembers(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: addResult.welcome,
+        signerBytes: bobId.signerBytes,
+      );
+
+      final aliceView = await alice.memberJoinEpochs(
+        groupIdBytes: groupIdBytes,
+      );
+      expect(aliceView.map((m) => m.leafIndex), [0, 1]);
+      expect(aliceView.map((m) => m.epoch.toInt()), [0, 1]);
+      expect(aliceView.every((m) => m.exact), isTrue);
+
+      // Bob only knows that Alice was there before he joined.
+      final bobView = await bob.memberJoinEpochs(groupIdBytes: groupIdBytes);
+      expect(bobView.map((m) => m.epoch.toInt()), [1, 1]);
+      expect(bobView.map((m) => m.exact), [false, true]);
+    });
+
+    test('member handles identify members across epochs', () async {
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final addResult = await alice.addMembers(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: addResult.welcome,
+        signerBytes: bobId.signerBytes,
+      );
+
+      final bobHandle = (await bob.groupOwnLeafNode(
+        groupIdBytes: groupIdBytes,
+      )).memberHandle;
+      final aliceMembers = await alice.groupMembers(groupIdBytes: groupIdBytes);
+      expect(aliceMembers[1].memberHandle, equals(bobHandle));
+
+      final message = await bob.createMessage(
+        groupIdBytes: groupIdBytes,
+        signerBytes: bobId.signerBytes,
+        message: utf8.encode('hi'),
+      );
+      final received = await alice.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: message.ciphertext,
+      );
+      expect(received.senderHandle, equals(bobHandle));
+
+      final resolved = await alice.resolveMember(
+        groupIdBytes: groupIdBytes,
+        memberHandle: bobHandle,
+      );
+      expect(resolved!.index, equals(1));
+
+      await alice.removeMembers(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        memberIndices: [1],
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
+      expect(
+        await alice.resolveMember(
+          groupIdBytes: groupIdBytes,
+          memberHandle: bobHandle,
+        ),
+        isNull,
+      );
+    });
+
     test('Alice and Bob exchange messages', () async {
       final bobKp = await bob.createKeyPackage(
         ciphersuite: ciphersuite,
@@ -80,6 +176,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
 
@@ -148,6 +245,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
 
@@ -173,6 +271,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
         memberIndices: [bobMember.index],
+        ensureGroupInfo: false,
       );
       expect(removeResult.commit, isNotEmpty);
 
@@ -181,6 +280,32 @@ void main() {
       final membersAfter = await alice.groupMembers(groupIdBytes: groupIdBytes);
       expect(membersAfter, hasLength(1));
     });
+
+    test('former members are kept when tombstones are enabled', () async {
+      expect(
+        await alice.formerMembers(groupIdBytes: groupIdBytes),
+        isEmpty,
+      );
+      alice.setMemberTombstonesEnabled(enabled: true);
+      final bobMember = (await alice.groupMembers(
+        groupIdBytes: groupIdBytes,
+      ))[1];
+
+      await alice.removeMembers(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        memberIndices: [bobMember.index],
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
+
+      final former = await alice.formerMembers(groupIdBytes: groupIdBytes);
+      expect(former, hasLength(1));
+      expect(former.single.leafIndex, equals(bobMember.index));
+      expect(former.single.credential, equals(bobMember.credential));
+      expect(former.single.memberHandle, equals(bobMember.memberHandle));
+      expect(former.single.removedEpoch, equals(BigInt.two));
+    });
   });
 
   group('self-update operations', () {
@@ -202,6 +327,7 @@ void main() {
       final result = await alice.selfUpdate(
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
       );
       expect(result.commit, isNotEmpty);
 
@@ -211,6 +337,39 @@ void main() {
       expect(epochAfter, equals(epochBefore + BigInt.one));
     });
 
+    test('ensureGroupInfo always returns a GroupInfo', () async {
+      final result = await alice.selfUpdate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: true,
+      );
+      expect(result.groupInfo, isNotNull);
+      expect(result.groupInfo, isNotEmpty);
+
+      final commit = await alice.commitToPendingProposals(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: true,
+      );
+      expect(commit.groupInfo, isNotNull);
+
+      final rotated = await alice.panicRotate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: true,
+      );
+      expect(rotated.groupInfo, isNotNull);
+
+      final advanced = await alice.advanceEpoch(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        forceSelfUpdate: false,
+        consumePendingProposals: false,
+        ensureGroupInfo: true,
+      );
+      expect(advanced.groupInfo, isNotNull);
+    });
+
     test('self-update with new signer rotates credential', () async {
       final newId = TestIdentity.create('alice-new');
 
@@ -220,6 +379,7 @@ void main() {
         newSignerBytes: newId.signerBytes,
         newCredentialIdentity: newId.credentialIdentity,
         newSignerPublicKey: newId.publicKey,
+        ensureGroupInfo: false,
       );
       expect(result.commit, isNotEmpty);
 
@@ -232,6 +392,56 @@ void main() {
         equals(newId.credentialIdentity),
       );
     });
+
+    test('panic rotation advances the epoch and drops old PSKs', () async {
+      final config = MlsGroupConfig(
+        ciphersuite: ciphersuite,
+        wireFormatPolicy: MlsWireFormatPolicy.ciphertext,
+        useRatchetTreeExtension: true,
+        maxPastEpochs: 2,
+        paddingSize: 0,
+        senderRatchetMaxOutOfOrder: 5,
+        senderRatchetMaxForwardDistance: 1000,
+        numberOfResumptionPsks: 4,
+      );
+      final result = await alice.createGroup(
+        config: config,
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final groupId = result.groupId;
+      final update = await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      expect(update.commit, isNotEmpty);
+      await alice.mergePendingCommit(groupIdBytes: groupId);
+      expect(
+        await alice.listResumptionPsks(groupIdBytes: groupId),
+        hasLength(greaterThan(1)),
+      );
+
+      final rotation = await alice.panicRotate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      expect(rotation.commit, isNotEmpty);
+
+      // Already merged: no pending commit is left behind.
+      final epoch = await alice.groupEpoch(groupIdBytes: groupId);
+      expect(epoch, equals(BigInt.two));
+      expect(
+        await alice.groupHasPendingProposals(groupIdBytes: groupId),
+        isFalse,
+      );
+      expect(
+        await alice.listResumptionPsks(groupIdBytes: groupId),
+        equals([epoch]),
+      );
+    });
   });
 
   group('add members without update', () {
@@ -255,6 +465,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       expect(addResult.commit, isNotEmpty);
       expect(addResult.welcome, isNotEmpty);
@@ -266,6 +477,124 @@ void main() {
     });
   });
 
+  group('key package validation', () {
+    late Uint8List groupIdBytes;
+
+    setUp(() async {
+      final result = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      groupIdBytes = result.groupId;
+    });
+
+    Future<Uint8List> bobKeyPackage(KeyPackageOptions options) async {
+      final kp = await bob.createKeyPackageWithOptions(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+        options: options,
+      );
+      return kp.keyPackageBytes;
+    }
+
+    test('last-resort key packages can be refused', () async {
+      final kp = await bobKeyPackage(KeyPackageOptions(lastResort: true));
+      await expectLater(
+        alice.addMembers(
+          groupIdBytes: groupIdBytes,
+          signerBytes: aliceId.signerBytes,
+          keyPackagesBytes: [kp],
+          validation: KeyPackageValidationOptions(
+            skipLifetimeValidation: false,
+            rejectLastResort: true,
+            allowedCiphersuites: const [],
+          ),
+          ensureGroupInfo: false,
+        ),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('last-resort')),
+        ),
+      );
+      expect(await alice.groupEpoch(groupIdBytes: groupIdBytes), BigInt.zero);
+
+      final result = await alice.addMembers(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [kp],
+        ensureGroupInfo: false,
+      );
+      expect(result.welcome, isNotEmpty);
+    });
+
+    test('ciphersuites outside the allowed list are refused', () async {
+      final kp = await bobKeyPackage(KeyPackageOptions(lastResort: false));
+      await expectLater(
+        alice.proposeAdd(
+          groupIdBytes: groupIdBytes,
+          signerBytes: aliceId.signerBytes,
+          keyPackageBytes: kp,
+          validation: KeyPackageValidationOptions(
+            skipLifetimeValidation: false,
+            rejectLastResort: false,
+            allowedCiphersuites: const [
+              MlsCiphersuite.mls128DhkemP256Aes128GcmSha256P256,
+            ],
+          ),
+        ),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('is not allowed')),
+        ),
+      );
+    });
+
+    test('expired key packages need skipped lifetime validation', () async {
+      final kp = await bobKeyPackage(
+        KeyPackageOptions(lifetimeSeconds: BigInt.zero, lastResort: false),
+      );
+      await expectLater(
+        alice.addMembers(
+          groupIdBytes: groupIdBytes,
+          signerBytes: aliceId.signerBytes,
+          keyPackagesBytes: [kp],
+          ensureGroupInfo: false,
+        ),
+        throwsA(isA<Object>()),
+      );
+
+      final skip = KeyPackageValidationOptions(
+        skipLifetimeValidation: true,
+        rejectLastResort: false,
+        allowedCiphersuites: const [],
+      );
+      alice.setStrictMode(enabled: true);
+      await expectLater(
+        alice.addMembers(
+          groupIdBytes: groupIdBytes,
+          signerBytes: aliceId.signerBytes,
+          keyPackagesBytes: [kp],
+          validation: skip,
+          ensureGroupInfo: false,
+        ),
+        throwsA(predicate<Object>((e) => e.toString().contains('Strict mode'))),
+      );
+      alice.setStrictMode(enabled: false);
+
+      final result = await alice.addMembers(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [kp],
+        validation: skip,
+        ensureGroupInfo: false,
+      );
+      expect(result.welcome, isNotEmpty);
+      expect(await alice.groupEpoch(groupIdBytes: groupIdBytes), BigInt.one);
+    });
+  });
+
   group('swap members', () {
     test('atomic remove and add', () async {
       final groupResult = await alice.createGroup(
@@ -286,6 +615,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
       await bob.joinGroupFromWelcome(
@@ -315,6 +645,7 @@ void main() {
         signerBytes: aliceId.signerBytes,
         removeIndices: [bobIdx!],
         addKeyPackagesBytes: [charlieKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       expect(swapResult.commit, isNotEmpty);
       expect(swapResult.welcome, isNotEmpty);
@@ -355,6 +686,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
       await bob.joinGroupFromWelcome(
@@ -409,6 +741,7 @@ void main() {
         groupIdBytes: gid,
         signerBytes: ptAliceId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await ptAlice.mergePendingCommit(groupIdBytes: gid);
       await ptBob.joinGroupFromWelcome(
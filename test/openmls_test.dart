@@ -57,6 +57,24 @@ void main() {
         Openmls.cleanup();
       });
     });
+
+    // =========================================================================
+    // Log redaction
+    // =========================================================================
+    group('log redaction', () {
+      setUpAll(Openmls.init);
+      tearDownAll(Openmls.cleanup);
+
+      test('policy can be tightened and read back', () {
+        final initial = logRedaction();
+        addTearDown(() => setLogRedaction(policy: initial));
+
+        setLogRedaction(policy: MlsLogRedaction.strict);
+        expect(logRedaction(), equals(MlsLogRedaction.strict));
+        setLogRedaction(policy: MlsLogRedaction.standard);
+        expect(logRedaction(), equals(MlsLogRedaction.standard));
+      });
+    });
   });
 
   // ===========================================================================
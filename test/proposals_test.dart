@@ -51,6 +51,7 @@ void main() {
       final commit = await alice.commitToPendingProposals(
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
       );
       expect(commit.commit, isNotEmpty);
 
@@ -84,6 +85,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
         keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
       );
       await alice.mergePendingCommit(groupIdBytes: groupIdBytes);
       await bob.joinGroupFromWelcome(
@@ -116,6 +118,7 @@ void main() {
       );
       expect(proposals, hasLength(1));
       expect(proposals.first.proposalType, MlsProposalType.add);
+      expect(proposals.first.groupContextExtensions, isNull);
     });
 
     test('propose remove member', () async {
@@ -141,15 +144,109 @@ void main() {
       expect(proposal.proposalMessage, isNotEmpty);
     });
 
+    test('commit result lists proposals pulled from the store', () async {
+      final charlieId = TestIdentity.create('charlie');
+      final charlie = await createTestEngine();
+      final charlieKp = await charlie.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: charlieId.signerBytes,
+        credentialIdentity: charlieId.credentialIdentity,
+        signerPublicKey: charlieId.publicKey,
+      );
+      final proposal = await bob.proposeAdd(
+        groupIdBytes: groupIdBytes,
+        signerBytes: bobId.signerBytes,
+        keyPackageBytes: charlieKp.keyPackageBytes,
+      );
+      await alice.processMessage(
+        groupIdBytes: groupIdBytes,
+        messageBytes: proposal.proposalMessage,
+      );
+
+      final commit = await alice.commitToPendingProposals(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      expect(commit.proposals, hasLength(1));
+      final committed = commit.proposals.single;
+      expect(committed.proposalType, MlsProposalType.add);
+      expect(committed.senderIndex, equals(1));
+      expect(committed.byReference, isTrue);
+      expect(committed.removedIndex, isNull);
+      expect(
+        identityFromCredential(committed.addedCredential!),
+        equals(charlieId.credentialIdentity),
+      );
+    });
+
+    test('commit result lists inline proposals', () async {
+      final result = await alice.removeMembers(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        memberIndices: [1],
+        ensureGroupInfo: false,
+      );
+      expect(result.proposals, hasLength(1));
+      final committed = result.proposals.single;
+      expect(committed.proposalType, MlsProposalType.remove);
+      expect(committed.senderIndex, equals(0));
+      expect(committed.byReference, isFalse);
+      expect(committed.removedIndex, equals(1));
+      expect(committed.addedCredential, isNull);
+    });
+
+    test('custom proposal types must be advertised by all members', () async {
+      await expectLater(
+        alice.proposeCustomProposal(
+          groupIdBytes: groupIdBytes,
+          signerBytes: aliceId.signerBytes,
+          proposalType: 0xF001, // private-use range
+          payload: utf8.encode('custom-data'),
+        ),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('members [0, 1]')),
+        ),
+      );
+    });
+  });
+
+  group('custom proposals', () {
     test('propose custom proposal', () async {
+      final result = await alice.createGroupWithBuilder(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+        capabilities: MlsCapabilities.withCustomProposals(
+          proposalTypes: Uint16List.fromList([0xF001]),
+        ),
+      );
       final proposal = await alice.proposeCustomProposal(
-        groupIdBytes: groupIdBytes,
+        groupIdBytes: result.groupId,
         signerBytes: aliceId.signerBytes,
         proposalType: 0xF001, // private-use range
         payload: utf8.encode('custom-data'),
       );
       expect(proposal.proposalMessage, isNotEmpty);
     });
+
+    test('capabilities helper lists custom proposal types once', () {
+      final caps = MlsCapabilities.withCustomProposals(
+        proposalTypes: Uint16List.fromList([0xF001]),
+      ).addCustomProposals(proposalTypes: Uint16List.fromList([0xF001, 0xF002]));
+      expect(caps.otherProposals, equals([0xF001, 0xF002]));
+      expect(caps.proposals, isEmpty);
+    });
+
+    test('capabilities helper rejects RFC 9420 proposal types', () {
+      expect(
+        () => MlsCapabilities.withCustomProposals(
+          proposalTypes: Uint16List.fromList([0x0001]),
+        ),
+        throwsA(anything),
+      );
+    });
   });
 
   group('clear operations', () {
@@ -193,6 +290,28 @@ void main() {
       final epoch = await alice.groupEpoch(groupIdBytes: groupIdBytes);
       expect(epoch, equals(BigInt.zero));
     });
+
+    test('commit rejection without a pending commit leaves state', () async {
+      await alice.proposeSelfUpdate(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+      );
+
+      await expectLater(
+        alice.handleCommitRejected(groupIdBytes: groupIdBytes),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('no pending commit')),
+        ),
+      );
+      expect(
+        await alice.groupHasPendingProposals(groupIdBytes: groupIdBytes),
+        isTrue,
+      );
+      expect(
+        await alice.groupEpoch(groupIdBytes: groupIdBytes),
+        equals(BigInt.zero),
+      );
+    });
   });
 
   group('PSK operations', () {
@@ -233,6 +352,21 @@ void main() {
       expect(psk, isNotNull);
       expect(psk, isNotEmpty);
     });
+
+    test('list resumption PSKs includes the current epoch', () async {
+      final epochs = await alice.listResumptionPsks(
+        groupIdBytes: groupIdBytes,
+      );
+      expect(epochs, isNotEmpty);
+      expect(epochs.last, await alice.groupEpoch(groupIdBytes: groupIdBytes));
+      for (final epoch in epochs) {
+        final psk = await alice.getPastResumptionPsk(
+          groupIdBytes: groupIdBytes,
+          epoch: epoch,
+        );
+        expect(psk, isNotNull);
+      }
+    });
   });
 
   group('group context extensions', () {
@@ -268,6 +402,16 @@ void main() {
         proposals.first.proposalType,
         MlsProposalType.groupContextExtensions,
       );
+
+      final change = proposals.first.groupContextExtensions;
+      expect(change, isNotNull);
+      expect(change!.addedTypes, contains(0xFF01));
+      expect(change.removedTypes, isEmpty);
+      expect(change.changedTypes, isEmpty);
+      final proposed = change.proposed.singleWhere(
+        (e) => e.extensionType == 0xFF01,
+      );
+      expect(proposed.data, equals(utf8.encode('ext-data')));
     });
 
     test('update group context extensions via commit', () async {
@@ -276,6 +420,7 @@ void main() {
         groupIdBytes: groupIdBytes,
         signerBytes: aliceId.signerBytes,
         extensions: [],
+        ensureGroupInfo: false,
       );
       expect(result.commit, isNotEmpty);
 
@@ -306,6 +451,63 @@ void main() {
       final active = await alice.groupIsActive(groupIdBytes: groupIdBytes);
       expect(active, isTrue);
     });
+
+    test('stored config matches and conflicts are reported', () async {
+      final result = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final groupIdBytes = result.groupId;
+
+      final stored = await alice.groupConfig(groupIdBytes: groupIdBytes);
+      expect(stored.wireFormatPolicy, MlsWireFormatPolicy.ciphertext);
+      expect(stored.senderRatchetMaxForwardDistance, 1000);
+      expect(
+        await alice.checkGroupConfig(groupIdBytes: groupIdBytes, config: stored),
+        isEmpty,
+      );
+
+      final plaintext = MlsGroupConfig(
+        ciphersuite: ciphersuite,
+        wireFormatPolicy: MlsWireFormatPolicy.plaintext,
+        useRatchetTreeExtension: true,
+        maxPastEpochs: 0,
+        paddingSize: 0,
+        senderRatchetMaxOutOfOrder: 5,
+        senderRatchetMaxForwardDistance: 1000,
+        numberOfResumptionPsks: 0,
+      );
+      expect(
+        await alice.checkGroupConfig(
+          groupIdBytes: groupIdBytes,
+          config: plaintext,
+        ),
+        equals(['wire_format_policy']),
+      );
+    });
+
+    test('rejects a config with another ciphersuite', () async {
+      final result = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+
+      await expectLater(
+        alice.setConfiguration(
+          groupIdBytes: result.groupId,
+          config: defaultConfig(
+            suite: MlsCiphersuite.mls128DhkemP256Aes128GcmSha256P256,
+          ),
+        ),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('ciphersuite')),
+        ),
+      );
+    });
   });
 
   group('flexible commit', () {
@@ -347,5 +549,417 @@ void main() {
       final members = await alice.groupMembers(groupIdBytes: groupIdBytes);
       expect(members, hasLength(2));
     });
+
+    test('proposal filter leaves out non-matching proposals', () async {
+      final groupResult = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final groupIdBytes = groupResult.groupId;
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      await alice.proposeAdd(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        keyPackageBytes: bobKp.keyPackageBytes,
+      );
+      await alice.proposeGroupContextExtensions(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        extensions: [
+          MlsExtension(
+            extensionType: 0xFF01,
+            data: Uint8List.fromList(utf8.encode('ext-data')),
+          ),
+        ],
+      );
+
+      final result = await alice.flexibleCommit(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        options: FlexibleCommitOptions(
+          addKeyPackages: [],
+          removeIndices: Uint32List(0),
+          forceSelfUpdate: false,
+          consumePendingProposals: true,
+          createGroupInfo: false,
+          useRatchetTreeExtension: true,
+          proposalFilter: MlsProposalFilter(
+            proposalTypes: [MlsProposalType.add],
+            senderIndices: Uint32List.fromList([0]),
+            includeRefs: [],
+            excludeRefs: [],
+          ),
+        ),
+      );
+      expect(result.proposals, hasLength(1));
+      expect(result.proposals.single.proposalType, MlsProposalType.add);
+      expect(await alice.groupMembers(groupIdBytes: groupIdBytes), hasLength(2));
+    });
+
+    test('proposal policy refuses pending proposals', () async {
+      final groupResult = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final groupIdBytes = groupResult.groupId;
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      await alice.proposeAdd(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        keyPackageBytes: bobKp.keyPackageBytes,
+      );
+      await alice.proposeGroupContextExtensions(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        extensions: [
+          MlsExtension(
+            extensionType: 0xFF01,
+            data: Uint8List.fromList(utf8.encode('ext-data')),
+          ),
+        ],
+      );
+
+      final checks = <MlsProposalCheck>[];
+      await alice.setProposalPolicy(
+        policy: (check) {
+          checks.add(check);
+          return check.proposalType != MlsProposalType.groupContextExtensions;
+        },
+      );
+      final result = await alice.flexibleCommit(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        options: FlexibleCommitOptions(
+          addKeyPackages: [],
+          removeIndices: Uint32List(0),
+          forceSelfUpdate: false,
+          consumePendingProposals: true,
+          createGroupInfo: false,
+          useRatchetTreeExtension: true,
+        ),
+      );
+      alice.clearProposalPolicy();
+
+      expect(checks, hasLength(2));
+      expect(checks.every((c) => c.senderIndex == 0), isTrue);
+      expect(checks.every((c) => c.proposal.isNotEmpty), isTrue);
+      expect(result.proposals, hasLength(1));
+      expect(result.proposals.single.proposalType, MlsProposalType.add);
+      expect(await alice.groupMembers(groupIdBytes: groupIdBytes), hasLength(2));
+    });
+
+    test('proposal policy applies to every commit call', () async {
+      final groupResult = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final groupIdBytes = groupResult.groupId;
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      await alice.proposeAdd(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        keyPackageBytes: bobKp.keyPackageBytes,
+      );
+
+      await alice.setProposalPolicy(
+        policy: (check) => check.proposalType != MlsProposalType.add,
+      );
+      final result = await alice.commitToPendingProposals(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      alice.clearProposalPolicy();
+
+      expect(result.proposals, isEmpty);
+      expect(await alice.groupMembers(groupIdBytes: groupIdBytes), hasLength(1));
+    });
+
+    MlsGroupConfig mixedConfig() => MlsGroupConfig(
+      ciphersuite: ciphersuite,
+      wireFormatPolicy: MlsWireFormatPolicy.mixedCiphertext,
+      useRatchetTreeExtension: true,
+      maxPastEpochs: 0,
+      paddingSize: 0,
+      senderRatchetMaxOutOfOrder: 5,
+      senderRatchetMaxForwardDistance: 1000,
+      numberOfResumptionPsks: 0,
+    );
+
+    FlexibleCommitOptions selfUpdateOptions(MlsWireFormat? wireFormat) =>
+        FlexibleCommitOptions(
+          addKeyPackages: [],
+          removeIndices: Uint32List(0),
+          forceSelfUpdate: true,
+          consumePendingProposals: true,
+          createGroupInfo: false,
+          useRatchetTreeExtension: true,
+          wireFormat: wireFormat,
+        );
+
+    test('wire format can be overridden under a mixed policy', () async {
+      final groupResult = await alice.createGroup(
+        config: mixedConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final groupIdBytes = groupResult.groupId;
+
+      final plain = await alice.flexibleCommit(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        options: selfUpdateOptions(MlsWireFormat.plaintext),
+      );
+      expect(plain.wireFormat, MlsWireFormat.plaintext);
+      expect(
+        mlsMessageWireFormat(messageBytes: plain.commit),
+        MlsWireFormat.plaintext,
+      );
+
+      // The override applies to one commit only.
+      final defaulted = await alice.flexibleCommit(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        options: selfUpdateOptions(null),
+      );
+      expect(defaulted.wireFormat, MlsWireFormat.ciphertext);
+      final config = await alice.groupConfiguration(groupIdBytes: groupIdBytes);
+      expect(config.wireFormatPolicy, MlsWireFormatPolicy.mixedCiphertext);
+    });
+
+    test('wire format override requires a mixed policy', () async {
+      final groupResult = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      expect(
+        () => alice.flexibleCommit(
+          groupIdBytes: groupResult.groupId,
+          signerBytes: aliceId.signerBytes,
+          options: selfUpdateOptions(MlsWireFormat.plaintext),
+        ),
+        throwsA(anything),
+      );
+    });
+  });
+
+  group('advance epoch', () {
+    late Uint8List groupIdBytes;
+
+    setUp(() async {
+      final result = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      groupIdBytes = result.groupId;
+    });
+
+    test('empty commit advances the epoch', () async {
+      final result = await alice.advanceEpoch(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        forceSelfUpdate: false,
+        consumePendingProposals: false,
+        ensureGroupInfo: false,
+      );
+      expect(result.commit, isNotEmpty);
+      expect(result.welcome, isNull);
+      expect(await alice.groupEpoch(groupIdBytes: groupIdBytes), BigInt.one);
+    });
+
+    test('refuses with pending proposals unless consumed', () async {
+      await alice.proposeGroupContextExtensions(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        extensions: [
+          MlsExtension(
+            extensionType: 0xFF01,
+            data: Uint8List.fromList(utf8.encode('ext-data')),
+          ),
+        ],
+      );
+
+      expect(
+        () => alice.advanceEpoch(
+          groupIdBytes: groupIdBytes,
+          signerBytes: aliceId.signerBytes,
+          forceSelfUpdate: true,
+          consumePendingProposals: false,
+          ensureGroupInfo: false,
+        ),
+        throwsA(anything),
+      );
+
+      await alice.advanceEpoch(
+        groupIdBytes: groupIdBytes,
+        signerBytes: aliceId.signerBytes,
+        forceSelfUpdate: true,
+        consumePendingProposals: true,
+        ensureGroupInfo: false,
+      );
+      expect(await alice.groupEpoch(groupIdBytes: groupIdBytes), BigInt.one);
+      expect(
+        await alice.groupPendingProposals(groupIdBytes: groupIdBytes),
+        isEmpty,
+      );
+    });
+  });
+
+  group('missing proposals', () {
+    Future<Uint8List> createGroupWithBob(MlsGroupConfig config) async {
+      final result = await alice.createGroup(
+        config: config,
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final addResult = await alice.addMembers(
+        groupIdBytes: result.groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: result.groupId);
+      await bob.joinGroupFromWelcome(
+        config: config,
+        welcomeBytes: addResult.welcome,
+        signerBytes: bobId.signerBytes,
+      );
+      return result.groupId;
+    }
+
+    /// Alice proposes adding Charlie and commits the proposal by reference;
+    /// Bob only receives the commit.
+    Future<(ProposalResult, Uint8List)> commitUnseenProposal(
+      Uint8List groupId,
+    ) async {
+      final charlieId = TestIdentity.create('charlie');
+      final charlie = await createTestEngine();
+      final charlieKp = await charlie.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: charlieId.signerBytes,
+        credentialIdentity: charlieId.credentialIdentity,
+        signerPublicKey: charlieId.publicKey,
+      );
+      final proposal = await alice.proposeAdd(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackageBytes: charlieKp.keyPackageBytes,
+      );
+      final commit = await alice.commitToPendingProposals(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      return (proposal, commit.commit);
+    }
+
+    test('commit is reported with the missing refs and applies after '
+        'injecting the proposal', () async {
+      final groupId = await createGroupWithBob(
+        MlsGroupConfig(
+          ciphersuite: ciphersuite,
+          wireFormatPolicy: MlsWireFormatPolicy.plaintext,
+          useRatchetTreeExtension: true,
+          maxPastEpochs: 0,
+          paddingSize: 0,
+          senderRatchetMaxOutOfOrder: 5,
+          senderRatchetMaxForwardDistance: 1000,
+          numberOfResumptionPsks: 0,
+        ),
+      );
+      final (proposal, commit) = await commitUnseenProposal(groupId);
+
+      final missing = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: commit,
+      );
+      expect(missing.messageType, ProcessedMessageType.missingProposals);
+      expect(missing.missingProposalRefs, hasLength(1));
+      expect(await bob.groupEpoch(groupIdBytes: groupId), BigInt.one);
+
+      final proposalRef = await bob.injectMissingProposal(
+        groupIdBytes: groupId,
+        proposalBytes: proposal.proposalMessage,
+      );
+      expect(proposalRef, equals(missing.missingProposalRefs!.single));
+
+      final applied = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: commit,
+      );
+      expect(applied.messageType, ProcessedMessageType.stagedCommit);
+      expect(await bob.groupMembers(groupIdBytes: groupId), hasLength(3));
+    });
+
+    test('encrypted commits are reported with unknown refs', () async {
+      final groupId = await createGroupWithBob(defaultConfig());
+      final (proposal, commit) = await commitUnseenProposal(groupId);
+
+      final missing = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: commit,
+      );
+      expect(missing.messageType, ProcessedMessageType.missingProposals);
+      expect(missing.missingProposalRefs, isNull);
+
+      await bob.injectMissingProposal(
+        groupIdBytes: groupId,
+        proposalBytes: proposal.proposalMessage,
+      );
+      final applied = await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: commit,
+      );
+      expect(applied.messageType, ProcessedMessageType.stagedCommit);
+    });
+
+    test('only proposals can be injected', () async {
+      final groupId = await createGroupWithBob(defaultConfig());
+      final message = await alice.createMessage(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList([1, 2, 3]),
+      );
+      await expectLater(
+        bob.injectMissingProposal(
+          groupIdBytes: groupId,
+          proposalBytes: message.ciphertext,
+        ),
+        throwsA(anything),
+      );
+    });
   });
 }
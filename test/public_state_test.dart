@@ -0,0 +1,106 @@
+import 'dart:typed_data';
+
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late MlsEngine bob;
+  late TestIdentity aliceId;
+  late TestIdentity bobId;
+  late Uint8List groupId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    bob = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    bobId = TestIdentity.create('bob');
+    final groupResult = await alice.createGroup(
+      config: defaultConfig(),
+      signerBytes: aliceId.signerBytes,
+      credentialIdentity: aliceId.credentialIdentity,
+      signerPublicKey: aliceId.publicKey,
+    );
+    groupId = groupResult.groupId;
+    final bobKp = await bob.createKeyPackage(
+      ciphersuite: ciphersuite,
+      signerBytes: bobId.signerBytes,
+      credentialIdentity: bobId.credentialIdentity,
+      signerPublicKey: bobId.publicKey,
+    );
+    final addResult = await alice.addMembers(
+      groupIdBytes: groupId,
+      signerBytes: aliceId.signerBytes,
+      keyPackagesBytes: [bobKp.keyPackageBytes],
+      ensureGroupInfo: false,
+    );
+    await bob.joinGroupFromWelcome(
+      config: defaultConfig(),
+      welcomeBytes: addResult.welcome,
+      signerBytes: bobId.signerBytes,
+    );
+  });
+
+  group('public state', () {
+    test('members export identical bytes for the same epoch', () async {
+      final fromAlice = await alice.exportPublicState(groupIdBytes: groupId);
+      final fromBob = await bob.exportPublicState(groupIdBytes: groupId);
+      expect(fromAlice, equals(fromBob));
+    });
+
+    test('verifies without group state', () async {
+      final bytes = await alice.exportPublicState(groupIdBytes: groupId);
+      final info = verifyPublicState(publicStateBytes: bytes);
+
+      final ctx = await alice.exportGroupContext(groupIdBytes: groupId);
+      expect(info.context.groupId, equals(groupId));
+      expect(info.context.epoch, BigInt.one);
+      expect(info.context.treeHash, equals(ctx.treeHash));
+      expect(info.memberCount, 2);
+      expect(
+        info.confirmationTag,
+        equals(await alice.groupConfirmationTag(groupIdBytes: groupId)),
+      );
+      expect(
+        info.ratchetTree,
+        equals(await alice.exportRatchetTree(groupIdBytes: groupId)),
+      );
+    });
+
+    test('rejects a tree that does not match the context', () async {
+      final bytes = await alice.exportPublicState(groupIdBytes: groupId);
+      await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      final newTree = await alice.exportRatchetTree(groupIdBytes: groupId);
+      final oldTree = verifyPublicState(publicStateBytes: bytes).ratchetTree;
+      final spliced = Uint8List.fromList([
+        ...bytes.sublist(0, bytes.length - oldTree.length),
+        ...newTree,
+      ]);
+      expect(
+        () => verifyPublicState(publicStateBytes: spliced),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('tree hash')),
+        ),
+      );
+    });
+
+    test('rejects an unknown version', () async {
+      final bytes = await alice.exportPublicState(groupIdBytes: groupId);
+      final tampered = Uint8List.fromList(bytes)..[0] = 2;
+      expect(
+        () => verifyPublicState(publicStateBytes: tampered),
+        throwsA(anything),
+      );
+    });
+  });
+}
@@ -0,0 +1,165 @@
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late MlsEngine bob;
+  late TestIdentity aliceId;
+  late TestIdentity bobId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    bob = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    bobId = TestIdentity.create('bob');
+  });
+
+  group('published key packages', () {
+    test('welcome marks published key package for revocation', () async {
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final ref = keyPackageRef(keyPackageBytes: bobKp.keyPackageBytes);
+      await bob.markKeyPackagePublished(
+        keyPackageRefBytes: ref,
+        dsId: 'ds.example',
+      );
+      expect(await bob.publishedRefsToRevoke(), isEmpty);
+
+      final groupResult = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final addResult = await alice.addMembers(
+        groupIdBytes: groupResult.groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: addResult.welcome,
+        signerBytes: bobId.signerBytes,
+      );
+
+      final toRevoke = await bob.publishedRefsToRevoke();
+      expect(toRevoke, hasLength(1));
+      expect(toRevoke.first.keyPackageRef, equals(ref));
+      expect(toRevoke.first.dsId, 'ds.example');
+
+      await bob.markKeyPackageRevoked(
+        keyPackageRefBytes: ref,
+        dsId: 'ds.example',
+      );
+      expect(await bob.publishedRefsToRevoke(), isEmpty);
+    });
+
+    test('only the key package the join used is consumed', () async {
+      final otherId = TestIdentity.create('bob-laptop');
+      final usedKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final otherKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: otherId.signerBytes,
+        credentialIdentity: otherId.credentialIdentity,
+        signerPublicKey: otherId.publicKey,
+      );
+      final usedRef = keyPackageRef(keyPackageBytes: usedKp.keyPackageBytes);
+      final otherRef = keyPackageRef(keyPackageBytes: otherKp.keyPackageBytes);
+      for (final ref in [usedRef, otherRef]) {
+        await bob.markKeyPackagePublished(
+          keyPackageRefBytes: ref,
+          dsId: 'ds.example',
+        );
+      }
+
+      final groupResult = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final addResult = await alice.addMembers(
+        groupIdBytes: groupResult.groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [usedKp.keyPackageBytes, otherKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await bob.joinGroupFromWelcomeWithPolicy(
+        config: defaultConfig(),
+        welcomeBytes: addResult.welcome,
+        signerBytes: bobId.signerBytes,
+        onExisting: ExistingGroupPolicy.abort,
+        preferredKeyPackageRef: usedRef,
+      );
+
+      final toRevoke = await bob.publishedRefsToRevoke();
+      expect(toRevoke, hasLength(1));
+      expect(toRevoke.single.keyPackageRef, equals(usedRef));
+    });
+
+    test('join reports the consumed key package', () async {
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final groupResult = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final addResult = await alice.addMembers(
+        groupIdBytes: groupResult.groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+
+      expect(
+        () => bob.joinGroupFromWelcomeWithPolicy(
+          config: defaultConfig(),
+          welcomeBytes: addResult.welcome,
+          signerBytes: bobId.signerBytes,
+          onExisting: ExistingGroupPolicy.abort,
+          preferredKeyPackageRef: [1, 2, 3],
+        ),
+        throwsA(anything),
+      );
+
+      final joinResult = await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: addResult.welcome,
+        signerBytes: bobId.signerBytes,
+      );
+      expect(
+        joinResult.keyPackageRef,
+        equals(keyPackageRef(keyPackageBytes: bobKp.keyPackageBytes)),
+      );
+    });
+
+    test('key package ref rejects invalid bytes', () {
+      expect(
+        () => keyPackageRef(keyPackageBytes: [1, 2, 3]),
+        throwsA(anything),
+      );
+    });
+  });
+}
@@ -0,0 +1,119 @@
+import 'dart:typed_data';
+
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late TestIdentity aliceId;
+  late Uint8List groupId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    final bob = await createTestEngine();
+    final bobId = TestIdentity.create('bob');
+    final groupResult = await alice.createGroup(
+      config: defaultConfig(),
+      signerBytes: aliceId.signerBytes,
+      credentialIdentity: aliceId.credentialIdentity,
+      signerPublicKey: aliceId.publicKey,
+    );
+    groupId = groupResult.groupId;
+    final bobKp = await bob.createKeyPackage(
+      ciphersuite: ciphersuite,
+      signerBytes: bobId.signerBytes,
+      credentialIdentity: bobId.credentialIdentity,
+      signerPublicKey: bobId.publicKey,
+    );
+    await alice.addMembers(
+      groupIdBytes: groupId,
+      signerBytes: aliceId.signerBytes,
+      keyPackagesBytes: [bobKp.keyPackageBytes],
+      ensureGroupInfo: false,
+    );
+  });
+
+  Future<Uint8List> carolKeyPackage() async {
+    final carol = await createTestEngine();
+    final carolId = TestIdentity.create('carol');
+    final kp = await carol.createKeyPackage(
+      ciphersuite: ciphersuite,
+      signerBytes: carolId.signerBytes,
+      credentialIdentity: carolId.credentialIdentity,
+      signerPublicKey: carolId.publicKey,
+    );
+    return kp.keyPackageBytes;
+  }
+
+  group('group sandboxes', () {
+    test('changes stay in the sandbox', () async {
+      final sandboxId = await alice.forkGroupSandbox(groupIdBytes: groupId);
+      final sandbox = alice.sandbox(sandboxId: sandboxId);
+
+      await sandbox.removeMembers(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        memberIndices: [1],
+        ensureGroupInfo: false,
+      );
+      await sandbox.addMembers(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [await carolKeyPackage()],
+        ensureGroupInfo: false,
+      );
+
+      final previewed = await sandbox.groupMembers(groupIdBytes: groupId);
+      expect(previewed.length, 2);
+      expect(await sandbox.groupEpoch(groupIdBytes: groupId), BigInt.from(3));
+
+      final members = await alice.groupMembers(groupIdBytes: groupId);
+      expect(members.length, 2);
+      expect(await alice.groupEpoch(groupIdBytes: groupId), BigInt.one);
+      expect(
+        identityFromCredential(members[1].credential),
+        isNot(equals(identityFromCredential(previewed[1].credential))),
+      );
+    });
+
+    test('dropped sandboxes behave like closed engines', () async {
+      final sandboxId = await alice.forkGroupSandbox(groupIdBytes: groupId);
+      final sandbox = alice.sandbox(sandboxId: sandboxId);
+      alice.dropSandbox(sandboxId: sandboxId);
+
+      expect(sandbox.isClosed(), isTrue);
+      expect(() => alice.sandbox(sandboxId: sandboxId), throwsA(anything));
+      expect(alice.isClosed(), isFalse);
+    });
+
+    test('closing the engine drops its sandboxes', () async {
+      final sandboxId = await alice.forkGroupSandbox(groupIdBytes: groupId);
+      final sandbox = alice.sandbox(sandboxId: sandboxId);
+      await alice.close();
+      expect(sandbox.isClosed(), isTrue);
+    });
+
+    test('engine info reports the sandbox backend', () async {
+      final sandboxId = await alice.forkGroupSandbox(groupIdBytes: groupId);
+      final info = await alice.sandbox(sandboxId: sandboxId).engineInfo();
+      expect(info.backend, MlsStorageBackend.sandbox);
+      expect(info.dbPath, isNull);
+    });
+
+    test('cannot fork an unknown group', () async {
+      expect(
+        () => alice.forkGroupSandbox(
+          groupIdBytes: Uint8List.fromList([1, 2, 3]),
+        ),
+        throwsA(anything),
+      );
+    });
+  });
+}
@@ -0,0 +1,106 @@
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late MlsEngine bob;
+  late TestIdentity aliceId;
+  late TestIdentity bobId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    bob = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    bobId = TestIdentity.create('bob');
+  });
+
+  group('search index key', () {
+    test('is shared by members and rotates per window', () async {
+      final (groupId, _) = await addBob(alice, aliceId, bob, bobId);
+      // Bob joined in epoch 1, after window 0 started.
+      final bobEarly = await bob.searchIndexKey(
+        groupIdBytes: groupId,
+        epochWindow: BigInt.two,
+      );
+      expect(bobEarly, isNull);
+      final aliceKey = await alice.searchIndexKey(
+        groupIdBytes: groupId,
+        epochWindow: BigInt.two,
+      );
+      expect(aliceKey, isNull);
+
+      final update = await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      await bob.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: update.commit,
+      );
+
+      final window1 = await alice.searchIndexKey(
+        groupIdBytes: groupId,
+        epochWindow: BigInt.two,
+      );
+      expect(window1, isNotNull);
+      expect(window1!.window, equals(BigInt.one));
+      expect(window1.firstEpoch, equals(BigInt.two));
+      expect(window1.key, hasLength(32));
+      final bobKey = await bob.searchIndexKey(
+        groupIdBytes: groupId,
+        epochWindow: BigInt.two,
+      );
+      expect(bobKey!.key, equals(window1.key));
+
+      // Stable within the window, new in the next one.
+      await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      final sameWindow = await alice.searchIndexKey(
+        groupIdBytes: groupId,
+        epochWindow: BigInt.two,
+      );
+      expect(sameWindow!.key, equals(window1.key));
+      await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      final window2 = await alice.searchIndexKey(
+        groupIdBytes: groupId,
+        epochWindow: BigInt.two,
+      );
+      expect(window2!.window, equals(BigInt.two));
+      expect(window2.key, isNot(equals(window1.key)));
+    });
+
+    test('is available at once in a window\'s first epoch', () async {
+      final (groupId, _) = await addBob(alice, aliceId, bob, bobId);
+      final key = await alice.searchIndexKey(
+        groupIdBytes: groupId,
+        epochWindow: BigInt.one,
+      );
+      expect(key!.firstEpoch, equals(BigInt.one));
+    });
+
+    test('rejects an empty window', () async {
+      final (groupId, _) = await addBob(alice, aliceId, bob, bobId);
+      expect(
+        () => alice.searchIndexKey(
+          groupIdBytes: groupId,
+          epochWindow: BigInt.zero,
+        ),
+        throwsA(anything),
+      );
+    });
+  });
+}
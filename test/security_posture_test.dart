@@ -0,0 +1,94 @@
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late TestIdentity aliceId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+  });
+
+  Future<List<int>> createGroup(MlsGroupConfig config) async {
+    final result = await alice.createGroup(
+      config: config,
+      signerBytes: aliceId.signerBytes,
+      credentialIdentity: aliceId.credentialIdentity,
+      signerPublicKey: aliceId.publicKey,
+    );
+    return result.groupId;
+  }
+
+  List<MlsSecurityFindingKind> kinds(MlsSecurityPosture posture) =>
+      posture.findings.map((f) => f.kind).toList();
+
+  group('security posture', () {
+    test('new group reports its never-updated creator', () async {
+      final groupId = await createGroup(defaultConfig());
+      final posture = await alice.securityPosture(groupIdBytes: groupId);
+
+      expect(posture.epoch, BigInt.zero);
+      expect(posture.epochsSinceOwnUpdate, isNull);
+      expect(posture.neverUpdatedMembers, [0]);
+      expect(posture.retainedPastEpochs, 0);
+      expect(posture.lastResortKeyPackageInUse, isFalse);
+      expect(posture.plaintextAllowed, isFalse);
+      expect(kinds(posture), [MlsSecurityFindingKind.membersNeverUpdated]);
+      expect(posture.severity, MlsSecuritySeverity.info);
+    });
+
+    test('a self update resets own staleness', () async {
+      final groupId = await createGroup(defaultConfig());
+      await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      final posture = await alice.securityPosture(groupIdBytes: groupId);
+
+      expect(posture.epochsSinceOwnUpdate, BigInt.zero);
+      expect(posture.neverUpdatedMembers, isEmpty);
+      expect(posture.findings, isEmpty);
+      expect(posture.severity, MlsSecuritySeverity.ok);
+    });
+
+    test('warns about plaintext and reports retained epochs', () async {
+      final groupId = await createGroup(
+        MlsGroupConfig(
+          ciphersuite: ciphersuite,
+          wireFormatPolicy: MlsWireFormatPolicy.plaintext,
+          useRatchetTreeExtension: true,
+          maxPastEpochs: 3,
+          paddingSize: 0,
+          senderRatchetMaxOutOfOrder: 5,
+          senderRatchetMaxForwardDistance: 1000,
+          numberOfResumptionPsks: 0,
+        ),
+      );
+      await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      final posture = await alice.securityPosture(groupIdBytes: groupId);
+
+      expect(posture.plaintextAllowed, isTrue);
+      expect(posture.retainedPastEpochs, 1);
+      expect(
+        kinds(posture),
+        containsAll([
+          MlsSecurityFindingKind.pastEpochsRetained,
+          MlsSecurityFindingKind.plaintextAllowed,
+        ]),
+      );
+      expect(posture.severity, MlsSecuritySeverity.warning);
+    });
+  });
+}
@@ -0,0 +1,110 @@
+import 'dart:typed_data';
+
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late MlsEngine bob;
+  late TestIdentity aliceId;
+  late TestIdentity bobId;
+  late Uint8List groupId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    bob = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    bobId = TestIdentity.create('bob');
+    final result = await alice.createGroup(
+      config: defaultConfig(),
+      signerBytes: aliceId.signerBytes,
+      credentialIdentity: aliceId.credentialIdentity,
+      signerPublicKey: aliceId.publicKey,
+    );
+    groupId = result.groupId;
+    final bobKp = await bob.createKeyPackage(
+      ciphersuite: ciphersuite,
+      signerBytes: bobId.signerBytes,
+      credentialIdentity: bobId.credentialIdentity,
+      signerPublicKey: bobId.publicKey,
+    );
+    final add = await alice.addMembers(
+      groupIdBytes: groupId,
+      signerBytes: aliceId.signerBytes,
+      keyPackagesBytes: [bobKp.keyPackageBytes],
+      ensureGroupInfo: false,
+    );
+    await bob.joinGroupFromWelcome(
+      config: defaultConfig(),
+      welcomeBytes: add.welcome,
+      signerBytes: bobId.signerBytes,
+    );
+  });
+
+  Future<Uint8List> selfUpdate() async => (await alice.selfUpdate(
+    groupIdBytes: groupId,
+    signerBytes: aliceId.signerBytes,
+    ensureGroupInfo: false,
+  )).commit;
+
+  group('staged commit review', () {
+    test('held commit is merged by reference', () async {
+      final staged = await bob.processMessageStaged(
+        groupIdBytes: groupId,
+        messageBytes: await selfUpdate(),
+      );
+      expect(staged.messageType, ProcessedMessageType.stagedCommit);
+      expect(staged.stagedCommitInfo, isNotNull);
+      expect(staged.stagedCommitRef, isNotNull);
+      expect(await bob.groupEpoch(groupIdBytes: groupId), BigInt.one);
+
+      await bob.mergeStagedCommitByRef(
+        groupIdBytes: groupId,
+        stagedCommitRef: staged.stagedCommitRef!,
+      );
+      expect(await bob.groupEpoch(groupIdBytes: groupId), BigInt.two);
+    });
+
+    test('rejected commit leaves the epoch unchanged', () async {
+      final staged = await bob.processMessageStaged(
+        groupIdBytes: groupId,
+        messageBytes: await selfUpdate(),
+      );
+      final ref = staged.stagedCommitRef!;
+      expect(
+        await bob.rejectStagedCommit(groupIdBytes: groupId, stagedCommitRef: ref),
+        isTrue,
+      );
+      expect(
+        await bob.rejectStagedCommit(groupIdBytes: groupId, stagedCommitRef: ref),
+        isFalse,
+      );
+      expect(await bob.groupEpoch(groupIdBytes: groupId), BigInt.one);
+      await expectLater(
+        bob.mergeStagedCommitByRef(groupIdBytes: groupId, stagedCommitRef: ref),
+        throwsA(anything),
+      );
+    });
+
+    test('other messages are processed as usual', () async {
+      final message = await alice.createMessage(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList([1, 2, 3]),
+      );
+      final result = await bob.processMessageStaged(
+        groupIdBytes: groupId,
+        messageBytes: message.ciphertext,
+      );
+      expect(result.messageType, ProcessedMessageType.application);
+      expect(result.applicationMessage, [1, 2, 3]);
+      expect(result.stagedCommitRef, isNull);
+    });
+  });
+}
@@ -56,6 +56,38 @@ void main() {
       expect(cs, equals(ciphersuite));
     });
 
+    test('protocol version is MLS 1.0', () async {
+      final version = await alice.protocolVersion(groupIdBytes: groupIdBytes);
+      expect(version, equals(1));
+    });
+
+    test('unsupported protocol version is rejected', () async {
+      final config = MlsGroupConfig(
+        ciphersuite: ciphersuite,
+        wireFormatPolicy: MlsWireFormatPolicy.ciphertext,
+        useRatchetTreeExtension: true,
+        maxPastEpochs: 0,
+        paddingSize: 0,
+        senderRatchetMaxOutOfOrder: 10,
+        senderRatchetMaxForwardDistance: 1000,
+        numberOfResumptionPsks: 0,
+        protocolVersion: 2,
+      );
+      expect(
+        () => alice.createGroup(
+          config: config,
+          signerBytes: aliceId.signerBytes,
+          credentialIdentity: aliceId.credentialIdentity,
+          signerPublicKey: aliceId.publicKey,
+        ),
+        throwsA(
+          predicate<Object>(
+            (e) => e.toString().contains('Unsupported MLS protocol version'),
+          ),
+        ),
+      );
+    });
+
     test('own index is 0', () async {
       final idx = await alice.groupOwnIndex(groupIdBytes: groupIdBytes);
       expect(idx, equals(0));
@@ -164,6 +196,18 @@ void main() {
       expect(tree, isNotEmpty);
     });
 
+    test('export tree as DOT', () async {
+      final dot = await alice.exportTreeDot(groupIdBytes: groupIdBytes);
+      expect(dot, startsWith('digraph ratchet_tree {'));
+      expect(dot, contains(utf8.decode(aliceId.credentialIdentity)));
+    });
+
+    test('export tree as ASCII', () async {
+      final ascii = await alice.exportTreeAscii(groupIdBytes: groupIdBytes);
+      expect(ascii.trim().split('\n'), hasLength(1));
+      expect(ascii, contains('0: leaf ${utf8.decode(aliceId.credentialIdentity)}'));
+    });
+
     test('export group info', () async {
       final info = await alice.exportGroupInfo(
         groupIdBytes: groupIdBytes,
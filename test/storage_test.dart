@@ -1,3 +1,5 @@
+import 'dart:convert';
+import 'dart:io';
 import 'dart:typed_data';
 
 import 'package:openmls/openmls.dart';
@@ -26,7 +28,118 @@ void main() {
 
     test('schema_version returns expected value', () async {
       final engine = await createTestEngine();
-      expect(engine.schemaVersion(), 1);
+      expect(engine.schemaVersion(), 4);
+    });
+
+    test('engine info describes an in-memory database', () async {
+      final engine = await createTestEngine();
+      final info = await engine.engineInfo();
+      expect(info.backend, MlsStorageBackend.sqlcipher);
+      expect(info.dbPath, ':memory:');
+      expect(info.schemaVersion, engine.schemaVersion());
+      expect(info.pageSize, greaterThan(0));
+      expect(info.journalMode, isNotNull);
+      expect(info.walActive, isFalse);
+    });
+
+    test('engine info reports the database file path', () async {
+      final dir = Directory.systemTemp.createTempSync('openmls_info');
+      addTearDown(() => dir.deleteSync(recursive: true));
+      final dbPath = '${dir.path}/info.db';
+
+      final engine = await MlsEngine.create(
+        dbPath: dbPath,
+        encryptionKey: testEncryptionKey(),
+      );
+      addTearDown(engine.close);
+      final info = await engine.engineInfo();
+      expect(File(info.dbPath!).absolute.path, File(dbPath).absolute.path);
+    });
+
+    test('the same database cannot be opened twice', () async {
+      final dir = Directory.systemTemp.createTempSync('openmls_twice');
+      addTearDown(() => dir.deleteSync(recursive: true));
+      final dbPath = '${dir.path}/twice.db';
+      final key = testEncryptionKey();
+
+      final engine = await MlsEngine.create(dbPath: dbPath, encryptionKey: key);
+      await expectLater(
+        MlsEngine.create(dbPath: '${dir.path}/./twice.db', encryptionKey: key),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('already open')),
+        ),
+      );
+
+      final shared = await MlsEngine.createWithPolicy(
+        dbPath: dbPath,
+        encryptionKey: key,
+        onAlreadyOpen: EngineOpenPolicy.share,
+      );
+      expect(shared.toToken(), engine.toToken());
+      await expectLater(
+        MlsEngine.createWithPolicy(
+          dbPath: dbPath,
+          encryptionKey: testEncryptionKey(),
+          onAlreadyOpen: EngineOpenPolicy.share,
+        ),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('different')),
+        ),
+      );
+
+      await engine.close();
+      final reopened = await MlsEngine.create(
+        dbPath: dbPath,
+        encryptionKey: key,
+      );
+      addTearDown(reopened.close);
+      expect(reopened.isClosed(), isFalse);
+    });
+
+    test('concurrent opens of a new database share one engine', () async {
+      final dir = Directory.systemTemp.createTempSync('openmls_concurrent');
+      addTearDown(() => dir.deleteSync(recursive: true));
+      final key = testEncryptionKey();
+      // A symlinked directory stands in for /var vs /private/var: the
+      // file does not exist yet when both opens resolve its path.
+      final link = Link('${dir.path}/link')..createSync(dir.path);
+
+      final engines = await Future.wait([
+        MlsEngine.createWithPolicy(
+          dbPath: '${dir.path}/new.db',
+          encryptionKey: key,
+          onAlreadyOpen: EngineOpenPolicy.share,
+        ),
+        MlsEngine.createWithPolicy(
+          dbPath: '${link.path}/new.db',
+          encryptionKey: key,
+          onAlreadyOpen: EngineOpenPolicy.share,
+        ),
+      ]);
+      addTearDown(engines.first.close);
+      expect(engines.last.toToken(), engines.first.toToken());
+    });
+
+    test('unregistered storage backend is rejected', () async {
+      await expectLater(
+        MlsEngine.createWithBackend(backendName: 'no-such-backend'),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('No storage backend')),
+        ),
+      );
+    });
+
+    test('PRF-derived keys are web only', () async {
+      await expectLater(
+        MlsEngine.createFromPrf(
+          dbPath: ':memory:',
+          prfOutput: testEncryptionKey(),
+          onAlreadyOpen: EngineOpenPolicy.fail,
+        ),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('only supported')),
+        ),
+      );
     });
   });
 
@@ -156,6 +269,7 @@ void main() {
         () => engine.selfUpdate(
           groupIdBytes: Uint8List.fromList([1, 2]),
           signerBytes: id.signerBytes,
+          ensureGroupInfo: false,
         ),
         throwsClosed,
       );
@@ -227,6 +341,478 @@ void main() {
     });
   });
 
+  group('operation timeout', () {
+    test('operations succeed within the timeout', () async {
+      final engine = await createTestEngine();
+      await engine.setOperationTimeout(timeoutMs: 5000);
+      final id = TestIdentity.create('timeout');
+      final result = await engine.createGroup(
+        config: defaultConfig(),
+        signerBytes: id.signerBytes,
+        credentialIdentity: id.credentialIdentity,
+        signerPublicKey: id.publicKey,
+      );
+      expect(
+        await engine.groupEpoch(groupIdBytes: result.groupId),
+        BigInt.zero,
+      );
+
+      await engine.setOperationTimeout();
+      expect(
+        await engine.groupEpoch(groupIdBytes: result.groupId),
+        BigInt.zero,
+      );
+    });
+
+    test('fails on a closed engine', () async {
+      final engine = await createTestEngine();
+      await engine.close();
+      await expectLater(
+        engine.setOperationTimeout(timeoutMs: 1000),
+        throwsA(anything),
+      );
+    });
+  });
+
+  group('secure wipe', () {
+    test('deletes the database file and closes the engine', () async {
+      final dir = Directory.systemTemp.createTempSync('openmls_wipe_test');
+      addTearDown(() => dir.deleteSync(recursive: true));
+      final dbPath = '${dir.path}/wipe.db';
+
+      final engine = await MlsEngine.create(
+        dbPath: dbPath,
+        encryptionKey: testEncryptionKey(),
+      );
+      final id = TestIdentity.create('wipe-test');
+      await engine.createGroup(
+        config: defaultConfig(),
+        signerBytes: id.signerBytes,
+        credentialIdentity: id.credentialIdentity,
+        signerPublicKey: id.publicKey,
+      );
+      expect(File(dbPath).existsSync(), isTrue);
+
+      await engine.secureWipe();
+
+      expect(File(dbPath).existsSync(), isFalse);
+      expect(engine.isClosed(), isTrue);
+    });
+
+    test('wipes an in-memory engine', () async {
+      final engine = await createTestEngine();
+      await engine.secureWipe();
+      expect(engine.isClosed(), isTrue);
+      expect(() => engine.secureWipe(), throwsA(anything));
+    });
+  });
+
+  group('backend migration', () {
+    test('dual-writes to the new database and switches to it', () async {
+      final dir = Directory.systemTemp.createTempSync('openmls_migration');
+      addTearDown(() => dir.deleteSync(recursive: true));
+      final dbPath = '${dir.path}/target.db';
+      final key = testEncryptionKey();
+
+      final engine = await createTestEngine();
+      final id = TestIdentity.create('migration');
+      final group = await engine.createGroup(
+        config: defaultConfig(),
+        signerBytes: id.signerBytes,
+        credentialIdentity: id.credentialIdentity,
+        signerPublicKey: id.publicKey,
+      );
+      expect(await engine.migrationStatus(), isNull);
+
+      await engine.beginBackendMigration(
+        targetDbPath: dbPath,
+        targetEncryptionKey: key,
+        dualWriteSeconds: BigInt.from(3600),
+      );
+      await engine.createMessage(
+        groupIdBytes: group.groupId,
+        signerBytes: id.signerBytes,
+        message: utf8.encode('during migration'),
+      );
+
+      final status = (await engine.migrationStatus())!;
+      expect(status.mirroredWrites, greaterThan(BigInt.zero));
+      expect(status.targetWriteFailures, BigInt.zero);
+      expect(status.mismatchedEntries, BigInt.zero);
+      expect(status.ready, isFalse);
+      await expectLater(
+        engine.finalizeBackendMigration(force: false),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('Dual-write')),
+        ),
+      );
+
+      await engine.finalizeBackendMigration(force: true);
+      expect(await engine.migrationStatus(), isNull);
+      final info = await engine.engineInfo();
+      expect(File(info.dbPath!).absolute.path, File(dbPath).absolute.path);
+      await engine.close();
+
+      final reopened = await MlsEngine.create(dbPath: dbPath, encryptionKey: key);
+      addTearDown(reopened.close);
+      expect(
+        await reopened.groupEpoch(groupIdBytes: group.groupId),
+        BigInt.zero,
+      );
+    });
+
+    test('is refused while groups are quarantined', () async {
+      final engine = await createTestEngine();
+      addTearDown(engine.close);
+      final id = TestIdentity.create('migration');
+      final group = await engine.createGroup(
+        config: defaultConfig(),
+        signerBytes: id.signerBytes,
+        credentialIdentity: id.credentialIdentity,
+        signerPublicKey: id.publicKey,
+      );
+      await engine.quarantineGroup(groupIdBytes: group.groupId, error: 'x');
+
+      await expectLater(
+        engine.beginBackendMigration(
+          targetDbPath: ':memory:',
+          targetEncryptionKey: testEncryptionKey(),
+          dualWriteSeconds: BigInt.zero,
+        ),
+        throwsA(predicate<Object>((e) => e.toString().contains('quarantined'))),
+      );
+      expect(await engine.migrationStatus(), isNull);
+    });
+
+    test('abort keeps the current database', () async {
+      final engine = await createTestEngine();
+      addTearDown(engine.close);
+      await expectLater(
+        engine.abortBackendMigration(),
+        throwsA(predicate<Object>((e) => e.toString().contains('No backend'))),
+      );
+
+      await engine.beginBackendMigration(
+        targetDbPath: ':memory:',
+        targetEncryptionKey: testEncryptionKey(),
+        dualWriteSeconds: BigInt.zero,
+      );
+      await expectLater(
+        engine.beginBackendMigration(
+          targetDbPath: ':memory:',
+          targetEncryptionKey: testEncryptionKey(),
+          dualWriteSeconds: BigInt.zero,
+        ),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('already in progress')),
+        ),
+      );
+      await engine.abortBackendMigration();
+      expect(await engine.migrationStatus(), isNull);
+      final signer = await engine.generateSignatureKeyPair(
+        ciphersuite: ciphersuite,
+      );
+      expect(signer.publicKey, isNotEmpty);
+    });
+  });
+
+  group('quarantine', () {
+    test('moves group state aside and exports it', () async {
+      final engine = await createTestEngine();
+      final id = TestIdentity.create('quarantine-test');
+      final result = await engine.createGroup(
+        config: defaultConfig(),
+        signerBytes: id.signerBytes,
+        credentialIdentity: id.credentialIdentity,
+        signerPublicKey: id.publicKey,
+      );
+
+      await engine.quarantineGroup(
+        groupIdBytes: result.groupId,
+        error: 'processing failed',
+      );
+
+      // The group no longer loads.
+      expect(
+        () => engine.groupIsActive(groupIdBytes: result.groupId),
+        throwsA(anything),
+      );
+
+      final quarantined = await engine.quarantinedGroups();
+      expect(quarantined, hasLength(1));
+      expect(quarantined.first.groupId, equals(result.groupId));
+      expect(quarantined.first.error, 'processing failed');
+      expect(quarantined.first.quarantinedAt, greaterThan(BigInt.zero));
+
+      final exported = await engine.exportQuarantinedGroup(
+        groupIdBytes: result.groupId,
+      );
+      final json = jsonDecode(utf8.decode(exported)) as Map<String, dynamic>;
+      expect(json['error'], 'processing failed');
+      expect(List<int>.from(json['group_id'] as List), result.groupId);
+      expect(json['rows'], isNotEmpty);
+    });
+
+    test('fails for an unknown group', () async {
+      final engine = await createTestEngine();
+      final unknown = Uint8List.fromList([9, 9, 9]);
+      expect(
+        () => engine.quarantineGroup(groupIdBytes: unknown, error: 'x'),
+        throwsA(anything),
+      );
+      expect(
+        () => engine.exportQuarantinedGroup(groupIdBytes: unknown),
+        throwsA(anything),
+      );
+      expect(await engine.quarantinedGroups(), isEmpty);
+    });
+  });
+
+  group('backup export', () {
+    test('streamed chunks concatenate to the full backup', () async {
+      final engine = await createTestEngine();
+      final id = TestIdentity.create('backup');
+      await engine.createGroup(
+        config: defaultConfig(),
+        signerBytes: id.signerBytes,
+        credentialIdentity: id.credentialIdentity,
+        signerPublicKey: id.publicKey,
+      );
+
+      final full = await engine.exportBackup();
+      expect(full.first, equals(1));
+      expect(full.length, greaterThan(1));
+
+      final chunks = await engine.exportBackupStream(chunkSize: 64).toList();
+      expect(chunks.length, greaterThan(1));
+      expect(chunks.expand((c) => c).toList(), equals(full));
+    });
+
+    test('exports databases larger than one storage page', () async {
+      final engine = await createTestEngine();
+      final id = TestIdentity.create('backup');
+      // Each key package stores a few entries; together well over a page.
+      for (var i = 0; i < 150; i++) {
+        await engine.createKeyPackage(
+          ciphersuite: ciphersuite,
+          signerBytes: id.signerBytes,
+          credentialIdentity: id.credentialIdentity,
+          signerPublicKey: id.publicKey,
+        );
+      }
+
+      final full = await engine.exportBackup();
+      final chunks = await engine.exportBackupStream(chunkSize: 4096).toList();
+      expect(chunks.expand((c) => c).toList(), equals(full));
+      expect(full.length, greaterThan(150 * 100));
+    });
+
+    test('empty engine exports only the version byte', () async {
+      final engine = await createTestEngine();
+      expect(await engine.exportBackup(), equals([1]));
+    });
+  });
+
+  group('group state export', () {
+    final passphrase = Uint8List.fromList(utf8.encode('correct horse'));
+
+    test('moves a group to another device', () async {
+      final alice = await createTestEngine();
+      final bob = await createTestEngine();
+      final bobNewDevice = await createTestEngine();
+      final aliceId = TestIdentity.create('alice');
+      final bobId = TestIdentity.create('bob');
+      final created = await alice.createGroup(
+        config: defaultConfig(),
+        signerBytes: aliceId.signerBytes,
+        credentialIdentity: aliceId.credentialIdentity,
+        signerPublicKey: aliceId.publicKey,
+      );
+      final groupId = created.groupId;
+      final bobKp = await bob.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: bobId.signerBytes,
+        credentialIdentity: bobId.credentialIdentity,
+        signerPublicKey: bobId.publicKey,
+      );
+      final add = await alice.addMembers(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [bobKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupId);
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: add.welcome,
+        signerBytes: bobId.signerBytes,
+      );
+
+      final blob = await bob.exportGroupState(
+        groupIdBytes: groupId,
+        passphrase: passphrase,
+      );
+      await expectLater(
+        bobNewDevice.importGroupState(
+          blob: blob,
+          passphrase: Uint8List.fromList(utf8.encode('wrong')),
+          includeGlobalEntries: true,
+        ),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('wrong passphrase')),
+        ),
+      );
+      final imported = await bobNewDevice.importGroupState(
+        blob: blob,
+        passphrase: passphrase,
+      );
+      expect(imported, equals(groupId));
+      expect(
+        await bobNewDevice.groupEpoch(groupIdBytes: groupId),
+        BigInt.one,
+      );
+
+      final commit = await alice.selfUpdate(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        ensureGroupInfo: false,
+      );
+      await alice.mergePendingCommit(groupIdBytes: groupId);
+      await bobNewDevice.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: commit.commit,
+      );
+      final msg = await alice.createMessage(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        message: Uint8List.fromList(utf8.encode('migrated')),
+      );
+      final received = await bobNewDevice.processMessage(
+        groupIdBytes: groupId,
+        messageBytes: msg.ciphertext,
+      );
+      expect(utf8.decode(received.applicationMessage!), 'migrated');
+
+      await expectLater(
+        bobNewDevice.importGroupState(blob: blob, passphrase: passphrase),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('already exists')),
+        ),
+      );
+    });
+
+    test('fails for an unknown group or an empty passphrase', () async {
+      final engine = await createTestEngine();
+      final id = TestIdentity.create('export');
+      final created = await engine.createGroup(
+        config: defaultConfig(),
+        signerBytes: id.signerBytes,
+        credentialIdentity: id.credentialIdentity,
+        signerPublicKey: id.publicKey,
+      );
+      await expectLater(
+        engine.exportGroupState(
+          groupIdBytes: Uint8List.fromList([9, 9, 9]),
+          passphrase: passphrase,
+        ),
+        throwsA(anything),
+      );
+      await expectLater(
+        engine.exportGroupState(
+          groupIdBytes: created.groupId,
+          passphrase: Uint8List(0),
+        ),
+        throwsA(anything),
+      );
+    });
+  });
+
+  group('interchange backup', () {
+    final passphrase = Uint8List.fromList(utf8.encode('correct horse'));
+
+    Future<Uint8List> createGroup(MlsEngine engine, String name) async {
+      final id = TestIdentity.create(name);
+      final created = await engine.createGroup(
+        config: defaultConfig(),
+        signerBytes: id.signerBytes,
+        credentialIdentity: id.credentialIdentity,
+        signerPublicKey: id.publicKey,
+      );
+      return created.groupId;
+    }
+
+    test('restores all groups or a selection', () async {
+      final source = await createTestEngine();
+      final first = await createGroup(source, 'first');
+      final second = await createGroup(source, 'second');
+      final blob = await source.exportInterchange(passphrase: passphrase);
+
+      final full = await createTestEngine();
+      final restored = await full.importInterchange(
+        blob: blob,
+        passphrase: passphrase,
+        includeGlobalEntries: true,
+      );
+      expect(restored.groupIds, unorderedEquals([first, second]));
+      expect(restored.globalEntries, greaterThan(0));
+      expect(await full.groupEpoch(groupIdBytes: first), BigInt.zero);
+
+      final partial = await createTestEngine();
+      final selected = await partial.importInterchange(
+        blob: blob,
+        passphrase: passphrase,
+        groupIds: [second],
+        includeGlobalEntries: false,
+      );
+      expect(selected.groupIds, [second]);
+      expect(selected.globalEntries, 0);
+      expect(await partial.groupIsActive(groupIdBytes: second), isTrue);
+      await expectLater(
+        partial.groupEpoch(groupIdBytes: first),
+        throwsA(anything),
+      );
+
+      await expectLater(
+        partial.importInterchange(
+          blob: blob,
+          passphrase: passphrase,
+          groupIds: [second],
+          includeGlobalEntries: false,
+        ),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('already exists')),
+        ),
+      );
+    });
+
+    test('rejects a wrong passphrase or a tampered backup', () async {
+      final source = await createTestEngine();
+      await createGroup(source, 'tamper');
+      final blob = await source.exportInterchange(passphrase: passphrase);
+      final target = await createTestEngine();
+
+      await expectLater(
+        target.importInterchange(
+          blob: blob,
+          passphrase: Uint8List.fromList(utf8.encode('wrong')),
+          includeGlobalEntries: true,
+        ),
+        throwsA(
+          predicate<Object>((e) => e.toString().contains('wrong passphrase')),
+        ),
+      );
+      final tampered = Uint8List.fromList(blob)..[blob.length - 1] ^= 1;
+      await expectLater(
+        target.importInterchange(
+          blob: tampered,
+          passphrase: passphrase,
+          includeGlobalEntries: true,
+        ),
+        throwsA(anything),
+      );
+    });
+  });
+
   group('engine isolation', () {
     test('separate engine instances are independent', () async {
       final engine1 = await createTestEngine();
@@ -270,4 +856,86 @@ void main() {
       );
     });
   });
+
+  group('legacy provider import', () {
+    /// Stand-in for a legacy callback backend: the raw entries of a group,
+    /// taken from its quarantine export.
+    Future<List<MlsStorageEntry>> legacyEntries(
+      MlsEngine engine,
+      Uint8List groupId,
+    ) async {
+      await engine.quarantineGroup(groupIdBytes: groupId, error: 'migrate');
+      final exported = await engine.exportQuarantinedGroup(
+        groupIdBytes: groupId,
+      );
+      final json = jsonDecode(utf8.decode(exported)) as Map<String, dynamic>;
+      return [
+        for (final row in json['rows'] as List)
+          MlsStorageEntry(
+            key: Uint8List.fromList(List<int>.from(row[0] as List)),
+            value: Uint8List.fromList(List<int>.from(row[1] as List)),
+          ),
+      ];
+    }
+
+    Future<List<MlsStorageEntry>> Function(Uint8List) readPrefix(
+      List<MlsStorageEntry> entries,
+    ) =>
+        (prefix) async => [
+          for (final entry in entries)
+            if (entry.key.length >= prefix.length &&
+                _startsWith(entry.key, prefix))
+              entry,
+        ];
+
+    test('imports groups into the database', () async {
+      final engine = await createTestEngine();
+      final id = TestIdentity.create('legacy');
+      final created = await engine.createGroup(
+        config: defaultConfig(),
+        signerBytes: id.signerBytes,
+        credentialIdentity: id.credentialIdentity,
+        signerPublicKey: id.publicKey,
+      );
+      final entries = await legacyEntries(engine, created.groupId);
+
+      final target = await createTestEngine();
+      final result = await target.importFromProviderStorage(
+        readPrefix: readPrefix(entries),
+      );
+      expect(result.groupIds, hasLength(1));
+      expect(result.groupIds.single, equals(created.groupId));
+      expect(result.groupEntries, entries.length);
+      expect(await target.groupIsActive(groupIdBytes: created.groupId), isTrue);
+
+      // A second import would overwrite the group and is refused.
+      expect(
+        () => target.importFromProviderStorage(
+          readPrefix: readPrefix(entries),
+        ),
+        throwsA(anything),
+      );
+    });
+
+    test('rejects unrecognized keys without writing', () async {
+      final engine = await createTestEngine();
+      final bogus = MlsStorageEntry(
+        key: Uint8List.fromList([...utf8.encode('Tree'), 1, 2, 0, 1]),
+        value: Uint8List.fromList([0]),
+      );
+      expect(
+        () => engine.importFromProviderStorage(
+          readPrefix: readPrefix([bogus]),
+        ),
+        throwsA(anything),
+      );
+    });
+  });
+}
+
+bool _startsWith(Uint8List bytes, Uint8List prefix) {
+  for (var i = 0; i < prefix.length; i++) {
+    if (bytes[i] != prefix[i]) return false;
+  }
+  return true;
 }
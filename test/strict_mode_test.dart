@@ -0,0 +1,96 @@
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late MlsEngine bob;
+  late TestIdentity aliceId;
+  late TestIdentity bobId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    bob = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    bobId = TestIdentity.create('bob');
+  });
+
+  group('strict mode', () {
+    test('is disabled by default and can be toggled', () {
+      expect(alice.isStrictMode(), isFalse);
+      alice.setStrictMode(enabled: true);
+      expect(alice.isStrictMode(), isTrue);
+      alice.setStrictMode(enabled: false);
+      expect(alice.isStrictMode(), isFalse);
+    });
+
+    test('refuses skip lifetime validation', () async {
+      final (_, welcome) = await addBob(
+        alice,
+        aliceId,
+        bob,
+        bobId,
+        join: false,
+      );
+      bob.setStrictMode(enabled: true);
+
+      expect(
+        () => bob.joinGroupFromWelcomeWithOptions(
+          config: defaultConfig(),
+          welcomeBytes: welcome,
+          signerBytes: bobId.signerBytes,
+          skipLifetimeValidation: true,
+        ),
+        throwsA(anything),
+      );
+    });
+
+    test('allows join with lifetime validation', () async {
+      final (_, welcome) = await addBob(
+        alice,
+        aliceId,
+        bob,
+        bobId,
+        join: false,
+      );
+      bob.setStrictMode(enabled: true);
+
+      final joinResult = await bob.joinGroupFromWelcomeWithOptions(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+        skipLifetimeValidation: false,
+      );
+      expect(joinResult.groupId, isNotEmpty);
+    });
+
+    test('accepts a commit adding a member with compatible capabilities',
+        () async {
+      final (groupId, _) = await addBob(alice, aliceId, bob, bobId);
+      bob.setStrictMode(enabled: true);
+
+      final carol = await createTestEngine();
+      final carolId = TestIdentity.create('carol');
+      final carolKp = await carol.createKeyPackage(
+        ciphersuite: ciphersuite,
+        signerBytes: carolId.signerBytes,
+        credentialIdentity: carolId.credentialIdentity,
+        signerPublicKey: carolId.publicKey,
+      );
+      final add = await alice.addMembers(
+        groupIdBytes: groupId,
+        signerBytes: aliceId.signerBytes,
+        keyPackagesBytes: [carolKp.keyPackageBytes],
+        ensureGroupInfo: false,
+      );
+
+      await bob.processMessage(groupIdBytes: groupId, messageBytes: add.commit);
+      expect(await bob.groupEpoch(groupIdBytes: groupId), BigInt.two);
+    });
+  });
+}
@@ -67,3 +67,46 @@ Uint8List testEncryptionKey() {
 /// Create a test MlsEngine with an in-memory SQLCipher database.
 Future<MlsEngine> createTestEngine() async =>
     MlsEngine.create(dbPath: ':memory:', encryptionKey: testEncryptionKey());
+
+/// Has [alice] create a group and add [bob], returning the group id and
+/// the Welcome.
+///
+/// Bob joins from the Welcome unless [join] is false. With [lastResort] he
+/// is added with a last-resort key package, so the Welcome can be
+/// processed more than once.
+Future<(Uint8List, Uint8List)> addBob(
+  MlsEngine alice,
+  TestIdentity aliceId,
+  MlsEngine bob,
+  TestIdentity bobId, {
+  bool join = true,
+  bool lastResort = false,
+}) async {
+  final groupResult = await alice.createGroup(
+    config: defaultConfig(),
+    signerBytes: aliceId.signerBytes,
+    credentialIdentity: aliceId.credentialIdentity,
+    signerPublicKey: aliceId.publicKey,
+  );
+  final bobKp = await bob.createKeyPackageWithOptions(
+    ciphersuite: ciphersuite,
+    signerBytes: bobId.signerBytes,
+    credentialIdentity: bobId.credentialIdentity,
+    signerPublicKey: bobId.publicKey,
+    options: KeyPackageOptions(lastResort: lastResort),
+  );
+  final addResult = await alice.addMembers(
+    groupIdBytes: groupResult.groupId,
+    signerBytes: aliceId.signerBytes,
+    keyPackagesBytes: [bobKp.keyPackageBytes],
+    ensureGroupInfo: false,
+  );
+  if (join) {
+    await bob.joinGroupFromWelcome(
+      config: defaultConfig(),
+      welcomeBytes: addResult.welcome,
+      signerBytes: bobId.signerBytes,
+    );
+  }
+  return (groupResult.groupId, addResult.welcome);
+}
@@ -0,0 +1,195 @@
+import 'dart:typed_data';
+
+import 'package:openmls/openmls.dart';
+import 'package:test/test.dart';
+
+import 'test_helpers.dart';
+
+void main() {
+  late MlsEngine alice;
+  late MlsEngine bob;
+  late TestIdentity aliceId;
+  late TestIdentity bobId;
+
+  setUpAll(() async {
+    await Openmls.init();
+  });
+
+  setUp(() async {
+    alice = await createTestEngine();
+    bob = await createTestEngine();
+    aliceId = TestIdentity.create('alice');
+    bobId = TestIdentity.create('bob');
+  });
+
+  group('welcome for an already-known group', () {
+    test('joins when no local state exists', () async {
+      final (_, welcome) = await addBob(
+        alice,
+        aliceId,
+        bob,
+        bobId,
+        join: false,
+        lastResort: true,
+      );
+      final outcome = await bob.joinGroupFromWelcomeWithPolicy(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+        onExisting: ExistingGroupPolicy.abort,
+      );
+      expect(outcome.joined, isTrue);
+      expect(outcome.alreadyExists, isNull);
+    });
+
+    test('abort reports both epochs and keeps local state', () async {
+      final (_, welcome) = await addBob(
+        alice,
+        aliceId,
+        bob,
+        bobId,
+        join: false,
+        lastResort: true,
+      );
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+      );
+
+      final outcome = await bob.joinGroupFromWelcomeWithPolicy(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+        onExisting: ExistingGroupPolicy.abort,
+      );
+      expect(outcome.joined, isFalse);
+      expect(outcome.alreadyExists, isNotNull);
+      expect(outcome.alreadyExists!.currentEpoch, BigInt.one);
+      expect(outcome.alreadyExists!.welcomeEpoch, BigInt.one);
+      expect(await bob.groupIsActive(groupIdBytes: outcome.groupId), isTrue);
+    });
+
+    test('plain join refuses to overwrite local state', () async {
+      final (_, welcome) = await addBob(
+        alice,
+        aliceId,
+        bob,
+        bobId,
+        join: false,
+        lastResort: true,
+      );
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+      );
+
+      expect(
+        () => bob.joinGroupFromWelcome(
+          config: defaultConfig(),
+          welcomeBytes: welcome,
+          signerBytes: bobId.signerBytes,
+        ),
+        throwsA(anything),
+      );
+    });
+
+    test('replace local state rejoins', () async {
+      final (_, welcome) = await addBob(
+        alice,
+        aliceId,
+        bob,
+        bobId,
+        join: false,
+        lastResort: true,
+      );
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+      );
+
+      final outcome = await bob.joinGroupFromWelcomeWithPolicy(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+        onExisting: ExistingGroupPolicy.replaceLocalState,
+      );
+      expect(outcome.joined, isTrue);
+      expect(outcome.alreadyExists, isNotNull);
+      expect(await bob.groupEpoch(groupIdBytes: outcome.groupId), BigInt.one);
+    });
+  });
+
+  group('join statistics', () {
+    test('counts successful and failed welcome joins', () async {
+      expect(bob.joinStatistics().welcomesProcessed, BigInt.zero);
+
+      final (_, welcome) = await addBob(
+        alice,
+        aliceId,
+        bob,
+        bobId,
+        join: false,
+        lastResort: true,
+      );
+      await bob.joinGroupFromWelcome(
+        config: defaultConfig(),
+        welcomeBytes: welcome,
+        signerBytes: bobId.signerBytes,
+      );
+      await expectLater(
+        bob.joinGroupFromWelcome(
+          config: defaultConfig(),
+          welcomeBytes: welcome,
+          signerBytes: bobId.signerBytes,
+        ),
+        throwsA(anything),
+      );
+
+      final stats = bob.joinStatistics();
+      expect(stats.welcomesProcessed, BigInt.one);
+      expect(stats.externalCommits, BigInt.zero);
+      expect(stats.failures, hasLength(1));
+      expect(stats.failures.single.reason, 'group_exists');
+      expect(stats.failures.single.count, BigInt.one);
+
+      bob.resetJoinStatistics();
+      expect(bob.joinStatistics().welcomesProcessed, BigInt.zero);
+      expect(bob.joinStatistics().failures, isEmpty);
+    });
+
+    test('reports malformed and unmatched welcomes by reason', () async {
+      final (_, welcome) = await addBob(alice, aliceId, bob, bobId, join: false);
+      final carol = await createTestEngine();
+      final carolId = TestIdentity.create('carol');
+
+      await expectLater(
+        carol.joinGroupFromWelcome(
+          config: defaultConfig(),
+          welcomeBytes: Uint8List.fromList([1, 2, 3]),
+          signerBytes: carolId.signerBytes,
+        ),
+        throwsA(anything),
+      );
+      await expectLater(
+        carol.joinGroupFromWelcome(
+          config: defaultConfig(),
+          welcomeBytes: welcome,
+          signerBytes: carolId.signerBytes,
+        ),
+        throwsA(anything),
+      );
+
+      final reasons = {
+        for (final failure in carol.joinStatistics().failures)
+          failure.reason: failure.count,
+      };
+      expect(reasons, {
+        'malformed_input': BigInt.one,
+        'no_matching_key_package': BigInt.one,
+      });
+    });
+  });
+}